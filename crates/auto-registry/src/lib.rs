@@ -1,6 +1,7 @@
 #![feature(proc_macro_span)]
-use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use lazy_static::lazy_static;
 use proc_macro::TokenStream;
@@ -11,11 +12,36 @@ use syn::parse::ParseStream;
 use syn::parse_macro_input;
 use syn::ItemStruct;
 
+/// A registry of structs
+#[derive(Default)]
+struct Registry {
+	/// Paths of the registered structs, each path needs to be valid in the
+	/// context of the [`generate_registry`] macro. Paths are sorted so that
+	/// the generated registry doesn't depend on the expansion order.
+	///
+	/// Each path is mapped to the `cfg` predicate the struct is registered
+	/// under, if any.
+	entries: BTreeMap<String, Option<String>>,
+	/// Set once the registry has been generated by [`generate_registry`]
+	generated: bool,
+}
+
 lazy_static! {
-	/// The registry, each key corresponds to an identifier that needs to be
-	/// valid in the context of the [`genegenerate_registry`] macro.
-	static ref REGISTRY: Mutex<RefCell<HashMap<String, Vec<String>>>> =
-		Mutex::new(RefCell::new(HashMap::new()));
+	/// The registries, keyed by crate name and registry name
+	///
+	/// Proc macros may be expanded for several crates by the same process
+	/// (e.g by rust-analyzer), registries are therefore kept separate for
+	/// every crate.
+	static ref REGISTRY: Mutex<HashMap<(String, String), Registry>> =
+		Mutex::new(HashMap::new());
+}
+
+/// Gets the key of registry `name` for the crate being compiled
+fn registry_key(name: &str) -> (String, String) {
+	(
+		std::env::var("CARGO_CRATE_NAME").unwrap_or_default(),
+		name.to_string(),
+	)
 }
 
 /// Arguments for the [`auto_registry`] proc macro
@@ -25,6 +51,8 @@ struct AutoRegistryArgs {
 	/// The absolute path to the struct, if not specified the macro will try
 	/// to automatically infer the full path.
 	path: Option<syn::LitStr>,
+	/// The `cfg` predicate the struct is registered under
+	cfg: Option<syn::LitStr>,
 }
 
 /// Parser for [`AutoRegistryArgs`]
@@ -32,6 +60,7 @@ impl Parse for AutoRegistryArgs {
 	fn parse(input: ParseStream) -> syn::Result<Self> {
 		let mut registry = None;
 		let mut path = None;
+		let mut cfg = None;
 		loop {
 			let key: syn::Ident = input.parse()?;
 			input.parse::<syn::Token![=]>()?;
@@ -40,11 +69,20 @@ impl Parse for AutoRegistryArgs {
 			match key.to_string().as_str() {
 				"registry" => registry = Some(value),
 				"path" => path = Some(value),
+				"cfg" => {
+					if value.value().parse::<proc_macro2::TokenStream>().is_err() {
+						return Err(syn::Error::new(
+							value.span(),
+							format!("Invalid cfg predicate `{}`", value.value()),
+						));
+					}
+					cfg = Some(value)
+				}
 				_ => {
 					return Err(syn::Error::new(
 						key.span(),
 						format!(
-							"Unknown attribute `{}`, excepted `registry` or `path`",
+							"Unknown attribute `{}`, excepted `registry`, `path` or `cfg`",
 							key.to_string()
 						),
 					))
@@ -66,6 +104,7 @@ impl Parse for AutoRegistryArgs {
 		Ok(AutoRegistryArgs {
 			registry: registry.unwrap(),
 			path,
+			cfg,
 		})
 	}
 }
@@ -76,6 +115,9 @@ impl Parse for AutoRegistryArgs {
 ///  - registry: (String) Name of the registry to collect the struct into
 ///  - path: (Optional String) The crate path in which the struct is located
 ///          If left empty, the path will be try to be automatically-deduced
+///  - cfg: (Optional String) A `cfg` predicate, e.g `feature = "lsp"`. The
+///         constructor of the struct is passed to the maker of the registry
+///         along with the predicate, see [`generate_registry`]
 ///
 /// # Note
 ///
@@ -142,12 +184,24 @@ pub fn auto_registry(attr: TokenStream, input: TokenStream) -> TokenStream {
 		}
 	};
 
-	let reg_mtx = REGISTRY.lock().unwrap();
-	let mut reg_borrow = reg_mtx.borrow_mut();
-	if let Some(ref mut vec) = reg_borrow.get_mut(args.registry.value().as_str()) {
-		vec.push(path);
-	} else {
-		reg_borrow.insert(args.registry.value(), vec![path]);
+	let mut registries = REGISTRY.lock().unwrap();
+	let registry = registries
+		.entry(registry_key(args.registry.value().as_str()))
+		.or_default();
+	let cfg = args.cfg.map(|cfg| cfg.value());
+	if registry.entries.insert(path.clone(), cfg).is_none() && registry.generated {
+		return syn::Error::new(
+			input.ident.span(),
+			format!(
+				"Registry `{}` was generated before `{path}` was added to it. \
+				Move the `generate_registry` invocation to a module that is \
+				expanded after every `auto_registry` use, e.g at the end of the \
+				crate root",
+				args.registry.value()
+			),
+		)
+		.to_compile_error()
+		.into();
 	}
 
 	quote! {
@@ -166,6 +220,8 @@ struct GenerateRegistryArgs {
 	maker: syn::Expr,
 	/// The return type for the function
 	return_type: syn::Type,
+	/// Manifest listing the entries of other crates
+	manifest: Option<syn::LitStr>,
 }
 
 /// Parser for [`GenerateRegistryArgs`]
@@ -175,6 +231,7 @@ impl Parse for GenerateRegistryArgs {
 		let mut target = None;
 		let mut maker = None;
 		let mut return_type = None;
+		let mut manifest = None;
 		loop {
 			let key: syn::Ident = input.parse()?;
 			input.parse::<syn::Token![=]>()?;
@@ -184,11 +241,12 @@ impl Parse for GenerateRegistryArgs {
 				"target" => target = Some(input.parse()?),
 				"maker" => maker = Some(input.parse()?),
 				"return_type" => return_type = Some(input.parse()?),
+				"manifest" => manifest = Some(input.parse()?),
 				_ => {
 					return Err(syn::Error::new(
 						key.span(),
 						format!(
-							"Unknown attribute `{}`, excepted `registry` or `target`",
+							"Unknown attribute `{}`, excepted `registry`, `target`, `maker`, `return_type` or `manifest`",
 							key.to_string()
 						),
 					))
@@ -227,25 +285,94 @@ impl Parse for GenerateRegistryArgs {
 			target: target.unwrap(),
 			maker: maker.unwrap(),
 			return_type: return_type.unwrap(),
+			manifest,
 		})
 	}
 }
 
+/// Reads the entries of `registry` from a manifest
+///
+/// Manifests list one entry per line, as the name of the registry followed by
+/// the absolute path to the struct, e.g `rules other_crate::rules::MyRule`.
+/// Empty lines and lines starting with `#` are ignored.
+fn read_manifest(manifest: &syn::LitStr, registry: &str) -> syn::Result<(PathBuf, Vec<String>)> {
+	let mut path = PathBuf::from(manifest.value());
+	if path.is_relative() {
+		let out_dir = std::env::var("OUT_DIR").map_err(|_| {
+			syn::Error::new(
+				manifest.span(),
+				"Relative manifest paths are resolved from `OUT_DIR`, which requires a build script",
+			)
+		})?;
+		path = PathBuf::from(out_dir).join(path);
+	}
+
+	let content = std::fs::read_to_string(&path).map_err(|e| {
+		syn::Error::new(
+			manifest.span(),
+			format!("Failed to read manifest `{}`: {}", path.display(), e),
+		)
+	})?;
+
+	let mut entries = vec![];
+	for (line_number, line) in content.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		match line.split_once(char::is_whitespace) {
+			Some((name, entry)) if !entry.trim().is_empty() => {
+				if name == registry {
+					entries.push(entry.trim().to_string());
+				}
+			}
+			_ => {
+				return Err(syn::Error::new(
+					manifest.span(),
+					format!(
+						"Invalid entry in manifest `{}` at line {}: expected `<registry> <path>`",
+						path.display(),
+						line_number + 1
+					),
+				))
+			}
+		}
+	}
+	Ok((path, entries))
+}
+
 /// The proc macro that generates the function to build the registry
 ///
+/// Structs are constructed in the order of their paths, independently of the
+/// order in which the [`auto_registry`] uses were expanded. Every
+/// [`auto_registry`] use must be expanded before the registry is generated:
+/// structs registered after the registry was generated are reported as errors.
+///
 /// # Attributes
 ///  - registry: (String) Name of the registry to generate
 ///  - target: (Identifier) Name of the resulting function
 ///  - maker: (Macro) A macro that will take all the newly constructed objects
-///           comma-separated and create the resulting expression
+///           comma-separated and create the resulting expression. Objects
+///           registered with a `cfg` predicate are preceded by
+///           `@cfg(<predicate>)`, which the maker must turn into a `#[cfg]`
+///           attribute
 ///  - return_type: (Type) The return type of the generated function.
 ///                 Must match the type of the macro invocation
+///  - manifest: (Optional String) Path to a manifest listing entries of other
+///              crates, see [`read_manifest`] for the format. Relative paths
+///              are resolved from `OUT_DIR`, so that the manifest can be
+///              generated by a build script
 ///
 /// # Example
 /// ```
 /// macro_rules! create_listeners {
-/// 	( $($construct:expr),+ $(,)? ) => {{
-/// 		vec![$(Box::new($construct) as Box<dyn Listener>,)+]
+/// 	( $( $(@cfg($cfg:meta))? $construct:expr ),+ $(,)? ) => {{
+/// 		let mut listeners: Vec<Box<dyn Listener>> = vec![];
+/// 		$(
+/// 			$(#[cfg($cfg)])?
+/// 			listeners.push(Box::new($construct));
+/// 		)+
+/// 		listeners
 /// 	}};
 /// }
 /// #[generate_registry(
@@ -262,21 +389,66 @@ impl Parse for GenerateRegistryArgs {
 #[proc_macro_attribute]
 pub fn generate_registry(attr: TokenStream, input: TokenStream) -> TokenStream {
 	let args = parse_macro_input!(attr as GenerateRegistryArgs);
-	let reg_mtx = REGISTRY.lock().unwrap();
+	let name = args.registry.value();
+
+	let mut registries = REGISTRY.lock().unwrap();
+	let registry = registries.entry(registry_key(name.as_str())).or_default();
+	registry.generated = true;
+	let mut names = registry.entries.clone();
+	drop(registries);
+
+	// Entries from other crates
+	let mut tracked = proc_macro2::TokenStream::new();
+	if let Some(manifest) = &args.manifest {
+		match read_manifest(manifest, name.as_str()) {
+			Ok((path, entries)) => {
+				// Rebuild when the manifest changes
+				let path = path.to_string_lossy().to_string();
+				tracked.extend(quote! {
+					const _: &[u8] = include_bytes!(#path);
+				});
+				names.extend(entries.into_iter().map(|entry| (entry, None)));
+			}
+			Err(err) => return err.to_compile_error().into(),
+		}
+	}
+
+	if names.is_empty() {
+		return syn::Error::new(
+			args.registry.span(),
+			format!(
+				"Registry `{name}` is empty. Structs are added to the registry by \
+				`auto_registry` uses expanded before `generate_registry`, or by a \
+				manifest"
+			),
+		)
+		.to_compile_error()
+		.into();
+	}
 
 	let mut stream = proc_macro2::TokenStream::new();
-	if let Some(names) = reg_mtx.borrow().get(args.registry.value().as_str()) {
-		for name in names {
-			let struct_name: proc_macro2::TokenStream = name.parse().unwrap();
+	for (name, cfg) in names {
+		let struct_name: proc_macro2::TokenStream = match name.parse() {
+			Ok(struct_name) => struct_name,
+			Err(_) => {
+				return syn::Error::new(
+					args.registry.span(),
+					format!("Invalid path `{name}` in registry"),
+				)
+				.to_compile_error()
+				.into()
+			}
+		};
+		if let Some(cfg) = cfg {
+			// Validated by `auto_registry`
+			let cfg: proc_macro2::TokenStream = cfg.parse().unwrap();
 			stream.extend(quote::quote_spanned!(proc_macro2::Span::call_site() =>
-				#struct_name::default(),
+				@cfg(#cfg)
 			));
 		}
-	} else {
-		panic!(
-			"Unable to find registry item with key=`{}`",
-			args.registry.value()
-		);
+		stream.extend(quote::quote_spanned!(proc_macro2::Span::call_site() =>
+			#struct_name::default(),
+		));
 	}
 
 	let function = args.target;
@@ -285,6 +457,7 @@ pub fn generate_registry(attr: TokenStream, input: TokenStream) -> TokenStream {
 
 	let rest: proc_macro2::TokenStream = input.into();
 	quote! {
+		#tracked
 		fn #function() -> #return_type {
 			#maker!(
 				#stream
@@ -1,7 +1,69 @@
+use std::path::Path;
+use std::time::Duration;
+
 use rusqlite::types::FromSql;
 use rusqlite::Connection;
+use rusqlite::ErrorCode;
 use rusqlite::ToSql;
 
+use crate::elements::code::elem::Code;
+use crate::elements::code::elem::CodeOutput;
+use crate::elements::graphviz::elem::Graphviz;
+use crate::elements::media::metadata::ImageFile;
+use crate::elements::media::thumbnail::Thumbnail;
+use crate::elements::render::elem::RenderJob;
+use crate::elements::tex::elem::FormattedTex;
+
+/// Time a connection waits for other connections to release the database
+///
+/// Parallel builds and the language server may access the same database concurrently.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Opens the cache database at `path`
+///
+/// The database uses write-ahead logging, so that readers don't block writers. Connections wait
+/// up to [`BUSY_TIMEOUT`] for the database to be available instead of failing immediately.
+pub fn open(path: impl AsRef<Path>) -> Result<Connection, rusqlite::Error> {
+	let con = Connection::open(path)?;
+	con.busy_timeout(BUSY_TIMEOUT)?;
+	con.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+	con.pragma_update(None, "synchronous", "NORMAL")?;
+	init(&con)?;
+	Ok(con)
+}
+
+/// Opens a cache database in memory, used when no database is set
+pub fn open_in_memory() -> Result<Connection, rusqlite::Error> {
+	let con = Connection::open_in_memory()?;
+	init(&con)?;
+	Ok(con)
+}
+
+/// Creates the cache tables of the elements
+///
+/// Called for every connection by [`open`] and [`open_in_memory`], so that elements can query
+/// the cache of any connection.
+fn init(con: &Connection) -> Result<(), rusqlite::Error> {
+	Code::init(con)?;
+	CodeOutput::init(con)?;
+	Graphviz::init(con)?;
+	FormattedTex::init(con)?;
+	RenderJob::init(con)?;
+	Thumbnail::init(con)?;
+	ImageFile::init(con)
+}
+
+/// Checks whether `err` was caused by another connection writing to the database
+///
+/// Such errors are not fatal for caches: the value was either inserted by the other connection,
+/// or will be computed again by the next build.
+fn is_concurrent_write(err: &rusqlite::Error) -> bool {
+	matches!(
+		err.sqlite_error_code(),
+		Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked | ErrorCode::ConstraintViolation)
+	)
+}
+
 pub enum CachedError<E> {
 	SqlErr(rusqlite::Error),
 	GenErr(E),
@@ -27,6 +89,43 @@ pub trait Cached {
 		con.execute(<Self as Cached>::sql_table(), ()).map(|_| ())
 	}
 
+	/// Looks up the cached value of an element, if any
+	fn lookup(&self, con: &Connection) -> Result<Option<<Self as Cached>::Value>, rusqlite::Error>
+	where
+		<Self as Cached>::Key: ToSql,
+		<Self as Cached>::Value: FromSql,
+	{
+		let key = self.key();
+		let mut query = con.prepare_cached(<Self as Cached>::sql_get_query())?;
+
+		Ok(query
+			.query_row([&key], |row| {
+				Ok(row.get_unwrap::<_, <Self as Cached>::Value>(0))
+			})
+			.ok())
+	}
+
+	/// Inserts the value of an element in the cache
+	///
+	/// Values inserted by another connection in the meantime are kept.
+	fn insert(
+		&self,
+		con: &Connection,
+		value: &<Self as Cached>::Value,
+	) -> Result<(), rusqlite::Error>
+	where
+		<Self as Cached>::Key: ToSql,
+		<Self as Cached>::Value: ToSql,
+	{
+		let key = self.key();
+		let mut query = con.prepare_cached(<Self as Cached>::sql_insert_query())?;
+
+		match query.execute((&key, value)) {
+			Err(err) if err.sqlite_error_code() == Some(ErrorCode::ConstraintViolation) => Ok(()),
+			result => result.map(|_| ()),
+		}
+	}
+
 	/// Attempts to retrieve a cached element from the compilation database
 	/// or create it (and insert it), if it doesn't exist
 	///
@@ -35,7 +134,8 @@ pub trait Cached {
 	/// Will return an error if the database connection(s) fail,
 	/// or if not cached, an error from the generator `f`
 	///
-	/// Note that on error, `f` may still have been called
+	/// Note that on error, `f` may still have been called. Failing to insert the value because
+	/// another connection is writing to the database is not an error.
 	fn cached<E, F>(
 		&self,
 		con: &Connection,
@@ -46,39 +146,93 @@ pub trait Cached {
 		<Self as Cached>::Value: FromSql + ToSql,
 		F: FnOnce(&Self) -> Result<<Self as Cached>::Value, E>,
 	{
-		let key = self.key();
-
 		// Find in cache
-		let mut query = match con.prepare(<Self as Cached>::sql_get_query()) {
-			Ok(query) => query,
-			Err(e) => return Err(CachedError::SqlErr(e)),
-		};
+		if let Some(value) = self.lookup(con).map_err(CachedError::SqlErr)? {
+			return Ok(value);
+		}
 
-		let value = query
-			.query_row([&key], |row| {
-				Ok(row.get_unwrap::<_, <Self as Cached>::Value>(0))
+		// Compute a value, then try to insert
+		let value = f(self).map_err(CachedError::GenErr)?;
+		match self.insert(con, &value) {
+			Err(err) if !is_concurrent_write(&err) => return Err(CachedError::SqlErr(err)),
+			_ => {}
+		}
+
+		Ok(value)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tempdir::TempDir;
+
+	struct Entry(&'static str);
+
+	impl Cached for Entry {
+		type Key = String;
+		type Value = String;
+
+		fn sql_table() -> &'static str {
+			"CREATE TABLE IF NOT EXISTS cached_test (key TEXT PRIMARY KEY, value TEXT NOT NULL);"
+		}
+
+		fn sql_get_query() -> &'static str { "SELECT value FROM cached_test WHERE key = (?1)" }
+
+		fn sql_insert_query() -> &'static str {
+			"INSERT INTO cached_test (key, value) VALUES (?1, ?2)"
+		}
+
+		fn key(&self) -> <Self as Cached>::Key { self.0.to_string() }
+	}
+
+	#[test]
+	fn concurrent() {
+		let dir = TempDir::new("cache");
+		let path = dir.join("cache.db");
+		let first = open(&path).unwrap();
+		let second = open(&path).unwrap();
+		let mode: String = first
+			.query_row("PRAGMA journal_mode", [], |row| row.get(0))
+			.unwrap();
+		assert_eq!(mode, "wal");
+		Entry::init(&first).unwrap();
+
+		// Value inserted by another connection while it was computed
+		let value = Entry("a")
+			.cached(&first, |_| {
+				Entry("a").insert(&second, &"second".to_string()).unwrap();
+				Ok::<_, ()>("first".to_string())
 			})
-			.ok();
-
-		if let Some(value) = value {
-			// Found in cache
-			Ok(value)
-		} else {
-			// Compute a value
-			let value = match f(self) {
-				Ok(val) => val,
-				Err(e) => return Err(CachedError::GenErr(e)),
-			};
-
-			// Try to insert
-			let mut query = match con.prepare(<Self as Cached>::sql_insert_query()) {
-				Ok(query) => query,
-				Err(e) => return Err(CachedError::SqlErr(e)),
-			};
-
-			match query.execute((&key, &value)) {
-				Ok(_) => Ok(value),
-				Err(e) => Err(CachedError::SqlErr(e)),
+			.ok()
+			.unwrap();
+		assert_eq!(value, "first");
+		assert_eq!(
+			Entry("a").lookup(&first).unwrap(),
+			Some("second".to_string())
+		);
+
+		// Readers don't block writers
+		first
+			.execute_batch("BEGIN; SELECT * FROM cached_test;")
+			.unwrap();
+		Entry("b").insert(&second, &"b".to_string()).unwrap();
+		first.execute_batch("COMMIT;").unwrap();
+		assert_eq!(Entry("b").lookup(&first).unwrap(), Some("b".to_string()));
+	}
+
+	#[test]
+	fn tables() {
+		// Every connection gets the tables, not only the first one of the process
+		for _ in 0..2 {
+			let con = open_in_memory().unwrap();
+			for table in ["cached_code", "cached_dot", "cached_tex", "cached_render"] {
+				let count: usize = con
+					.query_row(format!("SELECT COUNT(*) FROM {table}").as_str(), [], |row| {
+						row.get(0)
+					})
+					.unwrap();
+				assert_eq!(count, 0);
 			}
 		}
 	}
@@ -0,0 +1,664 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::UNIX_EPOCH;
+
+use serde::Serialize;
+
+use crate::cache::cache;
+use crate::document::element::Element;
+use crate::elements::conditional::condition::TARGET_VARIABLE;
+use crate::parser::langparser::LangParser;
+use crate::parser::parser::ParseMode;
+use crate::parser::parser::Parser;
+use crate::parser::parser::ParserState;
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
+use crate::parser::source::Source;
+use crate::parser::source::SourceFile;
+use crate::parser::source::SourcePosition;
+
+use super::compiler::CompiledDocument;
+use super::compiler::Compiler;
+use super::compiler::Target;
+use super::exec::ExecPolicy;
+use super::exec::EXEC_VARIABLE;
+use super::process::defines_document;
+use super::store;
+
+/// Settings for [`compile_str`]
+pub struct Settings {
+	/// Target to compile to
+	pub target: Target,
+	/// Variables defined for the document, they take precedence over the definitions made inside
+	/// the document
+	pub defines: Vec<(String, String)>,
+	/// Policy for the external commands elements may invoke
+	pub exec: ExecPolicy,
+	/// Cache database, where the results of external commands are stored. The cache isn't used
+	/// when unset or when the database can't be opened
+	pub database: Option<PathBuf>,
+}
+
+impl Default for Settings {
+	/// Compiles to HTML, external commands are disabled
+	fn default() -> Self {
+		Self {
+			target: Target::HTML,
+			defines: vec![],
+			exec: ExecPolicy {
+				disabled: true,
+				..ExecPolicy::default()
+			},
+			database: None,
+		}
+	}
+}
+
+/// Document compiled by [`compile_str`]
+pub struct Output {
+	/// Compiled document's header
+	pub header: String,
+	/// Compiled document's body, with references resolved
+	pub body: String,
+	/// Compiled document's footer
+	pub footer: String,
+	/// All the variables defined in the document
+	pub variables: HashMap<String, String>,
+	/// Warnings and lints reported while compiling
+	pub reports: Vec<Report>,
+}
+
+impl Output {
+	/// Gets the full page of the compiled document
+	pub fn page(&self) -> String { format!("{}{}{}", self.header, self.body, self.footer) }
+}
+
+/// Document of a project recompiled by [`rebuild`]
+pub struct Artifact {
+	/// Path of the document
+	pub input: String,
+	/// Compiled document, or the reports of the errors that prevented compiling it
+	pub result: Result<Output, Vec<Report>>,
+}
+
+/// Gets the variables defined for the documents, see [`Settings::defines`]
+fn defines(settings: &Settings) -> Vec<(String, String)> {
+	let mut defines = vec![
+		(
+			TARGET_VARIABLE.to_string(),
+			settings.target.name().to_string(),
+		),
+		(EXEC_VARIABLE.to_string(), settings.exec.name().to_string()),
+	];
+	defines.extend(settings.defines.iter().cloned());
+	defines
+}
+
+/// Checks whether `reports` contain errors
+fn has_errors(reports: &[Report]) -> bool {
+	reports
+		.iter()
+		.any(|report| matches!(report.kind, ReportKind::Error))
+}
+
+/// Element of the tree returned by [`parse_ast`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AstNode {
+	/// Name of the element, see [`Element::element_name`]
+	pub name: &'static str,
+	/// Byte range of the element in the parsed string
+	pub range: Range<usize>,
+	/// Elements contained by the element
+	pub children: Vec<AstNode>,
+}
+
+impl AstNode {
+	fn new(elem: &dyn Element) -> Self {
+		let location = elem.location();
+		Self {
+			name: elem.element_name(),
+			range: location.source().original_range(location.range.clone()).1,
+			children: elem.as_container().map_or(vec![], |container| {
+				container
+					.contained()
+					.iter()
+					.map(|elem| Self::new(elem.as_ref()))
+					.collect()
+			}),
+		}
+	}
+}
+
+/// Compiles an NML document from a string
+///
+/// Nothing is displayed: reports are returned, and the cache is only used when
+/// [`Settings::database`] is set. References to other documents can't be resolved.
+///
+/// # Errors
+///
+/// Returns every report (including warnings) when an error is reported while parsing or compiling.
+pub fn compile_str(source: &str, settings: &Settings) -> Result<Output, Vec<Report>> {
+	// Sources without a path don't change the working directory
+	compile_source(
+		Rc::new(SourceFile::with_content(
+			String::new(),
+			source.to_string(),
+			None,
+		)),
+		settings,
+	)
+}
+
+/// Compiles an NML document from a [`Source`], see [`compile_str`]
+pub(crate) fn compile_source(
+	source: Rc<dyn Source>,
+	settings: &Settings,
+) -> Result<Output, Vec<Report>> {
+	let reports = RefCell::new(vec![]);
+	let parser = LangParser::new(
+		false,
+		Box::new(|_, new_reports| reports.borrow_mut().extend(new_reports)),
+	);
+
+	let defines_doc = defines_document(&defines(settings));
+
+	// Parse
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source.clone(),
+		Some(&defines_doc),
+		ParseMode::default(),
+	);
+	if parser.has_error() || has_errors(&reports.borrow()) {
+		return Err(reports.take());
+	}
+
+	// Compile, without cache when the database can't be opened
+	let mut reports = reports.take();
+	let con = settings
+		.database
+		.as_ref()
+		.and_then(|path| match cache::open(path) {
+			Ok(con) => Some(con),
+			Err(err) => {
+				report_warn!(
+					&mut reports,
+					source.clone(),
+					"Unable to open the cache".into(),
+					span(0..0, format!("Unable to open `{}`: {err}", path.display()))
+				);
+				None
+			}
+		});
+	let compiler = Compiler::new(settings.target, con.as_ref(), &settings.exec).with_reports();
+	let (compiled, postprocess) = compiler.compile(&*doc);
+	reports.extend(compiler.take_reports());
+
+	// Post processing
+	let compiled = vec![(RefCell::new(compiled), None)];
+	match postprocess.apply(settings.target, &compiled, &compiled[0].0) {
+		Ok((body, ..)) => compiled[0].0.borrow_mut().body = body,
+		Err(err) => report_err!(
+			&mut reports,
+			source.clone(),
+			"Unable to resolve references".into(),
+			span(0..0, err)
+		),
+	}
+	if has_errors(&reports) {
+		return Err(reports);
+	}
+
+	let compiled = compiled.into_iter().next().unwrap().0.into_inner();
+	Ok(Output {
+		header: compiled.header,
+		body: compiled.body,
+		footer: compiled.footer,
+		variables: compiled.variables,
+		reports,
+	})
+}
+
+/// Gets the documents affected by a change to file `changed`
+///
+/// Affected documents are `changed` itself, the documents importing or including it (directly or
+/// through other affected files), then the documents referencing an affected document, whose
+/// references may have changed.
+fn affected_documents(
+	changed: &str,
+	cached: &HashMap<String, CompiledDocument>,
+	files: &[String],
+) -> HashSet<String> {
+	let mut affected = HashSet::from([changed.to_string()]);
+	loop {
+		let dependents = cached
+			.values()
+			.filter(|doc| !affected.contains(&doc.input))
+			.filter(|doc| doc.dependencies.keys().any(|path| affected.contains(path)))
+			.map(|doc| doc.input.clone())
+			.collect::<Vec<_>>();
+		if dependents.is_empty() {
+			break;
+		}
+		affected.extend(dependents);
+	}
+	let referencing = cached
+		.values()
+		.filter(|doc| doc.links.iter().any(|link| affected.contains(link)))
+		.map(|doc| doc.input.clone())
+		.collect::<Vec<_>>();
+	affected.extend(referencing);
+
+	// Documents missing from the cache are needed to resolve references
+	affected.extend(
+		files
+			.iter()
+			.filter(|input| !cached.contains_key(*input))
+			.cloned(),
+	);
+	affected.retain(|input| files.contains(input));
+	affected
+}
+
+/// Recompiles the documents of a project affected by a change, for editors and other embedding
+/// applications
+///
+/// `files` are the documents of the project and `changed` the path of the modified file, either a
+/// document or a file it imports. `content` is the new content of `changed` when it is a document
+/// whose changes are not saved yet, documents compiled from unsaved content are not considered
+/// up to date by later builds.
+///
+/// The project's documents are kept in the cache of [`Settings::database`] (in memory when unset),
+/// only the documents affected by the change are recompiled, see [`affected_documents`].
+///
+/// # Errors
+///
+/// Returns an error when the cache can't be used, errors reported while compiling a document are
+/// returned in its [`Artifact`].
+pub fn rebuild(
+	files: &[PathBuf],
+	changed: &Path,
+	content: Option<&str>,
+	settings: &Settings,
+) -> Result<Vec<Artifact>, String> {
+	let canonical = |path: &Path| {
+		std::fs::canonicalize(path)
+			.unwrap_or(path.to_path_buf())
+			.to_string_lossy()
+			.to_string()
+	};
+	let files = files.iter().map(|path| canonical(path)).collect::<Vec<_>>();
+	let changed = canonical(changed);
+
+	let con = match &settings.database {
+		Some(path) => cache::open(path),
+		None => cache::open_in_memory(),
+	}
+	.map_err(|err| format!("Unable to open connection to the database: {err}"))?;
+	CompiledDocument::init_cache(&con)
+		.map_err(|err| format!("Failed to initialize cached document table: {err}"))?;
+
+	let mut cached = files
+		.iter()
+		.filter_map(|input| {
+			CompiledDocument::from_cache(&con, input).map(|doc| (input.clone(), doc))
+		})
+		.collect::<HashMap<_, _>>();
+	// Documents compiled with other definitions are not up to date
+	let defines = defines(settings);
+	cached.retain(|_, doc| !doc.defines_changed(&defines));
+	let affected = affected_documents(changed.as_str(), &cached, &files);
+
+	// Compile the affected documents
+	let defines_doc = defines_document(&defines);
+	let mut compiled = vec![];
+	let mut reports = HashMap::new();
+	for input in files.iter().filter(|input| affected.contains(*input)) {
+		let source: Rc<dyn Source> = match content.filter(|_| *input == changed) {
+			Some(content) => Rc::new(SourceFile::with_content(
+				input.clone(),
+				content.to_string(),
+				None,
+			)),
+			None => match SourceFile::new(input.clone(), None) {
+				Ok(source) => Rc::new(source),
+				Err(err) => return Err(format!("Unable to read `{input}`: {err}")),
+			},
+		};
+
+		let parse_reports = RefCell::new(vec![]);
+		let parser = LangParser::new(
+			false,
+			Box::new(|_, new_reports| parse_reports.borrow_mut().extend(new_reports)),
+		);
+		let (doc, _) = parser.parse(
+			ParserState::new(&parser, None),
+			source,
+			Some(&defines_doc),
+			ParseMode::default(),
+		);
+		if parser.has_error() || has_errors(&parse_reports.borrow()) {
+			reports.insert(input.clone(), parse_reports.take());
+			continue;
+		}
+
+		let compiler = Compiler::new(settings.target, Some(&con), &settings.exec).with_reports();
+		let (mut doc, postprocess) = compiler.compile(&*doc);
+		let mut doc_reports = parse_reports.take();
+		doc_reports.extend(compiler.take_reports());
+		reports.insert(input.clone(), doc_reports);
+
+		// Same as [`super::process::process`], unsaved documents are never up to date
+		for (name, value) in &defines {
+			doc.variables.entry(name.clone()).or_insert(value.clone());
+		}
+		doc.defines = defines.iter().cloned().collect();
+		doc.mtime = match content.is_some() && *input == changed {
+			true => 0,
+			false => std::fs::metadata(input)
+				.and_then(|meta| meta.modified())
+				.map_or(0, |time| {
+					time.duration_since(UNIX_EPOCH)
+						.unwrap_or_default()
+						.as_secs()
+				}),
+		};
+		cached.remove(input);
+		compiled.push((RefCell::new(doc), Some(postprocess)));
+	}
+	compiled.extend(cached.into_values().map(|doc| (RefCell::new(doc), None)));
+
+	// Post processing, using the cached documents to resolve references
+	let mut artifacts = vec![];
+	for (doc, postprocess) in &compiled {
+		let Some(postprocess) = postprocess else {
+			continue;
+		};
+		let input = doc.borrow().input.clone();
+		let mut doc_reports = reports.remove(&input).unwrap_or_default();
+		match postprocess.apply(settings.target, &compiled, doc) {
+			Ok((body, splits, links)) => {
+				let mut doc = doc.borrow_mut();
+				doc.body = body;
+				doc.splits = splits;
+				doc.links = links;
+			}
+			Err(err) => report_err!(
+				&mut doc_reports,
+				Rc::new(SourceFile::with_content(input.clone(), String::new(), None))
+					as Rc<dyn Source>,
+				"Unable to resolve references".into(),
+				span(0..0, err)
+			),
+		}
+		if has_errors(&doc_reports) {
+			artifacts.push(Artifact {
+				input,
+				result: Err(doc_reports),
+			});
+			continue;
+		}
+
+		let doc = doc.borrow();
+		let previous = CompiledDocument::from_cache(&con, input.as_str())
+			.map_or(vec![], |cached| cached.assets);
+		con.unchecked_transaction()
+			.and_then(|transaction| {
+				store::update_references(&transaction, &previous, &doc.assets)?;
+				doc.insert_cache(&transaction)?;
+				transaction.commit()
+			})
+			.map_err(|err| {
+				format!("Failed to insert compiled document from `{input}` into cache: {err}")
+			})?;
+		artifacts.push(Artifact {
+			input,
+			result: Ok(Output {
+				header: doc.header.clone(),
+				body: doc.body.clone(),
+				footer: doc.footer.clone(),
+				variables: doc.variables.clone(),
+				reports: doc_reports,
+			}),
+		});
+	}
+
+	// Documents that failed to parse
+	for (input, reports) in reports {
+		artifacts.push(Artifact {
+			input,
+			result: Err(reports),
+		});
+	}
+	artifacts.sort_by_key(|artifact| files.iter().position(|input| *input == artifact.input));
+	Ok(artifacts)
+}
+
+/// Parses an NML document from a string, without compiling it
+///
+/// # Errors
+///
+/// Returns every report (including warnings) when an error is reported while parsing.
+pub fn parse_ast(source: &str, settings: &Settings) -> Result<Vec<AstNode>, Vec<Report>> {
+	let reports = RefCell::new(vec![]);
+	let parser = LangParser::new(
+		false,
+		Box::new(|_, new_reports| reports.borrow_mut().extend(new_reports)),
+	);
+	let defines_doc = defines_document(&defines(settings));
+	let source: Rc<dyn Source> = Rc::new(SourceFile::with_content(
+		String::new(),
+		source.to_string(),
+		None,
+	));
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		Some(&defines_doc),
+		ParseMode::default(),
+	);
+	if parser.has_error() || has_errors(&reports.borrow()) {
+		return Err(reports.take());
+	}
+
+	let content = doc.content().borrow();
+	Ok(content
+		.iter()
+		.map(|elem| AstNode::new(elem.as_ref()))
+		.collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tempdir::TempDir;
+
+	#[test]
+	fn compile() {
+		let settings = Settings {
+			defines: vec![("html.page_title".to_string(), "Embedded".to_string())],
+			..Settings::default()
+		};
+		let output = compile_str(
+			r#"
+@author = Doc
+#{sec} Section
+See &{sec}.
+
+[graph]
+digraph { a -> b }
+[/graph]
+"#,
+			&settings,
+		)
+		.unwrap();
+
+		assert!(output.header.contains("<title>Embedded</title>"));
+		assert!(output
+			.body
+			.contains(r##"<a class="section-reference" href="#Section">"##));
+		assert_eq!(output.variables.get("author").unwrap(), "Doc");
+		assert!(output.page().starts_with(output.header.as_str()));
+		// `dot` is refused
+		assert_eq!(output.reports.len(), 1);
+		assert!(matches!(output.reports[0].kind, ReportKind::Warning));
+	}
+
+	#[test]
+	fn errors() {
+		let reports = compile_str("@import missing.nml\n", &Settings::default()).err();
+		assert!(reports.is_some_and(|reports| !reports.is_empty()));
+
+		// Documents with parse errors aren't compiled, `dot` isn't refused
+		let reports = compile_str(
+			"@import missing.nml\n[graph]\ndigraph { a -> b }\n[/graph]\n",
+			&Settings::default(),
+		)
+		.err()
+		.unwrap();
+		assert!(reports
+			.iter()
+			.all(|report| matches!(report.kind, ReportKind::Error)));
+
+		let reports = compile_str("See &{missing}.\n", &Settings::default()).err();
+		assert!(reports.is_some_and(|reports| reports
+			.iter()
+			.any(|report| matches!(report.kind, ReportKind::Error))));
+	}
+
+	#[test]
+	fn databases() {
+		let root = TempDir::new("databases");
+
+		// One database per workspace, as in the language server
+		for workspace in ["a", "b"] {
+			let settings = Settings {
+				database: Some(root.join(format!("{workspace}.db"))),
+				..Settings::default()
+			};
+			let output = compile_str("[graph]\ndigraph { a -> b }\n[/graph]\n", &settings).unwrap();
+			// `dot` is refused
+			assert_eq!(output.reports.len(), 1);
+			assert!(matches!(output.reports[0].kind, ReportKind::Warning));
+		}
+	}
+
+	#[test]
+	fn database_error() {
+		// Reported instead of printed, the document is compiled without cache
+		let settings = Settings {
+			database: Some(PathBuf::from("/nonexistent/nml/cache.db")),
+			..Settings::default()
+		};
+		let output = compile_str("# Title\n", &settings).unwrap();
+		assert_eq!(output.reports.len(), 1);
+		assert!(matches!(output.reports[0].kind, ReportKind::Warning));
+		assert!(output.body.contains("Title"));
+	}
+
+	#[test]
+	fn partial_rebuild() {
+		let root = TempDir::new("rebuild");
+		std::fs::write(root.join("lib.nml"), "@html.page_title = Page\n").unwrap();
+		std::fs::write(
+			root.join("a.nml"),
+			"@import lib.nml\n@compiler.output = a.html\n#{intro} Introduction\n",
+		)
+		.unwrap();
+		std::fs::write(
+			root.join("b.nml"),
+			"@html.page_title = B\n@compiler.output = b.html\nSee &{a#intro}\n",
+		)
+		.unwrap();
+		std::fs::write(
+			root.join("c.nml"),
+			"@html.page_title = C\n@compiler.output = c.html\n# C\n",
+		)
+		.unwrap();
+		let files = ["a.nml", "b.nml", "c.nml"].map(|file| root.join(file));
+		let settings = Settings {
+			database: Some(root.join("cache.db")),
+			..Settings::default()
+		};
+		let inputs = |artifacts: &[Artifact]| {
+			artifacts
+				.iter()
+				.map(|artifact| {
+					Path::new(&artifact.input)
+						.file_name()
+						.unwrap()
+						.to_string_lossy()
+						.to_string()
+				})
+				.collect::<Vec<_>>()
+		};
+
+		// Nothing is cached yet
+		let artifacts = rebuild(&files, &files[0], None, &settings).unwrap();
+		assert_eq!(inputs(&artifacts), vec!["a.nml", "b.nml", "c.nml"]);
+		assert!(artifacts[1]
+			.result
+			.as_ref()
+			.unwrap()
+			.body
+			.contains("a.html#"));
+
+		// Imported file, `b` references `a`
+		let artifacts = rebuild(&files, &root.join("lib.nml"), None, &settings).unwrap();
+		assert_eq!(inputs(&artifacts), vec!["a.nml", "b.nml"]);
+
+		// Unsaved content
+		let artifacts = rebuild(&files, &files[2], Some("# Changed\n"), &settings).unwrap();
+		assert_eq!(inputs(&artifacts), vec!["c.nml"]);
+		assert!(artifacts[0]
+			.result
+			.as_ref()
+			.unwrap()
+			.body
+			.contains("Changed"));
+
+		// Errors are returned in the artifacts
+		let artifacts =
+			rebuild(&files, &files[2], Some("@import missing.nml\n"), &settings).unwrap();
+		assert!(artifacts[0].result.is_err());
+	}
+
+	#[test]
+	fn rebuild_cached_elements() {
+		let root = TempDir::new("rebuild-graph");
+		let file = root.join("graph.nml");
+		std::fs::write(&file, "[graph]\ndigraph { a -> b }\n[/graph]\n").unwrap();
+
+		// Every rebuild opens a new database in memory
+		for _ in 0..2 {
+			let artifacts =
+				rebuild(std::slice::from_ref(&file), &file, None, &Settings::default()).unwrap();
+			let output = artifacts[0].result.as_ref().unwrap();
+			// `dot` is refused
+			assert_eq!(output.reports.len(), 1);
+			assert!(matches!(output.reports[0].kind, ReportKind::Warning));
+		}
+	}
+
+	#[test]
+	fn ast() {
+		let ast = parse_ast("# Title\n\nSome *text*\n", &Settings::default()).unwrap();
+		assert_eq!(ast[0].name, "Section");
+		assert_eq!(ast[0].range, 0..7);
+		assert_eq!(ast[1].name, "Paragraph");
+		assert_eq!(
+			ast[1]
+				.children
+				.iter()
+				.map(|node| node.name)
+				.collect::<Vec<_>>(),
+			vec!["Text", "Style", "Text", "Style", "Document End"]
+		);
+		assert!(parse_ast("@import missing.nml\n", &Settings::default()).is_err());
+	}
+}
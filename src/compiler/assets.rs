@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::document::document::Document;
+
+use super::compiler::CompiledDocument;
+use super::compiler::Compiler;
+use super::compiler::Target;
+
+/// Name of the variable declaring the extra stylesheets of a document
+pub const CSS_VARIABLE: &str = "html.assets.css";
+
+/// Name of the variable declaring the extra scripts of a document
+pub const JS_VARIABLE: &str = "html.assets.js";
+
+/// Name of the variable holding the stylesheets of the project, see [`CSS_VARIABLE`]
+pub const PROJECT_CSS_VARIABLE: &str = "project.assets.css";
+
+/// Name of the variable holding the scripts of the project, see [`JS_VARIABLE`]
+pub const PROJECT_JS_VARIABLE: &str = "project.assets.js";
+
+/// Name of the directory assets are copied to, relative to the output pages
+pub const ASSETS_DIRECTORY: &str = "assets";
+
+/// Splits the comma separated list of paths of an asset variable
+pub fn asset_paths(value: &str) -> Vec<PathBuf> {
+	value
+		.split(',')
+		.map(str::trim)
+		.filter(|path| !path.is_empty())
+		.map(PathBuf::from)
+		.collect()
+}
+
+/// Gets the assets declared by the project and document variables `project` and `name`
+///
+/// Assets of the project come first, duplicates are removed.
+fn declared_assets<F>(get_variable: F, project: &str, name: &str) -> Vec<PathBuf>
+where
+	F: Fn(&str) -> Option<String>,
+{
+	let mut assets = vec![];
+	for value in [project, name].into_iter().filter_map(&get_variable) {
+		for path in asset_paths(value.as_str()) {
+			if !assets.contains(&path) {
+				assets.push(path);
+			}
+		}
+	}
+	assets
+}
+
+/// Gets the stylesheets and scripts of a compiled document, to be copied to the output
+pub fn compiled_assets(document: &CompiledDocument) -> Vec<PathBuf> {
+	let get_variable = |name: &str| document.get_variable(name).cloned();
+	let mut assets = declared_assets(get_variable, PROJECT_CSS_VARIABLE, CSS_VARIABLE);
+	assets.extend(declared_assets(
+		get_variable,
+		PROJECT_JS_VARIABLE,
+		JS_VARIABLE,
+	));
+	assets
+}
+
+/// Gets the path from the output pages to the assets directory
+///
+/// `output` is the output path of the document's page, as set by `compiler.output`.
+pub fn assets_directory(output: Option<&str>) -> String {
+	let depth = output.map_or(0, |output| output.matches('/').count());
+	"../".repeat(depth) + ASSETS_DIRECTORY
+}
+
+/// Gets the link to an asset, from the output pages
+fn asset_link(directory: &str, path: &Path) -> String {
+	let name = path
+		.file_name()
+		.map_or(String::new(), |name| name.to_string_lossy().to_string());
+	format!("{directory}/{name}")
+}
+
+/// Compiles the links to the stylesheets and scripts of a document, for the page's header
+pub fn compile_assets(target: Target, document: &dyn Document) -> String {
+	let output = document
+		.get_variable("compiler.output")
+		.map(|var| var.to_string());
+	let directory = assets_directory(output.as_deref());
+	let get_variable = |name: &str| document.get_variable(name).map(|var| var.to_string());
+
+	let mut result = String::new();
+	for path in declared_assets(get_variable, PROJECT_CSS_VARIABLE, CSS_VARIABLE) {
+		result += format!(
+			"<link rel=\"stylesheet\" href=\"{}\">",
+			Compiler::sanitize_url(target, asset_link(directory.as_str(), &path))
+		)
+		.as_str();
+	}
+	for path in declared_assets(get_variable, PROJECT_JS_VARIABLE, JS_VARIABLE) {
+		result += format!(
+			"<script src=\"{}\" defer></script>",
+			Compiler::sanitize_url(target, asset_link(directory.as_str(), &path))
+		)
+		.as_str();
+	}
+	result
+}
+
+/// Copies assets to the `directory` of the output
+///
+/// Assets are only copied when they changed.
+///
+/// # Errors
+///
+/// Returns an error when an asset can't be copied, or when two different assets have the same
+/// file name.
+pub fn copy_assets(assets: &[PathBuf], directory: &Path) -> Result<(), String> {
+	let mut copied: HashMap<PathBuf, &PathBuf> = HashMap::new();
+	for asset in assets {
+		let Some(name) = asset.file_name() else {
+			return Err(format!("Invalid asset `{}`", asset.display()));
+		};
+		let destination = directory.join(name);
+		if let Some(other) = copied.insert(destination.clone(), asset) {
+			if other != asset {
+				return Err(format!(
+					"Assets `{}` and `{}` are both copied to `{}`",
+					other.display(),
+					asset.display(),
+					destination.display()
+				));
+			}
+			continue;
+		}
+
+		let content = std::fs::read(asset)
+			.map_err(|err| format!("Unable to read asset `{}`: {err}", asset.display()))?;
+		if std::fs::read(&destination).is_ok_and(|existing| existing == content) {
+			continue;
+		}
+		std::fs::create_dir_all(directory)
+			.and_then(|_| std::fs::write(&destination, content))
+			.map_err(|err| {
+				format!(
+					"Unable to copy asset `{}` to `{}`: {err}",
+					asset.display(),
+					destination.display()
+				)
+			})?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tempdir::TempDir;
+	use crate::compiler::exec::ExecPolicy;
+	use crate::compiler::numbering::SectionNumbering;
+	use crate::compiler::process::process;
+
+	#[test]
+	fn paths() {
+		assert_eq!(
+			asset_paths(" a.css, theme/b.css ,,"),
+			vec![PathBuf::from("a.css"), PathBuf::from("theme/b.css")]
+		);
+		assert_eq!(assets_directory(None), "assets");
+		assert_eq!(assets_directory(Some("doc.html")), "assets");
+		assert_eq!(assets_directory(Some("posts/doc.html")), "../assets");
+	}
+
+	#[test]
+	fn header() {
+		let dir = TempDir::new("assets");
+		let input = dir.join("doc.nml");
+		std::fs::write(
+			&input,
+			"@compiler.output = posts/doc.html\n@html.assets.css = doc.css, theme/project.css\n@html.assets.js = doc.js\n",
+		)
+		.unwrap();
+
+		let defines = vec![(
+			PROJECT_CSS_VARIABLE.to_string(),
+			"theme/project.css".to_string(),
+		)];
+		let processed = process(
+			Target::HTML,
+			vec![input],
+			&rusqlite::Connection::open_in_memory().unwrap(),
+			false,
+			&defines,
+			&SectionNumbering::default(),
+			&ExecPolicy::default(),
+			&vec![],
+		)
+		.unwrap();
+
+		let compiled = processed[0].0.borrow();
+		assert!(compiled.header.contains(concat!(
+			r#"<link rel="stylesheet" href="../assets/project.css">"#,
+			r#"<link rel="stylesheet" href="../assets/doc.css">"#,
+			r#"<script src="../assets/doc.js" defer></script>"#
+		)));
+		assert_eq!(
+			compiled_assets(&compiled),
+			vec![
+				PathBuf::from("theme/project.css"),
+				PathBuf::from("doc.css"),
+				PathBuf::from("doc.js")
+			]
+		);
+	}
+
+	#[test]
+	fn copy() {
+		let dir = TempDir::new("assets-copy");
+		let (a, b) = (dir.join("a/style.css"), dir.join("b/style.css"));
+		for path in [&a, &b] {
+			std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+			std::fs::write(path, path.to_string_lossy().as_bytes()).unwrap();
+		}
+
+		let output = dir.join("out/assets");
+		copy_assets(&[a.clone(), a.clone()], &output).unwrap();
+		let copied = std::fs::read_to_string(output.join("style.css"));
+		let conflict = copy_assets(&[a.clone(), b], &output);
+
+		assert_eq!(copied.unwrap(), a.to_string_lossy());
+		assert!(conflict.is_err());
+	}
+}
@@ -0,0 +1,138 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use super::compiler::CompiledDocument;
+use super::postprocess::PostProcess;
+
+/// Checks whether a document is an entry of the navigation, see
+/// [`super::navigation::create_navigation`]
+fn is_navigation_root(doc: &CompiledDocument) -> bool {
+	(doc.get_variable("nav.title").is_some() || doc.get_variable("doc.title").is_some())
+		&& doc.get_variable("compiler.output").is_some()
+}
+
+/// Finds the documents that can't be reached from the navigation
+///
+/// Documents are reached from the entries of the navigation by following the references to other
+/// documents, and the imports.
+pub(crate) fn orphans(docs: &[(RefCell<CompiledDocument>, Option<PostProcess>)]) -> Vec<String> {
+	let mut reached = HashSet::new();
+	let mut queue = docs
+		.iter()
+		.map(|(doc, _)| doc.borrow())
+		.filter(|doc| is_navigation_root(doc))
+		.map(|doc| doc.input.clone())
+		.collect::<Vec<_>>();
+	while let Some(input) = queue.pop() {
+		if !reached.insert(input.clone()) {
+			continue;
+		}
+		let Some((doc, _)) = docs.iter().find(|(doc, _)| doc.borrow().input == input) else {
+			continue;
+		};
+		let doc = doc.borrow();
+		queue.extend(doc.links.iter().cloned());
+		queue.extend(doc.dependencies.keys().cloned());
+	}
+
+	docs.iter()
+		.map(|(doc, _)| doc.borrow().input.clone())
+		.filter(|input| !reached.contains(input))
+		.collect()
+}
+
+/// Finds the section titles used by several documents
+///
+/// Returns the titles along with the inputs of the documents using them, sorted by title.
+fn duplicate_sections(
+	docs: &[(RefCell<CompiledDocument>, Option<PostProcess>)],
+) -> Vec<(String, Vec<String>)> {
+	let mut titles: HashMap<String, Vec<String>> = HashMap::new();
+	for (doc, _) in docs {
+		let doc = doc.borrow();
+		for title in &doc.sections {
+			let inputs = titles.entry(title.clone()).or_default();
+			if !inputs.contains(&doc.input) {
+				inputs.push(doc.input.clone());
+			}
+		}
+	}
+
+	let mut duplicates = titles
+		.into_iter()
+		.filter(|(_, inputs)| inputs.len() > 1)
+		.collect::<Vec<_>>();
+	duplicates.sort();
+	duplicates
+}
+
+/// Checks the documents of a project build for orphan documents and duplicate sections
+///
+/// Orphan documents are never referenced or imported from the navigation, see [`orphans`].
+/// Sections whose title is used in several documents are reported as duplicate content.
+///
+/// # Returns
+///
+/// The warnings to display
+pub fn audit(docs: &[(RefCell<CompiledDocument>, Option<PostProcess>)]) -> Vec<String> {
+	let mut warnings = orphans(docs)
+		.into_iter()
+		.map(|input| {
+			format!("Document `{input}` is not referenced or imported from the navigation")
+		})
+		.collect::<Vec<_>>();
+	for (title, inputs) in duplicate_sections(docs) {
+		warnings.push(format!(
+			"Section `{title}` is duplicated in documents: {}",
+			inputs
+				.iter()
+				.map(|input| format!("`{input}`"))
+				.collect::<Vec<_>>()
+				.join(", ")
+		));
+	}
+	warnings
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::compiler::compiler::Target;
+	use crate::compiler::process::process_from_memory;
+
+	use super::*;
+
+	#[test]
+	fn warnings() {
+		let result = process_from_memory(
+			Target::HTML,
+			vec![
+				r#"
+@compiler.output = index.html
+@nav.title = Index
+See &{linked#intro}
+"#
+				.into(),
+				r#"
+@compiler.output = linked.html
+#{intro} Introduction
+"#
+				.into(),
+				r#"
+@compiler.output = orphan.html
+# Introduction
+"#
+				.into(),
+			],
+		)
+		.unwrap();
+
+		assert_eq!(
+			audit(&result),
+			vec![
+				"Document `2` is not referenced or imported from the navigation".to_string(),
+				"Section `Introduction` is duplicated in documents: `1`, `2`".to_string(),
+			]
+		);
+	}
+}
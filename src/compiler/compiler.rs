@@ -1,73 +1,216 @@
+use std::cell::Cell;
 use std::cell::Ref;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::path::Path;
 use std::rc::Rc;
+use std::time::UNIX_EPOCH;
 
+use crypto::digest::Digest;
+use crypto::sha2::Sha512;
+use rusqlite::types::Type;
 use rusqlite::Connection;
+use rusqlite::Row;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::document::document::Document;
+use crate::document::element::Element;
 use crate::document::references::CrossReference;
 use crate::document::references::ElemReference;
 use crate::document::variable::Variable;
+use crate::elements::section::elem::Section;
+use crate::elements::tex::elem::Tex;
+use crate::parser::parser::ReportColors;
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
 
+use super::assets::assets_directory;
+use super::assets::compile_assets;
+use super::exec::ExecPolicy;
+use super::numbering::SECTION_OFFSET_VARIABLE;
+use super::numbering::SECTION_PREFIX_VARIABLE;
 use super::postprocess::PostProcess;
+use super::social::social_tags;
+use super::store;
+use super::url::canonical_url;
 
 #[derive(Clone, Copy)]
 pub enum Target {
 	HTML,
 	#[allow(unused)]
 	LATEX,
+	/// reveal.js presentation, each top-level section is a slide
+	SLIDES,
+}
+
+impl Target {
+	/// Gets the name of the target, as exposed to documents
+	pub fn name(&self) -> &'static str {
+		match self {
+			Target::HTML => "html",
+			Target::LATEX => "latex",
+			Target::SLIDES => "slides",
+		}
+	}
+}
+
+/// Gets the modification time of a file, in seconds since epoch
+fn dependency_mtime(path: &Path) -> Option<u64> {
+	std::fs::metadata(path)
+		.and_then(|meta| meta.modified())
+		.ok()
+		.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+		.map(|time| time.as_secs())
+}
+
+/// Increments the section counter `counter` for a section of depth `depth`
+///
+/// Top-level numbers start after `offset`, see [`Compiler::section_offset`].
+pub fn increment_section_counter(counter: &mut Vec<usize>, depth: usize, offset: usize) {
+	// Increment current counter
+	if counter.len() == depth {
+		if let Some(id) = counter.last_mut() {
+			*id += 1;
+		}
+		return;
+	}
+
+	// Close, then increment the counter of the parent
+	if counter.len() > depth {
+		counter.truncate(depth);
+		if let Some(id) = counter.last_mut() {
+			*id += 1;
+		}
+	}
+
+	// Open
+	while counter.len() < depth {
+		counter.push(if counter.is_empty() { offset + 1 } else { 1 });
+	}
 }
 
 pub struct Compiler<'a> {
 	target: Target,
 	cache: Option<&'a Connection>,
+	exec: &'a ExecPolicy,
 	reference_count: RefCell<HashMap<String, HashMap<String, usize>>>,
 	sections_counter: RefCell<Vec<usize>>,
+	section_offset: Cell<usize>,
+	section_prefix: RefCell<String>,
 
 	unresolved_references: RefCell<Vec<(usize, CrossReference)>>,
+	splits: RefCell<Vec<SectionSplit>>,
+	dependencies: RefCell<HashMap<String, u64>>,
+	/// Assets added to the store, see [`Compiler::store_asset`]
+	assets: RefCell<Vec<String>>,
+	/// Number of ids generated for each prefix, see [`Compiler::unique_id`]
+	generated_ids: RefCell<HashMap<String, usize>>,
+	slide_open: Cell<bool>,
+	section_wrappers: RefCell<Vec<usize>>,
+	/// Reports collected instead of being displayed, see [`Compiler::with_reports`]
+	reports: Option<RefCell<Vec<Report>>>,
 }
 
 impl<'a> Compiler<'a> {
-	pub fn new(target: Target, con: Option<&'a Connection>) -> Self {
+	pub fn new(target: Target, con: Option<&'a Connection>, exec: &'a ExecPolicy) -> Self {
 		Self {
 			target,
 			cache: con,
+			exec,
 			reference_count: RefCell::new(HashMap::new()),
 			sections_counter: RefCell::new(vec![]),
+			section_offset: Cell::new(0),
+			section_prefix: RefCell::new(String::new()),
 			unresolved_references: RefCell::new(vec![]),
+			splits: RefCell::new(vec![]),
+			dependencies: RefCell::new(HashMap::new()),
+			assets: RefCell::new(vec![]),
+			generated_ids: RefCell::new(HashMap::new()),
+			slide_open: Cell::new(false),
+			section_wrappers: RefCell::new(vec![]),
+			reports: None,
+		}
+	}
+
+	/// Collects the reports and silences the progress messages of the compiler
+	///
+	/// Collected reports are retrieved using [`Compiler::take_reports`].
+	pub fn with_reports(mut self) -> Self {
+		self.reports = Some(RefCell::new(vec![]));
+		self
+	}
+
+	/// Displays reports, or collects them when created [`Compiler::with_reports`]
+	pub fn report(&self, reports: Vec<Report>) {
+		match &self.reports {
+			Some(collected) => collected.borrow_mut().extend(reports),
+			None => Report::reports_to_stdout(&ReportColors::with_colors(), reports),
+		}
+	}
+
+	/// Takes the reports collected so far
+	pub fn take_reports(&self) -> Vec<Report> {
+		self.reports
+			.as_ref()
+			.map_or(vec![], |collected| collected.take())
+	}
+
+	/// Displays a progress message, unless reports are collected
+	pub fn log(&self, message: &str) {
+		if self.reports.is_none() {
+			println!("{message}");
 		}
 	}
 
 	/// Gets the section counter for a given depth
 	/// This function modifies the section counter
 	pub fn section_counter(&self, depth: usize) -> Ref<'_, Vec<usize>> {
-		// Increment current counter
-		if self.sections_counter.borrow().len() == depth {
-			self.sections_counter
-				.borrow_mut()
-				.last_mut()
-				.map(|id| *id += 1);
-			return Ref::map(self.sections_counter.borrow(), |b| b);
-		}
+		increment_section_counter(
+			&mut self.sections_counter.borrow_mut(),
+			depth,
+			self.section_offset.get(),
+		);
+		Ref::map(self.sections_counter.borrow(), |b| b)
+	}
 
-		// Close
-		while self.sections_counter.borrow().len() > depth {
-			self.sections_counter.borrow_mut().pop();
-		}
+	/// Gets the offset applied to top-level section numbers
+	///
+	/// This is set by the `compiler.section_offset` variable, so that section numbers can continue
+	/// from another document.
+	pub fn section_offset(&self) -> usize { self.section_offset.get() }
+
+	/// Gets the prefix prepended to section numbers
+	///
+	/// This is set by the `compiler.section_prefix` variable.
+	pub fn section_prefix(&self) -> Ref<'_, String> { self.section_prefix.borrow() }
 
-		// Open
-		while self.sections_counter.borrow().len() < depth {
-			self.sections_counter.borrow_mut().push(1);
+	/// Closes the `<section>` wrappers of depth greater or equal to `depth`
+	///
+	/// Returns the closing tags of the wrappers
+	pub fn close_section_wrappers(&self, depth: usize) -> String {
+		let mut result = String::new();
+		let mut wrappers = self.section_wrappers.borrow_mut();
+		while wrappers.last().is_some_and(|last| *last >= depth) {
+			wrappers.pop();
+			result += "</section>";
 		}
+		result
+	}
 
-		Ref::map(self.sections_counter.borrow(), |b| b)
+	/// Registers a `<section>` wrapper for a section of depth `depth`
+	///
+	/// The wrapper must be opened by the caller, it is closed by the next section of lower or
+	/// equal depth, or at the end of the document.
+	pub fn push_section_wrapper(&self, depth: usize) {
+		self.section_wrappers.borrow_mut().push(depth);
 	}
 
 	/// Sanitizes text for a [`Target`]
 	pub fn sanitize<S: AsRef<str>>(target: Target, str: S) -> String {
 		match target {
-			Target::HTML => str
+			Target::HTML | Target::SLIDES => str
 				.as_ref()
 				.replace("&", "&amp;")
 				.replace("<", "&lt;")
@@ -85,7 +228,7 @@ impl<'a> Compiler<'a> {
 	/// by runtime_format.
 	pub fn sanitize_format<S: AsRef<str>>(target: Target, str: S) -> String {
 		match target {
-			Target::HTML => {
+			Target::HTML | Target::SLIDES => {
 				let mut out = String::new();
 
 				let mut braces = 0;
@@ -122,21 +265,75 @@ impl<'a> Compiler<'a> {
 		}
 	}
 
-	/// Gets a reference name
+	/// Sanitizes text placed in a quoted attribute for a [`Target`]
+	///
+	/// Unlike [`Self::sanitize`], single quotes are escaped as well.
+	pub fn sanitize_attribute<S: AsRef<str>>(target: Target, str: S) -> String {
+		match target {
+			Target::HTML | Target::SLIDES => Self::sanitize(target, str).replace('\'', "&#39;"),
+			_ => todo!("Sanitize not implemented"),
+		}
+	}
+
+	/// Percent-encodes the characters of `str` that are neither unreserved nor accepted by `keep`
+	fn percent_encode(str: &str, keep: impl Fn(char) -> bool) -> String {
+		let mut result = String::with_capacity(str.len());
+		for c in str.chars() {
+			if c.is_ascii_alphanumeric() || "-._~".contains(c) || keep(c) {
+				result.push(c);
+				continue;
+			}
+
+			let mut buf = [0u8; 4];
+			for byte in c.encode_utf8(&mut buf).bytes() {
+				result += format!("%{byte:02X}").as_str();
+			}
+		}
+		result
+	}
+
+	/// Sanitizes an url placed in an attribute (e.g `href` or `src`) for a [`Target`]
+	///
+	/// Characters that are not allowed in urls, such as spaces or non-ascii characters, are
+	/// percent-encoded. Reserved characters and percent-encoded sequences are kept, so that valid
+	/// urls are left unchanged.
+	pub fn sanitize_url<S: AsRef<str>>(target: Target, str: S) -> String {
+		let encoded = Self::percent_encode(str.as_ref(), |c| ":/?#[]@!$&'()*+,;=%".contains(c));
+		Self::sanitize_attribute(target, encoded)
+	}
+
+	/// Gets a reference name, to be used as an element's id
 	pub fn refname<S: AsRef<str>>(target: Target, str: S) -> String {
-		Self::sanitize(target, str).replace(' ', "_")
+		Self::sanitize_attribute(target, str.as_ref().replace(' ', "_"))
+	}
+
+	/// Gets the link (`#...`) to the element whose id is the reference name of `str`, see
+	/// [`Self::refname`]
+	pub fn anchor<S: AsRef<str>>(target: Target, str: S) -> String {
+		let encoded = Self::percent_encode(str.as_ref().replace(' ', "_").as_str(), |c| {
+			":/?@!$&'()*+,;=".contains(c)
+		});
+		format!("#{}", Self::sanitize_attribute(target, encoded))
 	}
 
 	/// Inserts or get a reference id for the compiled document
 	///
+	/// Ids are allocated in document order before compiling, see [`Self::allocate_reference_ids`],
+	/// so that they don't depend on the order elements are compiled in.
+	///
 	/// # Parameters
 	/// - [`reference`] The reference to get or insert
 	pub fn reference_id(&self, document: &dyn Document, reference: ElemReference) -> usize {
-		let mut borrow = self.reference_count.borrow_mut();
 		let reference = document.get_from_reference(&reference).unwrap();
-		let refkey = reference.refcount_key();
-		let refname = reference.reference_name().unwrap();
+		self.allocate_reference_id(
+			reference.refcount_key(),
+			reference.reference_name().unwrap(),
+		)
+	}
 
+	/// Gets the id of reference `refname` for counter `refkey`, allocating the next id if needed
+	fn allocate_reference_id(&self, refkey: &str, refname: &String) -> usize {
+		let mut borrow = self.reference_count.borrow_mut();
 		let map = match borrow.get_mut(refkey) {
 			Some(map) => map,
 			None => {
@@ -157,6 +354,37 @@ impl<'a> Compiler<'a> {
 		}
 	}
 
+	/// Allocates the reference ids of the referenceable elements in `elems`, in document order
+	///
+	/// This pass runs before compiling, so a reference to a figure appearing later in the document
+	/// doesn't take the figure's number before the figures preceding it.
+	fn allocate_reference_ids(&self, elems: &[Box<dyn Element>]) {
+		for elem in elems {
+			if let Some(referenceable) = elem.as_referenceable() {
+				if let Some(refname) = referenceable.reference_name() {
+					self.allocate_reference_id(referenceable.refcount_key(), refname);
+				}
+			}
+			if let Some(container) = elem.as_container() {
+				self.allocate_reference_ids(container.contained());
+			}
+		}
+	}
+
+	/// Generates an id for the markup of an element, e.g to link a spoiler's summary to its content
+	///
+	/// Ids are made of `prefix`, a hash of the document's input and a counter, e.g
+	/// `spoiler-3f2a9c1e-2`. They are unique across the pages of a document and across the
+	/// documents of a project.
+	pub fn unique_id(&self, document: &dyn Document, prefix: &str) -> String {
+		let mut hasher = Sha512::new();
+		hasher.input_str(document.source().name());
+		let mut ids = self.generated_ids.borrow_mut();
+		let count = ids.entry(prefix.to_string()).or_default();
+		*count += 1;
+		format!("{prefix}-{}-{count}", &hasher.result_str()[..8])
+	}
+
 	/// Inserts a new crossreference
 	pub fn insert_crossreference(&self, pos: usize, reference: CrossReference) {
 		self.unresolved_references
@@ -164,6 +392,66 @@ impl<'a> Compiler<'a> {
 			.push((pos, reference));
 	}
 
+	/// Inserts a new split point, used to split the output into multiple pages
+	///
+	/// # Parameters
+	/// - [`pos`] The byte position of the split in the compiled document's body
+	pub fn insert_split(&self, pos: usize, title: String, refname: String) {
+		self.splits.borrow_mut().push(SectionSplit {
+			pos,
+			title,
+			refname,
+		});
+	}
+
+	/// Adds a file the compiled document depends on
+	///
+	/// The document gets rebuilt when the modification time of one of its dependencies changes.
+	pub fn add_dependency(&self, path: &Path) {
+		let mtime = dependency_mtime(path).unwrap_or(0);
+		self.dependencies
+			.borrow_mut()
+			.insert(path.to_string_lossy().to_string(), mtime);
+	}
+
+	/// Adds a generated asset to the store, see [`crate::compiler::store`]
+	///
+	/// Returns the link to the stored asset from the document's page, or `None` when the store is
+	/// disabled by [`store::STORE_VARIABLE`] or when there is no cache.
+	pub fn store_asset(
+		&self,
+		document: &dyn Document,
+		content: &[u8],
+		extension: &str,
+	) -> Result<Option<String>, String> {
+		let Some(con) = self
+			.cache
+			.filter(|_| matches!(self.target, Target::HTML | Target::SLIDES))
+		else {
+			return Ok(None);
+		};
+		if !store::store_enabled(document)? {
+			return Ok(None);
+		}
+
+		let name = store::insert(con, content, extension)
+			.map_err(|e| format!("Querying the cache failed: {e}"))?;
+		let link = format!(
+			"{}/{name}",
+			assets_directory(
+				document
+					.get_variable("compiler.output")
+					.map(|var| var.to_string())
+					.as_deref()
+			)
+		);
+		let mut assets = self.assets.borrow_mut();
+		if !assets.contains(&name) {
+			assets.push(name);
+		}
+		Ok(Some(link))
+	}
+
 	pub fn target(&self) -> Target { self.target }
 
 	pub fn cache(&self) -> Option<&'a Connection> {
@@ -171,15 +459,46 @@ impl<'a> Compiler<'a> {
 		//self.cache.as_ref().map(RefCell::borrow_mut)
 	}
 
+	/// Gets the policy for the external commands elements may invoke
+	pub fn exec_policy(&self) -> &'a ExecPolicy { self.exec }
+
+	/// Gets the path to the reveal.js distribution used by [`Target::SLIDES`]
+	///
+	/// This is set by the `slides.reveal` variable, and defaults to `reveal.js`.
+	fn reveal_path(document: &dyn Document) -> String {
+		document
+			.get_variable("slides.reveal")
+			.map_or("reveal.js".to_string(), |var| var.to_string())
+	}
+
+	/// Compiles the transition between slides, before a top-level element
+	///
+	/// A new slide is started for every top-level section, elements preceding the first section
+	/// get their own slide.
+	fn slide_transition(&self, elem: &dyn Element) -> &'static str {
+		let new_slide = elem
+			.downcast_ref::<Section>()
+			.is_some_and(|section| section.depth == 1);
+		match (self.slide_open.replace(true), new_slide) {
+			(true, true) => "</section><section>",
+			(false, _) => "<section>",
+			(true, false) => "",
+		}
+	}
+
 	pub fn header(&self, document: &dyn Document) -> String {
 		pub fn get_variable_or_error(
+			compiler: &Compiler,
 			document: &dyn Document,
 			var_name: &'static str,
 		) -> Option<Rc<dyn Variable>> {
 			document.get_variable(var_name).or_else(|| {
-				println!(
-					"Missing variable `{var_name}` in {}",
-					document.source().name()
+				compiler.log(
+					format!(
+						"Missing variable `{var_name}` in {}",
+						document.source().name()
+					)
+					.as_str(),
 				);
 				None
 			})
@@ -187,10 +506,20 @@ impl<'a> Compiler<'a> {
 
 		let mut result = String::new();
 		match self.target() {
-			Target::HTML => {
-				result += "<!DOCTYPE HTML><html><head>";
+			Target::HTML | Target::SLIDES => {
+				result += "<!DOCTYPE HTML>";
+				match document.get_variable("html.language") {
+					Some(language) => {
+						result += format!(
+							"<html lang=\"{}\"><head>",
+							Compiler::sanitize_attribute(self.target(), language.to_string())
+						)
+						.as_str()
+					}
+					None => result += "<html><head>",
+				}
 				result += "<meta charset=\"UTF-8\">";
-				if let Some(page_title) = get_variable_or_error(document, "html.page_title") {
+				if let Some(page_title) = get_variable_or_error(self, document, "html.page_title") {
 					result += format!(
 						"<title>{}</title>",
 						Compiler::sanitize(self.target(), page_title.to_string())
@@ -198,6 +527,19 @@ impl<'a> Compiler<'a> {
 					.as_str();
 				}
 
+				if let Target::SLIDES = self.target() {
+					let reveal = Self::reveal_path(document);
+					let theme = document
+						.get_variable("slides.theme")
+						.map_or("black".to_string(), |var| var.to_string());
+					result += format!(
+						"<link rel=\"stylesheet\" href=\"{0}/dist/reveal.css\"><link rel=\"stylesheet\" href=\"{0}/dist/theme/{1}.css\">",
+						Compiler::sanitize(self.target(), reveal),
+						Compiler::sanitize(self.target(), theme)
+					)
+					.as_str();
+				}
+
 				if let Some(css) = document.get_variable("html.css") {
 					result += format!(
 						"<link rel=\"stylesheet\" href=\"{}\">",
@@ -205,7 +547,58 @@ impl<'a> Compiler<'a> {
 					)
 					.as_str();
 				}
-				result += r#"</head><body><div class="layout">"#;
+				result += compile_assets(self.target(), document).as_str();
+				if let Some(canonical) = canonical_url(document) {
+					result += format!(
+						"<link rel=\"canonical\" href=\"{}\">",
+						Compiler::sanitize_url(self.target(), canonical)
+					)
+					.as_str();
+				}
+
+				// Meta tags, e.g `@html.meta.author = Me` or `@html.og.title = Title`, followed by the
+				// generated social tags
+				let mut meta = document
+					.scope()
+					.borrow()
+					.variables
+					.iter()
+					.filter_map(|(name, value)| {
+						if let Some(name) = name.strip_prefix("html.meta.") {
+							Some(("name", name.to_string(), value.to_string()))
+						} else {
+							name.strip_prefix("html.og.")
+								.map(|name| ("property", format!("og:{name}"), value.to_string()))
+						}
+					})
+					.collect::<Vec<_>>();
+				meta.extend(social_tags(document, &meta));
+				meta.sort();
+				for (attribute, name, content) in meta {
+					result += format!(
+						"<meta {attribute}=\"{}\" content=\"{}\">",
+						Compiler::sanitize_attribute(self.target(), name),
+						Compiler::sanitize_attribute(self.target(), content)
+					)
+					.as_str();
+				}
+				// Raw html, not sanitized
+				if let Some(extra) = document.get_variable("html.head_extra") {
+					result += extra.to_string().as_str();
+				}
+
+				let body = match document.get_variable("html.body_class") {
+					Some(class) => format!(
+						"<body class=\"{}\">",
+						Compiler::sanitize_attribute(self.target(), class.to_string())
+					),
+					None => "<body>".to_string(),
+				};
+				if let Target::SLIDES = self.target() {
+					result += format!("</head>{body}").as_str();
+				} else {
+					result += format!(r#"</head>{body}<div class="layout">"#).as_str();
+				}
 
 				// TODO: Author, Date, Title, Div
 			}
@@ -214,11 +607,24 @@ impl<'a> Compiler<'a> {
 		result
 	}
 
-	pub fn footer(&self, _document: &dyn Document) -> String {
+	pub fn footer(&self, document: &dyn Document) -> String {
 		let mut result = String::new();
+		// Raw html at the end of the body, not sanitized
+		let extra = document
+			.get_variable("html.footer_extra")
+			.map_or(String::new(), |extra| extra.to_string());
 		match self.target() {
 			Target::HTML => {
-				result += "</div></body></html>";
+				result += format!("</div>{extra}</body></html>").as_str();
+			}
+			Target::SLIDES => {
+				let reveal = Compiler::sanitize(self.target(), Self::reveal_path(document));
+				result += format!("<script src=\"{reveal}/dist/reveal.js\"></script>").as_str();
+				result +=
+					format!("<script src=\"{reveal}/plugin/notes/notes.js\"></script>").as_str();
+				result +=
+					"<script>Reveal.initialize({ hash: true, plugins: [ RevealNotes ] });</script>";
+				result += format!("{extra}</body></html>").as_str();
 			}
 			Target::LATEX => todo!(""),
 		}
@@ -228,21 +634,73 @@ impl<'a> Compiler<'a> {
 	pub fn compile(&self, document: &dyn Document) -> (CompiledDocument, PostProcess) {
 		let borrow = document.content().borrow();
 
+		// Section numbering
+		if let Some(offset) = document.get_variable(SECTION_OFFSET_VARIABLE) {
+			match offset.to_string().trim().parse::<usize>() {
+				Ok(offset) => self.section_offset.set(offset),
+				Err(err) => {
+					let mut reports = vec![];
+					report_err!(
+						&mut reports,
+						offset.value_token().source(),
+						"Invalid Variable Value".into(),
+						span(
+							offset.value_token().range.clone(),
+							format!("Invalid value for `{SECTION_OFFSET_VARIABLE}`: {err}")
+						)
+					);
+					self.report(reports);
+				}
+			}
+		}
+		if let Some(prefix) = document.get_variable(SECTION_PREFIX_VARIABLE) {
+			*self.section_prefix.borrow_mut() = prefix.to_string();
+		}
+
+		// Reference ids
+		self.allocate_reference_ids(&borrow);
+
 		// Header
 		let header = self.header(document);
 
 		// Body
-		let mut body = r#"<div class="content">"#.to_string();
+		Tex::prerender(self, document);
+		let mut body = match self.target() {
+			Target::SLIDES => r#"<div class="reveal"><div class="slides">"#.to_string(),
+			_ => r#"<div class="content">"#.to_string(),
+		};
+		let mut reports = vec![];
+		// End position of every element in the body
+		let mut ends = Vec::with_capacity(borrow.len());
 
 		for i in 0..borrow.len() {
 			let elem = &borrow[i];
+			if let Target::SLIDES = self.target() {
+				body.push_str(self.slide_transition(elem.as_ref()));
+			}
 
 			match elem.compile(self, document, body.len()) {
 				Ok(result) => body.push_str(result.as_str()),
-				Err(err) => println!("Unable to compile element: {err}\n{elem:#?}"),
+				Err(err) => report_err!(
+					&mut reports,
+					elem.location().source(),
+					format!("Unable to compile {}", elem.element_name()),
+					span(elem.location().range.clone(), err)
+				),
+			}
+			ends.push(body.len());
+		}
+		match self.target() {
+			Target::SLIDES if self.slide_open.replace(false) => {
+				body.push_str("</section></div></div>")
+			}
+			Target::SLIDES => body.push_str("</div></div>"),
+			_ => {
+				body += self.close_section_wrappers(0).as_str();
+				body.push_str("</div>")
 			}
 		}
-		body.push_str("</div>");
+		self.report(reports);
 
 		// Footer
 		let footer = self.footer(document);
@@ -270,6 +728,44 @@ impl<'a> Compiler<'a> {
 			})
 			.collect::<HashMap<String, String>>();
 
+		// Pages of the anchors, when the document is split
+		let splits = self.splits.replace(vec![]);
+		let mut anchors = HashMap::new();
+		if !splits.is_empty() {
+			let page = |idx: usize| splits.iter().filter(|split| split.pos < ends[idx]).count();
+			for (idx, elem) in borrow.iter().enumerate() {
+				if let Some(section) = elem.downcast_ref::<Section>() {
+					anchors.insert(
+						Self::anchor(self.target(), section.title.as_str()),
+						page(idx),
+					);
+				}
+			}
+			for reference in document.scope().borrow().referenceable.values() {
+				let idx = match reference {
+					ElemReference::Direct(idx) | ElemReference::Nested(idx, _) => *idx,
+				};
+				let elem = document.get_from_reference(reference).unwrap();
+				let refid = self.reference_id(document, *reference);
+				anchors.insert(
+					Self::anchor(self.target(), elem.refid(self, refid)),
+					page(idx),
+				);
+			}
+		}
+
+		// Sections
+		let sections = borrow
+			.iter()
+			.filter_map(|elem| elem.downcast_ref::<Section>())
+			.map(|section| section.title.clone())
+			.collect();
+
+		// Imported files
+		for path in &document.scope().borrow().imports {
+			self.add_dependency(Path::new(path));
+		}
+
 		let postprocess = PostProcess {
 			resolve_references: self.unresolved_references.replace(vec![]),
 		};
@@ -282,6 +778,19 @@ impl<'a> Compiler<'a> {
 			header,
 			body,
 			footer,
+			splits,
+			anchors,
+			defines: HashMap::new(),
+			dependencies: self.dependencies.replace(HashMap::new()),
+			sections,
+			section_number: self
+				.sections_counter
+				.borrow()
+				.first()
+				.copied()
+				.unwrap_or(self.section_offset.get()),
+			links: vec![],
+			assets: self.assets.replace(vec![]),
 		};
 
 		(cdoc, postprocess)
@@ -309,11 +818,66 @@ pub struct CompiledDocument {
 	pub body: String,
 	/// Compiled document's footer
 	pub footer: String,
+
+	/// Positions in the body where the document should be split into pages
+	/// See [`crate::compiler::split`]
+	pub splits: Vec<SectionSplit>,
+	/// Pages of the anchors of a split document, see [`crate::compiler::split::split_pages`]
+	///
+	/// Anchors are stored as links, see [`Compiler::anchor`]
+	pub anchors: HashMap<String, usize>,
+
+	/// Variables defined from outside the document when it was compiled, see
+	/// [`crate::compiler::process::defines_document`]
+	///
+	/// The document gets rebuilt when the defined variables change, see [`Self::defines_changed`].
+	pub defines: HashMap<String, String>,
+
+	/// Files included by the document, with their modification time
+	/// See [`Compiler::add_dependency`]
+	pub dependencies: HashMap<String, u64>,
+
+	/// Titles of the top-level sections of the document
+	pub sections: Vec<String>,
+	/// Number of the last top-level section of the document, so that the next document can continue
+	/// the numbering, see [`crate::compiler::numbering::SectionNumbering`]
+	pub section_number: usize,
+	/// Inputs of the documents referenced by the document
+	/// See [`PostProcess::apply`]
+	pub links: Vec<String>,
+	/// Names of the assets the document added to the store
+	/// See [`Compiler::store_asset`]
+	pub assets: Vec<String>,
+}
+
+/// Position of a split in a [`CompiledDocument`]'s body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionSplit {
+	/// Byte position of the split in the body
+	pub pos: usize,
+	/// Title of the section starting the page
+	pub title: String,
+	/// Refname of the section starting the page
+	pub refname: String,
 }
 
 impl CompiledDocument {
 	pub fn get_variable(&self, name: &str) -> Option<&String> { self.variables.get(name) }
 
+	/// Checks whether one of the document's dependencies was modified or removed since it was compiled
+	pub fn dependencies_changed(&self) -> bool {
+		self.dependencies
+			.iter()
+			.any(|(path, mtime)| dependency_mtime(Path::new(path)) != Some(*mtime))
+	}
+
+	/// Checks whether the variables defined from outside the document differ from `defines`
+	///
+	/// Definitions that come later in `defines` take precedence.
+	pub fn defines_changed(&self, defines: &[(String, String)]) -> bool {
+		self.defines != defines.iter().cloned().collect::<HashMap<_, _>>()
+	}
+
 	fn sql_table() -> &'static str {
 		"CREATE TABLE IF NOT EXISTS compiled_documents(
 			input TEXT PRIMARY KEY,
@@ -322,30 +886,65 @@ impl CompiledDocument {
 			internal_references TEXT NOT NULL,
 			header TEXT NOT NULL,
 			body TEXT NOT NULL,
-			footer TEXT NOT NULL
+			footer TEXT NOT NULL,
+			splits TEXT NOT NULL,
+			anchors TEXT NOT NULL,
+			defines TEXT NOT NULL,
+			dependencies TEXT NOT NULL,
+			sections TEXT NOT NULL,
+			section_number INTEGER NOT NULL,
+			links TEXT NOT NULL,
+			assets TEXT NOT NULL
 		);"
 	}
 
 	fn sql_get_query() -> &'static str { "SELECT * FROM compiled_documents WHERE input = (?1)" }
 
 	fn sql_insert_query() -> &'static str {
-		"INSERT OR REPLACE INTO compiled_documents (input, mtime, variables, internal_references, header, body, footer) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"
+		"INSERT OR REPLACE INTO compiled_documents (input, mtime, variables, internal_references, header, body, footer, splits, anchors, defines, dependencies, sections, section_number, links, assets) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)"
 	}
 
+	/// Version of the `compiled_documents` schema, stored in the database's `user_version`
+	///
+	/// Must be bumped whenever a column is added to or removed from [`Self::sql_table`]
+	const SCHEMA_VERSION: i32 = 4;
+
+	/// Creates the cache table, dropping entries created with a previous schema
 	pub fn init_cache(con: &Connection) -> Result<usize, rusqlite::Error> {
+		let version: i32 = con.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+		if version != Self::SCHEMA_VERSION {
+			con.execute("DROP TABLE IF EXISTS compiled_documents", [])?;
+			con.pragma_update(None, "user_version", Self::SCHEMA_VERSION)?;
+		}
 		con.execute(Self::sql_table(), [])
 	}
 
+	/// Gets a [`CompiledDocument`] from cache, malformed entries are treated as cache misses
 	pub fn from_cache(con: &Connection, input: &str) -> Option<Self> {
+		fn json<T: DeserializeOwned>(row: &Row, idx: usize) -> Result<T, rusqlite::Error> {
+			let value = row.get::<_, String>(idx)?;
+			serde_json::from_str(value.as_str()).map_err(|err| {
+				rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, Box::new(err))
+			})
+		}
+
 		con.query_row(Self::sql_get_query(), [input], |row| {
 			Ok(CompiledDocument {
 				input: input.to_string(),
-				mtime: row.get_unwrap::<_, u64>(1),
-				variables: serde_json::from_str(row.get_unwrap::<_, String>(2).as_str()).unwrap(),
-				references: serde_json::from_str(row.get_unwrap::<_, String>(3).as_str()).unwrap(),
-				header: row.get_unwrap::<_, String>(4),
-				body: row.get_unwrap::<_, String>(5),
-				footer: row.get_unwrap::<_, String>(6),
+				mtime: row.get(1)?,
+				variables: json(row, 2)?,
+				references: json(row, 3)?,
+				header: row.get(4)?,
+				body: row.get(5)?,
+				footer: row.get(6)?,
+				splits: json(row, 7)?,
+				anchors: json(row, 8)?,
+				defines: json(row, 9)?,
+				dependencies: json(row, 10)?,
+				sections: json(row, 11)?,
+				section_number: row.get(12)?,
+				links: json(row, 13)?,
+				assets: json(row, 14)?,
 			})
 		})
 		.ok()
@@ -363,6 +962,14 @@ impl CompiledDocument {
 				&self.header,
 				&self.body,
 				&self.footer,
+				serde_json::to_string(&self.splits).unwrap(),
+				serde_json::to_string(&self.anchors).unwrap(),
+				serde_json::to_string(&self.defines).unwrap(),
+				serde_json::to_string(&self.dependencies).unwrap(),
+				serde_json::to_string(&self.sections).unwrap(),
+				&self.section_number,
+				serde_json::to_string(&self.links).unwrap(),
+				serde_json::to_string(&self.assets).unwrap(),
 			),
 		)
 	}
@@ -370,6 +977,8 @@ impl CompiledDocument {
 
 #[cfg(test)]
 mod tests {
+	use crate::compiler::process::process_from_memory;
+
 	use super::*;
 
 	#[test]
@@ -388,4 +997,229 @@ mod tests {
 		);
 		assert_eq!(Compiler::sanitize_format(Target::HTML, "{{<"), "{{&lt;");
 	}
+
+	#[test]
+	fn url_test() {
+		assert_eq!(
+			Compiler::sanitize_attribute(Target::HTML, "'a' & \"b\""),
+			"&#39;a&#39; &amp; &quot;b&quot;"
+		);
+
+		assert_eq!(
+			Compiler::sanitize_url(Target::HTML, "https://a.org/p?x=1&y=%20#top"),
+			"https://a.org/p?x=1&amp;y=%20#top"
+		);
+		assert_eq!(
+			Compiler::sanitize_url(Target::HTML, "my file \"é\".png"),
+			"my%20file%20%22%C3%A9%22.png"
+		);
+
+		assert_eq!(Compiler::refname(Target::HTML, "C & C++"), "C_&amp;_C++");
+		assert_eq!(Compiler::anchor(Target::HTML, "C & C++"), "#C_&amp;_C++");
+		assert_eq!(
+			Compiler::anchor(Target::HTML, "50% <é> #1"),
+			"#50%25_%3C%C3%A9%3E_%231"
+		);
+	}
+
+	#[test]
+	fn language() {
+		let result = process_from_memory(
+			Target::HTML,
+			vec![
+				"@html.page_title = 0\n@html.language = fr".into(),
+				"@html.page_title = 0".into(),
+			],
+		)
+		.unwrap();
+
+		assert!(result[0]
+			.0
+			.borrow()
+			.header
+			.starts_with(r#"<!DOCTYPE HTML><html lang="fr"><head>"#));
+		assert!(result[1]
+			.0
+			.borrow()
+			.header
+			.starts_with("<!DOCTYPE HTML><html><head>"));
+	}
+
+	#[test]
+	fn head_and_footer() {
+		let result = process_from_memory(
+			Target::HTML,
+			vec![r#"
+@html.page_title = Page
+@html.body_class = wide dark
+@html.meta.author = A & B
+@html.og.title = "Title"
+@html.og.type = article
+@html.head_extra = <meta name="robots" content="noindex">
+@html.footer_extra = <script src="analytics.js"></script>
+"#
+			.into()],
+		)
+		.unwrap();
+		let doc = result[0].0.borrow();
+
+		assert!(doc.header.ends_with(
+			r#"<meta name="author" content="A &amp; B"><meta property="og:title" content="&quot;Title&quot;"><meta property="og:type" content="article"><meta name="robots" content="noindex"></head><body class="wide dark"><div class="layout">"#
+		));
+		assert_eq!(
+			doc.footer,
+			r#"</div><script src="analytics.js"></script></body></html>"#
+		);
+	}
+
+	#[test]
+	fn unique_ids() {
+		let spoilers = "#+LAYOUT_BEGIN[title=A] Spoiler\nA\n#+LAYOUT_END\n#+LAYOUT_BEGIN[title=B] Spoiler\nB\n#+LAYOUT_END";
+		let result =
+			process_from_memory(Target::HTML, vec![spoilers.into(), spoilers.into()]).unwrap();
+
+		let re = regex::Regex::new(r#"<details class="spoiler" id="([^"]*)">"#).unwrap();
+		let ids = result
+			.iter()
+			.flat_map(|(doc, _)| {
+				re.captures_iter(&doc.borrow().body)
+					.map(|captures| captures[1].to_string())
+					.collect::<Vec<_>>()
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(ids.len(), 4);
+		assert!(ids[0].starts_with("spoiler-") && ids[0].ends_with("-1"));
+		assert!(ids[1].ends_with("-2"));
+		assert!(ids
+			.iter()
+			.enumerate()
+			.all(|(i, id)| !ids[i + 1..].contains(id)));
+
+		let body = &result[0].0.borrow().body;
+		let id = &ids[0];
+		assert!(body.contains(&format!(
+			r#"<summary id="{id}-summary" aria-controls="{id}-content" aria-label="Spoiler: A">A</summary><div id="{id}-content" role="region" aria-labelledby="{id}-summary">"#
+		)));
+	}
+
+	#[test]
+	fn reference_ids() {
+		let result = process_from_memory(
+			Target::HTML,
+			vec!["See &{b} and &{a}\n\n![a](a.png)[caption=A]\n\n![b](b.png)[caption=B]".into()],
+		)
+		.unwrap();
+		let doc = result[0].0.borrow();
+
+		// Numbers follow the document, not the order of the references
+		assert!(doc.body.contains(
+			r##"<a class="medium-ref" href="#medium-2">(2)<img src="b.png" alt=""></a>"##
+		));
+		assert!(doc
+			.body
+			.contains(r#"<div id="medium-1" class="medium"><a href="a.png">"#));
+		assert!(doc.body.contains(r#"<p class="medium-refname">(2)  B</p>"#));
+	}
+
+	#[test]
+	fn slides() {
+		let source = r#"
+@html.page_title = Talk
+@slides.reveal = ../reveal
+Title slide
+
+# First
+#+LAYOUT_BEGIN Split
+A
+#+LAYOUT_NEXT
+B
+#+LAYOUT_END
+>[!Speaker Notes]
+>Remember to breathe
+
+## Detail
+# Second
+"#;
+		let result = process_from_memory(Target::SLIDES, vec![source.to_string()]).unwrap();
+		let doc = result[0].0.borrow();
+
+		assert!(doc
+			.header
+			.contains(r#"<link rel="stylesheet" href="../reveal/dist/reveal.css">"#));
+		assert!(doc
+			.footer
+			.contains(r#"<script src="../reveal/dist/reveal.js"></script>"#));
+		assert!(doc
+			.body
+			.starts_with(r#"<div class="reveal"><div class="slides"><section>"#));
+		assert!(doc.body.ends_with("</section></div></div>"));
+		assert_eq!(doc.body.matches("<section>").count(), 3);
+		assert!(doc.body.contains(r#"<div class="split fragment">"#));
+		assert!(doc
+			.body
+			.contains(r#"<aside class="notes">Remember to breathe</aside>"#));
+
+		// Notes are hidden from documents
+		let result = process_from_memory(Target::HTML, vec![source.to_string()]).unwrap();
+		let doc = result[0].0.borrow();
+		assert!(!doc.body.contains("<section>"));
+		assert!(!doc.body.contains("fragment"));
+		assert!(!doc.body.contains("Remember to breathe"));
+	}
+
+	#[test]
+	fn cache_schema() {
+		// Cache created by a version of nml predating the `splits` column
+		let con = Connection::open_in_memory().unwrap();
+		con.execute_batch(
+			"CREATE TABLE compiled_documents(
+				input TEXT PRIMARY KEY,
+				mtime INTEGER NOT NULL,
+				variables TEXT NOT NULL,
+				internal_references TEXT NOT NULL,
+				header TEXT NOT NULL,
+				body TEXT NOT NULL,
+				footer TEXT NOT NULL
+			);
+			INSERT INTO compiled_documents VALUES ('a.nml', 0, '{}', '{}', '', '', '');",
+		)
+		.unwrap();
+
+		CompiledDocument::init_cache(&con).unwrap();
+		assert!(CompiledDocument::from_cache(&con, "a.nml").is_none());
+
+		let doc = CompiledDocument {
+			input: "a.nml".into(),
+			mtime: 1,
+			variables: HashMap::new(),
+			references: HashMap::new(),
+			header: String::new(),
+			body: "Body".into(),
+			footer: String::new(),
+			splits: vec![],
+			anchors: HashMap::new(),
+			defines: HashMap::new(),
+			dependencies: HashMap::new(),
+			sections: vec![],
+			section_number: 0,
+			links: vec![],
+			assets: vec![],
+		};
+		doc.insert_cache(&con).unwrap();
+		assert_eq!(
+			CompiledDocument::from_cache(&con, "a.nml").unwrap().body,
+			"Body"
+		);
+
+		// Malformed entries are cache misses
+		con.execute("UPDATE compiled_documents SET variables = 'invalid'", [])
+			.unwrap();
+		assert!(CompiledDocument::from_cache(&con, "a.nml").is_none());
+
+		// Up-to-date caches are kept
+		con.execute("UPDATE compiled_documents SET variables = '{}'", [])
+			.unwrap();
+		CompiledDocument::init_cache(&con).unwrap();
+		assert!(CompiledDocument::from_cache(&con, "a.nml").is_some());
+	}
 }
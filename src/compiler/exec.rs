@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde::Deserialize;
+
+/// Name of the variable telling documents whether external commands can be executed
+pub const EXEC_VARIABLE: &str = "compiler.exec";
+
+/// Policy for the external commands (`latex2svg`, `dot`, ...) documents may invoke
+///
+/// Read from the `[exec]` table of the project file.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecPolicy {
+	/// Executables that may be invoked, everything is allowed when unset
+	pub allow: Option<Vec<String>>,
+	/// Absolute paths executables are pinned to, pinned executables are always allowed
+	pub paths: BTreeMap<String, PathBuf>,
+	/// Disables every external command, set by `--no-exec`
+	#[serde(skip)]
+	pub disabled: bool,
+}
+
+impl ExecPolicy {
+	/// Gets the value of [`EXEC_VARIABLE`]
+	pub fn name(&self) -> &'static str {
+		if self.disabled || cfg!(not(feature = "exec")) {
+			"disabled"
+		} else {
+			"enabled"
+		}
+	}
+
+	/// Resolves the path `exec` should be invoked from
+	///
+	/// # Errors
+	///
+	/// Returns an error if `exec` may not be invoked, if it is pinned to a relative path, or when
+	/// built without the `exec` feature.
+	pub fn resolve(&self, exec: &str) -> Result<PathBuf, String> {
+		if cfg!(not(feature = "exec")) {
+			return Err(format!(
+				"Unable to run `{exec}`: external commands are not supported by this build"
+			));
+		}
+		if self.disabled {
+			return Err(format!(
+				"Unable to run `{exec}`: external commands are disabled"
+			));
+		}
+		if let Some(path) = self.paths.get(exec) {
+			if !path.is_absolute() {
+				return Err(format!(
+					"Unable to run `{exec}`: `exec.paths.{exec}` must be an absolute path, got `{}`",
+					path.display()
+				));
+			}
+			return Ok(path.clone());
+		}
+		if self
+			.allow
+			.as_ref()
+			.is_some_and(|allow| !allow.iter().any(|allowed| allowed == exec))
+		{
+			return Err(format!(
+				"Unable to run `{exec}`: not allowed by the project's execution policy"
+			));
+		}
+		Ok(PathBuf::from(exec))
+	}
+}
+
+/// Runs `exec` with `input` on its standard input, and returns its standard output
+///
+/// The process is killed if it doesn't complete within `timeout`.
+///
+/// # Errors
+///
+/// Returns an error if the process can't be spawned, times out or exits with a failure status. The
+/// error contains the process's standard error when available.
+pub fn run(exec: &Path, args: &[&str], input: &str, timeout: Duration) -> Result<String, String> {
+	let name = exec.display();
+	let mut process = Command::new(exec)
+		.args(args)
+		.stdout(Stdio::piped())
+		.stdin(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.map_err(|err| format!("Could not spawn `{name}`: {err}"))?;
+
+	// Pipes are handled from separate threads, so that the process can be killed on timeout
+	let mut stdin = process.stdin.take().unwrap();
+	let input = input.to_string();
+	let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+	let read_pipe = |mut pipe: Box<dyn Read + Send>| {
+		let (sender, receiver) = mpsc::channel();
+		thread::spawn(move || {
+			let mut result = String::new();
+			let _ = sender.send(pipe.read_to_string(&mut result).map(|_| result));
+		});
+		receiver
+	};
+	let stdout = read_pipe(Box::new(process.stdout.take().unwrap()));
+	let stderr = read_pipe(Box::new(process.stderr.take().unwrap()));
+
+	let deadline = Instant::now() + timeout;
+	let status = loop {
+		match process.try_wait() {
+			Ok(Some(status)) => break status,
+			Ok(None) if Instant::now() >= deadline => {
+				let _ = process.kill();
+				let _ = process.wait();
+				return Err(format!(
+					"`{name}` timed out after {}s",
+					timeout.as_secs_f64()
+				));
+			}
+			Ok(None) => thread::sleep(Duration::from_millis(10)),
+			Err(err) => return Err(format!("Unable to wait for `{name}`: {err}")),
+		}
+	};
+
+	// Output pipes may be kept open by children of the process
+	let grace = Duration::from_secs(1);
+	if !status.success() {
+		let stderr = stderr.recv_timeout(grace).ok().and_then(Result::ok);
+		return Err(format!(
+			"`{name}` failed ({status}){}",
+			stderr
+				.filter(|stderr| !stderr.trim().is_empty())
+				.map_or(String::new(), |stderr| format!(": {}", stderr.trim_end()))
+		));
+	}
+	if let Ok(Err(err)) = writer.join() {
+		return Err(format!("Unable to write to `{name}`'s stdin: {err}"));
+	}
+	match stdout.recv_timeout(grace) {
+		Ok(Ok(result)) => Ok(result),
+		Ok(Err(err)) => Err(format!("Unable to read `{name}`'s stdout: {err}")),
+		Err(_) => Err(format!("Unable to read `{name}`'s stdout: pipe left open")),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	#[cfg(feature = "exec")]
+	fn resolve() {
+		let policy = ExecPolicy {
+			allow: Some(vec!["latex2svg".into()]),
+			paths: BTreeMap::from([("dot".into(), PathBuf::from("/usr/bin/dot"))]),
+			disabled: false,
+		};
+		assert_eq!(policy.resolve("latex2svg"), Ok(PathBuf::from("latex2svg")));
+		assert_eq!(policy.resolve("dot"), Ok(PathBuf::from("/usr/bin/dot")));
+		assert!(policy.resolve("/tmp/latex2svg").is_err());
+
+		// Relative pins would depend on the working directory
+		let relative = ExecPolicy {
+			paths: BTreeMap::from([("dot".into(), PathBuf::from("bin/dot"))]),
+			..policy.clone()
+		};
+		assert!(relative.resolve("dot").is_err());
+
+		assert!(ExecPolicy::default().resolve("/tmp/latex2svg").is_ok());
+
+		let disabled = ExecPolicy {
+			disabled: true,
+			..policy
+		};
+		assert!(disabled.resolve("latex2svg").is_err());
+		assert!(disabled.resolve("dot").is_err());
+	}
+}
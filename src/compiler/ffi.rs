@@ -0,0 +1,104 @@
+//! C ABI of the compiler, for embedding NML in other languages
+//!
+//! The library is built as a `cdylib` exporting [`nml_compile`] and [`nml_free`]. For WebAssembly,
+//! build without the `exec` feature so that no external command is ever spawned.
+
+use std::ffi::c_char;
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::ptr;
+
+use crate::parser::parser::ReportColors;
+use crate::parser::reports::Report;
+
+use super::api::compile_str;
+use super::api::Settings;
+
+/// Converts a string for the caller, interior nul bytes are removed
+fn to_c_string(s: String) -> *mut c_char {
+	CString::new(s.replace('\0', ""))
+		.map(CString::into_raw)
+		.unwrap_or(ptr::null_mut())
+}
+
+/// Compiles a nul-terminated NML document to a HTML page
+///
+/// Returns the page, or a null pointer on failure. When `error` isn't null, it is set to the
+/// reports of the compilation formatted as text, or to a null pointer when there are none. Returned
+/// strings must be released using [`nml_free`].
+///
+/// # Safety
+///
+/// `source` must be a valid nul-terminated string, and `error` either null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nml_compile(
+	source: *const c_char,
+	error: *mut *mut c_char,
+) -> *mut c_char {
+	let set_error = |reports: Vec<Report>| {
+		if !error.is_null() {
+			*error = match reports.is_empty() {
+				true => ptr::null_mut(),
+				false => to_c_string(Report::reports_to_string(
+					&ReportColors::without_colors(),
+					reports,
+				)),
+			};
+		}
+	};
+	if source.is_null() {
+		set_error(vec![]);
+		return ptr::null_mut();
+	}
+
+	let source = CStr::from_ptr(source).to_string_lossy();
+	match compile_str(source.as_ref(), &Settings::default()) {
+		Ok(mut output) => {
+			set_error(std::mem::take(&mut output.reports));
+			to_c_string(output.page())
+		}
+		Err(reports) => {
+			set_error(reports);
+			ptr::null_mut()
+		}
+	}
+}
+
+/// Releases a string returned by [`nml_compile`]
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by [`nml_compile`], that wasn't already released.
+#[no_mangle]
+pub unsafe extern "C" fn nml_free(s: *mut c_char) {
+	if !s.is_null() {
+		drop(CString::from_raw(s));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn compile() {
+		unsafe {
+			let source = CString::new("#{sec} Section\nSee &{sec}.\n").unwrap();
+			let mut error = ptr::null_mut();
+			let page = nml_compile(source.as_ptr(), &mut error);
+			assert!(error.is_null());
+			assert!(CStr::from_ptr(page)
+				.to_string_lossy()
+				.contains(r##"<a class="section-reference" href="#Section">"##));
+			nml_free(page);
+
+			let source = CString::new("See &{missing}.\n").unwrap();
+			let page = nml_compile(source.as_ptr(), &mut error);
+			assert!(page.is_null());
+			assert!(CStr::from_ptr(error)
+				.to_string_lossy()
+				.contains("Unable to find reference `missing`"));
+			nml_free(error);
+		}
+	}
+}
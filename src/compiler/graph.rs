@@ -0,0 +1,179 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::audit::orphans;
+use super::compiler::CompiledDocument;
+use super::postprocess::PostProcess;
+
+/// Quotes `str` as a dot identifier
+fn quote(str: &str) -> String { format!("\"{}\"", str.replace('\\', "\\\\").replace('"', "\\\"")) }
+
+/// Label of a document's node: its title, or the name of its input file
+fn label(doc: &CompiledDocument) -> String {
+	doc.get_variable("nav.title")
+		.or(doc.get_variable("doc.title"))
+		.cloned()
+		.unwrap_or_else(|| {
+			Path::new(&doc.input)
+				.file_name()
+				.map_or(doc.input.clone(), |name| name.to_string_lossy().to_string())
+		})
+}
+
+/// Checks whether `to` references `from`, directly or through other documents
+fn reaches(
+	docs: &[(RefCell<CompiledDocument>, Option<PostProcess>)],
+	to: &str,
+	from: &str,
+) -> bool {
+	let mut reached = HashSet::new();
+	let mut queue = vec![to.to_string()];
+	while let Some(input) = queue.pop() {
+		if input == from {
+			return true;
+		}
+		if !reached.insert(input.clone()) {
+			continue;
+		}
+		if let Some((doc, _)) = docs.iter().find(|(doc, _)| doc.borrow().input == input) {
+			queue.extend(doc.borrow().links.iter().cloned());
+		}
+	}
+	false
+}
+
+/// Exports the documents of a project build as a Graphviz dot graph
+///
+/// Documents are linked by their references to other documents (solid edges) and by the files they
+/// import or include (dashed edges). To help spot problems in the structure of the project:
+///  * Documents that can't be reached from the navigation are drawn dashed, see
+///    [`super::audit::audit`]
+///  * References that form a cycle between documents are drawn in red
+///  * Included files that don't exist anymore are drawn in red
+pub fn document_graph(docs: &[(RefCell<CompiledDocument>, Option<PostProcess>)]) -> String {
+	let orphans = orphans(docs);
+	let inputs = docs
+		.iter()
+		.map(|(doc, _)| doc.borrow().input.clone())
+		.collect::<HashSet<_>>();
+
+	let mut graph = "digraph {\n\tnode [shape=box];\n".to_string();
+	let mut files = vec![];
+	for (doc, _) in docs {
+		let doc = doc.borrow();
+		let mut attributes = format!(
+			"label={}, tooltip={}",
+			quote(&label(&doc)),
+			quote(&doc.input)
+		);
+		if orphans.contains(&doc.input) {
+			attributes += ", style=dashed";
+		}
+		graph += format!("\t{} [{attributes}];\n", quote(&doc.input)).as_str();
+
+		for link in &doc.links {
+			let color = match reaches(docs, link, &doc.input) {
+				true => " [color=red]",
+				false => "",
+			};
+			graph += format!("\t{} -> {}{color};\n", quote(&doc.input), quote(link)).as_str();
+		}
+
+		let mut dependencies = doc.dependencies.keys().collect::<Vec<_>>();
+		dependencies.sort();
+		for dependency in dependencies {
+			graph += format!(
+				"\t{} -> {} [style=dashed];\n",
+				quote(&doc.input),
+				quote(dependency)
+			)
+			.as_str();
+			if !inputs.contains(dependency) && !files.contains(dependency) {
+				files.push(dependency.clone());
+			}
+		}
+	}
+
+	// Files that are not part of the build
+	for file in files {
+		let path = Path::new(&file);
+		let name = path
+			.file_name()
+			.map_or(file.clone(), |name| name.to_string_lossy().to_string());
+		let mut attributes = format!(
+			"label={}, tooltip={}, shape=note",
+			quote(&name),
+			quote(&file)
+		);
+		if !path.exists() {
+			attributes += ", color=red";
+		}
+		graph += format!("\t{} [{attributes}];\n", quote(&file)).as_str();
+	}
+	graph += "}\n";
+	graph
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::compiler::compiler::Target;
+	use crate::compiler::process::process_from_memory;
+
+	use super::*;
+
+	#[test]
+	fn graph() {
+		let result = process_from_memory(
+			Target::HTML,
+			vec![
+				r#"
+@compiler.output = index.html
+@nav.title = Index
+See &{a#intro}
+"#
+				.into(),
+				r#"
+@compiler.output = a.html
+#{intro} Introduction
+See &{b#details}
+"#
+				.into(),
+				r#"
+@compiler.output = b.html
+#{details} Details
+Back to &{a#intro}
+"#
+				.into(),
+				r#"
+@compiler.output = orphan.html
+# Orphan
+"#
+				.into(),
+			],
+		)
+		.unwrap();
+		result[3]
+			.0
+			.borrow_mut()
+			.dependencies
+			.insert("/missing/code.rs".into(), 0);
+
+		assert_eq!(
+			document_graph(&result),
+			r#"digraph {
+	node [shape=box];
+	"0" [label="Index", tooltip="0"];
+	"0" -> "1";
+	"1" [label="1", tooltip="1"];
+	"1" -> "2" [color=red];
+	"2" [label="2", tooltip="2"];
+	"2" -> "1" [color=red];
+	"3" [label="3", tooltip="3", style=dashed];
+	"3" -> "/missing/code.rs" [style=dashed];
+	"/missing/code.rs" [label="code.rs", tooltip="/missing/code.rs", shape=note, color=red];
+}
+"#
+		);
+	}
+}
@@ -0,0 +1,283 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha512;
+use rusqlite::Connection;
+use rusqlite::OptionalExtension;
+
+use super::compiler::CompiledDocument;
+use super::compiler::Compiler;
+use super::compiler::Target;
+use super::postprocess::PostProcess;
+
+/// Name of the variable holding the languages of the project, the first one is the language of
+/// the sources
+pub const LANGUAGES_VARIABLE: &str = "project.languages";
+
+/// Translation of a document
+#[derive(Debug)]
+struct Translation {
+	language: String,
+	input: String,
+	output: Option<String>,
+}
+
+/// Documents of a project written in several languages
+///
+/// Translations are parallel sources, in a directory per language: `docs/fr/guide/intro.nml` is
+/// the translation of `docs/en/guide/intro.nml`.
+#[derive(Debug)]
+pub struct Translations {
+	/// Languages of the project, the first one is the language of the sources
+	languages: Vec<String>,
+	/// Translations of every document, by path in the language directories
+	documents: BTreeMap<PathBuf, Vec<Translation>>,
+}
+
+/// Creates the table storing the state of the translations
+///
+/// For every translation, the hash of its source is recorded when the translation is modified.
+fn init(con: &Connection) -> Result<(), rusqlite::Error> {
+	con.execute(
+		"CREATE TABLE IF NOT EXISTS translations (
+			input       TEXT PRIMARY KEY,
+			source_hash TEXT NOT NULL,
+			mtime       INTEGER NOT NULL
+		);",
+		(),
+	)
+	.map(|_| ())
+}
+
+/// Gets the hash of the content of a file
+fn file_hash(path: &str) -> Result<String, String> {
+	let content = std::fs::read(path).map_err(|err| format!("Unable to read `{path}`: {err}"))?;
+	let mut hasher = Sha512::new();
+	hasher.input(&content);
+	Ok(hasher.result_str())
+}
+
+/// Gets the modification time of a file, in seconds
+fn file_mtime(path: &str) -> Result<i64, String> {
+	std::fs::metadata(path)
+		.and_then(|meta| meta.modified())
+		.map(|time| {
+			time.duration_since(UNIX_EPOCH)
+				.map_or(0, |time| time.as_secs() as i64)
+		})
+		.map_err(|err| format!("Unable to get metadata for `{path}`: {err}"))
+}
+
+/// Groups the documents of a project build by translation
+///
+/// `root` is the input directory, containing a directory for each of `languages`. Documents
+/// outside of the language directories are ignored.
+pub fn translations(
+	docs: &[(RefCell<CompiledDocument>, Option<PostProcess>)],
+	root: &Path,
+	languages: &[String],
+) -> Translations {
+	let root = std::fs::canonicalize(root).unwrap_or(root.to_path_buf());
+	let mut documents: BTreeMap<PathBuf, Vec<Translation>> = BTreeMap::new();
+	for (doc, _) in docs {
+		let doc = doc.borrow();
+		for language in languages {
+			let Ok(path) = Path::new(&doc.input).strip_prefix(root.join(language)) else {
+				continue;
+			};
+			documents
+				.entry(path.to_path_buf())
+				.or_default()
+				.push(Translation {
+					language: language.clone(),
+					input: doc.input.clone(),
+					output: doc.get_variable("compiler.output").cloned(),
+				});
+		}
+	}
+	Translations {
+		languages: languages.to_vec(),
+		documents,
+	}
+}
+
+impl Translations {
+	/// Checks that every document is translated, and that translations are up to date
+	///
+	/// A translation is out of date when its source was modified after it. The hash of the source
+	/// is stored in the cache whenever the translation is modified, so that a translation is up to
+	/// date again once it is edited.
+	///
+	/// # Returns
+	///
+	/// The warnings to display
+	pub fn check(&self, con: &Connection) -> Result<Vec<String>, String> {
+		init(con).map_err(|err| format!("Unable to create translations table: {err}"))?;
+		let Some((source_language, languages)) = self.languages.split_first() else {
+			return Ok(vec![]);
+		};
+
+		let mut warnings = vec![];
+		for translations in self.documents.values() {
+			let find = |language: &String| {
+				translations
+					.iter()
+					.find(|translation| &translation.language == language)
+			};
+			let Some(source) = find(source_language) else {
+				for translation in translations {
+					warnings.push(format!(
+						"Translation `{}` has no source in `{source_language}`",
+						translation.input
+					));
+				}
+				continue;
+			};
+
+			let hash = file_hash(source.input.as_str())?;
+			for language in languages {
+				let Some(translation) = find(language) else {
+					warnings.push(format!(
+						"Document `{}` is not translated in `{language}`",
+						source.input
+					));
+					continue;
+				};
+
+				let mtime = file_mtime(translation.input.as_str())?;
+				let recorded = con
+					.query_row(
+						"SELECT source_hash, mtime FROM translations WHERE input = ?1",
+						[&translation.input],
+						|row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+					)
+					.optional()
+					.map_err(|err| format!("Unable to query translations: {err}"))?;
+				match recorded {
+					Some((source_hash, recorded)) if recorded == mtime => {
+						if source_hash != hash {
+							warnings.push(format!(
+								"Translation `{}` is out of date, its source `{}` was modified",
+								translation.input, source.input
+							));
+						}
+					}
+					_ => {
+						con.execute(
+							"INSERT OR REPLACE INTO translations (input, source_hash, mtime) VALUES (?1, ?2, ?3)",
+							(&translation.input, &hash, mtime),
+						)
+						.map_err(|err| format!("Unable to update translations: {err}"))?;
+					}
+				}
+			}
+		}
+		Ok(warnings)
+	}
+
+	/// Gets the header of a document, with links to its translations for language switchers
+	///
+	/// Translations are linked with `<link rel="alternate" hreflang="...">` tags, including the
+	/// document itself.
+	pub fn header(&self, target: Target, doc: &CompiledDocument) -> String {
+		let Some(output) = doc.get_variable("compiler.output") else {
+			return doc.header.clone();
+		};
+		let Some(translations) = self.documents.values().find(|translations| {
+			translations
+				.iter()
+				.any(|translation| translation.input == doc.input)
+		}) else {
+			return doc.header.clone();
+		};
+
+		let depth = output.matches('/').count();
+		let mut links = String::new();
+		for translation in translations {
+			let Some(href) = &translation.output else {
+				continue;
+			};
+			links += format!(
+				"<link rel=\"alternate\" hreflang=\"{}\" href=\"{}\">",
+				Compiler::sanitize_attribute(target, translation.language.as_str()),
+				Compiler::sanitize_url(target, "../".repeat(depth) + href)
+			)
+			.as_str();
+		}
+		doc.header
+			.replacen("</head>", format!("{links}</head>").as_str(), 1)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::File;
+	use std::time::Duration;
+	use std::time::SystemTime;
+
+	use crate::compiler::process::process_from_memory;
+
+	use super::*;
+	use crate::tempdir::TempDir;
+
+	#[test]
+	fn check() {
+		let root = TempDir::new("i18n");
+		std::fs::create_dir_all(root.join("en/guide")).unwrap();
+		std::fs::create_dir_all(root.join("fr/guide")).unwrap();
+		let files = ["en/guide/a.nml", "en/b.nml", "fr/guide/a.nml"];
+		for file in files {
+			std::fs::write(root.join(file), file).unwrap();
+		}
+
+		let result = process_from_memory(
+			Target::HTML,
+			vec![
+				"@compiler.output = en/guide/a.html\n@html.page_title = A".into(),
+				"@compiler.output = en/b.html\n@html.page_title = B".into(),
+				"@compiler.output = fr/guide/a.html\n@html.page_title = A".into(),
+			],
+		)
+		.unwrap();
+		for (i, file) in files.iter().enumerate() {
+			result[i].0.borrow_mut().input = root.join(file).to_string_lossy().to_string();
+		}
+		let languages = vec!["en".to_string(), "fr".to_string()];
+		let translations = translations(&result, &root, &languages);
+		let con = Connection::open_in_memory().unwrap();
+		let input = |file: &str| root.join(file).display().to_string();
+
+		assert_eq!(
+			translations.check(&con).unwrap(),
+			vec![format!(
+				"Document `{}` is not translated in `fr`",
+				input("en/b.nml")
+			)]
+		);
+
+		// Source modified after its translation
+		std::fs::write(root.join("en/guide/a.nml"), "Modified").unwrap();
+		assert!(translations.check(&con).unwrap().contains(&format!(
+			"Translation `{}` is out of date, its source `{}` was modified",
+			input("fr/guide/a.nml"),
+			input("en/guide/a.nml")
+		)));
+
+		// Translation updated
+		File::options()
+			.write(true)
+			.open(root.join("fr/guide/a.nml"))
+			.unwrap()
+			.set_modified(SystemTime::now() + Duration::from_secs(10))
+			.unwrap();
+		assert_eq!(translations.check(&con).unwrap().len(), 1);
+
+		assert!(translations
+			.header(Target::HTML, &result[2].0.borrow())
+			.ends_with(r#"<link rel="alternate" hreflang="en" href="../../en/guide/a.html"><link rel="alternate" hreflang="fr" href="../../fr/guide/a.html"></head><body><div class="layout">"#));
+	}
+}
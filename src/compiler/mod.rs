@@ -1,4 +1,22 @@
+pub mod api;
+pub mod assets;
+pub mod audit;
 pub mod compiler;
+pub mod exec;
+pub mod ffi;
+pub mod graph;
+pub mod i18n;
 pub mod navigation;
+pub mod numbering;
 pub mod postprocess;
 pub mod process;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(any(test, feature = "snapshot"))]
+pub mod snapshot;
+pub mod social;
+pub mod split;
+pub mod standalone;
+pub mod store;
+pub mod svg;
+pub mod url;
@@ -36,7 +36,7 @@ impl NavEntries {
 		let mut result = String::new();
 		match target {
 			Target::HTML => {
-				result += r#"<input id="navbar-checkbox" class="toggle" type="checkbox" style="display:none" checked><div id="navbar"><ul>"#;
+				result += r#"<input id="navbar-checkbox" class="toggle" type="checkbox" style="display:none" checked><div id="navbar" role="navigation" aria-label="Navigation"><ul>"#;
 
 				fn process(
 					target: Target,
@@ -50,7 +50,7 @@ impl NavEntries {
 					// Orphans = Links
 					for entry in &entry.entries {
 						let style = if doc_path == &entry.path {
-							" class=\"navbar-entry-current\""
+							" class=\"navbar-entry-current\" aria-current=\"page\""
 						} else {
 							" class=\"navbar-entry\""
 						}
@@ -75,8 +75,10 @@ impl NavEntries {
 						result.push_str("<li>");
 						result.push_str(
 							format!(
-								"<details{}><summary class=\"navbar-category\">{}</summary>",
+								"<details{}><summary class=\"{}\">{}</summary>",
 								["", " open"][is_match as usize],
+								["navbar-category", "navbar-category navbar-category-current"]
+									[is_match as usize],
 								Compiler::sanitize(target, name)
 							)
 							.as_str(),
@@ -107,7 +109,7 @@ impl NavEntries {
 					0,
 				);
 
-				result += r#"</ul></div><label for="navbar-checkbox" class="navbar-checkbox-label">&#9776;</label>"#;
+				result += r#"</ul></div><label for="navbar-checkbox" class="navbar-checkbox-label" aria-label="Toggle navigation">&#9776;</label>"#;
 			}
 			_ => todo!(""),
 		}
@@ -369,5 +371,15 @@ mod tests {
 				},
 			]
 		);
+
+		// Current entry
+		let compiled = nav.compile(Target::HTML, &result[1].0);
+		assert!(compiled.contains(
+			r#"<li  class="navbar-entry-current" aria-current="page"><a href="1.html">A</a></li>"#
+		));
+		assert!(compiled.contains(r#"<li  class="navbar-entry"><a href="2.html">B</a></li>"#));
+		assert!(compiled.contains(
+			r#"<details open><summary class="navbar-category navbar-category-current">First</summary>"#
+		));
 	}
 }
@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+/// Name of the variable setting the offset of top-level section numbers
+pub const SECTION_OFFSET_VARIABLE: &str = "compiler.section_offset";
+/// Name of the variable setting the prefix of section numbers
+pub const SECTION_PREFIX_VARIABLE: &str = "compiler.section_prefix";
+
+/// Numbering of the sections of a project's documents
+///
+/// Read from the `[numbering]` table of the project file. The section counter of every document is
+/// seeded by defining [`SECTION_OFFSET_VARIABLE`] and [`SECTION_PREFIX_VARIABLE`] for it, see
+/// [`Self::defines`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SectionNumbering {
+	/// Whether top-level section numbers continue from the previous document, in input order
+	pub continuous: bool,
+	/// Numbering of specific documents
+	pub files: BTreeMap<PathBuf, FileNumbering>,
+}
+
+/// Numbering of a document, read from the `[numbering.files]` table of the project file
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileNumbering {
+	/// Number preceding the document's first top-level section, following documents continue
+	/// from the document when numbering is continuous
+	pub offset: Option<usize>,
+	/// Prefix of the document's section numbers, e.g `A.` for an appendix
+	pub prefix: Option<String>,
+}
+
+impl SectionNumbering {
+	/// Gets the numbering of document `input`
+	fn file(&self, input: &Path) -> Option<&FileNumbering> {
+		self.files.iter().find_map(|(path, numbering)| {
+			(path == input || std::fs::canonicalize(path).is_ok_and(|path| path == input))
+				.then_some(numbering)
+		})
+	}
+
+	/// Gets the variables seeding the section counter of document `input`
+	///
+	/// `previous` is the number of the last top-level section of the previous document, see
+	/// [`crate::compiler::compiler::CompiledDocument::section_number`].
+	pub fn defines(&self, input: &Path, previous: usize) -> Vec<(String, String)> {
+		let file = self.file(input);
+		let offset = file
+			.and_then(|file| file.offset)
+			.or(self.continuous.then_some(previous));
+		let prefix = file.and_then(|file| file.prefix.clone());
+		[
+			(SECTION_OFFSET_VARIABLE, offset.map(|offset| offset.to_string())),
+			(SECTION_PREFIX_VARIABLE, prefix),
+		]
+		.into_iter()
+		.filter_map(|(name, value)| Some((name.to_string(), value?)))
+		.collect()
+	}
+}
@@ -3,7 +3,10 @@ use std::cell::RefCell;
 use crate::document::references::CrossReference;
 
 use super::compiler::CompiledDocument;
+use super::compiler::Compiler;
+use super::compiler::SectionSplit;
 use super::compiler::Target;
+use super::split::page_path;
 
 /// Represents the list of tasks that have to run after the document has been compiled and the
 /// compiled document list has been built. Every task is stored with a raw byte position in the
@@ -17,14 +20,21 @@ pub struct PostProcess {
 
 impl PostProcess {
 	/// Applies postprocessing to a [`CompiledDocument`]
+	///
+	/// # Returns
+	///
+	/// The processed body, the document's [`SectionSplit`]s with their positions offset to match
+	/// the new body, and the inputs of the other documents referenced by the document.
 	pub fn apply(
 		&self,
-		_target: Target,
+		target: Target,
 		list: &Vec<(RefCell<CompiledDocument>, Option<PostProcess>)>,
 		doc: &RefCell<CompiledDocument>,
-	) -> Result<String, String> {
+	) -> Result<(String, Vec<SectionSplit>, Vec<String>), String> {
 		let mut content = doc.borrow().body.clone();
 
+		let mut links: Vec<String> = vec![];
+		let mut inserted = vec![];
 		let mut offset = 0;
 		for (pos, cross_ref) in &self.resolve_references {
 			// Cross-references
@@ -70,14 +80,41 @@ impl PostProcess {
 				let found_path = found_borrow
 					.get_variable("compiler.output")
 					.ok_or("Unable to get the output. Aborting postprocessing.".to_string())?;
-				let insert_content = format!("{found_path}#{found_ref}");
+				// Split documents link to the page containing the anchor
+				let anchor = Compiler::anchor(target, found_ref);
+				let page = found_borrow.anchors.get(&anchor).copied().unwrap_or(0);
+				let insert_content = format!(
+					"{}{anchor}",
+					Compiler::sanitize_url(target, page_path(found_path, page))
+				);
+				if !std::ptr::eq(*found_doc, doc) && !links.contains(&found_borrow.input) {
+					links.push(found_borrow.input.clone());
+				}
 				content.insert_str(pos + offset, insert_content.as_str());
+				inserted.push((*pos, insert_content.len()));
 				offset += insert_content.len();
 			} else {
 				return Err(format!("Cannot find reference `{cross_ref}` from document `{}`. Aborting postprocessing.", doc.borrow().input));
 			}
 		}
 
-		Ok(content)
+		// Offset splits
+		let splits = doc
+			.borrow()
+			.splits
+			.iter()
+			.map(|split| {
+				let shift = inserted
+					.iter()
+					.filter(|(pos, _)| *pos < split.pos)
+					.fold(0, |shift, (_, len)| shift + len);
+				SectionSplit {
+					pos: split.pos + shift,
+					..split.clone()
+				}
+			})
+			.collect();
+
+		Ok((content, splits, links))
 	}
 }
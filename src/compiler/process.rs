@@ -1,44 +1,119 @@
 use std::cell::RefCell;
+use std::io::IsTerminal;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::time::UNIX_EPOCH;
 
 use rusqlite::Connection;
 
 use crate::document::document::Document;
+use crate::document::langdocument::LangDocument;
+use crate::document::tree::format_tree;
+use crate::document::variable::BaseVariable;
+use crate::document::variable::DEFINES_SOURCE;
+use crate::elements::conditional::condition::TARGET_VARIABLE;
 use crate::parser::langparser::LangParser;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::Parser;
 use crate::parser::parser::ParserState;
+use crate::parser::parser::ReportColors;
 use crate::parser::source::Source;
 use crate::parser::source::SourceFile;
+use crate::parser::source::Token;
+use crate::parser::sourcemap::source_map;
 
 use super::compiler::CompiledDocument;
 use super::compiler::Compiler;
 use super::compiler::Target;
+use super::exec::ExecPolicy;
+use super::exec::EXEC_VARIABLE;
+use super::numbering::SectionNumbering;
 use super::postprocess::PostProcess;
+use super::store;
+
+/// Set when the build has been interrupted
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Requests the build to stop
+///
+/// Processing stops before the next document, documents already processed are kept in the cache.
+pub fn interrupt() { INTERRUPTED.store(true, Ordering::SeqCst); }
+
+/// Checks whether the build has been interrupted
+pub fn is_interrupted() -> bool { INTERRUPTED.load(Ordering::SeqCst) }
+
+/// Creates the document holding the variables defined from outside the documents
+///
+/// This document is used as the parent of every processed document, so that the defined variables
+/// are accessible from all documents.
+pub fn defines_document<'a>(defines: &[(String, String)]) -> LangDocument<'a> {
+	let mut content = String::new();
+	let mut ranges = vec![];
+	for (name, value) in defines {
+		let start = content.len();
+		content += format!("{name}={value}\n").as_str();
+		ranges.push((
+			start..content.len() - 1,
+			start + name.len() + 1..content.len() - 1,
+		));
+	}
+
+	let source: Rc<dyn Source> = Rc::new(SourceFile::with_content(
+		DEFINES_SOURCE.to_string(),
+		content,
+		None,
+	));
+	let doc = LangDocument::new(source.clone(), None);
+	for ((name, value), (range, value_range)) in defines.iter().zip(ranges) {
+		doc.add_variable(Rc::new(BaseVariable::new(
+			Token::new(range, source.clone()),
+			name.clone(),
+			Token::new(value_range, source.clone()),
+			value.clone(),
+		)));
+	}
+	doc
+}
 
 /// Parses a source file into a document
-fn parse(
+fn parse<'a>(
 	parser: &LangParser,
 	source: Rc<dyn Source>,
+	defines: &'a dyn Document<'a>,
 	debug_opts: &Vec<String>,
-) -> Result<Box<dyn Document<'static>>, String> {
+) -> Result<Box<dyn Document<'a> + 'a>, String> {
 	// Parse
 	//let source = SourceFile::new(input.to_string(), None).unwrap();
-	let (doc, _) = parser.parse(
+	let (doc, state) = parser.parse(
 		ParserState::new(parser, None),
 		source.clone(),
-		None,
+		Some(defines),
 		ParseMode::default(),
 	);
 
-	if debug_opts.contains(&"ast".to_string()) {
+	// `-z ast` or `-z ast=Section,Code`
+	if let Some(filter) = debug_opts.iter().find_map(|opt| match opt.split_once('=') {
+		Some(("ast", names)) => Some(
+			names
+				.split(',')
+				.map(|name| name.trim().to_string())
+				.filter(|name| !name.is_empty())
+				.collect::<Vec<_>>(),
+		),
+		None if opt == "ast" => Some(vec![]),
+		_ => None,
+	}) {
+		let colors = match std::io::stdout().is_terminal() {
+			true => ReportColors::with_colors(),
+			false => ReportColors::without_colors(),
+		};
 		println!("-- BEGIN AST DEBUGGING --");
-		doc.content()
-			.borrow()
-			.iter()
-			.for_each(|elem| println!("{elem:#?}"));
+		print!(
+			"{}",
+			format_tree(&colors, &doc.content().borrow(), &source, &filter)
+		);
 		println!("-- END AST DEBUGGING --");
 	}
 	if debug_opts.contains(&"ref".to_string()) {
@@ -57,6 +132,11 @@ fn parse(
 		});
 		println!("-- END VARIABLES DEBUGGING --");
 	}
+	if debug_opts.contains(&"sourcemap".to_string()) {
+		println!("-- BEGIN SOURCEMAP DEBUGGING --");
+		print!("{}", source_map(&state.shared.sources.borrow()));
+		println!("-- END SOURCEMAP DEBUGGING --");
+	}
 
 	if parser.has_error() {
 		return Err("Parsing failed due to errors while parsing".to_string());
@@ -66,24 +146,49 @@ fn parse(
 }
 
 /// Takes a list of paths and processes it into a list of compiled documents
+///
+/// # Parameters
+///
+/// `defines` is a list of variables defined for every document, they take precedence over the
+/// definitions made inside the documents. The name of the `target` is also defined as variable
+/// `compiler.target`, and whether `exec` allows external commands as `compiler.exec`.
+///
+/// The section counter of every document is seeded according to `numbering`, in the order of
+/// `files`.
+///
+/// Compiled documents are cached in `con`, along with the references of the documents to the
+/// asset store, see [`super::store`].
+#[allow(clippy::too_many_arguments)]
 pub fn process(
 	target: Target,
 	files: Vec<PathBuf>,
-	db_path: &Option<String>,
+	con: &Connection,
 	force_rebuild: bool,
+	defines: &[(String, String)],
+	numbering: &SectionNumbering,
+	exec: &ExecPolicy,
 	debug_opts: &Vec<String>,
 ) -> Result<Vec<(RefCell<CompiledDocument>, Option<PostProcess>)>, String> {
 	let mut compiled = vec![];
+	let project_defines = [
+		defines,
+		&[
+			(TARGET_VARIABLE.to_string(), target.name().to_string()),
+			(EXEC_VARIABLE.to_string(), exec.name().to_string()),
+		],
+	]
+	.concat();
+	let mut section_number = 0;
 
-	let con = db_path
-		.as_ref()
-		.map_or(Connection::open_in_memory(), Connection::open)
-		.map_err(|err| format!("Unable to open connection to the database: {err}"))?;
-	CompiledDocument::init_cache(&con)
+	CompiledDocument::init_cache(con)
 		.map_err(|err| format!("Failed to initialize cached document table: {err}"))?;
 
 	let parser = LangParser::default();
 	for file in files {
+		if is_interrupted() {
+			return Err("Build interrupted".into());
+		}
+
 		let meta = std::fs::metadata(&file)
 			.map_err(|err| format!("Failed to get metadata for `{file:#?}`: {err}"))?;
 
@@ -91,16 +196,30 @@ pub fn process(
 			.modified()
 			.map_err(|err| format!("Unable to query modification time for `{file:#?}`: {err}"))?;
 
+		let defines = [
+			project_defines.as_slice(),
+			&numbering.defines(&file, section_number),
+		]
+		.concat();
+		let defines_doc = defines_document(&defines);
 		let parse_and_compile = || -> Result<(CompiledDocument, Option<PostProcess>), String> {
 			// Parse
+			let compiler = Compiler::new(target, Some(con), exec);
 			let source = SourceFile::new(file.to_str().unwrap().to_string(), None).unwrap();
-			println!("Parsing {}...", source.name());
-			let doc = parse(&parser, Rc::new(source), debug_opts)?;
+			compiler.log(format!("Parsing {}...", source.name()).as_str());
+			let doc = parse(&parser, Rc::new(source), &defines_doc, debug_opts)?;
 
 			// Compile
-			let compiler = Compiler::new(target, Some(&con));
 			let (mut compiled, postprocess) = compiler.compile(&*doc);
 
+			// Keep defined variables, so the document gets rebuilt when they change
+			for (name, value) in &defines {
+				compiled
+					.variables
+					.entry(name.clone())
+					.or_insert(value.clone());
+			}
+			compiled.defines = defines.iter().cloned().collect();
 			compiled.mtime = modified.duration_since(UNIX_EPOCH).unwrap().as_secs();
 
 			Ok((compiled, Some(postprocess)))
@@ -109,9 +228,12 @@ pub fn process(
 		let (cdoc, post) = if force_rebuild {
 			parse_and_compile()?
 		} else {
-			match CompiledDocument::from_cache(&con, file.to_str().unwrap()) {
+			match CompiledDocument::from_cache(con, file.to_str().unwrap()) {
 				Some(compiled) => {
-					if compiled.mtime < modified.duration_since(UNIX_EPOCH).unwrap().as_secs() {
+					if compiled.mtime < modified.duration_since(UNIX_EPOCH).unwrap().as_secs()
+						|| compiled.dependencies_changed()
+						|| compiled.defines_changed(&defines)
+					{
 						parse_and_compile()?
 					} else {
 						(compiled, None)
@@ -121,6 +243,7 @@ pub fn process(
 			}
 		};
 
+		section_number = cdoc.section_number;
 		compiled.push((RefCell::new(cdoc), post));
 	}
 
@@ -128,21 +251,35 @@ pub fn process(
 		if postprocess.is_none() {
 			continue;
 		}
+		if is_interrupted() {
+			return Err("Build interrupted".into());
+		}
 
 		// Post processing
-		let body = postprocess
+		let (body, splits, links) = postprocess
 			.as_ref()
 			.unwrap()
 			.apply(target, &compiled, doc)?;
 		doc.borrow_mut().body = body;
+		doc.borrow_mut().splits = splits;
+		doc.borrow_mut().links = links;
 
-		// Insert into cache
-		doc.borrow().insert_cache(&con).map_err(|err| {
-			format!(
-				"Failed to insert compiled document from `{}` into cache: {err}",
-				doc.borrow().input
-			)
-		})?;
+		// Insert into cache, with the references to the stored assets
+		let doc = doc.borrow();
+		let previous = CompiledDocument::from_cache(con, doc.input.as_str())
+			.map_or(vec![], |cached| cached.assets);
+		con.unchecked_transaction()
+			.and_then(|transaction| {
+				store::update_references(&transaction, &previous, &doc.assets)?;
+				doc.insert_cache(&transaction)?;
+				transaction.commit()
+			})
+			.map_err(|err| {
+				format!(
+					"Failed to insert compiled document from `{}` into cache: {err}",
+					doc.input
+				)
+			})?;
 	}
 
 	Ok(compiled)
@@ -156,16 +293,21 @@ pub fn process_from_memory(
 	sources: Vec<String>,
 ) -> Result<Vec<(RefCell<CompiledDocument>, Option<PostProcess>)>, String> {
 	let mut compiled = vec![];
+	let exec = ExecPolicy::default();
+	let defines_doc = defines_document(&[
+		(TARGET_VARIABLE.to_string(), target.name().to_string()),
+		(EXEC_VARIABLE.to_string(), exec.name().to_string()),
+	]);
 
 	let parser = LangParser::default();
 	for (idx, content) in sources.iter().enumerate() {
 		let parse_and_compile = || -> Result<(CompiledDocument, Option<PostProcess>), String> {
 			// Parse
 			let source = SourceFile::with_content(format!("{idx}"), content.clone(), None);
-			let doc = parse(&parser, Rc::new(source), &vec![])?;
+			let doc = parse(&parser, Rc::new(source), &defines_doc, &vec![])?;
 
 			// Compile
-			let compiler = Compiler::new(target, None);
+			let compiler = Compiler::new(target, None, &exec);
 			let (compiled, postprocess) = compiler.compile(&*doc);
 
 			Ok((compiled, Some(postprocess)))
@@ -181,12 +323,130 @@ pub fn process_from_memory(
 		}
 
 		// Post processing
-		let body = postprocess
+		let (body, splits, links) = postprocess
 			.as_ref()
 			.unwrap()
 			.apply(target, &compiled, doc)?;
 		doc.borrow_mut().body = body;
+		doc.borrow_mut().splits = splits;
+		doc.borrow_mut().links = links;
 	}
 
 	Ok(compiled)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tempdir::TempDir;
+	use crate::compiler::numbering::FileNumbering;
+
+	#[test]
+	fn defines() {
+		let parser = LangParser::default();
+		let defines_doc = defines_document(&[
+			("version".to_string(), "1.2.0".to_string()),
+			("html.page_title".to_string(), "Defined".to_string()),
+		]);
+
+		let source = Rc::new(SourceFile::with_content(
+			"".to_string(),
+			r#"
+@version = dev
+@author = Doc
+"#
+			.to_string(),
+			None,
+		));
+		let doc = parse(&parser, source, &defines_doc, &vec![]).unwrap();
+
+		assert_eq!(doc.get_variable("version").unwrap().to_string(), "1.2.0");
+		assert!(doc.get_variable("version").unwrap().is_external());
+		assert_eq!(doc.get_variable("author").unwrap().to_string(), "Doc");
+		assert!(!doc.get_variable("author").unwrap().is_external());
+
+		let exec = ExecPolicy::default();
+		let compiler = Compiler::new(Target::HTML, None, &exec);
+		let (compiled, _) = compiler.compile(&*doc);
+		assert!(compiled.header.contains("<title>Defined</title>"));
+	}
+
+	#[test]
+	fn removed_define() {
+		let dir = TempDir::new("defines");
+		let file = dir.join("doc.nml");
+		std::fs::write(&file, "%<\" tostring(nml.variable.get(\"version\"))>%\n").unwrap();
+
+		let con = Connection::open_in_memory().unwrap();
+		let exec = ExecPolicy::default();
+		let build = |defines: &[(String, String)]| {
+			let result = process(
+				Target::HTML,
+				vec![file.clone()],
+				&con,
+				false,
+				defines,
+				&SectionNumbering::default(),
+				&exec,
+				&vec![],
+			)
+			.unwrap();
+			let body = result[0].0.borrow().body.clone();
+			body
+		};
+
+		assert!(build(&[("version".into(), "1.2.0".into())]).contains("1.2.0"));
+		// Removing the definition rebuilds the document
+		assert!(build(&[]).contains("nil"));
+	}
+
+	#[test]
+	fn numbering() {
+		let dir = TempDir::new("numbering");
+		let files = ["a.nml", "b.nml", "c.nml"].map(|name| dir.join(name));
+		std::fs::write(&files[0], "# A1\n## Nested\n# A2\n").unwrap();
+		std::fs::write(&files[1], "# B\n").unwrap();
+		std::fs::write(&files[2], "# C\n").unwrap();
+
+		let con = Connection::open_in_memory().unwrap();
+		let exec = ExecPolicy::default();
+		let build = |numbering: &SectionNumbering| {
+			let result = process(
+				Target::HTML,
+				files.to_vec(),
+				&con,
+				false,
+				&[],
+				numbering,
+				&exec,
+				&vec![],
+			)
+			.unwrap();
+			result
+				.iter()
+				.map(|(doc, _)| doc.borrow().body.clone())
+				.collect::<Vec<_>>()
+		};
+
+		let mut numbering = SectionNumbering {
+			continuous: true,
+			..SectionNumbering::default()
+		};
+		numbering.files.insert(
+			files[2].clone(),
+			FileNumbering {
+				offset: None,
+				prefix: Some("A.".into()),
+			},
+		);
+		let bodies = build(&numbering);
+		assert!(bodies[0].contains("2. A2"));
+		assert!(bodies[1].contains("3. B"));
+		assert!(bodies[2].contains("A.4. C"));
+
+		// Documents are rebuilt when their numbering changes
+		let bodies = build(&SectionNumbering::default());
+		assert!(bodies[1].contains("1. B"));
+		assert!(bodies[2].contains("1. C"));
+	}
+}
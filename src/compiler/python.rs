@@ -0,0 +1,114 @@
+//! Python module of the compiler, built with the `python` feature
+//!
+//! The library exports module `nml`, providing `nml.compile(source, **options)` and
+//! `nml.parse_ast(source, **options)`. Reports are raised as `ValueError`s.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use pyo3::exceptions::PyTypeError;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::parser::parser::ReportColors;
+use crate::parser::reports::Report;
+
+use super::api;
+use super::api::AstNode;
+use super::api::Settings;
+use super::compiler::Target;
+
+/// Converts the reports of a failed compilation to a Python exception
+fn reports_error(reports: Vec<Report>) -> PyErr {
+	PyValueError::new_err(Report::reports_to_string(
+		&ReportColors::without_colors(),
+		reports,
+	))
+}
+
+/// Reads the [`Settings`] from the keyword arguments of a function
+///
+/// Accepted options are `target` (`"html"` or `"slides"`), `defines` (a dictionary of variables)
+/// and `database` (path to the cache database). External commands are always disabled.
+fn settings(options: Option<&Bound<'_, PyDict>>) -> PyResult<Settings> {
+	let mut settings = Settings::default();
+	let Some(options) = options else {
+		return Ok(settings);
+	};
+
+	for (key, value) in options.iter() {
+		match key.extract::<String>()?.as_str() {
+			"target" => {
+				settings.target = match value.extract::<String>()?.as_str() {
+					"html" => Target::HTML,
+					"slides" => Target::SLIDES,
+					name => {
+						return Err(PyValueError::new_err(format!(
+							"Unknown target `{name}`, expected `html` or `slides`"
+						)))
+					}
+				}
+			}
+			"defines" => {
+				settings.defines = value
+					.extract::<HashMap<String, String>>()?
+					.into_iter()
+					.collect()
+			}
+			"database" => settings.database = Some(value.extract::<PathBuf>()?),
+			name => {
+				return Err(PyTypeError::new_err(format!(
+					"Unknown option `{name}`, expected `target`, `defines` or `database`"
+				)))
+			}
+		}
+	}
+	Ok(settings)
+}
+
+/// Converts an [`AstNode`] to a dictionary with keys `name`, `range` and `children`
+fn ast_node<'py>(py: Python<'py>, node: &AstNode) -> PyResult<Bound<'py, PyDict>> {
+	let dict = PyDict::new(py);
+	dict.set_item("name", node.name)?;
+	dict.set_item("range", (node.range.start, node.range.end))?;
+	dict.set_item(
+		"children",
+		node.children
+			.iter()
+			.map(|child| ast_node(py, child))
+			.collect::<PyResult<Vec<_>>>()?,
+	)?;
+	Ok(dict)
+}
+
+/// Compiles an NML document to a page, see [`api::compile_str`]
+#[pyfunction]
+#[pyo3(signature = (source, **options))]
+fn compile(source: &str, options: Option<&Bound<'_, PyDict>>) -> PyResult<String> {
+	api::compile_str(source, &settings(options)?)
+		.map(|output| output.page())
+		.map_err(reports_error)
+}
+
+/// Parses an NML document to a list of elements, see [`api::parse_ast`]
+#[pyfunction]
+#[pyo3(signature = (source, **options))]
+fn parse_ast<'py>(
+	py: Python<'py>,
+	source: &str,
+	options: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Vec<Bound<'py, PyDict>>> {
+	api::parse_ast(source, &settings(options)?)
+		.map_err(reports_error)?
+		.iter()
+		.map(|node| ast_node(py, node))
+		.collect()
+}
+
+#[pymodule]
+fn nml(m: &Bound<'_, PyModule>) -> PyResult<()> {
+	m.add_function(wrap_pyfunction!(compile, m)?)?;
+	m.add_function(wrap_pyfunction!(parse_ast, m)?)?;
+	Ok(())
+}
@@ -0,0 +1,190 @@
+//! Snapshot testing of compiled documents
+//!
+//! Available with the `snapshot` feature, for the tests of plugins. The body compiled from a
+//! document is compared to a stored snapshot, see [`assert_snapshot!`](crate::assert_snapshot).
+//! Missing snapshots fail the test, setting [`BLESS_VARIABLE`] (e.g `NML_BLESS=1 cargo test`)
+//! creates them and overwrites the snapshots that differ.
+
+use std::path::Path;
+
+use crate::parser::parser::ReportColors;
+use crate::parser::reports::Report;
+
+use super::api::compile_str;
+use super::api::Settings;
+
+/// Environment variable to set in order to create or overwrite the snapshots
+pub const BLESS_VARIABLE: &str = "NML_BLESS";
+
+/// Number of unchanged lines displayed around changes by [`diff`]
+const CONTEXT: usize = 2;
+
+/// Computes a line diff between `expected` and `found`
+///
+/// Removed lines are prefixed by `-`, added lines by `+`. Only the unchanged lines close to changes
+/// are displayed, see [`CONTEXT`].
+pub fn diff(expected: &str, found: &str) -> String {
+	let expected = expected.lines().collect::<Vec<_>>();
+	let found = found.lines().collect::<Vec<_>>();
+
+	// Longest common subsequence of the lines following (i, j)
+	let mut lcs = vec![vec![0usize; found.len() + 1]; expected.len() + 1];
+	for i in (0..expected.len()).rev() {
+		for j in (0..found.len()).rev() {
+			lcs[i][j] = if expected[i] == found[j] {
+				lcs[i + 1][j + 1] + 1
+			} else {
+				lcs[i + 1][j].max(lcs[i][j + 1])
+			};
+		}
+	}
+
+	let mut lines = vec![];
+	let (mut i, mut j) = (0, 0);
+	while i < expected.len() || j < found.len() {
+		if i < expected.len() && j < found.len() && expected[i] == found[j] {
+			lines.push((' ', expected[i]));
+			i += 1;
+			j += 1;
+		} else if i < expected.len() && (j == found.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+			lines.push(('-', expected[i]));
+			i += 1;
+		} else {
+			lines.push(('+', found[j]));
+			j += 1;
+		}
+	}
+
+	let changed = |index: usize| {
+		lines[index.saturating_sub(CONTEXT)..(index + CONTEXT + 1).min(lines.len())]
+			.iter()
+			.any(|(kind, _)| *kind != ' ')
+	};
+	let mut result = String::new();
+	let mut skipped = false;
+	for (index, (kind, line)) in lines.iter().enumerate() {
+		if changed(index) {
+			result += format!("{kind} {line}\n").as_str();
+			skipped = false;
+		} else if !skipped {
+			result += "  ...\n";
+			skipped = true;
+		}
+	}
+	result
+}
+
+/// Compares `found` to the snapshot at `path`
+///
+/// The snapshot is created or overwritten when [`BLESS_VARIABLE`] is set.
+///
+/// # Errors
+///
+/// Returns the differences with the snapshot, an error when the snapshot is missing, or the error
+/// encountered while writing it.
+pub fn check_snapshot(path: &Path, found: &str) -> Result<(), String> {
+	let bless = std::env::var_os(BLESS_VARIABLE).is_some();
+	match std::fs::read_to_string(path) {
+		Ok(expected) if expected == found => return Ok(()),
+		Ok(expected) if !bless => {
+			return Err(format!(
+				"Output differs from snapshot `{}`, set `{BLESS_VARIABLE}=1` to update it:\n{}",
+				path.display(),
+				diff(expected.as_str(), found)
+			))
+		}
+		Err(_) if !bless => {
+			return Err(format!(
+				"Missing snapshot `{}`, set `{BLESS_VARIABLE}=1` to create it",
+				path.display()
+			))
+		}
+		_ => {}
+	}
+
+	if let Some(parent) = path.parent() {
+		std::fs::create_dir_all(parent).map_err(|err| {
+			format!(
+				"Unable to create snapshot directory `{}`: {err}",
+				parent.display()
+			)
+		})?;
+	}
+	std::fs::write(path, found)
+		.map_err(|err| format!("Unable to write snapshot `{}`: {err}", path.display()))
+}
+
+/// Formats a compiled body for snapshots, adjacent tags are put on separate lines
+fn split_tags(body: &str) -> String { body.replace("><", ">\n<") + "\n" }
+
+/// Compiles `source` and compares the body of the result to the snapshot at `path`
+///
+/// Snapshots are formatted using [`split_tags`], so that differences are reported by line.
+///
+/// # Panics
+///
+/// Panics when the document fails to compile, or when it doesn't match the snapshot.
+pub fn assert_snapshot_file(path: &Path, source: &str, settings: &Settings) {
+	let output = compile_str(source, settings).unwrap_or_else(|reports| {
+		panic!(
+			"Unable to compile document:\n{}",
+			Report::reports_to_string(&ReportColors::without_colors(), reports)
+		)
+	});
+	if let Err(err) = check_snapshot(path, split_tags(output.body.as_str()).as_str()) {
+		panic!("{err}");
+	}
+}
+
+/// Compiles a document and compares it to the snapshot `<name>.html`
+///
+/// Snapshots are stored in the `snapshots` directory next to the file invoking the macro. The
+/// document is compiled using [`Settings::default`](crate::compiler::api::Settings), unless
+/// settings are given:
+/// ```ignore
+/// nml_core::assert_snapshot!("heading", "# Hello");
+/// nml_core::assert_snapshot!("heading_latex", "# Hello", &settings);
+/// ```
+#[macro_export]
+macro_rules! assert_snapshot {
+	($name:expr, $source:expr) => {
+		$crate::assert_snapshot!($name, $source, &$crate::compiler::api::Settings::default())
+	};
+	($name:expr, $source:expr, $settings:expr) => {{
+		let path = ::std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+			.join(file!())
+			.with_file_name("snapshots")
+			.join(format!("{}.html", $name));
+		$crate::compiler::snapshot::assert_snapshot_file(&path, $source, $settings)
+	}};
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tempdir::TempDir;
+
+	#[test]
+	fn diffs() {
+		assert_eq!(diff("a\nb\nc\n", "a\nb\nc\n"), "  ...\n");
+		assert_eq!(diff("a\nb\nc\n", "a\nx\nc\n"), "  a\n- b\n+ x\n  c\n");
+		assert_eq!(
+			diff("1\n2\n3\n4\n5\n6\n7\n", "1\n2\n3\n4\n5\n6\n7\n8\n"),
+			"  ...\n  6\n  7\n+ 8\n"
+		);
+	}
+
+	#[test]
+	fn snapshots() {
+		let dir = TempDir::new("snapshot");
+		let path = dir.join("doc.html");
+		let err = check_snapshot(&path, "a\nb\n").unwrap_err();
+		assert!(err.starts_with("Missing snapshot"));
+		assert!(!path.exists());
+
+		std::fs::write(&path, "a\nb\n").unwrap();
+		assert_eq!(check_snapshot(&path, "a\nb\n"), Ok(()));
+		let err = check_snapshot(&path, "a\nc\n").unwrap_err();
+		assert!(err.ends_with("  a\n- b\n+ c\n"));
+	}
+}
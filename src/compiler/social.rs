@@ -0,0 +1,152 @@
+use crate::document::document::Document;
+use crate::document::element::Element;
+use crate::elements::media::elem::MediaType;
+use crate::elements::media::elem::Medium;
+
+use super::url::absolute_url;
+use super::url::canonical_url;
+
+/// Name of the variable enabling the social tags, set by the `[social]` table of the project
+pub const SOCIAL_VARIABLE: &str = "social.enabled";
+
+/// Name of the variable holding the image of pages without images, relative to the base url of
+/// the project
+pub const IMAGE_VARIABLE: &str = "social.image";
+
+/// Name of the variable holding the name of the website
+pub const SITE_NAME_VARIABLE: &str = "social.site_name";
+
+/// Name of the variable holding the Twitter account of the website
+pub const TWITTER_VARIABLE: &str = "social.twitter";
+
+/// Gets the location of the first image of a document
+fn first_image(elems: &[Box<dyn Element>]) -> Option<String> {
+	elems.iter().find_map(|elem| {
+		if let Some(medium) = elem.downcast_ref::<Medium>() {
+			return matches!(medium.media_type, MediaType::IMAGE).then(|| medium.uri.clone());
+		}
+		elem.as_container()
+			.and_then(|container| first_image(container.contained()))
+	})
+}
+
+/// Gets the OpenGraph and Twitter card tags of a document, as `(attribute, name, content)`
+///
+/// Tags are generated when variable [`SOCIAL_VARIABLE`] is `true`:
+///  * `og:title` from `doc.title`, or the page title
+///  * `og:description` from `html.meta.description`
+///  * `og:image` from the first image of the document, or [`IMAGE_VARIABLE`]
+///  * `og:url` from the canonical url of the page, see [`canonical_url`]
+///  * `og:site_name` and `twitter:site` from [`SITE_NAME_VARIABLE`] and [`TWITTER_VARIABLE`]
+///
+/// Tags already present in `meta`, e.g set with `@html.og.image`, are not generated again.
+pub fn social_tags(
+	document: &dyn Document,
+	meta: &[(&'static str, String, String)],
+) -> Vec<(&'static str, String, String)> {
+	let get_variable = |name: &str| document.get_variable(name).map(|var| var.to_string());
+	if get_variable(SOCIAL_VARIABLE).as_deref() != Some("true") {
+		return vec![];
+	}
+
+	let output = get_variable("compiler.output");
+	let directory = output
+		.as_ref()
+		.and_then(|output| output.rfind('/').map(|pos| &output[..=pos]))
+		.unwrap_or("");
+	let image = first_image(&document.content().borrow())
+		.map(|uri| absolute_url(document, directory, uri.as_str()))
+		.or_else(|| {
+			get_variable(IMAGE_VARIABLE)
+				.map(|image| absolute_url(document, "", image.trim_start_matches('/')))
+		});
+
+	let og = [
+		(
+			"title",
+			get_variable("doc.title").or_else(|| get_variable("html.page_title")),
+		),
+		("description", get_variable("html.meta.description")),
+		("image", image),
+		("type", Some("website".to_string())),
+		("url", canonical_url(document)),
+		("site_name", get_variable(SITE_NAME_VARIABLE)),
+	];
+	let mut tags = og
+		.into_iter()
+		.filter_map(|(name, content)| Some(("property", format!("og:{name}"), content?)))
+		.collect::<Vec<_>>();
+
+	let has_image = tags
+		.iter()
+		.chain(meta.iter())
+		.any(|(_, name, _)| name == "og:image");
+	let card = match has_image {
+		true => "summary_large_image",
+		false => "summary",
+	};
+	tags.push(("name", "twitter:card".to_string(), card.to_string()));
+	if let Some(twitter) = get_variable(TWITTER_VARIABLE) {
+		tags.push(("name", "twitter:site".to_string(), twitter));
+	}
+
+	tags.retain(|(_, name, _)| !meta.iter().any(|(_, meta, _)| meta == name));
+	tags
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::compiler::compiler::Target;
+	use crate::compiler::process::process_from_memory;
+
+	#[test]
+	fn tags() {
+		let result = process_from_memory(
+			Target::HTML,
+			vec![
+				r#"
+@social.enabled = true
+@project.base_url = https://example.com/
+@social.image = /default.png
+@social.site_name = Site
+@social.twitter = @site
+@compiler.output = posts/post.html
+@html.page_title = Post
+@html.meta.description = About things
+@html.og.type = article
+
+![Cover](images/cover.png)
+"#
+				.into(),
+				r#"
+@social.enabled = true
+@project.base_url = https://example.com
+@social.image = default.png
+@html.page_title = Index
+"#
+				.into(),
+				"@html.page_title = Disabled\n".into(),
+			],
+		)
+		.unwrap();
+
+		assert!(result[0].0.borrow().header.contains(concat!(
+			r#"<meta name="description" content="About things">"#,
+			r#"<meta name="twitter:card" content="summary_large_image">"#,
+			r#"<meta name="twitter:site" content="@site">"#,
+			r#"<meta property="og:description" content="About things">"#,
+			r#"<meta property="og:image" content="https://example.com/posts/images/cover.png">"#,
+			r#"<meta property="og:site_name" content="Site">"#,
+			r#"<meta property="og:title" content="Post">"#,
+			r#"<meta property="og:type" content="article">"#,
+			r#"<meta property="og:url" content="https://example.com/posts/post.html">"#,
+		)));
+		assert!(result[1].0.borrow().header.contains(concat!(
+			r#"<meta name="twitter:card" content="summary_large_image">"#,
+			r#"<meta property="og:image" content="https://example.com/default.png">"#,
+			r#"<meta property="og:title" content="Index">"#,
+			r#"<meta property="og:type" content="website">"#,
+		)));
+		assert!(!result[2].0.borrow().header.contains("og:"));
+	}
+}
@@ -0,0 +1,302 @@
+use std::path::Path;
+
+use crate::compiler::compiler::Compiler;
+
+use super::compiler::CompiledDocument;
+use super::compiler::Target;
+
+/// Name of the variable enabling splitting documents by top-level sections
+pub const SPLIT_SECTIONS_VARIABLE: &str = "compiler.split_sections";
+
+/// A page of a split [`CompiledDocument`]
+#[derive(Debug)]
+pub struct Page {
+	/// Output path of the page
+	pub path: String,
+	/// Body of the page, header and footer excluded
+	pub body: String,
+}
+
+/// Gets the output path of the `n`-th page for a document's output path
+///
+/// # Example
+///
+/// `page_path("out/doc.html", 2) == "out/doc-2.html"`
+pub(crate) fn page_path(path: &str, n: usize) -> String {
+	if n == 0 {
+		return path.to_string();
+	}
+
+	let file_start = path.rfind('/').map_or(0, |pos| pos + 1);
+	match path[file_start..].rfind('.') {
+		Some(ext) => format!(
+			"{}-{n}{}",
+			&path[..file_start + ext],
+			&path[file_start + ext..]
+		),
+		None => format!("{path}-{n}"),
+	}
+}
+
+/// Gets the link to a page, relative to the other pages
+fn page_link(path: &str, n: usize) -> String {
+	let path = page_path(path, n);
+	Path::new(&path)
+		.file_name()
+		.and_then(|name| name.to_str())
+		.map_or(path.clone(), |name| name.to_string())
+}
+
+/// Makes the in-page links of the `n`-th page to anchors located in other pages link to these pages
+fn link_anchors(doc: &CompiledDocument, path: &str, n: usize, body: &str) -> String {
+	const HREF: &str = "href=\"#";
+
+	let mut result = String::with_capacity(body.len());
+	let mut rest = body;
+	while let Some(start) = rest.find(HREF) {
+		let anchor_start = start + HREF.len() - 1;
+		let Some(len) = rest[anchor_start..].find('"') else {
+			break;
+		};
+		let anchor = &rest[anchor_start..anchor_start + len];
+		result += &rest[..anchor_start];
+		match doc.anchors.get(anchor) {
+			Some(page) if *page != n => result += page_link(path, *page).as_str(),
+			_ => {}
+		}
+		result += anchor;
+		rest = &rest[anchor_start + len..];
+	}
+	result += rest;
+	result
+}
+
+/// Splits a [`CompiledDocument`] into multiple pages
+///
+/// The first page contains the content preceding the first top-level section. Every following page
+/// starts with a top-level section. Every page contains a table of content linking to all pages,
+/// where the current page is marked, and pages are linked together via previous/next links. Links
+/// to anchors located in another page, e.g from a table of content, are changed to link to that page.
+///
+/// When the document doesn't have any split (see
+/// [`crate::compiler::compiler::Compiler::insert_split`]), a single page is returned.
+///
+/// # Parameters
+///
+/// `path` is the output path of the document, it is used to derive the path of the other pages.
+pub fn split_pages(target: Target, doc: &CompiledDocument, path: &str) -> Vec<Page> {
+	if doc.splits.is_empty() {
+		return vec![Page {
+			path: path.to_string(),
+			body: doc.body.clone(),
+		}];
+	}
+
+	let mut pages = vec![];
+	match target {
+		Target::HTML => {
+			const CONTENT_BEGIN: &str = r#"<div class="content">"#;
+			const CONTENT_END: &str = "</div>";
+			let content_end = doc
+				.body
+				.strip_suffix(CONTENT_END)
+				.map_or(doc.body.len(), |body| body.len());
+
+			let nav = |n: usize| -> String {
+				let mut result = String::from(r#"<nav class="split-nav" aria-label="Previous and next pages">"#);
+				if n != 0 {
+					let title = if n == 1 {
+						"Contents"
+					} else {
+						doc.splits[n - 2].title.as_str()
+					};
+					result += format!(
+						r#"<a class="split-nav-previous" href="{}">{}</a>"#,
+						Compiler::sanitize_url(target, page_link(path, n - 1)),
+						Compiler::sanitize(target, title)
+					)
+					.as_str();
+				}
+				if let Some(next) = doc.splits.get(n) {
+					result += format!(
+						r#"<a class="split-nav-next" href="{}">{}</a>"#,
+						Compiler::sanitize_url(target, page_link(path, n + 1)),
+						Compiler::sanitize(target, next.title.as_str())
+					)
+					.as_str();
+				}
+				result += "</nav>";
+				result
+			};
+
+			// Table of content, with the current page marked
+			let toc = |n: usize| -> String {
+				let entry = |page: usize, title: &str| -> String {
+					let current = if page == n {
+						r#" class="split-toc-current" aria-current="page""#
+					} else {
+						""
+					};
+					format!(
+						r#"<li{current}><a href="{}">{}</a></li>"#,
+						Compiler::sanitize_url(target, page_link(path, page)),
+						Compiler::sanitize(target, title)
+					)
+				};
+
+				let mut result = String::from(r#"<nav class="split-toc" aria-label="Pages"><ol>"#);
+				result += entry(0, "Contents").as_str();
+				for (page, split) in doc.splits.iter().enumerate() {
+					result += entry(page + 1, split.title.as_str()).as_str();
+				}
+				result += "</ol></nav>";
+				result
+			};
+
+			// Index page
+			let mut index = doc.body[..doc.splits[0].pos].to_string();
+			index += toc(0).as_str();
+			index += CONTENT_END;
+			index += nav(0).as_str();
+			pages.push(Page {
+				path: page_path(path, 0),
+				body: link_anchors(doc, path, 0, index.as_str()),
+			});
+
+			// Sections
+			for (n, split) in doc.splits.iter().enumerate() {
+				let end = doc.splits.get(n + 1).map_or(content_end, |next| next.pos);
+
+				let mut body = CONTENT_BEGIN.to_string();
+				body += toc(n + 1).as_str();
+				body += &doc.body[split.pos..end];
+				body += CONTENT_END;
+				body += nav(n + 1).as_str();
+				pages.push(Page {
+					path: page_path(path, n + 1),
+					body: link_anchors(doc, path, n + 1, body.as_str()),
+				});
+			}
+		}
+		_ => todo!(""),
+	}
+
+	pages
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::compiler::process::process_from_memory;
+
+	use super::*;
+
+	#[test]
+	fn paths() {
+		assert_eq!(page_path("doc.html", 0), "doc.html");
+		assert_eq!(page_path("doc.html", 2), "doc-2.html");
+		assert_eq!(page_path("out.d/doc", 1), "out.d/doc-1");
+		assert_eq!(page_link("out/doc.html", 3), "doc-3.html");
+	}
+
+	#[test]
+	fn split() {
+		let result = process_from_memory(
+			Target::HTML,
+			vec![r#"
+@html.page_title = Doc
+@compiler.split_sections = true
+Intro
+
+# First
+A
+
+## Nested
+B
+
+# Second
+C
+"#
+			.into()],
+		)
+		.unwrap();
+
+		let doc = result[0].0.borrow();
+		assert_eq!(doc.splits.len(), 2);
+
+		let pages = split_pages(Target::HTML, &doc, "doc.html");
+		assert_eq!(pages.len(), 3);
+		assert_eq!(pages[0].path, "doc.html");
+		assert!(pages[0].body.contains("Intro"));
+		assert!(pages[0]
+			.body
+			.contains(r#"<li><a href="doc-1.html">First</a></li>"#));
+		assert!(pages[0].body.contains(
+			r#"<li class="split-toc-current" aria-current="page"><a href="doc.html">Contents</a></li>"#
+		));
+		assert!(!pages[0].body.contains("First</h1>"));
+
+		assert_eq!(pages[1].path, "doc-1.html");
+		assert!(pages[1].body.contains(
+			r#"<li class="split-toc-current" aria-current="page"><a href="doc-1.html">First</a></li>"#
+		));
+		assert!(pages[1]
+			.body
+			.contains(r#"<li><a href="doc.html">Contents</a></li>"#));
+		assert!(pages[1].body.contains("Nested"));
+		assert!(!pages[1].body.contains("Second</h1>"));
+		assert!(pages[1].body.contains(r#"href="doc.html">Contents</a>"#));
+		assert!(pages[1].body.contains(r#"href="doc-2.html">Second</a>"#));
+
+		assert_eq!(pages[2].path, "doc-2.html");
+		assert!(pages[2].body.contains("Second"));
+		assert!(pages[2].body.contains(r#"href="doc-1.html">First</a>"#));
+		assert!(!pages[2].body.contains("split-nav-next"));
+	}
+
+	#[test]
+	fn anchors() {
+		let result = process_from_memory(
+			Target::HTML,
+			vec![
+				r#"
+@html.page_title = Doc
+@compiler.output = doc.html
+@compiler.split_sections = true
+#+TABLE_OF_CONTENT Contents
+
+# First
+&{#second}
+
+#{second} Second
+C
+"#
+				.into(),
+				r#"
+@html.page_title = Other
+@compiler.output = other.html
+&{doc#second}
+"#
+				.into(),
+			],
+		)
+		.unwrap();
+
+		let doc = result[0].0.borrow();
+		let pages = split_pages(Target::HTML, &doc, "doc.html");
+		assert_eq!(pages.len(), 3);
+		// Table of content
+		assert!(pages[0].body.contains(r#"<a href="doc-1.html#First">First</a>"#));
+		assert!(pages[0]
+			.body
+			.contains(r#"<a href="doc-2.html#Second">Second</a>"#));
+		// Section link in the section's own page
+		assert!(pages[2].body.contains(r##"href="#Second""##));
+		// References
+		assert!(pages[1].body.contains(r#"<a href="doc-2.html#Second">"#));
+		assert!(result[1]
+			.0
+			.borrow()
+			.body
+			.contains(r#"<a href="doc-2.html#Second">"#));
+	}
+}
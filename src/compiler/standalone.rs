@@ -0,0 +1,222 @@
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use lazy_static::lazy_static;
+use regex::Captures;
+use regex::Regex;
+
+/// Gets the media type of a resource from its extension
+fn media_type(path: &Path) -> &'static str {
+	let extension = path
+		.extension()
+		.map(|ext| ext.to_string_lossy().to_lowercase())
+		.unwrap_or_default();
+	match extension.as_str() {
+		"css" => "text/css",
+		"js" => "text/javascript",
+		"png" => "image/png",
+		"jpg" | "jpeg" => "image/jpeg",
+		"gif" => "image/gif",
+		"webp" => "image/webp",
+		"avif" => "image/avif",
+		"svg" => "image/svg+xml",
+		"ico" => "image/x-icon",
+		"woff" => "font/woff",
+		"woff2" => "font/woff2",
+		"ttf" => "font/ttf",
+		"otf" => "font/otf",
+		_ => "application/octet-stream",
+	}
+}
+
+/// Decodes an url from an html attribute, see
+/// [`crate::compiler::compiler::Compiler::sanitize_url`]
+fn decode_url(url: &str) -> String {
+	let url = url
+		.replace("&lt;", "<")
+		.replace("&gt;", ">")
+		.replace("&quot;", "\"")
+		.replace("&#39;", "'")
+		.replace("&amp;", "&");
+
+	let mut bytes = Vec::with_capacity(url.len());
+	let mut pos = 0;
+	while pos < url.len() {
+		let byte = url.as_bytes()[pos];
+		if byte == b'%' {
+			if let Some(decoded) = url
+				.get(pos + 1..pos + 3)
+				.and_then(|hex| u8::from_str_radix(hex, 16).ok())
+			{
+				bytes.push(decoded);
+				pos += 3;
+				continue;
+			}
+		}
+		bytes.push(byte);
+		pos += 1;
+	}
+	String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// Resolves the path of a local resource, `None` for remote resources
+fn local_path(base: &Path, url: &str) -> Option<std::path::PathBuf> {
+	if url.is_empty() || url.starts_with('#') || url.starts_with("data:") || url.contains("://") {
+		return None;
+	}
+	// Fragments and queries are not part of the path
+	let url = decode_url(url);
+	let path = url.split(['#', '?']).next().unwrap_or_default();
+	Some(base.join(path))
+}
+
+/// Inlines a single local resource as a data uri
+fn data_uri(path: &Path, warnings: &mut Vec<String>) -> Option<String> {
+	let data = match std::fs::read(path) {
+		Ok(data) => data,
+		Err(err) => {
+			warnings.push(format!("Unable to inline `{}`: {err}", path.display()));
+			return None;
+		}
+	};
+	let data = match media_type(path) {
+		"text/css" => inline_stylesheet(
+			String::from_utf8_lossy(&data).as_ref(),
+			path.parent().unwrap_or(Path::new("")),
+			warnings,
+		)
+		.into_bytes(),
+		_ => data,
+	};
+	Some(format!(
+		"data:{};base64,{}",
+		media_type(path),
+		STANDARD.encode(data)
+	))
+}
+
+/// Inlines the resources (fonts, images, ...) referenced by `url(...)` in a stylesheet
+///
+/// `base` is the directory containing the stylesheet.
+fn inline_stylesheet(css: &str, base: &Path, warnings: &mut Vec<String>) -> String {
+	lazy_static! {
+		static ref url: Regex =
+			Regex::new(r#"url\(\s*(?:"([^"]*)"|'([^']*)'|([^)'"\s]*))\s*\)"#).unwrap();
+	}
+
+	url.replace_all(css, |caps: &Captures| {
+		let target = caps
+			.get(1)
+			.or(caps.get(2))
+			.or(caps.get(3))
+			.map_or("", |m| m.as_str());
+		local_path(base, target)
+			.and_then(|path| data_uri(&path, warnings))
+			.map_or(caps[0].to_string(), |uri| format!("url(\"{uri}\")"))
+	})
+	.to_string()
+}
+
+/// Makes a page self-contained, so that it can be shared as a single file
+///
+/// Local stylesheets are inlined in the page. Scripts, images and the resources referenced by
+/// stylesheets, such as fonts, are inlined as data uris. Resources are resolved from `base`, the
+/// directory containing the page, remote resources are kept as links.
+///
+/// # Return
+///
+/// Returns the self-contained page, and warnings for the resources that could not be inlined.
+pub fn inline_resources(html: &str, base: &Path) -> (String, Vec<String>) {
+	lazy_static! {
+		static ref stylesheet: Regex =
+			Regex::new(r#"<link rel="stylesheet" href="([^"]*)">"#).unwrap();
+		static ref source: Regex =
+			Regex::new(r#"(<(?:img|source|video|audio|script)\b[^>]*?\bsrc=")([^"]*)""#).unwrap();
+	}
+
+	let mut warnings = vec![];
+	let html = stylesheet.replace_all(html, |caps: &Captures| {
+		let Some(path) = local_path(base, &caps[1]) else {
+			return caps[0].to_string();
+		};
+		match std::fs::read_to_string(&path) {
+			Ok(css) => {
+				let css = inline_stylesheet(
+					css.as_str(),
+					path.parent().unwrap_or(Path::new("")),
+					&mut warnings,
+				);
+				format!("<style>{}</style>", css.replace("</style", "<\\/style"))
+			}
+			Err(err) => {
+				warnings.push(format!("Unable to inline `{}`: {err}", path.display()));
+				caps[0].to_string()
+			}
+		}
+	});
+	let html = source
+		.replace_all(&html, |caps: &Captures| {
+			local_path(base, &caps[2])
+				.and_then(|path| data_uri(&path, &mut warnings))
+				.map_or(caps[0].to_string(), |uri| format!("{}{uri}\"", &caps[1]))
+		})
+		.to_string();
+
+	(html, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tempdir::TempDir;
+
+	#[test]
+	fn urls() {
+		assert_eq!(decode_url("a%20b/c&amp;d.png"), "a b/c&d.png");
+		assert_eq!(decode_url("100%"), "100%");
+		assert_eq!(local_path(Path::new("out"), "https://a.org/b.png"), None);
+		assert_eq!(local_path(Path::new("out"), "data:image/png;base64,"), None);
+		assert_eq!(
+			local_path(Path::new("out"), "img/a%20b.png?v=1#top"),
+			Some(Path::new("out/img/a b.png").to_path_buf())
+		);
+	}
+
+	#[test]
+	fn inline() {
+		let dir = TempDir::new("standalone");
+		std::fs::create_dir_all(dir.join("assets/fonts")).unwrap();
+		std::fs::write(
+			dir.join("assets/style.css"),
+			"@font-face { src: url('fonts/a.woff2'); }",
+		)
+		.unwrap();
+		std::fs::write(dir.join("assets/fonts/a.woff2"), "font").unwrap();
+		std::fs::write(dir.join("script.js"), "let a;").unwrap();
+		std::fs::write(dir.join("image.svg"), "<svg></svg>").unwrap();
+
+		let (html, warnings) = inline_resources(
+			concat!(
+				r#"<link rel="stylesheet" href="assets/style.css">"#,
+				r#"<link rel="stylesheet" href="https://a.org/b.css">"#,
+				r#"<script src="script.js" defer></script>"#,
+				r#"<a href="image.svg"><img src="image.svg" alt="Image"></a>"#,
+				r#"<img src="missing.png" alt="">"#,
+			),
+			&dir,
+		);
+
+		assert_eq!(
+			html,
+			concat!(
+				r#"<style>@font-face { src: url("data:font/woff2;base64,Zm9udA=="); }</style>"#,
+				r#"<link rel="stylesheet" href="https://a.org/b.css">"#,
+				r#"<script src="data:text/javascript;base64,bGV0IGE7" defer></script>"#,
+				r#"<a href="image.svg"><img src="data:image/svg+xml;base64,PHN2Zz48L3N2Zz4=" alt="Image"></a>"#,
+				r#"<img src="missing.png" alt="">"#,
+			)
+		);
+		assert_eq!(warnings.len(), 1);
+	}
+}
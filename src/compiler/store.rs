@@ -0,0 +1,168 @@
+use std::path::Path;
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha512;
+use rusqlite::Connection;
+
+use crate::document::document::Document;
+
+/// Name of the variable enabling the asset store
+///
+/// When `true`, generated images (LaTeX, Graphviz, thumbnails) are written to the assets directory
+/// and linked from the pages, instead of being inlined or written next to their source.
+pub const STORE_VARIABLE: &str = "html.assets.store";
+
+/// Checks whether the asset store is enabled for `document`, see [`STORE_VARIABLE`]
+pub fn store_enabled(document: &dyn Document) -> Result<bool, String> {
+	match document.get_variable(STORE_VARIABLE) {
+		Some(var) => var.to_string().trim().parse::<bool>().map_err(|_| {
+			format!(
+				"Invalid value for `{STORE_VARIABLE}`: expected `true` or `false`, got `{}`",
+				var.to_string()
+			)
+		}),
+		None => Ok(false),
+	}
+}
+
+/// Gets the name of an asset in the store
+///
+/// Assets are named after the hash of their content, so identical assets are stored once.
+pub fn asset_name(content: &[u8], extension: &str) -> String {
+	let mut hasher = Sha512::new();
+	hasher.input(content);
+	format!("{}.{extension}", &hasher.result_str()[..32])
+}
+
+/// Creates the table of the store
+///
+/// Stored assets are kept in the cache along with the number of compiled documents using them.
+pub fn init(con: &Connection) -> Result<(), rusqlite::Error> {
+	con.execute(
+		"CREATE TABLE IF NOT EXISTS stored_assets (
+			name     TEXT PRIMARY KEY,
+			content  BLOB NOT NULL,
+			refcount INTEGER NOT NULL DEFAULT 0
+		);",
+		(),
+	)
+	.map(|_| ())
+}
+
+/// Adds an asset to the store, and returns its name
+///
+/// The asset isn't referenced until a compiled document using it is cached, see
+/// [`update_references`].
+pub fn insert(
+	con: &Connection,
+	content: &[u8],
+	extension: &str,
+) -> Result<String, rusqlite::Error> {
+	init(con)?;
+	let name = asset_name(content, extension);
+	con.prepare_cached("INSERT OR IGNORE INTO stored_assets (name, content) VALUES (?1, ?2)")?
+		.execute((&name, content))?;
+	Ok(name)
+}
+
+/// Updates the reference counts of the assets used by a document
+///
+/// `previous` are the assets used by the cached version of the document, `current` the assets
+/// used by its new version.
+pub fn update_references(
+	con: &Connection,
+	previous: &[String],
+	current: &[String],
+) -> Result<(), rusqlite::Error> {
+	if previous == current {
+		return Ok(());
+	}
+	init(con)?;
+	let mut query = con.prepare_cached(
+		"UPDATE stored_assets SET refcount = MAX(refcount + ?2, 0) WHERE name = ?1",
+	)?;
+	for name in previous.iter().filter(|name| !current.contains(name)) {
+		query.execute((name, -1))?;
+	}
+	for name in current.iter().filter(|name| !previous.contains(name)) {
+		query.execute((name, 1))?;
+	}
+	Ok(())
+}
+
+/// Writes the referenced assets of the store to `directory`, and removes the unreferenced ones
+///
+/// Assets are only written when missing from `directory`, their names depend on their content.
+///
+/// # Errors
+///
+/// Returns an error when the cache can't be queried, or when an asset can't be written or
+/// removed.
+pub fn write_store(con: &Connection, directory: &Path) -> Result<(), String> {
+	let sql_err = |err: rusqlite::Error| format!("Querying the asset store failed: {err}");
+	init(con).map_err(sql_err)?;
+
+	let mut query = con
+		.prepare("SELECT name, content, refcount FROM stored_assets")
+		.map_err(sql_err)?;
+	let assets = query
+		.query_map([], |row| {
+			Ok((
+				row.get::<_, String>(0)?,
+				row.get::<_, Vec<u8>>(1)?,
+				row.get::<_, i64>(2)?,
+			))
+		})
+		.and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+		.map_err(sql_err)?;
+
+	for (name, content, refcount) in assets {
+		let path = directory.join(&name);
+		if refcount > 0 {
+			if path.exists() {
+				continue;
+			}
+			std::fs::create_dir_all(directory)
+				.and_then(|_| std::fs::write(&path, content))
+				.map_err(|err| format!("Unable to write asset `{}`: {err}", path.display()))?;
+		} else {
+			if path.exists() {
+				std::fs::remove_file(&path)
+					.map_err(|err| format!("Unable to remove asset `{}`: {err}", path.display()))?;
+			}
+			con.execute("DELETE FROM stored_assets WHERE name = ?1", [&name])
+				.map_err(sql_err)?;
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tempdir::TempDir;
+
+	#[test]
+	fn references() {
+		let con = Connection::open_in_memory().unwrap();
+		let dir = TempDir::new("store");
+
+		let a = insert(&con, b"<svg>a</svg>", "svg").unwrap();
+		let b = insert(&con, b"<svg>b</svg>", "svg").unwrap();
+		assert_eq!(a, asset_name(b"<svg>a</svg>", "svg"));
+		assert_eq!(insert(&con, b"<svg>a</svg>", "svg").unwrap(), a);
+
+		// Two documents using `a`, one using `b`
+		update_references(&con, &[], std::slice::from_ref(&a)).unwrap();
+		update_references(&con, &[], &[a.clone(), b.clone()]).unwrap();
+		write_store(&con, &dir).unwrap();
+		assert_eq!(std::fs::read(dir.join(&a)).unwrap(), b"<svg>a</svg>");
+		assert!(dir.join(&b).exists());
+
+		// The second document no longer uses `a` and `b`
+		update_references(&con, &[a.clone(), b.clone()], &[]).unwrap();
+		write_store(&con, &dir).unwrap();
+		assert!(dir.join(&a).exists());
+		assert!(!dir.join(&b).exists());
+	}
+}
@@ -0,0 +1,84 @@
+use lazy_static::lazy_static;
+use regex::Captures;
+use regex::Regex;
+
+/// Replaces black in an svg with `color`
+///
+/// `color` can be any CSS color, e.g `currentColor` to follow the text color of the page, or a CSS
+/// variable such as `var(--fg)`. Elements without a fill inherit it from the root element, whose
+/// fill is set to `color` unless it already has one.
+pub fn recolor(svg: &str, color: &str) -> String {
+	lazy_static! {
+		// Black fills and strokes, as attributes or style properties
+		static ref black: Regex = Regex::new(
+			r#"(?i)(fill|stroke)(\s*=\s*['"]|\s*:\s*)(?:(?:black|#000000|#000)\b|rgb\(\s*0%?\s*,\s*0%?\s*,\s*0%?\s*\))"#
+		)
+		.unwrap();
+	}
+
+	let color = color.replace(['"', '\'', '<', '>'], "");
+	let mut result = black
+		.replace_all(svg, |caps: &Captures| {
+			format!("{}{}{color}", &caps[1], &caps[2])
+		})
+		.to_string();
+
+	if let Some(start) = result.find("<svg").map(|start| start + 4) {
+		let tag = &result[start..start + result[start..].find('>').unwrap_or(0)];
+		if !tag.contains("fill=") {
+			result.insert_str(start, format!(" fill=\"{color}\"").as_str());
+		}
+	}
+	result
+}
+
+/// Gets the `width`, `height` and `style` attributes of the root element of an svg
+///
+/// Images linking to a stored svg are given these attributes, so they are laid out like the
+/// inlined svg.
+pub fn size_attributes(svg: &str) -> String {
+	lazy_static! {
+		static ref attribute: Regex =
+			Regex::new(r#"\s(width|height|style)\s*=\s*("[^"]*"|'[^']*')"#).unwrap();
+	}
+
+	let Some(start) = svg.find("<svg") else {
+		return String::new();
+	};
+	let tag = &svg[start..start + svg[start..].find('>').unwrap_or(0)];
+	attribute
+		.captures_iter(tag)
+		.map(|caps| format!(" {}={}", &caps[1], &caps[2]))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn recolor_black() {
+		assert_eq!(
+			recolor(
+				r##"<svg width="1"><path fill="black" stroke='#000'/><text style="fill:rgb(0%, 0%, 0%);stroke: #000000">a</text><path fill="#0000ff"/></svg>"##,
+				"currentColor"
+			),
+			r##"<svg fill="currentColor" width="1"><path fill="currentColor" stroke='currentColor'/><text style="fill:currentColor;stroke: currentColor">a</text><path fill="#0000ff"/></svg>"##
+		);
+		assert_eq!(
+			recolor(r##"<svg fill="red"><path/></svg>"##, "var(--fg)"),
+			r##"<svg fill="red"><path/></svg>"##
+		);
+	}
+
+	#[test]
+	fn size() {
+		assert_eq!(
+			size_attributes(
+				r#"<?xml?><svg xmlns="x" width="2em" height='1em' style="vertical-align: -0.2em"><rect width="1"/></svg>"#
+			),
+			r#" width="2em" height='1em' style="vertical-align: -0.2em""#
+		);
+		assert_eq!(size_attributes("<svg>"), "");
+	}
+}
@@ -0,0 +1,122 @@
+use crate::document::document::Document;
+
+/// Name of the variable holding the url the project is published at
+pub const BASE_URL_VARIABLE: &str = "project.base_url";
+
+/// Name of the variable holding the path the project is published under, e.g `/project` for a
+/// site hosted at `https://user.github.io/project`
+pub const PATH_PREFIX_VARIABLE: &str = "project.path_prefix";
+
+/// Gets the path of a base url, to be used as the path prefix of the project
+///
+/// Returns `None` if the url has no path.
+pub fn url_path(base_url: &str) -> Option<String> {
+	let path = base_url
+		.split_once("://")
+		.map_or(base_url, |(_, rest)| {
+			rest.find('/').map_or("", |pos| &rest[pos..])
+		})
+		.trim_end_matches('/');
+	(!path.is_empty()).then(|| path.to_string())
+}
+
+/// Gets the scheme and host of a base url
+fn url_origin(base_url: &str) -> &str {
+	match base_url.split_once("://") {
+		Some((scheme, rest)) => {
+			&base_url[..scheme.len() + 3 + rest.find('/').unwrap_or(rest.len())]
+		}
+		None => base_url,
+	}
+}
+
+/// Adds the path prefix of the project to root-relative urls, e.g `/images/a.png`
+///
+/// Other urls are returned as is.
+pub fn prefixed(document: &dyn Document, url: &str) -> String {
+	if !url.starts_with('/') || url.starts_with("//") {
+		return url.to_string();
+	}
+	match document.get_variable(PATH_PREFIX_VARIABLE) {
+		Some(prefix) => format!("{}{url}", prefix.to_string().trim_end_matches('/')),
+		None => url.to_string(),
+	}
+}
+
+/// Makes `url` absolute using the base url of the project
+///
+/// `directory` is the directory relative urls are relative to, with a trailing `/`. Returns `url`
+/// as is if the base url is not set.
+pub fn absolute_url(document: &dyn Document, directory: &str, url: &str) -> String {
+	let Some(base_url) = document.get_variable(BASE_URL_VARIABLE) else {
+		return url.to_string();
+	};
+	let base_url = base_url.to_string();
+	if url.contains("://") {
+		url.to_string()
+	} else if url.starts_with('/') {
+		format!("{}{}", url_origin(&base_url), prefixed(document, url))
+	} else {
+		format!("{}/{directory}{url}", base_url.trim_end_matches('/'))
+	}
+}
+
+/// Gets the canonical url of a document's page, from the base url and `compiler.output`
+pub fn canonical_url(document: &dyn Document) -> Option<String> {
+	let output = document.get_variable("compiler.output")?.to_string();
+	document
+		.get_variable(BASE_URL_VARIABLE)
+		.is_some()
+		.then(|| absolute_url(document, "", output.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::compiler::compiler::Target;
+	use crate::compiler::process::process_from_memory;
+
+	use super::*;
+
+	#[test]
+	fn paths() {
+		assert_eq!(
+			url_path("https://user.github.io/project/"),
+			Some("/project".to_string())
+		);
+		assert_eq!(url_path("https://example.com"), None);
+		assert_eq!(
+			url_origin("https://user.github.io/project"),
+			"https://user.github.io"
+		);
+	}
+
+	#[test]
+	fn urls() {
+		let result = process_from_memory(
+			Target::HTML,
+			vec![
+				r#"
+@project.base_url = https://user.github.io/project
+@project.path_prefix = /project
+@compiler.output = posts/post.html
+[Home](/index.html) [Post](other.html) [Site](https://example.com)
+"#
+				.into(),
+				"@compiler.output = index.html\n[Home](/index.html)\n".into(),
+			],
+		)
+		.unwrap();
+
+		let doc = result[0].0.borrow();
+		assert!(doc.header.contains(
+			r#"<link rel="canonical" href="https://user.github.io/project/posts/post.html">"#
+		));
+		assert!(doc.body.contains(r#"<a href="/project/index.html">"#));
+		assert!(doc.body.contains(r#"<a href="other.html">"#));
+		assert!(doc.body.contains(r#"<a href="https://example.com">"#));
+
+		let doc = result[1].0.borrow();
+		assert!(!doc.header.contains("canonical"));
+		assert!(doc.body.contains(r#"<a href="/index.html">"#));
+	}
+}
@@ -2,6 +2,7 @@ use std::cell::Ref;
 use std::cell::RefCell;
 use std::cell::RefMut;
 use std::collections::hash_map::HashMap;
+use std::collections::HashSet;
 use std::rc::Rc;
 
 use crate::parser::source::Source;
@@ -18,6 +19,11 @@ pub struct Scope {
 	/// when [`Element::as_referenceable`] is called
 	pub referenceable: HashMap<String, ElemReference>,
 	pub variables: HashMap<String, Rc<dyn Variable>>,
+	/// Names of the variables exported by this scope.
+	/// Only exported variables are merged into the importing scope
+	pub exports: HashSet<String>,
+	/// Canonical paths of the files imported in this scope, including nested imports
+	pub imports: HashSet<String>,
 }
 
 impl Scope {
@@ -25,10 +31,25 @@ impl Scope {
 		Self {
 			referenceable: HashMap::new(),
 			variables: HashMap::new(),
+			exports: HashSet::new(),
+			imports: HashSet::new(),
 		}
 	}
 
+	/// Gets whether variable `name` is exported by this scope
+	pub fn is_exported(&self, name: &str) -> bool { self.exports.contains(name) }
+
+	/// Drains the exported variables out of this scope
+	fn drain_exports(&mut self) -> Vec<(String, Rc<dyn Variable>)> {
+		let exports = std::mem::take(&mut self.exports);
+		self.variables
+			.drain()
+			.filter(|(name, _)| exports.contains(name))
+			.collect()
+	}
+
 	pub fn merge(&mut self, other: &mut Scope, merge_as: &String, ref_offset: usize) {
+		self.imports.extend(other.imports.drain());
 		match merge_as.is_empty() {
 			true => {
 				// References
@@ -44,7 +65,7 @@ impl Scope {
 				));
 
 				// Variables
-				self.variables.extend(other.variables.drain());
+				self.variables.extend(other.drain_exports());
 			}
 			false => {
 				// References
@@ -64,8 +85,8 @@ impl Scope {
 				// Variables
 				self.variables.extend(
 					other
-						.variables
-						.drain()
+						.drain_exports()
+						.into_iter()
 						.map(|(name, var)| (format!("{merge_as}.{name}"), var)),
 				);
 			}
@@ -127,6 +148,9 @@ pub trait Document<'a>: core::fmt::Debug {
 		self.content().borrow_mut().push(elem);
 	}
 
+	/// Adds a variable to the document's scope
+	///
+	/// The variable is local to the document, unless it has previously been exported
 	fn add_variable(&self, variable: Rc<dyn Variable>) {
 		self.scope()
 			.borrow_mut()
@@ -134,6 +158,26 @@ pub trait Document<'a>: core::fmt::Debug {
 			.insert(variable.name().to_string(), variable);
 	}
 
+	/// Adds a variable to the document's scope and marks it as exported
+	///
+	/// Exported variables are merged into the importing document, see [`Document::merge`]
+	fn export_variable(&self, variable: Rc<dyn Variable>) {
+		let mut scope = self.scope().borrow_mut();
+		scope.exports.insert(variable.name().to_string());
+		scope
+			.variables
+			.insert(variable.name().to_string(), variable);
+	}
+
+	/// Gets the variable `name` defined in a parent document that would be shadowed
+	/// by defining `name` in this document
+	fn get_shadowed_variable(&self, name: &str) -> Option<Rc<dyn Variable>> {
+		if self.scope().borrow().variables.contains_key(name) {
+			return None;
+		}
+		self.parent().and_then(|parent| parent.get_variable(name))
+	}
+
 	fn get_variable(&self, name: &str) -> Option<Rc<dyn Variable>> {
 		match self.scope().borrow().variables.get(name) {
 			Some(variable) => Some(variable.clone()),
@@ -152,7 +196,8 @@ pub trait Document<'a>: core::fmt::Debug {
 	///
 	/// # Parameters
 	///
-	/// If `merge_as` is None, references and variables from the other document are not merged into self
+	/// If `merge_as` is None, references and variables from the other document are not merged into self.
+	/// Only the variables exported by the other document are merged, the others remain local to it
 	fn merge(
 		&self,
 		content: &RefCell<Vec<Box<dyn Element>>>,
@@ -73,12 +73,16 @@ pub trait ReferenceableElement: Element {
 	fn refcount_key(&self) -> &'static str;
 
 	/// Creates the reference element
+	///
+	/// `cursor` is the position of the reference in the compiled document, it should be used to
+	/// compile the reference's caption, see [`InternalReference::compile_caption`]
 	fn compile_reference(
 		&self,
 		compiler: &Compiler,
 		document: &dyn Document,
 		reference: &InternalReference,
 		refid: usize,
+		cursor: usize,
 	) -> Result<String, String>;
 
 	/// Gets the refid for a compiler. The refid is some key that can be used from an external
@@ -2,4 +2,5 @@ pub mod document;
 pub mod element;
 pub mod langdocument;
 pub mod references;
+pub mod tree;
 pub mod variable;
@@ -8,8 +8,9 @@ use super::document::Document;
 /// # Notes
 ///
 /// A valid reference name must not be empty and cannot contain the following:
-///  - Ascii punctuation outside of `.` and `_`. This is imposed in order to avoid confusion when
-///  passing a reference as a property, as properties are often delimited by `[]` or `:`
+///  - Ascii punctuation outside of `.`, `_` and `:` (e.g `eq:energy`). This is imposed in order to
+///  avoid confusion when passing a reference as a property, as properties are often delimited by
+///  `[]`
 ///  - white spaces, e.g spaces, tabs or `\n`
 ///  - no special ascii characters (no control sequences)
 pub fn validate_refname<'a>(
@@ -23,7 +24,7 @@ pub fn validate_refname<'a>(
 	}
 
 	for c in trimmed.chars() {
-		if c.is_ascii_punctuation() && !(c == '.' || c == '_') {
+		if c.is_ascii_punctuation() && !(c == '.' || c == '_' || c == ':') {
 			return Err(format!(
 				"Refname `{trimmed}` cannot contain punctuation codepoint: `{c}`"
 			));
@@ -84,6 +85,7 @@ pub mod tests {
 		assert!(validate_refname(&*doc, "\n", true).is_err());
 		assert!(validate_refname(&*doc, "'", true).is_err());
 		assert!(validate_refname(&*doc, "]", true).is_err());
+		assert_eq!(validate_refname(&*doc, "eq:energy", true), Ok("eq:energy"));
 
 		// Duplicate
 		assert!(validate_refname(&*doc, "ref", true).is_err());
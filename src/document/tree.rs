@@ -0,0 +1,184 @@
+use std::rc::Rc;
+
+use ariadne::Fmt;
+
+use crate::elements::text::elem::Text;
+use crate::parser::parser::ReportColors;
+use crate::parser::source::Source;
+
+use super::element::Element;
+
+/// Maximum number of characters of the text displayed for [`Text`] elements
+const TEXT_PREVIEW: usize = 32;
+
+/// Checks whether `elem` is selected by `filter`
+///
+/// Elements are selected by their name, or by the first word of their name (e.g `Code` selects
+/// `Code Block`). Every element is selected by an empty filter.
+fn selected(elem: &dyn Element, filter: &[String]) -> bool {
+	let name = elem.element_name();
+	filter.is_empty()
+		|| filter.iter().any(|selected| {
+			name == selected
+				|| name
+					.strip_prefix(selected.as_str())
+					.is_some_and(|rest| rest.starts_with(' '))
+		})
+}
+
+/// Checks whether `elem` or one of the elements it contains is selected by `filter`
+fn contains_selected(elem: &dyn Element, filter: &[String]) -> bool {
+	selected(elem, filter)
+		|| elem.as_container().is_some_and(|container| {
+			container
+				.contained()
+				.iter()
+				.any(|elem| contains_selected(elem.as_ref(), filter))
+		})
+}
+
+/// Formats an element of the tree
+///
+/// The source of the element is displayed when it differs from the source of its parent.
+fn describe(
+	colors: &ReportColors,
+	elem: &dyn Element,
+	parent: Option<&Rc<dyn Source>>,
+	selected: bool,
+) -> String {
+	let location = elem.location();
+	let mut description = match selected {
+		true => format!("{}", elem.element_name().fg(colors.highlight)),
+		false => elem.element_name().to_string(),
+	};
+	description += format!(
+		" {} [{}..{}]",
+		format!("{:?}", elem.kind()).fg(colors.info),
+		location.start(),
+		location.end()
+	)
+	.as_str();
+	if parent.is_none_or(|parent| !Rc::ptr_eq(parent, &location.source())) {
+		description += format!(" in {}", location.source().name()).as_str();
+	}
+	if let Some(text) = elem.downcast_ref::<Text>() {
+		let mut preview = text.content.chars().take(TEXT_PREVIEW).collect::<String>();
+		if preview.len() < text.content.len() {
+			preview += "...";
+		}
+		description += format!(" {preview:?}").as_str();
+	}
+	description
+}
+
+/// Formats `elems` and the elements they contain, with `prefix` before each line
+fn render(
+	colors: &ReportColors,
+	elems: &[Box<dyn Element>],
+	parent: Option<&Rc<dyn Source>>,
+	filter: &[String],
+	prefix: &str,
+	result: &mut String,
+) {
+	let elems = elems
+		.iter()
+		.filter(|elem| contains_selected(elem.as_ref(), filter))
+		.collect::<Vec<_>>();
+	for (id, elem) in elems.iter().enumerate() {
+		let last = id + 1 == elems.len();
+		*result += format!(
+			"{prefix}{} {}\n",
+			if last { "└─" } else { "├─" },
+			describe(
+				colors,
+				elem.as_ref(),
+				parent,
+				selected(elem.as_ref(), filter)
+			)
+		)
+		.as_str();
+		if let Some(container) = elem.as_container() {
+			render(
+				colors,
+				container.contained(),
+				Some(&elem.location().source()),
+				filter,
+				format!("{prefix}{}", if last { "   " } else { "│  " }).as_str(),
+				result,
+			);
+		}
+	}
+}
+
+/// Formats the tree of `elems`, with the kind and the range of each element
+///
+/// When `filter` is not empty, only the elements it selects are displayed, along with the
+/// containers they are nested in. This is used by the `-z ast` debug option, e.g
+/// `-z ast=Section,Code`.
+pub fn format_tree(
+	colors: &ReportColors,
+	elems: &[Box<dyn Element>],
+	source: &Rc<dyn Source>,
+	filter: &[String],
+) -> String {
+	let mut result = format!("{}\n", source.name());
+	render(colors, elems, Some(source), filter, "", &mut result);
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::parser::langparser::LangParser;
+	use crate::parser::parser::ParseMode;
+	use crate::parser::parser::Parser;
+	use crate::parser::parser::ParserState;
+	use crate::parser::source::SourceFile;
+
+	use super::*;
+
+	#[test]
+	fn tree() {
+		let source: Rc<dyn Source> = Rc::new(SourceFile::with_content(
+			"input.nml".to_string(),
+			"# Title\nSome *text* and a [link](url)\n``C\nint a;``\n".to_string(),
+			None,
+		));
+		let parser = LangParser::default();
+		let (doc, _) = parser.parse(
+			ParserState::new(&parser, None),
+			source.clone(),
+			None,
+			ParseMode::default(),
+		);
+
+		let colors = ReportColors::without_colors();
+		let content = doc.content().borrow();
+		assert_eq!(
+			format_tree(&colors, &content, &source, &[]),
+			"input.nml\n\
+			├─ Section Block [0..7]\n\
+			└─ Paragraph Special [7..51]\n   \
+			   ├─ Text Inline [7..13] \"Some \"\n   \
+			   ├─ Style Inline [13..14]\n   \
+			   ├─ Text Inline [14..18] \"text\"\n   \
+			   ├─ Style Inline [18..19]\n   \
+			   ├─ Text Inline [19..26] \" and a \"\n   \
+			   ├─ Link Inline [26..37]\n   \
+			   │  └─ Text Inline [0..4] in Link Display \"link\"\n   \
+			   ├─ Code Block Inline [38..50]\n   \
+			   └─ Document End Invisible [51..51]\n"
+		);
+		assert_eq!(
+			format_tree(
+				&colors,
+				&content,
+				&source,
+				&["Code".to_string(), "Link".to_string()]
+			),
+			"input.nml\n\
+			└─ Paragraph Special [7..51]\n   \
+			   ├─ Link Inline [26..37]\n   \
+			   └─ Code Block Inline [38..50]\n"
+		);
+	}
+}
@@ -5,8 +5,109 @@ use crate::parser::parser::ParserState;
 use crate::parser::source::Source;
 use crate::parser::source::Token;
 use crate::parser::source::VirtualSource;
+use mlua::HookTriggers;
+use mlua::Lua;
+use mlua::LuaOptions;
+use mlua::StdLib;
+use std::cell::Cell;
+use std::cell::OnceCell;
+use std::fmt::Display;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::str::FromStr;
+
+/// Name of the source holding the variables defined from outside the documents,
+/// e.g using `--define` on the command line
+pub const DEFINES_SOURCE: &str = ":DEFINES:";
+
+/// Type of a variable's value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VariableType {
+	/// Any text
+	String,
+	/// Signed integer
+	Int,
+	/// `true` or `false`
+	Bool,
+	/// Path to an accessible file or directory
+	Path,
+	/// One of the listed values
+	Enum(Vec<String>),
+}
+
+impl VariableType {
+	/// Checks that `value` is a valid value for this type
+	pub fn validate(&self, value: &str) -> Result<(), String> {
+		match self {
+			VariableType::String => Ok(()),
+			VariableType::Int => value
+				.parse::<i64>()
+				.map(|_| ())
+				.map_err(|err| format!("Expected an integer, got `{value}`: {err}")),
+			VariableType::Bool => match value {
+				"true" | "false" => Ok(()),
+				_ => Err(format!("Expected `true` or `false`, got `{value}`")),
+			},
+			VariableType::Path => std::fs::metadata(value)
+				.map(|_| ())
+				.map_err(|err| format!("Unable to access path `{value}`: {err}")),
+			VariableType::Enum(values) => {
+				if values.iter().any(|v| v == value) {
+					Ok(())
+				} else {
+					Err(format!(
+						"Expected one of {}, got `{value}`",
+						values
+							.iter()
+							.map(|v| format!("`{v}`"))
+							.collect::<Vec<_>>()
+							.join(", ")
+					))
+				}
+			}
+		}
+	}
+}
+
+impl FromStr for VariableType {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.trim() {
+			"string" => Ok(VariableType::String),
+			"int" => Ok(VariableType::Int),
+			"bool" => Ok(VariableType::Bool),
+			"path" => Ok(VariableType::Path),
+			ty => {
+				let values = ty
+					.strip_prefix("enum(")
+					.and_then(|ty| ty.strip_suffix(")"))
+					.ok_or(format!("Unknown variable type: `{ty}`"))?;
+				let values = values
+					.split(',')
+					.map(|v| v.trim().to_string())
+					.filter(|v| !v.is_empty())
+					.collect::<Vec<_>>();
+				if values.is_empty() {
+					return Err("Enum type requires at least one value".into());
+				}
+				Ok(VariableType::Enum(values))
+			}
+		}
+	}
+}
+
+impl Display for VariableType {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			VariableType::String => write!(f, "string"),
+			VariableType::Int => write!(f, "int"),
+			VariableType::Bool => write!(f, "bool"),
+			VariableType::Path => write!(f, "path"),
+			VariableType::Enum(values) => write!(f, "enum({})", values.join(", ")),
+		}
+	}
+}
 
 /// Trait for document variables
 pub trait Variable {
@@ -22,6 +123,21 @@ pub trait Variable {
 	/// The token when the variable value was defined from
 	fn value_token(&self) -> &Token;
 
+	/// Gets the declared type of the variable, if any
+	///
+	/// Later assignments to this variable must be valid values for this type
+	fn var_type(&self) -> Option<VariableType> { None }
+
+	/// Gets whether the variable was defined from outside the documents
+	///
+	/// External variables take precedence over the definitions made inside documents
+	fn is_external(&self) -> bool { self.location().source().name() == DEFINES_SOURCE }
+
+	/// Evaluates the value of the variable, for variables whose value is computed
+	///
+	/// This should be called before the variable is used, in order to report evaluation errors
+	fn evaluate(&self) -> Result<(), String> { Ok(()) }
+
 	/// Expands the variable when it is requested
 	fn parse<'a>(&self, state: &ParserState, location: Token, document: &'a dyn Document<'a>);
 }
@@ -39,6 +155,7 @@ pub struct BaseVariable {
 	name: String,
 	value_token: Token,
 	value: String,
+	var_type: Option<VariableType>,
 }
 
 impl BaseVariable {
@@ -48,8 +165,15 @@ impl BaseVariable {
 			name,
 			value_token,
 			value,
+			var_type: None,
 		}
 	}
+
+	/// Sets the declared type of the variable
+	pub fn with_type(mut self, var_type: Option<VariableType>) -> Self {
+		self.var_type = var_type;
+		self
+	}
 }
 
 impl Variable for BaseVariable {
@@ -61,6 +185,8 @@ impl Variable for BaseVariable {
 
 	fn value_token(&self) -> &Token { &self.value_token }
 
+	fn var_type(&self) -> Option<VariableType> { self.var_type.clone() }
+
 	fn parse<'a>(&self, state: &ParserState, _location: Token, document: &'a dyn Document<'a>) {
 		let source = Rc::new(VirtualSource::new(
 			self.location().clone(),
@@ -105,6 +231,8 @@ impl Variable for PathVariable {
 
 	fn value_token(&self) -> &Token { &self.value_token }
 
+	fn var_type(&self) -> Option<VariableType> { Some(VariableType::Path) }
+
 	fn parse(&self, state: &ParserState, location: Token, document: &dyn Document) {
 		let source = Rc::new(VirtualSource::new(
 			location,
@@ -121,3 +249,137 @@ impl Variable for PathVariable {
 		);
 	}
 }
+
+/// A variable whose value is computed from a Lua expression
+///
+/// The expression is evaluated in a sandboxed Lua environment when the variable is first used, the
+/// result is kept for later uses. Like [`BaseVariable`], the result is parsed when the variable is
+/// invoked.
+#[derive(Debug)]
+pub struct ComputedVariable {
+	location: Token,
+	name: String,
+	value_token: Token,
+	expression: String,
+	value: OnceCell<Result<String, String>>,
+}
+
+impl ComputedVariable {
+	pub fn new(location: Token, name: String, value_token: Token, expression: String) -> Self {
+		Self {
+			location,
+			name,
+			value_token,
+			expression,
+			value: OnceCell::new(),
+		}
+	}
+
+	/// Maximum number of Lua instructions an expression may run
+	const INSTRUCTION_LIMIT: u32 = 10_000_000;
+	/// Maximum memory used by the Lua environment, in bytes
+	const MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+	/// Evaluates the expression in a new sandboxed Lua environment
+	///
+	/// The sandbox only provides the `string`, `table`, `math` and `utf8` libraries, the functions
+	/// of the `os` library that don't interact with the system, e.g `os.date`, and the base
+	/// functions that don't load code or print. Evaluation fails when the expression runs more than
+	/// [`Self::INSTRUCTION_LIMIT`] instructions or uses more than [`Self::MEMORY_LIMIT`] bytes.
+	fn compute(&self) -> Result<String, String> {
+		let lua = Lua::new_with(
+			StdLib::STRING | StdLib::TABLE | StdLib::MATH | StdLib::UTF8 | StdLib::OS,
+			LuaOptions::default(),
+		)
+		.map_err(|err| format!("Failed to create Lua environment: {err}"))?;
+		lua.set_memory_limit(Self::MEMORY_LIMIT)
+			.map_err(|err| err.to_string())?;
+
+		// Abort long running expressions
+		const STEP: u32 = 1000;
+		let instructions = Cell::new(0u32);
+		lua.set_hook(
+			HookTriggers::new().every_nth_instruction(STEP),
+			move |_, _| {
+				instructions.set(instructions.get() + STEP);
+				if instructions.get() > Self::INSTRUCTION_LIMIT {
+					return Err(mlua::Error::runtime(format!(
+						"Expression exceeded the limit of {} instructions",
+						Self::INSTRUCTION_LIMIT
+					)));
+				}
+				Ok(())
+			},
+		);
+
+		let globals = lua.globals();
+		for name in ["dofile", "loadfile", "load", "print"] {
+			globals.set(name, mlua::Nil).map_err(|err| err.to_string())?;
+		}
+		let os = globals
+			.get::<_, mlua::Table>("os")
+			.map_err(|err| err.to_string())?;
+		for name in [
+			"execute",
+			"exit",
+			"getenv",
+			"remove",
+			"rename",
+			"setlocale",
+			"tmpname",
+		] {
+			os.set(name, mlua::Nil).map_err(|err| err.to_string())?;
+		}
+
+		let value = lua
+			.load(self.expression.as_str())
+			.set_name(format!(":VAR:{}", self.name))
+			.eval::<mlua::Value>()
+			.map_err(|err| err.to_string())?;
+		match value {
+			mlua::Value::Nil => Err("Expression evaluated to `nil`".into()),
+			value => lua
+				.globals()
+				.get::<_, mlua::Function>("tostring")
+				.and_then(|tostring| tostring.call::<_, String>(value))
+				.map_err(|err| err.to_string()),
+		}
+	}
+}
+
+impl Variable for ComputedVariable {
+	fn location(&self) -> &Token { &self.location }
+
+	fn name(&self) -> &str { self.name.as_str() }
+
+	fn to_string(&self) -> String {
+		self.value
+			.get_or_init(|| self.compute())
+			.clone()
+			.unwrap_or_default()
+	}
+
+	fn value_token(&self) -> &Token { &self.value_token }
+
+	fn evaluate(&self) -> Result<(), String> {
+		self.value
+			.get_or_init(|| self.compute())
+			.as_ref()
+			.map(|_| ())
+			.map_err(|err| err.clone())
+	}
+
+	fn parse<'a>(&self, state: &ParserState, _location: Token, document: &'a dyn Document<'a>) {
+		let source = Rc::new(VirtualSource::new(
+			self.location().clone(),
+			format!(":VAR:{}", self.name()),
+			self.to_string(),
+		));
+
+		state.with_state(|new_state| {
+			let _ = new_state
+				.parser
+				.parse_into(new_state, source, document, ParseMode::default());
+		});
+	}
+}
@@ -9,8 +9,14 @@ use runtime_format::FormatKeyError;
 
 use crate::compiler::compiler::Compiler;
 use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
 use crate::document::document::Document;
 use crate::document::element::ElemKind;
+use crate::elements::list::elem::CheckboxState;
+use crate::elements::list::elem::CustomListData;
+use crate::elements::list::elem::ListEntry;
+use crate::elements::list::elem::ListMarker;
+use crate::elements::list::elem::MarkerKind;
 use crate::parser::parser::ParserState;
 use crate::parser::property::Property;
 use crate::parser::property::PropertyParser;
@@ -41,7 +47,7 @@ impl Default for Quote {
 		let mut props = HashMap::new();
 		props.insert(
 			"author".to_string(),
-			Property::new("Quote author".to_string(), None),
+			Property::new("Quote author".to_string(), None).primary(),
 		);
 		props.insert(
 			"cite".to_string(),
@@ -82,6 +88,8 @@ impl FormatKey for QuoteFmtPair<'_> {
 impl BlockType for Quote {
 	fn name(&self) -> &'static str { "Quote" }
 
+	fn properties(&self) -> Option<&PropertyParser> { Some(&self.properties) }
+
 	fn parse_properties(
 		&self,
 		reports: &mut Vec<Report>,
@@ -134,7 +142,7 @@ impl BlockType for Quote {
 		let quote = properties.downcast_ref::<QuoteData>().unwrap();
 
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let mut result = r#"<div class="blockquote-content">"#.to_string();
 				let format_author = || -> Result<String, String> {
 					let mut result = String::new();
@@ -238,7 +246,7 @@ impl BlockType for Warning {
 		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let mut result = r#"<div class="block-warning">"#.to_string();
 				for elem in &block.content {
 					result += elem
@@ -278,7 +286,7 @@ impl BlockType for Note {
 		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let mut result = r#"<div class="block-note">"#.to_string();
 				for elem in &block.content {
 					result += elem
@@ -318,7 +326,7 @@ impl BlockType for Todo {
 		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let mut result = r#"<div class="block-todo">"#.to_string();
 				for elem in &block.content {
 					result += elem
@@ -358,7 +366,7 @@ impl BlockType for Tip {
 		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let mut result = r#"<div class="block-tip">"#.to_string();
 				for elem in &block.content {
 					result += elem
@@ -398,7 +406,7 @@ impl BlockType for Caution {
 		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let mut result = r#"<div class="block-caution">"#.to_string();
 				for elem in &block.content {
 					result += elem
@@ -412,3 +420,256 @@ impl BlockType for Caution {
 		}
 	}
 }
+
+#[derive(Debug, Default)]
+#[auto_registry::auto_registry(registry = "block_types")]
+pub struct SpeakerNotes;
+
+impl BlockType for SpeakerNotes {
+	fn name(&self) -> &'static str { "Speaker Notes" }
+
+	fn parse_properties(
+		&self,
+		_reports: &mut Vec<Report>,
+		_state: &ParserState,
+		_token: Token,
+	) -> Option<Box<dyn Any>> {
+		Some(Box::new(()))
+	}
+
+	fn compile(
+		&self,
+		block: &Block,
+		_properties: &Box<dyn Any>,
+		compiler: &Compiler,
+		document: &dyn Document,
+		cursor: usize,
+	) -> Result<String, String> {
+		match compiler.target() {
+			// Notes are only visible to the speaker
+			HTML => Ok(String::new()),
+			SLIDES => {
+				let mut result = r#"<aside class="notes">"#.to_string();
+				for elem in &block.content {
+					result += elem
+						.compile(compiler, document, cursor + result.len())?
+						.as_str();
+				}
+				result += "</aside>";
+				Ok(result)
+			}
+			_ => todo!(""),
+		}
+	}
+}
+
+#[derive(Debug)]
+#[auto_registry::auto_registry(registry = "block_types")]
+pub struct Solution {
+	properties: PropertyParser,
+}
+
+impl Default for Solution {
+	fn default() -> Self {
+		let mut props = HashMap::new();
+		props.insert(
+			"title".to_string(),
+			Property::new("Solution title".to_string(), Some("Solution".to_string())).primary(),
+		);
+		Self {
+			properties: PropertyParser { properties: props },
+		}
+	}
+}
+
+impl BlockType for Solution {
+	fn name(&self) -> &'static str { "Solution" }
+
+	fn properties(&self) -> Option<&PropertyParser> { Some(&self.properties) }
+
+	fn parse_properties(
+		&self,
+		reports: &mut Vec<Report>,
+		state: &ParserState,
+		token: Token,
+	) -> Option<Box<dyn Any>> {
+		let properties = match self
+			.properties
+			.parse("Block Solution", reports, state, token)
+		{
+			Some(props) => props,
+			None => return None,
+		};
+
+		properties
+			.get(reports, "title", |_, value| {
+				Result::<_, String>::Ok(value.value.clone())
+			})
+			.map(|title| Box::new(title) as Box<dyn Any>)
+	}
+
+	fn compile(
+		&self,
+		block: &Block,
+		properties: &Box<dyn Any>,
+		compiler: &Compiler,
+		document: &dyn Document,
+		cursor: usize,
+	) -> Result<String, String> {
+		let title = properties.downcast_ref::<String>().unwrap();
+
+		match compiler.target() {
+			HTML | SLIDES => {
+				let mut result = format!(
+					r#"<details class="block-solution"><summary>{}</summary>"#,
+					Compiler::sanitize(HTML, title)
+				);
+				for elem in &block.content {
+					result += elem
+						.compile(compiler, document, cursor + result.len())?
+						.as_str();
+				}
+				result += "</details>";
+				Ok(result)
+			}
+			_ => todo!(""),
+		}
+	}
+}
+
+#[derive(Debug)]
+#[auto_registry::auto_registry(registry = "block_types")]
+pub struct Quiz {
+	properties: PropertyParser,
+}
+
+impl Default for Quiz {
+	fn default() -> Self {
+		let mut props = HashMap::new();
+		props.insert(
+			"reveal".to_string(),
+			Property::new(
+				"Label of the button revealing the answer".to_string(),
+				Some("Show answer".to_string()),
+			)
+			.primary(),
+		);
+		Self {
+			properties: PropertyParser { properties: props },
+		}
+	}
+}
+
+impl BlockType for Quiz {
+	fn name(&self) -> &'static str { "Quiz" }
+
+	fn properties(&self) -> Option<&PropertyParser> { Some(&self.properties) }
+
+	fn parse_properties(
+		&self,
+		reports: &mut Vec<Report>,
+		state: &ParserState,
+		token: Token,
+	) -> Option<Box<dyn Any>> {
+		let properties = match self.properties.parse("Block Quiz", reports, state, token) {
+			Some(props) => props,
+			None => return None,
+		};
+
+		properties
+			.get(reports, "reveal", |_, value| {
+				Result::<_, String>::Ok(value.value.clone())
+			})
+			.map(|reveal| Box::new(reveal) as Box<dyn Any>)
+	}
+
+	fn compile(
+		&self,
+		block: &Block,
+		properties: &Box<dyn Any>,
+		compiler: &Compiler,
+		document: &dyn Document,
+		cursor: usize,
+	) -> Result<String, String> {
+		let reveal = properties.downcast_ref::<String>().unwrap();
+
+		// Choices are the checkbox entries, correct choices are the checked ones
+		let choices = block
+			.content
+			.iter()
+			.filter_map(|elem| elem.downcast_ref::<ListEntry>())
+			.filter_map(|entry| match entry.custom {
+				Some(CustomListData::Checkbox(state)) => {
+					Some((entry, state == CheckboxState::Checked))
+				}
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		let correct = choices.iter().filter(|(_, correct)| *correct).count();
+		if correct == 0 {
+			return Err("Quiz block has no correct choice, mark them using `[x]`".into());
+		}
+
+		match compiler.target() {
+			HTML | SLIDES => {
+				// Single answer questions use radio buttons
+				let input = if correct == 1 { "radio" } else { "checkbox" };
+				let name = format!("quiz-{cursor}");
+
+				let mut result = r#"<fieldset class="block-quiz">"#.to_string();
+				for elem in &block.content {
+					if let Some(marker) = elem.downcast_ref::<ListMarker>() {
+						result += match (marker.kind, marker.numbered) {
+							(MarkerKind::Open, true) => r#"<ol class="quiz-choices">"#,
+							(MarkerKind::Open, false) => r#"<ul class="quiz-choices">"#,
+							(MarkerKind::Close, true) => "</ol>",
+							(MarkerKind::Close, false) => "</ul>",
+						};
+					} else if let Some((entry, correct)) =
+						elem.downcast_ref::<ListEntry>().and_then(|entry| {
+							choices
+								.iter()
+								.find(|(choice, _)| std::ptr::eq(*choice, entry))
+						}) {
+						if *correct {
+							result += r#"<li class="quiz-choice quiz-correct">"#;
+						} else {
+							result += r#"<li class="quiz-choice">"#;
+						}
+						result +=
+							format!(r#"<label><input type="{input}" name="{name}">"#).as_str();
+						for elem in &entry.content {
+							result += elem
+								.compile(compiler, document, cursor + result.len())?
+								.as_str();
+						}
+						result += "</label></li>";
+					} else {
+						result += elem
+							.compile(compiler, document, cursor + result.len())?
+							.as_str();
+					}
+				}
+
+				// Answer
+				result += format!(
+					r#"<details class="quiz-answer"><summary>{}</summary><ul>"#,
+					Compiler::sanitize(HTML, reveal)
+				)
+				.as_str();
+				for (entry, _) in choices.iter().filter(|(_, correct)| *correct) {
+					result += "<li>";
+					for elem in &entry.content {
+						result += elem
+							.compile(compiler, document, cursor + result.len())?
+							.as_str();
+					}
+					result += "</li>";
+				}
+				result += "</ul></details></fieldset>";
+				Ok(result)
+			}
+			_ => todo!(""),
+		}
+	}
+}
@@ -5,6 +5,7 @@ use std::rc::Rc;
 use crate::compiler::compiler::Compiler;
 use crate::document::document::Document;
 use crate::parser::parser::ParserState;
+use crate::parser::property::PropertyParser;
 use crate::parser::reports::Report;
 use crate::parser::source::Token;
 
@@ -23,6 +24,9 @@ pub trait BlockType: core::fmt::Debug {
 		token: Token,
 	) -> Option<Box<dyn Any>>;
 
+	/// Gets the property schema of this block, if it has properties
+	fn properties(&self) -> Option<&PropertyParser> { None }
+
 	/// Compile block
 	fn compile(
 		&self,
@@ -40,11 +44,14 @@ pub struct BlockHolder {
 }
 
 macro_rules! create_blocks {
-	( $($construct:expr),+ $(,)? ) => {{
+	( $( $(@cfg($cfg:meta))? $construct:expr ),+ $(,)? ) => {{
 		let mut map = HashMap::new();
 		$(
-			let val = Rc::new($construct) as Rc<dyn BlockType>;
-			map.insert(val.name().to_string(), val);
+			$(#[cfg($cfg)])?
+			{
+				let val = Rc::new($construct) as Rc<dyn BlockType>;
+				map.insert(val.name().to_string(), val);
+			}
 		)+
 		map
 	}};
@@ -77,4 +84,7 @@ impl BlockHolder {
 			Ok(())
 		}
 	}
+
+	/// Iterates over all registered [`BlockType`]
+	pub fn iter(&self) -> impl Iterator<Item = &Rc<dyn BlockType>> { self.blocks.values() }
 }
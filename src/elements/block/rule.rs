@@ -1,7 +1,9 @@
+use crate::lua::kernel::Binding;
 use crate::parser::reports::macros::*;
 use crate::parser::reports::*;
 use std::any::Any;
 use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::elements::text::elem::Text;
 use ariadne::Fmt;
@@ -11,7 +13,12 @@ use elements::list::elem::ListEntry;
 use elements::list::elem::ListMarker;
 use elements::paragraph::elem::Paragraph;
 use lsp::conceal::Conceals;
+use lsp::hover::Hover;
 use lsp::semantic::Semantics;
+use lua::kernel::CTX;
+use mlua::Error::BadArgument;
+use mlua::Lua;
+use parser::source::Source;
 use parser::source::Token;
 use parser::source::VirtualSource;
 use parser::util::escape_source;
@@ -27,6 +34,50 @@ use crate::parser::source::Cursor;
 
 use super::elem::Block;
 
+/// Parses the content of a block, extracting paragraphs, lists and nested blocks
+///
+/// # Return
+///
+/// None is returned if the content contains any other element
+fn parse_block_content<'a>(
+	state: &ParserState,
+	document: &'a dyn Document<'a>,
+	source: Rc<dyn Source>,
+) -> Option<Vec<Box<dyn Element>>> {
+	let parsed_doc = state.with_state(|new_state| {
+		new_state
+			.parser
+			.parse(new_state, source, Some(document), ParseMode::default())
+			.0
+	});
+
+	let mut parsed_content: Vec<Box<dyn Element>> = vec![];
+	for mut elem in parsed_doc.content().borrow_mut().drain(..) {
+		if let Some(paragraph) = elem.downcast_mut::<Paragraph>() {
+			if let Some(last) = parsed_content.last() {
+				if last.kind() == ElemKind::Inline {
+					parsed_content.push(Box::new(Text {
+						location: Token::new(
+							last.location().end()..last.location().end(),
+							last.location().source(),
+						),
+						content: " ".to_string(),
+					}) as Box<dyn Element>);
+				}
+			}
+			parsed_content.extend(std::mem::take(&mut paragraph.content));
+		} else if elem.downcast_ref::<Block>().is_some()
+			|| elem.downcast_ref::<ListEntry>().is_some()
+			|| elem.downcast_ref::<ListMarker>().is_some()
+		{
+			parsed_content.push(elem);
+		} else {
+			return None;
+		}
+	}
+	Some(parsed_content)
+}
+
 #[auto_registry::auto_registry(registry = "rules")]
 pub struct BlockRule {
 	start_re: Regex,
@@ -157,6 +208,7 @@ impl Rule for BlockRule {
 			};
 			conceals.add(
 				start..start + 1,
+				"block",
 				lsp::conceal::ConcealTarget::Token {
 					token: "block".into(),
 					params: json!({
@@ -167,6 +219,7 @@ impl Rule for BlockRule {
 			let name_range = captures.get(1).unwrap().range();
 			conceals.add(
 				name_range.start - 2..name_range.end + 1,
+				"block_name",
 				lsp::conceal::ConcealTarget::Token {
 					token: "block_name".into(),
 					params: json!({
@@ -176,6 +229,20 @@ impl Rule for BlockRule {
 			)
 		}
 
+		// Hover
+		if let Some(hover) = Hover::from_source(cursor.source.clone(), &state.shared.lsp) {
+			let name_range = captures.get(1).unwrap().range();
+			let mut doc = format!(
+				"**Block `{}`** — `>[!{}][properties]`\n\nEvery following line starting with `>` is part of the block.",
+				block_type.name(),
+				block_type.name()
+			);
+			if let Some(properties) = block_type.properties() {
+				doc += format!("\n\n{}", properties.to_markdown()).as_str();
+			}
+			hover.add(name_range.start - 2..name_range.end + 1, doc);
+		}
+
 		// Content
 		let entry_start = captures.get(0).unwrap().end();
 		let mut entry_content = String::new();
@@ -221,6 +288,7 @@ impl Rule for BlockRule {
 				};
 				conceals.add(
 					start..start + 1,
+					"block",
 					lsp::conceal::ConcealTarget::Token {
 						token: "block".into(),
 						params: json!({
@@ -240,47 +308,18 @@ impl Rule for BlockRule {
 			offsets,
 		));
 		// Parse content
-		let parsed_doc = state.with_state(|new_state| {
-			new_state
-				.parser
-				.parse(new_state, entry_src, Some(document), ParseMode::default())
-				.0
-		});
-
-		// Extract paragraph and nested blockquotes
-		let mut parsed_content: Vec<Box<dyn Element>> = vec![];
-		for mut elem in parsed_doc.content().borrow_mut().drain(..) {
-			if let Some(paragraph) = elem.downcast_mut::<Paragraph>() {
-				if let Some(last) = parsed_content.last() {
-					if last.kind() == ElemKind::Inline {
-						parsed_content.push(Box::new(Text {
-							location: Token::new(
-								last.location().end()..last.location().end(),
-								last.location().source(),
-							),
-							content: " ".to_string(),
-						}) as Box<dyn Element>);
-					}
-				}
-				parsed_content.extend(std::mem::take(&mut paragraph.content));
-			} else if elem.downcast_ref::<Block>().is_some()
-				|| elem.downcast_ref::<ListEntry>().is_some()
-				|| elem.downcast_ref::<ListMarker>().is_some()
-			{
-				parsed_content.push(elem);
-			} else {
-				report_err!(
-					&mut reports,
-					token.source(),
-					"Unable to Parse Block Entry".into(),
-					span(
-						token.range.clone(),
-						"Block may only contain paragraphs and other blocks".into()
-					)
-				);
-				return (end_cursor, reports);
-			}
-		}
+		let Some(parsed_content) = parse_block_content(state, document, entry_src) else {
+			report_err!(
+				&mut reports,
+				token.source(),
+				"Unable to Parse Block Entry".into(),
+				span(
+					token.range.clone(),
+					"Block may only contain paragraphs and other blocks".into()
+				)
+			);
+			return (end_cursor, reports);
+		};
 
 		state.push(
 			document,
@@ -294,4 +333,89 @@ impl Rule for BlockRule {
 
 		(end_cursor, reports)
 	}
+
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
+		let mut bindings = vec![];
+
+		bindings.push(Binding::new(
+			"push",
+			"(name: string, properties: string, content: string)",
+			"Inserts a block of type `name`, e.g `Quote`",
+			lua.create_function(|_, (name, properties, content): (String, String, String)| {
+				let bad_argument = |pos: usize, name: &str, err: String| BadArgument {
+					to: Some("push".to_string()),
+					pos,
+					name: Some(name.to_string()),
+					cause: Arc::new(mlua::Error::external(err)),
+				};
+
+				// The block content may run lua code, the context is restored once the block
+				// is parsed
+				let Some(ctx) = CTX.take() else {
+					return Ok(());
+				};
+				let result = (|| {
+					// Get block type
+					let Some(block_type) = ctx.state.shared.blocks.borrow().get(name.as_str())
+					else {
+						return Err(bad_argument(
+							1,
+							"name",
+							format!("Cannot find block type `{name}`"),
+						));
+					};
+
+					// Parse properties
+					let prop_source = Rc::new(VirtualSource::new(
+						ctx.location.clone(),
+						":LUA:Block Properties".into(),
+						properties,
+					)) as Rc<dyn Source>;
+					let Some(block_properties) = block_type.parse_properties(
+						&mut ctx.reports,
+						ctx.state,
+						prop_source.into(),
+					) else {
+						return Err(bad_argument(
+							2,
+							"properties",
+							"Failed to parse properties".into(),
+						));
+					};
+
+					// Parse content
+					let entry_src = Rc::new(VirtualSource::new(
+						ctx.location.clone(),
+						":LUA:Block Entry".into(),
+						content,
+					));
+					let Some(content) = parse_block_content(ctx.state, ctx.document, entry_src)
+					else {
+						return Err(bad_argument(
+							3,
+							"content",
+							"Block may only contain paragraphs and other blocks".into(),
+						));
+					};
+
+					ctx.state.push(
+						ctx.document,
+						Box::new(Block {
+							location: ctx.location.clone(),
+							content,
+							block_type,
+							block_properties,
+						}),
+					);
+					Ok(())
+				})();
+				CTX.set(Some(ctx));
+
+				result
+			})
+			.unwrap(),
+		));
+
+		bindings
+	}
 }
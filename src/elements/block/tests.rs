@@ -1,5 +1,8 @@
 use std::rc::Rc;
 
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target;
+use crate::compiler::exec::ExecPolicy;
 use crate::elements::block::elem::Block;
 use crate::elements::block::style::AuthorPos;
 use crate::elements::block::style::QuoteStyle;
@@ -133,3 +136,105 @@ AFTER
 		]
 	);
 }
+
+#[test]
+pub fn exercises() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+>[!Quiz]
+>What is two plus two?
+> * [ ] 3
+> * [x] 4
+
+>[!Quiz][reveal=Answers]
+>Which are even?
+> * [x] 2
+> * [ ] 3
+> * [x] 4
+
+>[!Solution]
+>Count on your fingers
+
+>[!Solution][Hint]
+>Use an abacus
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert_eq!(
+		compiled
+			.body
+			.matches(r#"<fieldset class="block-quiz">"#)
+			.count(),
+		2
+	);
+	assert_eq!(compiled.body.matches(r#"type="radio""#).count(), 2);
+	assert_eq!(compiled.body.matches(r#"type="checkbox""#).count(), 3);
+	assert_eq!(compiled.body.matches("quiz-correct").count(), 3);
+	assert!(compiled.body.contains(
+		r#"<details class="quiz-answer"><summary>Show answer</summary><ul><li>4</li></ul></details>"#
+	));
+	assert!(compiled.body.contains(
+		r#"<details class="quiz-answer"><summary>Answers</summary><ul><li>2</li><li>4</li></ul></details>"#
+	));
+	assert!(compiled.body.contains(
+		r#"<details class="block-solution"><summary>Solution</summary>Count on your fingers</details>"#
+	));
+	assert!(compiled.body.contains(
+		r#"<details class="block-solution"><summary>Hint</summary>Use an abacus</details>"#
+	));
+}
+
+#[test]
+pub fn lua() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+%<
+nml.block.push("Quote", "author=A, cite=B", "Some **quote**")
+nml.block.push("Warning", "", ">[!Note]\n>Nested")
+>%
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+	Block {
+		Text { content == "Some " };
+		Style;
+		Text { content == "quote" };
+		Style;
+	};
+	Block {
+		Block {
+			Text { content == "Nested" };
+		};
+	};
+	);
+
+	let borrow = doc.content().borrow();
+	let quote = borrow[0].downcast_ref::<Block>().unwrap();
+	assert_eq!(quote.block_type.name(), "Quote");
+	let warning = borrow[1].downcast_ref::<Block>().unwrap();
+	assert_eq!(warning.block_type.name(), "Warning");
+}
@@ -0,0 +1,152 @@
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target;
+
+/// Pseudo-language of code blocks containing terminal output with ANSI escape sequences
+pub const ANSI_LANGUAGE: &str = "ANSI";
+
+/// Colors of the 16 basic ANSI colors, normal then bright
+const PALETTE: [&str; 16] = [
+	"#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd", "#e5e5e5",
+	"#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6", "#29b8db", "#ffffff",
+];
+
+/// Gets the color of an entry in the 256 colors palette
+fn color_256(index: u8) -> String {
+	match index {
+		0..=15 => PALETTE[index as usize].to_string(),
+		16..=231 => {
+			let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+			let index = index - 16;
+			format!(
+				"#{:02x}{:02x}{:02x}",
+				level(index / 36),
+				level((index / 6) % 6),
+				level(index % 6)
+			)
+		}
+		232..=255 => {
+			let gray = 8 + (index - 232) * 10;
+			format!("#{gray:02x}{gray:02x}{gray:02x}")
+		}
+	}
+}
+
+/// Graphic rendition set by ANSI escape sequences
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AnsiStyle {
+	foreground: Option<String>,
+	background: Option<String>,
+	bold: bool,
+	dim: bool,
+	italic: bool,
+	underline: bool,
+}
+
+impl AnsiStyle {
+	/// Applies the parameters of a SGR (`ESC[...m`) sequence
+	fn apply(&mut self, params: &str) {
+		let mut params = params
+			.split(';')
+			.map(|param| param.parse::<u8>().unwrap_or(0));
+		while let Some(param) = params.next() {
+			match param {
+				0 => *self = Self::default(),
+				1 => self.bold = true,
+				2 => self.dim = true,
+				3 => self.italic = true,
+				4 => self.underline = true,
+				22 => {
+					self.bold = false;
+					self.dim = false;
+				}
+				23 => self.italic = false,
+				24 => self.underline = false,
+				30..=37 => self.foreground = Some(PALETTE[param as usize - 30].to_string()),
+				90..=97 => self.foreground = Some(PALETTE[param as usize - 82].to_string()),
+				40..=47 => self.background = Some(PALETTE[param as usize - 40].to_string()),
+				100..=107 => self.background = Some(PALETTE[param as usize - 92].to_string()),
+				39 => self.foreground = None,
+				49 => self.background = None,
+				38 | 48 => {
+					let color = match params.next() {
+						Some(5) => params.next().map(color_256),
+						Some(2) => match (params.next(), params.next(), params.next()) {
+							(Some(r), Some(g), Some(b)) => Some(format!("#{r:02x}{g:02x}{b:02x}")),
+							_ => None,
+						},
+						_ => None,
+					};
+					if param == 38 {
+						self.foreground = color;
+					} else {
+						self.background = color;
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+
+	/// Gets the css for this style
+	fn css(&self) -> String {
+		let mut css = vec![];
+		if let Some(color) = &self.foreground {
+			css.push(format!("color:{color}"));
+		}
+		if let Some(color) = &self.background {
+			css.push(format!("background-color:{color}"));
+		}
+		if self.bold {
+			css.push("font-weight:bold".into());
+		}
+		if self.dim {
+			css.push("opacity:0.7".into());
+		}
+		if self.italic {
+			css.push("font-style:italic".into());
+		}
+		if self.underline {
+			css.push("text-decoration:underline".into());
+		}
+		css.join(";")
+	}
+
+	/// Converts text containing ANSI escape sequences to html, starting from this style
+	///
+	/// Escape sequences other than colors and text attributes are removed. The style at the end of
+	/// the text is kept, so that consecutive lines can be converted separately.
+	pub fn render(&mut self, target: Target, text: &str) -> String {
+		let mut result = String::new();
+		let span = |result: &mut String, style: &AnsiStyle, text: &str| {
+			if text.is_empty() {
+				return;
+			}
+			let text = Compiler::sanitize(target, text);
+			if *style == AnsiStyle::default() {
+				*result += text.as_str();
+			} else {
+				*result += format!("<span style=\"{}\">{text}</span>", style.css()).as_str();
+			}
+		};
+
+		let mut rest = text;
+		while let Some(pos) = rest.find('\x1b') {
+			span(&mut result, self, &rest[..pos]);
+			rest = &rest[pos + 1..];
+
+			// Control Sequence Introducer
+			if let Some(sequence) = rest.strip_prefix('[') {
+				let end = sequence
+					.find(|c: char| ('\x40'..='\x7e').contains(&c))
+					.unwrap_or(sequence.len());
+				if sequence[end..].starts_with('m') {
+					self.apply(&sequence[..end]);
+				}
+				rest = &sequence[(end + 1).min(sequence.len())..];
+			}
+		}
+		span(&mut result, self, rest);
+
+		result
+	}
+}
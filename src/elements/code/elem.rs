@@ -1,13 +1,23 @@
-use std::sync::Once;
+use std::path::PathBuf;
+use std::time::Duration;
 
 use crate::cache::cache::Cached;
 use crate::cache::cache::CachedError;
 use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target;
 use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
+use crate::compiler::exec::run;
 use crate::document::document::Document;
+use crate::document::element::ContainerElement;
 use crate::document::element::ElemKind;
 use crate::document::element::Element;
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
 use crate::parser::source::Token;
+
+use super::ansi::AnsiStyle;
+use super::ansi::ANSI_LANGUAGE;
 use crypto::digest::Digest;
 use crypto::sha2::Sha512;
 use lazy_static::lazy_static;
@@ -15,6 +25,9 @@ use syntect::easy::HighlightLines;
 use syntect::highlighting::ThemeSet;
 use syntect::parsing::SyntaxSet;
 
+/// Default timeout for running code blocks
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CodeKind {
 	FullBlock,
@@ -40,6 +53,28 @@ pub struct Code {
 	pub code: String,
 	pub theme: Option<String>,
 	pub line_offset: usize,
+	/// Adds a button that copies the code to the clipboard
+	pub copy_button: bool,
+	/// Adds an anchor to every line, e.g `#name-L10`
+	pub line_anchors: bool,
+	/// File the code was included from, see the `src` property
+	pub source: Option<PathBuf>,
+	/// Callout markers (e.g `// <1>`), as the index of the line and the numbers of the callouts
+	pub callouts: Vec<(usize, Vec<usize>)>,
+	/// Command running the code at build time, e.g `python3 -u`
+	///
+	/// The code is passed on the command's standard input, its output is displayed below the block.
+	pub run: Option<String>,
+}
+
+/// Gets the prefix of the anchors of a code block (lines and callouts)
+///
+/// Unnamed blocks use the start of their `digest`.
+fn code_anchor(target: Target, name: Option<&String>, digest: &str) -> String {
+	match name {
+		Some(name) => Compiler::refname(target, name),
+		None => format!("code-{}", &digest[..8]),
+	}
 }
 
 impl Code {
@@ -51,25 +86,52 @@ impl Code {
 		&syntax_set
 	}
 
+	/// Gets the prefix of the anchors in the block, see [`code_anchor`]
+	pub fn anchor(&self, target: Target) -> String {
+		code_anchor(target, self.name.as_ref(), self.key().as_str())
+	}
+
 	fn highlight_html(&self, compiler: &Compiler) -> Result<String, String> {
 		lazy_static! {
 			static ref theme_set: ThemeSet = ThemeSet::load_defaults();
 		}
-		let syntax = match Code::get_syntaxes().find_syntax_by_name(self.language.as_str()) {
-			Some(syntax) => syntax,
-			None => {
-				return Err(format!(
-					"Unable to find syntax for language: {}",
-					self.language
-				))
-			}
+		let mut h = if self.language == ANSI_LANGUAGE {
+			None
+		} else {
+			let syntax = match Code::get_syntaxes().find_syntax_by_name(self.language.as_str()) {
+				Some(syntax) => syntax,
+				None => {
+					return Err(format!(
+						"Unable to find syntax for language: {}",
+						self.language
+					))
+				}
+			};
+
+			let theme_string = match self.theme.as_ref() {
+				Some(theme) => theme.as_str(),
+				None => "base16-ocean.dark",
+			};
+			Some(HighlightLines::new(syntax, &theme_set.themes[theme_string]))
 		};
 
-		let theme_string = match self.theme.as_ref() {
-			Some(theme) => theme.as_str(),
-			None => "base16-ocean.dark",
+		// Terminal output keeps its colors across lines
+		let mut ansi = AnsiStyle::default();
+		let mut highlight = |line: &str| -> Result<String, String> {
+			let Some(h) = h.as_mut() else {
+				return Ok(ansi.render(compiler.target(), line));
+			};
+			match h.highlight_line(line, Code::get_syntaxes()) {
+				Err(e) => Err(format!("Error highlighting line `{line}`: {}", e)),
+				Ok(regions) => syntect::html::styled_line_to_highlighted_html(
+					&regions[..],
+					syntect::html::IncludeBackground::No,
+				)
+				.map_err(|e| format!("Error highlighting code: {}", e)),
+			}
 		};
-		let mut h = HighlightLines::new(syntax, &theme_set.themes[theme_string]);
+
+		let copy_button = "<button class=\"code-block-copy\" title=\"Copy\" onclick=\"navigator.clipboard.writeText(Array.from(this.closest('.code-block').querySelectorAll('.code-block-line')).map(l=>l.textContent).join('\\n'))\">Copy</button>";
 
 		let mut result = String::new();
 		if self.block == CodeKind::FullBlock {
@@ -81,35 +143,53 @@ impl Code {
 				)
 				.as_str();
 			}
+			if self.copy_button {
+				result += copy_button;
+			}
+
+			let prefix = self.anchor(compiler.target());
+			let anchor = self.line_anchors.then_some(prefix.as_str());
+			let mut callouts = self.callouts.iter().peekable();
 
 			result += "<div class=\"code-block-content\"><table class=\"code-block-table\" cellspacing=\"0\">"
 				.to_string()
 				.as_str();
 			for (line_id, line) in self.code.split('\n').enumerate() {
-				result += "<tr><td class=\"code-block-gutter\">";
+				let line_number = line_id + self.line_offset;
+				match &anchor {
+					Some(anchor) => {
+						result += format!(
+							"<tr id=\"{anchor}-L{line_number}\"><td class=\"code-block-gutter\">"
+						)
+						.as_str();
 
-				// Line number
-				result +=
-					format!("<pre><span>{}</span></pre>", line_id + self.line_offset).as_str();
+						// Line number
+						result += format!(
+							"<pre><a href=\"#{anchor}-L{line_number}\">{line_number}</a></pre>"
+						)
+						.as_str();
+					}
+					None => {
+						result += "<tr><td class=\"code-block-gutter\">";
+
+						// Line number
+						result += format!("<pre><span>{line_number}</span></pre>").as_str();
+					}
+				}
 
 				// Code
 				result += "</td><td class=\"code-block-line\"><pre>";
-				match h.highlight_line(line, Code::get_syntaxes()) {
-					Err(e) => return Err(format!("Error highlighting line `{line}`: {}", e)),
-					Ok(regions) => {
-						match syntect::html::styled_line_to_highlighted_html(
-							&regions[..],
-							syntect::html::IncludeBackground::No,
-						) {
-							Err(e) => return Err(format!("Error highlighting code: {}", e)),
-							Ok(highlighted) => {
-								result += if highlighted.is_empty() {
-									"<br>"
-								} else {
-									highlighted.as_str()
-								}
-							}
-						}
+				let highlighted = highlight(line)?;
+				result += if highlighted.is_empty() {
+					"<br>"
+				} else {
+					highlighted.as_str()
+				};
+
+				// Callouts
+				if let Some((_, numbers)) = callouts.next_if(|(line, _)| *line == line_id) {
+					for number in numbers {
+						result += format!("<a class=\"code-callout\" href=\"#{prefix}-callout-{number}\" data-callout=\"{number}\"></a>").as_str();
 					}
 				}
 				result += "</pre></td></tr>";
@@ -117,46 +197,27 @@ impl Code {
 
 			result += "</table></div></div>";
 		} else if self.block == CodeKind::MiniBlock {
-			result += "<div class=\"code-block\"><div class=\"code-block-content\"><table class=\"code-block-table\" cellspacing=\"0\">";
+			result += "<div class=\"code-block\">";
+			if self.copy_button {
+				result += copy_button;
+			}
+			result += "<div class=\"code-block-content\"><table class=\"code-block-table\" cellspacing=\"0\">";
 
 			for line in self.code.split('\n') {
 				result += "<tr><td class=\"code-block-line\"><pre>";
 				// Code
-				match h.highlight_line(line, Code::get_syntaxes()) {
-					Err(e) => return Err(format!("Error highlighting line `{line}`: {}", e)),
-					Ok(regions) => {
-						match syntect::html::styled_line_to_highlighted_html(
-							&regions[..],
-							syntect::html::IncludeBackground::No,
-						) {
-							Err(e) => return Err(format!("Error highlighting code: {}", e)),
-							Ok(highlighted) => {
-								result += if highlighted.is_empty() {
-									"<br>"
-								} else {
-									highlighted.as_str()
-								}
-							}
-						}
-					}
-				}
+				let highlighted = highlight(line)?;
+				result += if highlighted.is_empty() {
+					"<br>"
+				} else {
+					highlighted.as_str()
+				};
 				result += "</pre></td></tr>";
 			}
 			result += "</table></div></div>";
 		} else if self.block == CodeKind::Inline {
 			result += "<a class=\"inline-code\"><code>";
-			match h.highlight_line(self.code.as_str(), Code::get_syntaxes()) {
-				Err(e) => return Err(format!("Error highlighting line `{}`: {}", self.code, e)),
-				Ok(regions) => {
-					match syntect::html::styled_line_to_highlighted_html(
-						&regions[..],
-						syntect::html::IncludeBackground::No,
-					) {
-						Err(e) => return Err(format!("Error highlighting code: {}", e)),
-						Ok(highlighted) => result += highlighted.as_str(),
-					}
-				}
-			}
+			result += highlight(self.code.as_str())?.as_str();
 			result += "</code></a>";
 		}
 
@@ -184,6 +245,13 @@ impl Cached for Code {
 		let mut hasher = Sha512::new();
 		hasher.input((self.block as usize).to_be_bytes().as_slice());
 		hasher.input(self.line_offset.to_be_bytes().as_slice());
+		hasher.input(&[self.copy_button as u8, self.line_anchors as u8]);
+		for (line, numbers) in &self.callouts {
+			hasher.input(line.to_be_bytes().as_slice());
+			numbers
+				.iter()
+				.for_each(|number| hasher.input(number.to_be_bytes().as_slice()));
+		}
 		if let Some(theme) = self.theme.as_ref() {
 			hasher.input(theme.as_bytes())
 		}
@@ -197,6 +265,108 @@ impl Cached for Code {
 	}
 }
 
+/// Output of a code block run at build time, see [`Code::run`]
+pub(crate) struct CodeOutput<'a>(&'a Code);
+
+impl CodeOutput<'_> {
+	/// Runs the code, or gets its output from the cache
+	///
+	/// Returns `None` when the code doesn't run or when its runner is refused.
+	fn render(
+		&self,
+		compiler: &Compiler,
+		document: &dyn Document,
+	) -> Result<Option<String>, String> {
+		let Some(command) = &self.0.run else {
+			return Ok(None);
+		};
+
+		// Cached outputs remain available when the runner is refused
+		if let Some(con) = compiler.cache() {
+			match self.lookup(con) {
+				Ok(Some(output)) => return Ok(Some(output)),
+				Ok(None) => {}
+				Err(e) => return Err(format!("Querying the cache failed: {e}")),
+			}
+		}
+
+		let mut args = command.split_whitespace();
+		let exec = match compiler
+			.exec_policy()
+			.resolve(args.next().unwrap_or_default())
+		{
+			Ok(exec) => exec,
+			Err(err) => {
+				let mut reports = vec![];
+				report_warn!(
+					&mut reports,
+					self.0.location.source(),
+					"External Command Refused".into(),
+					span(self.0.location.range.clone(), err),
+					note("The output of the code is not displayed".into())
+				);
+				compiler.report(reports);
+				return Ok(None);
+			}
+		};
+		let timeout = match document.get_variable("code.timeout") {
+			Some(var) => var
+				.to_string()
+				.trim()
+				.parse::<f64>()
+				.ok()
+				.and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+				.ok_or(format!(
+					"Invalid timeout `{}`, expected a duration in seconds",
+					var.to_string()
+				))?,
+			None => DEFAULT_TIMEOUT,
+		};
+
+		compiler.log(format!("Running `{command}`...").as_str());
+		let output = run(
+			exec.as_path(),
+			args.collect::<Vec<_>>().as_slice(),
+			self.0.code.as_str(),
+			timeout,
+		)?;
+		if let Some(con) = compiler.cache() {
+			self.insert(con, &output)
+				.map_err(|e| format!("Querying the cache failed: {e}"))?;
+		}
+		Ok(Some(output))
+	}
+}
+
+impl Cached for CodeOutput<'_> {
+	type Key = String;
+	type Value = String;
+
+	fn sql_table() -> &'static str {
+		"CREATE TABLE IF NOT EXISTS cached_code_output (
+				digest	TEXT PRIMARY KEY,
+				output	BLOB NOT NULL);"
+	}
+
+	fn sql_get_query() -> &'static str {
+		"SELECT output FROM cached_code_output WHERE digest = (?1)"
+	}
+
+	fn sql_insert_query() -> &'static str {
+		"INSERT INTO cached_code_output (digest, output) VALUES (?1, ?2)"
+	}
+
+	fn key(&self) -> <Self as Cached>::Key {
+		let mut hasher = Sha512::new();
+		if let Some(run) = self.0.run.as_ref() {
+			hasher.input(run.as_bytes());
+		}
+		hasher.input(self.0.code.as_bytes());
+
+		hasher.result_str()
+	}
+}
+
 impl Element for Code {
 	fn location(&self) -> &Token { &self.location }
 
@@ -207,35 +377,152 @@ impl Element for Code {
 	fn compile(
 		&self,
 		compiler: &Compiler,
-		_document: &dyn Document,
+		document: &dyn Document,
 		_cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
-				static CACHE_INIT: Once = Once::new();
-				CACHE_INIT.call_once(|| {
-					if let Some(con) = compiler.cache() {
-						if let Err(e) = Code::init(con) {
-							eprintln!("Unable to create cache table: {e}");
-						}
-					}
-				});
+			HTML | SLIDES => {
+				if let Some(source) = &self.source {
+					compiler.add_dependency(source);
+				}
 
-				if let Some(con) = compiler.cache() {
+				let mut result = if let Some(con) = compiler.cache() {
 					match self.cached(con, |s| s.highlight_html(compiler)) {
-						Ok(s) => Ok(s),
+						Ok(s) => s,
 						Err(e) => match e {
 							CachedError::SqlErr(e) => {
-								Err(format!("Querying the cache failed: {e}"))
+								return Err(format!("Querying the cache failed: {e}"))
 							}
-							CachedError::GenErr(e) => Err(e),
+							CachedError::GenErr(e) => return Err(e),
 						},
 					}
 				} else {
-					self.highlight_html(compiler)
+					self.highlight_html(compiler)?
+				};
+
+				// Output
+				if let Some(output) = CodeOutput(self).render(compiler, document)? {
+					result += format!(
+						"<div class=\"code-block-output\"><pre>{}</pre></div>",
+						AnsiStyle::default().render(compiler.target(), output.trim_end())
+					)
+					.as_str();
 				}
+				Ok(result)
 			}
 			_ => todo!(""),
 		}
 	}
 }
+
+/// Annotations of the callouts of a code block
+#[derive(Debug)]
+pub struct CodeCallouts {
+	pub(crate) location: Token,
+	/// Name of the annotated block
+	pub(crate) name: Option<String>,
+	/// Digest of the annotated block, see [`code_anchor`]
+	pub(crate) digest: String,
+	/// Callout numbers of the annotated block
+	pub(crate) markers: Vec<usize>,
+	/// Annotations, as [`CodeCallout`]
+	pub(crate) callouts: Vec<Box<dyn Element>>,
+}
+
+impl Element for CodeCallouts {
+	fn location(&self) -> &Token { &self.location }
+
+	fn kind(&self) -> ElemKind { ElemKind::Block }
+
+	fn element_name(&self) -> &'static str { "Code Callouts" }
+
+	fn as_container(&self) -> Option<&dyn ContainerElement> { Some(self) }
+
+	fn compile(
+		&self,
+		compiler: &Compiler,
+		document: &dyn Document,
+		cursor: usize,
+	) -> Result<String, String> {
+		match compiler.target() {
+			HTML | SLIDES => {
+				let anchor = code_anchor(compiler.target(), self.name.as_ref(), &self.digest);
+				let mut result = String::from("<ol class=\"code-callouts\">");
+				for callout in &self.callouts {
+					let number = callout.downcast_ref::<CodeCallout>().unwrap().number;
+					result += format!(
+						"<li id=\"{anchor}-callout-{number}\" value=\"{number}\" data-callout=\"{number}\">"
+					)
+					.as_str();
+					result += callout
+						.compile(compiler, document, cursor + result.len())?
+						.as_str();
+					result += "</li>";
+				}
+				result += "</ol>";
+				Ok(result)
+			}
+			_ => todo!(""),
+		}
+	}
+}
+
+impl ContainerElement for CodeCallouts {
+	fn contained(&self) -> &Vec<Box<dyn Element>> { &self.callouts }
+
+	fn push(&mut self, elem: Box<dyn Element>) -> Result<(), String> {
+		if elem.downcast_ref::<CodeCallout>().is_none() {
+			return Err("Attempted to insert invalid element into Code Callouts".to_string());
+		}
+
+		self.location.range = self.location.start()..elem.location().end();
+		self.callouts.push(elem);
+		Ok(())
+	}
+}
+
+/// Annotation of a callout, e.g `<1> Explanation`
+#[derive(Debug)]
+pub struct CodeCallout {
+	pub(crate) location: Token,
+	pub(crate) number: usize,
+	pub(crate) content: Vec<Box<dyn Element>>,
+}
+
+impl Element for CodeCallout {
+	fn location(&self) -> &Token { &self.location }
+
+	fn kind(&self) -> ElemKind { ElemKind::Block }
+
+	fn element_name(&self) -> &'static str { "Code Callout" }
+
+	fn as_container(&self) -> Option<&dyn ContainerElement> { Some(self) }
+
+	fn compile(
+		&self,
+		compiler: &Compiler,
+		document: &dyn Document,
+		cursor: usize,
+	) -> Result<String, String> {
+		let mut result = String::new();
+		for elem in &self.content {
+			result += elem
+				.compile(compiler, document, cursor + result.len())?
+				.as_str();
+		}
+		Ok(result)
+	}
+}
+
+impl ContainerElement for CodeCallout {
+	fn contained(&self) -> &Vec<Box<dyn Element>> { &self.content }
+
+	fn push(&mut self, elem: Box<dyn Element>) -> Result<(), String> {
+		if elem.kind() == ElemKind::Block {
+			return Err("Cannot add block element inside a callout".to_string());
+		}
+
+		self.content.push(elem);
+		Ok(())
+	}
+}
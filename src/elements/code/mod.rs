@@ -1,4 +1,6 @@
+pub mod ansi;
 pub mod elem;
 pub mod rule;
+pub mod style;
 #[cfg(test)]
 pub mod tests;
@@ -1,13 +1,16 @@
 use std::collections::HashMap;
+use std::rc::Rc;
 
+use crate::lua::kernel::Binding;
 use crate::parser::reports::macros::*;
 use crate::parser::reports::*;
 use ariadne::Fmt;
+use lazy_static::lazy_static;
 use lsp::code::CodeRange;
 use lsp::conceal::Conceals;
+use lsp::hover::Hover;
 use lsp::semantic::Semantics;
 use lua::kernel::CTX;
-use mlua::Function;
 use mlua::Lua;
 use parser::property::Property;
 use parser::util;
@@ -15,17 +18,104 @@ use regex::Captures;
 use regex::Regex;
 use serde_json::json;
 
+use crate::cache::cache::Cached;
 use crate::document::document::Document;
+use crate::document::document::DocumentAccessors;
+use crate::document::element::ContainerElement;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::ParserState;
 use crate::parser::property::PropertyParser;
 use crate::parser::reports::Report;
 use crate::parser::rule::RegexRule;
+use crate::parser::source::resolve_path;
 use crate::parser::source::Token;
+use crate::parser::source::VirtualSource;
 use crate::parser::util::escape_source;
+use crate::parser::util::parse_paragraph;
 
+use super::ansi::ANSI_LANGUAGE;
 use super::elem::Code;
+use super::elem::CodeCallout;
+use super::elem::CodeCallouts;
 use super::elem::CodeKind;
+use super::style::CodeStyle;
+
+/// Parses a 1-based inclusive range of lines: `10-42`, `10-` (up to the last line) or `10`
+fn parse_line_range(range: &str) -> Result<(usize, Option<usize>), String> {
+	let parse = |line: &str| {
+		line.trim()
+			.parse::<usize>()
+			.map_err(|err| format!("Invalid line `{line}`: {err}"))
+	};
+	let (start, end) = match range.split_once('-') {
+		Some((start, end)) if end.trim().is_empty() => (parse(start)?, None),
+		Some((start, end)) => (parse(start)?, Some(parse(end)?)),
+		None => (parse(range)?, Some(parse(range)?)),
+	};
+	if start == 0 {
+		return Err("Lines start at 1".into());
+	}
+	if end.is_some_and(|end| end < start) {
+		return Err(format!("Range ends before line {start}"));
+	}
+	Ok((start, end))
+}
+
+/// Removes the leading whitespaces common to all non-blank lines
+fn dedent_code(code: &str) -> String {
+	let indent = code
+		.split('\n')
+		.filter(|line| !line.trim().is_empty())
+		.map(|line| &line[..line.len() - line.trim_start().len()])
+		.reduce(|common, indent| {
+			let len = common
+				.chars()
+				.zip(indent.chars())
+				.take_while(|(l, r)| l == r)
+				.map(|(c, _)| c.len_utf8())
+				.sum();
+			&common[..len]
+		})
+		.unwrap_or("");
+
+	code.split('\n')
+		.map(|line| line.strip_prefix(indent).unwrap_or(line.trim_start()))
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+/// Extracts the callout markers at the end of the lines of `code`, e.g `// <1>` or `# <1> <2>`
+///
+/// # Returns
+///
+/// The code without the markers, and the callouts as the index of the line and the numbers of the
+/// callouts on that line.
+fn extract_callouts(code: &str) -> (String, Vec<(usize, Vec<usize>)>) {
+	lazy_static! {
+		static ref marker_re: Regex =
+			Regex::new(r"(?:^|\s+)(?:(?://|#|--|;|%)\s*)?((?:<\d+>\s*)+)$").unwrap();
+		static ref number_re: Regex = Regex::new(r"<(\d+)>").unwrap();
+	}
+
+	let mut callouts = vec![];
+	let lines = code
+		.split('\n')
+		.enumerate()
+		.map(|(index, line)| {
+			let Some(captures) = marker_re.captures(line) else {
+				return line;
+			};
+			let numbers = number_re
+				.captures_iter(captures.get(1).unwrap().as_str())
+				.filter_map(|number| number.get(1).unwrap().as_str().parse::<usize>().ok())
+				.collect::<Vec<_>>();
+			callouts.push((index, numbers));
+			&line[..captures.get(0).unwrap().start()]
+		})
+		.collect::<Vec<_>>();
+
+	(lines.join("\n"), callouts)
+}
 
 #[auto_registry::auto_registry(registry = "rules")]
 pub struct CodeRule {
@@ -38,7 +128,54 @@ impl Default for CodeRule {
 		let mut props = HashMap::new();
 		props.insert(
 			"line_offset".to_string(),
-			Property::new("Line number offset".to_string(), Some("1".to_string())),
+			Property::new(
+				"Line number offset, defaults to the first line of `lines`".to_string(),
+				None,
+			),
+		);
+		props.insert(
+			"src".to_string(),
+			Property::new(
+				"Path of a file to include the code from, relative to the document".to_string(),
+				None,
+			),
+		);
+		props.insert(
+			"lines".to_string(),
+			Property::new(
+				"Range of lines to display, e.g `10-42`, `10-` or `10`".to_string(),
+				None,
+			),
+		);
+		props.insert(
+			"dedent".to_string(),
+			Property::new(
+				"Removes the indentation common to all lines".to_string(),
+				Some("false".to_string()),
+			),
+		);
+		props.insert(
+			"run".to_string(),
+			Property::new(
+				"Command running the code at build time, its output is displayed below the block"
+					.to_string(),
+				None,
+			),
+		);
+		props.insert(
+			"copy".to_string(),
+			Property::new(
+				"Adds a copy button to the block, defaults to `style.code.copy_button`".to_string(),
+				None,
+			),
+		);
+		props.insert(
+			"anchors".to_string(),
+			Property::new(
+				"Adds an anchor to every line of the block, defaults to `style.code.line_anchors`"
+					.to_string(),
+				None,
+			),
 		);
 		Self {
 			re: [
@@ -61,6 +198,10 @@ impl RegexRule for CodeRule {
 
 	fn previous(&self) -> Option<&'static str> { Some("Block") }
 
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> {
+		vec![("Code", &self.properties)]
+	}
+
 	fn regexes(&self) -> &[regex::Regex] { &self.re }
 
 	fn enabled(&self, mode: &ParseMode, id: usize) -> bool { !mode.paragraph_only || id != 0 }
@@ -99,9 +240,10 @@ impl RegexRule for CodeRule {
 				if code_lang.is_empty() {
 					code_lang = "Plain Text".into();
 				}
-				if Code::get_syntaxes()
-					.find_syntax_by_name(code_lang.as_str())
-					.is_none()
+				if code_lang != ANSI_LANGUAGE
+					&& Code::get_syntaxes()
+						.find_syntax_by_name(code_lang.as_str())
+						.is_none()
 				{
 					report_err!(
 						&mut reports,
@@ -139,6 +281,106 @@ impl RegexRule for CodeRule {
 			code_content.pop();
 		}
 
+		// Included file
+		let mut source = None;
+		let mut first_line = None;
+		let mut callouts = vec![];
+		if index == 0 {
+			let (src, lines, dedent) = match (
+				properties.get_opt(&mut reports, "src", |_, value| {
+					Result::<_, String>::Ok((value.value.clone(), value.value_range.clone()))
+				}),
+				properties.get_opt(&mut reports, "lines", |_, value| {
+					parse_line_range(value.value.as_str())
+						.map(|lines| (lines, value.value_range.clone()))
+				}),
+				properties.get(&mut reports, "dedent", |_, value| {
+					value.value.parse::<bool>()
+				}),
+			) {
+				(Some(src), Some(lines), Some(dedent)) => (src, lines, dedent),
+				_ => return reports,
+			};
+
+			if let Some((path, range)) = src {
+				if !code_content.is_empty() {
+					report_err!(
+						&mut reports,
+						token.source(),
+						"Invalid Code Content".into(),
+						span(
+							captures.get(4).unwrap().range(),
+							format!(
+								"Code blocks including a file with {} must be empty",
+								"src".fg(state.parser.colors().info)
+							)
+						)
+					);
+					return reports;
+				}
+
+				match std::fs::canonicalize(resolve_path(&token.source(), &path))
+					.and_then(|path| std::fs::read_to_string(&path).map(|content| (path, content)))
+				{
+					Ok((path, content)) => {
+						code_content = content;
+						source = Some(path);
+					}
+					Err(err) => {
+						report_err!(
+							&mut reports,
+							token.source(),
+							"Invalid Code Source".into(),
+							span(
+								range,
+								format!(
+									"Unable to read file `{}`: {err}",
+									path.fg(state.parser.colors().highlight)
+								)
+							)
+						);
+						return reports;
+					}
+				}
+			}
+
+			if let Some(((start, end), range)) = lines {
+				let count = code_content.lines().count();
+				let end = end.unwrap_or(count);
+				if start > count || end > count {
+					report_err!(
+						&mut reports,
+						token.source(),
+						"Invalid Code Range".into(),
+						span(
+							range,
+							format!(
+								"Range `{}` is out of bounds",
+								format!("{start}-{end}").fg(state.parser.colors().highlight)
+							)
+						),
+						note(format!("The code has {count} lines"))
+					);
+					return reports;
+				}
+				code_content = code_content
+					.lines()
+					.skip(start - 1)
+					.take(end + 1 - start)
+					.collect::<Vec<_>>()
+					.join("\n");
+				first_line = Some(start);
+			} else if source.is_some() {
+				code_content = code_content.lines().collect::<Vec<_>>().join("\n");
+			}
+
+			if dedent {
+				code_content = dedent_code(code_content.as_str());
+			}
+
+			(code_content, callouts) = extract_callouts(code_content.as_str());
+		}
+
 		if code_content.is_empty() {
 			report_err!(
 				&mut reports,
@@ -153,6 +395,27 @@ impl RegexRule for CodeRule {
 			.get_variable("code.theme")
 			.map(|var| var.to_string());
 
+		// Get style
+		let style = state
+			.shared
+			.styles
+			.borrow()
+			.current(CodeStyle::key())
+			.downcast_rc::<CodeStyle>()
+			.unwrap();
+		let (copy_button, line_anchors) = match (
+			properties.get_opt(&mut reports, "copy", |_, value| value.value.parse::<bool>()),
+			properties.get_opt(&mut reports, "anchors", |_, value| {
+				value.value.parse::<bool>()
+			}),
+		) {
+			(Some(copy_button), Some(line_anchors)) => (
+				copy_button.unwrap_or(style.copy_button),
+				line_anchors.unwrap_or(style.line_anchors),
+			),
+			_ => return reports,
+		};
+
 		if index == 0
 		// Block
 		{
@@ -160,10 +423,19 @@ impl RegexRule for CodeRule {
 				let code_name = name.as_str().trim_end().trim_start().to_string();
 				(!code_name.is_empty()).then_some(code_name)
 			});
-			let line_offset = match properties.get(&mut reports, "line_offset", |_, value| {
+			let line_offset = match properties.get_opt(&mut reports, "line_offset", |_, value| {
 				value.value.parse::<usize>()
 			}) {
-				Some(line_offset) => line_offset,
+				Some(line_offset) => line_offset.unwrap_or(first_line.unwrap_or(1)),
+				_ => return reports,
+			};
+			let run = match properties.get_opt(&mut reports, "run", |_, value| {
+				if value.value.trim().is_empty() {
+					return Err("Expected a command".to_string());
+				}
+				Ok(value.value.trim().to_string())
+			}) {
+				Some(run) => run,
 				_ => return reports,
 			};
 
@@ -177,6 +449,11 @@ impl RegexRule for CodeRule {
 					code: code_content,
 					theme,
 					line_offset,
+					copy_button,
+					line_anchors,
+					source,
+					callouts,
+					run,
 				}),
 			);
 
@@ -204,6 +481,7 @@ impl RegexRule for CodeRule {
 
 				conceals.add(
 					start..end,
+					"code",
 					lsp::conceal::ConcealTarget::Token {
 						token: "code".into(),
 						params: json!({
@@ -225,6 +503,7 @@ impl RegexRule for CodeRule {
 					.unwrap();
 				conceals.add(
 					range.end - 3..range.end,
+					"code",
 					lsp::conceal::ConcealTarget::Text("".into()),
 				);
 			}
@@ -247,6 +526,11 @@ impl RegexRule for CodeRule {
 					code: code_content,
 					theme,
 					line_offset: 1,
+					copy_button,
+					line_anchors: false,
+					source: None,
+					callouts: vec![],
+					run: None,
 				}),
 			);
 
@@ -278,6 +562,7 @@ impl RegexRule for CodeRule {
 
 					conceals.add(
 						start..end,
+						"code",
 						lsp::conceal::ConcealTarget::Token {
 							token: "code".into(),
 							params: json!({
@@ -299,6 +584,7 @@ impl RegexRule for CodeRule {
 						.unwrap();
 					conceals.add(
 						range.end - 2..range.end,
+						"code",
 						lsp::conceal::ConcealTarget::Text("".into()),
 					);
 				}
@@ -339,13 +625,37 @@ impl RegexRule for CodeRule {
 			);
 		}
 
+		if let Some(hover) = Hover::from_source(token.source(), &state.shared.lsp) {
+			let start = captures
+				.get(0)
+				.map(|m| {
+					if token.source().content().as_bytes()[m.start()] == b'\n' {
+						m.start() + 1
+					} else {
+						m.start()
+					}
+				})
+				.unwrap();
+			let doc = if index == 0 {
+				"**Code block** — ` ```[properties]language, title `\n\nHighlighted code block, ended by ` ``` `."
+			} else {
+				"**Inline code** — ` ``[properties]language, code`` `\n\nHighlighted inline code."
+			};
+			hover.add(
+				start..start + if index == 0 { 3 } else { 2 },
+				format!("{doc}\n\n{}", self.properties.to_markdown()),
+			);
+		}
+
 		reports
 	}
 
-	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
 		let mut bindings = vec![];
-		bindings.push((
-			"push_inline".to_string(),
+		bindings.push(Binding::new(
+			"push_inline",
+			"(language: string, content: string)",
+			"Inserts inline code, highlighted as `language`",
 			lua.create_function(|_, (language, content): (String, String)| {
 				CTX.with_borrow(|ctx| {
 					ctx.as_ref().map(|ctx| {
@@ -364,6 +674,11 @@ impl RegexRule for CodeRule {
 								code: content,
 								theme,
 								line_offset: 1,
+								copy_button: false,
+								line_anchors: false,
+								source: None,
+								callouts: vec![],
+								run: None,
 							}),
 						);
 					})
@@ -374,8 +689,10 @@ impl RegexRule for CodeRule {
 			.unwrap(),
 		));
 
-		bindings.push((
-			"push_miniblock".to_string(),
+		bindings.push(Binding::new(
+			"push_miniblock",
+			"(language: string, content: string, line_offset: integer?)",
+			"Inserts a mini code block, lines are numbered from `line_offset`",
 			lua.create_function(
 				|_, (language, content, line_offset): (String, String, Option<usize>)| {
 					CTX.with_borrow(|ctx| {
@@ -384,6 +701,14 @@ impl RegexRule for CodeRule {
 								.document
 								.get_variable("code.theme")
 								.map(|var| var.to_string());
+							let style = ctx
+								.state
+								.shared
+								.styles
+								.borrow()
+								.current(CodeStyle::key())
+								.downcast_rc::<CodeStyle>()
+								.unwrap();
 
 							ctx.state.push(
 								ctx.document,
@@ -395,6 +720,11 @@ impl RegexRule for CodeRule {
 									code: content,
 									theme,
 									line_offset: line_offset.unwrap_or(1),
+									copy_button: style.copy_button,
+									line_anchors: false,
+									source: None,
+									callouts: vec![],
+									run: None,
 								}),
 							);
 						})
@@ -406,8 +736,10 @@ impl RegexRule for CodeRule {
 			.unwrap(),
 		));
 
-		bindings.push((
-			"push_block".to_string(),
+		bindings.push(Binding::new(
+			"push_block",
+			"(language: string, name: string?, content: string, line_offset: integer?)",
+			"Inserts a code block, lines are numbered from `line_offset`",
 			lua.create_function(
 				|_,
 				 (language, name, content, line_offset): (
@@ -422,6 +754,14 @@ impl RegexRule for CodeRule {
 								.document
 								.get_variable("code.theme")
 								.map(|var| var.to_string());
+							let style = ctx
+								.state
+								.shared
+								.styles
+								.borrow()
+								.current(CodeStyle::key())
+								.downcast_rc::<CodeStyle>()
+								.unwrap();
 
 							ctx.state.push(
 								ctx.document,
@@ -433,6 +773,11 @@ impl RegexRule for CodeRule {
 									code: content,
 									theme,
 									line_offset: line_offset.unwrap_or(1),
+									copy_button: style.copy_button,
+									line_anchors: style.line_anchors,
+									source: None,
+									callouts: vec![],
+									run: None,
 								}),
 							);
 						})
@@ -447,3 +792,148 @@ impl RegexRule for CodeRule {
 		bindings
 	}
 }
+
+#[auto_registry::auto_registry(registry = "rules")]
+pub struct CodeCalloutRule {
+	re: [Regex; 1],
+}
+
+impl Default for CodeCalloutRule {
+	fn default() -> Self {
+		Self {
+			re: [Regex::new(r"(?:^|\n)<(\d+)>[^\S\r\n]+([^\n]*)").unwrap()],
+		}
+	}
+}
+
+impl RegexRule for CodeCalloutRule {
+	fn name(&self) -> &'static str { "Code Callout" }
+
+	fn previous(&self) -> Option<&'static str> { Some("Code") }
+
+	fn regexes(&self) -> &[regex::Regex] { &self.re }
+
+	fn enabled(&self, mode: &ParseMode, _id: usize) -> bool { !mode.paragraph_only }
+
+	fn on_regex_match<'a>(
+		&self,
+		_index: usize,
+		state: &ParserState,
+		document: &'a (dyn Document<'a> + 'a),
+		token: Token,
+		captures: Captures,
+	) -> Vec<Report> {
+		let mut reports = vec![];
+
+		let number = match captures.get(1).unwrap().as_str().parse::<usize>() {
+			Ok(number) => number,
+			Err(err) => {
+				report_err!(
+					&mut reports,
+					token.source(),
+					"Invalid Callout".into(),
+					span(
+						captures.get(1).unwrap().range(),
+						format!("Unable to parse callout number: {err}")
+					)
+				);
+				return reports;
+			}
+		};
+
+		// Callouts of the annotated block
+		let markers = if let Some(callouts) = document.last_element::<CodeCallouts>() {
+			callouts.markers.clone()
+		} else if let Some(code) = document
+			.last_element::<Code>()
+			.filter(|code| code.block == CodeKind::FullBlock && !code.callouts.is_empty())
+		{
+			let markers = code
+				.callouts
+				.iter()
+				.flat_map(|(_, numbers)| numbers.iter().cloned())
+				.collect::<Vec<_>>();
+			let callouts = CodeCallouts {
+				location: token.clone(),
+				name: code.name.clone(),
+				digest: code.key(),
+				markers: markers.clone(),
+				callouts: vec![],
+			};
+			drop(code);
+			state.push(document, Box::new(callouts));
+			markers
+		} else {
+			report_err!(
+				&mut reports,
+				token.source(),
+				"Orphan Callout".into(),
+				span(
+					token.range.clone(),
+					format!(
+						"Callout {} does not follow a code block with callouts",
+						format!("<{number}>").fg(state.parser.colors().info)
+					)
+				),
+				help(
+					"Add callout markers at the end of the code block's lines, e.g `// <1>`".into()
+				)
+			);
+			return reports;
+		};
+		if !markers.contains(&number) {
+			report_warn!(
+				&mut reports,
+				token.source(),
+				"Unknown Callout".into(),
+				span(
+					captures.get(1).unwrap().range(),
+					format!(
+						"The code block has no callout {}",
+						format!("<{number}>").fg(state.parser.colors().info)
+					)
+				)
+			);
+		}
+
+		// Parse content
+		let content = captures.get(2).unwrap();
+		let content_src = Rc::new(VirtualSource::new(
+			Token::new(content.range(), token.source()),
+			"Code Callout".to_string(),
+			content.as_str().to_string(),
+		));
+		let content = match parse_paragraph(state, content_src, document) {
+			Ok(mut paragraph) => std::mem::take(&mut paragraph.content),
+			Err(err) => {
+				report_warn!(
+					&mut reports,
+					token.source(),
+					"Unable to parse Code Callout".into(),
+					span(content.range(), err.into())
+				);
+				vec![]
+			}
+		};
+
+		let callout = Box::new(CodeCallout {
+			location: token.clone(),
+			number,
+			content,
+		});
+		if let Err(err) = document
+			.last_element_mut::<CodeCallouts>()
+			.unwrap()
+			.push(callout)
+		{
+			report_err!(
+				&mut reports,
+				token.source(),
+				"Invalid Callout".into(),
+				span(token.range.clone(), err)
+			);
+		}
+
+		reports
+	}
+}
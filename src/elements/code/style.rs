@@ -0,0 +1,16 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::impl_elementstyle;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[auto_registry::auto_registry(registry = "elem_styles")]
+pub struct CodeStyle {
+	/// Adds a button to copy the content of code blocks
+	pub copy_button: bool,
+	/// Adds an anchor to every line of full code blocks
+	pub line_anchors: bool,
+}
+
+impl_elementstyle!(CodeStyle, "style.code");
@@ -1,12 +1,18 @@
 use std::rc::Rc;
 
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target;
+use crate::compiler::exec::ExecPolicy;
 use crate::elements::code::elem::Code;
+use crate::elements::code::elem::CodeCallout;
+use crate::elements::code::elem::CodeCallouts;
 use crate::elements::code::elem::CodeKind;
 use crate::parser::langparser::LangParser;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::Parser;
 use crate::parser::parser::ParserState;
 use crate::parser::source::SourceFile;
+use crate::tempdir::TempDir;
 use crate::validate_semantics;
 
 #[test]
@@ -122,6 +128,280 @@ fn code_inline() {
 	assert_eq!(found[2].line_offset, 1);
 }
 
+#[test]
+fn copy_anchors() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+```[copy=true, anchors=true] C, Some Code
+int a;
+int b;
+```
+@@style.code = {
+	"copy_button": true,
+	"line_anchors": true
+}
+```[anchors=false] C, Other
+int c;
+```
+``C,
+int d;
+``
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	{
+		let borrow = doc.content().borrow();
+		let found = borrow
+			.iter()
+			.filter_map(|e| e.downcast_ref::<Code>())
+			.collect::<Vec<_>>();
+
+		assert!(found[0].copy_button);
+		assert!(found[0].line_anchors);
+		assert!(found[1].copy_button);
+		assert!(!found[1].line_anchors);
+		assert!(found[2].copy_button);
+		assert!(!found[2].line_anchors);
+	}
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert_eq!(
+		compiled
+			.body
+			.matches("<button class=\"code-block-copy\"")
+			.count(),
+		3
+	);
+	assert!(compiled.body.contains(
+		r##"<tr id="Some_Code-L2"><td class="code-block-gutter"><pre><a href="#Some_Code-L2">2</a></pre>"##
+	));
+	assert!(!compiled.body.contains("Other-L1"));
+}
+
+#[test]
+fn include() {
+	let dir = TempDir::new("code-include");
+	let path = dir.join("include.rs");
+	std::fs::write(&path, "mod a {\n\tfn b() {\n\t\tc();\n\n\t}\n}\n").unwrap();
+
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		format!(
+			r#"
+```[src={0}] Rust, All
+```
+```[src={0}, lines=2-4, dedent] Rust, Range
+```
+```[src={0}, lines=3-, line_offset=1] Rust, Offset
+```
+```[lines=2] Plain Text, Inline
+a
+b
+```
+```[src={0}, lines=4-9] Rust, Out of bounds
+```
+"#,
+			path.display()
+		),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	{
+		let borrow = doc.content().borrow();
+		let found = borrow
+			.iter()
+			.filter_map(|e| e.downcast_ref::<Code>())
+			.collect::<Vec<_>>();
+		assert_eq!(found.len(), 4);
+
+		assert_eq!(found[0].code, "mod a {\n\tfn b() {\n\t\tc();\n\n\t}\n}");
+		assert_eq!(found[0].line_offset, 1);
+		assert!(found[0].source.is_some());
+
+		assert_eq!(found[1].code, "fn b() {\n\tc();\n");
+		assert_eq!(found[1].line_offset, 2);
+
+		assert_eq!(found[2].code, "\t\tc();\n\n\t}\n}");
+		assert_eq!(found[2].line_offset, 1);
+
+		assert_eq!(found[3].code, "b");
+		assert_eq!(found[3].line_offset, 2);
+		assert!(found[3].source.is_none());
+	}
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert_eq!(compiled.dependencies.len(), 1);
+	assert!(!compiled.dependencies_changed());
+
+	std::fs::remove_file(&path).unwrap();
+	assert!(compiled.dependencies_changed());
+}
+
+#[test]
+#[cfg(all(unix, feature = "exec"))]
+fn run() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+```[run=sh] Plain Text, Run
+echo "<out>"
+```
+```[run=sh -s] Plain Text, Failing
+exit 2
+```
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	{
+		let borrow = doc.content().borrow();
+		let found = borrow
+			.iter()
+			.filter_map(|e| e.downcast_ref::<Code>())
+			.collect::<Vec<_>>();
+		assert_eq!(found[0].run, Some("sh".to_string()));
+		assert_eq!(found[1].run, Some("sh -s".to_string()));
+	}
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled
+		.body
+		.contains("<div class=\"code-block-output\"><pre>&lt;out&gt;</pre></div>"));
+	assert_eq!(compiled.body.matches("code-block-output").count(), 1);
+
+	let exec = ExecPolicy {
+		disabled: true,
+		..Default::default()
+	};
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(!compiled.body.contains("code-block-output"));
+}
+
+#[test]
+fn ansi() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		"```ANSI, Output\n\x1b[1;31merror<\x1b[0m: a\n\x1b[38;5;21mb\x1b[2K\nc\x1b[m d\n```\n"
+			.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled.body.contains(
+		r#"<pre><span style="color:#cd3131;font-weight:bold">error&lt;</span>: a</pre>"#
+	));
+	// Colors carry over to the next lines
+	assert!(compiled
+		.body
+		.contains(r#"<pre><span style="color:#0000ff">b</span></pre>"#));
+	assert!(compiled
+		.body
+		.contains(r#"<pre><span style="color:#0000ff">c</span> d</pre>"#));
+	assert!(!compiled.body.contains('\x1b'));
+}
+
+#[test]
+fn callouts() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+```Rust, Callouts
+fn main() { // <1>
+	let a = b<2>;
+	println!("{a}"); // <2> <3>
+}
+```
+<1> Entry *point*
+<3> Prints `a`
+<4> Unknown
+
+Text
+<1> Orphan
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	{
+		let borrow = doc.content().borrow();
+		let code = borrow[0].downcast_ref::<Code>().unwrap();
+		assert_eq!(
+			code.code,
+			"fn main() {\n\tlet a = b<2>;\n\tprintln!(\"{a}\");\n}"
+		);
+		assert_eq!(code.callouts, vec![(0, vec![1]), (2, vec![2, 3])]);
+
+		let callouts = borrow[1].downcast_ref::<CodeCallouts>().unwrap();
+		let numbers = callouts
+			.callouts
+			.iter()
+			.map(|callout| callout.downcast_ref::<CodeCallout>().unwrap().number)
+			.collect::<Vec<_>>();
+		assert_eq!(numbers, vec![1, 3, 4]);
+		assert!(borrow[2..]
+			.iter()
+			.all(|elem| elem.downcast_ref::<CodeCallouts>().is_none()));
+	}
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled
+		.body
+		.contains(r##"<a class="code-callout" href="#Callouts-callout-1" data-callout="1"></a>"##));
+	assert!(compiled.body.contains(
+		r#"<li id="Callouts-callout-1" value="1" data-callout="1">Entry <i>point</i></li>"#
+	));
+}
+
 #[test]
 fn semantic() {
 	let source = Rc::new(SourceFile::with_content(
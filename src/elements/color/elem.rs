@@ -0,0 +1,82 @@
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
+use crate::document::document::Document;
+use crate::document::element::ElemKind;
+use crate::document::element::Element;
+use crate::parser::source::Token;
+
+#[derive(Debug)]
+pub struct Color {
+	pub(crate) location: Token,
+	/// Red, green, blue and alpha channels
+	pub(crate) rgba: [u8; 4],
+}
+
+impl Color {
+	/// Parses an hexadecimal color literal: `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`
+	pub fn parse(literal: &str) -> Result<[u8; 4], String> {
+		let digits = match literal.strip_prefix('#') {
+			Some(digits) => digits,
+			None => return Err(format!("Color `{literal}` must start with `#`")),
+		};
+		if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+			return Err(format!(
+				"Color `{literal}` must only contain hexadecimal digits"
+			));
+		}
+
+		let digit = |b: u8| (b as char).to_digit(16).unwrap() as u8;
+		let mut rgba = [255u8; 4];
+		match digits.len() {
+			3 | 4 => {
+				for (b, channel) in digits.bytes().zip(rgba.iter_mut()) {
+					*channel = digit(b) * 17;
+				}
+			}
+			6 | 8 => {
+				for (pair, channel) in digits.as_bytes().chunks(2).zip(rgba.iter_mut()) {
+					*channel = digit(pair[0]) * 16 + digit(pair[1]);
+				}
+			}
+			_ => return Err(format!("Color `{literal}` must have 3, 4, 6 or 8 digits")),
+		}
+
+		Ok(rgba)
+	}
+
+	/// Gets the color as a lowercase hexadecimal string, omitting the alpha channel when opaque
+	pub fn to_hex(&self) -> String { Self::hex(self.rgba) }
+
+	/// Formats channels as a lowercase hexadecimal color, see [`Color::to_hex`]
+	pub fn hex(rgba: [u8; 4]) -> String {
+		let [r, g, b, a] = rgba;
+		if a == 255 {
+			format!("#{r:02x}{g:02x}{b:02x}")
+		} else {
+			format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+		}
+	}
+}
+
+impl Element for Color {
+	fn location(&self) -> &Token { &self.location }
+	fn kind(&self) -> ElemKind { ElemKind::Inline }
+	fn element_name(&self) -> &'static str { "Color" }
+	fn compile(
+		&self,
+		compiler: &Compiler,
+		_document: &dyn Document,
+		_cursor: usize,
+	) -> Result<String, String> {
+		match compiler.target() {
+			HTML | SLIDES => {
+				let hex = self.to_hex();
+				Ok(format!(
+					"<span class=\"color\"><span class=\"color-swatch\" style=\"background-color:{hex}\"></span><code>{hex}</code></span>"
+				))
+			}
+			_ => todo!(""),
+		}
+	}
+}
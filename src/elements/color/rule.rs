@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use crate::lua::kernel::Binding;
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
+use document::document::Document;
+use lsp::color::Colors;
+use lsp::semantic::Semantics;
+use lua::kernel::CTX;
+use mlua::Error::BadArgument;
+use mlua::Lua;
+use parser::parser::ParseMode;
+use parser::parser::ParserState;
+use parser::rule::RegexRule;
+use parser::source::Token;
+use regex::Captures;
+use regex::Regex;
+
+use super::elem::Color;
+
+#[auto_registry::auto_registry(registry = "rules")]
+pub struct ColorRule {
+	re: [Regex; 1],
+}
+
+impl Default for ColorRule {
+	fn default() -> Self {
+		Self {
+			// The leading newline is matched so that color literals take precedence over sections
+			re: [Regex::new(r"(\n)?#col\{(.*?)\}").unwrap()],
+		}
+	}
+}
+
+impl RegexRule for ColorRule {
+	fn name(&self) -> &'static str { "Color" }
+
+	fn previous(&self) -> Option<&'static str> { Some("Custom Style") }
+
+	fn regexes(&self) -> &[Regex] { &self.re }
+
+	fn enabled(&self, _mode: &ParseMode, _id: usize) -> bool { true }
+
+	fn on_regex_match<'a>(
+		&self,
+		_: usize,
+		state: &ParserState,
+		document: &'a (dyn Document<'a> + 'a),
+		token: Token,
+		matches: Captures,
+	) -> Vec<Report> {
+		let mut reports = vec![];
+
+		let value = matches.get(2).unwrap();
+		let start = matches
+			.get(1)
+			.map_or(token.start(), |newline| newline.end());
+		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
+			sems.add(start..value.start(), tokens.color_sep);
+			if !value.as_str().is_empty() {
+				sems.add(value.range(), tokens.color_value);
+			}
+			sems.add(value.end()..value.end() + 1, tokens.color_sep);
+		}
+
+		let rgba = match Color::parse(value.as_str().trim()) {
+			Ok(rgba) => rgba,
+			Err(err) => {
+				report_err!(
+					&mut reports,
+					token.source(),
+					"Invalid Color".into(),
+					span(value.range(), err)
+				);
+				return reports;
+			}
+		};
+
+		if let Some(colors) = Colors::from_source(token.source(), &state.shared.lsp) {
+			let [r, g, b, a] = rgba.map(|channel| channel as f32 / 255.0);
+			colors.add(
+				value.range(),
+				tower_lsp::lsp_types::Color {
+					red: r,
+					green: g,
+					blue: b,
+					alpha: a,
+				},
+			);
+		}
+
+		state.push(
+			document,
+			Box::new(Color {
+				location: Token::new(start..token.end(), token.source()),
+				rgba,
+			}),
+		);
+
+		reports
+	}
+
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
+		let mut bindings = vec![];
+
+		bindings.push(Binding::new(
+			"push",
+			"(color: string)",
+			"Inserts a color swatch",
+			lua.create_function(|_, color: String| {
+				let rgba = Color::parse(color.as_str()).map_err(|err| BadArgument {
+					to: Some("push".to_string()),
+					pos: 1,
+					name: Some("color".to_string()),
+					cause: Arc::new(mlua::Error::external(err)),
+				})?;
+
+				CTX.with_borrow(|ctx| {
+					ctx.as_ref().map(|ctx| {
+						ctx.state.push(
+							ctx.document,
+							Box::new(Color {
+								location: ctx.location.clone(),
+								rgba,
+							}),
+						);
+					})
+				});
+
+				Ok(())
+			})
+			.unwrap(),
+		));
+
+		bindings
+	}
+}
@@ -0,0 +1,144 @@
+use std::rc::Rc;
+
+use crate::elements::color::elem::Color;
+use crate::elements::paragraph::elem::Paragraph;
+use crate::elements::section::elem::Section;
+use crate::elements::text::elem::Text;
+use crate::parser::langparser::LangParser;
+use crate::parser::parser::ParseMode;
+use crate::parser::parser::Parser;
+use crate::parser::parser::ParserState;
+use crate::parser::source::Source;
+use crate::parser::source::SourceFile;
+use crate::validate_document;
+use crate::validate_semantics;
+
+#[test]
+fn parse() {
+	assert_eq!(Color::parse("#ff8800"), Ok([255, 136, 0, 255]));
+	assert_eq!(Color::parse("#F80"), Ok([255, 136, 0, 255]));
+	assert_eq!(Color::parse("#f808"), Ok([255, 136, 0, 136]));
+	assert_eq!(Color::parse("#ff880080"), Ok([255, 136, 0, 128]));
+	assert!(Color::parse("ff8800").is_err());
+	assert!(Color::parse("#ff88").is_ok());
+	assert!(Color::parse("#ff88g0").is_err());
+	assert!(Color::parse("#ff880").is_err());
+}
+
+#[test]
+fn parser() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+# Title
+#col{#ff8800}
+Orange #col{#F80} and
+#col{#00000080}
+			"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Section;
+		Paragraph {
+			Color { rgba == [255, 136, 0, 255] };
+			Text { content == " Orange " };
+			Color { rgba == [255, 136, 0, 255] };
+			Text { content == " and" };
+			Color { rgba == [0, 0, 0, 128] };
+		};
+	);
+}
+
+#[test]
+fn lua() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r##"
+Swatch: %<nml.color.push("#123456")>%
+			"##
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Paragraph {
+			Text { content == "Swatch: " };
+			Color { rgba == [0x12, 0x34, 0x56, 0xff] };
+		};
+	);
+}
+
+#[test]
+fn semantics() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+A #col{#abc}
+		"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+
+	validate_semantics!(state, source.clone(), 0,
+		color_sep { delta_line == 1, delta_start == 2, length == 5 };
+		color_value { delta_line == 0, delta_start == 5, length == 4 };
+		color_sep { delta_line == 0, delta_start == 4, length == 1 };
+	);
+}
+
+#[test]
+fn document_color() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+A #col{#ff0000}
+		"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+
+	let lsp = state.shared.lsp.as_ref().unwrap().borrow();
+	let colors = lsp
+		.colors
+		.get(&(source.clone() as Rc<dyn Source>))
+		.unwrap()
+		.colors
+		.borrow();
+	assert_eq!(colors.len(), 1);
+	assert_eq!(colors[0].range.start.line, 1);
+	assert_eq!(colors[0].range.start.character, 7);
+	assert_eq!(colors[0].range.end.character, 14);
+	assert_eq!(colors[0].color.red, 1.0);
+	assert_eq!(colors[0].color.green, 0.0);
+	assert_eq!(colors[0].color.alpha, 1.0);
+}
@@ -0,0 +1,127 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::document::document::Document;
+
+/// Variable holding the name of the compilation target
+pub const TARGET_VARIABLE: &str = "compiler.target";
+/// Variable holding the name of the compilation profile
+pub const PROFILE_VARIABLE: &str = "compiler.profile";
+/// Variable holding the comma-separated list of enabled feature flags
+pub const FLAGS_VARIABLE: &str = "compiler.flags";
+
+/// Gets the compilation target of `document`, if any
+pub fn compile_target(document: &dyn Document) -> Option<String> {
+	document
+		.get_variable(TARGET_VARIABLE)
+		.map(|var| var.to_string())
+}
+
+/// Gets the compilation profile of `document`, if any
+pub fn compile_profile(document: &dyn Document) -> Option<String> {
+	document
+		.get_variable(PROFILE_VARIABLE)
+		.map(|var| var.to_string())
+}
+
+/// Gets the feature flags enabled for `document`
+pub fn compile_flags(document: &dyn Document) -> Vec<String> {
+	document
+		.get_variable(FLAGS_VARIABLE)
+		.map(|var| {
+			var.to_string()
+				.split(',')
+				.map(|flag| flag.trim().to_string())
+				.filter(|flag| !flag.is_empty())
+				.collect()
+		})
+		.unwrap_or_default()
+}
+
+/// What a [`Condition`] is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionKey {
+	Target,
+	Profile,
+	Flag,
+}
+
+impl FromStr for ConditionKey {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"target" => Ok(ConditionKey::Target),
+			"profile" => Ok(ConditionKey::Profile),
+			"flag" => Ok(ConditionKey::Flag),
+			_ => Err(format!(
+				"Unknown condition `{s}`, expected `target`, `profile` or `flag`"
+			)),
+		}
+	}
+}
+
+impl Display for ConditionKey {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ConditionKey::Target => write!(f, "target"),
+			ConditionKey::Profile => write!(f, "profile"),
+			ConditionKey::Flag => write!(f, "flag"),
+		}
+	}
+}
+
+/// A single condition, e.g `target=html` or `flag!=draft`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+	pub key: ConditionKey,
+	pub value: String,
+	pub negated: bool,
+}
+
+impl Condition {
+	/// Parses a whitespace-separated list of conditions
+	pub fn parse_list(s: &str) -> Result<Vec<Condition>, String> {
+		let conditions = s
+			.split_whitespace()
+			.map(Condition::from_str)
+			.collect::<Result<Vec<_>, _>>()?;
+		if conditions.is_empty() {
+			return Err("Expected at least one condition".into());
+		}
+		Ok(conditions)
+	}
+
+	/// Checks the condition against the compilation settings of `document`
+	pub fn evaluate(&self, document: &dyn Document) -> bool {
+		let holds = match self.key {
+			ConditionKey::Target => compile_target(document).is_some_and(|t| t == self.value),
+			ConditionKey::Profile => compile_profile(document).is_some_and(|p| p == self.value),
+			ConditionKey::Flag => compile_flags(document).contains(&self.value),
+		};
+		holds != self.negated
+	}
+}
+
+impl FromStr for Condition {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (key, value, negated) = match s.split_once("!=") {
+			Some((key, value)) => (key, value, true),
+			None => match s.split_once('=') {
+				Some((key, value)) => (key, value, false),
+				None => return Err(format!("Invalid condition `{s}`, expected `KEY=VALUE`")),
+			},
+		};
+		if value.is_empty() {
+			return Err(format!("Missing value for condition `{s}`"));
+		}
+
+		Ok(Condition {
+			key: ConditionKey::from_str(key)?,
+			value: value.to_string(),
+			negated,
+		})
+	}
+}
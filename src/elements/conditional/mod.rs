@@ -0,0 +1,4 @@
+pub mod condition;
+pub mod rule;
+#[cfg(test)]
+pub mod tests;
@@ -0,0 +1,167 @@
+use std::rc::Rc;
+
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
+use ariadne::Fmt;
+use document::document::Document;
+use lsp::semantic::Semantics;
+use parser::parser::ParseMode;
+use parser::parser::ParserState;
+use parser::rule::RegexRule;
+use parser::source::Source;
+use parser::source::Token;
+use parser::source::VirtualSource;
+use regex::Captures;
+use regex::Match;
+use regex::Regex;
+use regex::RegexBuilder;
+
+use super::condition::Condition;
+
+/// Parses the content of the selected branch into `document`
+fn parse_branch<'a>(
+	state: &ParserState,
+	document: &'a (dyn Document<'a> + 'a),
+	token: &Token,
+	content: Match,
+) {
+	if content.as_str().is_empty() {
+		return;
+	}
+
+	let source = Rc::new(VirtualSource::new(
+		Token::new(content.range(), token.source()),
+		format!(":COND:{}", token.source().name()),
+		content.as_str().to_string(),
+	)) as Rc<dyn Source>;
+
+	state.with_state(|new_state| {
+		new_state
+			.parser
+			.parse_into(new_state, source, document, ParseMode::default());
+	});
+}
+
+#[auto_registry::auto_registry(registry = "rules")]
+pub struct ConditionalRule {
+	re: [Regex; 2],
+}
+
+impl Default for ConditionalRule {
+	fn default() -> Self {
+		Self {
+			re: [
+				RegexBuilder::new(
+					r"(?:^|\n)[^\S\n]*(#\+IF)(.*)$([\s\S]*?)(?:\n[^\S\n]*(#\+ELSE)[^\S\n]*$([\s\S]*?))?\n[^\S\n]*(#\+ENDIF)[^\S\n]*$",
+				)
+				.multi_line(true)
+				.build()
+				.unwrap(),
+				RegexBuilder::new(r"(?:^|\n)[^\S\n]*(#\+(?:IF|ELSE|ENDIF))\b.*$")
+					.multi_line(true)
+					.build()
+					.unwrap(),
+			],
+		}
+	}
+}
+
+impl RegexRule for ConditionalRule {
+	fn name(&self) -> &'static str { "Conditional" }
+
+	fn previous(&self) -> Option<&'static str> { Some("Import") }
+
+	fn regexes(&self) -> &[Regex] { &self.re }
+
+	fn enabled(&self, mode: &ParseMode, _id: usize) -> bool { !mode.paragraph_only }
+
+	fn on_regex_match<'a>(
+		&self,
+		index: usize,
+		state: &ParserState,
+		document: &'a (dyn Document<'a> + 'a),
+		token: Token,
+		matches: Captures,
+	) -> Vec<Report> {
+		let mut reports = vec![];
+
+		// Delimiter without a matching `#+IF` or `#+ENDIF`
+		if index == 1 {
+			let delimiter = matches.get(1).unwrap();
+			let message = if delimiter.as_str() == "#+IF" {
+				format!(
+					"Missing `{}` for this conditional",
+					"#+ENDIF".fg(state.parser.colors().highlight)
+				)
+			} else {
+				format!(
+					"`{}` has no matching `{}`",
+					delimiter.as_str().fg(state.parser.colors().highlight),
+					"#+IF".fg(state.parser.colors().highlight)
+				)
+			};
+			report_err!(
+				&mut reports,
+				token.source(),
+				"Invalid Conditional".into(),
+				span(delimiter.range(), message)
+			);
+			return reports;
+		}
+
+		let add_sep = |delimiter: Option<Match>| {
+			if let (Some(delimiter), Some((sems, tokens))) = (
+				delimiter,
+				Semantics::from_source(token.source(), &state.shared.lsp),
+			) {
+				sems.add(delimiter.range(), tokens.conditional_sep);
+			}
+		};
+
+		add_sep(matches.get(1));
+		let conditions = matches.get(2).unwrap();
+		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
+			if !conditions.as_str().trim().is_empty() {
+				let start = conditions.start()
+					+ (conditions.len() - conditions.as_str().trim_start().len());
+				sems.add(
+					start..conditions.start() + conditions.as_str().trim_end().len(),
+					tokens.conditional_condition,
+				);
+			}
+		}
+
+		let holds = match Condition::parse_list(conditions.as_str()) {
+			Ok(conditions) => conditions.iter().all(|cond| cond.evaluate(document)),
+			Err(err) => {
+				report_err!(
+					&mut reports,
+					token.source(),
+					"Invalid Conditional".into(),
+					span(
+						if conditions.as_str().trim().is_empty() {
+							matches.get(1).unwrap().range()
+						} else {
+							conditions.range()
+						},
+						err
+					)
+				);
+				return reports;
+			}
+		};
+
+		if holds {
+			parse_branch(state, document, &token, matches.get(3).unwrap());
+			add_sep(matches.get(4));
+		} else {
+			add_sep(matches.get(4));
+			if let Some(content) = matches.get(5) {
+				parse_branch(state, document, &token, content);
+			}
+		}
+		add_sep(matches.get(6));
+
+		reports
+	}
+}
@@ -0,0 +1,164 @@
+use std::rc::Rc;
+
+use crate::compiler::compiler::Target;
+use crate::compiler::process::process_from_memory;
+use crate::elements::conditional::condition::Condition;
+use crate::elements::conditional::condition::ConditionKey;
+use crate::elements::paragraph::elem::Paragraph;
+use crate::elements::style::elem::Style;
+use crate::elements::text::elem::Text;
+use crate::parser::langparser::LangParser;
+use crate::parser::parser::ParseMode;
+use crate::parser::parser::Parser;
+use crate::parser::parser::ParserState;
+use crate::parser::source::SourceFile;
+use crate::validate_document;
+use crate::validate_semantics;
+
+#[test]
+fn conditions() {
+	assert_eq!(
+		Condition::parse_list("target=html flag!=draft"),
+		Ok(vec![
+			Condition {
+				key: ConditionKey::Target,
+				value: "html".into(),
+				negated: false,
+			},
+			Condition {
+				key: ConditionKey::Flag,
+				value: "draft".into(),
+				negated: true,
+			},
+		])
+	);
+	assert!(Condition::parse_list("").is_err());
+	assert!(Condition::parse_list("target").is_err());
+	assert!(Condition::parse_list("target=").is_err());
+	assert!(Condition::parse_list("os=linux").is_err());
+}
+
+#[test]
+fn parser() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@compiler.target = html
+@compiler.profile = release
+@compiler.flags = web, print
+#+IF target=html
+A
+#+ELSE
+B
+#+ENDIF
+#+IF target=latex
+C
+#+ENDIF
+#+IF profile=release flag!=print
+D
+#+ELSE
+E
+#+ENDIF
+#+IF flag=web
+*F*
+#+ENDIF
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Paragraph {
+			Text { content == "A" };
+			Text { content == " E" };
+			Style;
+			Text { content == "F" };
+			Style;
+		};
+	);
+}
+
+#[test]
+fn target() {
+	let result = process_from_memory(
+		Target::HTML,
+		vec![r#"
+@html.page_title = 0
+#+IF target=html
+Only in html
+#+ENDIF
+#+IF target!=html
+Never in html
+#+ENDIF
+%<" "target:" .. nml.target() .. " profile:" .. tostring(nml.profile())>%
+"#
+		.into()],
+	)
+	.unwrap();
+
+	let body = &result[0].0.borrow().body;
+	assert!(body.contains("Only in html"));
+	assert!(!body.contains("Never in html"));
+	assert!(body.contains("target:html profile:nil"));
+}
+
+#[test]
+fn lua() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@compiler.flags = a,b
+%<" table.concat(nml.flags(), "+") .. tostring(nml.target())>%
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Paragraph {
+			Text { content == "a+bnil" };
+		};
+	);
+}
+
+#[test]
+fn semantics() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+#+IF  flag=a
+#+ELSE
+#+ENDIF
+		"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+
+	validate_semantics!(state, source.clone(), 0,
+		conditional_sep { delta_line == 1, delta_start == 0, length == 4 };
+		conditional_condition { delta_line == 0, delta_start == 6, length == 6 };
+		conditional_sep { delta_line == 1, delta_start == 0, length == 6 };
+		conditional_sep { delta_line == 1, delta_start == 0, length == 7 };
+	);
+}
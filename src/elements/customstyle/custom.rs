@@ -10,6 +10,7 @@ use crate::parser::reports::*;
 use crate::document::document::Document;
 use crate::lua::kernel::Kernel;
 use crate::parser::parser::ParserState;
+use crate::parser::property::PropertyParser;
 use crate::parser::reports::Report;
 use crate::parser::source::Token;
 
@@ -24,16 +25,20 @@ pub trait CustomStyle: core::fmt::Debug {
 	fn name(&self) -> &str;
 	/// Gets the begin and end token for a custom style
 	fn tokens(&self) -> &CustomStyleToken;
+	/// Gets the properties accepted after the start token, if the style has properties
+	fn properties(&self) -> Option<&PropertyParser> { None }
 
 	fn on_start<'a>(
 		&self,
 		location: Token,
+		properties: &HashMap<String, String>,
 		state: &ParserState,
 		document: &'a (dyn Document<'a> + 'a),
 	) -> Vec<Report>;
 	fn on_end<'a>(
 		&self,
 		location: Token,
+		properties: &HashMap<String, String>,
 		state: &ParserState,
 		document: &'a (dyn Document<'a> + 'a),
 	) -> Vec<Report>;
@@ -66,6 +71,7 @@ pub struct LuaCustomStyle {
 	pub(crate) tokens: CustomStyleToken,
 	pub(crate) start: mlua::Function<'static>,
 	pub(crate) end: mlua::Function<'static>,
+	pub(crate) properties: Option<PropertyParser>,
 }
 
 impl CustomStyle for LuaCustomStyle {
@@ -73,9 +79,12 @@ impl CustomStyle for LuaCustomStyle {
 
 	fn tokens(&self) -> &CustomStyleToken { &self.tokens }
 
+	fn properties(&self) -> Option<&PropertyParser> { self.properties.as_ref() }
+
 	fn on_start<'a>(
 		&self,
 		location: Token,
+		properties: &HashMap<String, String>,
 		state: &ParserState,
 		document: &'a dyn Document<'a>,
 	) -> Vec<Report> {
@@ -84,8 +93,11 @@ impl CustomStyle for LuaCustomStyle {
 		let mut ctx = KernelContext::new(location.clone(), state, document);
 
 		let mut reports = vec![];
-		kernel.run_with_context(&mut ctx, |_lua| {
-			if let Err(err) = self.start.call::<_, ()>(()) {
+		kernel.run_with_context(&mut ctx, |lua| {
+			let result = lua
+				.create_table_from(properties.clone())
+				.and_then(|properties| self.start.call::<_, ()>(properties));
+			if let Err(err) = result {
 				report_err!(
 					&mut reports,
 					location.source(),
@@ -106,6 +118,7 @@ impl CustomStyle for LuaCustomStyle {
 	fn on_end<'a>(
 		&self,
 		location: Token,
+		properties: &HashMap<String, String>,
 		state: &ParserState,
 		document: &'a dyn Document<'a>,
 	) -> Vec<Report> {
@@ -114,8 +127,11 @@ impl CustomStyle for LuaCustomStyle {
 		let mut ctx = KernelContext::new(location.clone(), state, document);
 
 		let mut reports = vec![];
-		kernel.run_with_context(&mut ctx, |_lua| {
-			if let Err(err) = self.end.call::<_, ()>(()) {
+		kernel.run_with_context(&mut ctx, |lua| {
+			let result = lua
+				.create_table_from(properties.clone())
+				.and_then(|properties| self.end.call::<_, ()>(properties));
+			if let Err(err) = result {
 				report_err!(
 					&mut reports,
 					location.source(),
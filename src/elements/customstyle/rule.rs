@@ -5,13 +5,19 @@ use std::rc::Rc;
 use std::sync::Arc;
 
 use ariadne::Fmt;
+use lsp::conceal::ConcealTarget;
+use lsp::conceal::Conceals;
+use lsp::linked;
 use lsp::semantic::Semantics;
 use mlua::Error::BadArgument;
-use mlua::Function;
 use mlua::Lua;
+use parser::property::Property;
+use parser::property::PropertyParser;
 use parser::rule::Rule;
 use parser::source::Token;
+use parser::util::escape_source;
 
+use crate::lua::kernel::Binding;
 use crate::parser::reports::macros::*;
 use crate::parser::reports::*;
 
@@ -26,12 +32,180 @@ use super::custom::CustomStyle;
 use super::custom::CustomStyleToken;
 use super::custom::LuaCustomStyle;
 use super::state::CustomStyleState;
+use super::state::OpenStyle;
 use super::state::STATE_NAME;
 
+/// Converts the property definitions of a custom style from lua
+///
+/// Properties are defined by a table mapping property names to their definition:
+/// `{ color = { description = "Text color", default = "red" } }`, properties without a default are
+/// required.
+fn lua_properties(
+	function: &str,
+	pos: usize,
+	table: Option<mlua::Table>,
+) -> mlua::Result<Option<PropertyParser>> {
+	let Some(table) = table else {
+		return Ok(None);
+	};
+
+	let mut properties = HashMap::new();
+	for pair in table.pairs::<String, mlua::Table>() {
+		let (name, definition) = pair.map_err(|err| BadArgument {
+			to: Some(function.to_string()),
+			pos,
+			name: Some("properties".to_string()),
+			cause: Arc::new(err),
+		})?;
+		let description = definition
+			.get::<_, Option<String>>("description")?
+			.unwrap_or_default();
+		let default = definition.get::<_, Option<String>>("default")?;
+		properties.insert(name, Property::new(description, default));
+	}
+
+	Ok(Some(PropertyParser { properties }))
+}
+
 #[auto_registry::auto_registry(registry = "rules")]
 #[derive(Default)]
 pub struct CustomStyleRule;
 
+impl CustomStyleRule {
+	/// Parses the properties following the start token of a style, e.g `|~[color=red]`
+	///
+	/// # Return
+	///
+	/// The value of the style's properties, and the position after the properties
+	fn parse_properties(
+		&self,
+		reports: &mut Vec<Report>,
+		state: &ParserState,
+		style: &dyn CustomStyle,
+		token: &Token,
+	) -> (HashMap<String, String>, usize) {
+		let mut values = HashMap::new();
+		let Some(properties) = style.properties() else {
+			return (values, token.end());
+		};
+
+		// Find the unescaped closing ']'
+		let content = token.source().content().clone();
+		let (mut range, mut end) = (token.end()..token.end(), token.end());
+		if content[token.end()..].starts_with('[') {
+			let mut escaped = 0usize;
+			let close = content[token.end() + 1..]
+				.char_indices()
+				.take_while(|(_, c)| *c != '\n')
+				.find(|(_, c)| {
+					let found = *c == ']' && escaped % 2 == 0;
+					escaped = if *c == '\\' { escaped + 1 } else { 0 };
+					found
+				});
+			match close {
+				Some((pos, _)) => {
+					range = token.end() + 1..token.end() + 1 + pos;
+					end = range.end + 1;
+				}
+				None => {
+					report_err!(
+						reports,
+						token.source(),
+						"Invalid Custom Style Properties".into(),
+						span(
+							token.end()..token.end() + 1,
+							format!(
+								"Missing closing ']' for the properties of style {}",
+								style.name().fg(state.parser.colors().info)
+							)
+						),
+					);
+				}
+			}
+		}
+
+		let source = escape_source(
+			token.source(),
+			range,
+			format!("Custom Style {} Properties", style.name()),
+			'\\',
+			"]",
+		);
+		let rule_name = format!("Custom Style {}", style.name());
+		let Some(map) = properties.parse(
+			rule_name.as_str(),
+			reports,
+			state,
+			Token::new(0..source.content().len(), source),
+		) else {
+			return (values, end);
+		};
+		for name in properties.properties.keys() {
+			if let Some(value) = map.get(reports, name, |_, value| {
+				Result::<_, String>::Ok(value.value.clone())
+			}) {
+				values.insert(name.clone(), value);
+			}
+		}
+
+		(values, end)
+	}
+
+	/// Reports styles overlapping a style that is being closed, e.g `[ ( ] )`
+	///
+	/// Overlaps are reported as warnings, unless variable `custom_style.strict` is set to `true`
+	fn report_overlaps<'a>(
+		&self,
+		reports: &mut Vec<Report>,
+		state: &ParserState,
+		document: &'a dyn Document<'a>,
+		name: &str,
+		token: &Token,
+		overlapping: Vec<OpenStyle>,
+	) {
+		let strict = document
+			.get_variable("custom_style.strict")
+			.is_some_and(|var| var.to_string() == "true");
+
+		for OpenStyle {
+			name: other,
+			token: start_token,
+			..
+		} in overlapping
+		{
+			let end_span = format!("Style {} ends here", name.fg(state.parser.colors().info));
+			let start_span = format!(
+				"While style {} starting here is still open",
+				other.as_str().fg(state.parser.colors().info)
+			);
+			let help = format!(
+				"Close style {} before closing style {}",
+				other.as_str().fg(state.parser.colors().info),
+				name.fg(state.parser.colors().info)
+			);
+			if strict {
+				report_err!(
+					reports,
+					token.source(),
+					"Overlapping Custom Styles".into(),
+					span(token.range.clone(), end_span),
+					span(start_token.source(), start_token.range.clone(), start_span),
+					help(help),
+				);
+			} else {
+				report_warn!(
+					reports,
+					token.source(),
+					"Overlapping Custom Styles".into(),
+					span(token.range.clone(), end_span),
+					span(start_token.source(), start_token.range.clone(), start_span),
+					help(help),
+				);
+			}
+		}
+	}
+}
+
 impl Rule for CustomStyleRule {
 	fn name(&self) -> &'static str { "Custom Style" }
 
@@ -106,9 +280,7 @@ impl Rule for CustomStyleRule {
 			None => {
 				match rule_state_borrow.insert(
 					STATE_NAME.into(),
-					Rc::new(RefCell::new(CustomStyleState {
-						toggled: HashMap::new(),
-					})),
+					Rc::new(RefCell::new(CustomStyleState { toggled: vec![] })),
 				) {
 					Err(err) => panic!("{err}"),
 					Ok(rule_state) => rule_state,
@@ -116,25 +288,28 @@ impl Rule for CustomStyleRule {
 			}
 		};
 
-		let (close, token) = match style.tokens() {
+		let mut reports = vec![];
+		let (closed, token) = match style.tokens() {
 			CustomStyleToken::Toggle(s) => {
 				let mut borrow = style_state.as_ref().borrow_mut();
 				let style_state = borrow.downcast_mut::<CustomStyleState>().unwrap();
 
-				if style_state.toggled.get(style.name()).is_some() {
+				let token = Token::new(cursor.pos..cursor.pos + s.len(), cursor.source.clone());
+				match style_state.close(style.name()) {
 					// Terminate style
-					let token = Token::new(cursor.pos..cursor.pos + s.len(), cursor.source.clone());
-
-					style_state.toggled.remove(style.name());
-					(true, token)
-				} else {
+					Some((closed, overlapping)) => {
+						self.report_overlaps(
+							&mut reports,
+							state,
+							document,
+							style.name(),
+							&token,
+							overlapping,
+						);
+						(Some(closed), token)
+					}
 					// Start style
-					let token = Token::new(cursor.pos..cursor.pos + s.len(), cursor.source.clone());
-
-					style_state
-						.toggled
-						.insert(style.name().into(), token.clone());
-					(false, token)
+					None => (None, token),
 				}
 			}
 			CustomStyleToken::Pair(s_begin, s_end) => {
@@ -144,8 +319,7 @@ impl Rule for CustomStyleRule {
 					// Terminate style
 					let token =
 						Token::new(cursor.pos..cursor.pos + s_end.len(), cursor.source.clone());
-					if style_state.toggled.get(style.name()).is_none() {
-						let mut reports = vec![];
+					let Some((closed, overlapping)) = style_state.close(style.name()) else {
 						report_err!(
 							&mut reports,
 							token.source(),
@@ -159,18 +333,24 @@ impl Rule for CustomStyleRule {
 							)
 						);
 						return (cursor.at(cursor.pos + s_end.len()), reports);
-					}
+					};
 
-					style_state.toggled.remove(style.name());
-					(true, token)
+					self.report_overlaps(
+						&mut reports,
+						state,
+						document,
+						style.name(),
+						&token,
+						overlapping,
+					);
+					(Some(closed), token)
 				} else {
 					// Start style
 					let token = Token::new(
 						cursor.pos..cursor.pos + s_begin.len(),
 						cursor.source.clone(),
 					);
-					if let Some(start_token) = style_state.toggled.get(style.name()) {
-						let mut reports = vec![];
+					if let Some(open) = style_state.get(style.name()) {
 						report_err!(
 							&mut reports,
 							token.source(),
@@ -179,63 +359,93 @@ impl Rule for CustomStyleRule {
 								token.range.clone(),
 								format!(
 									"When trying to start custom style {}",
-									self.name().fg(state.parser.colors().info)
+									style.name().fg(state.parser.colors().info)
 								)
 							),
 							span(
-								start_token.range.clone(),
+								open.token.source(),
+								open.token.range.clone(),
 								format!(
 									"Style {} previously starts here",
-									self.name().fg(state.parser.colors().info)
+									style.name().fg(state.parser.colors().info)
 								)
 							),
 						);
-						return (cursor.at(cursor.pos + s_end.len()), reports);
+						return (cursor.at(cursor.pos + s_begin.len()), reports);
 					}
 
-					style_state
-						.toggled
-						.insert(style.name().into(), token.clone());
-					(false, token)
+					(None, token)
 				}
 			}
 		};
 
-		let reports = if close {
-			style.on_end(token.clone(), state, document)
-		} else {
-			style.on_start(token.clone(), state, document)
+		let end_pos = match closed {
+			Some(closed) => {
+				linked::from_tokens(&[closed.token.clone(), token.clone()], &state.shared.lsp);
+				reports.extend(style.on_end(token.clone(), &closed.properties, state, document));
+				token.end()
+			}
+			None => {
+				let (properties, end_pos) =
+					self.parse_properties(&mut reports, state, style.as_ref(), &token);
+				style_state
+					.borrow_mut()
+					.downcast_mut::<CustomStyleState>()
+					.unwrap()
+					.open(OpenStyle {
+						name: style.name().into(),
+						token: token.clone(),
+						properties: properties.clone(),
+					});
+				reports.extend(style.on_start(token.clone(), &properties, state, document));
+				end_pos
+			}
 		};
 
 		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
 			sems.add(token.range.clone(), tokens.customstyle_marker);
+			if end_pos != token.end() {
+				sems.add(token.end()..token.end() + 1, tokens.customstyle_props_sep);
+				sems.add(end_pos - 1..end_pos, tokens.customstyle_props_sep);
+			}
+		}
+
+		if let Some(conceals) = Conceals::from_source(token.source(), &state.shared.lsp) {
+			conceals.add(
+				token.start()..end_pos,
+				"custom_style",
+				ConcealTarget::Text("".into()),
+			);
 		}
 
-		(cursor.at(token.end()), unsafe {
-			std::mem::transmute(reports)
-		})
+		(cursor.at(end_pos), unsafe { std::mem::transmute(reports) })
 	}
 
-	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
 		let mut bindings = vec![];
 
-		bindings.push((
-			"define_toggled".into(),
+		bindings.push(Binding::new(
+			"define_toggled",
+			"(name: string, token: string, on_start: function, on_end: function, properties: table?)",
+			"Defines a custom style toggled by `token`",
 			lua.create_function(
 				|_,
-				 (name, token, on_start, on_end): (
+				 (name, token, on_start, on_end, properties): (
 					String,
 					String,
 					mlua::Function,
 					mlua::Function,
+					Option<mlua::Table>,
 				)| {
 					let mut result = Ok(());
+					let properties = lua_properties("define_toggled", 5, properties)?;
 
 					let style = LuaCustomStyle {
 						tokens: CustomStyleToken::Toggle(token),
 						name: name.clone(),
 						start: unsafe { std::mem::transmute(on_start.clone()) },
 						end: unsafe { std::mem::transmute(on_end.clone()) },
+						properties,
 					};
 
 					CTX.with_borrow(|ctx| {
@@ -269,18 +479,22 @@ impl Rule for CustomStyleRule {
 			.unwrap(),
 		));
 
-		bindings.push((
-			"define_paired".into(),
+		bindings.push(Binding::new(
+			"define_paired",
+			"(name: string, token_start: string, token_end: string, on_start: function, on_end: function, properties: table?)",
+			"Defines a custom style delimited by `token_start` and `token_end`",
 			lua.create_function(
 				|_,
-				 (name, token_start, token_end, on_start, on_end): (
+				 (name, token_start, token_end, on_start, on_end, properties): (
 					String,
 					String,
 					String,
 					mlua::Function,
 					mlua::Function,
+					Option<mlua::Table>,
 				)| {
 					let mut result = Ok(());
+					let properties = lua_properties("define_paired", 6, properties)?;
 
 					if token_start == token_end
 					{
@@ -299,6 +513,7 @@ impl Rule for CustomStyleRule {
 						name: name.clone(),
 						start: unsafe { std::mem::transmute(on_start.clone()) },
 						end: unsafe { std::mem::transmute(on_end.clone()) },
+						properties,
 					};
 
 					CTX.with_borrow(|ctx| {
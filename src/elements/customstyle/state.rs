@@ -12,8 +12,42 @@ use crate::parser::state::RuleState;
 
 pub static STATE_NAME: &str = "elements.custom_style";
 
+/// A custom style that has been started
+#[derive(Debug, Clone)]
+pub struct OpenStyle {
+	/// Name of the style
+	pub name: String,
+	/// Location of the start token
+	pub token: Token,
+	/// Properties of the style, passed to the end handler
+	pub properties: HashMap<String, String>,
+}
+
 pub struct CustomStyleState {
-	pub toggled: HashMap<String, Token>,
+	/// Styles currently open, in opening order
+	pub toggled: Vec<OpenStyle>,
+}
+
+impl CustomStyleState {
+	/// Gets an open style by name
+	pub fn get(&self, name: &str) -> Option<&OpenStyle> {
+		self.toggled.iter().find(|style| style.name == name)
+	}
+
+	/// Opens a style
+	pub fn open(&mut self, style: OpenStyle) { self.toggled.push(style); }
+
+	/// Closes a style
+	///
+	/// # Return
+	///
+	/// The closed style, along with the styles opened after it that are still open, i.e the
+	/// styles overlapping the closed style
+	pub fn close(&mut self, name: &str) -> Option<(OpenStyle, Vec<OpenStyle>)> {
+		let pos = self.toggled.iter().position(|style| style.name == name)?;
+		let closed = self.toggled.remove(pos);
+		Some((closed, self.toggled[pos..].to_vec()))
+	}
 }
 
 impl RuleState for CustomStyleState {
@@ -22,38 +56,41 @@ impl RuleState for CustomStyleState {
 	fn on_remove(&self, state: &ParserState, document: &dyn Document) -> Vec<Report> {
 		let mut reports = vec![];
 
-		self.toggled.iter().for_each(|(style, token)| {
-			let container = std::cell::Ref::filter_map(document.content().borrow(), |content| {
-				content.last().and_then(|last| last.as_container())
-			})
-			.ok();
-			if container.is_none() {
-				return;
-			}
-			let paragraph_end = container
-				.unwrap()
-				.contained()
-				.last()
-				.map(|last| {
-					(
-						last.location().source(),
-						last.location().end_offset(1)..last.location().end(),
-					)
-				})
-				.unwrap();
-
-			report_err!(
-				&mut reports,
-				token.source(),
-				"Unterminated Custom Style".into(),
-				span(
-					token.range.clone(),
-					format!("Style {} starts here", style.fg(state.parser.colors().info))
-				),
-				span(paragraph_end.1, "Paragraph ends here".into()),
-				note("Styles cannot span multiple documents (i.e @import)".into())
-			);
-		});
+		self.toggled
+			.iter()
+			.for_each(|OpenStyle { name, token, .. }| {
+				let container =
+					std::cell::Ref::filter_map(document.content().borrow(), |content| {
+						content.last().and_then(|last| last.as_container())
+					})
+					.ok();
+				if container.is_none() {
+					return;
+				}
+				let paragraph_end = container
+					.unwrap()
+					.contained()
+					.last()
+					.map(|last| {
+						(
+							last.location().source(),
+							last.location().end_offset(1)..last.location().end(),
+						)
+					})
+					.unwrap();
+
+				report_err!(
+					&mut reports,
+					token.source(),
+					"Unterminated Custom Style".into(),
+					span(
+						token.range.clone(),
+						format!("Style {} starts here", name.fg(state.parser.colors().info))
+					),
+					span(paragraph_end.1, "Paragraph ends here".into()),
+					note("Styles cannot span multiple documents (i.e @import)".into())
+				);
+			});
 
 		reports
 	}
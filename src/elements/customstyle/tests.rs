@@ -1,12 +1,18 @@
+use std::cell::RefCell;
 use std::rc::Rc;
 
+use tower_lsp::lsp_types::Position;
+
 use crate::elements::paragraph::elem::Paragraph;
 use crate::elements::raw::elem::Raw;
 use crate::elements::text::elem::Text;
+use crate::lsp::linked::linked_at;
 use crate::parser::langparser::LangParser;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::Parser;
 use crate::parser::parser::ParserState;
+use crate::parser::reports::ReportKind;
+use crate::parser::source::Source;
 use crate::parser::source::SourceFile;
 use crate::validate_document;
 
@@ -107,3 +113,219 @@ pre [styled] post (Hello).
 		};
 	);
 }
+
+#[test]
+fn overlap() {
+	let parse = |content: &str| {
+		let source = Rc::new(SourceFile::with_content(
+			"".to_string(),
+			format!(
+				r#"
+%<[main]
+function nop() end
+nml.custom_style.define_paired("Square", "[", "]", nop, nop)
+nml.custom_style.define_paired("Paren", "(", ")", nop, nop)
+nml.custom_style.define_toggled("Degree", "°", nop, nop)
+>%
+{content}
+"#
+			),
+			None,
+		));
+		let reports = RefCell::new(vec![]);
+		let parser = LangParser::new(
+			false,
+			Box::new(|_, list| {
+				for report in list {
+					let spans = report
+						.spans
+						.iter()
+						.map(|span| span.token.range.clone())
+						.collect::<Vec<_>>();
+					reports.borrow_mut().push((
+						matches!(report.kind, ReportKind::Error),
+						report.message,
+						spans,
+					));
+				}
+			}),
+		);
+		let (doc, _) = parser.parse(
+			ParserState::new(&parser, None),
+			source,
+			None,
+			ParseMode::default(),
+		);
+		drop(doc);
+		drop(parser);
+		reports.into_inner()
+	};
+
+	assert!(parse("[a (b) c] °d [e] f°").is_empty());
+
+	let reports = parse("[a (b] c)");
+	assert_eq!(reports.len(), 1);
+	let (error, message, spans) = &reports[0];
+	assert!(!error);
+	assert_eq!(message, "Overlapping Custom Styles");
+	assert_eq!(spans.len(), 2);
+	assert_eq!(spans[1].end - spans[1].start, 1);
+	assert_eq!(spans[0].start - spans[1].start, 2);
+
+	let reports = parse("°a [b° c]");
+	assert_eq!(reports.len(), 1);
+	assert_eq!(reports[0].1, "Overlapping Custom Styles");
+
+	let reports = parse("@custom_style.strict = true\n[a (b] c)");
+	assert_eq!(reports.len(), 1);
+	assert!(reports[0].0);
+}
+
+#[test]
+fn properties() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+%<[main]
+function color_start(props)
+	nml.raw.push("inline", "<span style=\"color:" .. props.color .. ";font-weight:" .. props.weight .. "\">")
+end
+function color_end(props)
+	nml.raw.push("inline", "</span><!--" .. props.color .. "-->")
+end
+nml.custom_style.define_toggled("Color", "|~", color_start, color_end, {
+	color = { description = "Text color" },
+	weight = { description = "Font weight", default = "normal" },
+})
+>%
+a |~[color=red] b |~c |~[color=blue\], weight=bold]d|~
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Paragraph {
+			Text { content == "a " };
+			Raw { content == "<span style=\"color:red;font-weight:normal\">" };
+			Text { content == " b " };
+			Raw { content == "</span><!--red-->" };
+			Text { content == "c " };
+			Raw { content == "<span style=\"color:blue];font-weight:bold\">" };
+			Text { content == "d" };
+			Raw { content == "</span><!--blue]-->" };
+		};
+	);
+}
+
+#[test]
+fn properties_diagnostics() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+%<[main]
+function nop() end
+nml.custom_style.define_paired("Color", "[[", "]]", nop, nop, {
+	color = { description = "Text color" },
+})
+>%
+[[a]] [[[size=2]b]] [[[color=red
+"#
+		.to_string(),
+		None,
+	));
+	let reports = RefCell::new(vec![]);
+	let parser = LangParser::new(
+		false,
+		Box::new(|_, list| {
+			for report in list {
+				let spans = report
+					.spans
+					.iter()
+					.map(|span| span.message.clone())
+					.collect::<Vec<_>>();
+				reports.borrow_mut().push((report.message, spans));
+			}
+		}),
+	);
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+	drop(doc);
+	drop(parser);
+
+	let reports = reports.into_inner();
+	assert_eq!(reports.len(), 5);
+	assert_eq!(
+		reports[0].0,
+		"Failed to parse Custom Style Color properties"
+	);
+	assert!(reports[0].1[0].starts_with("Missing property color"));
+	assert_eq!(
+		reports[1].0,
+		"Failed to parse Custom Style Color properties"
+	);
+	assert!(reports[1].1[0].starts_with("Unknown property size"));
+	assert_eq!(reports[2].0, "Invalid Custom Style Properties");
+	assert!(reports[3].1[0].starts_with("Missing property color"));
+	assert_eq!(reports[4].0, "Unterminated Custom Style");
+}
+
+#[test]
+fn linked() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+%<[main]
+function nop() end
+nml.custom_style.define_toggled("Degree", "°", nop, nop)
+nml.custom_style.define_paired("Square", "[", "]", nop, nop)
+>%
+°a° [b] °c°
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+	let lsp = state.shared.lsp.as_ref().unwrap().borrow();
+	let linked = lsp
+		.linked
+		.get(&(source as Rc<dyn Source>))
+		.unwrap()
+		.linked
+		.borrow();
+	let columns = linked
+		.iter()
+		.map(|ranges| {
+			ranges
+				.iter()
+				.map(|range| (range.start.line, range.start.character, range.end.character))
+				.collect::<Vec<_>>()
+		})
+		.collect::<Vec<_>>();
+	// Delimiters with a different content are not linked
+	assert_eq!(
+		columns,
+		vec![vec![(6, 0, 1), (6, 2, 3)], vec![(6, 8, 9), (6, 10, 11)]]
+	);
+
+	let at = |character| linked_at(&linked, Position { line: 6, character });
+	assert_eq!(at(3), Some(linked[0].clone()));
+	assert_eq!(at(5), None);
+}
@@ -0,0 +1,68 @@
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::LATEX;
+use crate::compiler::compiler::Target::SLIDES;
+use crate::document::document::Document;
+use crate::document::element::ContainerElement;
+use crate::document::element::ElemKind;
+use crate::document::element::Element;
+use crate::parser::source::Token;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+	/// Inserted text: `{+added+}`
+	Insert,
+	/// Deleted text: `{-removed-}`
+	Delete,
+}
+
+#[derive(Debug)]
+pub struct Diff {
+	pub(crate) location: Token,
+	pub(crate) kind: DiffKind,
+	/// Inserted or deleted content
+	pub(crate) content: Vec<Box<dyn Element>>,
+}
+
+impl Element for Diff {
+	fn location(&self) -> &Token { &self.location }
+	fn kind(&self) -> ElemKind { ElemKind::Inline }
+	fn element_name(&self) -> &'static str { "Diff" }
+	fn compile(
+		&self,
+		compiler: &Compiler,
+		document: &dyn Document,
+		cursor: usize,
+	) -> Result<String, String> {
+		// LaTeX uses the `ulem` package
+		let (open, close) = match (compiler.target(), self.kind) {
+			(HTML | SLIDES, DiffKind::Insert) => ("<ins class=\"diff\">", "</ins>"),
+			(HTML | SLIDES, DiffKind::Delete) => ("<del class=\"diff\">", "</del>"),
+			(LATEX, DiffKind::Insert) => ("\\uline{", "}"),
+			(LATEX, DiffKind::Delete) => ("\\sout{", "}"),
+		};
+
+		let mut result = open.to_string();
+		for elem in &self.content {
+			result += elem
+				.compile(compiler, document, cursor + result.len())?
+				.as_str();
+		}
+		result += close;
+		Ok(result)
+	}
+
+	fn as_container(&self) -> Option<&dyn ContainerElement> { Some(self) }
+}
+
+impl ContainerElement for Diff {
+	fn contained(&self) -> &Vec<Box<dyn Element>> { &self.content }
+
+	fn push(&mut self, elem: Box<dyn Element>) -> Result<(), String> {
+		if elem.kind() == ElemKind::Block {
+			return Err("Cannot add block element inside a diff".to_string());
+		}
+		self.content.push(elem);
+		Ok(())
+	}
+}
@@ -0,0 +1,4 @@
+pub mod elem;
+pub mod rule;
+#[cfg(test)]
+pub mod tests;
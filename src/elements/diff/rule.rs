@@ -0,0 +1,108 @@
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
+use document::document::Document;
+use lsp::semantic::Semantics;
+use parser::parser::ParseMode;
+use parser::parser::ParserState;
+use parser::rule::RegexRule;
+use parser::source::Token;
+use parser::util::escape_source;
+use parser::util::parse_paragraph;
+use regex::Captures;
+use regex::Regex;
+
+use super::elem::Diff;
+use super::elem::DiffKind;
+
+#[auto_registry::auto_registry(registry = "rules")]
+pub struct DiffRule {
+	re: [Regex; 2],
+}
+
+impl Default for DiffRule {
+	fn default() -> Self {
+		Self {
+			re: [
+				Regex::new(r"\{\+((?:\\.|[^\\\\])*?)\+\}").unwrap(),
+				Regex::new(r"\{-((?:\\.|[^\\\\])*?)-\}").unwrap(),
+			],
+		}
+	}
+}
+
+impl RegexRule for DiffRule {
+	fn name(&self) -> &'static str { "Diff" }
+
+	fn previous(&self) -> Option<&'static str> { Some("Color") }
+
+	fn regexes(&self) -> &[Regex] { &self.re }
+
+	fn enabled(&self, _mode: &ParseMode, _id: usize) -> bool { true }
+
+	fn on_regex_match<'a>(
+		&self,
+		index: usize,
+		state: &ParserState,
+		document: &'a (dyn Document<'a> + 'a),
+		token: Token,
+		matches: Captures,
+	) -> Vec<Report> {
+		let mut reports = vec![];
+
+		let (kind, name, delimiter) = match index {
+			0 => (DiffKind::Insert, "Inserted Text", "+}"),
+			_ => (DiffKind::Delete, "Deleted Text", "-}"),
+		};
+		let content = matches.get(1).unwrap();
+		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
+			let sep = match kind {
+				DiffKind::Insert => tokens.diff_insert_sep,
+				DiffKind::Delete => tokens.diff_delete_sep,
+			};
+			sems.add(token.start()..content.start(), sep);
+		}
+
+		let source = escape_source(
+			token.source(),
+			content.range(),
+			name.into(),
+			'\\',
+			delimiter,
+		);
+		let content = if source.content().is_empty() {
+			vec![]
+		} else {
+			match parse_paragraph(state, source, document) {
+				Ok(mut paragraph) => std::mem::take(&mut paragraph.content),
+				Err(err) => {
+					report_err!(
+						&mut reports,
+						token.source(),
+						format!("Invalid {name}"),
+						span(content.range(), format!("Failed to parse content: {err}"))
+					);
+					return reports;
+				}
+			}
+		};
+
+		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
+			let sep = match kind {
+				DiffKind::Insert => tokens.diff_insert_sep,
+				DiffKind::Delete => tokens.diff_delete_sep,
+			};
+			sems.add(matches.get(1).unwrap().end()..token.end(), sep);
+		}
+
+		state.push(
+			document,
+			Box::new(Diff {
+				location: token,
+				kind,
+				content,
+			}),
+		);
+
+		reports
+	}
+}
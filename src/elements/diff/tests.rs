@@ -0,0 +1,73 @@
+use std::rc::Rc;
+
+use crate::elements::diff::elem::Diff;
+use crate::elements::diff::elem::DiffKind;
+use crate::elements::paragraph::elem::Paragraph;
+use crate::elements::style::elem::Style;
+use crate::elements::text::elem::Text;
+use crate::parser::langparser::LangParser;
+use crate::parser::parser::ParseMode;
+use crate::parser::parser::Parser;
+use crate::parser::parser::ParserState;
+use crate::parser::source::SourceFile;
+use crate::validate_document;
+use crate::validate_semantics;
+
+#[test]
+fn parser() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+Use {-old-}{+**new**+} and {+a \+} b+}.
+			"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Paragraph {
+			Text { content == "Use " };
+			Diff { kind == DiffKind::Delete } { Text { content == "old" }; };
+			Diff { kind == DiffKind::Insert } {
+				Style;
+				Text { content == "new" };
+				Style;
+			};
+			Text { content == " and " };
+			Diff { kind == DiffKind::Insert } { Text { content == "a +} b" }; };
+			Text { content == ". " };
+		};
+	);
+}
+
+#[test]
+fn semantic() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+{+a+} {-b-}
+			"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+	validate_semantics!(state, source.clone(), 0,
+		diff_insert_sep { delta_line == 1, delta_start == 0, length == 2 };
+		diff_insert_sep { delta_line == 0, delta_start == 3, length == 2 };
+		diff_delete_sep { delta_line == 0, delta_start == 3, length == 2 };
+		diff_delete_sep { delta_line == 0, delta_start == 3, length == 2 };
+	);
+}
@@ -4,9 +4,9 @@ use std::sync::Arc;
 
 use ariadne::Fmt;
 use document::document::Document;
+use lsp::elemstyle::ElemStyles;
 use lsp::semantic::Semantics;
 use lua::kernel::CTX;
-use mlua::Function;
 use mlua::Lua;
 use mlua::Value;
 use parser::parser::ParseMode;
@@ -16,6 +16,7 @@ use parser::source::Cursor;
 use parser::style::ElementStyle;
 use regex::Regex;
 
+use crate::lua::kernel::Binding;
 use crate::parser::reports::macros::*;
 use crate::parser::reports::*;
 use mlua::Error::BadArgument;
@@ -34,7 +35,7 @@ impl Default for ElemStyleRule {
 }
 
 /// Finds the json substring inside aother string
-fn json_substring(str: &str) -> Option<&str> {
+pub(crate) fn json_substring(str: &str) -> Option<&str> {
 	let mut in_string = false;
 	let mut brace_depth = 0;
 	let mut escaped = false;
@@ -56,6 +57,53 @@ fn json_substring(str: &str) -> Option<&str> {
 	None
 }
 
+impl ElemStyleRule {
+	/// Reports the fields of a style's json value that are not part of the style
+	fn validate_fields(
+		&self,
+		reports: &mut Vec<Report>,
+		state: &ParserState,
+		cursor: &Cursor,
+		json: &str,
+		style: &dyn ElementStyle,
+	) {
+		let (Ok(serde_json::Value::Object(fields)), serde_json::Value::Object(schema)) = (
+			serde_json::from_str::<serde_json::Value>(json),
+			style.to_json(),
+		) else {
+			return;
+		};
+
+		for name in fields.keys().filter(|name| !schema.contains_key(*name)) {
+			let quoted = format!("\"{name}\"");
+			let range = json
+				.find(quoted.as_str())
+				.map_or(0..json.len(), |pos| pos..pos + quoted.len());
+			report_warn!(
+				reports,
+				cursor.source.clone(),
+				"Unknown Style Field".into(),
+				span(
+					cursor.pos + range.start..cursor.pos + range.end,
+					format!(
+						"Style {} has no field {}, it will be ignored",
+						style.key().fg(state.parser.colors().info),
+						name.fg(state.parser.colors().highlight)
+					)
+				),
+				note(format!(
+					"Available fields: {}",
+					schema
+						.keys()
+						.map(|key| key.as_str())
+						.collect::<Vec<_>>()
+						.join(", ")
+				))
+			);
+		}
+	}
+}
+
 impl Rule for ElemStyleRule {
 	fn name(&self) -> &'static str { "Element Style" }
 
@@ -162,7 +210,15 @@ impl Rule for ElemStyleRule {
 							cursor = cursor.at(cursor.pos + json.len());
 							return (cursor, reports);
 						}
-						Ok(style) => {
+						Ok(new_style) => {
+							self.validate_fields(
+								&mut reports,
+								state,
+								&cursor,
+								json,
+								style.as_ref(),
+							);
+
 							if let Some((sems, tokens)) =
 								Semantics::from_source(cursor.source.clone(), &state.shared.lsp)
 							{
@@ -180,8 +236,17 @@ impl Rule for ElemStyleRule {
 								);
 							}
 
+							if let Some(elemstyles) =
+								ElemStyles::from_source(cursor.source.clone(), &state.shared.lsp)
+							{
+								elemstyles.add(
+									matches.get(0).unwrap().start()..cursor.pos + json.len(),
+									new_style.as_ref(),
+								);
+							}
+
 							cursor = cursor.at(cursor.pos + json.len());
-							style
+							new_style
 						}
 					}
 				}
@@ -192,11 +257,13 @@ impl Rule for ElemStyleRule {
 		(cursor, reports)
 	}
 
-	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
 		let mut bindings = vec![];
 
-		bindings.push((
-			"set".to_string(),
+		bindings.push(Binding::new(
+			"set",
+			"(style_key: string, style: table)",
+			"Sets the current value of element style `style_key`",
 			lua.create_function(|lua, (style_key, new_style): (String, Value)| {
 				let mut result = Ok(());
 				CTX.with_borrow(|ctx| {
@@ -228,6 +295,11 @@ impl Rule for ElemStyleRule {
 							Ok(new_style) => new_style,
 						};
 
+						if let Some(elemstyles) =
+							ElemStyles::from_source(ctx.location.source(), &ctx.state.shared.lsp)
+						{
+							elemstyles.add(ctx.location.range.clone(), new_style.as_ref());
+						}
 						ctx.state.shared.styles.borrow_mut().set_current(new_style);
 					})
 				});
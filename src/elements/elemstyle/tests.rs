@@ -1,9 +1,16 @@
+use std::cell::RefCell;
 use std::rc::Rc;
 
+use tower_lsp::lsp_types::Position;
+
+use crate::lsp::elemstyle::completion;
+use crate::lsp::elemstyle::effective_styles;
 use crate::parser::langparser::LangParser;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::Parser;
 use crate::parser::parser::ParserState;
+use crate::parser::reports::ReportKind;
+use crate::parser::source::Source;
 use crate::parser::source::SourceFile;
 use crate::validate_semantics;
 
@@ -38,3 +45,103 @@ fn semantics() {
 		elemstyle_value { delta_line == 1, delta_start == 0, length == 2 };
 	);
 }
+
+#[test]
+fn unknown_fields() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@@style.section = { "link_pos": "After", "colour": "red" }
+"#
+		.to_string(),
+		None,
+	));
+	let reports = RefCell::new(vec![]);
+	let parser = LangParser::new(
+		false,
+		Box::new(|_, list| {
+			for report in list {
+				let warning = matches!(report.kind, ReportKind::Warning);
+				reports.borrow_mut().push((
+					warning,
+					report.message,
+					report.spans[0].token.range.clone(),
+				));
+			}
+		}),
+	);
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+	drop(doc);
+	drop(parser);
+
+	let reports = reports.into_inner();
+	assert_eq!(reports.len(), 1);
+	assert!(reports[0].0);
+	assert_eq!(reports[0].1, "Unknown Style Field");
+	assert_eq!(reports[0].2, 42..50);
+}
+
+#[test]
+fn lsp() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"Text
+@@style.section = {
+	"link_pos": "After"
+}
+Text
+@@style.section = { "link_pos": "None" }
+Text
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+
+	let lsp = state.shared.lsp.as_ref().unwrap().borrow();
+	let assignments = lsp
+		.elemstyles
+		.get(&(source as Rc<dyn Source>))
+		.unwrap()
+		.assignments
+		.borrow();
+	assert_eq!(assignments.len(), 2);
+
+	let link_pos = |line| {
+		effective_styles(assignments.as_slice(), Position { line, character: 0 })
+			.into_iter()
+			.find(|style| style.key == "style.section")
+			.unwrap()
+			.value["link_pos"]
+			.clone()
+	};
+	assert_eq!(link_pos(0), "Before");
+	assert_eq!(link_pos(4), "After");
+	assert_eq!(link_pos(6), "None");
+}
+
+#[test]
+fn completions() {
+	let labels = |content: &str, line, character| {
+		completion(content, Position { line, character })
+			.into_iter()
+			.map(|item| item.label)
+			.collect::<Vec<_>>()
+	};
+
+	assert!(labels("@@sty", 0, 5).contains(&"style.section".to_string()));
+	assert!(labels("text\n@@style.section = {\n\t\"", 2, 2).contains(&"link_pos".to_string()));
+	assert!(labels("@@style.section = { \"", 0, 21).contains(&"link_hover".to_string()));
+	assert!(labels("@@style.section = {}\n\"", 1, 1).is_empty());
+}
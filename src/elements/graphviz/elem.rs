@@ -1,18 +1,23 @@
-use std::sync::Once;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
 
 use crypto::digest::Digest;
 use crypto::sha2::Sha512;
-use graphviz_rust::cmd::Format;
 use graphviz_rust::cmd::Layout;
-use graphviz_rust::exec_dot;
 
 use crate::cache::cache::Cached;
-use crate::cache::cache::CachedError;
 use crate::compiler::compiler::Compiler;
 use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
+use crate::compiler::svg::recolor;
 use crate::document::document::Document;
 use crate::document::element::ElemKind;
 use crate::document::element::Element;
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
 use crate::parser::source::Token;
 
 #[derive(Debug)]
@@ -21,17 +26,42 @@ pub struct Graphviz {
 	pub(crate) dot: String,
 	pub(crate) layout: Layout,
 	pub(crate) width: String,
+	pub(crate) env: String,
+	/// File the graph was read from, see the `src` property
+	///
+	/// The cache is keyed by the graph's content, so editing the file invalidates it.
+	pub(crate) source: Option<PathBuf>,
+	/// Whether to display the graph's source below the graph
+	pub(crate) keep_source: bool,
 }
 
 impl Graphviz {
-	/// Renders dot to svg
-	fn dot_to_svg(&self) -> Result<String, String> {
-		print!("Rendering Graphviz `{}`... ", self.dot);
-
-		let svg = match exec_dot(
-			self.dot.clone(),
-			vec![self.layout.into(), Format::Svg.into()],
-		) {
+	/// Renders dot to svg, using the `dot` executable at `exec`
+	fn dot_to_svg(&self, exec: &Path) -> Result<String, String> {
+		let run = || -> std::io::Result<Vec<u8>> {
+			let mut process = Command::new(exec)
+				.arg(format!("-K{}", format!("{:?}", self.layout).to_lowercase()))
+				.arg("-Tsvg")
+				.stdin(Stdio::piped())
+				.stdout(Stdio::piped())
+				.stderr(Stdio::piped())
+				.spawn()?;
+			let mut stdin = process.stdin.take().unwrap();
+			let dot = self.dot.clone();
+			let writer = std::thread::spawn(move || stdin.write_all(dot.as_bytes()));
+			let output = process.wait_with_output()?;
+			writer.join().unwrap()?;
+			if !output.status.success() {
+				return Err(std::io::Error::other(
+					String::from_utf8_lossy(&output.stderr)
+						.trim_end()
+						.to_string(),
+				));
+			}
+			Ok(output.stdout)
+		};
+
+		let svg = match run() {
 			Ok(svg) => {
 				let out = String::from_utf8_lossy(svg.as_slice());
 				let svg_start = out.find("<svg").unwrap(); // Remove svg header
@@ -42,12 +72,66 @@ impl Graphviz {
 
 				result
 			}
-			Err(e) => return Err(format!("Unable to execute dot: {e}")),
+			Err(e) => return Err(format!("Unable to execute `{}`: {e}", exec.display())),
 		};
-		println!("Done!");
+		Ok(svg)
+	}
 
+	/// Gets the svg of the graph from the cache, or renders it
+	///
+	/// Graphs that can't be rendered because `dot` is refused are displayed as source code.
+	fn render(&self, compiler: &Compiler) -> Result<String, String> {
+		// Cached graphs remain available when `dot` is refused
+		if let Some(con) = compiler.cache() {
+			match self.lookup(con) {
+				Ok(Some(svg)) => return Ok(svg),
+				Ok(None) => {}
+				Err(e) => return Err(format!("Querying the cache failed: {e}")),
+			}
+		}
+		let exec = match compiler.exec_policy().resolve("dot") {
+			Ok(exec) => exec,
+			Err(err) => {
+				let mut reports = vec![];
+				report_warn!(
+					&mut reports,
+					self.location.source(),
+					"External Command Refused".into(),
+					span(self.location.range.clone(), err),
+					note("The graph is displayed as source code".into())
+				);
+				compiler.report(reports);
+				return Ok(format!(
+					"<pre class=\"graphviz-placeholder\">{}</pre>",
+					Compiler::sanitize(HTML, &self.dot)
+				));
+			}
+		};
+
+		compiler.log(format!("Rendering Graphviz `{}`...", self.dot).as_str());
+		let svg = self.dot_to_svg(&exec)?;
+		if let Some(con) = compiler.cache() {
+			self.insert(con, &svg)
+				.map_err(|e| format!("Querying the cache failed: {e}"))?;
+		}
 		Ok(svg)
 	}
+
+	/// Gets the collapsible block displaying the graph's source
+	fn source_block(&self) -> String {
+		let title = self
+			.source
+			.as_ref()
+			.and_then(|path| path.file_name())
+			.map_or("Source".to_string(), |name| {
+				name.to_string_lossy().to_string()
+			});
+		format!(
+			"<details class=\"graphviz-source\"><summary>{}</summary><pre>{}</pre></details>",
+			Compiler::sanitize(HTML, title),
+			Compiler::sanitize(HTML, &self.dot)
+		)
+	}
 }
 
 impl Cached for Graphviz {
@@ -84,37 +168,39 @@ impl Element for Graphviz {
 	fn compile(
 		&self,
 		compiler: &Compiler,
-		_document: &dyn Document,
+		document: &dyn Document,
 		_cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
-				static CACHE_INIT: Once = Once::new();
-				CACHE_INIT.call_once(|| {
-					if let Some(con) = compiler.cache() {
-						if let Err(e) = Graphviz::init(con) {
-							eprintln!("Unable to create cache table: {e}");
-						}
-					}
-				});
+			HTML | SLIDES => {
 				// TODO: Format svg in a div
 
-				if let Some(con) = compiler.cache() {
-					match self.cached(con, |s| s.dot_to_svg()) {
-						Ok(s) => Ok(s),
-						Err(e) => match e {
-							CachedError::SqlErr(e) => {
-								Err(format!("Querying the cache failed: {e}"))
-							}
-							CachedError::GenErr(e) => Err(e),
-						},
-					}
-				} else {
-					match self.dot_to_svg() {
-						Ok(svg) => Ok(svg),
-						Err(e) => Err(e),
-					}
+				let svg = self.render(compiler)?;
+
+				// Colors
+				let mut result =
+					match document.get_variable(format!("graphviz.{}.color", self.env).as_str()) {
+						Some(color) => recolor(
+							// Transparent background
+							&svg.replacen(r#"<polygon fill="white""#, r#"<polygon fill="none""#, 1),
+							&color.to_string(),
+						),
+						None => svg,
+					};
+
+				// Graphs added to the store keep their width, their height follows
+				if let Some(link) = compiler.store_asset(document, result.as_bytes(), "svg")? {
+					result = format!(
+						r#"<img class="graphviz-image" src="{}" alt="" width="{}">"#,
+						Compiler::sanitize_url(HTML, link),
+						Compiler::sanitize_attribute(HTML, &self.width)
+					);
+				}
+
+				if self.keep_source {
+					result += self.source_block().as_str();
 				}
+				Ok(result)
 			}
 			_ => todo!("Unimplemented"),
 		}
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::document::document::Document;
+use crate::lua::kernel::Binding;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::ParserState;
 use crate::parser::property::PropertyParser;
@@ -9,13 +10,13 @@ use crate::parser::reports::macros::*;
 use crate::parser::reports::Report;
 use crate::parser::reports::*;
 use crate::parser::rule::RegexRule;
+use crate::parser::source::resolve_path;
 use crate::parser::source::Token;
 use ariadne::Fmt;
 use graphviz_rust::cmd::Layout;
 use lsp::semantic::Semantics;
 use lua::kernel::CTX;
 use mlua::Error::BadArgument;
-use mlua::Function;
 use mlua::Lua;
 use parser::property::Property;
 use parser::util::escape_source;
@@ -53,12 +54,28 @@ impl Default for GraphRule {
 			Property::new(
 				"Graphviz layout engine see <https://graphviz.org/docs/layouts/>".to_string(),
 				Some("dot".to_string()),
-			),
+			)
+			.primary(),
 		);
 		props.insert(
 			"width".to_string(),
 			Property::new("SVG width".to_string(), Some("100%".to_string())),
 		);
+		props.insert(
+			"env".to_string(),
+			Property::new("Graphviz environment".to_string(), Some("main".to_string())),
+		);
+		props.insert(
+			"src".to_string(),
+			Property::new("Path of a file to read the graph from".to_string(), None),
+		);
+		props.insert(
+			"keep_source".to_string(),
+			Property::new(
+				"Also display the graph's source in a collapsible block".to_string(),
+				Some("false".to_string()),
+			),
+		);
 		Self {
 			re: [Regex::new(
 				r"\[graph\](?:\[((?:\\.|[^\[\]\\])*?)\])?(?:((?:\\.|[^\\\\])*?)\[/graph\])?",
@@ -74,6 +91,10 @@ impl RegexRule for GraphRule {
 
 	fn previous(&self) -> Option<&'static str> { Some("Tex") }
 
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> {
+		vec![("Graphviz", &self.properties)]
+	}
+
 	fn regexes(&self) -> &[regex::Regex] { &self.re }
 
 	fn enabled(&self, mode: &ParseMode, _id: usize) -> bool { !mode.paragraph_only }
@@ -88,13 +109,13 @@ impl RegexRule for GraphRule {
 	) -> Vec<Report> {
 		let mut reports = vec![];
 
-		let graph_content = match matches.get(2) {
+		let mut graph_content = match matches.get(2) {
 			// Unterminated `[graph]`
 			None => {
 				report_err!(
 					&mut reports,
 					token.source(),
-					"Unterminamted Graph Code".into(),
+					"Unterminated Graph Code".into(),
 					span(
 						token.range.clone(),
 						format!(
@@ -106,20 +127,7 @@ impl RegexRule for GraphRule {
 				);
 				return reports;
 			}
-			Some(content) => {
-				let processed = escape_text('\\', "[/graph]", content.as_str(), true);
-
-				if processed.is_empty() {
-					report_err!(
-						&mut reports,
-						token.source(),
-						"Empty Graph Code".into(),
-						span(content.range(), "Graph code is empty".into())
-					);
-					return reports;
-				}
-				processed
-			}
+			Some(content) => escape_text('\\', "[/graph]", content.as_str(), true),
 		};
 
 		// Properties
@@ -138,17 +146,90 @@ impl RegexRule for GraphRule {
 				Some(props) => props,
 				None => return reports,
 			};
-		let (graph_layout, graph_width) = match (
+		let (graph_layout, graph_width, graph_env) = match (
 			properties.get(&mut reports, "layout", |_, value| {
 				layout_from_str(value.value.as_str())
 			}),
 			properties.get(&mut reports, "width", |_, value| {
 				Result::<_, String>::Ok(value.value.clone())
 			}),
+			properties.get(&mut reports, "env", |_, value| {
+				Result::<_, String>::Ok(value.value.clone())
+			}),
 		) {
-			(Some(graph_layout), Some(graph_width)) => (graph_layout, graph_width),
+			(Some(graph_layout), Some(graph_width), Some(graph_env)) => {
+				(graph_layout, graph_width, graph_env)
+			}
 			_ => return reports,
 		};
+		let (graph_src, keep_source) = match (
+			properties.get_opt(&mut reports, "src", |_, value| {
+				Result::<_, String>::Ok((value.value.clone(), value.value_range.clone()))
+			}),
+			properties.get(&mut reports, "keep_source", |_, value| {
+				value.value.parse::<bool>()
+			}),
+		) {
+			(Some(graph_src), Some(keep_source)) => (graph_src, keep_source),
+			_ => return reports,
+		};
+
+		// Graph file
+		let mut source = None;
+		if let Some((path, range)) = graph_src {
+			if !graph_content.is_empty() {
+				report_err!(
+					&mut reports,
+					token.source(),
+					"Invalid Graph Code".into(),
+					span(
+						matches.get(2).unwrap().range(),
+						format!(
+							"Graphs read from a file with {} must be empty",
+							"src".fg(state.parser.colors().info)
+						)
+					)
+				);
+				return reports;
+			}
+
+			match std::fs::canonicalize(resolve_path(&token.source(), &path))
+				.and_then(|path| std::fs::read_to_string(&path).map(|content| (path, content)))
+			{
+				Ok((path, content)) => {
+					graph_content = content;
+					source = Some(path);
+				}
+				Err(err) => {
+					report_err!(
+						&mut reports,
+						token.source(),
+						"Invalid Graph Source".into(),
+						span(
+							range,
+							format!(
+								"Unable to read file `{}`: {err}",
+								path.fg(state.parser.colors().highlight)
+							)
+						)
+					);
+					return reports;
+				}
+			}
+		}
+
+		if graph_content.is_empty() {
+			report_err!(
+				&mut reports,
+				token.source(),
+				"Empty Graph Code".into(),
+				span(
+					matches.get(2).unwrap().range(),
+					"Graph code is empty".into()
+				)
+			);
+			return reports;
+		}
 
 		state.push(
 			document,
@@ -157,6 +238,9 @@ impl RegexRule for GraphRule {
 				dot: graph_content,
 				layout: graph_layout,
 				width: graph_width,
+				env: graph_env,
+				source,
+				keep_source,
 			}),
 		);
 
@@ -174,10 +258,12 @@ impl RegexRule for GraphRule {
 		reports
 	}
 
-	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
 		let mut bindings = vec![];
-		bindings.push((
-			"push".to_string(),
+		bindings.push(Binding::new(
+			"push",
+			"(layout: string, width: string, dot: string)",
+			"Inserts a Graphviz graph, rendered using layout `layout`",
 			lua.create_function(|_, (layout, width, dot): (String, String, String)| {
 				let mut result = Ok(());
 
@@ -205,6 +291,9 @@ impl RegexRule for GraphRule {
 								dot,
 								layout,
 								width,
+								env: "main".to_string(),
+								source: None,
+								keep_source: false,
 							}),
 						);
 					})
@@ -1,11 +1,15 @@
 use std::rc::Rc;
 
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target;
+use crate::compiler::exec::ExecPolicy;
 use crate::elements::graphviz::elem::Graphviz;
 use crate::parser::langparser::LangParser;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::Parser;
 use crate::parser::parser::ParserState;
 use crate::parser::source::SourceFile;
+use crate::tempdir::TempDir;
 use crate::validate_document;
 use crate::validate_semantics;
 
@@ -14,7 +18,7 @@ pub fn parse() {
 	let source = Rc::new(SourceFile::with_content(
 		"".to_string(),
 		r#"
-[graph][width=200px, layout=neato]
+[graph][width=200px, layout=neato, env=dark]
 Some graph...
 [/graph]
 [graph]
@@ -33,8 +37,8 @@ Another graph
 	);
 
 	validate_document!(doc.content().borrow(), 0,
-		Graphviz { width == "200px", dot == "Some graph..." };
-		Graphviz { dot == "Another graph" };
+		Graphviz { width == "200px", dot == "Some graph...", env == "dark" };
+		Graphviz { dot == "Another graph", env == "main" };
 	);
 }
 
@@ -96,3 +100,49 @@ digraph {
 		graph_sep { delta_line == 1, delta_start == 0, length == 8 };
 	);
 }
+
+#[test]
+fn source() {
+	let dir = TempDir::new("graphviz-src");
+	let path = dir.join("graph.dot");
+	std::fs::write(&path, "digraph { a -> b }\n").unwrap();
+
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		format!(
+			r#"
+[graph][src={0}, keep_source=true][/graph]
+[graph][src={0}]Not empty[/graph]
+[graph][keep_source]digraph {{ <c> }}[/graph]
+"#,
+			path.display()
+		),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Graphviz { dot == "digraph { a -> b }\n", keep_source == true };
+		Graphviz { dot == "digraph { <c> }", keep_source == true };
+	);
+
+	let exec = ExecPolicy {
+		disabled: true,
+		..Default::default()
+	};
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	let filename = path.file_name().unwrap().to_string_lossy();
+	assert!(compiled.body.contains(
+		format!("<details class=\"graphviz-source\"><summary>{filename}</summary><pre>digraph {{ a -&gt; b }}\n</pre></details>").as_str()
+	));
+	assert!(compiled.body.contains(
+		"<details class=\"graphviz-source\"><summary>Source</summary><pre>digraph { &lt;c&gt; }</pre></details>"
+	));
+}
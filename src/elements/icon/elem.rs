@@ -0,0 +1,148 @@
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::LATEX;
+use crate::compiler::compiler::Target::SLIDES;
+use crate::document::document::Document;
+use crate::document::element::ElemKind;
+use crate::document::element::Element;
+use crate::parser::source::Token;
+
+/// Icons bundled with nml, as the content of a `0 0 24 24` svg drawn with strokes
+pub const BUNDLED_ICONS: &[(&str, &str)] = &[
+	("arrow-left", r#"<path d="M19 12H5M11 6l-6 6 6 6"/>"#),
+	("arrow-right", r#"<path d="M5 12h14M13 6l6 6-6 6"/>"#),
+	("check", r#"<path d="M4 12l5 5L20 6"/>"#),
+	("download", r#"<path d="M12 4v12M6 10l6 6 6-6M4 20h16"/>"#),
+	(
+		"error",
+		r#"<circle cx="12" cy="12" r="9"/><path d="M9 9l6 6M15 9l-6 6"/>"#,
+	),
+	(
+		"external-link",
+		r#"<path d="M14 4h6v6M20 4l-9 9"/><path d="M18 14v5a1 1 0 0 1-1 1H5a1 1 0 0 1-1-1V7a1 1 0 0 1 1-1h5"/>"#,
+	),
+	(
+		"file",
+		r#"<path d="M14 3H6a1 1 0 0 0-1 1v16a1 1 0 0 0 1 1h12a1 1 0 0 0 1-1V8z"/><path d="M14 3v5h5"/>"#,
+	),
+	(
+		"info",
+		r#"<circle cx="12" cy="12" r="9"/><path d="M12 11v6M12 7h.01"/>"#,
+	),
+	(
+		"link",
+		r#"<path d="M10 14a4 4 0 0 0 5.66 0l3-3a4 4 0 0 0-5.66-5.66l-1 1"/><path d="M14 10a4 4 0 0 0-5.66 0l-3 3a4 4 0 0 0 5.66 5.66l1-1"/>"#,
+	),
+	("minus", r#"<path d="M5 12h14"/>"#),
+	("plus", r#"<path d="M12 5v14M5 12h14"/>"#),
+	(
+		"search",
+		r#"<circle cx="11" cy="11" r="7"/><path d="M16 16l5 5"/>"#,
+	),
+	(
+		"star",
+		r#"<path d="M12 3l2.8 5.7 6.2.9-4.5 4.4 1.1 6.2L12 17.3 6.4 20.2l1.1-6.2L3 9.6l6.2-.9z"/>"#,
+	),
+	("terminal", r#"<path d="M4 17l6-5-6-5M12 19h8"/>"#),
+	(
+		"warning",
+		r#"<path d="M12 3L2 20h20L12 3z"/><path d="M12 10v4M12 17h.01"/>"#,
+	),
+	("x", r#"<path d="M6 6l12 12M18 6L6 18"/>"#),
+];
+
+/// Drawing of an icon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconSvg {
+	/// Value of the svg's `viewBox` attribute
+	pub view_box: String,
+	/// Content of the svg
+	pub content: String,
+}
+
+impl IconSvg {
+	/// Gets a bundled icon by name, see [`BUNDLED_ICONS`]
+	pub fn bundled(name: &str) -> Option<Self> {
+		BUNDLED_ICONS
+			.iter()
+			.find(|(icon, _)| *icon == name)
+			.map(|(_, content)| Self {
+				view_box: "0 0 24 24".into(),
+				content: format!(
+					"<g fill=\"none\" stroke=\"currentColor\" stroke-width=\"2\" stroke-linecap=\"round\" stroke-linejoin=\"round\">{content}</g>"
+				),
+			})
+	}
+
+	/// Gets an icon by name from a sprite sheet: an svg made of `<symbol id="NAME">` elements
+	pub fn from_sprite(sprite: &str, name: &str) -> Option<Self> {
+		let re = Regex::new(r#"(?s)<symbol\b([^>]*)>(.*?)</symbol>"#).unwrap();
+		let id = Regex::new(r#"\bid\s*=\s*"([^"]*)""#).unwrap();
+		let view_box = Regex::new(r#"\bviewBox\s*=\s*"([^"]*)""#).unwrap();
+		let symbol = re.captures_iter(sprite).find(|symbol| {
+			id.captures(symbol.get(1).unwrap().as_str())
+				.is_some_and(|id| id.get(1).unwrap().as_str() == name)
+		});
+		symbol.map(|symbol| Self {
+			view_box: view_box
+				.captures(symbol.get(1).unwrap().as_str())
+				.map_or("0 0 24 24".into(), |view_box| {
+					view_box.get(1).unwrap().as_str().to_string()
+				}),
+			content: symbol.get(2).unwrap().as_str().trim().to_string(),
+		})
+	}
+}
+
+#[derive(Debug)]
+pub struct Icon {
+	pub(crate) location: Token,
+	/// Name of the icon
+	pub(crate) name: String,
+	pub(crate) svg: IconSvg,
+	/// Width and height of the icon, as a CSS length
+	pub(crate) size: String,
+	/// Color of the icon, as a CSS color
+	pub(crate) color: Option<String>,
+	/// Sprite sheet the icon comes from, for bundled icons this is `None`
+	pub(crate) sprite: Option<String>,
+}
+
+impl Element for Icon {
+	fn location(&self) -> &Token { &self.location }
+	fn kind(&self) -> ElemKind { ElemKind::Inline }
+	fn element_name(&self) -> &'static str { "Icon" }
+	fn compile(
+		&self,
+		compiler: &Compiler,
+		_document: &dyn Document,
+		_cursor: usize,
+	) -> Result<String, String> {
+		if let Some(sprite) = &self.sprite {
+			compiler.add_dependency(Path::new(sprite));
+		}
+
+		match compiler.target() {
+			HTML | SLIDES => {
+				let size = Compiler::sanitize_attribute(compiler.target(), &self.size);
+				let style = self.color.as_ref().map_or(String::new(), |color| {
+					format!(
+						" style=\"color:{}\"",
+						Compiler::sanitize_attribute(compiler.target(), color)
+					)
+				});
+				Ok(format!(
+					"<svg class=\"icon icon-{}\" xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{}\" width=\"{size}\" height=\"{size}\"{style} aria-hidden=\"true\">{}</svg>",
+					Compiler::sanitize_attribute(compiler.target(), &self.name),
+					Compiler::sanitize_attribute(compiler.target(), &self.svg.view_box),
+					self.svg.content
+				))
+			}
+			LATEX => Err(format!("Icon `{}` can't be rendered in LaTeX", self.name)),
+		}
+	}
+}
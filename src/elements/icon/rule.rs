@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
+use ariadne::Fmt;
+use document::document::Document;
+use lsp::semantic::Semantics;
+use parser::parser::ParseMode;
+use parser::parser::ParserState;
+use parser::property::Property;
+use parser::property::PropertyParser;
+use parser::rule::RegexRule;
+use parser::source::resolve_path;
+use parser::source::Token;
+use parser::util::escape_source;
+use regex::Captures;
+use regex::Regex;
+
+use super::elem::Icon;
+use super::elem::IconSvg;
+use super::elem::BUNDLED_ICONS;
+
+#[auto_registry::auto_registry(registry = "rules")]
+pub struct IconRule {
+	re: [Regex; 1],
+	properties: PropertyParser,
+}
+
+impl Default for IconRule {
+	fn default() -> Self {
+		let mut props = HashMap::new();
+		props.insert(
+			"size".to_string(),
+			Property::new(
+				"Width and height of the icon, as a CSS length".to_string(),
+				Some("1em".to_string()),
+			)
+			.primary(),
+		);
+		props.insert(
+			"color".to_string(),
+			Property::new(
+				"Color of the icon, as a CSS color. Defaults to the color of the text".to_string(),
+				None,
+			),
+		);
+		Self {
+			re: [Regex::new(r":icon\{([^}\n]*)\}(?:\[((?:\\.|[^\[\]\\])*?)\])?").unwrap()],
+			properties: PropertyParser { properties: props },
+		}
+	}
+}
+
+impl RegexRule for IconRule {
+	fn name(&self) -> &'static str { "Icon" }
+
+	fn previous(&self) -> Option<&'static str> { Some("Break") }
+
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> {
+		vec![("Icon", &self.properties)]
+	}
+
+	fn regexes(&self) -> &[Regex] { &self.re }
+
+	fn enabled(&self, _mode: &ParseMode, _id: usize) -> bool { true }
+
+	fn on_regex_match<'a>(
+		&self,
+		_: usize,
+		state: &ParserState,
+		document: &'a (dyn Document<'a> + 'a),
+		token: Token,
+		matches: Captures,
+	) -> Vec<Report> {
+		let mut reports = vec![];
+
+		let name = matches.get(1).unwrap();
+
+		// Properties
+		let prop_source = escape_source(
+			token.source(),
+			matches.get(2).map_or(0..0, |m| m.range()),
+			"Icon Properties".into(),
+			'\\',
+			"]",
+		);
+		let properties = self.properties.parse(
+			"Icon",
+			&mut reports,
+			state,
+			Token::new(0..prop_source.content().len(), prop_source),
+		);
+		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
+			sems.add(token.start()..name.start(), tokens.icon_sep);
+			if !name.as_str().is_empty() {
+				sems.add(name.range(), tokens.icon_name);
+			}
+			sems.add(name.end()..name.end() + 1, tokens.icon_sep);
+			if let Some(props) = matches.get(2).map(|m| m.range()) {
+				sems.add(props.start - 1..props.start, tokens.icon_props_sep);
+				sems.add(props.end..props.end + 1, tokens.icon_props_sep);
+			}
+		}
+
+		let properties = match properties {
+			Some(props) => props,
+			None => return reports,
+		};
+		let (size, color) = match (
+			properties.get(&mut reports, "size", |_, value| {
+				Result::<_, String>::Ok(value.value.clone())
+			}),
+			properties.get_opt(&mut reports, "color", |_, value| {
+				Result::<_, String>::Ok(value.value.clone())
+			}),
+		) {
+			(Some(size), Some(color)) => (size, color),
+			_ => return reports,
+		};
+
+		// Icons from the project's sprite sheet take precedence over bundled icons
+		let sprite = document
+			.get_variable("icon.sprite")
+			.map(|var| var.to_string());
+		let mut svg = None;
+		let mut sprite_path = None;
+		if let Some(path) = &sprite {
+			match std::fs::canonicalize(resolve_path(&token.source(), path))
+				.and_then(|path| std::fs::read_to_string(&path).map(|content| (path, content)))
+			{
+				Ok((path, content)) => {
+					svg = IconSvg::from_sprite(&content, name.as_str().trim());
+					sprite_path = svg.as_ref().map(|_| path.to_string_lossy().to_string());
+				}
+				Err(err) => {
+					report_err!(
+						&mut reports,
+						token.source(),
+						"Invalid Icon Sprite".into(),
+						span(
+							token.range.clone(),
+							format!(
+								"Unable to read sprite sheet `{}`: {err}",
+								path.fg(state.parser.colors().info)
+							)
+						)
+					);
+					return reports;
+				}
+			}
+		}
+		let Some(svg) = svg.or_else(|| IconSvg::bundled(name.as_str().trim())) else {
+			report_err!(
+				&mut reports,
+				token.source(),
+				"Unknown Icon".into(),
+				span(
+					name.range(),
+					format!(
+						"Icon `{}` is neither bundled nor in the sprite sheet",
+						name.as_str().fg(state.parser.colors().highlight)
+					)
+				),
+				note(format!(
+					"Bundled icons: {}",
+					BUNDLED_ICONS
+						.iter()
+						.map(|(name, _)| *name)
+						.collect::<Vec<_>>()
+						.join(", ")
+				))
+			);
+			return reports;
+		};
+
+		state.push(
+			document,
+			Box::new(Icon {
+				location: token,
+				name: name.as_str().trim().to_string(),
+				svg,
+				size,
+				color,
+				sprite: sprite_path,
+			}),
+		);
+
+		reports
+	}
+}
@@ -0,0 +1,129 @@
+use std::rc::Rc;
+
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target;
+use crate::compiler::exec::ExecPolicy;
+use crate::elements::icon::elem::Icon;
+use crate::elements::icon::elem::IconSvg;
+use crate::elements::paragraph::elem::Paragraph;
+use crate::elements::text::elem::Text;
+use crate::parser::langparser::LangParser;
+use crate::parser::parser::ParseMode;
+use crate::parser::parser::Parser;
+use crate::parser::parser::ParserState;
+use crate::parser::source::SourceFile;
+use crate::validate_document;
+use crate::validate_semantics;
+
+#[test]
+fn parser() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+Done :icon{check} and :icon{ warning }[size=2em, color=#f80].
+			"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Paragraph {
+			Text { content == "Done " };
+			Icon { name == "check", size == "1em", color == None::<String>, sprite == None::<String> };
+			Text { content == " and " };
+			Icon { name == "warning", size == "2em", color == Some("#f80".to_string()) };
+			Text { content == ". " };
+		};
+	);
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled.body.contains(
+		"<svg class=\"icon icon-warning\" xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 24 24\" width=\"2em\" height=\"2em\" style=\"color:#f80\" aria-hidden=\"true\"><g fill=\"none\""
+	));
+}
+
+#[test]
+fn unknown() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		":icon{unknown}".to_string(),
+		None,
+	));
+	let reports = std::cell::RefCell::new(vec![]);
+	let parser = LangParser::new(
+		false,
+		Box::new(|_, list| {
+			reports
+				.borrow_mut()
+				.extend(list.into_iter().map(|report| report.message))
+		}),
+	);
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+	drop(doc);
+	drop(parser);
+	assert_eq!(reports.into_inner(), vec!["Unknown Icon".to_string()]);
+}
+
+#[test]
+fn sprite() {
+	let sprite = r#"<svg xmlns="http://www.w3.org/2000/svg">
+	<symbol id="logo" viewBox="0 0 16 16"><circle cx="8" cy="8" r="8"/></symbol>
+	<symbol viewBox="0 0 10 10" id="dot">
+		<rect width="10" height="10"/>
+	</symbol>
+</svg>"#;
+	assert_eq!(
+		IconSvg::from_sprite(sprite, "logo"),
+		Some(IconSvg {
+			view_box: "0 0 16 16".into(),
+			content: r#"<circle cx="8" cy="8" r="8"/>"#.into(),
+		})
+	);
+	assert_eq!(
+		IconSvg::from_sprite(sprite, "dot"),
+		Some(IconSvg {
+			view_box: "0 0 10 10".into(),
+			content: r#"<rect width="10" height="10"/>"#.into(),
+		})
+	);
+	assert_eq!(IconSvg::from_sprite(sprite, "check"), None);
+}
+
+#[test]
+fn semantic() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+:icon{check}[size=2em]
+			"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+	validate_semantics!(state, source.clone(), 0,
+		icon_sep { delta_line == 1, delta_start == 0, length == 6 };
+		icon_name { delta_line == 0, delta_start == 6, length == 5 };
+		icon_sep { delta_line == 0, delta_start == 5, length == 1 };
+		icon_props_sep { delta_line == 0, delta_start == 1, length == 1 };
+	);
+}
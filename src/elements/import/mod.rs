@@ -1 +1,3 @@
 pub mod rule;
+#[cfg(test)]
+pub mod tests;
@@ -10,6 +10,8 @@ use parser::parser::ParseMode;
 use parser::parser::ParserState;
 use parser::parser::ReportColors;
 use parser::rule::RegexRule;
+use parser::source::resolve_path;
+use parser::source::Source;
 use parser::source::SourceFile;
 use parser::source::Token;
 use regex::Captures;
@@ -59,6 +61,29 @@ impl RegexRule for ImportRule {
 	) -> Vec<Report> {
 		let mut reports = vec![];
 
+		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
+			// @import
+			let import =
+				if token.source().content().as_bytes()[matches.get(0).unwrap().start()] == b'\n' {
+					matches.get(0).unwrap().start() + 1
+				} else {
+					matches.get(0).unwrap().start()
+				};
+			sems.add(import..import + 7, tokens.import_import);
+
+			if let Some(import_as) = matches.get(1) {
+				sems.add(
+					import_as.start() - 1..import_as.start(),
+					tokens.import_as_sep,
+				);
+				sems.add(import_as.range(), tokens.import_as);
+				sems.add(import_as.end()..import_as.end() + 1, tokens.import_as_sep);
+			}
+
+			let path = matches.get(2).unwrap().range();
+			sems.add(path, tokens.import_path);
+		}
+
 		// Path
 		let import_file = match matches.get(2) {
 			Some(name) => match validate_name(state.parser.colors(), name.as_str()) {
@@ -79,7 +104,8 @@ impl RegexRule for ImportRule {
 					return reports;
 				}
 				Ok(filename) => {
-					let meta = match std::fs::metadata(filename.as_str()) {
+					let path = resolve_path(&token.source(), filename.as_str());
+					let meta = match std::fs::metadata(&path) {
 						Err(_) => {
 							report_err!(
 								&mut reports,
@@ -114,7 +140,7 @@ impl RegexRule for ImportRule {
 						return reports;
 					}
 
-					filename
+					path.to_string_lossy().to_string()
 				}
 			},
 			_ => panic!("Invalid name for import"),
@@ -167,8 +193,49 @@ impl RegexRule for ImportRule {
 				Some(document),
 				ParseMode::default(),
 			);
+
+			// Exported variables shadowing existing variables
+			for name in import_doc.scope().borrow().exports.iter() {
+				let merged_name = if import_as.is_empty() {
+					name.clone()
+				} else {
+					format!("{import_as}.{name}")
+				};
+				if let Some(shadowed) = document.get_variable(merged_name.as_str()) {
+					report_warn!(
+						&mut reports,
+						token.source(),
+						"Shadowed Variable".into(),
+						span(
+							token.range.clone(),
+							format!(
+								"Importing `{}` shadows variable `{}`",
+								import.name().fg(state.parser.colors().info),
+								merged_name.fg(state.parser.colors().highlight)
+							)
+						),
+						span(
+							shadowed.location().source(),
+							shadowed.location().range.clone(),
+							"Previously defined here".into()
+						),
+						help(format!(
+							"Use `{}` to import the variables under a prefix",
+							"@import[prefix] <PATH>".fg(state.parser.colors().info)
+						))
+					);
+				}
+			}
+
 			document.merge(import_doc.content(), import_doc.scope(), Some(&import_as));
 		});
+		if let Ok(path) = std::fs::canonicalize(import.name()) {
+			document
+				.scope()
+				.borrow_mut()
+				.imports
+				.insert(path.to_string_lossy().to_string());
+		}
 
 		// Close paragraph
 		// TODO2: Check if this is safe to remove
@@ -182,29 +249,6 @@ impl RegexRule for ImportRule {
 			);
 		}
 
-		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
-			// @import
-			let import =
-				if token.source().content().as_bytes()[matches.get(0).unwrap().start()] == b'\n' {
-					matches.get(0).unwrap().start() + 1
-				} else {
-					matches.get(0).unwrap().start()
-				};
-			sems.add(import..import + 7, tokens.import_import);
-
-			if let Some(import_as) = matches.get(1) {
-				sems.add(
-					import_as.start() - 1..import_as.start(),
-					tokens.import_as_sep,
-				);
-				sems.add(import_as.range(), tokens.import_as);
-				sems.add(import_as.end()..import_as.end() + 1, tokens.import_as_sep);
-			}
-
-			let path = matches.get(2).unwrap().range();
-			sems.add(path, tokens.import_path);
-		}
-
 		// Definition point to start of imported document
 		definition::from_source(token, &Token::new(0..0, import), &state.shared.lsp);
 
@@ -0,0 +1,105 @@
+use std::rc::Rc;
+
+use crate::parser::langparser::LangParser;
+use crate::parser::parser::ParseMode;
+use crate::parser::parser::Parser;
+use crate::parser::parser::ParserState;
+use crate::parser::source::SourceFile;
+use crate::tempdir::TempDir;
+use crate::validate_semantics;
+
+#[test]
+fn semantic() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@import[x] missing.nml
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+	// Imports are highlighted even when the file cannot be found
+	validate_semantics!(state, source.clone(), 0,
+	import_import { delta_line == 1, delta_start == 0, length == 7 };
+	import_as_sep { delta_line == 0, delta_start == 7, length == 1 };
+	import_as { delta_line == 0, delta_start == 1, length == 1 };
+	import_as_sep { delta_line == 0, delta_start == 1, length == 1 };
+	import_path { delta_line == 0, delta_start == 2, length == 11 };
+	);
+}
+
+#[test]
+fn imports() {
+	let root = TempDir::new("imports");
+	std::fs::write(root.join("a.nml"), "@import b.nml").unwrap();
+	std::fs::write(root.join("b.nml"), "B").unwrap();
+
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		format!("@import {}", root.join("a.nml").display()),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let mut imports = doc
+		.scope()
+		.borrow()
+		.imports
+		.iter()
+		.cloned()
+		.collect::<Vec<_>>();
+	imports.sort();
+	assert_eq!(
+		imports,
+		vec![
+			root.join("a.nml").to_string_lossy().to_string(),
+			root.join("b.nml").to_string_lossy().to_string(),
+		]
+	);
+}
+
+#[test]
+fn relative_paths() {
+	let root = TempDir::new("imports-relative");
+	std::fs::create_dir(root.join("lib")).unwrap();
+	std::fs::write(root.join("doc.nml"), "@import lib/a.nml").unwrap();
+	std::fs::write(root.join("lib/a.nml"), "@import b.nml").unwrap();
+	std::fs::write(root.join("lib/b.nml"), "B").unwrap();
+	let cwd = std::env::current_dir().unwrap();
+
+	// Paths are relative to the importing file, so parses don't depend on the working directory
+	let parses = (0..4)
+		.map(|_| {
+			let path = root.join("doc.nml").to_string_lossy().to_string();
+			std::thread::spawn(move || {
+				let parser = LangParser::default();
+				let (doc, _) = parser.parse(
+					ParserState::new(&parser, None),
+					Rc::new(SourceFile::new(path, None).unwrap()),
+					None,
+					ParseMode::default(),
+				);
+				assert!(!parser.has_error());
+				let imports = doc.scope().borrow().imports.len();
+				imports
+			})
+		})
+		.collect::<Vec<_>>();
+	for parse in parses {
+		assert_eq!(parse.join().unwrap(), 2);
+	}
+	assert_eq!(std::env::current_dir().unwrap(), cwd);
+}
@@ -1,5 +1,6 @@
 use crate::compiler::compiler::Compiler;
 use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
 use crate::document::document::Document;
 use crate::parser::parser::ParserState;
 use crate::parser::property::Property;
@@ -55,6 +56,8 @@ impl Default for Centered {
 impl LayoutType for Centered {
 	fn name(&self) -> &'static str { "Centered" }
 
+	fn properties(&self) -> Option<&PropertyParser> { Some(&self.0) }
+
 	fn expects(&self) -> Range<usize> { 1..1 }
 
 	fn parse_properties(
@@ -87,7 +90,7 @@ impl LayoutType for Centered {
 		_document: &dyn Document,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let style = match properties.downcast_ref::<String>().unwrap().as_str() {
 					"" => "".to_string(),
 					str => format!(r#" style={}"#, Compiler::sanitize(compiler.target(), str)),
@@ -125,6 +128,8 @@ impl Default for Split {
 impl LayoutType for Split {
 	fn name(&self) -> &'static str { "Split" }
 
+	fn properties(&self) -> Option<&PropertyParser> { Some(&self.0) }
+
 	fn expects(&self) -> Range<usize> { 2..usize::MAX }
 
 	fn parse_properties(
@@ -157,16 +162,23 @@ impl LayoutType for Split {
 		_document: &dyn Document,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let style = match properties.downcast_ref::<String>().unwrap().as_str() {
 					"" => "".to_string(),
 					str => format!(r#" style={}"#, Compiler::sanitize(compiler.target(), str)),
 				};
+				// Following splits are revealed one by one in slides
+				let fragment = match compiler.target() {
+					SLIDES => " fragment",
+					_ => "",
+				};
 				match token {
 					LayoutToken::Begin => Ok(format!(
 						r#"<div class="split-container"><div class="split"{style}>"#
 					)),
-					LayoutToken::Next => Ok(format!(r#"</div><div class="split"{style}>"#)),
+					LayoutToken::Next => {
+						Ok(format!(r#"</div><div class="split{fragment}"{style}>"#))
+					}
 					LayoutToken::End => Ok(r#"</div></div>"#.to_string()),
 				}
 			}
@@ -184,7 +196,7 @@ impl Default for Spoiler {
 		let mut properties = HashMap::new();
 		properties.insert(
 			"title".to_string(),
-			Property::new("Spoiler title".to_string(), Some("".to_string())),
+			Property::new("Spoiler title".to_string(), Some("".to_string())).primary(),
 		);
 
 		Self(PropertyParser { properties })
@@ -194,6 +206,8 @@ impl Default for Spoiler {
 impl LayoutType for Spoiler {
 	fn name(&self) -> &'static str { "Spoiler" }
 
+	fn properties(&self) -> Option<&PropertyParser> { Some(&self.0) }
+
 	fn expects(&self) -> Range<usize> { 1..1 }
 
 	fn parse_properties(
@@ -223,17 +237,22 @@ impl LayoutType for Spoiler {
 		_id: usize,
 		properties: &Box<dyn Any>,
 		compiler: &Compiler,
-		_document: &dyn Document,
+		document: &dyn Document,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let title = properties.downcast_ref::<String>().unwrap();
 				match token {
-					LayoutToken::Begin => Ok(format!(
-						r#"<details class="spoiler"><summary>{}</summary>"#,
-						Compiler::sanitize(compiler.target(), title)
-					)),
-					LayoutToken::End => Ok(r#"</details>"#.to_string()),
+					LayoutToken::Begin => {
+						// The summary is focusable and toggles the content, which is labelled by the summary
+						let id = compiler.unique_id(document, "spoiler");
+						Ok(format!(
+							r#"<details class="spoiler" id="{id}"><summary id="{id}-summary" aria-controls="{id}-content" aria-label="Spoiler: {}">{}</summary><div id="{id}-content" role="region" aria-labelledby="{id}-summary">"#,
+							Compiler::sanitize_attribute(compiler.target(), title),
+							Compiler::sanitize(compiler.target(), title)
+						))
+					}
+					LayoutToken::End => Ok(r#"</div></details>"#.to_string()),
 					_ => panic!(),
 				}
 			}
@@ -6,6 +6,7 @@ use std::rc::Rc;
 use crate::compiler::compiler::Compiler;
 use crate::document::document::Document;
 use crate::parser::parser::ParserState;
+use crate::parser::property::PropertyParser;
 use crate::parser::reports::Report;
 use crate::parser::source::Token;
 
@@ -24,6 +25,9 @@ pub trait LayoutType: core::fmt::Debug {
 		token: Token,
 	) -> Option<Box<dyn Any>>;
 
+	/// Gets the property schema of this layout, if it has properties
+	fn properties(&self) -> Option<&PropertyParser> { None }
+
 	/// Expected number of blocks
 	fn expects(&self) -> Range<usize>;
 
@@ -43,11 +47,14 @@ pub struct LayoutHolder {
 }
 
 macro_rules! create_layouts {
-	( $($construct:expr),+ $(,)? ) => {{
+	( $( $(@cfg($cfg:meta))? $construct:expr ),+ $(,)? ) => {{
 		let mut map = HashMap::new();
 		$(
-			let val = Rc::new($construct) as Rc<dyn LayoutType>;
-			map.insert(val.name().to_string(), val);
+			$(#[cfg($cfg)])?
+			{
+				let val = Rc::new($construct) as Rc<dyn LayoutType>;
+				map.insert(val.name().to_string(), val);
+			}
 		)+
 		map
 	}};
@@ -70,4 +77,7 @@ impl LayoutHolder {
 	pub fn insert(&mut self, layout: Rc<dyn LayoutType>) {
 		self.layouts.insert(layout.name().into(), layout);
 	}
+
+	/// Iterates over all registered [`LayoutType`]
+	pub fn iter(&self) -> impl Iterator<Item = &Rc<dyn LayoutType>> { self.layouts.values() }
 }
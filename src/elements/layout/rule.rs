@@ -4,15 +4,16 @@ use std::rc::Rc;
 use std::str::FromStr;
 use std::sync::Arc;
 
+use crate::lua::kernel::Binding;
 use crate::parser::reports::macros::*;
 use crate::parser::reports::*;
 use ariadne::Fmt;
 use document::document::Document;
 use lsp::hints::Hints;
+use lsp::hover::Hover;
 use lsp::semantic::Semantics;
 use lua::kernel::CTX;
 use mlua::Error::BadArgument;
-use mlua::Function;
 use mlua::Lua;
 use parser::parser::ParseMode;
 use parser::parser::ParserState;
@@ -71,6 +72,52 @@ pub fn parse_properties<'a>(
 	)
 }
 
+/// Adds the documentation of a layout delimiter to the language server's hovers
+fn add_hover(
+	state: &ParserState,
+	token: &Token,
+	start: usize,
+	layout_token: LayoutToken,
+	layout_type: &dyn LayoutType,
+) {
+	let Some(hover) = Hover::from_source(token.source(), &state.shared.lsp) else {
+		return;
+	};
+
+	let (delimiter, description) = match layout_token {
+		LayoutToken::Begin => (
+			"LAYOUT_BEGIN",
+			"Starts a new layout, followed by its first block.",
+		),
+		LayoutToken::Next => (
+			"LAYOUT_NEXT",
+			"Starts the next block of the current layout.",
+		),
+		LayoutToken::End => ("LAYOUT_END", "Ends the current layout."),
+	};
+	let expects = layout_type.expects();
+	let count = if expects.end == usize::MAX {
+		format!("at least {}", expects.start)
+	} else if expects.start == expects.end {
+		format!("exactly {}", expects.start)
+	} else {
+		format!("{} to {}", expects.start, expects.end)
+	};
+	let blocks = if expects.start == 1 && expects.end == 1 {
+		"block"
+	} else {
+		"blocks"
+	};
+	let mut doc = format!(
+		"**`#+{delimiter}`** — Layout `{}`\n\n{description}\n\nExpects {count} {blocks}.",
+		layout_type.name(),
+	);
+	if let Some(properties) = layout_type.properties() {
+		doc += format!("\n\n{}", properties.to_markdown()).as_str();
+	}
+	hover.add(start..start + 2 + delimiter.len(), doc);
+}
+
 #[auto_registry::auto_registry(registry = "rules")]
 pub struct LayoutRule {
 	re: [Regex; 3],
@@ -251,6 +298,20 @@ impl RegexRule for LayoutRule {
 						}
 						sems.add(matches.get(2).unwrap().range(), tokens.layout_type);
 					}
+
+					let start = matches
+						.get(0)
+						.map(|m| {
+							m.start() + token.source().content()[m.start()..].find('#').unwrap()
+						})
+						.unwrap();
+					add_hover(
+						state,
+						&token,
+						start,
+						LayoutToken::Begin,
+						layout_type.as_ref(),
+					);
 				}
 			};
 			return reports;
@@ -336,6 +397,12 @@ impl RegexRule for LayoutRule {
 				}
 			}
 
+			let start = matches
+				.get(0)
+				.map(|m| m.start() + token.source().content()[m.start()..].find('#').unwrap())
+				.unwrap();
+			add_hover(state, &token, start, LayoutToken::Next, &**layout_type);
+
 			tokens.push(token.clone());
 			(
 				tokens.len() - 1,
@@ -426,6 +493,12 @@ impl RegexRule for LayoutRule {
 				}
 			}
 
+			let start = matches
+				.get(0)
+				.map(|m| m.start() + token.source().content()[m.start()..].find('#').unwrap())
+				.unwrap();
+			add_hover(state, &token, start, LayoutToken::End, layout_type.as_ref());
+
 			(id, LayoutToken::End, layout_type, properties)
 		};
 
@@ -444,11 +517,13 @@ impl RegexRule for LayoutRule {
 	}
 
 	// TODO: Add method to create new layouts
-	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
 		let mut bindings = vec![];
 
-		bindings.push((
-			"push".to_string(),
+		bindings.push(Binding::new(
+			"push",
+			"(token: string, layout: string, properties: string)",
+			"Inserts a delimiter of layout `layout`, `token` is `begin`, `next` or `end`",
 			lua.create_function(
 				|_, (token, layout, properties): (String, String, String)| {
 					let mut result = Ok(());
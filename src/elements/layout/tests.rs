@@ -224,3 +224,57 @@ fn hints() {
 		}
 	}
 }
+
+#[test]
+fn hover() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+#+LAYOUT_BEGIN[style=A] Split
+	A
+#+LAYOUT_NEXT
+	B
+#+LAYOUT_END
+		"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+	let lsp = state.shared.lsp.as_ref().unwrap().borrow();
+	let hovers = lsp
+		.hovers
+		.get(&(source as Rc<dyn Source>))
+		.unwrap()
+		.hovers
+		.borrow();
+
+	let range = |line, start, end| tower_lsp::lsp_types::Range {
+		start: tower_lsp::lsp_types::Position {
+			line,
+			character: start,
+		},
+		end: tower_lsp::lsp_types::Position {
+			line,
+			character: end,
+		},
+	};
+	assert_eq!(hovers.len(), 4);
+	// Property
+	assert_eq!(hovers[0].0, range(1, 15, 20));
+	assert!(hovers[0].1.starts_with("**style**"));
+	// Delimiters
+	assert_eq!(hovers[1].0, range(1, 0, 14));
+	assert!(hovers[1].1.contains("Expects at least 2 blocks."));
+	assert!(hovers[1].1.contains("Layout `Split`"));
+	assert!(hovers[1].1.contains("`style`"));
+	assert_eq!(hovers[2].0, range(3, 0, 13));
+	assert!(hovers[2].1.starts_with("**`#+LAYOUT_NEXT`**"));
+	assert_eq!(hovers[3].0, range(5, 0, 12));
+	assert!(hovers[3].1.starts_with("**`#+LAYOUT_END`**"));
+}
@@ -0,0 +1,79 @@
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::LATEX;
+use crate::compiler::compiler::Target::SLIDES;
+use crate::document::document::Document;
+use crate::document::element::ContainerElement;
+use crate::document::element::ElemKind;
+use crate::document::element::Element;
+use crate::parser::source::Token;
+
+/// Forced line break: `\\` at the end of a line
+#[derive(Debug)]
+pub struct LineBreak {
+	pub(crate) location: Token,
+}
+
+impl Element for LineBreak {
+	fn location(&self) -> &Token { &self.location }
+	fn kind(&self) -> ElemKind { ElemKind::Inline }
+	fn element_name(&self) -> &'static str { "Line Break" }
+	fn compile(
+		&self,
+		compiler: &Compiler,
+		_document: &dyn Document,
+		_cursor: usize,
+	) -> Result<String, String> {
+		match compiler.target() {
+			HTML | SLIDES => Ok("<br>".into()),
+			LATEX => Ok("\\newline{}".into()),
+		}
+	}
+}
+
+/// Content that can't be broken across lines: `\nobreak{content}`
+#[derive(Debug)]
+pub struct NoBreak {
+	pub(crate) location: Token,
+	pub(crate) content: Vec<Box<dyn Element>>,
+}
+
+impl Element for NoBreak {
+	fn location(&self) -> &Token { &self.location }
+	fn kind(&self) -> ElemKind { ElemKind::Inline }
+	fn element_name(&self) -> &'static str { "No Break" }
+	fn compile(
+		&self,
+		compiler: &Compiler,
+		document: &dyn Document,
+		cursor: usize,
+	) -> Result<String, String> {
+		let (open, close) = match compiler.target() {
+			HTML | SLIDES => ("<span style=\"white-space:nowrap\">", "</span>"),
+			LATEX => ("\\mbox{", "}"),
+		};
+
+		let mut result = open.to_string();
+		for elem in &self.content {
+			result += elem
+				.compile(compiler, document, cursor + result.len())?
+				.as_str();
+		}
+		result += close;
+		Ok(result)
+	}
+
+	fn as_container(&self) -> Option<&dyn ContainerElement> { Some(self) }
+}
+
+impl ContainerElement for NoBreak {
+	fn contained(&self) -> &Vec<Box<dyn Element>> { &self.content }
+
+	fn push(&mut self, elem: Box<dyn Element>) -> Result<(), String> {
+		if elem.kind() == ElemKind::Block {
+			return Err("Cannot add block element inside a no-break group".to_string());
+		}
+		self.content.push(elem);
+		Ok(())
+	}
+}
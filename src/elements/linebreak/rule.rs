@@ -0,0 +1,108 @@
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
+use document::document::Document;
+use lsp::semantic::Semantics;
+use parser::parser::ParseMode;
+use parser::parser::ParserState;
+use parser::rule::RegexRule;
+use parser::source::Token;
+use parser::util::escape_source;
+use parser::util::parse_paragraph;
+use regex::Captures;
+use regex::Regex;
+
+use super::elem::LineBreak;
+use super::elem::NoBreak;
+
+#[auto_registry::auto_registry(registry = "rules")]
+pub struct BreakRule {
+	re: [Regex; 2],
+}
+
+impl Default for BreakRule {
+	fn default() -> Self {
+		Self {
+			re: [
+				// Only at the end of a line, so that `\\` still escapes a backslash elsewhere
+				Regex::new(r"(?m)\\\\[ \t]*$").unwrap(),
+				Regex::new(r"\\nobreak\{((?:\\.|[^\\\\])*?)\}").unwrap(),
+			],
+		}
+	}
+}
+
+impl RegexRule for BreakRule {
+	fn name(&self) -> &'static str { "Break" }
+
+	fn previous(&self) -> Option<&'static str> { Some("Diff") }
+
+	fn regexes(&self) -> &[Regex] { &self.re }
+
+	fn enabled(&self, _mode: &ParseMode, _id: usize) -> bool { true }
+
+	fn on_regex_match<'a>(
+		&self,
+		index: usize,
+		state: &ParserState,
+		document: &'a (dyn Document<'a> + 'a),
+		token: Token,
+		matches: Captures,
+	) -> Vec<Report> {
+		let mut reports = vec![];
+
+		if index == 0 {
+			if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp)
+			{
+				sems.add(token.start()..token.start() + 2, tokens.linebreak);
+			}
+			state.push(document, Box::new(LineBreak { location: token }));
+			return reports;
+		}
+
+		let content = matches.get(1).unwrap();
+		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
+			sems.add(token.start()..content.start(), tokens.nobreak_sep);
+		}
+
+		let source = escape_source(
+			token.source(),
+			content.range(),
+			"No Break".into(),
+			'\\',
+			"}",
+		);
+		let content = if source.content().is_empty() {
+			vec![]
+		} else {
+			match parse_paragraph(state, source, document) {
+				Ok(mut paragraph) => std::mem::take(&mut paragraph.content),
+				Err(err) => {
+					report_err!(
+						&mut reports,
+						token.source(),
+						"Invalid No Break".into(),
+						span(content.range(), format!("Failed to parse content: {err}"))
+					);
+					return reports;
+				}
+			}
+		};
+
+		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
+			sems.add(
+				matches.get(1).unwrap().end()..token.end(),
+				tokens.nobreak_sep,
+			);
+		}
+
+		state.push(
+			document,
+			Box::new(NoBreak {
+				location: token,
+				content,
+			}),
+		);
+
+		reports
+	}
+}
@@ -0,0 +1,75 @@
+use std::rc::Rc;
+
+use crate::elements::linebreak::elem::LineBreak;
+use crate::elements::linebreak::elem::NoBreak;
+use crate::elements::paragraph::elem::Paragraph;
+use crate::elements::style::elem::Style;
+use crate::elements::text::elem::Text;
+use crate::parser::langparser::LangParser;
+use crate::parser::parser::ParseMode;
+use crate::parser::parser::Parser;
+use crate::parser::parser::ParserState;
+use crate::parser::source::SourceFile;
+use crate::validate_document;
+use crate::validate_semantics;
+
+#[test]
+fn parser() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+First line\\
+Second line, \\ kept \nobreak{*10 km*} and \nobreak{a \} b}.
+			"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Paragraph {
+			Text { content == "First line" };
+			LineBreak;
+			Text { content == "Second line, \\ kept " };
+			NoBreak {
+				Style;
+				Text { content == "10 km" };
+				Style;
+			};
+			Text { content == " and " };
+			NoBreak { Text { content == "a } b" }; };
+			Text { content == ". " };
+		};
+	);
+}
+
+#[test]
+fn semantic() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+a\\
+\nobreak{b}
+			"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+	validate_semantics!(state, source.clone(), 0,
+		linebreak { delta_line == 1, delta_start == 1, length == 2 };
+		nobreak_sep { delta_line == 1, delta_start == 0, length == 9 };
+		nobreak_sep { delta_line == 0, delta_start == 10, length == 1 };
+	);
+}
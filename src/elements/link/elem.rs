@@ -1,5 +1,7 @@
 use crate::compiler::compiler::Compiler;
 use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
+use crate::compiler::url::prefixed;
 use crate::document::document::Document;
 use crate::document::element::ContainerElement;
 use crate::document::element::ElemKind;
@@ -13,6 +15,10 @@ pub struct Link {
 	pub(crate) display: Vec<Box<dyn Element>>,
 	/// Url of link
 	pub(crate) url: String,
+	/// Title of link, displayed when hovered
+	pub(crate) title: Option<String>,
+	/// Browsing context to open the link in
+	pub(crate) target: Option<String>,
 }
 
 impl Element for Link {
@@ -26,11 +32,33 @@ impl Element for Link {
 		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let mut result = format!(
-					"<a href=\"{}\">",
-					Compiler::sanitize(compiler.target(), self.url.as_str())
+					"<a href=\"{}\"",
+					Compiler::sanitize_url(
+						compiler.target(),
+						prefixed(document, self.url.as_str())
+					)
 				);
+				if let Some(title) = &self.title {
+					result += format!(
+						" title=\"{}\"",
+						Compiler::sanitize_attribute(compiler.target(), title.as_str())
+					)
+					.as_str();
+				}
+				if let Some(target) = &self.target {
+					result += format!(
+						" target=\"{}\"",
+						Compiler::sanitize_attribute(compiler.target(), target.as_str())
+					)
+					.as_str();
+					// Prevent the opened page from accessing `window.opener`
+					if target == "_blank" {
+						result += " rel=\"noopener\"";
+					}
+				}
+				result += ">";
 
 				for elem in &self.display {
 					result += elem
@@ -1,17 +1,25 @@
+use std::collections::HashMap;
 use std::rc::Rc;
 use std::sync::Arc;
 
+use crate::lua::kernel::Binding;
 use crate::parser::reports::macros::*;
 use crate::parser::reports::*;
 use ariadne::Fmt;
 use document::document::Document;
+use document::element::Element;
+use lsp::conceal::ConcealTarget;
+use lsp::conceal::Conceals;
 use lsp::semantic::Semantics;
+use lsp::styles::Style;
+use lsp::styles::Styles;
 use lua::kernel::CTX;
 use mlua::Error::BadArgument;
-use mlua::Function;
 use mlua::Lua;
 use parser::parser::ParseMode;
 use parser::parser::ParserState;
+use parser::property::Property;
+use parser::property::PropertyParser;
 use parser::rule::RegexRule;
 use parser::source::Token;
 use parser::source::VirtualSource;
@@ -20,22 +28,60 @@ use parser::util::parse_paragraph;
 use parser::util::process_text;
 use regex::Captures;
 use regex::Regex;
+use serde_json::json;
+
+use crate::elements::text::elem::Text;
 
 use super::elem::Link;
 
 #[auto_registry::auto_registry(registry = "rules")]
 pub struct LinkRule {
-	re: [Regex; 1],
+	re: [Regex; 2],
+	properties: PropertyParser,
 }
 
 impl Default for LinkRule {
 	fn default() -> Self {
+		let mut props = HashMap::new();
+		props.insert(
+			"title".to_string(),
+			Property::new(
+				"Title of the link, displayed when hovered".to_string(),
+				None,
+			),
+		);
+		props.insert(
+			"target".to_string(),
+			Property::new(
+				"Browsing context to open the link in, e.g `_blank`".to_string(),
+				None,
+			),
+		);
 		Self {
-			re: [Regex::new(r"\[((?:\\.|[^\\\\])*?)\]\(((?:\\.|[^\\\\])*?)\)").unwrap()],
+			re: [
+				// Properties must contain a `=`, so that `[a](b)[c](d)` remains two links
+				Regex::new(
+					r"\[((?:\\.|[^\\\\])*?)\]\(((?:\\.|[^\\\\])*?)\)(?:\[((?:\\.|[^\\\]])*?=(?:\\.|[^\\\]])*?)\])?",
+				)
+				.unwrap(),
+				Regex::new(r#"\b(?:https?|ftp)://[^\s<>\[\]()"]*[^\s<>\[\]()"'.,;:!?]"#).unwrap(),
+			],
+			properties: PropertyParser { properties: props },
 		}
 	}
 }
 
+/// Moves the content of links nested in `content` into `content`, as links cannot be nested
+fn flatten_links(content: Vec<Box<dyn Element>>) -> Vec<Box<dyn Element>> {
+	content
+		.into_iter()
+		.flat_map(|elem| match elem.downcast::<Link>() {
+			Ok(link) => link.display,
+			Err(elem) => vec![elem],
+		})
+		.collect()
+}
+
 impl RegexRule for LinkRule {
 	fn name(&self) -> &'static str { "Link" }
 
@@ -45,9 +91,13 @@ impl RegexRule for LinkRule {
 
 	fn enabled(&self, _mode: &ParseMode, _id: usize) -> bool { true }
 
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> {
+		vec![("Link", &self.properties)]
+	}
+
 	fn on_regex_match<'a>(
 		&self,
-		_: usize,
+		index: usize,
 		state: &ParserState,
 		document: &'a (dyn Document<'a> + 'a),
 		token: Token,
@@ -55,6 +105,29 @@ impl RegexRule for LinkRule {
 	) -> Vec<Report> {
 		let mut reports = vec![];
 
+		// Bare urls
+		if index == 1 {
+			let url = matches.get(0).unwrap().as_str().to_string();
+			let autolink = document
+				.get_variable("link.autolink")
+				.is_some_and(|var| var.to_string() == "true");
+			if autolink {
+				state.push(
+					document,
+					Box::new(Link {
+						location: token.clone(),
+						display: vec![Box::new(Text::new(token.clone(), url.clone()))],
+						url,
+						title: None,
+						target: None,
+					}),
+				);
+			} else {
+				state.push(document, Box::new(Text::new(token.clone(), url)));
+			}
+			return reports;
+		}
+
 		let link_display = match matches.get(1) {
 			Some(display) => {
 				if display.as_str().is_empty() {
@@ -111,7 +184,7 @@ impl RegexRule for LinkRule {
 						);
 						return reports;
 					}
-					Ok(mut paragraph) => std::mem::take(&mut paragraph.content),
+					Ok(mut paragraph) => flatten_links(std::mem::take(&mut paragraph.content)),
 				}
 			}
 			_ => panic!("Empty link name"),
@@ -151,12 +224,71 @@ impl RegexRule for LinkRule {
 			_ => panic!("Empty link url"),
 		};
 
+		// Properties
+		let prop_source = escape_source(
+			token.source(),
+			matches.get(3).map_or(0..0, |m| m.range()),
+			"Link Properties".into(),
+			'\\',
+			"]",
+		);
+		let properties = match self.properties.parse(
+			"Link",
+			&mut reports,
+			state,
+			Token::new(0..prop_source.content().len(), prop_source),
+		) {
+			Some(props) => props,
+			None => return reports,
+		};
+		let (title, target) = match (
+			properties.get_opt(&mut reports, "title", |_, value| {
+				Result::<_, String>::Ok(value.value.clone())
+			}),
+			properties.get_opt(&mut reports, "target", |_, value| {
+				Result::<_, String>::Ok(value.value.clone())
+			}),
+		) {
+			(Some(title), Some(target)) => (title, target),
+			_ => return reports,
+		};
+
+		// Conceals
+		if let Some(conceals) = Conceals::from_source(token.source(), &state.shared.lsp) {
+			let display = matches.get(1).unwrap().range();
+			conceals.add(
+				display.start - 1..display.start,
+				"link",
+				ConcealTarget::Text("".into()),
+			);
+			// Conceals cannot span multiple lines
+			if !token.source().content()[display.end..token.end()].contains('\n') {
+				conceals.add(
+					display.end..token.end(),
+					"link",
+					ConcealTarget::Token {
+						token: "link".into(),
+						params: json!({
+							"url": link_url,
+						}),
+					},
+				);
+			}
+		}
+
+		// Styles
+		if let Some(styles) = Styles::from_source(token.source(), &state.shared.lsp) {
+			styles.add(matches.get(1).unwrap().range(), Style::Group("Link".into()));
+		}
+
 		state.push(
 			document,
 			Box::new(Link {
 				location: token.clone(),
 				display: link_display,
 				url: link_url,
+				title,
+				target,
 			}),
 		);
 
@@ -169,16 +301,22 @@ impl RegexRule for LinkRule {
 			sems.add(url.start - 1..url.start, tokens.link_url_sep);
 			sems.add(url.clone(), tokens.link_url);
 			sems.add(url.end..url.end + 1, tokens.link_url_sep);
+			if let Some(props) = matches.get(3).map(|m| m.range()) {
+				sems.add(props.start - 1..props.start, tokens.link_props_sep);
+				sems.add(props.end..props.end + 1, tokens.link_props_sep);
+			}
 		}
 
 		reports
 	}
 
-	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
 		let mut bindings = vec![];
 
-		bindings.push((
-			"push".to_string(),
+		bindings.push(Binding::new(
+			"push",
+			"(display: string, url: string)",
+			"Inserts a link",
 			lua.create_function(|_, (display, url): (String, String)| {
 				let mut result = Ok(());
 				CTX.with_borrow(|ctx| {
@@ -201,7 +339,9 @@ impl RegexRule for LinkRule {
 								});
 								return;
 							}
-							Ok(mut paragraph) => std::mem::take(&mut paragraph.content),
+							Ok(mut paragraph) => {
+								flatten_links(std::mem::take(&mut paragraph.content))
+							}
 						};
 
 						ctx.state.push(
@@ -210,6 +350,8 @@ impl RegexRule for LinkRule {
 								location: ctx.location.clone(),
 								display: display_content,
 								url,
+								title: None,
+								target: None,
 							}),
 						);
 					})
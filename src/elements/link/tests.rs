@@ -1,13 +1,18 @@
 use std::rc::Rc;
 
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target;
+use crate::compiler::exec::ExecPolicy;
 use crate::elements::link::elem::Link;
 use crate::elements::paragraph::elem::Paragraph;
 use crate::elements::style::elem::Style;
 use crate::elements::text::elem::Text;
+use crate::lsp::conceal::ConcealTarget;
 use crate::parser::langparser::LangParser;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::Parser;
 use crate::parser::parser::ParserState;
+use crate::parser::source::Source;
 use crate::parser::source::SourceFile;
 use crate::validate_document;
 use crate::validate_semantics;
@@ -45,6 +50,90 @@ Some [link](url).
 	);
 }
 
+#[test]
+fn properties() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+[link](url)[title=Some "title", target=_blank]
+[a](b)[c](d)
+			"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Paragraph {
+			Link { url == "url", title == Some("Some \"title\"".to_string()), target == Some("_blank".to_string()) } {
+				Text { content == "link" };
+			};
+			Link { url == "b", title == None::<String>, target == None::<String> } { Text { content == "a" }; };
+			Link { url == "d" } { Text { content == "c" }; };
+		};
+	);
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled.body.contains(
+		r#"<a href="url" title="Some &quot;title&quot;" target="_blank" rel="noopener">link</a>"#
+	));
+}
+
+#[test]
+fn autolink() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+See https://example.com/page?a=b.
+
+@link.autolink = true
+See https://example.com/page?a=b, or [https://a.org](https://a.org).
+
+@link.autolink = false
+See https://example.com.
+			"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Paragraph {
+			Text { content == "See " };
+			Text { content == "https://example.com/page?a=b" };
+			Text { content == "." };
+		};
+		Paragraph {
+			Text { content == "See " };
+			Link { url == "https://example.com/page?a=b" } {
+				Text { content == "https://example.com/page?a=b" };
+			};
+			Text { content == ", or " };
+			Link { url == "https://a.org" } { Text { content == "https://a.org" }; };
+			Text { content == "." };
+		};
+		Paragraph {
+			Text { content == "See " };
+			Text { content == "https://example.com" };
+			Text { content == ". " };
+		};
+	);
+}
+
 #[test]
 fn lua() {
 	let source = Rc::new(SourceFile::with_content(
@@ -109,3 +198,39 @@ fn semantics() {
 	link_url_sep { delta_line == 0, delta_start == 3, length == 1 };
 	);
 }
+
+#[test]
+fn conceals() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"[link](url)"#.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+
+	let lsp = state.shared.lsp.as_ref().unwrap().borrow();
+	let conceals = lsp
+		.conceals
+		.get(&(source.clone() as Rc<dyn Source>))
+		.unwrap()
+		.conceals
+		.borrow();
+	assert_eq!(conceals.len(), 2);
+	assert_eq!(conceals[0].range.start.character, 0);
+	assert_eq!(conceals[0].range.end.character, 1);
+	assert_eq!(conceals[1].range.start.character, 5);
+	assert_eq!(conceals[1].range.end.character, 11);
+	match &conceals[1].conceal_text {
+		ConcealTarget::Token { token, params } => {
+			assert_eq!(token, "link");
+			assert_eq!(params["url"], "url");
+		}
+		_ => panic!("Expected token conceal"),
+	}
+}
@@ -2,6 +2,7 @@ use serde::Serialize;
 
 use crate::compiler::compiler::Compiler;
 use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
 use crate::document::document::Document;
 use crate::document::element::ContainerElement;
 use crate::document::element::ElemKind;
@@ -35,7 +36,7 @@ impl Element for ListMarker {
 		_cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => match (self.kind, self.numbered) {
+			HTML | SLIDES => match (self.kind, self.numbered) {
 				(MarkerKind::Close, true) => Ok("</ol>".to_string()),
 				(MarkerKind::Close, false) => Ok("</ul>".to_string()),
 				(MarkerKind::Open, true) => Ok("<ol>".to_string()),
@@ -83,7 +84,7 @@ impl Element for ListEntry {
 		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let mut result = String::new();
 				if let Some((numbered, number)) = self.numbering.last() {
 					if *numbered {
@@ -2,7 +2,9 @@ use std::any::Any;
 use std::cell::Ref;
 use std::collections::HashMap;
 use std::rc::Rc;
+use std::sync::Arc;
 
+use crate::lua::kernel::Binding;
 use crate::parser::reports::macros::*;
 use crate::parser::reports::*;
 use ariadne::Fmt;
@@ -10,7 +12,11 @@ use lsp::conceal::ConcealTarget;
 use lsp::conceal::Conceals;
 use lsp::hints::Hints;
 use lsp::semantic::Semantics;
+use lua::kernel::CTX;
+use mlua::Error::BadArgument;
+use mlua::Lua;
 use parser::rule::Rule;
+use parser::source::Source;
 use parser::source::Token;
 use parser::source::VirtualSource;
 use parser::util::parse_paragraph;
@@ -131,23 +137,27 @@ pub struct ListRule {
 	properties: PropertyParser,
 }
 
+/// Properties of list entries
+fn list_properties() -> PropertyParser {
+	let mut props = HashMap::new();
+	props.insert(
+		"offset".to_string(),
+		Property::new("Entry numbering offset".to_string(), None),
+	);
+	props.insert(
+		"bullet".to_string(),
+		Property::new("Entry bullet".to_string(), None),
+	);
+	PropertyParser { properties: props }
+}
+
 impl Default for ListRule {
 	fn default() -> Self {
-		let mut props = HashMap::new();
-		props.insert(
-			"offset".to_string(),
-			Property::new("Entry numbering offset".to_string(), None),
-		);
-		props.insert(
-			"bullet".to_string(),
-			Property::new("Entry bullet".to_string(), None),
-		);
-
 		Self {
 			start_re: Regex::new(r"(?:^|\n)(?:[^\S\r\n]+)([*-]+)(?:\[((?:\\.|[^\\\\])*?)\])?(?:[^\S\r\n]{0,1}\[((?:\\.|[^\\\\])*?)\])?(?:[^\S\r\n]+)(.*)")
 				.unwrap(),
 			continue_re: Regex::new(r"(?:^|\n)([^\S\r\n].*)").unwrap(),
-			properties: PropertyParser { properties: props },
+			properties: list_properties(),
 		}
 	}
 }
@@ -157,6 +167,10 @@ impl Rule for ListRule {
 
 	fn previous(&self) -> Option<&'static str> { Some("Raw") }
 
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> {
+		vec![("List", &self.properties)]
+	}
+
 	fn next_match(
 		&self,
 		mode: &ParseMode,
@@ -271,6 +285,7 @@ impl Rule for ListRule {
 						match data {
 							CustomListData::Checkbox(checkbox_state) => conceals.add(
 								custom_data.start - 1..custom_data.end + 1,
+								"checkbox",
 								ConcealTarget::Token {
 									token: "checkbox".into(),
 									params: json!({
@@ -306,6 +321,7 @@ impl Rule for ListRule {
 					for (depth, (numbered, _)) in depth.iter().enumerate() {
 						conceals.add(
 							i..i + 1,
+							"bullet",
 							lsp::conceal::ConcealTarget::Token {
 								token: "bullet".into(),
 								params: json!({
@@ -414,4 +430,136 @@ impl Rule for ListRule {
 
 		(end_cursor, reports)
 	}
+
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
+		let mut bindings = vec![];
+
+		bindings.push(Binding::new(
+			"push_entry",
+			"(depth: string, content: string, properties: string?)",
+			"Inserts a list entry, `depth` is a sequence of `*` (unordered) and `-` (ordered)",
+			lua.create_function(
+				|_, (depth, content, properties): (String, String, Option<String>)| {
+					let bad_argument = |pos: usize, name: &str, err: String| BadArgument {
+						to: Some("push_entry".to_string()),
+						pos,
+						name: Some(name.to_string()),
+						cause: Arc::new(mlua::Error::external(err)),
+					};
+					if depth.is_empty() || depth.contains(|c| c != '*' && c != '-') {
+						return Err(bad_argument(
+							1,
+							"depth",
+							format!(
+								"Invalid list depth `{depth}`, expected a sequence of `*` and `-`"
+							),
+						));
+					}
+
+					// The entry content may run lua code, the context is restored once the entry
+					// is parsed
+					let Some(ctx) = CTX.take() else {
+						return Ok(());
+					};
+					let result = (|| {
+						// Parse properties
+						let prop_source = Rc::new(VirtualSource::new(
+							ctx.location.clone(),
+							":LUA:List Properties".into(),
+							properties.unwrap_or_default(),
+						)) as Rc<dyn Source>;
+						let parser = list_properties();
+						let Some(properties) =
+							parser.parse("List", &mut ctx.reports, ctx.state, prop_source.into())
+						else {
+							return Err(bad_argument(
+								3,
+								"properties",
+								"Failed to parse properties".into(),
+							));
+						};
+						let (Some(offset), Some(bullet)) = (
+							properties.get_opt(&mut ctx.reports, "offset", |_, value| {
+								value.value.parse::<usize>()
+							}),
+							properties.get_opt(&mut ctx.reports, "bullet", |_, value| {
+								Result::<_, String>::Ok(value.value.clone())
+							}),
+						) else {
+							return Err(bad_argument(3, "properties", "Invalid properties".into()));
+						};
+						let bullet = bullet.or_else(|| {
+							ctx.document
+								.last_element::<ListEntry>()
+								.and_then(|prev| prev.bullet.clone())
+						});
+						let depth = parse_depth(&depth, ctx.document, offset.unwrap_or(usize::MAX));
+
+						// Parse content
+						let entry_src = Rc::new(VirtualSource::new(
+							ctx.location.clone(),
+							":LUA:List Entry".into(),
+							content,
+						));
+						let content = parse_paragraph(ctx.state, entry_src, ctx.document)
+							.map(|mut paragraph| std::mem::take(&mut paragraph.content))
+							.map_err(|err| bad_argument(2, "content", err.to_string()))?;
+
+						let previous_depth = ctx
+							.document
+							.last_element::<ListEntry>()
+							.map(|ent| ent.numbering.clone())
+							.unwrap_or_default();
+						push_markers(
+							&ctx.location,
+							ctx.state,
+							ctx.document,
+							&previous_depth,
+							&depth,
+						);
+						ctx.state.push(
+							ctx.document,
+							Box::new(ListEntry {
+								location: ctx.location.clone(),
+								numbering: depth,
+								content,
+								bullet,
+								custom: None,
+							}),
+						);
+						Ok(())
+					})();
+					CTX.set(Some(ctx));
+
+					result
+				},
+			)
+			.unwrap(),
+		));
+
+		bindings.push(Binding::new(
+			"close",
+			"()",
+			"Closes the current lists",
+			lua.create_function(|_, ()| {
+				CTX.with_borrow(|ctx| {
+					ctx.as_ref().map(|ctx| {
+						// Close all lists
+						if let Some(current) = ctx
+							.document
+							.last_element::<ListEntry>()
+							.map(|ent| ent.numbering.clone())
+						{
+							push_markers(&ctx.location, ctx.state, ctx.document, &current, &vec![]);
+						}
+					})
+				});
+
+				Ok(())
+			})
+			.unwrap(),
+		));
+
+		bindings
+	}
 }
@@ -6,6 +6,7 @@ use crate::elements::list::elem::ListEntry;
 use crate::elements::list::elem::ListMarker;
 use crate::elements::list::elem::MarkerKind;
 use crate::elements::paragraph::elem::Paragraph;
+use crate::elements::style::elem::Style;
 use crate::elements::text::elem::Text;
 use crate::parser::langparser::LangParser;
 use crate::parser::parser::ParseMode;
@@ -94,6 +95,50 @@ fn parser() {
 	);
 }
 
+#[test]
+fn lua() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+%<
+for i = 1, 2 do
+	nml.list.push_entry("*", "Entry **" .. i .. "**")
+end
+nml.list.push_entry("*-", "Nested", "bullet=(*)")
+nml.list.push_entry("*", "Back")
+nml.list.close()
+>%
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		ListMarker { numbered == false, kind == MarkerKind::Open };
+		ListEntry { numbering == vec![(false, 1)] } {
+			Text { content == "Entry " };
+			Style;
+			Text { content == "1" };
+			Style;
+		};
+		ListEntry { numbering == vec![(false, 2)] };
+		ListMarker { numbered == true, kind == MarkerKind::Open };
+		ListEntry { numbering == vec![(false, 3), (true, 1)], bullet == Some("(*)".to_string()) } {
+			Text { content == "Nested" };
+		};
+		ListMarker { numbered == true, kind == MarkerKind::Close };
+		ListEntry { numbering == vec![(false, 3)] };
+		ListMarker { numbered == false, kind == MarkerKind::Close };
+	);
+}
+
 #[test]
 fn semantic() {
 	let source = Rc::new(SourceFile::with_content(
@@ -125,3 +170,16 @@ fn semantic() {
 		list_bullet { delta_line == 2, delta_start == 1, length == 2 };
 	);
 }
+
+#[test]
+fn snapshot() {
+	crate::assert_snapshot!(
+		"list",
+		r#"
+ * First **bold**
+ *[offset=5] Second
+ ** Nested
+ - [x] Done
+"#
+	);
+}
@@ -3,6 +3,8 @@ use std::str::FromStr;
 use crate::compiler::compiler::Compiler;
 use crate::compiler::compiler::Target;
 use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
+use crate::compiler::url::prefixed;
 use crate::document::document::Document;
 use crate::document::element::ContainerElement;
 use crate::document::element::ElemKind;
@@ -12,6 +14,12 @@ use crate::elements::paragraph::elem::Paragraph;
 use crate::elements::reference::elem::InternalReference;
 use crate::parser::source::Token;
 
+use super::metadata::ImageMetadata;
+use super::metadata::METADATA_VARIABLE;
+use super::thumbnail::Thumbnail;
+use super::thumbnail::ThumbnailSize;
+use super::thumbnail::THUMBNAIL_VARIABLE;
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum MediaType {
 	IMAGE,
@@ -54,7 +62,7 @@ impl Element for Media {
 		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let mut result = String::new();
 
 				result.push_str("<div class=\"media\">");
@@ -102,6 +110,13 @@ pub struct Medium {
 	pub(crate) media_type: MediaType,
 	pub(crate) width: Option<String>,
 	pub(crate) caption: Option<String>,
+	/// Text alternative for images
+	pub(crate) alt: Option<String>,
+	/// Whether to read the caption from the image metadata, defaults to variable
+	/// [`METADATA_VARIABLE`]
+	pub(crate) metadata: Option<bool>,
+	/// Maximum dimensions of the image's thumbnail, defaults to variable [`THUMBNAIL_VARIABLE`]
+	pub(crate) thumbnail: Option<ThumbnailSize>,
 	pub(crate) description: Option<Paragraph>,
 }
 
@@ -121,17 +136,28 @@ impl Element for Medium {
 		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			Target::HTML => {
+			Target::HTML | Target::SLIDES => {
 				let mut result = String::new();
 
 				// Reference
 				let elemref = document.get_reference(self.reference.as_str()).unwrap();
 				let refcount = compiler.reference_id(document, elemref);
 
-				let width = self
-					.width
+				let width = self.width.as_ref().map_or(String::new(), |w| {
+					format!(
+						r#" style="width:{};""#,
+						Compiler::sanitize_attribute(compiler.target(), w)
+					)
+				});
+				let uri = Compiler::sanitize_url(
+					compiler.target(),
+					prefixed(document, self.uri.as_str()),
+				);
+				let metadata = self.image_metadata(compiler, document);
+				let caption = self
+					.caption
 					.as_ref()
-					.map_or(String::new(), |w| format!(r#" style="width:{w};""#));
+					.or(metadata.as_ref().and_then(|data| data.caption.as_ref()));
 				result.push_str(
 					format!(
 						r#"<div id="{}" class="medium"{width}>"#,
@@ -140,25 +166,51 @@ impl Element for Medium {
 					.as_str(),
 				);
 				result += match self.media_type {
-					MediaType::IMAGE => format!(r#"<a href="{0}"><img src="{0}"></a>"#, self.uri),
-					MediaType::VIDEO => format!(
-						r#"<video controls{width}><source src="{0}"></video>"#,
-						self.uri
-					),
-					MediaType::AUDIO => {
-						format!(r#"<audio controls src="{0}"{width}></audio>"#, self.uri)
+					MediaType::IMAGE => {
+						let alt = self.alt.as_ref().or(caption).map_or(String::new(), |alt| {
+							Compiler::sanitize_attribute(compiler.target(), alt)
+						});
+						match self.thumbnail_path(compiler, document)? {
+							Some(thumbnail) => {
+								let thumbnail =
+									Compiler::sanitize_url(compiler.target(), thumbnail.as_str());
+								let id = self.refid(compiler, refcount);
+								format!(
+									"<a class=\"medium-thumbnail\" href=\"#lightbox-{id}\">\
+									<img src=\"{thumbnail}\" alt=\"{alt}\"></a>\
+									<a class=\"medium-lightbox\" id=\"lightbox-{id}\" href=\"#{id}\">\
+									<img src=\"{uri}\" alt=\"{alt}\" loading=\"lazy\"></a>"
+								)
+							}
+							None => {
+								format!(r#"<a href="{uri}"><img src="{uri}" alt="{alt}"></a>"#)
+							}
+						}
+					}
+					MediaType::VIDEO => {
+						format!(r#"<video controls{width}><source src="{uri}"></video>"#)
 					}
+					MediaType::AUDIO => format!(r#"<audio controls src="{uri}"{width}></audio>"#),
 				}
 				.as_str();
 
-				let caption = self
-					.caption
-					.as_ref()
+				let caption = caption
 					.map(|cap| format!(" {}", Compiler::sanitize(compiler.target(), cap.as_str())))
 					.unwrap_or_default();
+				let author = metadata
+					.as_ref()
+					.and_then(|data| data.author.as_ref())
+					.map(|author| {
+						format!(
+							r#" <span class="medium-author">{}</span>"#,
+							Compiler::sanitize(compiler.target(), author.as_str())
+						)
+					})
+					.unwrap_or_default();
 
 				result.push_str(
-					format!(r#"<p class="medium-refname">({refcount}) {caption}</p>"#).as_str(),
+					format!(r#"<p class="medium-refname">({refcount}) {caption}{author}</p>"#)
+						.as_str(),
 				);
 				if let Some(paragraph) = self.description.as_ref() {
 					result += paragraph
@@ -174,6 +226,83 @@ impl Element for Medium {
 	}
 }
 
+impl Medium {
+	/// Gets the metadata of local images without a caption
+	///
+	/// Reading the metadata is enabled by the `metadata` property, or by variable
+	/// [`METADATA_VARIABLE`] when unset. Remote images and unreadable files have no metadata.
+	fn image_metadata(
+		&self,
+		compiler: &Compiler,
+		document: &dyn Document,
+	) -> Option<ImageMetadata> {
+		if self.media_type != MediaType::IMAGE
+			|| self.caption.is_some()
+			|| self.uri.contains("://")
+			|| self.uri.starts_with("data:")
+		{
+			return None;
+		}
+		let enabled = self.metadata.unwrap_or_else(|| {
+			document
+				.get_variable(METADATA_VARIABLE)
+				.is_none_or(|var| var.to_string() != "false")
+		});
+		if !enabled {
+			return None;
+		}
+
+		ImageMetadata::from_file(compiler, std::path::Path::new(self.uri.as_str())).ok()
+	}
+
+	/// Gets the path to the thumbnail of local images larger than the thumbnail size
+	///
+	/// The thumbnail size is set by the `thumbnail` property, or by variable
+	/// [`THUMBNAIL_VARIABLE`] when unset. Thumbnails are disabled by default. When the asset store
+	/// is enabled, this is the link to the stored thumbnail.
+	fn thumbnail_path(
+		&self,
+		compiler: &Compiler,
+		document: &dyn Document,
+	) -> Result<Option<String>, String> {
+		if self.media_type != MediaType::IMAGE
+			|| self.uri.contains("://")
+			|| self.uri.starts_with("data:")
+		{
+			return Ok(None);
+		}
+		let size = match (self.thumbnail, document.get_variable(THUMBNAIL_VARIABLE)) {
+			(Some(size), _) => size,
+			(None, Some(var)) => ThumbnailSize::from_str(var.to_string().as_str())?,
+			(None, None) => ThumbnailSize::Disabled,
+		};
+		let ThumbnailSize::Max(width, height) = size else {
+			return Ok(None);
+		};
+
+		// Missing files are displayed as links to the image
+		let path = match Thumbnail::new(std::path::Path::new(self.uri.as_str()), width, height) {
+			Ok(Some(thumbnail)) => thumbnail.generate(compiler, &self.location)?,
+			_ => None,
+		};
+
+		// Thumbnails added to the store are linked from the assets directory
+		let Some(path) = path else {
+			return Ok(None);
+		};
+		let content = std::fs::read(&path)
+			.map_err(|err| format!("Unable to read thumbnail `{path}`: {err}"))?;
+		let extension = std::path::Path::new(&path)
+			.extension()
+			.map_or(String::new(), |ext| ext.to_string_lossy().to_string());
+		Ok(Some(
+			compiler
+				.store_asset(document, &content, &extension)?
+				.unwrap_or(path),
+		))
+	}
+}
+
 impl ReferenceableElement for Medium {
 	fn reference_name(&self) -> Option<&String> { Some(&self.reference) }
 
@@ -182,27 +311,35 @@ impl ReferenceableElement for Medium {
 	fn compile_reference(
 		&self,
 		compiler: &Compiler,
-		_document: &dyn Document,
+		document: &dyn Document,
 		reference: &InternalReference,
 		refid: usize,
+		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			Target::HTML => {
-				let caption = reference
-					.caption()
-					.map_or(format!("({refid})"), |cap| cap.clone());
+			Target::HTML | Target::SLIDES => {
+				let link = format!(
+					"<a class=\"medium-ref\" href=\"{}\">",
+					Compiler::anchor(compiler.target(), self.refid(compiler, refid))
+				);
+				let caption =
+					match reference.compile_caption(compiler, document, cursor + link.len()) {
+						Some(caption) => caption?,
+						None => format!("({refid})"),
+					};
 
 				// TODO Handle other kind of media
+				let uri = Compiler::sanitize_url(
+					compiler.target(),
+					prefixed(document, self.uri.as_str()),
+				);
 				match self.media_type {
-					MediaType::IMAGE => Ok(format!(
-						"<a class=\"medium-ref\" href=\"#{}\">{caption}<img src=\"{}\"></a>",
-						self.refid(compiler, refid),
-						self.uri
-					)),
+					// The preview is decorative, the reference's caption is displayed
+					MediaType::IMAGE => {
+						Ok(format!("{link}{caption}<img src=\"{uri}\" alt=\"\"></a>"))
+					}
 					MediaType::VIDEO => Ok(format!(
-						"<a class=\"medium-ref\" href=\"#{}\">{caption}<video><source src=\"{}\"></video></a>",
-						self.refid(compiler, refid),
-						self.uri
+						"{link}{caption}<video><source src=\"{uri}\"></video></a>"
 					)),
 					_ => todo!(""),
 				}
@@ -0,0 +1,250 @@
+use std::path::Path;
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha512;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::cache::cache::Cached;
+use crate::compiler::compiler::Compiler;
+
+/// Name of the variable enabling captions from image metadata
+pub const METADATA_VARIABLE: &str = "media.metadata";
+
+/// Caption and author read from the metadata of an image
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageMetadata {
+	/// IPTC caption, or EXIF image description
+	pub caption: Option<String>,
+	/// IPTC by-line, or EXIF artist
+	pub author: Option<String>,
+}
+
+/// Reads an unsigned integer from the `bytes` at `offset`
+fn read_uint(bytes: &[u8], offset: usize, len: usize, big_endian: bool) -> Option<usize> {
+	let bytes = bytes.get(offset..offset + len)?;
+	let fold = |value: usize, byte: &u8| (value << 8) | *byte as usize;
+	Some(if big_endian {
+		bytes.iter().fold(0, fold)
+	} else {
+		bytes.iter().rev().fold(0, fold)
+	})
+}
+
+/// Converts a metadata string, trimming padding and ignoring empty values
+fn metadata_string(bytes: &[u8]) -> Option<String> {
+	let value = String::from_utf8_lossy(bytes);
+	let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+	(!value.is_empty()).then(|| value.to_string())
+}
+
+/// Reads the dimensions (width, height) of a PNG, GIF or JPEG image
+pub fn image_dimensions(data: &[u8]) -> Option<(usize, usize)> {
+	if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+		return Some((read_uint(data, 16, 4, true)?, read_uint(data, 20, 4, true)?));
+	} else if data.starts_with(b"GIF8") {
+		return Some((read_uint(data, 6, 2, false)?, read_uint(data, 8, 2, false)?));
+	} else if !data.starts_with(&[0xFF, 0xD8]) {
+		return None;
+	}
+
+	// JPEG start of frame segment
+	let mut pos = 2;
+	while let (Some(0xFF), Some(marker)) = (data.get(pos), data.get(pos + 1)) {
+		if (0xC0..=0xCF).contains(marker) && ![0xC4, 0xC8, 0xCC].contains(marker) {
+			return Some((
+				read_uint(data, pos + 7, 2, true)?,
+				read_uint(data, pos + 5, 2, true)?,
+			));
+		}
+		pos += 2 + read_uint(data, pos + 2, 2, true)?;
+	}
+	None
+}
+
+impl ImageMetadata {
+	/// Reads the metadata from the content of an image file
+	///
+	/// Supports EXIF and IPTC metadata of JPEG files, and EXIF metadata of TIFF files. IPTC values
+	/// take precedence over EXIF values.
+	pub fn read(data: &[u8]) -> Self {
+		let mut exif = Self::default();
+		let mut iptc = Self::default();
+
+		if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+			exif = Self::read_tiff(data);
+		} else if data.starts_with(&[0xFF, 0xD8]) {
+			// JPEG segments
+			let mut pos = 2;
+			while let (Some(0xFF), Some(marker)) = (data.get(pos), data.get(pos + 1)) {
+				// Start of scan, metadata segments come before
+				if *marker == 0xDA {
+					break;
+				}
+				let Some(len) = read_uint(data, pos + 2, 2, true) else {
+					break;
+				};
+				let Some(segment) = data.get(pos + 4..pos + 2 + len) else {
+					break;
+				};
+				match marker {
+					0xE1 if segment.starts_with(b"Exif\0\0") => {
+						exif = Self::read_tiff(&segment[6..])
+					}
+					0xED if segment.starts_with(b"Photoshop 3.0\0") => {
+						iptc = Self::read_photoshop(&segment[14..])
+					}
+					_ => {}
+				}
+				pos += 2 + len;
+			}
+		}
+
+		Self {
+			caption: iptc.caption.or(exif.caption),
+			author: iptc.author.or(exif.author),
+		}
+	}
+
+	/// Reads the EXIF `ImageDescription` and `Artist` tags of the first IFD of a TIFF structure
+	fn read_tiff(tiff: &[u8]) -> Self {
+		let mut metadata = Self::default();
+		let big_endian = tiff.starts_with(b"MM");
+		let Some(ifd) = read_uint(tiff, 4, 4, big_endian) else {
+			return metadata;
+		};
+		let count = read_uint(tiff, ifd, 2, big_endian).unwrap_or(0);
+		for entry in (0..count).map(|i| ifd + 2 + i * 12) {
+			let (Some(tag), Some(kind), Some(len)) = (
+				read_uint(tiff, entry, 2, big_endian),
+				read_uint(tiff, entry + 2, 2, big_endian),
+				read_uint(tiff, entry + 4, 4, big_endian),
+			) else {
+				break;
+			};
+			// ASCII values
+			if kind != 2 || (tag != 0x010E && tag != 0x013B) {
+				continue;
+			}
+			// Values of 4 bytes or less are stored in the entry
+			let offset = if len <= 4 {
+				Some(entry + 8)
+			} else {
+				read_uint(tiff, entry + 8, 4, big_endian)
+			};
+			let value = offset
+				.and_then(|offset| tiff.get(offset..offset + len))
+				.and_then(metadata_string);
+			if tag == 0x010E {
+				metadata.caption = value;
+			} else {
+				metadata.author = value;
+			}
+		}
+		metadata
+	}
+
+	/// Reads the IPTC `Caption/Abstract` and `By-line` datasets from Photoshop image resources
+	fn read_photoshop(resources: &[u8]) -> Self {
+		let mut pos = 0;
+		while resources.get(pos..pos + 4) == Some(b"8BIM") {
+			let Some(id) = read_uint(resources, pos + 4, 2, true) else {
+				break;
+			};
+			// Pascal string name, padded to an even length
+			let name_len = *resources.get(pos + 6).unwrap_or(&0) as usize;
+			let size_pos = pos + 6 + (name_len + 1).div_ceil(2) * 2;
+			let Some(size) = read_uint(resources, size_pos, 4, true) else {
+				break;
+			};
+			let Some(data) = resources.get(size_pos + 4..size_pos + 4 + size) else {
+				break;
+			};
+			if id == 0x0404 {
+				return Self::read_iptc(data);
+			}
+			pos = size_pos + 4 + size.div_ceil(2) * 2;
+		}
+		Self::default()
+	}
+
+	/// Reads the IPTC `Caption/Abstract` (2:120) and `By-line` (2:80) datasets
+	fn read_iptc(iptc: &[u8]) -> Self {
+		let mut metadata = Self::default();
+		let mut pos = 0;
+		while iptc.get(pos) == Some(&0x1C) {
+			let (Some(record), Some(dataset), Some(len)) = (
+				iptc.get(pos + 1),
+				iptc.get(pos + 2),
+				read_uint(iptc, pos + 3, 2, true),
+			) else {
+				break;
+			};
+			let value = iptc.get(pos + 5..pos + 5 + len).and_then(metadata_string);
+			match (record, dataset) {
+				(2, 120) => metadata.caption = metadata.caption.or(value),
+				(2, 80) => metadata.author = metadata.author.or(value),
+				_ => {}
+			}
+			pos += 5 + len;
+		}
+		metadata
+	}
+
+	/// Gets the metadata of a local image file, from the cache if possible
+	///
+	/// Metadata are cached by the hash of the file's content.
+	pub fn from_file(compiler: &Compiler, path: &Path) -> Result<Self, String> {
+		let data = std::fs::read(path)
+			.map_err(|err| format!("Unable to read `{}`: {err}", path.display()))?;
+		let file = ImageFile { data };
+
+		let Some(con) = compiler.cache() else {
+			return Ok(Self::read(file.data.as_slice()));
+		};
+
+		let json = match file.lookup(con) {
+			Ok(Some(json)) => json,
+			Ok(None) => {
+				let json = serde_json::to_string(&Self::read(file.data.as_slice()))
+					.map_err(|e| e.to_string())?;
+				file.insert(con, &json)
+					.map_err(|e| format!("Querying the cache failed: {e}"))?;
+				json
+			}
+			Err(e) => return Err(format!("Querying the cache failed: {e}")),
+		};
+		serde_json::from_str(json.as_str()).map_err(|e| e.to_string())
+	}
+}
+
+/// Content of an image file, cached by hash
+pub(crate) struct ImageFile {
+	data: Vec<u8>,
+}
+
+impl Cached for ImageFile {
+	type Key = String;
+	type Value = String;
+
+	fn sql_table() -> &'static str {
+		"CREATE TABLE IF NOT EXISTS cached_media_metadata (
+				digest   TEXT PRIMARY KEY,
+				metadata TEXT NOT NULL);"
+	}
+
+	fn sql_get_query() -> &'static str {
+		"SELECT metadata FROM cached_media_metadata WHERE digest = (?1)"
+	}
+
+	fn sql_insert_query() -> &'static str {
+		"INSERT INTO cached_media_metadata (digest, metadata) VALUES (?1, ?2)"
+	}
+
+	fn key(&self) -> <Self as Cached>::Key {
+		let mut hasher = Sha512::new();
+		hasher.input(self.data.as_slice());
+
+		hasher.result_str()
+	}
+}
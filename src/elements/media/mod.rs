@@ -1,4 +1,6 @@
 pub mod elem;
+pub mod metadata;
 pub mod rule;
 #[cfg(test)]
 pub mod tests;
+pub mod thumbnail;
@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
 
 use ariadne::Fmt;
 use document::document::Document;
@@ -6,12 +8,17 @@ use document::document::DocumentAccessors;
 use document::element::ContainerElement;
 use document::references::validate_refname;
 use lsp::semantic::Semantics;
+use lua::kernel::CTX;
+use mlua::Error::BadArgument;
+use mlua::Lua;
+use mlua::Table;
 use parser::parser::ParseMode;
 use parser::parser::ParserState;
 use parser::property::Property;
 use parser::property::PropertyParser;
 use parser::rule::RegexRule;
 use parser::source::Token;
+use parser::source::VirtualSource;
 use parser::util::escape_source;
 use parser::util::escape_text;
 use parser::util::parse_paragraph;
@@ -19,6 +26,7 @@ use regex::Captures;
 use regex::Regex;
 use regex::RegexBuilder;
 
+use crate::lua::kernel::Binding;
 use crate::parser::reports::macros::*;
 use crate::parser::reports::*;
 use std::str::FromStr;
@@ -26,6 +34,7 @@ use std::str::FromStr;
 use super::elem::Media;
 use super::elem::MediaType;
 use super::elem::Medium;
+use super::thumbnail::ThumbnailSize;
 
 #[auto_registry::auto_registry(registry = "rules")]
 pub struct MediaRule {
@@ -42,12 +51,31 @@ impl Default for MediaRule {
 		);
 		props.insert(
 			"width".to_string(),
-			Property::new("Override for the media width".to_string(), None),
+			Property::new("Override for the media width".to_string(), None).primary(),
 		);
 		props.insert(
 			"caption".to_string(),
 			Property::new("Medium caption".to_string(), None),
 		);
+		props.insert(
+			"alt".to_string(),
+			Property::new("Text alternative for images".to_string(), None),
+		);
+		props.insert(
+			"metadata".to_string(),
+			Property::new(
+				"Read the caption and author from the image metadata when no caption is given"
+					.to_string(),
+				None,
+			),
+		);
+		props.insert(
+			"thumbnail".to_string(),
+			Property::new(
+				"Maximum dimensions of the image's thumbnail, `WIDTHxHEIGHT` or `none`".to_string(),
+				None,
+			),
+		);
 		Self {
 			re: [RegexBuilder::new(
 				r"^!\[(.*)\]\(((?:\\.|[^\\\\])*?)\)(?:\[((?:\\.|[^\\\\])*?)\])?((?:\\(?:.|\n)|[^\\\\])*?$)?",
@@ -89,7 +117,11 @@ fn detect_filetype(filename: &str) -> Option<MediaType> {
 impl RegexRule for MediaRule {
 	fn name(&self) -> &'static str { "Media" }
 
-	fn previous(&self) -> Option<&'static str> { Some("Graphviz") }
+	fn previous(&self) -> Option<&'static str> { Some("Render") }
+
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> {
+		vec![("Media", &self.properties)]
+	}
 
 	fn regexes(&self) -> &[regex::Regex] { &self.re }
 
@@ -105,6 +137,33 @@ impl RegexRule for MediaRule {
 	) -> Vec<Report> {
 		let mut reports = vec![];
 
+		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
+			sems.add(
+				matches.get(0).unwrap().start()..matches.get(0).unwrap().start() + 1,
+				tokens.media_sep,
+			);
+			// Refname
+			sems.add(
+				matches.get(0).unwrap().start() + 1..matches.get(0).unwrap().start() + 2,
+				tokens.media_refname_sep,
+			);
+			sems.add(matches.get(1).unwrap().range(), tokens.media_refname);
+			sems.add(
+				matches.get(1).unwrap().end()..matches.get(1).unwrap().end() + 1,
+				tokens.media_refname_sep,
+			);
+			// Uri
+			sems.add(
+				matches.get(2).unwrap().start() - 1..matches.get(2).unwrap().start(),
+				tokens.media_uri_sep,
+			);
+			sems.add(matches.get(2).unwrap().range(), tokens.media_uri);
+			sems.add(
+				matches.get(2).unwrap().end()..matches.get(2).unwrap().end() + 1,
+				tokens.media_uri_sep,
+			);
+		}
+
 		let refname = match (
 			matches.get(1).unwrap(),
 			validate_refname(document, matches.get(1).unwrap().as_str(), true),
@@ -145,12 +204,20 @@ impl RegexRule for MediaRule {
 			'\\',
 			"]",
 		);
-		let properties = match self.properties.parse(
+		let properties = self.properties.parse(
 			"Media",
 			&mut reports,
 			state,
 			Token::new(0..prop_source.content().len(), prop_source),
+		);
+		if let (Some((sems, tokens)), Some(props)) = (
+			Semantics::from_source(token.source(), &state.shared.lsp),
+			matches.get(3).map(|m| m.range()),
 		) {
+			sems.add(props.start - 1..props.start, tokens.media_props_sep);
+			sems.add(props.end..props.end + 1, tokens.media_props_sep);
+		}
+		let properties = match properties {
 			Some(props) => props,
 			None => return reports,
 		};
@@ -192,37 +259,42 @@ impl RegexRule for MediaRule {
 			}
 			_ => return reports,
 		};
-
-		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
-			sems.add(
-				matches.get(0).unwrap().start()..matches.get(0).unwrap().start() + 1,
-				tokens.media_sep,
-			);
-			// Refname
-			sems.add(
-				matches.get(0).unwrap().start() + 1..matches.get(0).unwrap().start() + 2,
-				tokens.media_refname_sep,
-			);
-			sems.add(matches.get(1).unwrap().range(), tokens.media_refname);
-			sems.add(
-				matches.get(1).unwrap().end()..matches.get(1).unwrap().end() + 1,
-				tokens.media_refname_sep,
-			);
-			// Uri
-			sems.add(
-				matches.get(2).unwrap().start() - 1..matches.get(2).unwrap().start(),
-				tokens.media_uri_sep,
-			);
-			sems.add(matches.get(2).unwrap().range(), tokens.media_uri);
-			sems.add(
-				matches.get(2).unwrap().end()..matches.get(2).unwrap().end() + 1,
-				tokens.media_uri_sep,
+		let alt = match properties.get_opt(&mut reports, "alt", |_, value| {
+			Result::<_, String>::Ok(value.value.clone())
+		}) {
+			Some(alt) => alt,
+			None => return reports,
+		};
+		let metadata = match properties.get_opt(&mut reports, "metadata", |_, value| {
+			value.value.parse::<bool>()
+		}) {
+			Some(metadata) => metadata,
+			None => return reports,
+		};
+		let thumbnail = match properties.get_opt(&mut reports, "thumbnail", |_, value| {
+			ThumbnailSize::from_str(value.value.as_str())
+		}) {
+			Some(thumbnail) => thumbnail,
+			None => return reports,
+		};
+		if media_type == MediaType::IMAGE && alt.is_none() {
+			report_lint!(
+				&mut reports,
+				token.source(),
+				Lint::A11y,
+				"Missing Image Alt Text".into(),
+				span(
+					token.start() + 1..token.end(),
+					format!(
+						"Image `{}` has no text alternative",
+						refname.as_str().fg(state.parser.colors().info)
+					)
+				),
+				help(format!(
+					"Describe the image with the `{}` property",
+					"alt".fg(state.parser.colors().info)
+				))
 			);
-			// Props
-			if let Some(props) = matches.get(3) {
-				sems.add(props.start() - 1..props.start(), tokens.media_props_sep);
-				sems.add(props.end()..props.end() + 1, tokens.media_props_sep);
-			}
 		}
 
 		let description = match matches.get(4) {
@@ -279,6 +351,9 @@ impl RegexRule for MediaRule {
 			media_type,
 			width,
 			caption,
+			alt,
+			metadata,
+			thumbnail,
 			description,
 		})) {
 			report_err!(
@@ -291,4 +366,124 @@ impl RegexRule for MediaRule {
 
 		reports
 	}
+
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
+		let mut bindings = vec![];
+
+		bindings.push(Binding::new(
+			"push",
+			"(refname: string, uri: string, options: table?)",
+			"Inserts a medium, `options` may set keys `type`, `caption`, `width`, `alt`, `description`, `metadata` and `thumbnail`",
+			lua.create_function(
+				|_, (refname, uri, options): (String, String, Option<Table>)| {
+					let bad_argument = |pos: usize, name: &str, err: String| BadArgument {
+						to: Some("push".to_string()),
+						pos,
+						name: Some(name.to_string()),
+						cause: Arc::new(mlua::Error::external(err)),
+					};
+					let option = |key: &str| -> mlua::Result<Option<String>> {
+						options
+							.as_ref()
+							.map_or(Ok(None), |options| options.get::<_, Option<String>>(key))
+					};
+					let uri = validate_uri(uri.as_str())
+						.map_err(|err| bad_argument(2, "uri", err))?
+						.to_string();
+					let media_type = match option("type")? {
+						Some(media_type) => MediaType::from_str(media_type.as_str())
+							.map_err(|err| bad_argument(3, "options", err))?,
+						None => detect_filetype(uri.as_str()).ok_or_else(|| {
+							bad_argument(
+								2,
+								"uri",
+								format!("Failed to detect media type for `{uri}`"),
+							)
+						})?,
+					};
+					let (caption, width, alt, description) = (
+						option("caption")?,
+						option("width")?,
+						option("alt")?,
+						option("description")?,
+					);
+					let metadata = option("metadata")?
+						.map(|metadata| metadata.parse::<bool>())
+						.transpose()
+						.map_err(|err| bad_argument(3, "options", err.to_string()))?;
+					let thumbnail = option("thumbnail")?
+						.map(|thumbnail| ThumbnailSize::from_str(thumbnail.as_str()))
+						.transpose()
+						.map_err(|err| bad_argument(3, "options", err))?;
+
+					// The description may run lua code, the context is restored once the medium
+					// is created
+					let Some(ctx) = CTX.take() else {
+						return Ok(());
+					};
+					let result = (|| {
+						let refname = validate_refname(ctx.document, refname.as_str(), true)
+							.map_err(|err| bad_argument(1, "refname", err))?
+							.to_string();
+
+						let description = match description {
+							Some(description) if !description.is_empty() => {
+								let source = Rc::new(VirtualSource::new(
+									ctx.location.clone(),
+									format!(":LUA:Media[{refname}] description"),
+									description,
+								));
+								let paragraph = parse_paragraph(ctx.state, source, ctx.document)
+									.map_err(|err| {
+										bad_argument(
+											3,
+											"options",
+											format!("Could not parse description: {err}"),
+										)
+									})?;
+								Some(*paragraph)
+							}
+							_ => None,
+						};
+
+						let mut group = match ctx.document.last_element_mut::<Media>() {
+							Some(group) => group,
+							None => {
+								ctx.state.push(
+									ctx.document,
+									Box::new(Media {
+										location: ctx.location.clone(),
+										media: vec![],
+									}),
+								);
+
+								ctx.document.last_element_mut::<Media>().unwrap()
+							}
+						};
+
+						group
+							.push(Box::new(Medium {
+								location: ctx.location.clone(),
+								reference: refname,
+								uri,
+								media_type,
+								width,
+								caption,
+								alt,
+								metadata,
+								thumbnail,
+								description,
+							}))
+							.map_err(mlua::Error::external)
+					})();
+					CTX.set(Some(ctx));
+
+					result
+				},
+			)
+			.unwrap(),
+		));
+
+		bindings
+	}
 }
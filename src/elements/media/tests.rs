@@ -1,14 +1,26 @@
+use std::cell::RefCell;
 use std::rc::Rc;
 
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target;
+use crate::compiler::exec::ExecPolicy;
 use crate::elements::media::elem::MediaType;
 use crate::elements::media::elem::Medium;
+use crate::elements::media::metadata::image_dimensions;
+use crate::elements::media::metadata::ImageMetadata;
 use crate::elements::media::rule::MediaRule;
+use crate::elements::media::thumbnail::Thumbnail;
+use crate::elements::media::thumbnail::ThumbnailSize;
 use crate::parser::langparser::LangParser;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::Parser;
 use crate::parser::parser::ParserState;
+use crate::parser::reports::Lint;
+use crate::parser::reports::ReportKind;
 use crate::parser::rule::RegexRule;
 use crate::parser::source::SourceFile;
+use crate::tempdir::TempDir;
+use crate::validate_semantics;
 
 #[test]
 fn regex() {
@@ -22,6 +34,96 @@ fn regex() {
 	assert!(re.is_match_at("![r1](uri1)[props1] desc1\n![r2](uri2)[props2] desc2", 26));
 }
 
+#[test]
+fn alt() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+![missing](a.png)[caption=Caption]
+![flower](b.png)[alt=A "red" flower]
+![video](c.mp4)
+"#
+		.to_string(),
+		None,
+	));
+	let lints = RefCell::new(vec![]);
+	let parser = LangParser::new(
+		false,
+		Box::new(|_, reports| {
+			for report in reports {
+				if let ReportKind::Lint(lint) = report.kind {
+					lints.borrow_mut().push((lint, report.message));
+				}
+			}
+		}),
+	);
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+	assert_eq!(
+		*lints.borrow(),
+		vec![(Lint::A11y, "Missing Image Alt Text".to_string())]
+	);
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled.body.contains(r#"<img src="a.png" alt="Caption">"#));
+	assert!(compiled
+		.body
+		.contains(r#"<img src="b.png" alt="A &quot;red&quot; flower">"#));
+}
+
+#[test]
+fn lua() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+%<
+for i, file in ipairs({ "a.png", "b.webm" }) do
+	nml.media.push("gallery" .. i, file, { caption = "Figure " .. i, description = "From **lua**" })
+end
+nml.media.push("sound", "c", { type = "audio", width = "50%" })
+>%
+
+See &{gallery2}
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let borrow = doc.content().borrow();
+	let group = borrow.first().as_ref().unwrap().as_container().unwrap();
+	assert_eq!(group.contained().len(), 3);
+
+	let first = group.contained()[0].downcast_ref::<Medium>().unwrap();
+	assert_eq!(first.reference, "gallery1");
+	assert_eq!(first.uri, "a.png");
+	assert_eq!(first.media_type, MediaType::IMAGE);
+	assert_eq!(first.caption, Some("Figure 1".to_string()));
+	assert_eq!(first.description.as_ref().unwrap().content.len(), 4);
+
+	let second = group.contained()[1].downcast_ref::<Medium>().unwrap();
+	assert_eq!(second.media_type, MediaType::VIDEO);
+
+	let third = group.contained()[2].downcast_ref::<Medium>().unwrap();
+	assert_eq!(third.media_type, MediaType::AUDIO);
+	assert_eq!(third.width, Some("50%".to_string()));
+	assert!(third.description.is_none());
+
+	assert!(doc.get_reference("gallery2").is_some());
+}
+
 #[test]
 fn element_test() {
 	let source = Rc::new(SourceFile::with_content(
@@ -56,3 +158,229 @@ fn element_test() {
 	assert_eq!(second.uri, "ur)i\\");
 	assert_eq!(second.media_type, MediaType::AUDIO);
 }
+
+#[test]
+fn semantic() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+![ref](image.png)[width=200px]
+![bad](  )
+				"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+	validate_semantics!(state, source.clone(), 0,
+	media_sep { delta_line == 1, delta_start == 0, length == 1 };
+	media_refname_sep { delta_line == 0, delta_start == 1, length == 1 };
+	media_refname { delta_line == 0, delta_start == 1, length == 3 };
+	media_refname_sep { delta_line == 0, delta_start == 3, length == 1 };
+	media_uri_sep { delta_line == 0, delta_start == 1, length == 1 };
+	media_uri { delta_line == 0, delta_start == 1, length == 9 };
+	media_uri_sep { delta_line == 0, delta_start == 9, length == 1 };
+	media_props_sep { delta_line == 0, delta_start == 1, length == 1 };
+	prop_name { delta_line == 0, delta_start == 1, length == 5 };
+	prop_equal { delta_line == 0, delta_start == 5, length == 1 };
+	prop_value { delta_line == 0, delta_start == 1, length == 5 };
+	media_props_sep { delta_line == 0, delta_start == 5, length == 1 };
+	// Invalid media are still highlighted
+	media_sep { delta_line == 1, delta_start == 0, length == 1 };
+	media_refname_sep { delta_line == 0, delta_start == 1, length == 1 };
+	media_refname { delta_line == 0, delta_start == 1, length == 3 };
+	media_refname_sep { delta_line == 0, delta_start == 3, length == 1 };
+	media_uri_sep { delta_line == 0, delta_start == 1, length == 1 };
+	media_uri { delta_line == 0, delta_start == 1, length == 2 };
+	media_uri_sep { delta_line == 0, delta_start == 2, length == 1 };
+	);
+}
+
+/// Creates a jpeg with an EXIF description and artist, and an optional IPTC caption
+fn jpeg(description: &str, artist: &str, iptc_caption: Option<&str>) -> Vec<u8> {
+	let mut tiff = b"II*\0\x08\0\0\0\x02\0".to_vec();
+	let mut offset = 8 + 2 + 2 * 12 + 4;
+	for (tag, value) in [(0x010Eu16, description), (0x013B, artist)] {
+		let len = value.len() as u32 + 1;
+		tiff.extend(tag.to_le_bytes());
+		tiff.extend(2u16.to_le_bytes());
+		tiff.extend(len.to_le_bytes());
+		tiff.extend((offset as u32).to_le_bytes());
+		offset += len as usize;
+	}
+	tiff.extend([0; 4]);
+	for value in [description, artist] {
+		tiff.extend(value.bytes().chain([0]));
+	}
+
+	let mut data = vec![0xFF, 0xD8];
+	let mut segment = |marker: u8, payload: Vec<u8>| {
+		data.extend([0xFF, marker]);
+		data.extend((payload.len() as u16 + 2).to_be_bytes());
+		data.extend(payload);
+	};
+	segment(0xE1, [b"Exif\0\0".to_vec(), tiff].concat());
+	// 640x480 frame
+	segment(0xC0, vec![8, 0x01, 0xE0, 0x02, 0x80, 1, 1, 0x11, 0]);
+	if let Some(caption) = iptc_caption {
+		let mut iptc = vec![0x1C, 2, 120];
+		iptc.extend((caption.len() as u16).to_be_bytes());
+		iptc.extend(caption.bytes());
+		let mut resources = b"Photoshop 3.0\08BIM\x04\x04\0\0".to_vec();
+		resources.extend((iptc.len() as u32).to_be_bytes());
+		resources.extend(iptc);
+		segment(0xED, resources);
+	}
+	data.extend([0xFF, 0xDA]);
+	data
+}
+
+#[test]
+fn read_metadata() {
+	assert_eq!(
+		ImageMetadata::read(jpeg("Description", "Artist", None).as_slice()),
+		ImageMetadata {
+			caption: Some("Description".into()),
+			author: Some("Artist".into()),
+		}
+	);
+	assert_eq!(
+		ImageMetadata::read(jpeg("Description", "Artist", Some("Caption")).as_slice()),
+		ImageMetadata {
+			caption: Some("Caption".into()),
+			author: Some("Artist".into()),
+		}
+	);
+	assert_eq!(ImageMetadata::read(b"\x89PNG"), ImageMetadata::default());
+}
+
+#[test]
+fn metadata() {
+	let dir = TempDir::new("media-metadata");
+	let path = dir.join("image.jpg");
+	std::fs::write(&path, jpeg("A <red> flower", "Jane", None)).unwrap();
+	let path = path.to_str().unwrap();
+
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		format!(
+			r#"
+![first]({path})[alt=Flower]
+![second]({path})[alt=Flower, caption=Given]
+![third]({path})[alt=Flower, metadata=false]
+"#
+		),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+
+	assert!(compiled
+		.body
+		.contains(r#"(1)  A &lt;red&gt; flower <span class="medium-author">Jane</span></p>"#));
+	assert!(compiled.body.contains(r#"(2)  Given</p>"#));
+	assert!(compiled.body.contains(r#"(3) </p>"#));
+
+	// Project switch
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		format!("@media.metadata = false\n![first]({path})[alt=Flower]\n"),
+		None,
+	));
+	std::fs::write(path, jpeg("Description", "Jane", None)).unwrap();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(!compiled.body.contains("Description"));
+	assert!(!compiled.body.contains("medium-author"));
+}
+
+#[test]
+fn thumbnail_size() {
+	assert_eq!("none".parse(), Ok(ThumbnailSize::Disabled));
+	assert_eq!(" 800x600".parse(), Ok(ThumbnailSize::Max(800, 600)));
+	assert!("800".parse::<ThumbnailSize>().is_err());
+	assert!("0x600".parse::<ThumbnailSize>().is_err());
+
+	assert_eq!(
+		image_dimensions(jpeg("", "", None).as_slice()),
+		Some((640, 480))
+	);
+	assert_eq!(
+		image_dimensions(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\x01\0\0\0\0\x80"),
+		Some((256, 128))
+	);
+	assert_eq!(image_dimensions(b"GIF89a\x20\0\x10\0"), Some((32, 16)));
+}
+
+#[test]
+fn thumbnail() {
+	let dir = TempDir::new("media-thumbnail");
+	let path = dir.join("image.jpg");
+	std::fs::write(&path, jpeg("", "", None)).unwrap();
+	let thumbnail_path = Thumbnail::new(&path, 320, 320).unwrap().unwrap().path();
+	assert!(Thumbnail::new(&path, 640, 480).unwrap().is_none());
+	let path = path.to_str().unwrap();
+
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		format!(
+			r#"
+@media.thumbnail = 320x320
+![first]({path})[alt=Image]
+![second]({path})[alt=Image, thumbnail=none]
+"#
+		),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	// `magick` is refused, the image is displayed in full
+	let exec = ExecPolicy {
+		disabled: true,
+		..Default::default()
+	};
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(!compiled.body.contains("medium-thumbnail"));
+
+	// Existing thumbnail
+	std::fs::write(&thumbnail_path, []).unwrap();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+
+	let thumbnail_path = thumbnail_path.to_str().unwrap();
+	assert!(compiled.body.contains(&format!(
+		r##"<a class="medium-thumbnail" href="#lightbox-medium-1"><img src="{thumbnail_path}" alt="Image"></a>"##
+	)));
+	assert!(compiled.body.contains(&format!(
+		r##"<a class="medium-lightbox" id="lightbox-medium-1" href="#medium-1"><img src="{path}" alt="Image" loading="lazy"></a>"##
+	)));
+	assert!(compiled.body.contains(&format!(
+		r#"<a href="{path}"><img src="{path}" alt="Image"></a>"#
+	)));
+}
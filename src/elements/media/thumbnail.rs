@@ -0,0 +1,190 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha512;
+
+use crate::cache::cache::Cached;
+use crate::compiler::compiler::Compiler;
+use crate::compiler::exec::run;
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
+use crate::parser::source::Token;
+
+use super::metadata::image_dimensions;
+
+/// Name of the variable setting the maximum dimensions of thumbnails
+pub const THUMBNAIL_VARIABLE: &str = "media.thumbnail";
+
+/// Timeout for generating a thumbnail
+const THUMBNAIL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum dimensions of the thumbnails of an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThumbnailSize {
+	/// Images are displayed in full
+	Disabled,
+	/// Images larger than (width, height) are displayed as thumbnails
+	Max(usize, usize),
+}
+
+impl FromStr for ThumbnailSize {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
+		if s == "none" {
+			return Ok(Self::Disabled);
+		}
+		let size = s.split_once('x').and_then(|(width, height)| {
+			Some((width.parse::<usize>().ok()?, height.parse::<usize>().ok()?))
+		});
+		match size {
+			Some((width, height)) if width != 0 && height != 0 => Ok(Self::Max(width, height)),
+			_ => Err(format!(
+				"Invalid thumbnail size `{s}`, expected `none` or `WIDTHxHEIGHT`"
+			)),
+		}
+	}
+}
+
+/// Thumbnail of a local image
+///
+/// Thumbnails are generated next to their image using ImageMagick's `magick`, their file name
+/// contains the image's hash so editing the image invalidates them.
+pub struct Thumbnail {
+	/// Content of the image
+	data: Vec<u8>,
+	/// Path to the image
+	path: PathBuf,
+	/// Maximum width of the thumbnail
+	width: usize,
+	/// Maximum height of the thumbnail
+	height: usize,
+}
+
+impl Thumbnail {
+	/// Reads the image at `path`, returns `None` when it fits in (`width`, `height`) or its
+	/// dimensions are unknown
+	pub fn new(path: &Path, width: usize, height: usize) -> Result<Option<Self>, String> {
+		let data = std::fs::read(path)
+			.map_err(|err| format!("Unable to read `{}`: {err}", path.display()))?;
+		let Some((image_width, image_height)) = image_dimensions(data.as_slice()) else {
+			return Ok(None);
+		};
+		if image_width <= width && image_height <= height {
+			return Ok(None);
+		}
+
+		Ok(Some(Self {
+			data,
+			path: path.to_path_buf(),
+			width,
+			height,
+		}))
+	}
+
+	/// Path of the thumbnail, in the image's directory
+	pub fn path(&self) -> PathBuf {
+		let stem = self
+			.path
+			.file_stem()
+			.map_or(String::new(), |stem| stem.to_string_lossy().to_string());
+		let extension = self
+			.path
+			.extension()
+			.map_or(String::new(), |ext| format!(".{}", ext.to_string_lossy()));
+		self.path.with_file_name(format!(
+			"{stem}.thumb-{}x{}-{}{extension}",
+			self.width,
+			self.height,
+			&self.key()[..16]
+		))
+	}
+
+	/// Gets the path to the thumbnail, generating it if needed
+	///
+	/// Returns `None` when `magick` is refused, images are then displayed in full.
+	pub fn generate(
+		&self,
+		compiler: &Compiler,
+		location: &Token,
+	) -> Result<Option<String>, String> {
+		let path = self.path().to_string_lossy().to_string();
+		if let Some(con) = compiler.cache() {
+			match self.lookup(con) {
+				Ok(Some(path)) if Path::new(&path).exists() => return Ok(Some(path)),
+				Ok(_) => {}
+				Err(e) => return Err(format!("Querying the cache failed: {e}")),
+			}
+		} else if Path::new(&path).exists() {
+			return Ok(Some(path));
+		}
+
+		let exec = match compiler.exec_policy().resolve("magick") {
+			Ok(exec) => exec,
+			Err(err) => {
+				let mut reports = vec![];
+				report_warn!(
+					&mut reports,
+					location.source(),
+					"External Command Refused".into(),
+					span(location.range.clone(), err),
+					note("The image is displayed in full".into())
+				);
+				compiler.report(reports);
+				return Ok(None);
+			}
+		};
+
+		compiler.log(format!("Generating thumbnail `{path}`...").as_str());
+		let size = format!("{}x{}>", self.width, self.height);
+		run(
+			&exec,
+			&[
+				self.path.to_string_lossy().as_ref(),
+				"-thumbnail",
+				size.as_str(),
+				path.as_str(),
+			],
+			"",
+			THUMBNAIL_TIMEOUT,
+		)?;
+
+		if let Some(con) = compiler.cache() {
+			self.insert(con, &path)
+				.map_err(|e| format!("Querying the cache failed: {e}"))?;
+		}
+		Ok(Some(path))
+	}
+}
+
+impl Cached for Thumbnail {
+	type Key = String;
+	type Value = String;
+
+	fn sql_table() -> &'static str {
+		"CREATE TABLE IF NOT EXISTS cached_media_thumbnails (
+				digest TEXT PRIMARY KEY,
+				path   TEXT NOT NULL);"
+	}
+
+	fn sql_get_query() -> &'static str {
+		"SELECT path FROM cached_media_thumbnails WHERE digest = (?1)"
+	}
+
+	fn sql_insert_query() -> &'static str {
+		// Replaces thumbnails that were removed
+		"INSERT OR REPLACE INTO cached_media_thumbnails (digest, path) VALUES (?1, ?2)"
+	}
+
+	fn key(&self) -> <Self as Cached>::Key {
+		let mut hasher = Sha512::new();
+		hasher.input(self.data.as_slice());
+		hasher.input(format!("{}x{}", self.width, self.height).as_bytes());
+
+		hasher.result_str()
+	}
+}
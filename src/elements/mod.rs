@@ -1,17 +1,23 @@
 pub mod block;
 pub mod code;
+pub mod color;
 pub mod comment;
+pub mod conditional;
 pub mod customstyle;
+pub mod diff;
 pub mod elemstyle;
 pub mod graphviz;
+pub mod icon;
 pub mod import;
 pub mod layout;
+pub mod linebreak;
 pub mod link;
 pub mod list;
 pub mod media;
 pub mod paragraph;
 pub mod raw;
 pub mod reference;
+pub mod render;
 pub mod script;
 pub mod section;
 pub mod style;
@@ -1,11 +1,72 @@
 use crate::compiler::compiler::Compiler;
 use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
 use crate::document::document::Document;
 use crate::document::element::ContainerElement;
 use crate::document::element::ElemKind;
 use crate::document::element::Element;
 use crate::parser::source::Token;
 
+/// Separator inserted for soft line breaks in [`LineBreaks::Preserve`] mode, compiled to `<br>`
+pub const LINE_SEPARATOR: char = '\u{2028}';
+
+/// How soft line breaks (single newlines) inside paragraphs are handled
+///
+/// The mode is set by variable `paragraph.line_breaks`, blank lines always separate paragraphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineBreaks {
+	/// Lines are joined with a space
+	#[default]
+	Join,
+	/// Lines are kept, separated by a [`LINE_SEPARATOR`]
+	Preserve,
+	/// Lines are joined with a space, and spaces around French punctuation are replaced by
+	/// non-breaking spaces, see [`french_spacing`]
+	French,
+}
+
+impl LineBreaks {
+	/// Gets the mode for `document`, unknown values use [`LineBreaks::Join`]
+	pub fn from_document(document: &dyn Document) -> Self {
+		match document
+			.get_variable("paragraph.line_breaks")
+			.map(|var| var.to_string())
+			.as_deref()
+		{
+			Some("preserve") => LineBreaks::Preserve,
+			Some("french") => LineBreaks::French,
+			_ => LineBreaks::Join,
+		}
+	}
+}
+
+/// Replaces the spaces before `;`, `!`, `?` by narrow non-breaking spaces, and the spaces before
+/// `:` and `»` or after `«` by non-breaking spaces
+///
+/// Missing spaces are not added, so urls and times (e.g `12:30`) are left unchanged.
+pub fn french_spacing(text: &str) -> String {
+	let mut result = String::with_capacity(text.len());
+	let mut chars = text.chars().peekable();
+	let mut after_quote = false;
+	while let Some(c) = chars.next() {
+		if c == ' ' {
+			if after_quote {
+				result.push('\u{a0}');
+				continue;
+			}
+			match chars.peek() {
+				Some(';' | '!' | '?') => result.push('\u{202f}'),
+				Some(':' | '»') => result.push('\u{a0}'),
+				_ => result.push(c),
+			}
+		} else {
+			result.push(c);
+		}
+		after_quote = c == '«';
+	}
+	result
+}
+
 #[derive(Debug)]
 pub struct Paragraph {
 	pub(crate) location: Token,
@@ -39,7 +100,7 @@ impl Element for Paragraph {
 		}
 
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				if self.content.is_empty() {
 					return Ok(String::new());
 				}
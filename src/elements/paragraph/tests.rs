@@ -46,3 +46,43 @@ Last paragraph
 	};
 	);
 }
+
+#[test]
+fn line_breaks() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+First line
+Second line
+
+@paragraph.line_breaks = preserve
+First line 
+Second line
+
+@paragraph.line_breaks = french
+« Bonjour »
+vous ? Il est 12:30 : déjà !
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+	Paragraph {
+		Text { content == "First line Second line" };
+	};
+	Paragraph {
+		Text { content == "First line\u{2028}Second line" };
+	};
+	Paragraph {
+		Text { content == "«\u{a0}Bonjour\u{a0}» vous\u{202f}? Il est 12:30\u{a0}: déjà\u{202f}!" };
+	};
+	);
+}
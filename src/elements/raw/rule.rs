@@ -1,3 +1,4 @@
+use crate::lua::kernel::Binding;
 use crate::parser::reports::macros::*;
 use crate::parser::reports::*;
 use std::collections::HashMap;
@@ -9,7 +10,6 @@ use document::element::ElemKind;
 use lsp::semantic::Semantics;
 use lua::kernel::CTX;
 use mlua::Error::BadArgument;
-use mlua::Function;
 use mlua::Lua;
 use parser::util::escape_source;
 use parser::util::{self};
@@ -41,7 +41,8 @@ impl Default for RawRule {
 			Property::new(
 				"Element display kind".to_string(),
 				Some("inline".to_string()),
-			),
+			)
+			.primary(),
 		);
 		Self {
 			re: [
@@ -58,6 +59,8 @@ impl RegexRule for RawRule {
 
 	fn previous(&self) -> Option<&'static str> { Some("Variable Substitution") }
 
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> { vec![("Raw", &self.properties)] }
+
 	fn regexes(&self) -> &[regex::Regex] { &self.re }
 
 	fn enabled(&self, _mode: &ParseMode, _id: usize) -> bool { true }
@@ -153,11 +156,13 @@ impl RegexRule for RawRule {
 		reports
 	}
 
-	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
 		let mut bindings = vec![];
 
-		bindings.push((
-			"push".to_string(),
+		bindings.push(Binding::new(
+			"push",
+			"(kind: string, content: string)",
+			"Inserts raw content, `kind` is `inline`, `block`, `special` or `invisible`",
 			lua.create_function(|_, (kind, content): (String, String)| {
 				// Validate kind
 				let kind = match ElemKind::from_str(kind.as_str()) {
@@ -7,10 +7,12 @@ use runtime_format::FormatKeyError;
 use crate::compiler::compiler::Compiler;
 use crate::compiler::compiler::Target;
 use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
 use crate::document::document::Document;
 use crate::document::element::ElemKind;
 use crate::document::element::Element;
 use crate::document::references::CrossReference;
+use crate::elements::paragraph::elem::Paragraph;
 use crate::parser::source::Token;
 
 use super::style::ExternalReferenceStyle;
@@ -19,11 +21,33 @@ use super::style::ExternalReferenceStyle;
 pub struct InternalReference {
 	pub(crate) location: Token,
 	pub(crate) refname: String,
-	pub(crate) caption: Option<String>,
+	pub(crate) caption: Option<Paragraph>,
 }
 
 impl InternalReference {
-	pub fn caption(&self) -> Option<&String> { self.caption.as_ref() }
+	pub fn caption(&self) -> Option<&Paragraph> { self.caption.as_ref() }
+
+	/// Compiles the content of the caption, if any
+	///
+	/// # Parameters
+	///
+	/// `cursor` is the position of the caption in the compiled document
+	pub fn compile_caption(
+		&self,
+		compiler: &Compiler,
+		document: &dyn Document,
+		cursor: usize,
+	) -> Option<Result<String, String>> {
+		self.caption.as_ref().map(|caption| {
+			let mut result = String::new();
+			for elem in &caption.content {
+				result += elem
+					.compile(compiler, document, cursor + result.len())?
+					.as_str();
+			}
+			Ok(result)
+		})
+	}
 }
 
 impl Element for InternalReference {
@@ -37,10 +61,10 @@ impl Element for InternalReference {
 		&self,
 		compiler: &Compiler,
 		document: &dyn Document,
-		_cursor: usize,
+		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let elemref = document
 					.get_reference(self.refname.as_str())
 					.ok_or(format!(
@@ -54,6 +78,7 @@ impl Element for InternalReference {
 					document,
 					self,
 					compiler.reference_id(document, elemref),
+					cursor,
 				)
 			}
 			_ => todo!(""),
@@ -109,7 +134,7 @@ impl Element for ExternalReference {
 		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let mut result = "<a href=\"".to_string();
 
 				// Link position
@@ -1,12 +1,18 @@
 use crate::document::references::CrossReference;
 use crate::parser::reports::macros::*;
 use crate::parser::reports::*;
+use lsp::conceal::ConcealTarget;
+use lsp::conceal::Conceals;
 use lsp::semantic::Semantics;
 use parser::property::PropertyParser;
+use parser::source::VirtualSource;
 use parser::util::escape_source;
+use parser::util::parse_paragraph;
 use regex::Captures;
 use regex::Regex;
+use serde_json::json;
 use std::collections::HashMap;
+use std::rc::Rc;
 
 use crate::document::document::Document;
 use crate::document::references::validate_refname;
@@ -32,7 +38,7 @@ impl Default for ReferenceRule {
 		let mut props = HashMap::new();
 		props.insert(
 			"caption".to_string(),
-			Property::new("Override the display of the reference".to_string(), None),
+			Property::new("Override the display of the reference".to_string(), None).primary(),
 		);
 		Self {
 			re: [Regex::new(r"&\{(.*?)\}(?:\[((?:\\.|[^\\\\])*?)\])?").unwrap()],
@@ -46,6 +52,10 @@ impl RegexRule for ReferenceRule {
 
 	fn previous(&self) -> Option<&'static str> { Some("Text") }
 
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> {
+		vec![("Reference", &self.properties)]
+	}
+
 	fn regexes(&self) -> &[regex::Regex] { &self.re }
 
 	fn enabled(&self, _mode: &ParseMode, _id: usize) -> bool { true }
@@ -60,6 +70,28 @@ impl RegexRule for ReferenceRule {
 	) -> Vec<Report> {
 		let mut reports = vec![];
 
+		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
+			let link = matches.get(1).unwrap().range();
+			sems.add(link.start - 2..link.start - 1, tokens.reference_operator);
+			sems.add(link.start - 1..link.start, tokens.reference_link_sep);
+			match matches.get(1).unwrap().as_str().find('#') {
+				// External reference
+				Some(sep) => {
+					if sep != 0 {
+						sems.add(link.start..link.start + sep, tokens.reference_doc);
+					}
+					sems.add(
+						link.start + sep..link.start + sep + 1,
+						tokens.reference_doc_sep,
+					);
+					sems.add(link.start + sep + 1..link.end, tokens.reference_link);
+				}
+				// Internal reference
+				None => sems.add(link.clone(), tokens.reference_link),
+			}
+			sems.add(link.end..link.end + 1, tokens.reference_link_sep);
+		}
+
 		let (refdoc, refname) = if let Some(refname_match) = matches.get(1) {
 			if let Some(sep) = refname_match.as_str().find('#')
 			// External reference
@@ -106,23 +138,61 @@ impl RegexRule for ReferenceRule {
 			'\\',
 			"]",
 		);
-		let properties = match self.properties.parse(
+		let properties = self.properties.parse(
 			"Reference",
 			&mut reports,
 			state,
-			Token::new(0..prop_source.content().len(), prop_source),
+			Token::new(0..prop_source.content().len(), prop_source.clone()),
+		);
+		if let (Some((sems, tokens)), Some(props)) = (
+			Semantics::from_source(token.source(), &state.shared.lsp),
+			matches.get(2).map(|m| m.range()),
 		) {
+			sems.add(props.start - 1..props.start, tokens.reference_props_sep);
+			// Queued, as the caption may be parsed after
+			sems.add_to_queue(props.end..props.end + 1, tokens.reference_props_sep);
+		}
+		let properties = match properties {
 			Some(props) => props,
 			None => return reports,
 		};
 
 		let caption = match properties.get_opt(&mut reports, "caption", |_, value| {
-			Result::<_, String>::Ok(value.value.clone())
+			Result::<_, String>::Ok((value.value_range.clone(), value.value.clone()))
 		}) {
 			Some(caption) => caption,
 			None => return reports,
 		};
 
+		// Conceals
+		if let Some(conceals) = Conceals::from_source(token.source(), &state.shared.lsp) {
+			let link = matches.get(1).unwrap().range();
+			conceals.add(
+				link.start - 2..link.end + 1,
+				"reference",
+				ConcealTarget::Token {
+					token: "reference".into(),
+					params: json!({
+						"document": refdoc,
+						"refname": refname,
+						"caption": caption.as_ref().map(|(_, caption)| caption),
+					}),
+				},
+			);
+			// Conceals cannot span multiple lines
+			if let Some(props) = matches
+				.get(2)
+				.map(|m| m.range())
+				.filter(|props| !token.source().content()[props.clone()].contains('\n'))
+			{
+				conceals.add(
+					props.start - 1..props.end + 1,
+					"reference",
+					ConcealTarget::Text("".into()),
+				);
+			}
+		}
+
 		if let Some(refdoc) = refdoc {
 			// Get style
 			let style = state
@@ -133,6 +203,8 @@ impl RegexRule for ReferenceRule {
 				.downcast_rc::<ExternalReferenceStyle>()
 				.unwrap();
 
+			let caption = caption.map(|(_, caption)| caption);
+
 			// &{#refname}
 			if refdoc.is_empty() {
 				state.push(
@@ -156,27 +228,37 @@ impl RegexRule for ReferenceRule {
 					}),
 				);
 			}
-
-			if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp)
-			{
-				let link = matches.get(1).unwrap().range();
-				sems.add(link.start - 2..link.start - 1, tokens.reference_operator);
-				sems.add(link.start - 1..link.start, tokens.reference_link_sep);
-
-				if !refdoc.is_empty() {
-					sems.add(link.start..refdoc.len() + link.start, tokens.reference_doc);
-				}
-				sems.add(
-					refdoc.len() + link.start..refdoc.len() + link.start + 1,
-					tokens.reference_doc_sep,
-				);
-				sems.add(
-					refdoc.len() + link.start + 1..link.end,
-					tokens.reference_link,
-				);
-				sems.add(link.end..link.end + 1, tokens.reference_link_sep);
-			}
 		} else {
+			// Parse caption
+			let caption = match caption {
+				Some((range, caption)) => {
+					let raw = &prop_source.content()[range.clone()];
+					let start = range.start + (raw.len() - raw.trim_start().len());
+					let source = Rc::new(VirtualSource::new(
+						Token::new(start..start + caption.len(), prop_source.clone()),
+						"Reference Caption".into(),
+						caption,
+					));
+					match parse_paragraph(state, source, document) {
+						Ok(paragraph) => Some(*paragraph),
+						Err(err) => {
+							report_err!(
+								&mut reports,
+								token.source(),
+								"Invalid Reference Caption".into(),
+								span(
+									prop_source.clone(),
+									range,
+									format!("Failed to parse reference caption: {err}")
+								)
+							);
+							return reports;
+						}
+					}
+				}
+				None => None,
+			};
+
 			state.push(
 				document,
 				Box::new(InternalReference {
@@ -185,23 +267,6 @@ impl RegexRule for ReferenceRule {
 					caption,
 				}),
 			);
-
-			if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp)
-			{
-				let link = matches.get(1).unwrap().range();
-				sems.add(link.start - 2..link.start - 1, tokens.reference_operator);
-				sems.add(link.start - 1..link.start, tokens.reference_link_sep);
-				sems.add(link.clone(), tokens.reference_link);
-				sems.add(link.end..link.end + 1, tokens.reference_link_sep);
-			}
-		}
-
-		if let (Some((sems, tokens)), Some(props)) = (
-			Semantics::from_source(token.source(), &state.shared.lsp),
-			matches.get(2).map(|m| m.range()),
-		) {
-			sems.add(props.start - 1..props.start, tokens.reference_props_sep);
-			sems.add(props.end..props.end + 1, tokens.reference_props_sep);
 		}
 
 		reports
@@ -7,12 +7,14 @@ use crate::elements::paragraph::elem::Paragraph;
 use crate::elements::reference::elem::ExternalReference;
 use crate::elements::reference::elem::InternalReference;
 use crate::elements::section::elem::Section;
+use crate::elements::text::elem::Text;
 use crate::parser::langparser::LangParser;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::Parser;
 use crate::parser::parser::ParserState;
 use crate::parser::source::SourceFile;
 use crate::validate_document;
+use crate::validate_semantics;
 
 #[test]
 pub fn parse_internal() {
@@ -41,13 +43,50 @@ pub fn parse_internal() {
 	validate_document!(doc.content().borrow(), 0,
 		Section;
 		Paragraph {
-			InternalReference { refname == "ref", caption == Some("Section".to_string()) };
-			InternalReference { refname == "ref", caption == Some("Another".to_string()) };
-			InternalReference { refname == "ref2", caption == Some("Before".to_string()) };
+			InternalReference { refname == "ref" };
+			InternalReference { refname == "ref" };
+			InternalReference { refname == "ref2" };
 		};
 		Paragraph;
 		Section;
 	);
+
+	let borrow = doc.content().borrow();
+	let captions = borrow[1]
+		.as_container()
+		.unwrap()
+		.contained()
+		.iter()
+		.filter_map(|elem| elem.downcast_ref::<InternalReference>())
+		.map(|reference| {
+			reference.caption().unwrap().content[0]
+				.downcast_ref::<Text>()
+				.unwrap()
+				.content
+				.clone()
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(captions, vec!["Section", "Another", "Before"]);
+}
+
+#[test]
+pub fn rich_caption() {
+	let result = process_from_memory(
+		Target::HTML,
+		vec![r#"
+@html.page_title = 0
+
+#{ref} Referenceable section
+
+&{ref}[caption=**Bold** section]
+"#
+		.into()],
+	)
+	.unwrap();
+
+	assert!(result[0].0.borrow().body.contains(
+		"<a class=\"section-reference\" href=\"#Referenceable_section\"><b>Bold</b> section</a>"
+	));
 }
 
 #[test]
@@ -121,3 +160,41 @@ pub fn test_external() {
 	assert!(result[1].0.borrow().body.starts_with("<div class=\"content\"><p><a href=\"a.html#Referenceable_section\">(#ref)</a><a href=\"a.html#Referenceable_section\">(a#ref)</a></p>"));
 	assert!(result[2].0.borrow().body.starts_with("<div class=\"content\"><p><a href=\"a.html#Referenceable_section\">from 0</a><a href=\"a.html#Referenceable_section\">[UNSPECIFIC ref]</a><a href=\"b.html#Another_Referenceable_section\">from 1</a><a href=\"b.html#Another_Referenceable_section\">[SPECIFIC b:ref2]</a></p>"));
 }
+
+#[test]
+fn semantic() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+&{ref}[caption=Hi]
+&{doc#bad ref}
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+	validate_semantics!(state, source.clone(), 0,
+	reference_operator { delta_line == 1, delta_start == 0, length == 1 };
+	reference_link_sep { delta_line == 0, delta_start == 1, length == 1 };
+	reference_link { delta_line == 0, delta_start == 1, length == 3 };
+	reference_link_sep { delta_line == 0, delta_start == 3, length == 1 };
+	reference_props_sep { delta_line == 0, delta_start == 1, length == 1 };
+	prop_name { delta_line == 0, delta_start == 1, length == 7 };
+	prop_equal { delta_line == 0, delta_start == 7, length == 1 };
+	prop_value { delta_line == 0, delta_start == 1, length == 2 };
+	reference_props_sep { delta_line == 0, delta_start == 2, length == 1 };
+	// Invalid references are still highlighted
+	reference_operator { delta_line == 1, delta_start == 0, length == 1 };
+	reference_link_sep { delta_line == 0, delta_start == 1, length == 1 };
+	reference_doc { delta_line == 0, delta_start == 1, length == 3 };
+	reference_doc_sep { delta_line == 0, delta_start == 3, length == 1 };
+	reference_link { delta_line == 0, delta_start == 1, length == 7 };
+	reference_link_sep { delta_line == 0, delta_start == 7, length == 1 };
+	);
+}
@@ -0,0 +1,381 @@
+use std::fs::OpenOptions;
+use std::io::ErrorKind;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha512;
+use serde::Deserialize;
+
+use crate::cache::cache::Cached;
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
+use crate::compiler::exec::run;
+use crate::document::document::Document;
+use crate::document::element::ElemKind;
+use crate::document::element::Element;
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
+use crate::parser::source::Token;
+
+/// Time after which a renderer is aborted, unless set by `render.{name}.timeout`
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How the content of a render block is passed to its renderer
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderInput {
+	/// Content is written to the renderer's standard input
+	#[default]
+	Stdin,
+	/// Content is written to a temporary file, whose path replaces `{input}` in the arguments
+	File,
+}
+
+impl RenderInput {
+	/// Gets the name of the mode, as set by `render.{name}.input`
+	pub fn name(&self) -> &'static str {
+		match self {
+			RenderInput::Stdin => "stdin",
+			RenderInput::File => "file",
+		}
+	}
+
+	fn parse(value: &str) -> Result<Self, String> {
+		match value.trim() {
+			"stdin" => Ok(RenderInput::Stdin),
+			"file" => Ok(RenderInput::File),
+			_ => Err(format!(
+				"Unknown input mode `{value}`, expected `stdin` or `file`"
+			)),
+		}
+	}
+}
+
+/// How the standard output of a renderer is inserted in the page
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderOutput {
+	/// An svg image, anything before the `<svg` tag is removed
+	#[default]
+	Svg,
+	/// Raw html
+	Html,
+	/// Preformatted text
+	Text,
+}
+
+impl RenderOutput {
+	/// Gets the name of the output type, as set by `render.{name}.output`
+	pub fn name(&self) -> &'static str {
+		match self {
+			RenderOutput::Svg => "svg",
+			RenderOutput::Html => "html",
+			RenderOutput::Text => "text",
+		}
+	}
+
+	fn parse(value: &str) -> Result<Self, String> {
+		match value.trim() {
+			"svg" => Ok(RenderOutput::Svg),
+			"html" => Ok(RenderOutput::Html),
+			"text" => Ok(RenderOutput::Text),
+			_ => Err(format!(
+				"Unknown output type `{value}`, expected `svg`, `html` or `text`"
+			)),
+		}
+	}
+}
+
+/// External program rendering the content of render blocks
+///
+/// Renderers are defined by the `render.{name}.*` variables, usually set from the `[render]`
+/// tables of the project file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Renderer {
+	/// Executable, resolved by the execution policy
+	pub command: String,
+	/// Arguments of the executable
+	pub args: Vec<String>,
+	pub input: RenderInput,
+	pub output: RenderOutput,
+	/// Whether results are stored in the cache database
+	pub cache: bool,
+	pub timeout: Duration,
+}
+
+impl Renderer {
+	/// Gets the variable defining `key` for renderer `name`
+	pub fn variable(name: &str, key: &str) -> String { format!("render.{name}.{key}") }
+
+	/// Checks that `name` can name a renderer, names may only contain letters, digits, `_` and `-`
+	pub fn validate_name(name: &str) -> Result<(), String> {
+		if name.is_empty() {
+			return Err("Renderer name must not be empty".into());
+		}
+		if let Some(c) = name
+			.chars()
+			.find(|c| !c.is_ascii_alphanumeric() && *c != '_' && *c != '-')
+		{
+			return Err(format!(
+				"Renderer name `{name}` cannot contain `{c}`, only letters, digits, `_` and `-` are allowed"
+			));
+		}
+		Ok(())
+	}
+
+	/// Reads renderer `name` from the variables of `document`
+	///
+	/// # Errors
+	///
+	/// Returns an error when the renderer isn't defined, or when one of its variables is invalid.
+	pub fn from_document(document: &dyn Document, name: &str) -> Result<Self, String> {
+		let get = |key: &str| {
+			document
+				.get_variable(Self::variable(name, key).as_str())
+				.map(|var| var.to_string())
+		};
+		let invalid = |key: &str, err: String| {
+			format!("Invalid value for `{}`: {err}", Self::variable(name, key))
+		};
+
+		let command = get("command")
+			.filter(|command| !command.trim().is_empty())
+			.ok_or(format!(
+				"Renderer `{name}` is not defined, `{}` is not set",
+				Self::variable(name, "command")
+			))?;
+		let args = get("args").map_or(vec![], |args| {
+			args.split_whitespace().map(str::to_string).collect()
+		});
+		let input = get("input")
+			.map(|input| RenderInput::parse(&input).map_err(|err| invalid("input", err)))
+			.transpose()?
+			.unwrap_or_default();
+		let output = get("output")
+			.map(|output| RenderOutput::parse(&output).map_err(|err| invalid("output", err)))
+			.transpose()?
+			.unwrap_or_default();
+		let cache = get("cache")
+			.map(|cache| {
+				cache
+					.trim()
+					.parse::<bool>()
+					.map_err(|err| invalid("cache", err.to_string()))
+			})
+			.transpose()?
+			.unwrap_or(true);
+		let timeout = get("timeout")
+			.map(|timeout| {
+				timeout
+					.trim()
+					.parse::<f64>()
+					.ok()
+					.and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+					.filter(|timeout| !timeout.is_zero())
+					.ok_or(invalid(
+						"timeout",
+						format!("expected a positive number of seconds, got `{timeout}`"),
+					))
+			})
+			.transpose()?
+			.unwrap_or(DEFAULT_TIMEOUT);
+
+		Ok(Self {
+			command: command.trim().to_string(),
+			args,
+			input,
+			output,
+			cache,
+			timeout,
+		})
+	}
+
+	/// Runs the renderer `exec` on `content`, and returns its output
+	fn run(&self, exec: &Path, content: &str) -> Result<String, String> {
+		match self.input {
+			RenderInput::Stdin => {
+				let args = self.args.iter().map(String::as_str).collect::<Vec<_>>();
+				run(exec, &args, content, self.timeout)
+			}
+			RenderInput::File => {
+				// Created with `create_new`, so that existing files and symlinks are never written to
+				static COUNTER: AtomicUsize = AtomicUsize::new(0);
+				let (path, mut file) = loop {
+					let path = std::env::temp_dir().join(format!(
+						"nml-render-{}-{}",
+						std::process::id(),
+						COUNTER.fetch_add(1, Ordering::Relaxed)
+					));
+					match OpenOptions::new().write(true).create_new(true).open(&path) {
+						Ok(file) => break (path, file),
+						Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+						Err(err) => {
+							return Err(format!("Unable to create `{}`: {err}", path.display()))
+						}
+					}
+				};
+				let written = file.write_all(content.as_bytes());
+				drop(file);
+				if let Err(err) = written {
+					let _ = std::fs::remove_file(&path);
+					return Err(format!("Unable to write `{}`: {err}", path.display()));
+				}
+
+				let input = path.to_string_lossy().to_string();
+				let mut args = self
+					.args
+					.iter()
+					.map(|arg| arg.replace("{input}", &input))
+					.collect::<Vec<_>>();
+				if !self.args.iter().any(|arg| arg.contains("{input}")) {
+					args.push(input);
+				}
+				let result = run(
+					exec,
+					&args.iter().map(String::as_str).collect::<Vec<_>>(),
+					"",
+					self.timeout,
+				);
+				let _ = std::fs::remove_file(&path);
+				result
+			}
+		}
+	}
+}
+
+/// Content rendered by a [`Renderer`], cached by the renderer's settings and the content
+pub(crate) struct RenderJob<'a> {
+	renderer: &'a Renderer,
+	content: &'a str,
+}
+
+impl Cached for RenderJob<'_> {
+	type Key = String;
+	type Value = String;
+
+	fn sql_table() -> &'static str {
+		"CREATE TABLE IF NOT EXISTS cached_render (
+				digest TEXT PRIMARY KEY,
+				output BLOB NOT NULL);"
+	}
+
+	fn sql_get_query() -> &'static str { "SELECT output FROM cached_render WHERE digest = (?1)" }
+
+	fn sql_insert_query() -> &'static str {
+		"INSERT INTO cached_render (digest, output) VALUES (?1, ?2)"
+	}
+
+	fn key(&self) -> <Self as Cached>::Key {
+		let mut hasher = Sha512::new();
+		hasher.input(self.renderer.command.as_bytes());
+		for arg in &self.renderer.args {
+			hasher.input(b"\0");
+			hasher.input(arg.as_bytes());
+		}
+		hasher.input(b"\0");
+		hasher.input(self.renderer.input.name().as_bytes());
+		hasher.input(self.renderer.output.name().as_bytes());
+		hasher.input(b"\0");
+		hasher.input(self.content.as_bytes());
+
+		hasher.result_str()
+	}
+}
+
+#[derive(Debug)]
+pub struct Render {
+	pub(crate) location: Token,
+	/// Name of the renderer, see [`Renderer`]
+	pub(crate) renderer: String,
+	pub(crate) content: String,
+}
+
+impl Render {
+	/// Gets the output of the renderer from the cache, or runs it
+	///
+	/// Content that can't be rendered because the renderer is refused is displayed as source code.
+	fn render(&self, compiler: &Compiler, renderer: &Renderer) -> Result<String, String> {
+		let job = RenderJob {
+			renderer,
+			content: &self.content,
+		};
+		let cache = compiler.cache().filter(|_| renderer.cache);
+		// Cached results remain available when the renderer is refused
+		if let Some(con) = cache {
+			match job.lookup(con) {
+				Ok(Some(output)) => return Ok(output),
+				Ok(None) => {}
+				Err(e) => return Err(format!("Querying the cache failed: {e}")),
+			}
+		}
+		let exec = match compiler.exec_policy().resolve(&renderer.command) {
+			Ok(exec) => exec,
+			Err(err) => {
+				let mut reports = vec![];
+				report_warn!(
+					&mut reports,
+					self.location.source(),
+					"External Command Refused".into(),
+					span(self.location.range.clone(), err),
+					note("The content is displayed as source code".into())
+				);
+				compiler.report(reports);
+				return Ok(format!(
+					"<pre class=\"render-placeholder\">{}</pre>",
+					Compiler::sanitize(HTML, &self.content)
+				));
+			}
+		};
+
+		compiler.log(format!("Rendering `{}` with `{}`...", self.content, self.renderer).as_str());
+		let output = renderer.run(&exec, &self.content)?;
+		let output = match renderer.output {
+			RenderOutput::Svg => {
+				let start = output.find("<svg").ok_or(format!(
+					"Renderer `{}` did not output an svg image",
+					self.renderer
+				))?;
+				output[start..].trim_end().to_string()
+			}
+			RenderOutput::Html => output,
+			RenderOutput::Text => format!("<pre>{}</pre>", Compiler::sanitize(HTML, &output)),
+		};
+		if let Some(con) = cache {
+			job.insert(con, &output)
+				.map_err(|e| format!("Querying the cache failed: {e}"))?;
+		}
+		Ok(output)
+	}
+}
+
+impl Element for Render {
+	fn location(&self) -> &Token { &self.location }
+
+	fn kind(&self) -> ElemKind { ElemKind::Block }
+
+	fn element_name(&self) -> &'static str { "Render" }
+
+	fn compile(
+		&self,
+		compiler: &Compiler,
+		document: &dyn Document,
+		_cursor: usize,
+	) -> Result<String, String> {
+		match compiler.target() {
+			HTML | SLIDES => {
+				let renderer = Renderer::from_document(document, &self.renderer)?;
+				Ok(format!(
+					"<div class=\"render render-{}\">{}</div>",
+					Compiler::sanitize(HTML, &self.renderer),
+					self.render(compiler, &renderer)?
+				))
+			}
+			_ => todo!("Unimplemented"),
+		}
+	}
+}
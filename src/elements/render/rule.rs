@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use crate::document::document::Document;
+use crate::lua::kernel::Binding;
+use crate::parser::parser::ParseMode;
+use crate::parser::parser::ParserState;
+use crate::parser::property::PropertyParser;
+use crate::parser::reports::macros::*;
+use crate::parser::reports::Report;
+use crate::parser::reports::*;
+use crate::parser::rule::RegexRule;
+use crate::parser::source::Token;
+use ariadne::Fmt;
+use lsp::semantic::Semantics;
+use lua::kernel::CTX;
+use mlua::Lua;
+use parser::property::Property;
+use parser::util::escape_source;
+use regex::Captures;
+use regex::Regex;
+use regex::RegexBuilder;
+
+use super::elem::Render;
+use super::elem::Renderer;
+
+#[auto_registry::auto_registry(registry = "rules")]
+pub struct RenderRule {
+	re: [Regex; 2],
+	properties: PropertyParser,
+}
+
+impl Default for RenderRule {
+	fn default() -> Self {
+		let mut props = HashMap::new();
+		props.insert(
+			"renderer".to_string(),
+			Property::new(
+				"Renderer defined by the `render.<name>.*` variables".to_string(),
+				None,
+			)
+			.primary(),
+		);
+		Self {
+			re: [
+				RegexBuilder::new(
+					r"(?:^|\n)[^\S\n]*(#\+RENDER)(?:\[((?:\\.|[^\[\]\\])*?)\])?[^\S\n]*$([\s\S]*?)\n[^\S\n]*(#\+RENDER_END)[^\S\n]*$",
+				)
+				.multi_line(true)
+				.build()
+				.unwrap(),
+				RegexBuilder::new(r"(?:^|\n)[^\S\n]*(#\+RENDER(?:_END)?)\b.*$")
+					.multi_line(true)
+					.build()
+					.unwrap(),
+			],
+			properties: PropertyParser { properties: props },
+		}
+	}
+}
+
+impl RegexRule for RenderRule {
+	fn name(&self) -> &'static str { "Render" }
+
+	fn previous(&self) -> Option<&'static str> { Some("Graphviz") }
+
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> {
+		vec![("Render", &self.properties)]
+	}
+
+	fn regexes(&self) -> &[Regex] { &self.re }
+
+	fn enabled(&self, mode: &ParseMode, _id: usize) -> bool { !mode.paragraph_only }
+
+	fn on_regex_match(
+		&self,
+		index: usize,
+		state: &ParserState,
+		document: &dyn Document,
+		token: Token,
+		matches: Captures,
+	) -> Vec<Report> {
+		let mut reports = vec![];
+
+		// Delimiter without a matching `#+RENDER` or `#+RENDER_END`
+		if index == 1 {
+			let delimiter = matches.get(1).unwrap();
+			let message = if delimiter.as_str() == "#+RENDER" {
+				format!(
+					"Missing `{}` for this render block",
+					"#+RENDER_END".fg(state.parser.colors().highlight)
+				)
+			} else {
+				format!(
+					"`{}` has no matching `{}`",
+					delimiter.as_str().fg(state.parser.colors().highlight),
+					"#+RENDER".fg(state.parser.colors().highlight)
+				)
+			};
+			report_err!(
+				&mut reports,
+				token.source(),
+				"Invalid Render Block".into(),
+				span(delimiter.range(), message)
+			);
+			return reports;
+		}
+
+		// Properties
+		let prop_source = escape_source(
+			token.source(),
+			matches.get(2).map_or(0..0, |m| m.range()),
+			"Render Properties".into(),
+			'\\',
+			"]",
+		);
+		let properties =
+			match self
+				.properties
+				.parse("Render", &mut reports, state, prop_source.into())
+			{
+				Some(props) => props,
+				None => return reports,
+			};
+		let Some((renderer, renderer_range)) =
+			properties.get(&mut reports, "renderer", |_, value| {
+				Renderer::validate_name(value.value.as_str())
+					.map(|_| (value.value.clone(), value.value_range.clone()))
+			})
+		else {
+			return reports;
+		};
+
+		if document
+			.get_variable(Renderer::variable(&renderer, "command").as_str())
+			.is_none()
+		{
+			report_err!(
+				&mut reports,
+				token.source(),
+				"Unknown Renderer".into(),
+				span(
+					renderer_range,
+					format!(
+						"Renderer `{}` is not defined",
+						renderer.as_str().fg(state.parser.colors().highlight)
+					)
+				),
+				help(format!(
+					"Declare it in the project file with a `{}` table, or set `{}`",
+					format!("[render.{renderer}]").fg(state.parser.colors().info),
+					Renderer::variable(&renderer, "command").fg(state.parser.colors().info)
+				))
+			);
+			return reports;
+		}
+
+		let content = matches.get(3).unwrap();
+		let text = content
+			.as_str()
+			.strip_prefix('\n')
+			.unwrap_or(content.as_str());
+		if text.trim().is_empty() {
+			report_err!(
+				&mut reports,
+				token.source(),
+				"Empty Render Content".into(),
+				span(
+					matches.get(1).unwrap().range(),
+					"Render block is empty".into()
+				)
+			);
+			return reports;
+		}
+
+		state.push(
+			document,
+			Box::new(Render {
+				location: Token::new(matches.get(1).unwrap().start()..token.end(), token.source()),
+				renderer,
+				content: text.to_string(),
+			}),
+		);
+
+		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
+			sems.add(matches.get(1).unwrap().range(), tokens.render_sep);
+			if let Some(props) = matches.get(2).map(|m| m.range()) {
+				sems.add(props.start - 1..props.start, tokens.render_props_sep);
+				sems.add(props.end..props.end + 1, tokens.render_props_sep);
+			}
+			let start = content.start() + (content.len() - text.len());
+			if start < content.end() {
+				sems.add(start..content.end(), tokens.render_content);
+			}
+			sems.add(matches.get(4).unwrap().range(), tokens.render_sep);
+		}
+
+		reports
+	}
+
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
+		let mut bindings = vec![];
+		bindings.push(Binding::new(
+			"push",
+			"(renderer: string, content: string)",
+			"Inserts the output of renderer `renderer` for `content`",
+			lua.create_function(|_, (renderer, content): (String, String)| {
+				if let Err(err) = Renderer::validate_name(renderer.as_str()) {
+					return Err(mlua::Error::BadArgument {
+						to: Some("push".to_string()),
+						pos: 1,
+						name: Some("renderer".to_string()),
+						cause: std::sync::Arc::new(mlua::Error::external(err)),
+					});
+				}
+
+				CTX.with_borrow(|ctx| {
+					ctx.as_ref().map(|ctx| {
+						ctx.state.push(
+							ctx.document,
+							Box::new(Render {
+								location: ctx.location.clone(),
+								renderer,
+								content,
+							}),
+						);
+					})
+				});
+
+				Ok(())
+			})
+			.unwrap(),
+		));
+
+		bindings
+	}
+}
@@ -0,0 +1,204 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::cache::cache;
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target;
+use crate::compiler::exec::ExecPolicy;
+use crate::elements::render::elem::Render;
+use crate::parser::langparser::LangParser;
+use crate::parser::parser::ParseMode;
+use crate::parser::parser::Parser;
+use crate::parser::parser::ParserState;
+use crate::parser::source::SourceFile;
+use crate::tempdir::TempDir;
+use crate::validate_document;
+
+#[test]
+fn parse() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@render.music.command = lilypond
+#+RENDER[renderer=music]
+\relative { c' d e }
+#+RENDER_END
+  #+RENDER[music]
+a
+  b
+  #+RENDER_END
+%<nml.render.push("music", "from lua")>%
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Render { renderer == "music", content == "\\relative { c' d e }" };
+		Render { renderer == "music", content == "a\n  b" };
+		Render { renderer == "music", content == "from lua" };
+	);
+}
+
+#[test]
+fn errors() {
+	let reports = RefCell::new(vec![]);
+	let parser = LangParser::new(
+		false,
+		Box::new(|_, list| {
+			reports
+				.borrow_mut()
+				.extend(list.into_iter().map(|report| report.message))
+		}),
+	);
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@render.music.command = lilypond
+#+RENDER[unknown]
+a
+#+RENDER_END
+#+RENDER[renderer=a.b]
+a
+#+RENDER_END
+#+RENDER[music]
+
+#+RENDER_END
+#+RENDER_END
+#+RENDER[music]
+"#
+		.to_string(),
+		None,
+	));
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+	drop(doc);
+	drop(parser);
+
+	assert_eq!(
+		reports.into_inner(),
+		vec![
+			"Unknown Renderer",
+			"Failed to parse Render properties",
+			"Empty Render Content",
+			"Invalid Render Block",
+			"Invalid Render Block",
+		]
+	);
+}
+
+#[test]
+fn disabled() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@render.music.command = lilypond
+#+RENDER[music]
+<c d>
+#+RENDER_END
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let exec = ExecPolicy {
+		disabled: true,
+		..Default::default()
+	};
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled.body.contains(
+		"<div class=\"render render-music\"><pre class=\"render-placeholder\">&lt;c d&gt;</pre></div>"
+	));
+}
+
+#[test]
+#[cfg(all(unix, feature = "exec"))]
+fn render() {
+	use std::os::unix::fs::PermissionsExt;
+
+	let dir = TempDir::new("render-exec");
+	let exec = dir.join("upper");
+	std::fs::write(
+		&exec,
+		"#!/bin/sh\necho 'header'\necho \"<svg>$(cat \"${1:--}\" | tr a-z A-Z)</svg>\"\n",
+	)
+	.unwrap();
+	std::fs::set_permissions(&exec, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+	let con = cache::open_in_memory().unwrap();
+
+	let compile = || {
+		let source = Rc::new(SourceFile::with_content(
+			"".to_string(),
+			format!(
+				r#"
+@render.upper.command = {0}
+@render.file.command = {0}
+@render.file.input = file
+@render.text.command = {0}
+@render.text.output = text
+#+RENDER[upper]
+stdin
+#+RENDER_END
+#+RENDER[file]
+file
+#+RENDER_END
+#+RENDER[text]
+text
+#+RENDER_END
+"#,
+				exec.display()
+			),
+			None,
+		));
+		let parser = LangParser::default();
+		let (doc, _) = parser.parse(
+			ParserState::new(&parser, None),
+			source,
+			None,
+			ParseMode::default(),
+		);
+		let policy = ExecPolicy::default();
+		let compiler = Compiler::new(Target::HTML, Some(&con), &policy);
+		compiler.compile(&*doc).0.body
+	};
+
+	let check = |body: String| {
+		assert!(body.contains("<div class=\"render render-upper\"><svg>STDIN</svg></div>"));
+		assert!(body.contains("<div class=\"render render-file\"><svg>FILE</svg></div>"));
+		assert!(body.contains(
+			"<div class=\"render render-text\"><pre>header\n&lt;svg&gt;TEXT&lt;/svg&gt;\n</pre></div>"
+		));
+	};
+	// Input files already present aren't written to
+	let victim = dir.join("victim");
+	std::fs::write(&victim, "victim").unwrap();
+	let planted = std::env::temp_dir().join(format!("nml-render-{}-0", std::process::id()));
+	std::os::unix::fs::symlink(&victim, &planted).unwrap();
+
+	check(compile());
+	assert_eq!(std::fs::read_to_string(&victim).unwrap(), "victim");
+	std::fs::remove_file(&planted).unwrap();
+	std::fs::remove_file(&exec).unwrap();
+	// Cached
+	check(compile());
+}
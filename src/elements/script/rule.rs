@@ -84,7 +84,7 @@ fn validate_kernel_name(colors: &ReportColors, name: &str) -> Result<String, Str
 impl RegexRule for ScriptRule {
 	fn name(&self) -> &'static str { "Script" }
 
-	fn previous(&self) -> Option<&'static str> { Some("Import") }
+	fn previous(&self) -> Option<&'static str> { Some("Conditional") }
 
 	fn regexes(&self) -> &[regex::Regex] { &self.re }
 
@@ -103,3 +103,43 @@ end
 		script_sep { delta_line == 1, delta_start == 0, length == 2 };
 	);
 }
+
+#[test]
+fn bindings() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@<
+local signatures = {}
+for _, binding in ipairs(nml.bindings()) do signatures[binding.name] = binding.signature end
+found = signatures["nml.section.push"] .. " " .. signatures["nml.help"]
+>@
+%<" found>%
+
+%<" nml.help("nml.variable.get")>%
+
+%<" tostring(nml.help("nml.missing"))>%
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Paragraph {
+			Text { content == "nml.section.push(title: string, depth: integer, kind: string?, reference: string?) nml.help(name: string?): string?" };
+		};
+		Paragraph {
+			Text { content == "nml.variable.get(name: string): any Gets the value of variable `name`, converted to the variable's type" };
+		};
+		Paragraph {
+			Text { content == "nil" };
+		};
+	);
+}
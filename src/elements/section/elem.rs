@@ -2,6 +2,8 @@ use std::rc::Rc;
 
 use crate::compiler::compiler::Compiler;
 use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
+use crate::compiler::split::SPLIT_SECTIONS_VARIABLE;
 use crate::document::document::Document;
 use crate::document::element::ElemKind;
 use crate::document::element::Element;
@@ -35,16 +37,36 @@ impl Element for Section {
 	fn compile(
 		&self,
 		compiler: &Compiler,
-		_document: &dyn Document,
-		_cursor: usize,
+		document: &dyn Document,
+		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
+				// Close wrappers of the previous sections
+				let mut result = String::new();
+				if matches!(compiler.target(), HTML) {
+					result += compiler.close_section_wrappers(self.depth).as_str();
+				}
+
+				// Split output by top-level sections
+				if self.depth == 1
+					&& matches!(compiler.target(), HTML)
+					&& document
+						.get_variable(SPLIT_SECTIONS_VARIABLE)
+						.is_some_and(|var| var.to_string() == "true")
+				{
+					compiler.insert_split(
+						cursor + result.len(),
+						self.title.clone(),
+						Compiler::refname(compiler.target(), self.title.as_str()),
+					);
+				}
+
 				// Section numbering
 				let number = if (self.kind & section_kind::NO_NUMBER) != section_kind::NO_NUMBER {
 					let numbering = compiler.section_counter(self.depth);
 
-					let mut result = String::new();
+					let mut result = compiler.section_prefix().clone();
 					for num in numbering.iter() {
 						result = result + num.to_string().as_str() + ".";
 					}
@@ -55,40 +77,56 @@ impl Element for Section {
 					String::new()
 				};
 
-				if self.style.link_pos == SectionLinkPos::None {
-					return Ok(format!(
-						r#"<h{0} id="{1}">{number}{2}</h{0}>"#,
-						self.depth,
-						Compiler::refname(compiler.target(), self.title.as_str()),
-						Compiler::sanitize(compiler.target(), self.title.as_str())
-					));
-				}
-
+				// Slides are already wrapped in `<section>`s by reveal.js
 				let refname = Compiler::refname(compiler.target(), self.title.as_str());
-				let link = format!(
-					"{}<a class=\"section-link\" href=\"#{refname}\">{}</a>{}",
-					Compiler::sanitize(compiler.target(), self.style.link[0].as_str()),
-					Compiler::sanitize(compiler.target(), self.style.link[1].as_str()),
-					Compiler::sanitize(compiler.target(), self.style.link[2].as_str())
-				);
+				let id = if self.style.wrap && matches!(compiler.target(), HTML) {
+					compiler.push_section_wrapper(self.depth);
+					result += format!(r#"<section id="{refname}">"#).as_str();
+					String::new()
+				} else {
+					format!(r#" id="{refname}""#)
+				};
 
-				if self.style.link_pos == SectionLinkPos::After {
-					Ok(format!(
-						r#"<h{0} id="{1}">{number}{2}{link}</h{0}>"#,
-						self.depth,
-						Compiler::refname(compiler.target(), self.title.as_str()),
-						Compiler::sanitize(compiler.target(), self.title.as_str())
-					))
-				} else
-				// Before
-				{
-					Ok(format!(
-						r#"<h{0} id="{1}">{link}{number}{2}</h{0}>"#,
-						self.depth,
-						Compiler::refname(compiler.target(), self.title.as_str()),
-						Compiler::sanitize(compiler.target(), self.title.as_str())
-					))
+				let link = if self.style.link_pos == SectionLinkPos::None {
+					String::new()
+				} else {
+					let class = if self.style.link_hover {
+						"section-link section-link-hover"
+					} else {
+						"section-link"
+					};
+					let label = self
+						.style
+						.link_label
+						.as_ref()
+						.map_or(String::new(), |label| {
+							format!(
+								" aria-label=\"{}\"",
+								Compiler::sanitize_attribute(
+									compiler.target(),
+									label.replace("{title}", self.title.as_str())
+								)
+							)
+						});
+					format!(
+						"{}<a class=\"{class}\" href=\"{}\"{label}>{}</a>{}",
+						Compiler::sanitize(compiler.target(), self.style.link[0].as_str()),
+						Compiler::anchor(compiler.target(), self.title.as_str()),
+						Compiler::sanitize(compiler.target(), self.style.link[1].as_str()),
+						Compiler::sanitize(compiler.target(), self.style.link[2].as_str())
+					)
+				};
+
+				let title = Compiler::sanitize(compiler.target(), self.title.as_str());
+				if self.style.link_pos == SectionLinkPos::Before {
+					result +=
+						format!("<h{0}{id}>{link}{number}{title}</h{0}>", self.depth).as_str();
+				} else {
+					result +=
+						format!("<h{0}{id}>{number}{title}{link}</h{0}>", self.depth).as_str();
 				}
+
+				Ok(result)
 			}
 			_ => todo!(""),
 		}
@@ -105,30 +143,31 @@ impl ReferenceableElement for Section {
 	fn compile_reference(
 		&self,
 		compiler: &Compiler,
-		_document: &dyn Document,
+		document: &dyn Document,
 		reference: &InternalReference,
 		_refid: usize,
+		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
-				let caption = reference.caption().map_or(
-					format!(
-						"({})",
-						Compiler::sanitize(compiler.target(), self.title.as_str())
-					),
-					|cap| cap.clone(),
+			HTML | SLIDES => {
+				let link = format!(
+					"<a class=\"section-reference\" href=\"{}\">",
+					Compiler::anchor(compiler.target(), self.title.as_str())
 				);
+				let caption =
+					match reference.compile_caption(compiler, document, cursor + link.len()) {
+						Some(caption) => caption?,
+						None => format!(
+							"({})",
+							Compiler::sanitize(compiler.target(), self.title.as_str())
+						),
+					};
 
-				Ok(format!(
-					"<a class=\"section-reference\" href=\"#{}\">{caption}</a>",
-					Compiler::refname(compiler.target(), self.title.as_str())
-				))
+				Ok(format!("{link}{caption}</a>"))
 			}
 			_ => todo!(""),
 		}
 	}
 
-	fn refid(&self, compiler: &Compiler, _refid: usize) -> String {
-		Compiler::refname(compiler.target(), self.title.as_str())
-	}
+	fn refid(&self, _compiler: &Compiler, _refid: usize) -> String { self.title.replace(' ', "_") }
 }
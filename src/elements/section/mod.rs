@@ -1,5 +1,6 @@
 pub mod elem;
 pub mod rule;
+pub mod state;
 pub mod style;
 #[cfg(test)]
 pub mod tests;
@@ -2,7 +2,11 @@ use mlua::Error::BadArgument;
 use parser::rule::RegexRule;
 use std::sync::Arc;
 
+use crate::compiler::compiler::increment_section_counter;
+use crate::compiler::numbering::SECTION_OFFSET_VARIABLE;
+use crate::compiler::numbering::SECTION_PREFIX_VARIABLE;
 use crate::document::document::Document;
+use crate::lua::kernel::Binding;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::ParserState;
 use crate::parser::reports::macros::*;
@@ -10,13 +14,19 @@ use crate::parser::reports::Report;
 use crate::parser::reports::*;
 use crate::parser::source::Token;
 use ariadne::Fmt;
+use lsp::hints::HintKind;
+use lsp::hints::Hints;
 use lsp::semantic::Semantics;
 use lua::kernel::CTX;
-use mlua::Function;
 use mlua::Lua;
+use parser::state::RuleState;
 use regex::Regex;
+use std::cell::RefCell;
+use std::rc::Rc;
 
 use super::elem::Section;
+use super::state::SectionState;
+use super::state::STATE_NAME;
 use super::style::SectionStyle;
 
 #[auto_registry::auto_registry(registry = "rules")]
@@ -32,6 +42,51 @@ impl Default for SectionRule {
 	}
 }
 
+pub fn initialize_state(state: &ParserState) -> Rc<RefCell<dyn RuleState>> {
+	let mut rule_state_borrow = state.shared.rule_state.borrow_mut();
+	match rule_state_borrow.get(STATE_NAME) {
+		Some(state) => state,
+		None => {
+			// Insert as a new state
+			match rule_state_borrow.insert(
+				STATE_NAME.into(),
+				Rc::new(RefCell::new(SectionState { counter: vec![] })),
+			) {
+				Err(err) => panic!("{err}"),
+				Ok(state) => state,
+			}
+		}
+	}
+}
+
+/// Adds the number of a section to the language server's hints
+///
+/// Numbers are computed like [`Section::compile`](crate::document::element::Element::compile),
+/// using the values of `compiler.section_offset` and `compiler.section_prefix` at the section.
+fn add_number_hint(state: &ParserState, document: &dyn Document, token: &Token, depth: usize) {
+	let Some(hints) = Hints::from_source(token.source(), &state.shared.lsp) else {
+		return;
+	};
+	let offset = document
+		.get_variable(SECTION_OFFSET_VARIABLE)
+		.and_then(|offset| offset.to_string().trim().parse::<usize>().ok())
+		.unwrap_or(0);
+	let prefix = document
+		.get_variable(SECTION_PREFIX_VARIABLE)
+		.map_or(String::new(), |prefix| prefix.to_string());
+
+	let rule_state = initialize_state(state);
+	let mut rule_state_borrow = rule_state.borrow_mut();
+	let section_state = rule_state_borrow.downcast_mut::<SectionState>().unwrap();
+	increment_section_counter(&mut section_state.counter, depth, offset);
+
+	let mut number = prefix;
+	for num in &section_state.counter {
+		number += format!("{num}.").as_str();
+	}
+	hints.add_kind(token.end(), number, HintKind::SectionNumber);
+}
+
 pub mod section_kind {
 	pub const NONE: u8 = 0x00;
 	pub const NO_TOC: u8 = 0x01;
@@ -41,7 +96,7 @@ pub mod section_kind {
 impl RegexRule for SectionRule {
 	fn name(&self) -> &'static str { "Section" }
 
-	fn previous(&self) -> Option<&'static str> { Some("Custom Style") }
+	fn previous(&self) -> Option<&'static str> { Some("Icon") }
 
 	fn regexes(&self) -> &[Regex] { &self.re }
 
@@ -208,6 +263,10 @@ impl RegexRule for SectionRule {
 			}),
 		);
 
+		if (section_kind & section_kind::NO_NUMBER) != section_kind::NO_NUMBER {
+			add_number_hint(state, document, &token, section_depth);
+		}
+
 		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
 			sems.add(matches.get(1).unwrap().range(), tokens.section_heading);
 			if let Some(reference) = matches.get(2) {
@@ -225,11 +284,13 @@ impl RegexRule for SectionRule {
 		reports
 	}
 
-	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
 		let mut bindings = vec![];
 
-		bindings.push((
-			"push".to_string(),
+		bindings.push(Binding::new(
+			"push",
+			"(title: string, depth: integer, kind: string?, reference: string?)",
+			"Inserts a section, `kind` combines `*` (unnumbered) and `+` (not in the table of contents)",
 			lua.create_function(
 				|_,
 				 (title, depth, kind, reference): (
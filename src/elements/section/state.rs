@@ -0,0 +1,19 @@
+use crate::document::document::Document;
+use crate::parser::parser::ParserState;
+use crate::parser::reports::Report;
+use crate::parser::state::RuleState;
+use crate::parser::state::Scope;
+
+pub static STATE_NAME: &str = "elements.section";
+
+/// Numbering of the parsed sections, for the language server's hints
+pub struct SectionState {
+	/// The section counter, see [`crate::compiler::compiler::increment_section_counter`]
+	pub(crate) counter: Vec<usize>,
+}
+
+impl RuleState for SectionState {
+	fn scope(&self) -> Scope { Scope::GLOBAL }
+
+	fn on_remove(&self, _state: &ParserState, _document: &dyn Document) -> Vec<Report> { vec![] }
+}
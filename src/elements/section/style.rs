@@ -11,10 +11,17 @@ pub enum SectionLinkPos {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 #[auto_registry::auto_registry(registry = "elem_styles")]
 pub struct SectionStyle {
 	pub link_pos: SectionLinkPos,
 	pub link: [String; 3],
+	/// Only display the section link when the heading is hovered
+	pub link_hover: bool,
+	/// Accessible label of the section link, `{title}` is replaced by the section's title
+	pub link_label: Option<String>,
+	/// Places the section's id on a `<section>` element wrapping the section's content
+	pub wrap: bool,
 }
 
 impl Default for SectionStyle {
@@ -22,6 +29,9 @@ impl Default for SectionStyle {
 		Self {
 			link_pos: SectionLinkPos::Before,
 			link: ["".into(), "🔗".into(), " ".into()],
+			link_hover: true,
+			link_label: None,
+			wrap: false,
 		}
 	}
 }
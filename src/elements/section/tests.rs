@@ -1,13 +1,21 @@
 use std::rc::Rc;
 
+use tower_lsp::lsp_types::InlayHintLabel;
+
+use crate::compiler::api::compile_str;
+use crate::compiler::api::Settings;
+use crate::compiler::compiler::Target;
+use crate::compiler::process::process_from_memory;
 use crate::elements::section::elem::Section;
 use crate::elements::section::rule::section_kind;
 use crate::elements::section::style::SectionLinkPos;
 use crate::elements::section::style::SectionStyle;
+use crate::lsp::hints::HintsConfig;
 use crate::parser::langparser::LangParser;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::Parser;
 use crate::parser::parser::ParserState;
+use crate::parser::source::Source;
 use crate::parser::source::SourceFile;
 use crate::validate_document;
 use crate::validate_semantics;
@@ -147,3 +155,124 @@ fn semantics() {
 	section_name { delta_line == 0, delta_start == 2 };
 	);
 }
+
+#[test]
+fn numbering() {
+	let result = process_from_memory(
+		Target::HTML,
+		vec![r#"
+@html.page_title = 0
+@compiler.section_offset = 2
+@compiler.section_prefix = A.
+# First
+## Nested
+# Second
+"#
+		.into()],
+	)
+	.unwrap();
+
+	let body = &result[0].0.borrow().body;
+	assert!(body.contains("A.3. First"));
+	assert!(body.contains("A.3.1. Nested"));
+	assert!(body.contains("A.4. Second"));
+}
+
+#[test]
+fn invalid_offset() {
+	let reports = compile_str(
+		"@compiler.section_offset = two\n# First\n",
+		&Settings::default(),
+	)
+	.err()
+	.unwrap();
+	assert_eq!(reports.len(), 1);
+	assert_eq!(reports[0].message, "Invalid Variable Value");
+	assert_eq!(reports[0].spans[0].token.range, 26..30);
+}
+
+#[test]
+fn permalinks() {
+	let result = process_from_memory(
+		Target::HTML,
+		vec![r#"
+@html.page_title = 0
+@@style.section = {
+	"link_pos": "After",
+	"link_hover": false,
+	"link_label": "Link to {title}",
+	"wrap": true
+}
+# First
+## Nested
+# Second
+"#
+		.into()],
+	)
+	.unwrap();
+
+	let body = &result[0].0.borrow().body;
+	assert!(body.contains(
+		r##"<section id="First"><h1>1. First<a class="section-link" href="#First" aria-label="Link to First">🔗</a> </h1>"##
+	));
+	assert!(body.contains(r#"</h2></section></section><section id="Second"><h1>"#));
+	assert!(body.ends_with("</section></div>"));
+}
+
+#[test]
+fn snapshot() {
+	crate::assert_snapshot!(
+		"sections",
+		r#"
+# First
+## Nested
+#{ref}* Unnumbered
+"#
+	);
+}
+
+#[test]
+fn hints() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@compiler.section_offset = 2
+# First
+## Nested
+#* Unnumbered
+## Second
+# Third
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+	let lsp = state.shared.lsp.as_ref().unwrap().borrow();
+	let hints = lsp
+		.inlay_hints
+		.get(&(source as Rc<dyn Source>))
+		.unwrap()
+		.hints
+		.borrow();
+	let labels = hints
+		.iter()
+		.filter(|hint| HintsConfig::default().enabled(hint))
+		.map(|hint| match &hint.label {
+			InlayHintLabel::String(label) => (hint.position.line, label.as_str()),
+			_ => panic!("Invalid label"),
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(labels, vec![(2, "3."), (3, "3.1."), (5, "3.2."), (6, "4.")]);
+
+	let config = HintsConfig {
+		section_numbers: false,
+		..HintsConfig::default()
+	};
+	assert!(hints.iter().all(|hint| !config.enabled(hint)));
+}
@@ -12,7 +12,6 @@ use lsp::semantic::Semantics;
 use lsp::styles::Styles;
 use lua::kernel::CTX;
 use mlua::Error::BadArgument;
-use mlua::Function;
 use parser::parser::ParseMode;
 use parser::parser::ParserState;
 use parser::rule::RegexRule;
@@ -20,6 +19,7 @@ use parser::source::Token;
 use regex::Captures;
 use regex::Regex;
 
+use crate::lua::kernel::Binding;
 use crate::parser::reports::macros::*;
 use crate::parser::reports::*;
 #[auto_registry::auto_registry(registry = "rules")]
@@ -125,7 +125,7 @@ impl RegexRule for StyleRule {
 
 			// Conceals
 			if let Some(conceals) = Conceals::from_source(token.source(), &state.shared.lsp) {
-				conceals.add(token.range.clone(), ConcealTarget::Text("".into()));
+				conceals.add(token.range.clone(), "style", ConcealTarget::Text("".into()));
 			}
 		} else {
 			panic!("Invalid state at `{STATE_NAME}`");
@@ -134,11 +134,13 @@ impl RegexRule for StyleRule {
 		vec![]
 	}
 
-	fn register_bindings<'lua>(&self, lua: &'lua mlua::Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua mlua::Lua) -> Vec<Binding<'lua>> {
 		let mut bindings = vec![];
 
-		bindings.push((
-			"toggle".to_string(),
+		bindings.push(Binding::new(
+			"toggle",
+			"(style: string)",
+			"Toggles style `bold`, `italic`, `underline` or `emphasis`",
 			lua.create_function(|_, style: String| {
 				let kind = match style.as_str() {
 					"bold" | "Bold" => 0,
@@ -1,13 +1,23 @@
+use std::rc::Rc;
+
+use serde::Deserialize;
+use serde::Serialize;
+
 use crate::compiler::compiler::Compiler;
 use crate::compiler::compiler::Target;
 use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
 use crate::document::document::Document;
 use crate::document::element::ElemKind;
 use crate::document::element::Element;
 use crate::document::element::ReferenceableElement;
+use crate::elements::color::elem::Color;
 use crate::elements::reference::elem::InternalReference;
 use crate::parser::source::Token;
 
+use super::style::TablePadding;
+use super::style::TableStyle;
+
 /// Converts to style
 trait ToStyle {
 	fn to_style(&self, target: Target) -> String;
@@ -38,7 +48,7 @@ impl TryFrom<&String> for Align {
 impl ToStyle for Option<Align> {
 	fn to_style(&self, target: Target) -> String {
 		match target {
-			HTML => match self {
+			HTML | SLIDES => match self {
 				Some(Align::Right) => "text-align: right;".into(),
 				Some(Align::Center) => "text-align: center;".into(),
 				Some(Align::Left) | None => "".into(),
@@ -48,8 +58,51 @@ impl ToStyle for Option<Align> {
 	}
 }
 
+/// Scope of header cells, i.e the cells described by the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderScope {
+	Col,
+	Row,
+	ColGroup,
+	RowGroup,
+}
+
+impl TryFrom<&String> for HeaderScope {
+	type Error = String;
+
+	fn try_from(value: &String) -> Result<Self, Self::Error> {
+		match value.as_str() {
+			"col" => Ok(HeaderScope::Col),
+			"row" => Ok(HeaderScope::Row),
+			"colgroup" => Ok(HeaderScope::ColGroup),
+			"rowgroup" => Ok(HeaderScope::RowGroup),
+			_ => Err(format!("Unknown header scope: `{value}`")),
+		}
+	}
+}
+
+impl HeaderScope {
+	fn name(&self) -> &'static str {
+		match self {
+			HeaderScope::Col => "col",
+			HeaderScope::Row => "row",
+			HeaderScope::ColGroup => "colgroup",
+			HeaderScope::RowGroup => "rowgroup",
+		}
+	}
+}
+
+/// Section of the table containing a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowGroup {
+	/// Rows in `<thead>`, at the start of the table
+	Head,
+	/// Rows in `<tfoot>`, at the end of the table
+	Foot,
+}
+
 /// Border style for cells
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BorderStyle {
 	#[default]
 	Solid,
@@ -58,12 +111,52 @@ pub enum BorderStyle {
 	None,
 }
 
-impl ToStyle for Option<BorderStyle> {
-	fn to_style(&self, _target: Target) -> String {
-		if self.is_none() {
-			return String::new();
+impl BorderStyle {
+	pub(crate) fn name(&self) -> &'static str {
+		match self {
+			BorderStyle::Solid => "solid",
+			BorderStyle::Dashed => "dashed",
+			BorderStyle::Dotted => "dotted",
+			BorderStyle::None => "none",
+		}
+	}
+
+	/// Parses one to four border styles, for the top, right, bottom and left borders like CSS
+	/// shorthands
+	pub(crate) fn parse_sides(value: &str) -> Result<[Option<BorderStyle>; 4], String> {
+		let styles = value
+			.split_whitespace()
+			.map(|style| BorderStyle::try_from(&style.to_string()))
+			.collect::<Result<Vec<_>, _>>()?;
+		let [top, right, bottom, left] = match styles[..] {
+			[all] => [all; 4],
+			[vertical, horizontal] => [vertical, horizontal, vertical, horizontal],
+			[top, horizontal, bottom] => [top, horizontal, bottom, horizontal],
+			[top, right, bottom, left] => [top, right, bottom, left],
+			_ => {
+				return Err(format!(
+					"Expected 1 to 4 border styles, got {}",
+					styles.len()
+				))
+			}
+		};
+		Ok([Some(top), Some(right), Some(bottom), Some(left)])
+	}
+}
+
+/// Borders of the top, right, bottom and left sides
+impl ToStyle for [Option<BorderStyle>; 4] {
+	fn to_style(&self, target: Target) -> String {
+		match target {
+			HTML | SLIDES => ["top", "right", "bottom", "left"]
+				.iter()
+				.zip(self)
+				.filter_map(|(side, border)| {
+					border.map(|border| format!("border-{side}-style: {};", border.name()))
+				})
+				.collect(),
+			_ => todo!(),
 		}
-		todo!();
 	}
 }
 
@@ -90,16 +183,21 @@ pub struct CellProperties {
 	pub(crate) hspan: Option<usize>,
 	/// Text alignment for the cell
 	pub(crate) align: Option<Align>,
+	/// Makes the cell a header cell
+	pub(crate) header: Option<HeaderScope>,
 	/// Borders formatting for the cell
 	pub(crate) borders: [Option<BorderStyle>; 4],
+	/// Background color of the cell
+	pub(crate) background: Option<[u8; 4]>,
 }
 
 impl ToStyle for CellProperties {
 	fn to_style(&self, target: Target) -> String {
 		let mut style = String::new();
 		style += self.align.to_style(target).as_str();
-		for border in &self.borders {
-			style += border.to_style(target).as_str();
+		style += self.borders.to_style(target).as_str();
+		if let Some(background) = self.background {
+			style += format!("background-color: {};", Color::hex(background)).as_str();
 		}
 		return style;
 	}
@@ -122,9 +220,7 @@ impl ToStyle for Option<ColumnProperties> {
 
 		let props = self.as_ref().unwrap();
 		let mut style = String::new();
-		for border in &props.borders {
-			style += border.to_style(target).as_str();
-		}
+		style += props.borders.to_style(target).as_str();
 		return style;
 	}
 }
@@ -136,6 +232,10 @@ pub struct RowProperties {
 	pub(crate) vspan: Option<usize>,
 	/// Text alignment for the cells in this row
 	pub(crate) align: Option<Align>,
+	/// Makes the cells in this row header cells
+	pub(crate) header: Option<HeaderScope>,
+	/// Places the row in the header or footer of the table, its cells are header cells
+	pub(crate) group: Option<RowGroup>,
 	/// Borders formatting for cells in this row
 	pub(crate) borders: [Option<BorderStyle>; 4],
 }
@@ -149,9 +249,7 @@ impl ToStyle for Option<RowProperties> {
 		let props = self.as_ref().unwrap();
 		let mut style = String::new();
 		style += props.align.to_style(target).as_str();
-		for border in &props.borders {
-			style += border.to_style(target).as_str();
-		}
+		style += props.borders.to_style(target).as_str();
 		return style;
 	}
 }
@@ -169,9 +267,7 @@ impl ToStyle for TableProperties {
 	fn to_style(&self, target: Target) -> String {
 		let mut style = String::new();
 		style += self.align.to_style(target).as_str();
-		for border in &self.borders {
-			style += border.to_style(target).as_str();
-		}
+		style += self.borders.to_style(target).as_str();
 		return style;
 	}
 }
@@ -212,6 +308,8 @@ pub struct Table {
 	pub(crate) title: Option<String>,
 	/// Optional reference name for the table
 	pub(crate) reference: Option<String>,
+	/// Style of the table
+	pub(crate) style: Rc<TableStyle>,
 }
 
 impl Element for Table {
@@ -269,14 +367,49 @@ impl Element for Table {
 			String::new()
 		};
 
+		let mut classes = vec![];
+		if self.style.borders != BorderStyle::Solid {
+			classes.push(format!("table-border-{}", self.style.borders.name()));
+		}
+		if self.style.striped {
+			classes.push("table-striped".to_string());
+		}
+		match self.style.padding {
+			TablePadding::Compact => classes.push("table-padding-compact".to_string()),
+			TablePadding::Normal => {}
+			TablePadding::Relaxed => classes.push("table-padding-relaxed".to_string()),
+		}
+
 		let mut pos = (0usize, 0usize);
+		result += "<table";
+		if !classes.is_empty() {
+			result += format!(" class=\"{}\"", classes.join(" ")).as_str();
+		}
 		if !table_style.is_empty() {
-			result += format!("<table style=\"{table_style}\">").as_str()
-		} else {
-			result += "<table>"
+			result += format!(" style=\"{table_style}\"").as_str();
 		}
+		result += ">";
 		result += colgroup.as_str();
+		// Rows are placed in sections when the table has a header or footer
+		let group_tag = |group: Option<RowGroup>| match group {
+			Some(RowGroup::Head) => "thead",
+			Some(RowGroup::Foot) => "tfoot",
+			None => "tbody",
+		};
+		let grouped = self
+			.rows
+			.iter()
+			.any(|row| row.as_ref().is_some_and(|row| row.group.is_some()));
+		let mut current_group = None;
 		for cell in &self.data {
+			let group = self.rows[pos.1].as_ref().and_then(|row| row.group);
+			if pos.0 == 0 && grouped && (pos.1 == 0 || current_group != group) {
+				if pos.1 != 0 {
+					result += format!("</{}>", group_tag(current_group)).as_str();
+				}
+				result += format!("<{}>", group_tag(group)).as_str();
+				current_group = group;
+			}
 			if pos.0 == 0 {
 				// Row styling
 				let style = {
@@ -311,13 +444,30 @@ impl Element for Table {
 						cell_data.properties.vspan.unwrap_or(1),
 					);
 
+					// Header cells
+					let header = cell_data
+						.properties
+						.header
+						.or(self.rows[pos.1].as_ref().and_then(|row| row.header))
+						.or((group == Some(RowGroup::Head)).then_some(HeaderScope::Col));
+					let (tag, scope) = match (header, group) {
+						(Some(scope), _) => ("th", format!(" scope=\"{}\"", scope.name())),
+						(None, Some(_)) => ("th", String::new()),
+						(None, None) => ("td", String::new()),
+					};
+
 					match (hspan, vspan) {
-						(1, 1) => result += format!("<td{style}>").as_str(),
-						(1, v) => result += format!("<td rowspan=\"{v}\"{style}>").as_str(),
-						(h, 1) => result += format!("<td colspan=\"{h}\"{style}>").as_str(),
+						(1, 1) => result += format!("<{tag}{scope}{style}>").as_str(),
+						(1, v) => {
+							result += format!("<{tag}{scope} rowspan=\"{v}\"{style}>").as_str()
+						}
+						(h, 1) => {
+							result += format!("<{tag}{scope} colspan=\"{h}\"{style}>").as_str()
+						}
 						(h, v) => {
 							result +=
-								format!("<td rowspan=\"{v}\" colspan=\"{h}\"{style}>").as_str()
+								format!("<{tag}{scope} rowspan=\"{v}\" colspan=\"{h}\"{style}>")
+									.as_str()
 						}
 					}
 					for elem in &cell_data.content {
@@ -325,7 +475,7 @@ impl Element for Table {
 							.compile(compiler, document, cursor + result.len())?
 							.as_str();
 					}
-					result += "</td>";
+					result += format!("</{tag}>").as_str();
 				}
 				Cell::Reference(_) => {}
 			}
@@ -338,6 +488,9 @@ impl Element for Table {
 				pos.1 += 1;
 			}
 		}
+		if grouped {
+			result += format!("</{}>", group_tag(current_group)).as_str();
+		}
 		result += "</table>";
 
 		if self.reference.is_some() {
@@ -384,20 +537,24 @@ impl ReferenceableElement for Table {
 	fn compile_reference(
 		&self,
 		compiler: &Compiler,
-		_document: &dyn Document,
+		document: &dyn Document,
 		reference: &InternalReference,
 		refid: usize,
+		cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			Target::HTML => {
-				let caption = reference
-					.caption()
-					.map_or(format!("(Table {refid})"), |cap| cap.clone());
-
-				Ok(format!(
-					"<a class=\"table-ref\" href=\"#{}\">{caption}</a>",
+			Target::HTML | Target::SLIDES => {
+				let link = format!(
+					"<a class=\"table-ref\" href=\"#{}\">",
 					self.refid(compiler, refid)
-				))
+				);
+				let caption =
+					match reference.compile_caption(compiler, document, cursor + link.len()) {
+						Some(caption) => caption?,
+						None => format!("(Table {refid})"),
+					};
+
+				Ok(format!("{link}{caption}</a>"))
 			}
 			_ => todo!(""),
 		}
@@ -0,0 +1,274 @@
+use std::ops::Range;
+use std::rc::Rc;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::document::document::Document;
+use crate::document::element::Element;
+use crate::parser::source::Source;
+use crate::parser::source::SourcePosition;
+
+use super::elem::Table;
+
+/// Cell of a row being formatted
+struct FormatCell<'a> {
+	/// Properties of the cell, including the surrounding `:`
+	properties: Option<&'a str>,
+	content: &'a str,
+	/// Horizontal span of the cell, from the `hspan` property
+	hspan: usize,
+}
+
+impl FormatCell<'_> {
+	/// Text of the cell, without its padding
+	fn text(&self) -> String {
+		match self.properties {
+			Some(properties) if self.content.is_empty() => properties.to_string(),
+			Some(properties) => format!("{properties} {}", self.content),
+			None => format!(" {}", self.content),
+		}
+	}
+}
+
+/// Splits a row into the raw content of its cells, `\` escapes the next character
+///
+/// Returns `None` when the line is not a complete row.
+fn split_row(row: &str) -> Option<Vec<&str>> {
+	let row = row.strip_prefix('|')?;
+	let mut cells = vec![];
+	let mut start = 0;
+	let mut chars = row.char_indices();
+	while let Some((pos, c)) = chars.next() {
+		match c {
+			'\\' => {
+				chars.next();
+			}
+			'|' => {
+				cells.push(&row[start..pos]);
+				start = pos + 1;
+			}
+			_ => {}
+		}
+	}
+	(start == row.len() && !cells.is_empty()).then_some(cells)
+}
+
+/// Splits the properties (`:...:`) from the content of a cell
+fn parse_cell(cell: &str) -> FormatCell<'_> {
+	let trimmed = cell.trim_start();
+	let mut properties = None;
+	let mut content = trimmed;
+	if trimmed.starts_with(':') {
+		let mut chars = trimmed.char_indices().skip(2);
+		while let Some((pos, c)) = chars.next() {
+			match c {
+				'\\' => {
+					chars.next();
+				}
+				':' => {
+					properties = Some(&trimmed[..=pos]);
+					content = trimmed[pos + 1..].trim_start();
+					break;
+				}
+				_ => {}
+			}
+		}
+	}
+
+	// Keep escaped trailing whitespaces
+	let mut end = content.trim_end().len();
+	if content[..end]
+		.chars()
+		.rev()
+		.take_while(|c| *c == '\\')
+		.count()
+		% 2 == 1
+	{
+		end += content[end..].chars().next().map_or(0, char::len_utf8);
+	}
+
+	let hspan = properties
+		.and_then(|properties| {
+			properties.match_indices("hspan").find_map(|(pos, _)| {
+				let before = properties[..pos].chars().next_back();
+				if before.is_some_and(char::is_alphanumeric) {
+					return None;
+				}
+				let value = properties[pos + "hspan".len()..]
+					.trim_start()
+					.strip_prefix('=')?
+					.trim_start();
+				let digits = value.chars().take_while(char::is_ascii_digit).count();
+				value[..digits].parse::<usize>().ok()
+			})
+		})
+		.unwrap_or(1)
+		.max(1);
+
+	FormatCell {
+		properties,
+		content: &content[..end],
+		hspan,
+	}
+}
+
+/// Formats the rows of a table, cells are padded so that the pipes of the columns are aligned
+///
+/// Cells spanning over multiple columns (`hspan`) are aligned with the columns they span. The
+/// content and properties of the cells are kept, only the whitespaces surrounding them change.
+///
+/// Returns `None` when a line is not a table row.
+pub fn format_rows(rows: &[&str]) -> Option<Vec<String>> {
+	let rows = rows
+		.iter()
+		.map(|row| {
+			Some(
+				split_row(row)?
+					.into_iter()
+					.map(parse_cell)
+					.collect::<Vec<_>>(),
+			)
+		})
+		.collect::<Option<Vec<_>>>()?;
+	let width = |text: &str| text.graphemes(true).count();
+
+	// Width of the columns, cells spanning over a single column first
+	let columns = rows
+		.iter()
+		.map(|row| row.iter().map(|cell| cell.hspan).sum::<usize>())
+		.max()
+		.unwrap_or(0);
+	let mut widths = vec![0; columns];
+	for row in &rows {
+		let mut column = 0;
+		for cell in row {
+			if cell.hspan == 1 {
+				widths[column] = widths[column].max(width(&cell.text()));
+			}
+			column += cell.hspan;
+		}
+	}
+	for row in &rows {
+		let mut column = 0;
+		for cell in row {
+			let spanned = column..column + cell.hspan;
+			let available = widths[spanned.clone()].iter().sum::<usize>() + 3 * (cell.hspan - 1);
+			let required = width(&cell.text());
+			if cell.hspan != 1 && required > available {
+				widths[spanned.end - 1] += required - available;
+			}
+			column += cell.hspan;
+		}
+	}
+
+	Some(
+		rows.iter()
+			.map(|row| {
+				let mut result = "|".to_string();
+				let mut column = 0;
+				for cell in row {
+					let spanned = column..column + cell.hspan;
+					let available = widths[spanned].iter().sum::<usize>() + 3 * (cell.hspan - 1);
+					let text = cell.text();
+					result += text.as_str();
+					result += " ".repeat(available - width(&text)).as_str();
+					result += " |";
+					column += cell.hspan;
+				}
+				result
+			})
+			.collect(),
+	)
+}
+
+/// Gets the edits formatting the tables of a document, see [`format_rows`]
+///
+/// Edits are byte ranges of the document's source with their replacement, tables from other
+/// sources are not formatted.
+pub fn table_edits(document: &dyn Document) -> Vec<(Range<usize>, String)> {
+	fn visit(elem: &dyn Element, source: &Rc<dyn Source>, tables: &mut Vec<Range<usize>>) {
+		if let Some(table) = elem.downcast_ref::<Table>() {
+			let (table_source, range) = table
+				.location
+				.source()
+				.original_range(table.location.range.clone());
+			if Rc::ptr_eq(&table_source, source) {
+				tables.push(range);
+			}
+		} else if let Some(container) = elem.as_container() {
+			for elem in container.contained() {
+				visit(elem.as_ref(), source, tables);
+			}
+		}
+	}
+
+	let source = document.source();
+	let mut tables = vec![];
+	for elem in document.content().borrow().iter() {
+		visit(elem.as_ref(), &source, &mut tables);
+	}
+
+	let content = source.content();
+	let mut edits = vec![];
+	for table in tables {
+		// Rows are the lines of the table starting with `|`
+		let mut start = content[..table.start].rfind('\n').map_or(0, |pos| pos + 1);
+		let mut rows = vec![];
+		while start < table.end.max(table.start + 1) && start < content.len() {
+			let end = content[start..]
+				.find('\n')
+				.map_or(content.len(), |pos| start + pos);
+			if content[start..].starts_with('|') {
+				rows.push(start..end);
+			}
+			start = end + 1;
+		}
+		let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+			continue;
+		};
+		let lines = rows
+			.iter()
+			.map(|range| &content[range.clone()])
+			.collect::<Vec<_>>();
+		let Some(formatted) = format_rows(&lines) else {
+			continue;
+		};
+		if formatted != lines {
+			edits.push((first.start..last.end, formatted.join("\n")));
+		}
+	}
+	edits
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rows() {
+		let formatted = format_rows(&[
+			"| **First column** |:hspan=2: **Second column**|",
+			"|:align=center: Centered|B|C|",
+			"|:hspan=2: 1|  2  |",
+			"|é\\|\\ | |x|",
+		])
+		.unwrap();
+		assert_eq!(
+			formatted,
+			vec![
+				"| **First column**       |:hspan=2: **Second column** |",
+				"|:align=center: Centered | B | C                     |",
+				"|:hspan=2: 1                  | 2                     |",
+				"| é\\|\\                   |   | x                     |",
+			]
+		);
+		assert_eq!(
+			format_rows(&formatted.iter().map(String::as_str).collect::<Vec<_>>()),
+			Some(formatted)
+		);
+
+		// Not a row
+		assert_eq!(format_rows(&["| a | b"]), None);
+		assert_eq!(format_rows(&["| a \\|"]), None);
+	}
+}
@@ -1,4 +1,6 @@
 pub mod elem;
+pub mod format;
 pub mod rule;
+pub mod style;
 #[cfg(test)]
 pub mod tests;
@@ -2,8 +2,11 @@ use std::any::Any;
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::ops::Range;
+use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::document::document::Document;
+use crate::lua::kernel::Binding;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::ParserState;
 use crate::parser::property::Property;
@@ -20,24 +23,35 @@ use document::element::ElemKind;
 use document::element::Element;
 use document::references::validate_refname;
 use elements::block::elem::Block;
+use elements::color::elem::Color;
 use elements::list::elem::ListEntry;
 use elements::list::elem::ListMarker;
 use elements::paragraph::elem::Paragraph;
 use elements::text::elem::Text;
 use lsp::semantic::Semantics;
 use lua::kernel::CTX;
+use mlua::Error::BadArgument;
+use mlua::FromLua;
+use mlua::Lua;
+use mlua::Value;
 use parser::property::PropertyMap;
+use parser::source::Source;
 use parser::source::Token;
+use parser::source::VirtualSource;
 use regex::Regex;
 
 use super::elem::Align;
+use super::elem::BorderStyle;
 use super::elem::Cell;
 use super::elem::CellData;
 use super::elem::CellProperties;
 use super::elem::ColumnProperties;
+use super::elem::HeaderScope;
+use super::elem::RowGroup;
 use super::elem::RowProperties;
 use super::elem::Table;
 use super::elem::TableProperties;
+use super::style::TableStyle;
 
 /// Represents a position inside the table grid
 #[derive(Clone, Copy)]
@@ -155,11 +169,30 @@ fn parse_properties(
 		Some(align) => align,
 		None => return None,
 	};
+	let header = match properties.get_opt(reports, "header", |_, value| {
+		HeaderScope::try_from(&value.value)
+	}) {
+		Some(header) => header,
+		None => return None,
+	};
+	let borders = match properties.get_opt(reports, "border", |_, value| {
+		BorderStyle::parse_sides(&value.value)
+	}) {
+		Some(borders) => borders.unwrap_or([None; 4]),
+		None => return None,
+	};
+	let background =
+		match properties.get_opt(reports, "bg", |_, value| Color::parse(value.value.as_str())) {
+			Some(background) => background,
+			None => return None,
+		};
 	let cell_properties = CellProperties {
 		hspan,
 		vspan,
 		align,
-		borders: [None; 4],
+		header,
+		borders,
+		background,
 	};
 
 	// Row
@@ -196,6 +229,8 @@ fn parse_properties(
 			row.replace(RowProperties {
 				vspan: None,
 				align: Some(align.1),
+				header: None,
+				group: None,
 				borders: [None; 4],
 			});
 		}
@@ -212,6 +247,71 @@ fn parse_properties(
 				row.replace(RowProperties {
 					vspan: Some(span),
 					align: None,
+					header: None,
+					group: None,
+					borders: [None; 4],
+				});
+			}
+		}
+	}
+	// Row header
+	if let Some(header) = match properties.get_opt(reports, "rheader", |_, value| {
+		HeaderScope::try_from(&value.value)
+	}) {
+		Some(header) => header,
+		None => return None,
+	} {
+		match row {
+			Some(row) => row.header = Some(header),
+			None => {
+				row.replace(RowProperties {
+					vspan: None,
+					align: None,
+					header: Some(header),
+					group: None,
+					borders: [None; 4],
+				});
+			}
+		}
+	}
+
+	// Row group
+	for (key, group) in [("rhead", RowGroup::Head), ("rfoot", RowGroup::Foot)] {
+		let Some(enabled) = properties.get_opt(reports, key, |_, value| {
+			value
+				.value
+				.parse::<bool>()
+				.map(|enabled| (value.value_range.clone(), enabled))
+		}) else {
+			return None;
+		};
+		let Some((range, true)) = enabled else {
+			continue;
+		};
+		match row {
+			Some(row) if row.group.is_some_and(|current| current != group) => {
+				report_err!(
+					reports,
+					properties.token.source(),
+					"Invalid Table Row".into(),
+					span(
+						range,
+						format!(
+							"Row may not be in both the header and footer, remove {} or {}",
+							"rhead".fg(state.parser.colors().info),
+							"rfoot".fg(state.parser.colors().info)
+						)
+					),
+				);
+				return None;
+			}
+			Some(row) => row.group = Some(group),
+			None => {
+				row.replace(RowProperties {
+					vspan: None,
+					align: None,
+					header: None,
+					group: Some(group),
 					borders: [None; 4],
 				});
 			}
@@ -267,6 +367,284 @@ fn parse_properties(
 	Some(cell_properties)
 }
 
+/// Gets the section of the table containing a row
+fn row_group(rows: &[Option<RowProperties>], row: usize) -> Option<RowGroup> {
+	rows.get(row)
+		.and_then(Option::as_ref)
+		.and_then(|row| row.group)
+}
+
+/// Parses the content of a cell
+///
+/// # Return
+///
+/// None is returned if the cell contains elements other than paragraphs, lists or blocks
+fn parse_cell<'a>(
+	state: &ParserState,
+	document: &'a dyn Document<'a>,
+	source: Rc<dyn Source>,
+) -> Option<Vec<Box<dyn Element>>> {
+	let parsed_cell = state.with_state(|new_state| {
+		new_state
+			.parser
+			.parse(new_state, source, Some(document), ParseMode::default())
+			.0
+	});
+	let mut parsed_content: Vec<Box<dyn Element>> = vec![];
+	for mut elem in parsed_cell.content().borrow_mut().drain(..) {
+		if let Some(paragraph) = elem.downcast_mut::<Paragraph>() {
+			// Insert space between paragraphs
+			if let Some(last) = parsed_content.last() {
+				if last.kind() == ElemKind::Inline {
+					parsed_content.push(Box::new(Text {
+						location: Token::new(
+							last.location().end()..last.location().end(),
+							last.location().source(),
+						),
+						content: " ".to_string(),
+					}) as Box<dyn Element>);
+				}
+			}
+			parsed_content.extend(std::mem::take(&mut paragraph.content));
+		} else if elem.downcast_ref::<Block>().is_some()
+			|| elem.downcast_ref::<ListEntry>().is_some()
+			|| elem.downcast_ref::<ListMarker>().is_some()
+		{
+			parsed_content.push(elem);
+		} else {
+			return None;
+		}
+	}
+	Some(parsed_content)
+}
+
+/// Cell of a table constructed from lua
+struct LuaCell {
+	content: String,
+	hspan: usize,
+	vspan: usize,
+	align: Option<Align>,
+	header: Option<HeaderScope>,
+}
+
+impl<'lua> FromLua<'lua> for LuaCell {
+	/// Cells are either a value converted to string, or a table with the content as first element
+	/// and optional keys `hspan`, `vspan`, `align` and `header`
+	fn from_lua(value: Value<'lua>, lua: &'lua Lua) -> mlua::Result<Self> {
+		let span = |table: &mlua::Table<'lua>, key: &str| -> mlua::Result<usize> {
+			match table.get::<_, Option<usize>>(key)? {
+				Some(0) => Err(mlua::Error::external(format!("`{key}` may not be 0"))),
+				span => Ok(span.unwrap_or(1)),
+			}
+		};
+		match value {
+			Value::Table(table) => Ok(LuaCell {
+				content: table.get::<_, Option<String>>(1)?.unwrap_or_default(),
+				hspan: span(&table, "hspan")?,
+				vspan: span(&table, "vspan")?,
+				align: table
+					.get::<_, Option<String>>("align")?
+					.map(|align| Align::try_from(&align))
+					.transpose()
+					.map_err(mlua::Error::external)?,
+				header: table
+					.get::<_, Option<String>>("header")?
+					.map(|header| HeaderScope::try_from(&header))
+					.transpose()
+					.map_err(mlua::Error::external)?,
+			}),
+			value => Ok(LuaCell {
+				content: String::from_lua(value, lua)?,
+				hspan: 1,
+				vspan: 1,
+				align: None,
+				header: None,
+			}),
+		}
+	}
+}
+
+/// Places cells constructed from lua on the table grid
+///
+/// Positions covered by a spanning cell are filled with references to that cell, `owning` is
+/// called to create the data of every other cell.
+///
+/// # Return
+///
+/// The dimensions of the table, as well as its cells
+fn lua_cells<F>(rows: &[Vec<LuaCell>], mut owning: F) -> Result<((usize, usize), Vec<Cell>), String>
+where
+	F: FnMut(&LuaCell) -> Result<CellData, String>,
+{
+	let width = rows
+		.first()
+		.map_or(0, |row| row.iter().map(|cell| cell.hspan).sum());
+	let height = rows.len();
+	let mut grid: Vec<Option<(usize, &LuaCell)>> = vec![None; width * height];
+
+	for (y, row) in rows.iter().enumerate() {
+		let mut x = 0;
+		for (i, cell) in row.iter().enumerate() {
+			while x < width && grid[x + y * width].is_some() {
+				x += 1;
+			}
+			if x + cell.hspan > width || y + cell.vspan > height {
+				return Err(format!(
+					"Cell {} of row {} does not fit in the {width}x{height} table",
+					i + 1,
+					y + 1
+				));
+			}
+			for j in y..y + cell.vspan {
+				for k in x..x + cell.hspan {
+					if grid[k + j * width].is_some() {
+						return Err(format!(
+							"Cell {} of row {} overlaps with another cell",
+							i + 1,
+							y + 1
+						));
+					}
+					grid[k + j * width] = Some((x + y * width, cell));
+				}
+			}
+			x += cell.hspan;
+		}
+		if let Some(x) = (0..width).find(|x| grid[x + y * width].is_none()) {
+			return Err(format!(
+				"Row {} has horizontal width {x}, table requires {width}",
+				y + 1
+			));
+		}
+	}
+
+	let mut cells = Vec::with_capacity(grid.len());
+	for (pos, (owner, cell)) in grid.into_iter().map(Option::unwrap).enumerate() {
+		if owner == pos {
+			cells.push(Cell::Owning(owning(cell)?));
+		} else {
+			cells.push(Cell::Reference(owner));
+		}
+	}
+	Ok(((width, height), cells))
+}
+
+/// Swaps the rows and columns of a table
+///
+/// Spans and header scopes of the cells are swapped. Columns can't hold the alignment and header of
+/// rows, so these are set on the cells of the rows instead. Cells of the table's header and footer
+/// become header cells.
+fn transpose(table: &mut Table) {
+	let (width, height) = table.size;
+	if table.data.len() != width * height {
+		// Invalid tables have already been reported
+		return;
+	}
+
+	let header = |scope: HeaderScope| match scope {
+		HeaderScope::Col => HeaderScope::Row,
+		HeaderScope::Row => HeaderScope::Col,
+		HeaderScope::ColGroup => HeaderScope::RowGroup,
+		HeaderScope::RowGroup => HeaderScope::ColGroup,
+	};
+	let position = |index: usize| (index % width) * height + index / width;
+	// Top and left borders are swapped, as well as bottom and right borders
+	let mirror = |mut borders: [Option<BorderStyle>; 4]| {
+		borders.reverse();
+		borders
+	};
+
+	// Owning cell of the positions covered by spanning cells
+	let mut owners = vec![None; table.data.len()];
+	for (index, cell) in table.data.iter_mut().enumerate() {
+		let Cell::Owning(cell) = cell else {
+			continue;
+		};
+		let (x, y) = (index % width, index / width);
+		for j in y..(y + cell.properties.vspan.unwrap_or(1)).min(height) {
+			for i in x..(x + cell.properties.hspan.unwrap_or(1)).min(width) {
+				owners[i + j * width] = Some(index);
+			}
+		}
+
+		let properties = &mut cell.properties;
+		if let Some(Some(row)) = table.rows.get(y) {
+			properties.align = properties.align.or(row.align);
+			properties.header = properties
+				.header
+				.or(row.header)
+				.or(row.group.map(|_| HeaderScope::Col));
+		}
+		std::mem::swap(&mut properties.hspan, &mut properties.vspan);
+		properties.header = properties.header.map(header);
+		properties.borders = mirror(properties.borders);
+	}
+
+	let mut data = std::mem::take(&mut table.data)
+		.into_iter()
+		.enumerate()
+		.map(|(index, cell)| match cell {
+			Cell::Owning(cell) => (position(index), Cell::Owning(cell)),
+			Cell::Reference(owner) => (
+				position(index),
+				Cell::Reference(position(owners[index].unwrap_or(owner))),
+			),
+		})
+		.collect::<Vec<_>>();
+	data.sort_by_key(|(index, _)| *index);
+	table.data = data.into_iter().map(|(_, cell)| cell).collect();
+
+	let mut columns = std::mem::take(&mut table.columns);
+	let mut rows = std::mem::take(&mut table.rows);
+	columns.resize_with(width, || None);
+	rows.resize_with(height, || None);
+	table.rows = columns
+		.into_iter()
+		.map(|column| {
+			column.map(|column| RowProperties {
+				vspan: column.hspan,
+				align: None,
+				header: None,
+				group: None,
+				borders: mirror(column.borders),
+			})
+		})
+		.collect();
+	table.columns = rows
+		.into_iter()
+		.map(|row| {
+			// Other properties have been moved to the cells
+			row.filter(|row| row.vspan.is_some() || row.borders.iter().any(Option::is_some))
+				.map(|row| ColumnProperties {
+					hspan: row.vspan,
+					borders: mirror(row.borders),
+				})
+		})
+		.collect();
+	table.size = (height, width);
+}
+
+/// Properties of tables constructed from lua
+fn lua_table_properties() -> PropertyParser {
+	let mut props = HashMap::new();
+	props.insert(
+		"title".to_string(),
+		Property::new("Table title".to_string(), None),
+	);
+	props.insert(
+		"refname".to_string(),
+		Property::new("Table reference name".to_string(), None),
+	);
+	props.insert(
+		"align".to_string(),
+		Property::new("Table text alignment".to_string(), None),
+	);
+	props.insert(
+		"transpose".to_string(),
+		Property::new("Swap the rows and columns of the table".to_string(), None),
+	);
+	PropertyParser { properties: props }
+}
+
 #[auto_registry::auto_registry(registry = "rules")]
 pub struct TableRule {
 	properties: PropertyParser,
@@ -283,6 +661,10 @@ impl Default for TableRule {
 			"export_as".to_string(),
 			Property::new("Export the table to LUA".to_string(), None),
 		);
+		props.insert(
+			"transpose".to_string(),
+			Property::new("Swap the rows and columns of the table".to_string(), None),
+		);
 
 		// Cell properties
 		let mut cell_props = HashMap::new();
@@ -298,6 +680,25 @@ impl Default for TableRule {
 			"align".to_string(),
 			Property::new("Cell text alignment".to_string(), None),
 		);
+		cell_props.insert(
+			"bg".to_string(),
+			Property::new("Cell background color, e.g `#f00`".to_string(), None),
+		);
+		cell_props.insert(
+			"border".to_string(),
+			Property::new(
+				"Cell borders: `solid`, `dashed`, `dotted` or `none`, for all sides or for the top, right, bottom and left sides".to_string(),
+				None,
+			),
+		);
+		cell_props.insert(
+			"header".to_string(),
+			Property::new(
+				"Makes the cell a header for its `col`, `row`, `colgroup` or `rowgroup`"
+					.to_string(),
+				None,
+			),
+		);
 
 		// Row properties
 		cell_props.insert(
@@ -308,6 +709,21 @@ impl Default for TableRule {
 			"rvspan".to_string(),
 			Property::new("Row vertical span".to_string(), None),
 		);
+		cell_props.insert(
+			"rheader".to_string(),
+			Property::new(
+				"Makes the cells of the row headers, see `header`".to_string(),
+				None,
+			),
+		);
+		cell_props.insert(
+			"rhead".to_string(),
+			Property::new("Places the row in the table header".to_string(), None),
+		);
+		cell_props.insert(
+			"rfoot".to_string(),
+			Property::new("Places the row in the table footer".to_string(), None),
+		);
 
 		// Column properties
 		cell_props.insert(
@@ -343,6 +759,13 @@ impl Rule for TableRule {
 
 	fn previous(&self) -> Option<&'static str> { Some("Toc") }
 
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> {
+		vec![
+			("Table", &self.properties),
+			("Table Cell", &self.cell_properties),
+		]
+	}
+
 	fn next_match(
 		&self,
 		mode: &ParseMode,
@@ -422,6 +845,12 @@ impl Rule for TableRule {
 			Some(name) => name,
 			None => return (end_cursor, reports),
 		};
+		let transposed = match properties.get_opt(&mut reports, "transpose", |_, value| {
+			value.value.parse::<bool>()
+		}) {
+			Some(transposed) => transposed.unwrap_or(false),
+			None => return (end_cursor, reports),
+		};
 
 		// Get table refname if any
 		let refname = match table_capture.get(2) {
@@ -576,51 +1005,18 @@ impl Rule for TableRule {
 			}
 
 			// Parse content
-			let parsed_cell = state.with_state(|new_state| {
-				new_state
-					.parser
-					.parse(
-						new_state,
-						cell_source.clone(),
-						Some(document),
-						ParseMode::default(),
+			let Some(parsed_content) = parse_cell(state, document, cell_source.clone()) else {
+				report_err!(
+					&mut reports,
+					end_cursor.source.clone(),
+					"Unable to Parse Table Cell".into(),
+					span(
+						captures.get(2).unwrap().range(),
+						"Cells may only contain paragraphs, lists or blocks".into()
 					)
-					.0
-			});
-			let mut parsed_content: Vec<Box<dyn Element>> = vec![];
-			for mut elem in parsed_cell.content().borrow_mut().drain(..) {
-				if let Some(paragraph) = elem.downcast_mut::<Paragraph>() {
-					// Insert space between paragraphs
-					if let Some(last) = parsed_content.last() {
-						if last.kind() == ElemKind::Inline {
-							parsed_content.push(Box::new(Text {
-								location: Token::new(
-									last.location().end()..last.location().end(),
-									last.location().source(),
-								),
-								content: " ".to_string(),
-							}) as Box<dyn Element>);
-						}
-					}
-					parsed_content.extend(std::mem::take(&mut paragraph.content));
-				} else if elem.downcast_ref::<Block>().is_some()
-					|| elem.downcast_ref::<ListEntry>().is_some()
-					|| elem.downcast_ref::<ListMarker>().is_some()
-				{
-					parsed_content.push(elem);
-				} else {
-					report_err!(
-						&mut reports,
-						end_cursor.source.clone(),
-						"Unable to Parse Table Cell".into(),
-						span(
-							captures.get(2).unwrap().range(),
-							"Cells may only contain paragraphs, lists or blocks".into()
-						)
-					);
-					return (end_cursor, reports);
-				}
-			}
+				);
+				return (end_cursor, reports);
+			};
 
 			// If empty, insert reference to owning cell, may insert multiple references
 			if prop_source.content().is_empty() && cell_source.content().trim_start().len() == 0 {
@@ -709,6 +1105,31 @@ impl Rule for TableRule {
 						)
 					);
 				}
+				// Header rows come first and footer rows last
+				if cell_pos.1 != 0 {
+					let message = match (
+						row_group(&table_state.rows, cell_pos.1 - 1),
+						row_group(&table_state.rows, cell_pos.1),
+					) {
+						(Some(RowGroup::Head), _) | (None, None) | (_, Some(RowGroup::Foot)) => {
+							None
+						}
+						(_, Some(RowGroup::Head)) => {
+							Some("Header rows must be at the start of the table")
+						}
+						(Some(RowGroup::Foot), None) => {
+							Some("Footer rows must be at the end of the table")
+						}
+					};
+					if let Some(message) = message {
+						report_err!(
+							&mut reports,
+							cursor.source.clone(),
+							"Invalid Table Row".into(),
+							span(table_state.current_row.clone(), message.into())
+						);
+					}
+				}
 
 				end_cursor.pos += 2;
 				table_state.current_row = end_cursor.pos..end_cursor.pos + 1;
@@ -747,6 +1168,52 @@ impl Rule for TableRule {
 			return (end_cursor, reports);
 		}
 
+		// Checks for cells spanning over multiple sections of the table
+		for (range, top_left, bottom_right) in &table_state.overlaps.cells {
+			let group = row_group(&table_state.rows, top_left.1);
+			if (top_left.1..bottom_right.1).any(|row| row_group(&table_state.rows, row) != group) {
+				report_err!(
+					&mut reports,
+					cursor.source.clone(),
+					"Invalid Table Cell".into(),
+					span(
+						range.clone(),
+						format!(
+							"Cell {} spans over rows from different sections of the table",
+							top_left.fg(state.parser.colors().info)
+						)
+					),
+					note(
+						"Cells of the header and footer may only span over rows of their section"
+							.into()
+					)
+				);
+				return (end_cursor, reports);
+			}
+		}
+
+		let mut table = Table {
+			location: Token::new(cursor.pos..end_cursor.pos, cursor.source.clone()),
+			size: (dimensions.0, dimensions.1),
+			columns: table_state.columns,
+			rows: table_state.rows,
+			properties: table_state.properties,
+			data: cells,
+			reference: refname,
+			title,
+			style: state
+				.shared
+				.styles
+				.borrow()
+				.current(TableStyle::key())
+				.downcast_rc::<TableStyle>()
+				.unwrap(),
+		};
+		if transposed {
+			transpose(&mut table);
+		}
+		let (dimensions, cells) = (table.size, &table.data);
+
 		if let Some(export_as) = export_as {
 			let mut kernels_borrow = state.shared.kernels.borrow_mut();
 			let kernel = kernels_borrow.get("main").unwrap();
@@ -792,20 +1259,132 @@ impl Rule for TableRule {
 			}
 		}
 
-		state.push(
-			document,
-			Box::new(Table {
-				location: Token::new(cursor.pos..end_cursor.pos, cursor.source.clone()),
-				size: (dimensions.0, dimensions.1),
-				columns: table_state.columns,
-				rows: table_state.rows,
-				properties: table_state.properties,
-				data: cells,
-				reference: refname,
-				title,
-			}),
-		);
+		state.push(document, Box::new(table));
 
 		(end_cursor, reports)
 	}
+
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
+		let mut bindings = vec![];
+
+		bindings.push(Binding::new(
+			"push",
+			"(rows: table, properties: string?)",
+			"Inserts a table, cells are values or tables with the content as first element and keys `hspan`, `vspan`, `align` and `header`",
+			lua.create_function(
+				|_, (rows, properties): (Vec<Vec<LuaCell>>, Option<String>)| {
+					let bad_argument = |pos: usize, name: &str, err: String| BadArgument {
+						to: Some("push".to_string()),
+						pos,
+						name: Some(name.to_string()),
+						cause: Arc::new(mlua::Error::external(err)),
+					};
+
+					// Cells may run lua code, the context is restored once the table is constructed
+					let Some(ctx) = CTX.take() else {
+						return Ok(());
+					};
+					let result = (|| {
+						// Parse properties
+						let prop_source = Rc::new(VirtualSource::new(
+							ctx.location.clone(),
+							":LUA:Table Properties".into(),
+							properties.unwrap_or_default(),
+						)) as Rc<dyn Source>;
+						let parser = lua_table_properties();
+						let Some(properties) = parser.parse(
+							"Lua Table",
+							&mut ctx.reports,
+							ctx.state,
+							prop_source.into(),
+						) else {
+							return Err(bad_argument(
+								2,
+								"properties",
+								"Failed to parse properties".into(),
+							));
+						};
+						let (Some(title), Some(refname), Some(align), Some(transposed)) = (
+							properties.get_opt(&mut ctx.reports, "title", |_, value| {
+								Result::<_, String>::Ok(value.value.clone())
+							}),
+							properties.get_opt(&mut ctx.reports, "refname", |_, value| {
+								validate_refname(ctx.document, value.value.as_str(), true)
+									.map(str::to_string)
+							}),
+							properties.get_opt(&mut ctx.reports, "align", |_, value| {
+								Align::try_from(&value.value)
+							}),
+							properties.get_opt(&mut ctx.reports, "transpose", |_, value| {
+								value.value.parse::<bool>()
+							}),
+						) else {
+							return Err(bad_argument(2, "properties", "Invalid properties".into()));
+						};
+
+						// Cells
+						let (size, data) = lua_cells(&rows, |cell| {
+							let source = Rc::new(VirtualSource::new(
+								ctx.location.clone(),
+								":LUA:Table Cell".into(),
+								cell.content.clone(),
+							)) as Rc<dyn Source>;
+							let content = parse_cell(ctx.state, ctx.document, source.clone())
+								.ok_or(
+									"Cells may only contain paragraphs, lists or blocks"
+										.to_string(),
+								)?;
+							Ok(CellData {
+								location: ctx.location.clone(),
+								content_location: source.into(),
+								content,
+								properties: CellProperties {
+									vspan: (cell.vspan != 1).then_some(cell.vspan),
+									hspan: (cell.hspan != 1).then_some(cell.hspan),
+									align: cell.align,
+									header: cell.header,
+									borders: [None; 4],
+									background: None,
+								},
+							})
+						})
+						.map_err(|err| bad_argument(1, "rows", err))?;
+
+						let mut table = Table {
+							location: ctx.location.clone(),
+							size,
+							columns: (0..size.0).map(|_| None).collect(),
+							rows: (0..size.1).map(|_| None).collect(),
+							properties: TableProperties {
+								align,
+								borders: [None; 4],
+							},
+							data,
+							title,
+							reference: refname,
+							style: ctx
+								.state
+								.shared
+								.styles
+								.borrow()
+								.current(TableStyle::key())
+								.downcast_rc::<TableStyle>()
+								.unwrap(),
+						};
+						if transposed.unwrap_or(false) {
+							transpose(&mut table);
+						}
+						ctx.state.push(ctx.document, Box::new(table));
+						Ok(())
+					})();
+					CTX.set(Some(ctx));
+
+					result
+				},
+			)
+			.unwrap(),
+		));
+
+		bindings
+	}
 }
@@ -0,0 +1,38 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::impl_elementstyle;
+
+use super::elem::BorderStyle;
+
+/// Padding of the cells
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum TablePadding {
+	Compact,
+	Normal,
+	Relaxed,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+#[auto_registry::auto_registry(registry = "elem_styles")]
+pub struct TableStyle {
+	/// Default borders of the cells, using the `table-border-*` classes
+	pub borders: BorderStyle,
+	/// Alternates the background of the rows, using the `table-striped` class
+	pub striped: bool,
+	/// Padding of the cells, using the `table-padding-*` classes
+	pub padding: TablePadding,
+}
+
+impl Default for TableStyle {
+	fn default() -> Self {
+		Self {
+			borders: BorderStyle::Solid,
+			striped: false,
+			padding: TablePadding::Normal,
+		}
+	}
+}
+
+impl_elementstyle!(TableStyle, "style.table");
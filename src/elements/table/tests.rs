@@ -1,6 +1,15 @@
+use std::cell::RefCell;
 use std::rc::Rc;
 
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target;
+use crate::compiler::exec::ExecPolicy;
+use crate::elements::style::elem::Style;
 use crate::elements::table::elem::Align;
+use crate::elements::table::elem::Cell;
+use crate::elements::table::elem::HeaderScope;
+use crate::elements::table::elem::RowGroup;
+use crate::elements::text::elem::Text;
 use crate::parser::langparser::LangParser;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::Parser;
@@ -47,3 +56,258 @@ pub fn parser() {
 	assert_eq!(table2.reference, Some("refname".to_string()));
 	assert_eq!(table2.title, Some("Title".to_string()));
 }
+
+#[test]
+pub fn lua() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+%<
+nml.table.push({
+	{ "A", { "B", hspan = 2 } },
+	{ { "**1**", vspan = 2 }, 2, 3.5 },
+	{ "4", { "5", align = "center" } },
+}, "title=From Lua, refname=lua, align=right")
+>%
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let borrow = doc.content().borrow_mut();
+	let table = &borrow[0].downcast_ref::<Table>().unwrap();
+	assert_eq!(table.size, (3, 3));
+	assert_eq!(table.properties.align, Some(Align::Right));
+	assert_eq!(table.title, Some("From Lua".to_string()));
+	assert_eq!(table.reference, Some("lua".to_string()));
+	assert!(matches!(table.data[2], Cell::Reference(1)));
+	assert!(matches!(table.data[6], Cell::Reference(3)));
+	let Cell::Owning(cell) = &table.data[3] else {
+		panic!("Expected owning cell");
+	};
+	assert_eq!(cell.properties.vspan, Some(2));
+	assert!(cell.content[0].downcast_ref::<Style>().is_some());
+	let Cell::Owning(cell) = &table.data[5] else {
+		panic!("Expected owning cell");
+	};
+	assert_eq!(
+		cell.content[0].downcast_ref::<Text>().unwrap().content,
+		"3.5"
+	);
+	let Cell::Owning(cell) = &table.data[8] else {
+		panic!("Expected owning cell");
+	};
+	assert_eq!(cell.properties.align, Some(Align::Center));
+}
+
+#[test]
+pub fn headers() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+| :rheader=col: Name | Value      |
+| :header=row: A     | 1          |
+| :header=row: B     | 2          |
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let borrow = doc.content().borrow();
+	let table = borrow.first().unwrap().downcast_ref::<Table>().unwrap();
+	assert_eq!(
+		table.rows[0].as_ref().unwrap().header,
+		Some(HeaderScope::Col)
+	);
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled
+		.body
+		.contains(r#"<tr><th scope="col">Name </th><th scope="col">Value </th></tr>"#));
+	assert!(compiled
+		.body
+		.contains(r#"<tr><th scope="row">A </th><td>1 </td></tr>"#));
+}
+
+#[test]
+pub fn transpose() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+:TABLE[transpose]
+| :rheader=col: Name | :hspan=2: Value |
+| A                  | 1 | 2           |
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let borrow = doc.content().borrow();
+	let table = borrow.first().unwrap().downcast_ref::<Table>().unwrap();
+	assert_eq!(table.size, (2, 3));
+	assert!(table.columns.iter().all(Option::is_none));
+	assert!(matches!(table.data[4], Cell::Reference(2)));
+	let Cell::Owning(cell) = &table.data[2] else {
+		panic!("Expected owning cell");
+	};
+	assert_eq!(cell.properties.vspan, Some(2));
+	assert_eq!(cell.properties.hspan, None);
+	assert_eq!(cell.properties.header, Some(HeaderScope::Row));
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled.body.contains(concat!(
+		r#"<table><tr><th scope="row">Name </th><td>A </td></tr>"#,
+		r#"<tr><th scope="row" rowspan="2">Value </th><td>1 </td></tr>"#,
+		r#"<tr><td>2 </td></tr></table>"#
+	)));
+}
+
+#[test]
+pub fn groups() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+| :rhead, vspan=2: Name | :hspan=2: Values |
+|                       | :rhead: A | B    |
+| x                     | 1         | 2    |
+| :rfoot: Total         | 1         | 2    |
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let borrow = doc.content().borrow();
+	let table = borrow.first().unwrap().downcast_ref::<Table>().unwrap();
+	assert_eq!(
+		table
+			.rows
+			.iter()
+			.map(|row| row.as_ref().and_then(|row| row.group))
+			.collect::<Vec<_>>(),
+		vec![
+			Some(RowGroup::Head),
+			Some(RowGroup::Head),
+			None,
+			Some(RowGroup::Foot)
+		]
+	);
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled.body.contains(concat!(
+		r#"<table><thead><tr><th scope="col" rowspan="2">Name </th>"#,
+		r#"<th scope="col" colspan="2">Values </th></tr>"#,
+		r#"<tr><th scope="col">A </th><th scope="col">B </th></tr></thead>"#,
+		r#"<tbody><tr><td>x </td><td>1 </td><td>2 </td></tr></tbody>"#,
+		r#"<tfoot><tr><th>Total </th><th>1 </th><th>2 </th></tr></tfoot></table>"#
+	)));
+}
+
+#[test]
+pub fn groups_errors() {
+	let errors = |content: &str| {
+		let source = Rc::new(SourceFile::with_content(
+			"".to_string(),
+			content.to_string(),
+			None,
+		));
+		let reports = RefCell::new(vec![]);
+		let parser = LangParser::new(
+			false,
+			Box::new(|_, list| {
+				reports
+					.borrow_mut()
+					.extend(list.into_iter().map(|report| report.message));
+			}),
+		);
+		let (doc, _) = parser.parse(
+			ParserState::new(&parser, None),
+			source,
+			None,
+			ParseMode::default(),
+		);
+		drop(doc);
+		drop(parser);
+		reports.into_inner()
+	};
+
+	assert!(errors("\n| :rhead: A |\n| B |\n| :rfoot: C |\n").is_empty());
+	assert_eq!(
+		errors("\n| A |\n| :rhead: B |\n"),
+		vec!["Invalid Table Row"]
+	);
+	assert_eq!(
+		errors("\n| :rfoot: A |\n| B |\n"),
+		vec!["Invalid Table Row"]
+	);
+	assert_eq!(
+		errors("\n| :rhead, rfoot: A |\n"),
+		vec!["Invalid Table Row"]
+	);
+	assert_eq!(
+		errors("\n| :rhead, vspan=2: A |\n| |\n"),
+		vec!["Invalid Table Cell"]
+	);
+}
+
+#[test]
+pub fn style() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@@style.table = { "borders": "Dashed", "striped": true }
+| :bg=#f00, border=none solid: A | :border=dotted: B |
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled.body.contains(concat!(
+		r#"<table class="table-border-dashed table-striped"><tr>"#,
+		r#"<td style="border-top-style: none;border-right-style: solid;border-bottom-style: none;border-left-style: solid;background-color: #ff0000;">A </td>"#,
+		r#"<td style="border-top-style: dotted;border-right-style: dotted;border-bottom-style: dotted;border-left-style: dotted;">B </td>"#
+	)));
+}
@@ -1,22 +1,38 @@
-use std::io::Read;
-use std::io::Write;
-use std::process::Command;
-use std::process::Stdio;
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::path::Path;
 use std::str::FromStr;
-use std::sync::Once;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 
 use crate::cache::cache::Cached;
 use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
 use crypto::digest::Digest;
 use crypto::sha2::Sha512;
+use latex2mathml::latex_to_mathml;
+use latex2mathml::DisplayStyle;
 
 use crate::cache::cache::CachedError;
 use crate::compiler::compiler::Compiler;
+use crate::compiler::exec::run;
+use crate::compiler::svg::recolor;
+use crate::compiler::svg::size_attributes;
 use crate::document::document::Document;
 use crate::document::element::ElemKind;
 use crate::document::element::Element;
+use crate::document::element::ReferenceableElement;
+use crate::elements::reference::elem::InternalReference;
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
 use crate::parser::source::Token;
 
+/// Time after which rendering a latex fragment is aborted, unless set by `tex.{env}.timeout`
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum TexKind {
 	Block,
@@ -52,6 +68,12 @@ pub struct Tex {
 	pub(crate) env: String,
 	pub(crate) tex: String,
 	pub(crate) caption: Option<String>,
+	/// Text read by screen readers, defaults to the TeX source
+	pub(crate) alt: Option<String>,
+	/// Reference name of the equation, numbered equations are referenceable
+	pub(crate) reference: Option<String>,
+	/// Rendering result, set by [`Tex::prerender`]
+	pub(crate) rendered: OnceCell<Result<String, String>>,
 }
 
 impl Tex {
@@ -67,39 +89,359 @@ impl Tex {
 			fontsize, preamble, tex
 		))
 	}
-}
 
-struct FormattedTex(String);
+	/// Gets the MathML representation of math mode elements, when `tex.{env}.mathml` is `true`
+	fn mathml(&self, compiler: &Compiler, document: &dyn Document) -> Option<String> {
+		document
+			.get_variable(format!("tex.{}.mathml", self.env).as_str())
+			.filter(|var| self.mathmode && var.to_string() == "true")
+			.and_then(|_| {
+				let display = match self.kind {
+					TexKind::Inline => DisplayStyle::Inline,
+					TexKind::Block => DisplayStyle::Block,
+				};
+				match latex_to_mathml(&self.tex, display) {
+					Ok(mathml) => Some(mathml),
+					Err(err) => {
+						compiler.log(
+							format!("Unable to convert `{}` to MathML: {err}", self.tex).as_str(),
+						);
+						None
+					}
+				}
+			})
+	}
 
-impl FormattedTex {
-	/// Renders latex to svg
-	fn latex_to_svg(&self, exec: &String, fontsize: &String) -> Result<String, String> {
-		print!("Rendering LaTex `{}`... ", self.0);
-		let process = match Command::new(exec)
-			.arg("--fontsize")
-			.arg(fontsize)
-			.stdout(Stdio::piped())
-			.stdin(Stdio::piped())
-			.spawn()
-		{
-			Err(e) => return Err(format!("Could not spawn `{exec}`: {}", e)),
-			Ok(process) => process,
+	/// Makes a rendered svg accessible
+	///
+	/// The svg is labelled with the alternative text and given the caption (or alternative text) as
+	/// title. When `tex.{env}.mathml` is `true`, math mode elements are followed by their MathML
+	/// representation for screen readers, and the svg is hidden from them.
+	fn accessible_svg(
+		&self,
+		compiler: &Compiler,
+		document: &dyn Document,
+		mut svg: String,
+	) -> String {
+		let Some(start) = svg.find("<svg").map(|start| start + 4) else {
+			return svg;
+		};
+		let alt = self.alt.as_ref().unwrap_or(&self.tex);
+		let title = self.caption.as_ref().unwrap_or(alt);
+
+		if let Some(end) = svg[start..].find('>') {
+			svg.insert_str(
+				start + end + 1,
+				format!("<title>{}</title>", Compiler::sanitize(HTML, title)).as_str(),
+			);
+		}
+		match self.mathml(compiler, document) {
+			Some(mathml) => {
+				svg.insert_str(start, r#" aria-hidden="true""#);
+				svg + format!(r#"<span class="tex-mathml">{mathml}</span>"#).as_str()
+			}
+			None => {
+				svg.insert_str(
+					start,
+					format!(
+						r#" role="img" aria-label="{}""#,
+						Compiler::sanitize(HTML, alt)
+					)
+					.as_str(),
+				);
+				svg
+			}
+		}
+	}
+
+	/// Displays a rendered svg added to the asset store, see [`Compiler::store_asset`]
+	///
+	/// The image is given the same alternative text, title and MathML as
+	/// [`Self::accessible_svg`].
+	fn stored_image(
+		&self,
+		compiler: &Compiler,
+		document: &dyn Document,
+		svg: &str,
+		link: &str,
+	) -> String {
+		let alt = self.alt.as_ref().unwrap_or(&self.tex);
+		let title = self.caption.as_ref().unwrap_or(alt);
+		let image = |alt: &str| {
+			format!(
+				r#"<img class="tex-image" src="{}" alt="{}" title="{}"{}>"#,
+				Compiler::sanitize_url(HTML, link),
+				Compiler::sanitize(HTML, alt),
+				Compiler::sanitize(HTML, title),
+				size_attributes(svg)
+			)
 		};
+		match self.mathml(compiler, document) {
+			Some(mathml) => {
+				image("") + format!(r#"<span class="tex-mathml">{mathml}</span>"#).as_str()
+			}
+			None => image(alt),
+		}
+	}
+
+	/// Displays the number of referenceable equations next to them
+	fn numbered(&self, compiler: &Compiler, document: &dyn Document, html: String) -> String {
+		let Some(reference) = &self.reference else {
+			return html;
+		};
+		let elemref = document.get_reference(reference.as_str()).unwrap();
+		let refcount = compiler.reference_id(document, elemref);
+		format!(
+			r#"<div class="equation" id="{}">{html}<span class="equation-number">({refcount})</span></div>"#,
+			self.refid(compiler, refcount)
+		)
+	}
+
+	/// Gets the latex to render and the options to render it with
+	fn render_options(
+		&self,
+		document: &dyn Document,
+	) -> Result<(FormattedTex, RenderOptions), String> {
+		let exec = document
+			.get_variable(format!("tex.{}.exec", self.env).as_str())
+			.map_or("latex2svg".to_string(), |var| var.to_string());
+		// FIXME: Because fontsize is passed as an arg, verify that it cannot be used to execute python/shell code
+		let fontsize = document
+			.get_variable(format!("tex.{}.fontsize", self.env).as_str())
+			.map_or("12".to_string(), |var| var.to_string());
+		let preamble = document
+			.get_variable(format!("tex.{}.preamble", self.env).as_str())
+			.map_or("".to_string(), |var| var.to_string());
+		let prepend = if self.mathmode {
+			"".to_string()
+		} else {
+			document
+				.get_variable(format!("tex.{}.block_prepend", self.env).as_str())
+				.map_or("".to_string(), |var| var.to_string() + "\n")
+		};
+		let timeout = match document.get_variable(format!("tex.{}.timeout", self.env).as_str()) {
+			Some(var) => var
+				.to_string()
+				.trim()
+				.parse::<f64>()
+				.ok()
+				.and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+				.filter(|timeout| !timeout.is_zero())
+				.ok_or(format!(
+					"Invalid value for `tex.{}.timeout`: expected a positive number of seconds, got `{}`",
+					self.env,
+					var.to_string()
+				))?,
+			None => DEFAULT_TIMEOUT,
+		};
+
+		let latex = if self.mathmode {
+			Tex::format_latex(&fontsize, &preamble, &format!("${{{}}}$", self.tex))
+		} else {
+			Tex::format_latex(&fontsize, &preamble, &format!("{prepend}{}", self.tex))
+		};
+
+		Ok((
+			latex,
+			RenderOptions {
+				exec,
+				fontsize,
+				timeout,
+			},
+		))
+	}
+
+	/// Renders every latex element of a document ahead of compilation
+	///
+	/// Elements missing from the cache are rendered concurrently, the number of processes is set by
+	/// variable `tex.jobs` and defaults to the available parallelism. Rendering failures are
+	/// reported at the location of the failing elements, which then compile to their source.
+	pub fn prerender(compiler: &Compiler, document: &dyn Document) {
+		if !matches!(compiler.target(), HTML | SLIDES) {
+			return;
+		}
+		fn collect<'a>(elems: &'a [Box<dyn Element>], list: &mut Vec<&'a Tex>) {
+			for elem in elems {
+				if let Some(tex) = elem.downcast_ref::<Tex>() {
+					list.push(tex);
+				} else if let Some(container) = elem.as_container() {
+					collect(container.contained(), list);
+				}
+			}
+		}
+		let content = document.content().borrow();
+		let mut elems = vec![];
+		collect(&content, &mut elems);
+		elems.retain(|tex| tex.rendered.get().is_none());
+
+		// Identical fragments are only rendered once
+		let mut reports = vec![];
+		let mut fragments = vec![];
+		let mut pending = vec![];
+		let mut ids = HashMap::new();
+		for tex in elems {
+			let (latex, options) = match tex.render_options(document) {
+				Ok(result) => result,
+				Err(err) => {
+					report_err!(
+						&mut reports,
+						tex.location.source(),
+						"LaTeX Rendering Failed".into(),
+						span(tex.location.range.clone(), err.clone())
+					);
+					let _ = tex.rendered.set(Err(err));
+					continue;
+				}
+			};
+			if let Some(con) = compiler.cache() {
+				if let Ok(Some(svg)) = latex.lookup(con) {
+					let _ = tex.rendered.set(Ok(svg));
+					continue;
+				}
+			}
+			// Cached fragments remain available when the executable is refused
+			let options = match compiler.exec_policy().resolve(&options.exec) {
+				Ok(exec) => RenderOptions {
+					exec: exec.to_string_lossy().to_string(),
+					..options
+				},
+				Err(err) => {
+					report_warn!(
+						&mut reports,
+						tex.location.source(),
+						"External Command Refused".into(),
+						span(tex.location.range.clone(), err.clone()),
+						note("The element is displayed as source code".into())
+					);
+					let _ = tex.rendered.set(Err(err));
+					continue;
+				}
+			};
+			let id = *ids.entry(latex.0.clone()).or_insert_with(|| {
+				fragments.push((latex, options));
+				fragments.len() - 1
+			});
+			pending.push((tex, id));
+		}
 
-		if let Err(e) = process.stdin.unwrap().write_all(self.0.as_bytes()) {
-			panic!("Unable to write to `latex2svg`'s stdin: {}", e);
+		let jobs = match document
+			.get_variable("tex.jobs")
+			.filter(|_| !fragments.is_empty())
+		{
+			Some(var) => match var.to_string().trim().parse::<usize>() {
+				Ok(jobs) if jobs != 0 => jobs,
+				_ => {
+					compiler.log(
+						format!(
+							"Invalid value for `tex.jobs` in {}: expected a positive integer, got `{}`",
+							document.source().name(),
+							var.to_string()
+						)
+						.as_str(),
+					);
+					1
+				}
+			},
+			None => thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+		};
+		for (latex, _) in &fragments {
+			compiler.log(format!("Rendering LaTex `{}`...", latex.0).as_str());
 		}
+		let results = render_all(&fragments, jobs);
 
-		let mut result = String::new();
-		if let Err(e) = process.stdout.unwrap().read_to_string(&mut result) {
-			panic!("Unable to read `latex2svg` stdout: {}", e)
+		let mut cache_errors = HashMap::new();
+		if let Some(con) = compiler.cache() {
+			for (id, ((latex, _), result)) in fragments.iter().zip(&results).enumerate() {
+				if let Ok(svg) = result {
+					if let Err(err) = latex.insert(con, svg) {
+						cache_errors.insert(id, err.to_string());
+					}
+				}
+			}
+		}
+		for (tex, id) in pending {
+			if let Err(err) = &results[id] {
+				report_err!(
+					&mut reports,
+					tex.location.source(),
+					"LaTeX Rendering Failed".into(),
+					span(tex.location.range.clone(), err.clone())
+				);
+			} else if let Some(err) = cache_errors.remove(&id) {
+				report_warn!(
+					&mut reports,
+					tex.location.source(),
+					"Unable to cache LaTeX".into(),
+					span(tex.location.range.clone(), err)
+				);
+			}
+			let _ = tex.rendered.set(results[id].clone());
 		}
-		println!("Done!");
+		compiler.report(reports);
+	}
+}
 
-		Ok(result)
+pub(crate) struct FormattedTex(String);
+
+impl FormattedTex {
+	/// Renders latex to svg
+	///
+	/// The process is killed if it doesn't complete within `timeout`.
+	fn latex_to_svg(
+		&self,
+		exec: &str,
+		fontsize: &str,
+		timeout: Duration,
+	) -> Result<String, String> {
+		run(
+			Path::new(exec),
+			&["--fontsize", fontsize],
+			self.0.as_str(),
+			timeout,
+		)
 	}
 }
 
+/// Options used to render a [`FormattedTex`]
+struct RenderOptions {
+	exec: String,
+	fontsize: String,
+	timeout: Duration,
+}
+
+/// Renders a list of latex fragments, using at most `jobs` concurrent processes
+///
+/// # Returns
+///
+/// The rendering results, in the same order as `fragments`.
+fn render_all(
+	fragments: &[(FormattedTex, RenderOptions)],
+	jobs: usize,
+) -> Vec<Result<String, String>> {
+	let next = AtomicUsize::new(0);
+	let results = Mutex::new((0..fragments.len()).map(|_| None).collect::<Vec<_>>());
+
+	thread::scope(|scope| {
+		for _ in 0..jobs.clamp(1, fragments.len().max(1)) {
+			scope.spawn(|| loop {
+				let id = next.fetch_add(1, Ordering::Relaxed);
+				let Some((latex, options)) = fragments.get(id) else {
+					break;
+				};
+				let result = latex.latex_to_svg(&options.exec, &options.fontsize, options.timeout);
+				results.lock().unwrap()[id] = Some(result);
+			});
+		}
+	});
+
+	results
+		.into_inner()
+		.unwrap()
+		.into_iter()
+		.map(Option::unwrap)
+		.collect()
+}
+
 impl Cached for FormattedTex {
 	type Key = String;
 	type Value = String;
@@ -136,67 +478,93 @@ impl Element for Tex {
 		_cursor: usize,
 	) -> Result<String, String> {
 		match compiler.target() {
-			HTML => {
-				static CACHE_INIT: Once = Once::new();
-				CACHE_INIT.call_once(|| {
-					if let Some(con) = compiler.cache() {
-						if let Err(e) = FormattedTex::init(con) {
-							eprintln!("Unable to create cache table: {e}");
+			HTML | SLIDES => {
+				let result = match self.rendered.get() {
+					// Already reported
+					Some(Err(_)) => {
+						return Ok(self.numbered(
+							compiler,
+							document,
+							format!(
+								"<code class=\"tex-error\">{}</code>",
+								Compiler::sanitize(HTML, &self.tex)
+							),
+						))
+					}
+					Some(result) => result.clone(),
+					None => {
+						let (latex, options) = self.render_options(document)?;
+						let exec = compiler.exec_policy().resolve(&options.exec)?;
+						let render = |latex: &FormattedTex| {
+							compiler.log(format!("Rendering LaTex `{}`...", latex.0).as_str());
+							latex.latex_to_svg(
+								&exec.to_string_lossy(),
+								&options.fontsize,
+								options.timeout,
+							)
+						};
+						match compiler.cache() {
+							Some(con) => latex.cached(con, render).map_err(|err| match err {
+								CachedError::SqlErr(e) => format!("Querying the cache failed: {e}"),
+								CachedError::GenErr(e) => e,
+							}),
+							None => render(&latex),
 						}
 					}
-				});
-
-				let exec = document
-					.get_variable(format!("tex.{}.exec", self.env).as_str())
-					.map_or("latex2svg".to_string(), |var| var.to_string());
-				// FIXME: Because fontsize is passed as an arg, verify that it cannot be used to execute python/shell code
-				let fontsize = document
-					.get_variable(format!("tex.{}.fontsize", self.env).as_str())
-					.map_or("12".to_string(), |var| var.to_string());
-				let preamble = document
-					.get_variable(format!("tex.{}.preamble", self.env).as_str())
-					.map_or("".to_string(), |var| var.to_string());
-				let prepend = if self.mathmode {
-					"".to_string()
-				} else {
-					document
-						.get_variable(format!("tex.{}.block_prepend", self.env).as_str())
-						.map_or("".to_string(), |var| var.to_string() + "\n")
 				};
 
-				let latex = if self.mathmode {
-					Tex::format_latex(&fontsize, &preamble, &format!("${{{}}}$", self.tex))
-				} else {
-					Tex::format_latex(&fontsize, &preamble, &format!("{prepend}{}", self.tex))
-				};
+				result
+					.map(|svg| {
+						match document.get_variable(format!("tex.{}.color", self.env).as_str()) {
+							Some(color) => recolor(&svg, &color.to_string()),
+							None => svg,
+						}
+					})
+					.and_then(|svg| {
+						match compiler.store_asset(document, svg.as_bytes(), "svg")? {
+							Some(link) => Ok(self.stored_image(compiler, document, &svg, &link)),
+							None => Ok(self.accessible_svg(compiler, document, svg)),
+						}
+					})
+					.map(|html| self.numbered(compiler, document, html))
+			}
+			_ => todo!("Unimplemented"),
+		}
+	}
 
-				let result = if let Some(con) = compiler.cache() {
-					match latex.cached(con, |s| s.latex_to_svg(&exec, &fontsize)) {
-						Ok(s) => Ok(s),
-						Err(e) => match e {
-							CachedError::SqlErr(e) => {
-								Err(format!("Querying the cache failed: {e}"))
-							}
-							CachedError::GenErr(e) => Err(e),
-						},
-					}
-				} else {
-					latex.latex_to_svg(&exec, &fontsize)
-				};
+	fn as_referenceable(&self) -> Option<&dyn ReferenceableElement> { Some(self) }
+}
 
-				// Caption
-				result.map(|mut result| {
-					if let (Some(caption), Some(start)) = (&self.caption, result.find('>')) {
-						result.insert_str(
-							start + 1,
-							format!("<title>{}</title>", Compiler::sanitize(HTML, caption))
-								.as_str(),
-						);
-					}
-					result
-				})
+impl ReferenceableElement for Tex {
+	fn reference_name(&self) -> Option<&String> { self.reference.as_ref() }
+
+	fn refcount_key(&self) -> &'static str { "equation" }
+
+	fn compile_reference(
+		&self,
+		compiler: &Compiler,
+		document: &dyn Document,
+		reference: &InternalReference,
+		refid: usize,
+		cursor: usize,
+	) -> Result<String, String> {
+		match compiler.target() {
+			HTML | SLIDES => {
+				let link = format!(
+					"<a class=\"equation-ref\" href=\"#{}\">",
+					self.refid(compiler, refid)
+				);
+				let caption =
+					match reference.compile_caption(compiler, document, cursor + link.len()) {
+						Some(caption) => caption?,
+						None => format!("({refid})"),
+					};
+
+				Ok(format!("{link}{caption}</a>"))
 			}
-			_ => todo!("Unimplemented"),
+			_ => todo!(""),
 		}
 	}
+
+	fn refid(&self, _compiler: &Compiler, refid: usize) -> String { format!("equation-{refid}") }
 }
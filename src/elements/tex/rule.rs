@@ -1,10 +1,10 @@
 use ariadne::Fmt;
 use document::document::Document;
+use document::references::validate_refname;
 use lsp::code::CodeRange;
 use lsp::semantic::Semantics;
 use lua::kernel::CTX;
 use mlua::Error::BadArgument;
-use mlua::Function;
 use mlua::Lua;
 use parser::parser::ParseMode;
 use parser::parser::ParserState;
@@ -16,10 +16,12 @@ use parser::util::escape_source;
 use parser::util::escape_text;
 use regex::Captures;
 use regex::Regex;
+use std::cell::OnceCell;
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
 
+use crate::lua::kernel::Binding;
 use crate::parser::reports::macros::*;
 use crate::parser::reports::*;
 
@@ -40,12 +42,27 @@ impl Default for TexRule {
 		);
 		props.insert(
 			"kind".to_string(),
-			Property::new("Element display kind".to_string(), None),
+			Property::new("Element display kind".to_string(), None).primary(),
 		);
 		props.insert(
 			"caption".to_string(),
 			Property::new("Latex caption".to_string(), None),
 		);
+		props.insert(
+			"ref".to_string(),
+			Property::new(
+				"Reference name of the equation, numbered equations are displayed as blocks"
+					.to_string(),
+				None,
+			),
+		);
+		props.insert(
+			"alt".to_string(),
+			Property::new(
+				"Text read by screen readers, defaults to the TeX source".to_string(),
+				None,
+			),
+		);
 		Self {
 			re: [
 				Regex::new(r"\$\|(?:\[((?:\\.|[^\\\\])*?)\])?(?:((?:\\.|[^\\\\])*?)\|\$)?")
@@ -60,7 +77,9 @@ impl Default for TexRule {
 impl RegexRule for TexRule {
 	fn name(&self) -> &'static str { "Tex" }
 
-	fn previous(&self) -> Option<&'static str> { Some("Code") }
+	fn previous(&self) -> Option<&'static str> { Some("Code Callout") }
+
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> { vec![("Tex", &self.properties)] }
 
 	fn regexes(&self) -> &[regex::Regex] { &self.re }
 
@@ -132,11 +151,17 @@ impl RegexRule for TexRule {
 			None => return reports,
 		};
 
-		let (tex_kind, caption, tex_env) = match (
+		let reference = match properties.get_opt(&mut reports, "ref", |_, value| {
+			validate_refname(document, value.value.as_str(), true).map(str::to_string)
+		}) {
+			Some(reference) => reference,
+			None => return reports,
+		};
+		let (tex_kind, caption, alt, tex_env) = match (
 			properties.get_or(
 				&mut reports,
 				"kind",
-				if index == 1 {
+				if index == 1 && reference.is_none() {
 					TexKind::Inline
 				} else {
 					TexKind::Block
@@ -146,13 +171,33 @@ impl RegexRule for TexRule {
 			properties.get_opt(&mut reports, "caption", |_, value| {
 				Result::<_, String>::Ok(value.value.clone())
 			}),
+			properties.get_opt(&mut reports, "alt", |_, value| {
+				Result::<_, String>::Ok(value.value.clone())
+			}),
 			properties.get(&mut reports, "env", |_, value| {
 				Result::<_, String>::Ok(value.value.clone())
 			}),
 		) {
-			(Some(tex_kind), Some(caption), Some(tex_env)) => (tex_kind, caption, tex_env),
+			(Some(tex_kind), Some(caption), Some(alt), Some(tex_env)) => {
+				(tex_kind, caption, alt, tex_env)
+			}
 			_ => return reports,
 		};
+		if reference.is_some() && tex_kind == TexKind::Inline {
+			report_err!(
+				&mut reports,
+				token.source(),
+				"Invalid Tex Properties".into(),
+				span(
+					matches.get(1).unwrap().range(),
+					format!(
+						"Equations with a {} must be displayed as blocks",
+						"ref".fg(state.parser.colors().info)
+					)
+				)
+			);
+			return reports;
+		}
 
 		// Code ranges
 		if let Some(coderanges) = CodeRange::from_source(token.source(), &state.shared.lsp) {
@@ -181,6 +226,9 @@ impl RegexRule for TexRule {
 				env: tex_env,
 				tex: tex_content,
 				caption,
+				alt,
+				reference,
+				rendered: OnceCell::new(),
 			}),
 		);
 
@@ -205,10 +253,12 @@ impl RegexRule for TexRule {
 		reports
 	}
 
-	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
 		let mut bindings = vec![];
-		bindings.push((
-			"push_math".to_string(),
+		bindings.push(Binding::new(
+			"push_math",
+			"(kind: string, tex: string, env: string?, caption: string?)",
+			"Inserts LaTeX in math mode, `kind` is `inline` or `block`",
 			lua.create_function(
 				|_, (kind, tex, env, caption): (String, String, Option<String>, Option<String>)| {
 					let mut result = Ok(());
@@ -238,6 +288,9 @@ impl RegexRule for TexRule {
 									env: env.unwrap_or("main".to_string()),
 									tex,
 									caption,
+									alt: None,
+									reference: None,
+									rendered: OnceCell::new(),
 								}),
 							);
 						})
@@ -249,8 +302,10 @@ impl RegexRule for TexRule {
 			.unwrap(),
 		));
 
-		bindings.push((
-			"push".to_string(),
+		bindings.push(Binding::new(
+			"push",
+			"(kind: string, tex: string, env: string?, caption: string?)",
+			"Inserts LaTeX, `kind` is `inline` or `block`",
 			lua.create_function(
 				|_, (kind, tex, env, caption): (String, String, Option<String>, Option<String>)| {
 					let mut result = Ok(());
@@ -280,6 +335,9 @@ impl RegexRule for TexRule {
 									env: env.unwrap_or("main".to_string()),
 									tex,
 									caption,
+									alt: None,
+									reference: None,
+									rendered: OnceCell::new(),
 								}),
 							);
 						})
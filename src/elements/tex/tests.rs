@@ -1,5 +1,9 @@
 use std::rc::Rc;
 
+use crate::cache::cache;
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target;
+use crate::compiler::exec::ExecPolicy;
 use crate::elements::paragraph::elem::Paragraph;
 use crate::elements::tex::elem::Tex;
 use crate::parser::langparser::LangParser;
@@ -7,6 +11,7 @@ use crate::parser::parser::ParseMode;
 use crate::parser::parser::Parser;
 use crate::parser::parser::ParserState;
 use crate::parser::source::SourceFile;
+use crate::tempdir::TempDir;
 use crate::validate_document;
 use crate::validate_semantics;
 
@@ -106,3 +111,264 @@ $[kind=inline]\LaTeX$
 		tex_sep { delta_line == 0, delta_start == 6, length == 1 };
 	);
 }
+
+#[cfg(unix)]
+fn render_script(dir: &std::path::Path, name: &str, script: &str) -> std::path::PathBuf {
+	use std::os::unix::fs::PermissionsExt;
+
+	let path = dir.join(name);
+	std::fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+	std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+	path
+}
+
+#[test]
+#[cfg(all(unix, feature = "exec"))]
+fn render() {
+	let dir = TempDir::new("tex-render");
+	let ok = render_script(&dir, "ok", "cat > /dev/null; echo '<svg>rendered</svg>'");
+	let fail = render_script(
+		&dir,
+		"fail",
+		"cat > /dev/null; echo 'Undefined control sequence' >&2; exit 3",
+	);
+	let slow = render_script(&dir, "slow", "sleep 5");
+
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		format!(
+			r#"
+@tex.main.exec = {}
+@tex.fail.exec = {}
+@tex.slow.exec = {}
+@tex.slow.timeout = 0.2
+@tex.jobs = 2
+$a$ $a$ $[env=fail]b$ $[env=slow]c$
+"#,
+			ok.display(),
+			fail.display(),
+			slow.display()
+		),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let exec = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert_eq!(compiled.body.matches(">rendered</svg>").count(), 2);
+	assert_eq!(
+		compiled.body.matches("<code class=\"tex-error\">").count(),
+		2
+	);
+
+	let content = doc.content().borrow();
+	let paragraph = content.last().unwrap().downcast_ref::<Paragraph>().unwrap();
+	let results = paragraph
+		.content
+		.iter()
+		.filter_map(|elem| elem.downcast_ref::<Tex>())
+		.map(|tex| tex.rendered.get().unwrap().clone())
+		.collect::<Vec<_>>();
+	assert!(results[0].is_ok());
+	assert!(results[2]
+		.as_ref()
+		.unwrap_err()
+		.contains("Undefined control sequence"));
+	assert!(results[3].as_ref().unwrap_err().contains("timed out"));
+}
+
+#[test]
+fn refused() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		"$a$ $|b|$".to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let exec = ExecPolicy {
+		disabled: true,
+		..Default::default()
+	};
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled.body.contains("<code class=\"tex-error\">a</code>"));
+	assert!(compiled.body.contains("<code class=\"tex-error\">b</code>"));
+}
+
+#[test]
+#[cfg(all(unix, feature = "exec"))]
+fn preamble_cache() {
+	let dir = TempDir::new("tex-preamble");
+	let exec = render_script(
+		&dir,
+		"preamble",
+		"echo \"<svg>$(grep -o usepackage | wc -l | tr -d ' ')</svg>\"",
+	);
+	let con = cache::open_in_memory().unwrap();
+
+	let compile = |preamble: &str| {
+		let source = Rc::new(SourceFile::with_content(
+			"".to_string(),
+			format!(
+				"@tex.main.exec = {}\n@tex.main.preamble = {preamble}\n$x$\n",
+				exec.display()
+			),
+			None,
+		));
+		let parser = LangParser::default();
+		let (doc, _) = parser.parse(
+			ParserState::new(&parser, None),
+			source,
+			None,
+			ParseMode::default(),
+		);
+		let policy = ExecPolicy::default();
+		let compiler = Compiler::new(Target::HTML, Some(&con), &policy);
+		compiler.compile(&*doc).0.body
+	};
+	assert!(compile("\\usepackage{a}").contains(">1</svg>"));
+	assert!(compile("\\usepackage{a} \\usepackage{b}").contains(">2</svg>"));
+	std::fs::remove_file(&exec).unwrap();
+	// Cached
+	assert!(compile("\\usepackage{a}").contains(">1</svg>"));
+}
+
+#[test]
+#[cfg(all(unix, feature = "exec"))]
+fn accessibility() {
+	let dir = TempDir::new("tex-a11y");
+	let exec = render_script(&dir, "a11y", "cat > /dev/null; echo '<svg>rendered</svg>'");
+
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		format!(
+			r#"
+@tex.main.exec = {0}
+@tex.other.exec = {0}
+@tex.other.mathml = true
+$x<1$ $[alt=x less than one, caption=Bound]x<1$ $[env=other]x^2$
+"#,
+			exec.display()
+		),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let policy = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, None, &policy);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled
+		.body
+		.contains(r#"<svg role="img" aria-label="x&lt;1"><title>x&lt;1</title>rendered</svg>"#));
+	assert!(compiled.body.contains(
+		r#"<svg role="img" aria-label="x less than one"><title>Bound</title>rendered</svg>"#
+	));
+	assert!(compiled
+		.body
+		.contains(r#"<svg aria-hidden="true"><title>x^2</title>rendered</svg>"#));
+	assert!(compiled.body.contains(r#"<span class="tex-mathml"><math"#));
+}
+
+#[test]
+fn equations() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+$[ref=eq:energy] E=mc^2 $
+$|[ref=eq:sum] \sum |$
+See &{eq:energy} and &{eq:sum}[caption=the sum].
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let exec = ExecPolicy {
+		disabled: true,
+		..Default::default()
+	};
+	let compiler = Compiler::new(Target::HTML, None, &exec);
+	let (compiled, _) = compiler.compile(&*doc);
+	assert!(compiled.body.contains(concat!(
+		r#"<div class="equation" id="equation-1"><code class="tex-error">E=mc^2</code>"#,
+		r#"<span class="equation-number">(1)</span></div>"#
+	)));
+	assert!(compiled
+		.body
+		.contains(r#"<div class="equation" id="equation-2">"#));
+	assert!(compiled
+		.body
+		.contains(r##"<a class="equation-ref" href="#equation-1">(1)</a>"##));
+	assert!(compiled
+		.body
+		.contains(r##"<a class="equation-ref" href="#equation-2">the sum</a>"##));
+}
+
+#[test]
+#[cfg(all(unix, feature = "exec"))]
+fn store() {
+	let dir = TempDir::new("tex-store");
+	let exec = render_script(
+		&dir,
+		"store",
+		"cat > /dev/null; echo '<svg width=\"2ex\" height=\"1ex\">stored</svg>'",
+	);
+	let con = cache::open_in_memory().unwrap();
+
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		format!(
+			"@tex.main.exec = {}\n@html.assets.store = true\n@compiler.output = dir/page.html\n$x$ $x$\n",
+			exec.display()
+		),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+	let policy = ExecPolicy::default();
+	let compiler = Compiler::new(Target::HTML, Some(&con), &policy);
+	let (compiled, _) = compiler.compile(&*doc);
+
+	// Identical formulas are stored once
+	assert_eq!(compiled.assets.len(), 1);
+	let image = format!(
+		r#"<img class="tex-image" src="../assets/{}" alt="x" title="x" width="2ex" height="1ex">"#,
+		compiled.assets[0]
+	);
+	assert_eq!(compiled.body.matches(image.as_str()).count(), 2);
+	let stored: i64 = con
+		.query_row("SELECT COUNT(*) FROM stored_assets", [], |row| row.get(0))
+		.unwrap();
+	assert_eq!(stored, 1);
+}
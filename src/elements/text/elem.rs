@@ -2,6 +2,7 @@ use crate::compiler::compiler::Compiler;
 use crate::document::document::Document;
 use crate::document::element::ElemKind;
 use crate::document::element::Element;
+use crate::elements::paragraph::elem::LINE_SEPARATOR;
 use crate::parser::source::Token;
 
 #[derive(Debug)]
@@ -25,6 +26,8 @@ impl Element for Text {
 		_document: &dyn Document,
 		_cursor: usize,
 	) -> Result<String, String> {
-		Ok(Compiler::sanitize(compiler.target(), self.content.as_str()))
+		// Preserved line breaks
+		Ok(Compiler::sanitize(compiler.target(), self.content.as_str())
+			.replace(LINE_SEPARATOR, "<br>"))
 	}
 }
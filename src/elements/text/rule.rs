@@ -1,9 +1,9 @@
 use std::any::Any;
 
-use mlua::Function;
 use mlua::Lua;
 
 use crate::document::document::Document;
+use crate::lua::kernel::Binding;
 use crate::lua::kernel::CTX;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::ParserState;
@@ -41,10 +41,12 @@ impl Rule for TextRule {
 		panic!("Text cannot match");
 	}
 
-	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
 		let mut bindings = vec![];
-		bindings.push((
-			"push".to_string(),
+		bindings.push(Binding::new(
+			"push",
+			"(content: string)",
+			"Inserts text",
 			lua.create_function(|_, content: String| {
 				CTX.with_borrow(|ctx| {
 					ctx.as_ref().map(|ctx| {
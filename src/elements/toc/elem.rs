@@ -1,5 +1,6 @@
 use crate::compiler::compiler::Compiler;
 use crate::compiler::compiler::Target::HTML;
+use crate::compiler::compiler::Target::SLIDES;
 use crate::document::document::Document;
 use crate::document::element::ElemKind;
 use crate::document::element::Element;
@@ -50,6 +51,8 @@ impl Element for Toc {
 					} else {
 						sections.push((section, sections[last].1 + 1))
 					}
+				} else if section.depth == 1 {
+					sections.push((section, compiler.section_offset() + 1));
 				} else {
 					sections.push((section, 1));
 				}
@@ -61,7 +64,7 @@ impl Element for Toc {
 		}
 
 		match compiler.target() {
-			HTML => {
+			HTML | SLIDES => {
 				let match_depth = |current: usize, target: usize| -> String {
 					let mut result = String::new();
 					for _ in current..target {
@@ -86,15 +89,15 @@ impl Element for Toc {
 					result += match_depth(current_depth, section.depth).as_str();
 					if section.kind & section_kind::NO_NUMBER != 0 {
 						result += format!(
-							"<li style=\"list-style-type:none\"><a href=\"#{}\">{}</a></li>",
-							Compiler::refname(compiler.target(), section.title.as_str()),
+							"<li style=\"list-style-type:none\"><a href=\"{}\">{}</a></li>",
+							Compiler::anchor(compiler.target(), section.title.as_str()),
 							Compiler::sanitize(compiler.target(), section.title.as_str())
 						)
 						.as_str();
 					} else {
 						result += format!(
-							"<li value=\"{number}\"><a href=\"#{}\">{}</a></li>",
-							Compiler::refname(compiler.target(), section.title.as_str()),
+							"<li value=\"{number}\"><a href=\"{}\">{}</a></li>",
+							Compiler::anchor(compiler.target(), section.title.as_str()),
 							Compiler::sanitize(compiler.target(), section.title.as_str())
 						)
 						.as_str();
@@ -1,4 +1,3 @@
-use mlua::Function;
 use mlua::Lua;
 use regex::Captures;
 use regex::Regex;
@@ -7,6 +6,7 @@ use regex::RegexBuilder;
 use crate::document::document::Document;
 use crate::elements::toc::elem::Toc;
 use crate::lsp::semantic::Semantics;
+use crate::lua::kernel::Binding;
 use crate::lua::kernel::CTX;
 use crate::parser::parser::ParseMode;
 use crate::parser::parser::ParserState;
@@ -75,10 +75,12 @@ impl RegexRule for TocRule {
 		vec![]
 	}
 
-	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
 		let mut bindings = vec![];
-		bindings.push((
-			"push".to_string(),
+		bindings.push(Binding::new(
+			"push",
+			"(title: string?)",
+			"Inserts a table of contents",
 			lua.create_function(|_, title: Option<String>| {
 				CTX.with_borrow(|ctx| {
 					ctx.as_ref().map(|ctx| {
@@ -1,30 +1,38 @@
+use crate::lua::kernel::Binding;
 use crate::parser::reports::macros::*;
 use crate::parser::reports::*;
 use ariadne::Fmt;
 use lsp::definition;
+use lsp::hints::HintKind;
 use lsp::hints::Hints;
 use lsp::semantic::Semantics;
 use lua::kernel::CTX;
-use mlua::Function;
+use mlua::Error::BadArgument;
 use mlua::Lua;
+use mlua::Value;
 use parser::parser::ParseMode;
 use regex::Regex;
 use std::rc::Rc;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use crate::document::document::Document;
 use crate::document::variable::BaseVariable;
+use crate::document::variable::ComputedVariable;
 use crate::document::variable::PathVariable;
 use crate::document::variable::Variable;
+use crate::document::variable::VariableType;
 use crate::parser::parser::ParserState;
 use crate::parser::parser::ReportColors;
 use crate::parser::rule::RegexRule;
+use crate::parser::source::resolve_path;
 use crate::parser::source::Token;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum VariableKind {
 	Regular,
 	Path,
+	Computed,
 }
 
 impl FromStr for VariableKind {
@@ -34,6 +42,7 @@ impl FromStr for VariableKind {
 		match s {
 			"regular" | "" => Ok(VariableKind::Regular),
 			"path" | "'" => Ok(VariableKind::Path),
+			"computed" | "!" => Ok(VariableKind::Computed),
 			_ => Err(format!("Uknnown variable kind: `{s}`")),
 		}
 	}
@@ -45,6 +54,33 @@ pub struct VariableRule {
 	kinds: Vec<(String, String)>,
 }
 
+/// Creates a variable with an optional type
+///
+/// The value must have been validated against the type beforehand, see [`VariableType::validate`]
+fn create_variable(
+	colors: &ReportColors,
+	location: Token,
+	name: String,
+	value_token: Token,
+	value: String,
+	var_type: Option<VariableType>,
+) -> Result<Rc<dyn Variable>, String> {
+	match var_type {
+		Some(VariableType::Path) => {
+			match std::fs::canonicalize(resolve_path(&location.source(), value.as_str())) // TODO: not canonicalize
+			{
+				Ok(path) => Ok(Rc::new(PathVariable::new(location, name, value_token, path))),
+				Err(e) => Err(format!("Unable to canonicalize path `{}`: {}",
+						value.fg(colors.highlight),
+						e))
+			}
+		}
+		var_type => Ok(Rc::new(
+			BaseVariable::new(location, name, value_token, value).with_type(var_type),
+		)),
+	}
+}
+
 impl VariableRule {
 	pub fn make_variable(
 		&self,
@@ -54,33 +90,37 @@ impl VariableRule {
 		name: String,
 		value_token: Token,
 		value: String,
+		var_type: Option<VariableType>,
 	) -> Result<Rc<dyn Variable>, String> {
-		match self.kinds[kind].0.as_str() {
-			"" => Ok(Rc::new(BaseVariable::new(
-				location,
-				name,
-				value_token,
-				value,
-			))),
-			"'" => {
-				match std::fs::canonicalize(value.as_str()) // TODO: not canonicalize
-				{
-					Ok(path) => Ok(Rc::new(PathVariable::new(location, name, value_token, path))),
-					Err(e) => Err(format!("Unable to canonicalize path `{}`: {}",
-							value.fg(colors.highlight),
-							e))
-				}
+		let var_type = match self.kinds[kind].0.as_str() {
+			"" => var_type,
+			"'" => Some(VariableType::Path),
+			"!" => {
+				return Ok(Rc::new(ComputedVariable::new(
+					location,
+					name,
+					value_token,
+					value,
+				)))
 			}
 			_ => panic!("Unhandled variable kind"),
-		}
+		};
+		create_variable(colors, location, name, value_token, value, var_type)
 	}
 }
 
 impl Default for VariableRule {
 	fn default() -> Self {
 		Self {
-			re: [Regex::new(r"(?:^|\n)@(')?(.*?)=((?:\\\n|.)*)").unwrap()],
-			kinds: vec![("".into(), "Regular".into()), ("'".into(), "Path".into())],
+			re: [
+				Regex::new(r"(?:^|\n)@(export[^\S\r\n]+)?('|!)?(.*?)(?::(.*?))?=((?:\\\n|.)*)")
+					.unwrap(),
+			],
+			kinds: vec![
+				("".into(), "Regular".into()),
+				("'".into(), "Path".into()),
+				("!".into(), "Computed".into()),
+			],
 		}
 	}
 }
@@ -88,7 +128,9 @@ impl Default for VariableRule {
 // Trim and check variable name for validity
 pub fn validate_name<'a>(colors: &ReportColors, original_name: &'a str) -> Result<&'a str, String> {
 	let name = original_name.trim_start().trim_end();
-	if name.contains("%") {
+	if name.is_empty() {
+		return Err("Name cannot be empty".into());
+	} else if name.contains("%") {
 		return Err(format!("Name cannot contain '{}'", "%".fg(colors.info)));
 	}
 	Ok(name)
@@ -141,8 +183,35 @@ impl RegexRule for VariableRule {
 		matches: regex::Captures,
 	) -> Vec<Report> {
 		let mut reports = vec![];
+
+		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
+			let name = matches.get(3).unwrap().range();
+			if let Some(export) = matches.get(1).map(|m| m.range()) {
+				sems.add(export.start - 1..export.start, tokens.variable_operator);
+				sems.add(export.start..export.start + 6, tokens.variable_export);
+			}
+			if let Some(kind) = matches.get(2).map(|m| m.range()) {
+				if matches.get(1).is_none() {
+					sems.add(kind.start - 1..kind.start, tokens.variable_operator);
+				}
+				sems.add(kind, tokens.variable_kind);
+			} else if matches.get(1).is_none() {
+				sems.add(name.start - 1..name.start, tokens.variable_operator);
+			}
+			sems.add(name.clone(), tokens.variable_name);
+			if let Some(ty) = matches.get(4).map(|m| m.range()) {
+				sems.add(ty.start - 1..ty.start, tokens.variable_sep);
+				sems.add(ty.clone(), tokens.variable_type);
+				sems.add(ty.end..ty.end + 1, tokens.variable_sep);
+			} else {
+				sems.add(name.end..name.end + 1, tokens.variable_sep);
+			}
+			let value = matches.get(5).unwrap().range();
+			sems.add(value.clone(), tokens.variable_value);
+		}
+
 		// [Optional] variable kind
-		let var_kind = match matches.get(1) {
+		let var_kind = match matches.get(2) {
 			Some(kind) => {
 				// Find kind
 				let r = self
@@ -156,7 +225,7 @@ impl RegexRule for VariableRule {
 					report_err!(
 						&mut reports,
 						token.source(),
-						"Unknowm Variable Kind".into(),
+						"Unknown Variable Kind".into(),
 						span(
 							kind.range(),
 							format!(
@@ -187,7 +256,7 @@ impl RegexRule for VariableRule {
 			None => 0,
 		};
 
-		let var_name = match matches.get(2) {
+		let var_name = match matches.get(3) {
 			Some(name) => match validate_name(state.parser.colors(), name.as_str()) {
 				Ok(var_name) => var_name,
 				Err(msg) => {
@@ -210,7 +279,7 @@ impl RegexRule for VariableRule {
 			_ => panic!("Unknown variable name"),
 		};
 
-		let (val_token, var_value) = match matches.get(3) {
+		let (val_token, var_value) = match matches.get(5) {
 			Some(value) => match validate_value(value.as_str()) {
 				Ok(var_value) => (Token::new(value.range(), token.source()), var_value),
 				Err(msg) => {
@@ -233,6 +302,138 @@ impl RegexRule for VariableRule {
 			_ => panic!("Invalid variable value"),
 		};
 
+		// [Optional] variable type
+		let declared_type = match matches.get(4) {
+			Some(ty) => match VariableType::from_str(ty.as_str()) {
+				Ok(var_type) => Some(var_type),
+				Err(msg) => {
+					report_err!(
+						&mut reports,
+						token.source(),
+						"Invalid Variable Type".into(),
+						span(
+							ty.range(),
+							format!(
+								"Variable type `{}` is invalid. {msg}",
+								ty.as_str().trim().fg(state.parser.colors().highlight)
+							)
+						),
+						help(format!(
+							"Available types: {}, {}, {}, {}, {}",
+							"string".fg(state.parser.colors().info),
+							"int".fg(state.parser.colors().info),
+							"bool".fg(state.parser.colors().info),
+							"path".fg(state.parser.colors().info),
+							"enum(a, b, ...)".fg(state.parser.colors().info),
+						))
+					);
+
+					return reports;
+				}
+			},
+			None if self.kinds[var_kind].0 == "'" => Some(VariableType::Path),
+			None => None,
+		};
+		if self.kinds[var_kind].0 == "'" && declared_type != Some(VariableType::Path) {
+			report_err!(
+				&mut reports,
+				token.source(),
+				"Invalid Variable Type".into(),
+				span(
+					matches.get(4).unwrap().range(),
+					format!(
+						"Path variables cannot be declared with type `{}`",
+						declared_type
+							.as_ref()
+							.unwrap()
+							.fg(state.parser.colors().highlight)
+					)
+				),
+			);
+
+			return reports;
+		}
+
+		// Type from a previous declaration
+		let previous = document.get_variable(var_name);
+		let previous_type = previous.as_ref().and_then(|var| var.var_type());
+		if let (Some(declared_type), Some(previous_type)) = (&declared_type, &previous_type) {
+			if declared_type != previous_type {
+				let previous = previous.as_ref().unwrap();
+				report_err!(
+					&mut reports,
+					token.source(),
+					"Conflicting Variable Type".into(),
+					span(
+						token.range.clone(),
+						format!(
+							"Variable `{}` is declared with type `{}`, but was previously declared with type `{}`",
+							var_name.fg(state.parser.colors().highlight),
+							declared_type.fg(state.parser.colors().info),
+							previous_type.fg(state.parser.colors().info)
+						)
+					),
+					span(
+						previous.location().source(),
+						previous.location().range.clone(),
+						"Previously declared here".into()
+					),
+				);
+
+				return reports;
+			}
+		}
+		let var_type = declared_type.or(previous_type);
+		if self.kinds[var_kind].0 == "!" && var_type.is_some() {
+			report_err!(
+				&mut reports,
+				token.source(),
+				"Invalid Variable Type".into(),
+				span(
+					token.range.clone(),
+					format!(
+						"Computed variable `{}` cannot have type `{}`",
+						var_name.fg(state.parser.colors().highlight),
+						var_type.as_ref().unwrap().fg(state.parser.colors().info)
+					)
+				),
+				note("The value of computed variables is only known once they are used".into())
+			);
+
+			return reports;
+		}
+
+		// Validate value
+		if let Some(Err(msg)) = var_type.as_ref().map(|ty| ty.validate(var_value.as_str())) {
+			let value = matches.get(5).unwrap();
+			let message = format!(
+				"Value is invalid for type `{}`. {msg}",
+				var_type.as_ref().unwrap().fg(state.parser.colors().info)
+			);
+			match previous.as_ref().filter(|_| matches.get(4).is_none()) {
+				// Type inherited from the previous declaration
+				Some(previous) => report_err!(
+					&mut reports,
+					token.source(),
+					"Invalid Variable Value".into(),
+					span(value.range(), message),
+					span(
+						previous.location().source(),
+						previous.location().range.clone(),
+						"Type declared here".into()
+					),
+				),
+				None => report_err!(
+					&mut reports,
+					token.source(),
+					"Invalid Variable Value".into(),
+					span(value.range(), message),
+				),
+			}
+
+			return reports;
+		}
+
 		match self.make_variable(
 			state.parser.colors(),
 			token.clone(),
@@ -240,8 +441,42 @@ impl RegexRule for VariableRule {
 			var_name.to_string(),
 			val_token,
 			var_value,
+			var_type,
 		) {
-			Ok(variable) => document.add_variable(variable),
+			// Variables defined from the command line take precedence
+			Ok(_)
+				if document
+					.get_variable(var_name)
+					.is_some_and(|var| var.is_external()) => {}
+			Ok(variable) => {
+				// Shadowing
+				if let Some(shadowed) = document.get_shadowed_variable(var_name) {
+					let name = matches.get(3).unwrap();
+					report_warn!(
+						&mut reports,
+						token.source(),
+						"Shadowed Variable".into(),
+						span(
+							name.range(),
+							format!(
+								"Variable `{}` shadows a variable from an enclosing scope",
+								var_name.fg(state.parser.colors().highlight)
+							)
+						),
+						span(
+							shadowed.location().source(),
+							shadowed.location().range.clone(),
+							"Previously defined here".into()
+						),
+						note("The new definition is local to this scope".into())
+					);
+				}
+
+				match matches.get(1) {
+					Some(_) => document.export_variable(variable),
+					None => document.add_variable(variable),
+				}
+			}
 			Err(msg) => {
 				let m = matches.get(0).unwrap();
 				report_err!(
@@ -262,57 +497,136 @@ impl RegexRule for VariableRule {
 			}
 		}
 
-		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
-			let name = matches.get(2).unwrap().range();
-			if let Some(kind) = matches.get(1).map(|m| m.range()) {
-				sems.add(kind.start - 1..kind.start, tokens.variable_operator);
-				sems.add(kind, tokens.variable_kind);
-			} else {
-				sems.add(name.start - 1..name.start, tokens.variable_operator);
-			}
-			sems.add(name.clone(), tokens.variable_name);
-			sems.add(name.end..name.end + 1, tokens.variable_sep);
-			let value = matches.get(3).unwrap().range();
-			sems.add(value.clone(), tokens.variable_value);
-		}
-
 		reports
 	}
 
-	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
 		let mut bindings = vec![];
-		bindings.push((
-			"insert".to_string(),
-			lua.create_function(|_, (name, value): (String, String)| {
+		bindings.push(Binding::new(
+			"insert",
+			"(name: string, value: string, export: boolean?)",
+			"Defines variable `name`, `export` makes it visible to the importing documents",
+			lua.create_function(|_, (name, value, export): (String, String, Option<bool>)| {
+				let mut result = Ok(());
 				CTX.with_borrow(|ctx| {
 					ctx.as_ref().map(|ctx| {
-						let var = Rc::new(BaseVariable::new(
+						// Variables defined from the command line take precedence
+						let previous = ctx.document.get_variable(name.as_str());
+						if previous.as_ref().is_some_and(|var| var.is_external()) {
+							return;
+						}
+
+						// Type from a previous declaration
+						let var_type = previous.and_then(|var| var.var_type());
+						if let Some(Err(msg)) =
+							var_type.as_ref().map(|ty| ty.validate(value.as_str()))
+						{
+							result = Err(BadArgument {
+								to: Some("insert".to_string()),
+								pos: 2,
+								name: Some("value".to_string()),
+								cause: Arc::new(mlua::Error::external(format!(
+									"Value is invalid for type `{}`. {msg}",
+									var_type.unwrap()
+								))),
+							});
+							return;
+						}
+
+						let var = match create_variable(
+							ctx.state.parser.colors(),
 							ctx.location.clone(),
 							name,
 							ctx.location.clone(),
 							value,
-						));
-						ctx.document.add_variable(var);
+							var_type,
+						) {
+							Ok(var) => var,
+							Err(msg) => {
+								result = Err(mlua::Error::external(msg));
+								return;
+							}
+						};
+						if export.unwrap_or(false) {
+							ctx.document.export_variable(var);
+						} else {
+							ctx.document.add_variable(var);
+						}
 					})
 				});
 
-				Ok(())
+				result
 			})
 			.unwrap(),
 		));
-		bindings.push((
-			"get".to_string(),
+		bindings.push(Binding::new(
+			"get",
+			"(name: string): any",
+			"Gets the value of variable `name`, converted to the variable's type",
+			lua.create_function(|lua, name: String| {
+				let mut variable = None;
+				CTX.with_borrow(|ctx| {
+					ctx.as_ref().map(|ctx| {
+						variable = ctx.document.get_variable(name.as_str());
+					})
+				});
+
+				// Typed variables are converted to the corresponding lua type
+				let Some(variable) = variable else {
+					return Ok(Value::Nil);
+				};
+				variable.evaluate().map_err(mlua::Error::external)?;
+				let value = variable.to_string();
+				Ok(match variable.var_type() {
+					Some(VariableType::Int) => {
+						value.parse::<i64>().map_or(Value::Nil, Value::Integer)
+					}
+					Some(VariableType::Bool) => Value::Boolean(value == "true"),
+					_ => Value::String(lua.create_string(value)?),
+				})
+			})
+			.unwrap(),
+		));
+		bindings.push(Binding::new(
+			"scope",
+			"(): table",
+			"Gets the values of the variables of the current scope, by name",
+			lua.create_function(|lua, ()| {
+				let mut variables = vec![];
+				CTX.with_borrow(|ctx| {
+					ctx.as_ref().map(|ctx| {
+						variables = ctx
+							.document
+							.scope()
+							.borrow()
+							.variables
+							.iter()
+							.map(|(name, var)| (name.clone(), var.to_string()))
+							.collect();
+					})
+				});
+
+				let table = lua.create_table()?;
+				for (name, value) in variables {
+					table.set(name, value)?;
+				}
+				Ok(table)
+			})
+			.unwrap(),
+		));
+		bindings.push(Binding::new(
+			"exported",
+			"(name: string): boolean",
+			"Checks whether variable `name` is exported",
 			lua.create_function(|_, name: String| {
-				let mut value: Option<String> = None;
+				let mut exported = false;
 				CTX.with_borrow(|ctx| {
 					ctx.as_ref().map(|ctx| {
-						if let Some(var) = ctx.document.get_variable(name.as_str()) {
-							value = Some(var.to_string());
-						}
+						exported = ctx.document.scope().borrow().is_exported(name.as_str());
 					})
 				});
 
-				Ok(value)
+				Ok(exported)
 			})
 			.unwrap(),
 		));
@@ -353,6 +667,13 @@ impl RegexRule for VariableSubstitutionRule {
 	) -> Vec<Report> {
 		let mut reports = vec![];
 
+		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
+			let name = matches.get(1).unwrap().range();
+			sems.add(name.start - 1..name.start, tokens.variable_sub_sep);
+			sems.add(name.clone(), tokens.variable_sub_name);
+			sems.add(name.end..name.end + 1, tokens.variable_sub_sep);
+		}
+
 		let variable = match matches.get(1) {
 			Some(name) => {
 				// Empty name
@@ -434,19 +755,39 @@ impl RegexRule for VariableSubstitutionRule {
 			_ => panic!("Unknown error"),
 		};
 
-		variable.parse(state, token.clone(), document);
-
-		if let Some((sems, tokens)) = Semantics::from_source(token.source(), &state.shared.lsp) {
-			let name = matches.get(1).unwrap().range();
-			sems.add(name.start - 1..name.start, tokens.variable_sub_sep);
-			sems.add(name.clone(), tokens.variable_sub_name);
-			sems.add(name.end..name.end + 1, tokens.variable_sub_sep);
+		// Evaluate computed variables
+		if let Err(msg) = variable.evaluate() {
+			report_err!(
+				&mut reports,
+				token.source(),
+				"Variable Evaluation Failed".into(),
+				span(
+					matches.get(1).unwrap().range(),
+					format!(
+						"Failed to evaluate variable `{}`: {msg}",
+						variable.name().fg(state.parser.colors().highlight)
+					)
+				),
+				span(
+					variable.location().source(),
+					variable.value_token().range.clone(),
+					"Evaluated from here".into()
+				)
+			);
+
+			return reports;
 		}
 
+		variable.parse(state, token.clone(), document);
+
 		if let Some(hints) = Hints::from_source(token.source(), &state.shared.lsp) {
 			let label = variable.to_string();
 			if !label.is_empty() {
-				hints.add(matches.get(0).unwrap().end(), label);
+				hints.add_kind(
+					matches.get(0).unwrap().end(),
+					label,
+					HintKind::VariableValue,
+				);
 			}
 		}
 
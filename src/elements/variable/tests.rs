@@ -0,0 +1,308 @@
+use std::rc::Rc;
+
+use crate::document::langdocument::LangDocument;
+use crate::document::variable::VariableType;
+use crate::elements::paragraph::elem::Paragraph;
+use crate::elements::text::elem::Text;
+use crate::parser::langparser::LangParser;
+use crate::parser::parser::ParseMode;
+use crate::parser::parser::Parser;
+use crate::parser::parser::ParserState;
+use crate::parser::source::SourceFile;
+use crate::validate_document;
+use crate::validate_semantics;
+
+#[test]
+fn export() {
+	let parser = LangParser::default();
+	let lib = Rc::new(SourceFile::with_content(
+		"lib".to_string(),
+		r#"
+@export a = A
+@b = B
+@export 'c = .
+"#
+		.to_string(),
+		None,
+	));
+	let (lib_doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		lib,
+		None,
+		ParseMode::default(),
+	);
+	assert!(lib_doc.scope().borrow().is_exported("a"));
+	assert!(!lib_doc.scope().borrow().is_exported("b"));
+	assert!(lib_doc.scope().borrow().is_exported("c"));
+
+	let main = Rc::new(SourceFile::with_content(
+		"main".to_string(),
+		"@b = Main".to_string(),
+		None,
+	));
+	let (main_doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		main,
+		None,
+		ParseMode::default(),
+	);
+	main_doc.merge(lib_doc.content(), lib_doc.scope(), Some(&"".to_string()));
+
+	assert_eq!(main_doc.get_variable("a").unwrap().to_string(), "A");
+	assert_eq!(main_doc.get_variable("b").unwrap().to_string(), "Main");
+	assert!(main_doc.get_variable("c").is_some());
+	// Imported variables are not re-exported
+	assert!(!main_doc.scope().borrow().is_exported("a"));
+}
+
+#[test]
+fn export_as() {
+	let parser = LangParser::default();
+	let lib = Rc::new(SourceFile::with_content(
+		"lib".to_string(),
+		r#"
+@export a = A
+@b = B
+"#
+		.to_string(),
+		None,
+	));
+	let (lib_doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		lib,
+		None,
+		ParseMode::default(),
+	);
+
+	let main = Rc::new(SourceFile::with_content(
+		"main".to_string(),
+		"".to_string(),
+		None,
+	));
+	let (main_doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		main,
+		None,
+		ParseMode::default(),
+	);
+	main_doc.merge(lib_doc.content(), lib_doc.scope(), Some(&"lib".to_string()));
+
+	assert_eq!(main_doc.get_variable("lib.a").unwrap().to_string(), "A");
+	assert!(main_doc.get_variable("lib.b").is_none());
+	assert!(main_doc.get_variable("a").is_none());
+}
+
+#[test]
+fn shadowing() {
+	let parser = LangParser::default();
+	let parent = Rc::new(SourceFile::with_content(
+		"parent".to_string(),
+		"@a = Parent".to_string(),
+		None,
+	));
+	let parent_doc = LangDocument::new(parent.clone(), None);
+	parser.parse_into(
+		ParserState::new(&parser, None),
+		parent,
+		&parent_doc,
+		ParseMode::default(),
+	);
+
+	let child = Rc::new(SourceFile::with_content(
+		"child".to_string(),
+		"@b = Child".to_string(),
+		None,
+	));
+	let (child_doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		child,
+		Some(&parent_doc),
+		ParseMode::default(),
+	);
+
+	assert_eq!(
+		child_doc.get_shadowed_variable("a").unwrap().to_string(),
+		"Parent"
+	);
+	assert!(child_doc.get_shadowed_variable("b").is_none());
+	assert!(child_doc.get_shadowed_variable("c").is_none());
+}
+
+#[test]
+fn lua() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@export a = A
+@b = B
+@<
+nml.variable.insert("c", "C", true)
+>@
+%<" nml.variable.scope()["b"] .. tostring(nml.variable.exported("a")) .. tostring(nml.variable.exported("b"))>%
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Paragraph {
+			Text { content == "Btruefalse" };
+		};
+	);
+	assert!(doc.scope().borrow().is_exported("c"));
+}
+
+#[test]
+fn typed() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@count: int = 3
+@draft : bool = false
+@mode: enum(light, dark) = dark
+@invalid: int = abc
+@count = NaN
+@mode = blue
+@draft = true
+%<" nml.variable.get("count") + 1>%
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	let count = doc.get_variable("count").unwrap();
+	assert_eq!(count.var_type(), Some(VariableType::Int));
+	assert_eq!(count.to_string(), "3");
+	let draft = doc.get_variable("draft").unwrap();
+	assert_eq!(draft.var_type(), Some(VariableType::Bool));
+	assert_eq!(draft.to_string(), "true");
+	let mode = doc.get_variable("mode").unwrap();
+	assert_eq!(
+		mode.var_type(),
+		Some(VariableType::Enum(vec!["light".into(), "dark".into()]))
+	);
+	assert_eq!(mode.to_string(), "dark");
+	assert!(doc.get_variable("invalid").is_none());
+
+	validate_document!(doc.content().borrow(), 0,
+		Paragraph {
+			Text { content == "4" };
+		};
+	);
+}
+
+#[test]
+fn types() {
+	assert_eq!("int".parse::<VariableType>(), Ok(VariableType::Int));
+	assert_eq!(
+		" enum(a,b , c) ".parse::<VariableType>(),
+		Ok(VariableType::Enum(vec!["a".into(), "b".into(), "c".into()]))
+	);
+	assert!("enum()".parse::<VariableType>().is_err());
+	assert!("float".parse::<VariableType>().is_err());
+
+	assert!(VariableType::Int.validate("-12").is_ok());
+	assert!(VariableType::Int.validate("1.5").is_err());
+	assert!(VariableType::Bool.validate("yes").is_err());
+	assert!(VariableType::Enum(vec!["a".into()]).validate("b").is_err());
+}
+
+#[test]
+fn computed() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@!year = 2000 + 24
+@!fail = error("Failure")
+@!sandboxed = os.execute("true")
+@!file = dofile("/etc/hostname")
+@!code = load("return 1")()
+@!endless = (function() while true do end end)()
+@!typed: int = 1
+%year%
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		None,
+		ParseMode::default(),
+	);
+
+	validate_document!(doc.content().borrow(), 0,
+		Paragraph {
+			Text { content == "2024" };
+		};
+	);
+	assert!(doc.get_variable("year").unwrap().evaluate().is_ok());
+	assert!(doc.get_variable("fail").unwrap().evaluate().is_err());
+	assert!(doc.get_variable("sandboxed").unwrap().evaluate().is_err());
+	assert!(doc.get_variable("file").unwrap().evaluate().is_err());
+	assert!(doc.get_variable("code").unwrap().evaluate().is_err());
+	assert!(doc
+		.get_variable("endless")
+		.unwrap()
+		.evaluate()
+		.is_err_and(|err| err.contains("instructions")));
+	assert!(doc.get_variable("typed").is_none());
+}
+
+#[test]
+fn semantic() {
+	let source = Rc::new(SourceFile::with_content(
+		"".to_string(),
+		r#"
+@a = 1
+@export !b:int = 2
+%a% %c%
+"#
+		.to_string(),
+		None,
+	));
+	let parser = LangParser::default();
+	let (_, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None),
+		source.clone(),
+		None,
+		ParseMode::default(),
+	);
+	validate_semantics!(state, source.clone(), 0,
+	variable_operator { delta_line == 1, delta_start == 0, length == 1 };
+	variable_name { delta_line == 0, delta_start == 1, length == 2 };
+	variable_sep { delta_line == 0, delta_start == 2, length == 1 };
+	variable_value { delta_line == 0, delta_start == 1, length == 2 };
+	// Invalid definitions are still highlighted
+	variable_operator { delta_line == 1, delta_start == 0, length == 1 };
+	variable_export { delta_line == 0, delta_start == 1, length == 6 };
+	variable_kind { delta_line == 0, delta_start == 7, length == 1 };
+	variable_name { delta_line == 0, delta_start == 1, length == 1 };
+	variable_sep { delta_line == 0, delta_start == 1, length == 1 };
+	variable_type { delta_line == 0, delta_start == 1, length == 4 };
+	variable_sep { delta_line == 0, delta_start == 4, length == 1 };
+	variable_value { delta_line == 0, delta_start == 1, length == 2 };
+	variable_sub_sep { delta_line == 1, delta_start == 0, length == 1 };
+	variable_sub_name { delta_line == 0, delta_start == 1, length == 1 };
+	variable_sub_sep { delta_line == 0, delta_start == 1, length == 1 };
+	// Unknown variables are still highlighted
+	variable_sub_sep { delta_line == 0, delta_start == 2, length == 1 };
+	variable_sub_name { delta_line == 0, delta_start == 1, length == 1 };
+	variable_sub_sep { delta_line == 0, delta_start == 1, length == 1 };
+	);
+}
@@ -0,0 +1,18 @@
+pub mod cache;
+pub mod compiler;
+pub mod document;
+pub mod elements;
+pub mod lsp;
+pub mod lua;
+pub mod parser;
+pub mod project;
+#[cfg(test)]
+pub mod tempdir;
+
+pub use compiler::api::compile_str;
+pub use compiler::api::parse_ast;
+pub use compiler::api::rebuild;
+pub use compiler::api::Artifact;
+pub use compiler::api::AstNode;
+pub use compiler::api::Output;
+pub use compiler::api::Settings;
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use dashmap::DashMap;
+use tower_lsp::lsp_types::ColorInformation;
+use tower_lsp::lsp_types::Diagnostic;
+use tower_lsp::lsp_types::InlayHint;
+use tower_lsp::lsp_types::Location;
+use tower_lsp::lsp_types::Range;
+use tower_lsp::lsp_types::SemanticToken;
+
+use crate::compiler::process::defines_document;
+use crate::parser::budget::ParseBudget;
+use crate::parser::cancel::Cancellation;
+use crate::parser::langparser::LangParser;
+use crate::parser::parser::ParseMode;
+use crate::parser::parser::Parser;
+use crate::parser::parser::ParserState;
+use crate::parser::reports::Report;
+use crate::parser::source::Source;
+use crate::parser::source::SourceFile;
+
+use super::code::CodeRangeInfo;
+use super::conceal::ConcealInfo;
+use super::elemstyle::ElemStyleInfo;
+use super::outline::OutlineItem;
+use super::references::IndexedDocument;
+use super::styles::StyleInfo;
+
+/// Document to parse with [`analyze`]
+///
+/// Every field can be sent to another thread, so that the parse doesn't block the language server
+/// and can be cancelled while it runs.
+#[derive(Debug, Clone)]
+pub struct AnalysisInput {
+	/// Uri of the document
+	pub uri: String,
+	/// Path of the document, if it is a file
+	pub path: Option<PathBuf>,
+	/// Content of the document
+	pub text: String,
+	/// Variables defined for the document, see [`super::workspace::Workspace::defines`]
+	pub defines: Vec<(String, String)>,
+	pub budget: ParseBudget,
+	pub cancellation: Cancellation,
+	/// References of the other documents, see [`super::references::unresolved`]
+	pub documents: Vec<IndexedDocument>,
+}
+
+/// Language server data of a document parsed by [`analyze`]
+///
+/// Data of the parsed sources (the document and the files it imports) is given by path.
+#[derive(Debug)]
+pub struct Analysis {
+	/// Names of the parsed sources, their previous diagnostics are replaced by [`Self::diagnostics`]
+	pub sources: Vec<String>,
+	pub diagnostics: Vec<(String, Vec<Diagnostic>)>,
+	/// References exported by the document
+	pub indexed: IndexedDocument,
+	/// Files imported by the document
+	pub dependencies: HashSet<PathBuf>,
+	pub outline: Vec<OutlineItem>,
+	pub semantic_tokens: Vec<(String, Vec<SemanticToken>)>,
+	pub hints: Vec<(String, Vec<InlayHint>)>,
+	pub definitions: Vec<(String, Vec<(Location, Range)>)>,
+	pub conceals: Vec<(String, Vec<ConcealInfo>)>,
+	pub styles: Vec<(String, Vec<StyleInfo>)>,
+	pub coderanges: Vec<(String, Vec<CodeRangeInfo>)>,
+	pub hovers: Vec<(String, Vec<(Range, String)>)>,
+	pub colors: Vec<(String, Vec<ColorInformation>)>,
+	pub elemstyles: Vec<(String, Vec<ElemStyleInfo>)>,
+	pub linked: Vec<(String, Vec<Vec<Range>>)>,
+}
+
+/// Takes the data of the parsed files, by path
+fn by_path<T, V>(
+	data: &HashMap<Rc<dyn Source>, T>,
+	take: impl Fn(&T) -> Vec<V>,
+) -> Vec<(String, Vec<V>)> {
+	data.iter()
+		.filter_map(|(source, data)| {
+			let source = source.clone().downcast_rc::<SourceFile>().ok()?;
+			Some((source.path().to_owned(), take(data)))
+		})
+		.collect()
+}
+
+/// Parses a document opened in the language server
+///
+/// # Returns
+///
+/// The data of the document, or `None` when the parse was cancelled.
+pub fn analyze(input: AnalysisInput) -> Option<Analysis> {
+	let source = Rc::new(SourceFile::with_content(
+		input.uri.clone(),
+		input.text,
+		None,
+	));
+
+	// Parse
+	let defines = defines_document(&input.defines);
+	let diagnostics = DashMap::new();
+	let parser = LangParser::new(
+		false,
+		Box::new(|_colors, reports| Report::reports_to_diagnostics(&diagnostics, reports)),
+	);
+	let (doc, state) = parser.parse(
+		ParserState::new_with_semantics(&parser, None)
+			.with_cancellation(input.cancellation)
+			.with_budget(input.budget),
+		source,
+		Some(&defines),
+		ParseMode::default(),
+	);
+	if state.is_cancelled() {
+		return None;
+	}
+
+	// References
+	let indexed = IndexedDocument::from_document(
+		input.path.as_ref().map_or(input.uri.clone(), |path| {
+			path.to_string_lossy().to_string()
+		}),
+		&*doc,
+	);
+	let mut documents = input.documents;
+	documents.retain(|document| document.input != indexed.input);
+	documents.push(indexed.clone());
+	let unresolved = super::references::unresolved(&documents, &*doc);
+	if !unresolved.is_empty() {
+		diagnostics
+			.entry(input.uri.clone())
+			.or_default()
+			.extend(unresolved);
+	}
+
+	let dependencies = doc
+		.scope()
+		.borrow()
+		.imports
+		.iter()
+		.map(PathBuf::from)
+		.collect();
+
+	// Parsed with semantics, data of the sources is always available
+	let lsp = state.shared.lsp.as_ref().unwrap().borrow();
+	let analysis = Analysis {
+		sources: lsp
+			.semantic_data
+			.keys()
+			.map(|source| source.name().to_owned())
+			.collect(),
+		diagnostics: diagnostics
+			.iter()
+			.map(|entry| (entry.key().clone(), entry.value().clone()))
+			.collect(),
+		indexed,
+		dependencies,
+		outline: super::outline::outline(&*doc),
+		semantic_tokens: by_path(&lsp.semantic_data, |data| data.tokens.take()),
+		hints: by_path(&lsp.inlay_hints, |data| data.hints.take()),
+		definitions: by_path(&lsp.definitions, |data| data.definitions.take()),
+		conceals: by_path(&lsp.conceals, |data| data.conceals.take()),
+		styles: by_path(&lsp.styles, |data| data.styles.take()),
+		coderanges: by_path(&lsp.coderanges, |data| data.coderanges.take()),
+		hovers: by_path(&lsp.hovers, |data| data.hovers.take()),
+		colors: by_path(&lsp.colors, |data| data.colors.take()),
+		elemstyles: by_path(&lsp.elemstyles, |data| data.assignments.take()),
+		linked: by_path(&lsp.linked, |data| data.linked.take()),
+	};
+	Some(analysis)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::mpsc;
+	use std::time::Duration;
+
+	use super::*;
+
+	fn input(text: &str) -> AnalysisInput {
+		AnalysisInput {
+			uri: "file:///doc.nml".into(),
+			path: None,
+			text: text.into(),
+			defines: vec![],
+			budget: ParseBudget::default(),
+			cancellation: Cancellation::new(),
+			documents: vec![],
+		}
+	}
+
+	#[test]
+	fn analysis() {
+		let analysis = analyze(input("# Title\n@import \"missing.nml\"\n")).unwrap();
+		assert_eq!(analysis.outline[0].name, "Title");
+		assert_eq!(analysis.sources, vec!["file:///doc.nml".to_string()]);
+		assert!(analysis
+			.diagnostics
+			.iter()
+			.any(|(source, diagnostics)| source == "file:///doc.nml" && !diagnostics.is_empty()));
+		assert!(!analysis.semantic_tokens.is_empty());
+	}
+
+	#[test]
+	fn cancel_in_flight() {
+		// Long enough that the parse is still running when cancelled
+		let input = input("*a* _b_ `c`\n".repeat(200_000).as_str());
+		let cancellation = input.cancellation.clone();
+		let (started, start) = mpsc::channel();
+		let parse = std::thread::spawn(move || {
+			started.send(()).unwrap();
+			analyze(input)
+		});
+
+		start.recv().unwrap();
+		std::thread::sleep(Duration::from_millis(50));
+		assert!(!parse.is_finished());
+		cancellation.cancel();
+		assert!(parse.join().unwrap().is_none());
+	}
+}
@@ -0,0 +1,107 @@
+use std::cell::Ref;
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+
+use tower_lsp::lsp_types::Color;
+use tower_lsp::lsp_types::ColorInformation;
+use tower_lsp::lsp_types::Position;
+
+use crate::parser::source::LineCursor;
+use crate::parser::source::OffsetEncoding;
+use crate::parser::source::Source;
+use crate::parser::source::SourceFile;
+use crate::parser::source::SourcePosition;
+use crate::parser::source::VirtualSource;
+
+use super::data::LSPData;
+
+/// Per file color literals
+#[derive(Debug)]
+pub struct ColorData {
+	/// The source
+	source: Rc<dyn Source>,
+
+	/// The colors
+	pub colors: RefCell<Vec<ColorInformation>>,
+}
+
+impl ColorData {
+	pub fn new(source: Rc<dyn Source>) -> Self {
+		Self {
+			source,
+			colors: RefCell::new(vec![]),
+		}
+	}
+}
+
+/// Temporary data returned by [`Self::from_source_impl`]
+#[derive(Debug)]
+pub struct Colors<'a> {
+	pub(self) colors: Ref<'a, ColorData>,
+	// The source used when resolving the parent source
+	pub(self) original_source: Rc<dyn Source>,
+}
+
+impl<'a> Colors<'a> {
+	fn from_source_impl(
+		source: Rc<dyn Source>,
+		lsp: &'a Option<RefCell<LSPData>>,
+		original_source: Rc<dyn Source>,
+	) -> Option<Self> {
+		if (source.name().starts_with(":LUA:") || source.name().starts_with(":VAR:"))
+			&& source.downcast_ref::<VirtualSource>().is_some()
+		{
+			return None;
+		}
+
+		if let Some(location) = source
+			.clone()
+			.downcast_rc::<VirtualSource>()
+			.ok()
+			.as_ref()
+			.map(|parent| parent.location())
+			.unwrap_or(None)
+		{
+			return Self::from_source_impl(location.source(), lsp, original_source);
+		} else if let Ok(source) = source.clone().downcast_rc::<SourceFile>() {
+			return Ref::filter_map(lsp.as_ref().unwrap().borrow(), |lsp: &LSPData| {
+				lsp.colors.get(&(source.clone() as Rc<dyn Source>))
+			})
+			.ok()
+			.map(|colors| Self {
+				colors,
+				original_source,
+			});
+		}
+		None
+	}
+
+	pub fn from_source(source: Rc<dyn Source>, lsp: &'a Option<RefCell<LSPData>>) -> Option<Self> {
+		if lsp.is_none() {
+			return None;
+		}
+		Self::from_source_impl(source.clone(), lsp, source)
+	}
+
+	/// Adds a color literal spanning `range`
+	pub fn add(&self, range: Range<usize>, color: Color) {
+		let range = self.original_source.original_range(range).1;
+		let mut cursor = LineCursor::new(self.colors.source.clone(), OffsetEncoding::Utf16);
+		cursor.move_to(range.start);
+		let start = Position {
+			line: cursor.line as u32,
+			character: cursor.line_pos as u32,
+		};
+		cursor.move_to(range.end);
+		let end = Position {
+			line: cursor.line as u32,
+			character: cursor.line_pos as u32,
+		};
+
+		self.colors.colors.borrow_mut().push(ColorInformation {
+			range: tower_lsp::lsp_types::Range { start, end },
+			color,
+		})
+	}
+}
@@ -1,5 +1,6 @@
 use std::cell::Ref;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::ops::Range;
 use std::rc::Rc;
 
@@ -27,6 +28,8 @@ pub struct ConcealParams {
 #[serde(rename_all = "camelCase")]
 pub struct ConcealInfo {
 	pub range: tower_lsp::lsp_types::Range,
+	/// Kind of the concealed text, e.g `bullet` or `block_name`, see [`ConcealOverride`]
+	pub kind: String,
 	pub conceal_text: ConcealTarget,
 }
 
@@ -44,6 +47,38 @@ pub enum ConcealTarget {
 	},
 }
 
+/// Per kind override of the conceals, set in the `conceal` settings of the language server
+///
+/// ```json
+/// { "conceal": { "block": false, "bullet": "•" } }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ConcealOverride {
+	/// Enables or disables the conceals of this kind
+	Enabled(bool),
+	/// Replaces the conceals of this kind with a text
+	Text(String),
+}
+
+/// Applies the per kind `overrides` to `conceals`
+pub fn apply_overrides(
+	conceals: &[ConcealInfo],
+	overrides: &HashMap<String, ConcealOverride>,
+) -> Vec<ConcealInfo> {
+	conceals
+		.iter()
+		.filter_map(|conceal| match overrides.get(&conceal.kind) {
+			None | Some(ConcealOverride::Enabled(true)) => Some(conceal.clone()),
+			Some(ConcealOverride::Enabled(false)) => None,
+			Some(ConcealOverride::Text(text)) => Some(ConcealInfo {
+				conceal_text: ConcealTarget::Text(text.clone()),
+				..conceal.clone()
+			}),
+		})
+		.collect()
+}
+
 /// Per file conceals
 #[derive(Debug)]
 pub struct ConcealsData {
@@ -111,7 +146,9 @@ impl<'a> Conceals<'a> {
 		Self::from_source_impl(source.clone(), lsp, source)
 	}
 
-	pub fn add(&self, range: Range<usize>, text: ConcealTarget) {
+	/// Conceals `range` with `text`, `kind` is used to configure the conceals, see
+	/// [`ConcealOverride`]
+	pub fn add(&self, range: Range<usize>, kind: &str, text: ConcealTarget) {
 		let range = self.original_source.original_range(range.clone()).1;
 		let mut cursor = LineCursor::new(self.source.clone(), OffsetEncoding::Utf8);
 
@@ -134,7 +171,44 @@ impl<'a> Conceals<'a> {
 					character: end_char as u32,
 				},
 			},
+			kind: kind.to_string(),
 			conceal_text: text,
 		})
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn overrides() {
+		let conceal = |kind: &str| ConcealInfo {
+			range: tower_lsp::lsp_types::Range::default(),
+			kind: kind.into(),
+			conceal_text: ConcealTarget::Text("".into()),
+		};
+		let overrides = serde_json::from_value::<HashMap<String, ConcealOverride>>(
+			serde_json::json!({ "block": false, "bullet": "•", "link": true }),
+		)
+		.unwrap();
+
+		let conceals = apply_overrides(
+			&[
+				conceal("block"),
+				conceal("bullet"),
+				conceal("link"),
+				conceal("style"),
+			],
+			&overrides,
+		);
+		assert_eq!(
+			conceals
+				.iter()
+				.map(|conceal| conceal.kind.as_str())
+				.collect::<Vec<_>>(),
+			vec!["bullet", "link", "style"]
+		);
+		assert!(matches!(&conceals[0].conceal_text, ConcealTarget::Text(text) if text == "•"));
+	}
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tower_lsp::lsp_types::Diagnostic;
+use tower_lsp::lsp_types::DiagnosticSeverity;
+use tower_lsp::lsp_types::NumberOrString;
+
+use crate::parser::budget::ParseBudget;
+use crate::parser::parser::ReportColors;
+use crate::parser::reports::Report;
+use crate::parser::source::SourceFile;
+use crate::project::settings::ProjectSettings;
+use crate::project::settings::PROJECT_FILE;
+
+use super::conceal::ConcealOverride;
+use super::hints::HintsConfig;
+
+/// Severity of diagnostics, see [`ServerConfig::severity`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+	Error,
+	Warning,
+	Information,
+	Hint,
+	/// The diagnostics are not displayed
+	Off,
+}
+
+/// Configuration of the language server
+///
+/// The configuration is read from the `initializationOptions` of the client, and updated by
+/// `workspace/didChangeConfiguration` notifications.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ServerConfig {
+	/// Severity of the diagnostics, by code (e.g `NML0005`, or `a11y` for lints) or by default
+	/// severity (`error`, `warning` or `information`)
+	pub severity: HashMap<String, Severity>,
+	/// Whether to send the conceals of `textDocument/conceal`
+	pub conceals: bool,
+	/// Overrides of the conceals, by kind (e.g `bullet`)
+	pub conceal: HashMap<String, ConcealOverride>,
+	/// Whether to send semantic tokens
+	pub semantic_tokens: bool,
+	/// Location of the project file, relative paths are resolved from each workspace folder. Defaults
+	/// to [`PROJECT_FILE`] in the folder
+	pub project_file: Option<PathBuf>,
+	/// Inlay hints
+	pub hints: HintsConfig,
+	/// Maximum duration of a parse in seconds, `null` removes the limit, see [`ParseBudget`]
+	pub parse_timeout: Option<f64>,
+	/// Maximum number of rules matched by a parse, `null` removes the limit
+	pub max_rule_matches: Option<usize>,
+}
+
+impl Default for ServerConfig {
+	fn default() -> Self {
+		Self {
+			severity: HashMap::new(),
+			conceals: true,
+			conceal: HashMap::new(),
+			semantic_tokens: true,
+			project_file: None,
+			hints: HintsConfig::default(),
+			parse_timeout: Some(10.0),
+			max_rule_matches: Some(1_000_000),
+		}
+	}
+}
+
+impl ServerConfig {
+	/// Gets the budget of the parses, invalid timeouts (e.g negative) remove the limit
+	pub fn parse_budget(&self) -> ParseBudget {
+		ParseBudget {
+			timeout: self
+				.parse_timeout
+				.and_then(|secs| Duration::try_from_secs_f64(secs).ok()),
+			max_matches: self.max_rule_matches,
+		}
+	}
+
+	/// Reads the configuration from the client's settings, which may be namespaced under `nml`
+	///
+	/// # Errors
+	///
+	/// Returns an error when the settings are invalid.
+	pub fn from_settings(settings: &serde_json::Value) -> Result<Self, String> {
+		serde_json::from_value(settings.get("nml").unwrap_or(settings).clone())
+			.map_err(|err| format!("Invalid configuration: {err}"))
+	}
+
+	/// Applies [`Self::severity`] to diagnostics
+	pub fn apply_severity(&self, diagnostics: &[Diagnostic]) -> Vec<Diagnostic> {
+		diagnostics
+			.iter()
+			.filter_map(|diagnostic| {
+				let code = match &diagnostic.code {
+					Some(NumberOrString::String(code)) => Some(code.as_str()),
+					_ => None,
+				};
+				let default = match diagnostic.severity {
+					Some(DiagnosticSeverity::ERROR) => Some("error"),
+					Some(DiagnosticSeverity::WARNING) => Some("warning"),
+					Some(DiagnosticSeverity::INFORMATION) => Some("information"),
+					_ => None,
+				};
+				let severity = code
+					.and_then(|code| self.severity.get(code))
+					.or(default.and_then(|default| self.severity.get(default)));
+
+				let mut diagnostic = diagnostic.clone();
+				diagnostic.severity = match severity {
+					None => diagnostic.severity,
+					Some(Severity::Error) => Some(DiagnosticSeverity::ERROR),
+					Some(Severity::Warning) => Some(DiagnosticSeverity::WARNING),
+					Some(Severity::Information) => Some(DiagnosticSeverity::INFORMATION),
+					Some(Severity::Hint) => Some(DiagnosticSeverity::HINT),
+					Some(Severity::Off) => return None,
+				};
+				Some(diagnostic)
+			})
+			.collect()
+	}
+
+	/// Gets the path of the project file, see [`Self::project_file`]
+	pub fn project_path(&self, root: Option<&Path>) -> Option<PathBuf> {
+		match (&self.project_file, root) {
+			(Some(path), Some(root)) => Some(root.join(path)),
+			(Some(path), None) => Some(path.clone()),
+			(None, Some(root)) => Some(root.join(PROJECT_FILE)).filter(|path| path.is_file()),
+			(None, None) => None,
+		}
+	}
+}
+
+/// Reads the project file at `path`
+///
+/// # Errors
+///
+/// Returns the problems found in the project file.
+pub fn load_project(path: &Path) -> Result<ProjectSettings, String> {
+	let source = SourceFile::new(path.to_string_lossy().to_string(), None)
+		.map_err(|err| format!("Unable to read project file `{}`: {err}", path.display()))?;
+	ProjectSettings::from_source(Rc::new(source))
+		.map_err(|reports| Report::reports_to_string(&ReportColors::without_colors(), reports))
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+
+	#[test]
+	fn severity() {
+		let config = ServerConfig::from_settings(&json!({
+			"nml": {
+				"severity": { "a11y": "off", "warning": "hint", "NML0005": "warning" },
+				"hints": { "sectionNumbers": false }
+			}
+		}))
+		.unwrap();
+		assert!(!config.hints.section_numbers);
+		assert!(config.semantic_tokens);
+
+		let diagnostic = |code: &str, severity| Diagnostic {
+			severity: Some(severity),
+			code: Some(NumberOrString::String(code.into())),
+			..Diagnostic::default()
+		};
+		let diagnostics = config.apply_severity(&[
+			diagnostic("a11y", DiagnosticSeverity::INFORMATION),
+			diagnostic("NML0005", DiagnosticSeverity::ERROR),
+			diagnostic("NML0001", DiagnosticSeverity::WARNING),
+			diagnostic("NML0002", DiagnosticSeverity::ERROR),
+		]);
+		assert_eq!(
+			diagnostics
+				.iter()
+				.map(|diagnostic| diagnostic.severity.unwrap())
+				.collect::<Vec<_>>(),
+			vec![
+				DiagnosticSeverity::WARNING,
+				DiagnosticSeverity::HINT,
+				DiagnosticSeverity::ERROR
+			]
+		);
+
+		assert_eq!(
+			config.parse_budget(),
+			ParseBudget {
+				timeout: Some(Duration::from_secs(10)),
+				max_matches: Some(1_000_000)
+			}
+		);
+		let config =
+			ServerConfig::from_settings(&json!({ "parseTimeout": 2.5, "maxRuleMatches": null }))
+				.unwrap();
+		assert_eq!(
+			config.parse_budget(),
+			ParseBudget {
+				timeout: Some(Duration::from_millis(2500)),
+				max_matches: None
+			}
+		);
+
+		assert!(ServerConfig::from_settings(&json!({ "conceals": 1 })).is_err());
+	}
+}
@@ -4,9 +4,13 @@ use std::rc::Rc;
 use crate::parser::source::Source;
 
 use super::code::CodeRangeData;
+use super::color::ColorData;
 use super::conceal::ConcealsData;
 use super::definition::DefinitionData;
+use super::elemstyle::ElemStylesData;
 use super::hints::HintsData;
+use super::hover::HoverData;
+use super::linked::LinkedData;
 use super::semantic::SemanticsData;
 use super::semantic::Tokens;
 use super::styles::StylesData;
@@ -20,6 +24,10 @@ pub struct LSPData {
 	pub conceals: HashMap<Rc<dyn Source>, ConcealsData>,
 	pub styles: HashMap<Rc<dyn Source>, StylesData>,
 	pub coderanges: HashMap<Rc<dyn Source>, CodeRangeData>,
+	pub hovers: HashMap<Rc<dyn Source>, HoverData>,
+	pub colors: HashMap<Rc<dyn Source>, ColorData>,
+	pub elemstyles: HashMap<Rc<dyn Source>, ElemStylesData>,
+	pub linked: HashMap<Rc<dyn Source>, LinkedData>,
 }
 
 impl LSPData {
@@ -32,6 +40,10 @@ impl LSPData {
 			conceals: HashMap::new(),
 			styles: HashMap::new(),
 			coderanges: HashMap::new(),
+			hovers: HashMap::new(),
+			colors: HashMap::new(),
+			elemstyles: HashMap::new(),
+			linked: HashMap::new(),
 		}
 	}
 
@@ -58,5 +70,20 @@ impl LSPData {
 		if !self.coderanges.contains_key(&source) {
 			self.coderanges.insert(source.clone(), CodeRangeData::new());
 		}
+		if !self.hovers.contains_key(&source) {
+			self.hovers
+				.insert(source.clone(), HoverData::new(source.clone()));
+		}
+		if !self.colors.contains_key(&source) {
+			self.colors
+				.insert(source.clone(), ColorData::new(source.clone()));
+		}
+		if !self.elemstyles.contains_key(&source) {
+			self.elemstyles
+				.insert(source.clone(), ElemStylesData::new());
+		}
+		if !self.linked.contains_key(&source) {
+			self.linked.insert(source.clone(), LinkedData::new());
+		}
 	}
 }
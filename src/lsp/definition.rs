@@ -89,7 +89,11 @@ fn from_source_impl(
 			let uri = if orignal_target.0.name().starts_with("file://") {
 				Url::try_from(orignal_target.0.name().as_str()).unwrap()
 			} else {
-				let target_path = std::fs::canonicalize(orignal_target.0.name().as_str()).unwrap();
+				// Sources that do not exist on disk can't be linked to
+				let Ok(target_path) = std::fs::canonicalize(orignal_target.0.name().as_str())
+				else {
+					return;
+				};
 				Url::from_file_path(target_path).unwrap()
 			};
 			db.push((
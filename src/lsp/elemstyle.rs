@@ -0,0 +1,218 @@
+use std::cell::Ref;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use tower_lsp::lsp_types::CompletionItem;
+use tower_lsp::lsp_types::CompletionItemKind;
+use tower_lsp::lsp_types::Position;
+
+use crate::elements::elemstyle::rule::json_substring;
+use crate::parser::source::LineCursor;
+use crate::parser::source::OffsetEncoding;
+use crate::parser::source::Source;
+use crate::parser::source::SourceFile;
+use crate::parser::source::SourcePosition;
+use crate::parser::source::VirtualSource;
+use crate::parser::style::ElementStyle;
+use crate::parser::style::StyleHolder;
+
+use super::data::LSPData;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElemStyleParams {
+	pub text_document: tower_lsp::lsp_types::TextDocumentIdentifier,
+	pub position: Position,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ElemStyleInfo {
+	/// Key of the style, e.g `style.section`
+	pub key: String,
+	/// Value of the style
+	pub value: Value,
+	/// Range of the assignment that set the style, `None` for default styles
+	pub range: Option<tower_lsp::lsp_types::Range>,
+}
+
+/// Per file element style assignments
+#[derive(Debug)]
+pub struct ElemStylesData {
+	/// The assignments, in parsing order
+	pub assignments: RefCell<Vec<ElemStyleInfo>>,
+}
+
+impl ElemStylesData {
+	pub fn new() -> Self {
+		Self {
+			assignments: RefCell::new(vec![]),
+		}
+	}
+}
+
+/// Temporary data returned by [`Self::from_source_impl`]
+#[derive(Debug)]
+pub struct ElemStyles<'a> {
+	pub(self) elemstyles: Ref<'a, ElemStylesData>,
+	// The source used when resolving the parent source
+	pub(self) original_source: Rc<dyn Source>,
+	/// The resolved parent source
+	pub(self) source: Rc<dyn Source>,
+}
+
+impl<'a> ElemStyles<'a> {
+	fn from_source_impl(
+		source: Rc<dyn Source>,
+		lsp: &'a Option<RefCell<LSPData>>,
+		original_source: Rc<dyn Source>,
+	) -> Option<Self> {
+		if (source.name().starts_with(":LUA:") || source.name().starts_with(":VAR:"))
+			&& source.downcast_ref::<VirtualSource>().is_some()
+		{
+			return None;
+		}
+
+		if let Some(location) = source
+			.clone()
+			.downcast_rc::<VirtualSource>()
+			.ok()
+			.as_ref()
+			.map(|parent| parent.location())
+			.unwrap_or(None)
+		{
+			return Self::from_source_impl(location.source(), lsp, original_source);
+		} else if let Ok(source) = source.clone().downcast_rc::<SourceFile>() {
+			return Ref::filter_map(lsp.as_ref().unwrap().borrow(), |lsp: &LSPData| {
+				lsp.elemstyles.get(&(source.clone() as Rc<dyn Source>))
+			})
+			.ok()
+			.map(|elemstyles| Self {
+				elemstyles,
+				source,
+				original_source,
+			});
+		}
+		None
+	}
+
+	pub fn from_source(source: Rc<dyn Source>, lsp: &'a Option<RefCell<LSPData>>) -> Option<Self> {
+		if lsp.is_none() {
+			return None;
+		}
+		Self::from_source_impl(source.clone(), lsp, source)
+	}
+
+	/// Records the assignment of `style` over `range`
+	pub fn add(&self, range: std::ops::Range<usize>, style: &dyn ElementStyle) {
+		let range = self.original_source.original_range(range).1;
+		let mut cursor = LineCursor::new(self.source.clone(), OffsetEncoding::Utf16);
+
+		cursor.move_to(range.start);
+		let start = Position {
+			line: cursor.line as u32,
+			character: cursor.line_pos as u32,
+		};
+
+		cursor.move_to(range.end);
+		let end = Position {
+			line: cursor.line as u32,
+			character: cursor.line_pos as u32,
+		};
+
+		self.elemstyles
+			.assignments
+			.borrow_mut()
+			.push(ElemStyleInfo {
+				key: style.key().to_string(),
+				value: style.to_json(),
+				range: Some(tower_lsp::lsp_types::Range { start, end }),
+			});
+	}
+}
+
+/// Gets the styles in effect at `position`, given the style assignments of a document
+///
+/// Styles that are not assigned before `position` have their default value.
+pub fn effective_styles(assignments: &[ElemStyleInfo], position: Position) -> Vec<ElemStyleInfo> {
+	let mut styles = StyleHolder::default()
+		.styles()
+		.iter()
+		.map(|style| ElemStyleInfo {
+			key: style.key().to_string(),
+			value: style.to_json(),
+			range: None,
+		})
+		.collect::<Vec<_>>();
+
+	for assignment in assignments
+		.iter()
+		.filter(|assignment| assignment.range.is_some_and(|range| range.end <= position))
+	{
+		if let Some(style) = styles.iter_mut().find(|style| style.key == assignment.key) {
+			*style = assignment.clone();
+		}
+	}
+	styles
+}
+
+/// Gets the completion items for an element style assignment at `position`
+///
+/// Style keys are completed after `@@`, and style fields are completed inside the json value of
+/// an assignment.
+pub fn completion(content: &str, position: Position) -> Vec<CompletionItem> {
+	let lines = content.split('\n').collect::<Vec<_>>();
+	let Some(line) = lines.get(position.line as usize) else {
+		return vec![];
+	};
+	let prefix = line
+		.chars()
+		.take(position.character as usize)
+		.collect::<String>();
+	let styles = StyleHolder::default().styles();
+
+	// Style keys
+	if prefix.starts_with("@@") && !prefix.contains('=') {
+		return styles
+			.iter()
+			.map(|style| CompletionItem {
+				label: style.key().to_string(),
+				kind: Some(CompletionItemKind::MODULE),
+				detail: Some("Element style".into()),
+				..CompletionItem::default()
+			})
+			.collect();
+	}
+
+	// Style fields, find the enclosing assignment
+	let text = format!("\n{}\n{prefix}", lines[..position.line as usize].join("\n"));
+	let Some(start) = text.rfind("\n@@") else {
+		return vec![];
+	};
+	let Some((key, json)) = text[start + 3..].split_once('=') else {
+		return vec![];
+	};
+	let json = json.trim_start();
+	if !json.starts_with('{') || json_substring(json).is_some() {
+		return vec![];
+	}
+	let Some(style) = styles.iter().find(|style| style.key() == key.trim()) else {
+		return vec![];
+	};
+	let Value::Object(fields) = style.to_json() else {
+		return vec![];
+	};
+
+	fields
+		.iter()
+		.map(|(name, default)| CompletionItem {
+			label: name.clone(),
+			kind: Some(CompletionItemKind::FIELD),
+			detail: Some(format!("Default: {default}")),
+			..CompletionItem::default()
+		})
+		.collect()
+}
@@ -0,0 +1,67 @@
+use tower_lsp::lsp_types::Position;
+use tower_lsp::lsp_types::Range;
+use tower_lsp::lsp_types::TextEdit;
+
+use crate::project::format::format_edits;
+
+/// Gets the position of byte offset `pos` in `content`
+fn position(content: &str, pos: usize) -> Position {
+	let line_start = content[..pos].rfind('\n').map_or(0, |start| start + 1);
+	Position {
+		line: content[..pos].matches('\n').count() as u32,
+		character: content[line_start..pos].encode_utf16().count() as u32,
+	}
+}
+
+/// Gets the edits formatting a document, see [`format_edits`]
+///
+/// When `range` is set, only the tables intersecting the range are formatted.
+pub fn formatting(
+	name: String,
+	content: String,
+	defines: &[(String, String)],
+	range: Option<Range>,
+) -> Vec<TextEdit> {
+	format_edits(name, content.clone(), defines)
+		.into_iter()
+		.map(|(edit, new_text)| TextEdit {
+			range: Range {
+				start: position(&content, edit.start),
+				end: position(&content, edit.end),
+			},
+			new_text,
+		})
+		.filter(|edit| {
+			range.is_none_or(|range| edit.range.start <= range.end && range.start <= edit.range.end)
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn ranges() {
+		let content = "| a | bé |\n|cc|d|\n\nText\n\n|x|yy|\n|zzz|w|\n".to_string();
+		let edits = formatting("".into(), content.clone(), &[], None);
+		assert_eq!(
+			edits,
+			vec![
+				TextEdit {
+					range: Range::new(Position::new(0, 0), Position::new(1, 6)),
+					new_text: "| a  | bé |\n| cc | d  |".into()
+				},
+				TextEdit {
+					range: Range::new(Position::new(5, 0), Position::new(6, 7)),
+					new_text: "| x   | yy |\n| zzz | w  |".into()
+				}
+			]
+		);
+
+		let range = Range::new(Position::new(6, 1), Position::new(6, 2));
+		let edits = formatting("".into(), content, &[], Some(range));
+		assert_eq!(edits.len(), 1);
+		assert_eq!(edits[0].range.start.line, 5);
+	}
+}
@@ -2,6 +2,8 @@ use std::cell::Ref;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use serde::Deserialize;
+use serde::Serialize;
 use tower_lsp::lsp_types::InlayHint;
 
 use crate::parser::source::LineCursor;
@@ -13,6 +15,50 @@ use crate::parser::source::VirtualSource;
 
 use super::data::LSPData;
 
+/// Hints that can be disabled from the configuration of the language server, see [`HintsConfig`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HintKind {
+	/// Value of a variable, next to its usage
+	VariableValue,
+	/// Number of a section, next to its heading
+	SectionNumber,
+}
+
+/// Configuration of the hints, read from the `hints` settings of the language server
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HintsConfig {
+	/// Display the values of variables
+	pub variable_values: bool,
+	/// Display the numbers of sections
+	pub section_numbers: bool,
+}
+
+impl Default for HintsConfig {
+	fn default() -> Self {
+		Self {
+			variable_values: true,
+			section_numbers: true,
+		}
+	}
+}
+
+impl HintsConfig {
+	/// Checks whether a hint is enabled, hints without a [`HintKind`] are always displayed
+	pub fn enabled(&self, hint: &InlayHint) -> bool {
+		match hint
+			.data
+			.as_ref()
+			.and_then(|data| serde_json::from_value::<HintKind>(data.clone()).ok())
+		{
+			Some(HintKind::VariableValue) => self.variable_values,
+			Some(HintKind::SectionNumber) => self.section_numbers,
+			None => true,
+		}
+	}
+}
+
 /// Per file hints
 #[derive(Debug)]
 pub struct HintsData {
@@ -84,7 +130,14 @@ impl<'a> Hints<'a> {
 		Self::from_source_impl(source.clone(), lsp, source)
 	}
 
-	pub fn add(&self, position: usize, label: String) {
+	pub fn add(&self, position: usize, label: String) { self.add_impl(position, label, None) }
+
+	/// Adds a hint that can be disabled, see [`HintsConfig`]
+	pub fn add_kind(&self, position: usize, label: String, kind: HintKind) {
+		self.add_impl(position, label, serde_json::to_value(kind).ok())
+	}
+
+	fn add_impl(&self, position: usize, label: String, data: Option<serde_json::Value>) {
 		let position = self.original_source.original_position(position).1;
 		let mut cursor = self.hints.cursor.borrow_mut();
 		cursor.move_to(position);
@@ -100,7 +153,7 @@ impl<'a> Hints<'a> {
 			tooltip: None,
 			padding_left: None,
 			padding_right: None,
-			data: None,
+			data,
 		})
 	}
 }
@@ -0,0 +1,106 @@
+use std::cell::Ref;
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+
+use tower_lsp::lsp_types::Position;
+
+use crate::parser::source::LineCursor;
+use crate::parser::source::OffsetEncoding;
+use crate::parser::source::Source;
+use crate::parser::source::SourceFile;
+use crate::parser::source::SourcePosition;
+use crate::parser::source::VirtualSource;
+
+use super::data::LSPData;
+
+/// Per file hover documentation
+#[derive(Debug)]
+pub struct HoverData {
+	/// The source
+	source: Rc<dyn Source>,
+
+	/// The hovers, as ranges and their markdown content
+	pub hovers: RefCell<Vec<(tower_lsp::lsp_types::Range, String)>>,
+}
+
+impl HoverData {
+	pub fn new(source: Rc<dyn Source>) -> Self {
+		Self {
+			source,
+			hovers: RefCell::new(vec![]),
+		}
+	}
+}
+
+/// Temporary data returned by [`Self::from_source_impl`]
+#[derive(Debug)]
+pub struct Hover<'a> {
+	pub(self) hovers: Ref<'a, HoverData>,
+	// The source used when resolving the parent source
+	pub(self) original_source: Rc<dyn Source>,
+}
+
+impl<'a> Hover<'a> {
+	fn from_source_impl(
+		source: Rc<dyn Source>,
+		lsp: &'a Option<RefCell<LSPData>>,
+		original_source: Rc<dyn Source>,
+	) -> Option<Self> {
+		if (source.name().starts_with(":LUA:") || source.name().starts_with(":VAR:"))
+			&& source.downcast_ref::<VirtualSource>().is_some()
+		{
+			return None;
+		}
+
+		if let Some(location) = source
+			.clone()
+			.downcast_rc::<VirtualSource>()
+			.ok()
+			.as_ref()
+			.map(|parent| parent.location())
+			.unwrap_or(None)
+		{
+			return Self::from_source_impl(location.source(), lsp, original_source);
+		} else if let Ok(source) = source.clone().downcast_rc::<SourceFile>() {
+			return Ref::filter_map(lsp.as_ref().unwrap().borrow(), |lsp: &LSPData| {
+				lsp.hovers.get(&(source.clone() as Rc<dyn Source>))
+			})
+			.ok()
+			.map(|hovers| Self {
+				hovers,
+				original_source,
+			});
+		}
+		None
+	}
+
+	pub fn from_source(source: Rc<dyn Source>, lsp: &'a Option<RefCell<LSPData>>) -> Option<Self> {
+		if lsp.is_none() {
+			return None;
+		}
+		Self::from_source_impl(source.clone(), lsp, source)
+	}
+
+	/// Adds markdown documentation displayed when hovering `range`
+	pub fn add(&self, range: Range<usize>, content: String) {
+		let range = self.original_source.original_range(range).1;
+		// Hovers are not added in order, so a new cursor is required
+		let mut cursor = LineCursor::new(self.hovers.source.clone(), OffsetEncoding::Utf16);
+		cursor.move_to(range.start);
+		let start = Position {
+			line: cursor.line as u32,
+			character: cursor.line_pos as u32,
+		};
+		cursor.move_to(range.end);
+		let end = Position {
+			line: cursor.line as u32,
+			character: cursor.line_pos as u32,
+		};
+
+		self.hovers
+			.hovers
+			.borrow_mut()
+			.push((tower_lsp::lsp_types::Range { start, end }, content))
+	}
+}
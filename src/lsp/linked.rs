@@ -0,0 +1,86 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use tower_lsp::lsp_types::Position;
+use tower_lsp::lsp_types::Range;
+
+use crate::parser::source::LineCursor;
+use crate::parser::source::OffsetEncoding;
+use crate::parser::source::SourceFile;
+use crate::parser::source::SourcePosition;
+use crate::parser::source::Token;
+
+use super::data::LSPData;
+
+/// Per file linked editing ranges
+#[derive(Debug)]
+pub struct LinkedData {
+	/// Groups of ranges that are edited together
+	pub linked: RefCell<Vec<Vec<Range>>>,
+}
+
+impl LinkedData {
+	pub fn new() -> Self {
+		Self {
+			linked: RefCell::new(vec![]),
+		}
+	}
+}
+
+/// Links tokens so that they are edited together, e.g the delimiters of a custom style
+///
+/// Tokens are only linked when they are located in the same file and have the same content, as
+/// required by `textDocument/linkedEditingRange`.
+pub fn from_tokens(tokens: &[Token], lsp: &Option<RefCell<LSPData>>) {
+	let Some(lsp) = lsp.as_ref() else {
+		return;
+	};
+	let resolved = tokens
+		.iter()
+		.map(|token| token.source().original_range(token.range.clone()))
+		.collect::<Vec<_>>();
+	let Some((source, first)) = resolved.first() else {
+		return;
+	};
+	if source.downcast_ref::<SourceFile>().is_none()
+		|| resolved.iter().any(|(other, range)| {
+			!Rc::ptr_eq(source, other)
+				|| source.content()[range.clone()] != source.content()[first.clone()]
+		}) {
+		return;
+	}
+
+	let borrow = lsp.borrow();
+	let Some(data) = borrow.linked.get(source) else {
+		return;
+	};
+	let position = |pos: usize| {
+		let mut cursor = LineCursor::new(source.clone(), OffsetEncoding::Utf16);
+		cursor.move_to(pos);
+		Position {
+			line: cursor.line as u32,
+			character: cursor.line_pos as u32,
+		}
+	};
+	data.linked.borrow_mut().push(
+		resolved
+			.iter()
+			.map(|(_, range)| Range {
+				start: position(range.start),
+				end: position(range.end),
+			})
+			.collect(),
+	);
+}
+
+/// Gets the group of linked ranges containing `position`
+pub fn linked_at(linked: &[Vec<Range>], position: Position) -> Option<Vec<Range>> {
+	linked
+		.iter()
+		.find(|ranges| {
+			ranges
+				.iter()
+				.any(|range| range.start <= position && position <= range.end)
+		})
+		.cloned()
+}
@@ -1,7 +1,18 @@
+pub mod analysis;
 pub mod code;
+pub mod color;
 pub mod conceal;
+pub mod config;
 pub mod data;
 pub mod definition;
+pub mod elemstyle;
+pub mod format;
 pub mod hints;
+pub mod hover;
+pub mod linked;
+pub mod outline;
+pub mod preview;
+pub mod references;
 pub mod semantic;
 pub mod styles;
+pub mod workspace;
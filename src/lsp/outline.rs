@@ -0,0 +1,240 @@
+use std::ops::Range;
+use std::rc::Rc;
+
+use serde::Deserialize;
+use serde::Serialize;
+use tower_lsp::lsp_types::Position;
+
+use crate::document::document::Document;
+use crate::document::element::Element;
+use crate::elements::layout::custom::LayoutToken;
+use crate::elements::layout::elem::Layout;
+use crate::elements::media::elem::Medium;
+use crate::elements::reference::elem::ExternalReference;
+use crate::elements::reference::elem::InternalReference;
+use crate::elements::section::elem::Section;
+use crate::elements::table::elem::Table;
+use crate::parser::source::LineCursor;
+use crate::parser::source::OffsetEncoding;
+use crate::parser::source::Source;
+use crate::parser::source::SourcePosition;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineParams {
+	pub text_document: tower_lsp::lsp_types::TextDocumentIdentifier,
+}
+
+/// Element of the outline returned by the `nml/outline` request
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineItem {
+	/// Name of the element, see [`Element::element_name`]
+	pub kind: String,
+	/// Displayed name: section title, layout name, caption, ...
+	pub name: String,
+	/// Reference name of the element, if any
+	pub reference: Option<String>,
+	pub range: tower_lsp::lsp_types::Range,
+	/// Sections contain the elements up to the next section of the same depth, and layouts
+	/// contain the elements up to their end
+	pub children: Vec<OutlineItem>,
+}
+
+/// Outline entries being built, the last entry contains the next items
+struct Builder {
+	source: Rc<dyn Source>,
+	/// Open entries, along with the depth of sections
+	stack: Vec<(OutlineItem, Option<usize>)>,
+	items: Vec<OutlineItem>,
+}
+
+impl Builder {
+	/// Converts a range of the document's source, leading whitespaces are excluded
+	fn range(&self, range: Range<usize>) -> tower_lsp::lsp_types::Range {
+		let content = &self.source.content()[range.clone()];
+		let range = range.start + content.len() - content.trim_start().len()..range.end;
+		let position = |pos: usize| {
+			let mut cursor = LineCursor::new(self.source.clone(), OffsetEncoding::Utf16);
+			cursor.move_to(pos);
+			Position {
+				line: cursor.line as u32,
+				character: cursor.line_pos as u32,
+			}
+		};
+		tower_lsp::lsp_types::Range {
+			start: position(range.start),
+			end: position(range.end),
+		}
+	}
+
+	/// Closes the last open entry
+	fn close(&mut self) {
+		let (item, _) = self.stack.pop().unwrap();
+		match self.stack.last_mut() {
+			Some((parent, _)) => parent.children.push(item),
+			None => self.items.push(item),
+		}
+	}
+
+	fn push(&mut self, item: OutlineItem) {
+		match self.stack.last_mut() {
+			Some((parent, _)) => parent.children.push(item),
+			None => self.items.push(item),
+		}
+	}
+
+	fn add(&mut self, elem: &dyn Element) {
+		let location = elem.location();
+		let (source, range) = location.source().original_range(location.range.clone());
+		// Elements from other sources (e.g imports) are skipped
+		if !Rc::ptr_eq(&source, &self.source) {
+			return;
+		}
+		let range = self.range(range);
+		let item = |name: String, reference: Option<&String>| OutlineItem {
+			kind: elem.element_name().to_string(),
+			name,
+			reference: reference.cloned(),
+			range,
+			children: vec![],
+		};
+
+		if let Some(section) = elem.downcast_ref::<Section>() {
+			// Close sections of the same or greater depth, inside the current layout
+			while self
+				.stack
+				.last()
+				.is_some_and(|(_, depth)| depth.is_some_and(|depth| depth >= section.depth))
+			{
+				self.close();
+			}
+			let item = item(section.title.clone(), section.reference.as_ref());
+			self.stack.push((item, Some(section.depth)));
+		} else if let Some(layout) = elem.downcast_ref::<Layout>() {
+			match layout.token {
+				LayoutToken::Begin => {
+					let item = item(layout.layout.name().to_string(), None);
+					self.stack.push((item, None));
+				}
+				LayoutToken::Next => {}
+				LayoutToken::End => {
+					// Close the sections opened inside the layout
+					while self.stack.last().is_some_and(|(_, depth)| depth.is_some()) {
+						self.close();
+					}
+					if let Some((item, _)) = self.stack.last_mut() {
+						item.range.end = range.end;
+						self.close();
+					}
+				}
+			}
+		} else if let Some(table) = elem.downcast_ref::<Table>() {
+			let item = item(
+				table.title.clone().unwrap_or_default(),
+				table.reference.as_ref(),
+			);
+			self.push(item);
+		} else if let Some(medium) = elem.downcast_ref::<Medium>() {
+			let item = item(
+				medium.caption.clone().unwrap_or(medium.uri.clone()),
+				Some(&medium.reference),
+			);
+			self.push(item);
+		} else if let Some(reference) = elem.downcast_ref::<InternalReference>() {
+			let item = item(reference.refname.clone(), None);
+			self.push(item);
+		} else if let Some(reference) = elem.downcast_ref::<ExternalReference>() {
+			let item = item(reference.reference.to_string(), None);
+			self.push(item);
+		} else if let Some(container) = elem.as_container() {
+			for elem in container.contained() {
+				self.add(elem.as_ref());
+			}
+		}
+	}
+}
+
+/// Gets the outline of a document
+///
+/// The outline contains the sections, layouts, tables, media and references of the document,
+/// it's richer than the symbols of `textDocument/documentSymbol`, so that editors can build
+/// specialized views (e.g a list of figures).
+pub fn outline(document: &dyn Document) -> Vec<OutlineItem> {
+	let mut builder = Builder {
+		source: document.source(),
+		stack: vec![],
+		items: vec![],
+	};
+	for elem in document.content().borrow().iter() {
+		builder.add(elem.as_ref());
+	}
+	while !builder.stack.is_empty() {
+		builder.close();
+	}
+	builder.items
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::parser::langparser::LangParser;
+	use crate::parser::parser::ParseMode;
+	use crate::parser::parser::Parser;
+	use crate::parser::parser::ParserState;
+	use crate::parser::source::SourceFile;
+
+	use super::*;
+
+	#[test]
+	fn items() {
+		let source = Rc::new(SourceFile::with_content(
+			"".to_string(),
+			r#"
+# First
+See &{cat}
+## Nested
+#+LAYOUT_BEGIN Centered
+![cat](cat.png)[caption=A cat, alt=Cat]
+#+LAYOUT_END
+# Second
+"#
+			.to_string(),
+			None,
+		));
+		let parser = LangParser::default();
+		let (doc, _) = parser.parse(
+			ParserState::new(&parser, None),
+			source,
+			None,
+			ParseMode::default(),
+		);
+
+		let summary = |item: &OutlineItem| {
+			(
+				item.kind.clone(),
+				item.name.clone(),
+				item.range.start.line,
+				item.range.end.line,
+			)
+		};
+		let items = outline(&*doc);
+		assert_eq!(
+			items.iter().map(summary).collect::<Vec<_>>(),
+			vec![
+				("Section".into(), "First".into(), 1, 1),
+				("Section".into(), "Second".into(), 7, 7)
+			]
+		);
+		assert_eq!(
+			items[0].children.iter().map(summary).collect::<Vec<_>>(),
+			vec![
+				("Reference".into(), "cat".into(), 2, 2),
+				("Section".into(), "Nested".into(), 3, 3)
+			]
+		);
+		let layout = &items[0].children[1].children[0];
+		assert_eq!(summary(layout), ("Layout".into(), "Centered".into(), 4, 6));
+		assert_eq!(layout.children[0].name, "A cat");
+		assert_eq!(layout.children[0].reference, Some("cat".into()));
+	}
+}
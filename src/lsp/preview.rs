@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::compiler::api::compile_source;
+use crate::compiler::api::Settings;
+use crate::parser::parser::ReportColors;
+use crate::parser::reports::Report;
+use crate::parser::source::SourceFile;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompilePreviewParams {
+	pub text_document: tower_lsp::lsp_types::TextDocumentIdentifier,
+}
+
+/// Result of the `nml/compilePreview` request
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CompilePreview {
+	/// The compiled page, missing when errors were reported
+	pub html: Option<String>,
+	/// Reports of the compilation, as text
+	pub reports: String,
+}
+
+/// Compiles the content of a document to HTML, for editors to display a preview
+///
+/// External commands are disabled, but results stored in the `database` by previous builds are
+/// used. References to other documents can't be resolved.
+pub fn compile_preview(
+	name: String,
+	content: String,
+	defines: Vec<(String, String)>,
+	database: Option<PathBuf>,
+) -> CompilePreview {
+	let settings = Settings {
+		defines,
+		database,
+		..Settings::default()
+	};
+	let source = Rc::new(SourceFile::with_content(name, content, None));
+	let (html, reports) = match compile_source(source, &settings) {
+		Ok(output) => (Some(output.page()), output.reports),
+		Err(reports) => (None, reports),
+	};
+
+	CompilePreview {
+		html,
+		reports: Report::reports_to_string(&ReportColors::without_colors(), reports),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn preview() {
+		let preview = compile_preview(
+			"".into(),
+			"#{intro} Introduction\n*Preview*".into(),
+			vec![("html.page_title".into(), "Preview".into())],
+			None,
+		);
+		let html = preview.html.unwrap();
+		assert!(html.contains("<title>Preview</title>"));
+		assert!(html.contains("Introduction"));
+
+		let preview = compile_preview("".into(), "&{missing}".into(), vec![], None);
+		assert!(preview.html.is_none());
+		assert!(!preview.reports.is_empty());
+	}
+}
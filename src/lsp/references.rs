@@ -0,0 +1,322 @@
+use std::rc::Rc;
+
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde::Serialize;
+use tower_lsp::lsp_types::CompletionItem;
+use tower_lsp::lsp_types::CompletionItemKind;
+use tower_lsp::lsp_types::Diagnostic;
+use tower_lsp::lsp_types::DiagnosticSeverity;
+use tower_lsp::lsp_types::NumberOrString;
+use tower_lsp::lsp_types::Position;
+
+use crate::document::document::Document;
+use crate::document::element::Element;
+use crate::document::references::CrossReference;
+use crate::elements::reference::elem::ExternalReference;
+use crate::parser::codes::ReportCode;
+use crate::parser::source::LineCursor;
+use crate::parser::source::OffsetEncoding;
+use crate::parser::source::Source;
+use crate::parser::source::SourcePosition;
+
+/// Reference exported by a document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedReference {
+	/// Name of the reference
+	pub name: String,
+	/// Name of the referenced element, see [`Element::element_name`]
+	pub kind: String,
+}
+
+/// References of a document, stored in the cache database by the language server so that other
+/// documents can use them without compiling the project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedDocument {
+	/// Path of the document
+	pub input: String,
+	/// Name of the document in references (`&{name#refname}`), given by `compiler.output` without
+	/// its extension
+	pub name: Option<String>,
+	pub references: Vec<ExportedReference>,
+}
+
+impl IndexedDocument {
+	pub fn from_document(input: String, document: &dyn Document) -> Self {
+		let name = document.get_variable("compiler.output").map(|output| {
+			let output = output.to_string();
+			let split_at = output.rfind('.').unwrap_or(output.len());
+			output[..split_at].to_string()
+		});
+
+		let scope = document.scope().borrow();
+		let mut references = scope
+			.referenceable
+			.iter()
+			.filter_map(|(name, reference)| {
+				let elem = document.get_from_reference(reference)?;
+				Some(ExportedReference {
+					name: name.clone(),
+					kind: elem.element_name().to_string(),
+				})
+			})
+			.collect::<Vec<_>>();
+		references.sort_by(|a, b| a.name.cmp(&b.name));
+
+		Self {
+			input,
+			name,
+			references,
+		}
+	}
+
+	fn sql_table() -> &'static str {
+		"CREATE TABLE IF NOT EXISTS lsp_references(
+			input TEXT PRIMARY KEY,
+			name TEXT,
+			refs TEXT NOT NULL
+		);"
+	}
+
+	fn sql_get_query() -> &'static str { "SELECT input, name, refs FROM lsp_references" }
+
+	fn sql_insert_query() -> &'static str {
+		"INSERT OR REPLACE INTO lsp_references (input, name, refs) VALUES (?1, ?2, ?3)"
+	}
+
+	pub fn init_cache(con: &Connection) -> Result<usize, rusqlite::Error> {
+		con.execute(Self::sql_table(), [])
+	}
+
+	/// Inserts the references of the document into the cache
+	pub fn insert_cache(&self, con: &Connection) -> Result<usize, rusqlite::Error> {
+		con.execute(
+			Self::sql_insert_query(),
+			(
+				&self.input,
+				&self.name,
+				serde_json::to_string(&self.references).unwrap(),
+			),
+		)
+	}
+
+	/// Gets the references of every indexed document
+	pub fn get_references(con: &Connection) -> Result<Vec<Self>, rusqlite::Error> {
+		let mut query = con.prepare_cached(Self::sql_get_query())?;
+		let rows = query.query_map([], |row| {
+			Ok(IndexedDocument {
+				input: row.get(0)?,
+				name: row.get(1)?,
+				references: serde_json::from_str(row.get::<_, String>(2)?.as_str())
+					.unwrap_or_default(),
+			})
+		})?;
+		rows.collect()
+	}
+}
+
+/// Gets the completion items for a reference at `position`
+///
+/// References of the current document (`input`) are completed by name, references of the other
+/// documents are completed as `#refname` and `name#refname`.
+pub fn completion(
+	documents: &[IndexedDocument],
+	input: &str,
+	content: &str,
+	position: Position,
+) -> Vec<CompletionItem> {
+	let Some(line) = content.split('\n').nth(position.line as usize) else {
+		return vec![];
+	};
+	let prefix = line
+		.chars()
+		.take(position.character as usize)
+		.collect::<String>();
+	if prefix
+		.rfind("&{")
+		.is_none_or(|start| prefix[start..].contains('}'))
+	{
+		return vec![];
+	}
+
+	let item =
+		|label: String, reference: &ExportedReference, document: &IndexedDocument| CompletionItem {
+			label,
+			kind: Some(CompletionItemKind::REFERENCE),
+			detail: Some(format!("{} in {}", reference.kind, document.input)),
+			..CompletionItem::default()
+		};
+	let mut items = vec![];
+	for document in documents {
+		for reference in &document.references {
+			if document.input == input {
+				items.push(item(reference.name.clone(), reference, document));
+				continue;
+			}
+			items.push(item(format!("#{}", reference.name), reference, document));
+			if let Some(name) = &document.name {
+				items.push(item(
+					format!("{name}#{}", reference.name),
+					reference,
+					document,
+				));
+			}
+		}
+	}
+	items
+}
+
+/// Reports the references to indexed documents that don't define the referenced name
+pub fn unresolved(documents: &[IndexedDocument], document: &dyn Document) -> Vec<Diagnostic> {
+	fn visit(
+		elem: &dyn Element,
+		source: &Rc<dyn Source>,
+		documents: &[IndexedDocument],
+		diagnostics: &mut Vec<Diagnostic>,
+	) {
+		if let Some(container) = elem.as_container() {
+			for elem in container.contained() {
+				visit(elem.as_ref(), source, documents, diagnostics);
+			}
+			return;
+		}
+		let Some(reference) = elem.downcast_ref::<ExternalReference>() else {
+			return;
+		};
+		let CrossReference::Specific(name, refname) = &reference.reference else {
+			return;
+		};
+		let Some(found) = documents
+			.iter()
+			.find(|document| document.name.as_ref() == Some(name))
+		else {
+			return;
+		};
+		if found
+			.references
+			.iter()
+			.any(|exported| &exported.name == refname)
+		{
+			return;
+		}
+
+		let (elem_source, range) = reference
+			.location
+			.source()
+			.original_range(reference.location.range.clone());
+		if !Rc::ptr_eq(&elem_source, source) {
+			return;
+		}
+		let position = |pos: usize| {
+			let mut cursor = LineCursor::new(source.clone(), OffsetEncoding::Utf16);
+			cursor.move_to(pos);
+			Position {
+				line: cursor.line as u32,
+				character: cursor.line_pos as u32,
+			}
+		};
+		let message = "Unknown Reference";
+		diagnostics.push(Diagnostic {
+			range: tower_lsp::lsp_types::Range {
+				start: position(range.start),
+				end: position(range.end),
+			},
+			severity: Some(DiagnosticSeverity::WARNING),
+			code: ReportCode::from_message(message)
+				.map(|code| NumberOrString::String(code.code.to_string())),
+			message: format!(
+				"{message}: Document `{name}` ({}) has no reference named `{refname}`",
+				found.input
+			),
+			..Diagnostic::default()
+		});
+	}
+
+	let source = document.source();
+	let mut diagnostics = vec![];
+	for elem in document.content().borrow().iter() {
+		visit(elem.as_ref(), &source, documents, &mut diagnostics);
+	}
+	diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::parser::langparser::LangParser;
+	use crate::parser::parser::ParseMode;
+	use crate::parser::parser::Parser;
+	use crate::parser::parser::ParserState;
+	use crate::parser::source::SourceFile;
+
+	use super::*;
+
+	fn parse(content: &str, f: impl FnOnce(&dyn Document)) {
+		let source = Rc::new(SourceFile::with_content(
+			"".to_string(),
+			content.to_string(),
+			None,
+		));
+		let parser = LangParser::default();
+		let (doc, _) = parser.parse(
+			ParserState::new(&parser, None),
+			source,
+			None,
+			ParseMode::default(),
+		);
+		f(&*doc);
+	}
+
+	#[test]
+	fn index() {
+		let con = Connection::open_in_memory().unwrap();
+		IndexedDocument::init_cache(&con).unwrap();
+
+		parse(
+			r#"
+@compiler.output = other.html
+#{intro} Introduction
+![cat](cat.png)[caption=A cat]
+"#,
+			|doc| {
+				let indexed = IndexedDocument::from_document("other.nml".into(), doc);
+				assert_eq!(indexed.name, Some("other".into()));
+				assert_eq!(
+					indexed.references,
+					vec![
+						ExportedReference {
+							name: "cat".into(),
+							kind: "Medium".into()
+						},
+						ExportedReference {
+							name: "intro".into(),
+							kind: "Section".into()
+						},
+					]
+				);
+				indexed.insert_cache(&con).unwrap();
+			},
+		);
+
+		let documents = IndexedDocument::get_references(&con).unwrap();
+		assert_eq!(documents.len(), 1);
+		parse(
+			"See &{other#intro}, &{other#missing} and &{unknown#ref}",
+			|doc| {
+				let diagnostics = unresolved(&documents, doc);
+				assert_eq!(diagnostics.len(), 1);
+				assert_eq!(diagnostics[0].range.start.character, 20);
+				assert_eq!(
+					diagnostics[0].code,
+					Some(NumberOrString::String("NML0102".into()))
+				);
+			},
+		);
+
+		let labels = completion(&documents, "index.nml", "See &{oth", Position::new(0, 9))
+			.into_iter()
+			.map(|item| item.label)
+			.collect::<Vec<_>>();
+		assert_eq!(labels, vec!["#cat", "other#cat", "#intro", "other#intro"]);
+		assert!(completion(&documents, "index.nml", "See &{a}", Position::new(0, 8)).is_empty());
+	}
+}
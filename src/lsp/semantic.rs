@@ -107,10 +107,12 @@ pub struct Tokens {
 	pub link_display_sep: (u32, u32),
 	pub link_url_sep: (u32, u32),
 	pub link_url: (u32, u32),
+	pub link_props_sep: (u32, u32),
 
 	pub style_marker: (u32, u32),
 
 	pub customstyle_marker: (u32, u32),
+	pub customstyle_props_sep: (u32, u32),
 
 	pub import_import: (u32, u32),
 	pub import_as_sep: (u32, u32),
@@ -125,7 +127,9 @@ pub struct Tokens {
 	pub reference_props_sep: (u32, u32),
 
 	pub variable_operator: (u32, u32),
+	pub variable_export: (u32, u32),
 	pub variable_kind: (u32, u32),
+	pub variable_type: (u32, u32),
 	pub variable_name: (u32, u32),
 	pub variable_sep: (u32, u32),
 	pub variable_value: (u32, u32),
@@ -169,6 +173,10 @@ pub struct Tokens {
 	pub graph_props_sep: (u32, u32),
 	pub graph_content: (u32, u32),
 
+	pub render_sep: (u32, u32),
+	pub render_props_sep: (u32, u32),
+	pub render_content: (u32, u32),
+
 	pub layout_sep: (u32, u32),
 	pub layout_token: (u32, u32),
 	pub layout_props_sep: (u32, u32),
@@ -190,6 +198,22 @@ pub struct Tokens {
 	pub media_uri_sep: (u32, u32),
 	pub media_uri: (u32, u32),
 	pub media_props_sep: (u32, u32),
+
+	pub color_sep: (u32, u32),
+	pub color_value: (u32, u32),
+
+	pub diff_insert_sep: (u32, u32),
+	pub diff_delete_sep: (u32, u32),
+
+	pub linebreak: (u32, u32),
+	pub nobreak_sep: (u32, u32),
+
+	pub icon_sep: (u32, u32),
+	pub icon_name: (u32, u32),
+	pub icon_props_sep: (u32, u32),
+
+	pub conditional_sep: (u32, u32),
+	pub conditional_condition: (u32, u32),
 }
 
 impl Tokens {
@@ -210,10 +234,12 @@ impl Tokens {
 			link_display_sep: token!("macro"),
 			link_url_sep: token!("macro"),
 			link_url: token!("function", "readonly", "abstract", "abstract"),
+			link_props_sep: token!("operator"),
 
 			style_marker: token!("operator"),
 
 			customstyle_marker: token!("operator"),
+			customstyle_props_sep: token!("operator"),
 
 			import_import: token!("macro"),
 			import_as_sep: token!("operator"),
@@ -228,7 +254,9 @@ impl Tokens {
 			reference_props_sep: token!("operator"),
 
 			variable_operator: token!("operator"),
+			variable_export: token!("keyword"),
 			variable_kind: token!("operator"),
+			variable_type: token!("type"),
 			variable_name: token!("macro"),
 			variable_sep: token!("operator"),
 			variable_value: token!("parameter"),
@@ -272,6 +300,10 @@ impl Tokens {
 			graph_props_sep: token!("operator"),
 			graph_content: token!("string"),
 
+			render_sep: token!("keyword"),
+			render_props_sep: token!("operator"),
+			render_content: token!("string"),
+
 			layout_sep: token!("number"),
 			layout_token: token!("number"),
 			layout_props_sep: token!("operator"),
@@ -293,6 +325,22 @@ impl Tokens {
 			media_uri_sep: token!("macro"),
 			media_uri: token!("function"),
 			media_props_sep: token!("operator"),
+
+			color_sep: token!("macro"),
+			color_value: token!("number"),
+
+			diff_insert_sep: token!("operator"),
+			diff_delete_sep: token!("operator", "deprecated"),
+
+			linebreak: token!("operator"),
+			nobreak_sep: token!("macro"),
+
+			icon_sep: token!("macro"),
+			icon_name: token!("enum"),
+			icon_props_sep: token!("operator"),
+
+			conditional_sep: token!("keyword"),
+			conditional_condition: token!("parameter"),
 		}
 	}
 }
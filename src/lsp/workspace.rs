@@ -0,0 +1,79 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use super::config::load_project;
+use super::config::ServerConfig;
+
+/// Workspace folder opened in the editor
+///
+/// Each workspace has its own project file, documents are parsed with the variables of the
+/// workspace containing them.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+	/// Name of the folder, as given by the client
+	pub name: String,
+	/// Path to the folder
+	pub root: PathBuf,
+	/// Variables defined by the project file of the workspace
+	pub defines: Vec<(String, String)>,
+	/// Cache database of the project, see [`super::references`]
+	pub database: Option<PathBuf>,
+}
+
+impl Workspace {
+	pub fn new(name: String, root: PathBuf) -> Self {
+		Self {
+			name,
+			root,
+			defines: vec![],
+			database: None,
+		}
+	}
+
+	/// Loads the project file of the workspace, see [`ServerConfig::project_path`]
+	///
+	/// # Errors
+	///
+	/// Returns the problems found in the project file, the workspace then has no variables and no
+	/// database.
+	pub fn load(&mut self, config: &ServerConfig) -> Result<(), String> {
+		self.defines = vec![];
+		self.database = None;
+		if let Some(path) = config.project_path(Some(&self.root)) {
+			let settings =
+				load_project(&path).map_err(|err| format!("Workspace `{}`: {err}", self.name))?;
+			self.defines = settings.defines();
+			self.database = settings.database.map(|database| self.root.join(database));
+		}
+		Ok(())
+	}
+}
+
+/// Finds the workspace containing `path`, nested workspaces take precedence over their parents
+pub fn find<'a>(workspaces: &'a [Workspace], path: &Path) -> Option<&'a Workspace> {
+	workspaces
+		.iter()
+		.filter(|workspace| path.starts_with(&workspace.root))
+		.max_by_key(|workspace| workspace.root.components().count())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn routing() {
+		let workspaces = vec![
+			Workspace::new("docs".into(), "/home/docs".into()),
+			Workspace::new("nested".into(), "/home/docs/nested".into()),
+			Workspace::new("other".into(), "/home/other".into()),
+		];
+		let name = |path: &str| find(&workspaces, Path::new(path)).map(|ws| ws.name.as_str());
+
+		assert_eq!(name("/home/docs/index.nml"), Some("docs"));
+		assert_eq!(name("/home/docs/nested/index.nml"), Some("nested"));
+		assert_eq!(name("/home/docs/nested_other/index.nml"), Some("docs"));
+		assert_eq!(name("/home/other/a/b.nml"), Some("other"));
+		assert_eq!(name("/tmp/index.nml"), None);
+	}
+}
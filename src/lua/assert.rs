@@ -0,0 +1,127 @@
+use ariadne::Fmt;
+use mlua::Lua;
+use mlua::Value;
+
+use crate::document::element::Element;
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
+
+use super::kernel::Binding;
+use super::kernel::KernelContext;
+use super::kernel::CTX;
+
+/// Counts the elements named `name` in `elems`, including contained elements
+fn count_elements(elems: &[Box<dyn Element>], name: &str) -> usize {
+	elems
+		.iter()
+		.map(|elem| {
+			let contained = elem
+				.as_container()
+				.map_or(0, |container| count_elements(container.contained(), name));
+			contained + (elem.element_name() == name) as usize
+		})
+		.sum()
+}
+
+/// Reports a failed assertion at the location of the running script
+fn fail(ctx: &mut KernelContext, message: String) {
+	report_err!(
+		&mut ctx.reports,
+		ctx.location.source(),
+		"Assertion Failed".into(),
+		span(ctx.location.range.clone(), message)
+	);
+}
+
+/// Gets the assertion bindings, exported as `nml.assert`
+///
+/// Assertions don't stop the script, failures are reported as errors. Elements and variables are
+/// checked as they are when the assertion runs, i.e only the content before the script is visible.
+pub fn bindings(lua: &Lua) -> Vec<Binding<'_>> {
+	let mut bindings = vec![];
+
+	bindings.push(Binding::new(
+		"element_count",
+		"(name: string, expected: integer)",
+		"Asserts that the document contains `expected` elements named `name`",
+		lua.create_function(|_, (name, expected): (String, usize)| {
+			CTX.with_borrow_mut(|ctx| {
+				ctx.as_mut().map(|ctx| {
+					let count = count_elements(&ctx.document.content().borrow(), name.as_str());
+					if count != expected {
+						let colors = ctx.state.parser.colors();
+						let message = format!(
+							"Expected {} `{}` elements, found {}",
+							expected.fg(colors.info),
+							name.as_str().fg(colors.highlight),
+							count.fg(colors.info)
+						);
+						fail(ctx, message);
+					}
+				})
+			});
+			Ok(())
+		})
+		.unwrap(),
+	));
+
+	bindings.push(Binding::new(
+		"variable",
+		"(name: string, expected: string)",
+		"Asserts that variable `name` has value `expected`",
+		lua.create_function(|_, (name, expected): (String, String)| {
+			CTX.with_borrow_mut(|ctx| {
+				ctx.as_mut().map(|ctx| {
+					let value = ctx
+						.document
+						.get_variable(name.as_str())
+						.map(|var| var.to_string());
+					if value.as_ref() != Some(&expected) {
+						let colors = ctx.state.parser.colors();
+						let message = format!(
+							"Expected variable `{}` to be `{}`, found {}",
+							name.as_str().fg(colors.highlight),
+							expected.as_str().fg(colors.info),
+							value.map_or("no variable".to_string(), |value| format!("`{value}`"))
+						);
+						fail(ctx, message);
+					}
+				})
+			});
+			Ok(())
+		})
+		.unwrap(),
+	));
+
+	bindings.push(Binding::new(
+		"equal",
+		"(actual: any, expected: any, message: string?)",
+		"Asserts that `actual` and `expected` are equal once converted to strings",
+		lua.create_function(
+			|_, (actual, expected, message): (Value, Value, Option<String>)| {
+				if actual.equals(&expected)? {
+					return Ok(());
+				}
+				let (actual, expected) = (actual.to_string()?, expected.to_string()?);
+				CTX.with_borrow_mut(|ctx| {
+					ctx.as_mut().map(|ctx| {
+						let colors = ctx.state.parser.colors();
+						let mut report = format!(
+							"Expected `{}`, found `{}`",
+							expected.as_str().fg(colors.info),
+							actual.as_str().fg(colors.info)
+						);
+						if let Some(message) = message {
+							report = format!("{message}: {report}");
+						}
+						fail(ctx, report);
+					})
+				});
+				Ok(())
+			},
+		)
+		.unwrap(),
+	));
+
+	bindings
+}
@@ -1,17 +1,24 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
 
 use graphviz_rust::attributes::overlap_scaling;
+use mlua::Function;
 use mlua::IntoLua;
 use mlua::Lua;
 use mlua::Table;
 
 use crate::document::document::Document;
+use crate::elements::conditional::condition::compile_flags;
+use crate::elements::conditional::condition::compile_profile;
+use crate::elements::conditional::condition::compile_target;
 use crate::parser::parser::Parser;
 use crate::parser::parser::ParserState;
 use crate::parser::reports::Report;
 use crate::parser::source::Token;
 
+use super::assert;
+
 /// Redirected data from lua execution
 pub struct KernelRedirect {
 	/// Message source e.g print()
@@ -48,6 +55,47 @@ thread_local! {
 	pub static CTX: RefCell<Option<&'static mut KernelContext<'static, 'static, 'static>>> = const { RefCell::new(None) };
 }
 
+/// Function exported to lua, see [`crate::parser::rule::Rule::register_bindings`]
+pub struct Binding<'lua> {
+	/// Name of the function in its table
+	pub name: &'static str,
+	/// Parameters and return type of the function, e.g `(name: string): string?`
+	pub signature: &'static str,
+	/// Description of the function
+	pub doc: &'static str,
+	/// The exported function
+	pub function: Function<'lua>,
+}
+
+impl<'lua> Binding<'lua> {
+	pub fn new(
+		name: &'static str,
+		signature: &'static str,
+		doc: &'static str,
+		function: Function<'lua>,
+	) -> Self {
+		Self {
+			name,
+			signature,
+			doc,
+			function,
+		}
+	}
+}
+
+/// Documentation of a registered [`Binding`], see `nml.bindings()` and `nml.help()`
+struct BindingDoc {
+	/// Full name of the function, e.g `nml.section.push`
+	name: String,
+	signature: &'static str,
+	doc: &'static str,
+}
+
+impl BindingDoc {
+	/// Gets the help text of the function: its signature followed by its description
+	fn help(&self) -> String { format!("{}{}\n{}", self.name, self.signature, self.doc) }
+}
+
 #[derive(Debug)]
 pub struct Kernel {
 	lua: Lua,
@@ -63,15 +111,40 @@ impl Kernel {
 				.set("tables", lua.create_table().unwrap())
 				.unwrap();
 
+			// Documentation of the registered bindings
+			let docs: Rc<RefCell<Vec<BindingDoc>>> = Rc::default();
+			let register = |table: &Table, path: &str, binding: Binding| {
+				docs.borrow_mut().push(BindingDoc {
+					name: format!("{path}.{}", binding.name),
+					signature: binding.signature,
+					doc: binding.doc,
+				});
+				table.set(binding.name, binding.function).unwrap();
+			};
+
 			for rule in parser.rules() {
 				let table = lua.create_table().unwrap();
 				// TODO: Export this so we can check for duplicate rules based on this name
 				let name = rule.name().to_lowercase().replace(' ', "_");
-				for (fun_name, fun) in rule.register_bindings(&lua) {
-					table.set(fun_name, fun).unwrap();
+				for binding in rule.register_bindings(&lua) {
+					register(&table, format!("nml.{name}").as_str(), binding);
 				}
 				nml_table.set(name, table).unwrap();
 			}
+
+			// Assertions
+			let table = lua.create_table().unwrap();
+			for binding in assert::bindings(&lua) {
+				register(&table, "nml.assert", binding);
+			}
+			nml_table.set("assert", table).unwrap();
+
+			// Compilation settings and introspection
+			for binding in Self::bindings(&lua, docs.clone()) {
+				register(&nml_table, "nml", binding);
+			}
+			docs.borrow_mut().sort_by(|a, b| a.name.cmp(&b.name));
+
 			lua.globals().set("nml", nml_table).unwrap();
 		}
 
@@ -96,6 +169,92 @@ impl Kernel {
 		Self { lua }
 	}
 
+	/// Gets the bindings exported directly in the `nml` table
+	///
+	/// `docs` is the documentation of every registered binding, used by `nml.bindings()` and
+	/// `nml.help()`.
+	fn bindings(lua: &Lua, docs: Rc<RefCell<Vec<BindingDoc>>>) -> Vec<Binding<'_>> {
+		let mut bindings = vec![];
+
+		bindings.push(Binding::new(
+			"target",
+			"(): string?",
+			"Gets the compilation target, e.g `html`",
+			lua.create_function(|_, ()| {
+				Ok(CTX.with_borrow(|ctx| {
+					ctx.as_ref().and_then(|ctx| compile_target(ctx.document))
+				}))
+			})
+			.unwrap(),
+		));
+
+		bindings.push(Binding::new(
+			"profile",
+			"(): string?",
+			"Gets the compilation profile",
+			lua.create_function(|_, ()| {
+				Ok(CTX.with_borrow(|ctx| {
+					ctx.as_ref().and_then(|ctx| compile_profile(ctx.document))
+				}))
+			})
+			.unwrap(),
+		));
+
+		bindings.push(Binding::new(
+			"flags",
+			"(): table",
+			"Gets the list of compilation flags",
+			lua.create_function(|_, ()| {
+				Ok(CTX.with_borrow(|ctx| {
+					ctx.as_ref()
+						.map(|ctx| compile_flags(ctx.document))
+						.unwrap_or_default()
+				}))
+			})
+			.unwrap(),
+		));
+
+		let list = docs.clone();
+		bindings.push(Binding::new(
+			"bindings",
+			"(): table",
+			"Lists the functions exported to lua sorted by name, as tables with keys `name`, \
+			 `signature` and `doc`",
+			lua.create_function(move |lua, ()| {
+				list.borrow()
+					.iter()
+					.map(|doc| {
+						let table = lua.create_table()?;
+						table.set("name", doc.name.as_str())?;
+						table.set("signature", format!("{}{}", doc.name, doc.signature))?;
+						table.set("doc", doc.doc)?;
+						Ok(table)
+					})
+					.collect::<mlua::Result<Vec<_>>>()
+			})
+			.unwrap(),
+		));
+
+		bindings.push(Binding::new(
+			"help",
+			"(name: string?): string?",
+			"Gets the signature and description of function `name` (e.g `nml.section.push`), or of \
+			 every function when `name` is unset",
+			lua.create_function(move |_, name: Option<String>| {
+				let help = docs
+					.borrow()
+					.iter()
+					.filter(|doc| name.as_ref().is_none_or(|name| &doc.name == name))
+					.map(BindingDoc::help)
+					.collect::<Vec<_>>();
+				Ok((!help.is_empty()).then(|| help.join("\n\n")))
+			})
+			.unwrap(),
+		));
+
+		bindings
+	}
+
 	/// Runs a procedure with a context
 	///
 	/// This is the only way lua code shoule be ran, because exported
@@ -104,12 +263,17 @@ impl Kernel {
 	where
 		F: FnOnce(&Lua) -> T,
 	{
+		/// Clears [`CTX`] once the procedure returns or panics, so that it never outlives `context`
+		struct ContextGuard;
+
+		impl Drop for ContextGuard {
+			fn drop(&mut self) { CTX.set(None); }
+		}
+
 		// Redirects
 		CTX.set(Some(unsafe { std::mem::transmute(context) }));
-		let ret = f(&self.lua);
-		CTX.set(None);
-
-		ret
+		let _guard = ContextGuard;
+		f(&self.lua)
 	}
 
 	/// Exports a table to lua
@@ -140,3 +304,33 @@ impl KernelHolder {
 		self.kernels.insert(kernel_name, kernel);
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::panic::catch_unwind;
+	use std::panic::AssertUnwindSafe;
+
+	use crate::document::langdocument::LangDocument;
+	use crate::parser::langparser::LangParser;
+	use crate::parser::source::Source;
+	use crate::parser::source::SourceFile;
+
+	use super::*;
+
+	#[test]
+	fn context_panic() {
+		let source = Rc::new(SourceFile::with_content("".into(), "".into(), None)) as Rc<dyn Source>;
+		let parser = LangParser::default();
+		let state = ParserState::new(&parser, None);
+		let doc = LangDocument::new(source.clone(), None);
+		let kernel = Kernel::new(&parser);
+
+		let mut ctx = KernelContext::new(Token::new(0..0, source), &state, &doc);
+		let result = catch_unwind(AssertUnwindSafe(|| {
+			kernel.run_with_context(&mut ctx, |_| panic!("binding panicked"))
+		}));
+		assert!(result.is_err());
+		// The context isn't referenced after the panic
+		assert!(CTX.with_borrow(Option::is_none));
+	}
+}
@@ -1 +1,3 @@
+pub mod assert;
 pub mod kernel;
+pub mod repl;
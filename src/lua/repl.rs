@@ -0,0 +1,216 @@
+use std::io::BufRead;
+use std::io::Write;
+use std::rc::Rc;
+
+use mlua::Lua;
+use mlua::Value;
+
+use crate::compiler::compiler::Compiler;
+use crate::compiler::compiler::Target;
+use crate::compiler::exec::ExecPolicy;
+use crate::compiler::exec::EXEC_VARIABLE;
+use crate::compiler::process::defines_document;
+use crate::document::document::Document;
+use crate::elements::conditional::condition::TARGET_VARIABLE;
+use crate::parser::langparser::LangParser;
+use crate::parser::parser::ParseMode;
+use crate::parser::parser::Parser;
+use crate::parser::parser::ParserState;
+use crate::parser::reports::Report;
+use crate::parser::source::Source;
+use crate::parser::source::SourceFile;
+use crate::parser::source::Token;
+use crate::parser::source::VirtualSource;
+
+use super::kernel::Kernel;
+use super::kernel::KernelContext;
+
+/// Prompt displayed before a new chunk
+const PROMPT: &str = "> ";
+
+/// Prompt displayed while a chunk is incomplete
+const CONTINUATION_PROMPT: &str = ">> ";
+
+/// Runs a chunk, expressions are evaluated and their value is returned
+///
+/// Chunks that can't be parsed as an expression are executed as statements.
+fn run_chunk<'lua>(lua: &'lua Lua, code: &str) -> mlua::Result<Value<'lua>> {
+	match lua.load(format!("return {code}")).set_name("=repl").eval() {
+		Err(mlua::Error::SyntaxError { .. }) => {
+			lua.load(code).set_name("=repl").exec()?;
+			Ok(Value::Nil)
+		}
+		result => result,
+	}
+}
+
+/// Runs an interactive Lua session on a document
+///
+/// The document at `path` (or an empty document) is parsed first, chunks read from `input` are
+/// then run by the `main` kernel of the document, so that the functions it defines and the `nml.*`
+/// bindings are available. After each chunk, the value of the chunk, the output of `print()` and
+/// the compiled elements pushed by the chunk are written to `output`.
+pub fn repl<R: BufRead, W: Write>(
+	path: Option<&str>,
+	target: Target,
+	exec: &ExecPolicy,
+	input: R,
+	output: &mut W,
+) -> Result<(), String> {
+	let source: Rc<dyn Source> = match path {
+		Some(path) => Rc::new(
+			SourceFile::new(path.to_string(), None)
+				.map_err(|err| format!("Unable to read `{path}`: {err}"))?,
+		),
+		None => Rc::new(SourceFile::with_content(String::new(), String::new(), None)),
+	};
+	let defines = defines_document(&[
+		(TARGET_VARIABLE.to_string(), target.name().to_string()),
+		(EXEC_VARIABLE.to_string(), exec.name().to_string()),
+	]);
+
+	let parser = LangParser::default();
+	let (doc, state) = parser.parse(
+		ParserState::new(&parser, None),
+		source,
+		Some(&defines),
+		ParseMode::default(),
+	);
+	if state.shared.kernels.borrow().get("main").is_none() {
+		state
+			.shared
+			.kernels
+			.borrow_mut()
+			.insert("main".to_string(), Kernel::new(&parser));
+	}
+
+	// The document outlives the session
+	let document: &dyn Document = unsafe { std::mem::transmute(&*doc as &dyn Document) };
+
+	let write_err = |err: std::io::Error| format!("Unable to write output: {err}");
+	let mut lines = input.lines();
+	let mut count = 0;
+	loop {
+		// Read a complete chunk
+		write!(output, "{PROMPT}").map_err(write_err)?;
+		output.flush().map_err(write_err)?;
+		let mut code = String::new();
+		let value = loop {
+			let Some(line) = lines.next() else {
+				return Ok(());
+			};
+			code += line
+				.map_err(|err| format!("Unable to read input: {err}"))?
+				.as_str();
+			code.push('\n');
+
+			count += 1;
+			let source = Rc::new(VirtualSource::new(
+				Token::new(0..0, doc.source()),
+				format!(":LUA:repl#{count}"),
+				code.clone(),
+			)) as Rc<dyn Source>;
+			let mut ctx = KernelContext::new(Token::new(0..code.len(), source), &state, document);
+			let elements = doc.content().borrow().len();
+			let kernels = state.shared.kernels.borrow();
+			let result = kernels
+				.get("main")
+				.unwrap()
+				.run_with_context(&mut ctx, |lua| {
+					run_chunk(lua, code.as_str()).map(|value| match value {
+						Value::Nil => None,
+						value => Some(
+							lua.coerce_string(value.clone())
+								.ok()
+								.flatten()
+								.and_then(|s| s.to_str().ok().map(str::to_string))
+								.unwrap_or(format!("{value:?}")),
+						),
+					})
+				});
+			drop(kernels);
+
+			match result {
+				Err(mlua::Error::SyntaxError {
+					incomplete_input: true,
+					..
+				}) => {
+					write!(output, "{CONTINUATION_PROMPT}").map_err(write_err)?;
+					output.flush().map_err(write_err)?;
+				}
+				result => break (result, ctx, elements),
+			}
+		};
+
+		// Results
+		let (result, ctx, elements) = value;
+		for redirect in &ctx.redirects {
+			writeln!(output, "{}", redirect.content).map_err(write_err)?;
+		}
+		if !ctx.reports.is_empty() {
+			write!(
+				output,
+				"{}",
+				Report::reports_to_string(parser.colors(), ctx.reports)
+			)
+			.map_err(write_err)?;
+		}
+		match result {
+			Ok(Some(value)) => writeln!(output, "{value}").map_err(write_err)?,
+			Ok(None) => {}
+			Err(err) => writeln!(output, "{err}").map_err(write_err)?,
+		}
+
+		// Compiled elements
+		let compiler = Compiler::new(target, None, exec);
+		for elem in doc.content().borrow().iter().skip(elements) {
+			match elem.compile(&compiler, &*doc, 0) {
+				Ok(compiled) if !compiled.is_empty() => {
+					writeln!(output, "{compiled}").map_err(write_err)?
+				}
+				Ok(_) => {}
+				Err(err) => writeln!(output, "Unable to compile {}: {err}", elem.element_name())
+					.map_err(write_err)?,
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn session() {
+		let input = r#"
+1 + 2
+x = 5
+print("x is " .. x)
+function twice(n)
+	return n * 2
+end
+twice(x)
+nml.raw.push("block", "<hr>")
+error("oops")
+"#;
+		let mut output = vec![];
+		repl(
+			None,
+			Target::HTML,
+			&ExecPolicy::default(),
+			input.as_bytes(),
+			&mut output,
+		)
+		.unwrap();
+
+		let output = String::from_utf8(output).unwrap();
+		let results = output
+			.split(PROMPT)
+			.map(str::trim)
+			.filter(|out| !out.is_empty())
+			.collect::<Vec<_>>();
+		// Continuation prompts are split as `>`
+		assert_eq!(results[..6], ["3", "x is 5", ">", ">", "10", "<hr>"]);
+		assert!(results[6].starts_with("runtime error: repl:1: oops"));
+	}
+}
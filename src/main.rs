@@ -1,25 +1,61 @@
-mod cache;
-mod compiler;
-mod document;
-mod elements;
-mod lsp;
-mod lua;
-mod parser;
-
+use std::collections::HashMap;
 use std::env;
 use std::io::BufWriter;
 use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
 use std::process::ExitCode;
+use std::rc::Rc;
 
-use compiler::compiler::Target;
-use compiler::navigation::create_navigation;
 use getopts::Options;
-use walkdir::WalkDir;
+use nml_core::cache::cache;
+use nml_core::compiler::api::Settings as CompileSettings;
+use nml_core::compiler::assets::assets_directory;
+use nml_core::compiler::assets::compiled_assets;
+use nml_core::compiler::assets::copy_assets;
+use nml_core::compiler::assets::ASSETS_DIRECTORY;
+use nml_core::compiler::audit::audit;
+use nml_core::compiler::compiler::Target;
+use nml_core::compiler::exec::ExecPolicy;
+use nml_core::compiler::graph::document_graph;
+use nml_core::compiler::i18n::translations;
+use nml_core::compiler::navigation::create_navigation;
+use nml_core::compiler::numbering::SectionNumbering;
+use nml_core::compiler::process;
+use nml_core::compiler::split::split_pages;
+use nml_core::compiler::standalone::inline_resources;
+use nml_core::compiler::store::write_store;
+use nml_core::lua::repl::repl;
+use nml_core::parser::codes::ReportCode;
+use nml_core::parser::langparser::LangParser;
+use nml_core::parser::parser::ParserState;
+use nml_core::parser::parser::ReportColors;
+use nml_core::parser::property::property_docs;
+use nml_core::parser::reports::Report;
+use nml_core::parser::source::SourceFile;
+use nml_core::project::format::format_path;
+use nml_core::project::init::init_project;
+use nml_core::project::inputs::collect_inputs;
+use nml_core::project::settings::ProjectSettings;
+use nml_core::project::settings::VersionSettings;
+use nml_core::project::settings::PROJECT_FILE;
+use nml_core::project::testing::run_tests;
+use nml_core::project::testing::TESTS_DIRECTORY;
+use nml_core::project::versions::checkout_tag;
+use nml_core::project::versions::version_defines;
+use nml_core::project::versions::versions_manifest;
+use nml_core::project::versions::VERSIONS_MANIFEST;
+
+#[cfg(test)]
+mod tempdir;
 
 extern crate getopts;
 
 fn print_usage(program: &str, opts: Options) {
-	let brief = format!("Usage: {} -i PATH -o PATH [options]", program);
+	let brief = format!(
+		"Usage: {0} -i PATH -o PATH [options]\n       {0} init [PATH]\n       {0} repl [PATH]\n       {0} test [PATH]",
+		program
+	);
 	print!("{}", opts.usage(&brief));
 }
 
@@ -37,39 +73,121 @@ NML version: 0.4\n"
 	);
 }
 
-fn main() -> ExitCode {
-	let args: Vec<String> = env::args().collect();
-	let program = args[0].clone();
+/// Prints the properties of every element, grouped by element
+fn print_properties() {
+	let parser = LangParser::default();
+	let state = ParserState::new(&parser, None);
 
-	let mut opts = Options::new();
-	opts.optopt("i", "input", "Input path", "PATH");
-	opts.optopt("o", "output", "Output path", "PATH");
-	opts.optopt("d", "database", "Cache database location", "PATH");
-	opts.optflag("", "force-rebuild", "Force rebuilding of cached documents");
-	opts.optmulti("z", "debug", "Debug options", "[ast,ref,var]");
-	opts.optflag("h", "help", "Print this help menu");
-	opts.optflag("v", "version", "Print program version and licenses");
+	let mut element = None;
+	for doc in property_docs(&state) {
+		if element.as_ref() != Some(&doc.element) {
+			println!("{}:", doc.element);
+			element = Some(doc.element.clone());
+		}
+		print!(" - {}", doc.name);
+		if doc.primary {
+			print!(" (primary)");
+		}
+		print!(": {}", doc.description);
+		if let Some(default) = &doc.default {
+			print!(" (Default: `{default}`)");
+		}
+		println!();
+	}
+}
 
-	let matches = match opts.parse(&args[1..]) {
-		Ok(m) => m,
-		Err(f) => {
-			panic!("{}", f.to_string())
+/// Reads and validates the project file at `path`, printing the problems found
+fn load_settings(path: &Path) -> Option<ProjectSettings> {
+	let source = match SourceFile::new(path.to_string_lossy().to_string(), None) {
+		Ok(source) => source,
+		Err(err) => {
+			eprintln!("Unable to read project file `{}`: {err}", path.display());
+			return None;
 		}
 	};
-	if matches.opt_present("v") {
-		print_version();
-		return ExitCode::SUCCESS;
+
+	match ProjectSettings::from_source(Rc::new(source)) {
+		Ok(settings) => Some(settings),
+		Err(reports) => {
+			Report::reports_to_stdout(&ReportColors::with_colors(), reports);
+			None
+		}
 	}
-	if matches.opt_present("h") {
-		print_usage(&program, opts);
-		return ExitCode::SUCCESS;
+}
+
+/// Writes an output page
+///
+/// The page is first written to a temporary file, then moved to `path`, so that an interrupted
+/// build never leaves a partially written page. Pages are not written when the existing file has
+/// the same content. Standalone pages have their resources inlined, see
+/// [`inline_resources`].
+fn write_page(path: &str, parts: &[&str], standalone: bool) -> Result<(), String> {
+	let inlined;
+	let parts = if standalone {
+		let base = Path::new(path).parent().unwrap_or(Path::new(""));
+		let warnings;
+		(inlined, warnings) = inline_resources(parts.concat().as_str(), base);
+		for warning in warnings {
+			eprintln!("{warning}");
+		}
+		&[inlined.as_str()]
+	} else {
+		parts
+	};
+
+	// Keep unchanged pages, so that their modification time is preserved
+	if std::fs::read(path).is_ok_and(|existing| {
+		existing.len() == parts.iter().map(|part| part.len()).sum::<usize>()
+			&& existing == parts.concat().as_bytes()
+	}) {
+		return Ok(());
 	}
-	if !matches.opt_present("i") || !matches.opt_present("o") {
-		print_usage(&program, opts);
-		return ExitCode::FAILURE;
+
+	// Pages can be output to subdirectories, e.g one per language
+	if let Some(parent) = Path::new(path)
+		.parent()
+		.filter(|parent| !parent.as_os_str().is_empty())
+	{
+		std::fs::create_dir_all(parent)
+			.map_err(|err| format!("Unable to create directory `{}`: {err}", parent.display()))?;
 	}
 
-	let input = matches.opt_str("i").unwrap();
+	let tmp_path = format!("{path}.tmp");
+	let write = || -> std::io::Result<()> {
+		let mut writer = BufWriter::new(std::fs::File::create(&tmp_path)?);
+		for part in parts {
+			writer.write_all(part.as_bytes())?;
+		}
+		writer.flush()?;
+		std::fs::rename(&tmp_path, path)
+	};
+
+	write().map_err(|err| {
+		let _ = std::fs::remove_file(&tmp_path);
+		format!("Unable to write output `{path}`: {err}")
+	})
+}
+
+/// Options shared by the builds of a project
+struct BuildOptions {
+	target: Target,
+	db_path: Option<String>,
+	force_rebuild: bool,
+	debug_opts: Vec<String>,
+	standalone: bool,
+	numbering: SectionNumbering,
+	exec: ExecPolicy,
+	graph: Option<String>,
+	languages: Vec<String>,
+}
+
+/// Builds `input` (file or directory) to `output`
+fn build(
+	options: &BuildOptions,
+	input: String,
+	output: String,
+	defines: &[(String, String)],
+) -> ExitCode {
 	let input_meta = match std::fs::metadata(&input) {
 		Ok(meta) => meta,
 		Err(e) => {
@@ -77,7 +195,6 @@ fn main() -> ExitCode {
 			return ExitCode::FAILURE;
 		}
 	};
-	let output = matches.opt_str("o").unwrap();
 	if input_meta.is_dir() {
 		// Create ouput directories
 		if !std::fs::exists(&output).unwrap_or(false) {
@@ -116,93 +233,28 @@ fn main() -> ExitCode {
 		}
 	}
 
-	let db_path = match matches.opt_str("d") {
-		Some(db) => {
-			if std::fs::exists(&db).unwrap_or(false) {
-				match std::fs::canonicalize(&db)
-					.map_err(|err| format!("Failed to cannonicalize database path `{db}`: {err}"))
-					.as_ref()
-					.map(|path| path.to_str())
-				{
-					Ok(Some(path)) => Some(path.to_string()),
-					Ok(None) => {
-						eprintln!("Failed to transform path to string `{db}`");
-						return ExitCode::FAILURE;
-					}
-					Err(err) => {
-						eprintln!("{err}");
-						return ExitCode::FAILURE;
-					}
-				}
-			} else
-			// Cannonicalize parent path, then append the database name
-			{
-				match std::fs::canonicalize(".")
-					.map_err(|err| {
-						format!("Failed to cannonicalize database parent path `{db}`: {err}")
-					})
-					.map(|path| path.join(&db))
-					.as_ref()
-					.map(|path| path.to_str())
-				{
-					Ok(Some(path)) => Some(path.to_string()),
-					Ok(None) => {
-						eprintln!("Failed to transform path to string `{db}`");
-						return ExitCode::FAILURE;
-					}
-					Err(err) => {
-						eprintln!("{err}");
-						return ExitCode::FAILURE;
-					}
-				}
-			}
-		}
-		None => None,
-	};
-	let force_rebuild = matches.opt_present("force-rebuild");
-	let debug_opts = matches.opt_strs("z");
-
 	let mut files = vec![];
 	if input_meta.is_dir() {
-		if db_path.is_none() {
+		if options.db_path.is_none() {
 			eprintln!("Directory mode requires a database (-d)");
 			return ExitCode::FAILURE;
 		}
 
-		for entry in WalkDir::new(&input) {
-			if let Err(err) = entry {
-				eprintln!("Failed to recursively walk over input directory: {err}");
-				return ExitCode::FAILURE;
-			}
-			match entry.as_ref().unwrap().metadata() {
-				Ok(meta) => {
-					if !meta.is_file() {
-						continue;
-					}
-				}
-				Err(e) => {
-					eprintln!("Faield to get metadata for `{entry:#?}`: {e}");
-					return ExitCode::FAILURE;
+		files = match collect_inputs(input.as_ref()) {
+			Ok((files, skipped)) => {
+				for message in skipped {
+					println!("{message}");
 				}
+				files
 			}
-
-			let path = match entry.as_ref().unwrap().path().to_str() {
-				Some(path) => path.to_string(),
-				None => {
-					eprintln!("Faield to convert input file `{entry:#?}` to UTF-8");
-					return ExitCode::FAILURE;
-				}
-			};
-			if !path.ends_with(".nml") {
-				println!("Skipping '{path}'");
-				continue;
+			Err(err) => {
+				eprintln!("{err}");
+				return ExitCode::FAILURE;
 			}
-
-			files.push(std::fs::canonicalize(path).unwrap());
-		}
+		};
 	} else {
 		// Single file mode
-		files.push(std::fs::canonicalize(input).unwrap());
+		files.push(std::fs::canonicalize(&input).unwrap());
 	}
 
 	// Check that all files have a valid unicode path
@@ -214,15 +266,40 @@ fn main() -> ExitCode {
 	}
 
 	// Parse, compile using the cache
-	let processed =
-		match compiler::process::process(Target::HTML, files, &db_path, force_rebuild, &debug_opts)
-		{
-			Ok(processed) => processed,
-			Err(e) => {
-				eprintln!("{e}");
-				return ExitCode::FAILURE;
-			}
-		};
+	let con = match options
+		.db_path
+		.as_ref()
+		.map_or_else(cache::open_in_memory, cache::open)
+	{
+		Ok(con) => con,
+		Err(err) => {
+			eprintln!("Unable to open connection to the database: {err}");
+			return ExitCode::FAILURE;
+		}
+	};
+	let processed = match process::process(
+		options.target,
+		files,
+		&con,
+		options.force_rebuild,
+		defines,
+		&options.numbering,
+		&options.exec,
+		&options.debug_opts,
+	) {
+		Ok(processed) => processed,
+		Err(e) => {
+			eprintln!("{e}");
+			return ExitCode::FAILURE;
+		}
+	};
+
+	if let Some(path) = &options.graph {
+		if let Err(err) = std::fs::write(path, document_graph(&processed)) {
+			eprintln!("Unable to write graph to `{path}`: {err}");
+			return ExitCode::FAILURE;
+		}
+	}
 
 	if input_meta.is_dir()
 	// Batch mode
@@ -236,7 +313,24 @@ fn main() -> ExitCode {
 			}
 		};
 
-		// Output
+		// Orphan documents and duplicate sections
+		for warning in audit(&processed) {
+			eprintln!("{warning}");
+		}
+
+		// Missing and outdated translations
+		let translations = translations(&processed, Path::new(&input), &options.languages);
+		match translations.check(&con) {
+			Ok(warnings) => warnings.iter().for_each(|warning| eprintln!("{warning}")),
+			Err(err) => {
+				eprintln!("{err}");
+				return ExitCode::FAILURE;
+			}
+		}
+
+		// Get output pages
+		let mut outputs = vec![];
+		let mut written: HashMap<String, String> = HashMap::new();
 		for (doc, _) in &processed {
 			let out_path = match doc
 				.borrow()
@@ -250,41 +344,527 @@ fn main() -> ExitCode {
 				}
 			};
 
-			let nav = navigation.compile(Target::HTML, doc);
-			let file = std::fs::File::create(output.clone() + "/" + out_path.as_str()).unwrap();
+			let pages = split_pages(options.target, &doc.borrow(), out_path.as_str());
+			for page in &pages {
+				let input = doc.borrow().input.clone();
+				if let Some(other) = written.insert(page.path.clone(), input.clone()) {
+					eprintln!(
+						"Documents `{other}` and `{input}` both output to `{}`",
+						page.path
+					);
+					return ExitCode::FAILURE;
+				}
+			}
+			outputs.push((doc, pages));
+		}
 
-			let mut writer = BufWriter::new(file);
+		// Assets of every document
+		let assets = outputs
+			.iter()
+			.flat_map(|(doc, _)| compiled_assets(&doc.borrow()))
+			.collect::<Vec<_>>();
+		let assets_path = Path::new(&output).join(ASSETS_DIRECTORY);
+		if let Err(err) =
+			copy_assets(&assets, &assets_path).and_then(|_| write_store(&con, &assets_path))
+		{
+			eprintln!("{err}");
+			return ExitCode::FAILURE;
+		}
 
-			write!(
-				writer,
-				"{}{}{}{}",
-				doc.borrow().header,
-				nav,
-				doc.borrow().body,
-				doc.borrow().footer
-			)
-			.unwrap();
-			writer.flush().unwrap();
+		// Output
+		for (doc, pages) in outputs {
+			// Slides have no navigation bar
+			let nav = match options.target {
+				Target::SLIDES => String::new(),
+				_ => navigation.compile(options.target, doc),
+			};
+			let header = translations.header(options.target, &doc.borrow());
+			for page in pages {
+				let path = output.clone() + "/" + page.path.as_str();
+				let doc = doc.borrow();
+				if let Err(err) = write_page(
+					&path,
+					&[&header, &nav, &page.body, &doc.footer],
+					options.standalone,
+				) {
+					eprintln!("{err}");
+					return ExitCode::FAILURE;
+				}
+			}
 		}
 	} else
 	// Single file
 	{
 		for (doc, _) in &processed {
-			let file = std::fs::File::create(output.clone()).unwrap();
+			let assets_path =
+				Path::new(&output)
+					.parent()
+					.unwrap_or(Path::new(""))
+					.join(assets_directory(
+						doc.borrow()
+							.get_variable("compiler.output")
+							.map(String::as_str),
+					));
+			if let Err(err) = copy_assets(&compiled_assets(&doc.borrow()), &assets_path)
+				.and_then(|_| write_store(&con, &assets_path))
+			{
+				eprintln!("{err}");
+				return ExitCode::FAILURE;
+			}
+			for page in split_pages(options.target, &doc.borrow(), output.as_str()) {
+				let doc = doc.borrow();
+				if let Err(err) = write_page(
+					&page.path,
+					&[&doc.header, &page.body, &doc.footer],
+					options.standalone,
+				) {
+					eprintln!("{err}");
+					return ExitCode::FAILURE;
+				}
+			}
+		}
+	}
 
-			let mut writer = BufWriter::new(file);
+	ExitCode::SUCCESS
+}
 
-			write!(
-				writer,
-				"{}{}{}",
-				doc.borrow().header,
-				doc.borrow().body,
-				doc.borrow().footer
-			)
-			.unwrap();
-			writer.flush().unwrap();
+/// Builds every version of a project to a subdirectory of `output`, then writes the manifest
+/// listing the versions
+///
+/// Versions built from a git tag are extracted next to the database, so that the cache can be
+/// reused between builds.
+fn build_versions(
+	options: &BuildOptions,
+	versions: &[VersionSettings],
+	project_input: Option<&Path>,
+	output: String,
+	defines: &[(String, String)],
+) -> ExitCode {
+	let checkouts = options
+		.db_path
+		.as_ref()
+		.map_or(std::env::temp_dir().join("nml-versions"), |db_path| {
+			PathBuf::from(format!("{db_path}.versions"))
+		});
+	for version in versions {
+		if process::is_interrupted() {
+			return ExitCode::FAILURE;
+		}
+		println!("Building version `{}`", version.name);
+		let input = match (&version.input, &version.tag, project_input) {
+			(Some(input), _, _) => input.clone(),
+			(None, Some(tag), Some(project_input)) => {
+				match checkout_tag(tag, project_input, &checkouts.join(&version.name)) {
+					Ok(input) => input,
+					Err(err) => {
+						eprintln!("Unable to build version `{}`: {err}", version.name);
+						return ExitCode::FAILURE;
+					}
+				}
+			}
+			_ => {
+				eprintln!("Version `{}` has no input", version.name);
+				return ExitCode::FAILURE;
+			}
+		};
+		let status = build(
+			options,
+			input.to_string_lossy().to_string(),
+			format!("{output}/{}", version.name),
+			&version_defines(version.name.as_str(), defines),
+		);
+		if status != ExitCode::SUCCESS {
+			return status;
 		}
 	}
 
+	let names = versions
+		.iter()
+		.map(|version| version.name.as_str())
+		.collect::<Vec<_>>();
+	let path = Path::new(&output).join(VERSIONS_MANIFEST);
+	if let Err(err) = std::fs::write(&path, versions_manifest(&names)) {
+		eprintln!("Unable to write `{}`: {err}", path.display());
+		return ExitCode::FAILURE;
+	}
 	ExitCode::SUCCESS
 }
+
+fn main() -> ExitCode {
+	let args: Vec<String> = env::args().collect();
+	let program = args[0].clone();
+
+	let mut opts = Options::new();
+	opts.optopt("i", "input", "Input path", "PATH");
+	opts.optopt(
+		"c",
+		"config",
+		"Project file, defaults to `nml.toml` if present",
+		"PATH",
+	);
+	opts.optopt("o", "output", "Output path", "PATH");
+	opts.optopt("d", "database", "Cache database location", "PATH");
+	opts.optflag("", "force-rebuild", "Force rebuilding of cached documents");
+	opts.optmulti(
+		"D",
+		"define",
+		"Define a variable for all documents",
+		"NAME=VALUE",
+	);
+	opts.optmulti(
+		"",
+		"env",
+		"Define variable `env.NAME` from environment variable NAME",
+		"NAME",
+	);
+	opts.optopt(
+		"t",
+		"target",
+		"Output target, defaults to `html`",
+		"[html,slides]",
+	);
+	opts.optopt("", "profile", "Compilation profile", "NAME");
+	opts.optmulti("", "flag", "Enable a feature flag", "NAME");
+	opts.optflag(
+		"",
+		"no-exec",
+		"Do not run external commands, elements requiring them are replaced by placeholders",
+	);
+	opts.optflag(
+		"",
+		"standalone",
+		"Output self-contained pages, with stylesheets, scripts, fonts and images inlined",
+	);
+	opts.optopt(
+		"",
+		"graph",
+		"Export the graph of the documents, their references and imports as Graphviz dot to PATH",
+		"PATH",
+	);
+	opts.optmulti(
+		"z",
+		"debug",
+		"Debug options",
+		"[ast[=Name,...],ref,var,sourcemap]",
+	);
+	opts.optflag(
+		"",
+		"list-properties",
+		"Print the properties of every element",
+	);
+	opts.optflagopt(
+		"",
+		"init",
+		"Create a new project in PATH, defaults to the current directory",
+		"PATH",
+	);
+	opts.optopt(
+		"",
+		"fmt",
+		"Align the tables of the documents in PATH (file or directory), in place",
+		"PATH",
+	);
+	opts.optflag(
+		"",
+		"check-config",
+		"Validate the project file without building",
+	);
+	opts.optopt(
+		"",
+		"explain",
+		"Print the documentation of a report code",
+		"CODE",
+	);
+	opts.optflag("h", "help", "Print this help menu");
+	opts.optflag("v", "version", "Print program version and licenses");
+
+	let matches = match opts.parse(&args[1..]) {
+		Ok(m) => m,
+		Err(f) => {
+			panic!("{}", f.to_string())
+		}
+	};
+	if matches.opt_present("v") {
+		print_version();
+		return ExitCode::SUCCESS;
+	}
+	if matches.opt_present("h") {
+		print_usage(&program, opts);
+		return ExitCode::SUCCESS;
+	}
+	if matches.opt_present("list-properties") {
+		print_properties();
+		return ExitCode::SUCCESS;
+	}
+	if let Some(code) = matches.opt_str("explain") {
+		return match ReportCode::find(code.as_str()) {
+			Some(code) => {
+				println!("{}: {}\n\n{}", code.code, code.message, code.explanation);
+				ExitCode::SUCCESS
+			}
+			None => {
+				eprintln!("Unknown report code `{code}`");
+				ExitCode::FAILURE
+			}
+		};
+	}
+	if matches.opt_present("init") || matches.free.first().is_some_and(|arg| arg == "init") {
+		let path = matches
+			.opt_str("init")
+			.or(matches.free.get(1).cloned())
+			.unwrap_or(".".into());
+		return match init_project(path.as_ref()) {
+			Ok(created) => {
+				for file in created {
+					println!("Created {}", file.display());
+				}
+				ExitCode::SUCCESS
+			}
+			Err(err) => {
+				eprintln!("{err}");
+				ExitCode::FAILURE
+			}
+		};
+	}
+
+	// Project settings
+	let settings_path = matches
+		.opt_str("c")
+		.map(PathBuf::from)
+		.or(ProjectSettings::find());
+	let mut settings = match &settings_path {
+		Some(path) => match load_settings(path) {
+			Some(settings) => settings,
+			None => return ExitCode::FAILURE,
+		},
+		None => ProjectSettings::default(),
+	};
+	if matches.opt_present("check-config") {
+		return match settings_path {
+			Some(path) => {
+				println!("Project file `{}` is valid", path.display());
+				ExitCode::SUCCESS
+			}
+			None => {
+				eprintln!("No project file found, expected `{PROJECT_FILE}`");
+				ExitCode::FAILURE
+			}
+		};
+	}
+	let target = match matches.opt_str("target").as_deref() {
+		None | Some("html") => Target::HTML,
+		Some("slides") => Target::SLIDES,
+		Some(name) => {
+			eprintln!("Unknown target `{name}`, expected `html` or `slides`");
+			return ExitCode::FAILURE;
+		}
+	};
+	// Options given on the command line take precedence over the project file
+	if let Some(profile) = matches.opt_str("profile") {
+		settings.profile = Some(profile);
+	}
+	settings.flags.extend(matches.opt_strs("flag"));
+	let mut exec = settings.exec.clone();
+	exec.disabled = matches.opt_present("no-exec");
+	// Defined variables
+	let mut defines = settings.defines();
+	for define in matches.opt_strs("D") {
+		match define.split_once('=') {
+			Some((name, value)) if !name.trim().is_empty() => {
+				// Override definitions from the project file
+				defines.retain(|(defined, _)| defined != name.trim());
+				defines.push((name.trim().to_string(), value.to_string()))
+			}
+			_ => {
+				eprintln!("Invalid variable definition `{define}`, expected `NAME=VALUE`");
+				return ExitCode::FAILURE;
+			}
+		}
+	}
+	for name in matches.opt_strs("env") {
+		match env::var(&name) {
+			Ok(value) => defines.push((format!("env.{name}"), value)),
+			Err(err) => eprintln!("Unable to get environment variable `{name}`: {err}"),
+		}
+	}
+
+	if let Some(path) = matches.opt_str("fmt") {
+		return match format_path(path.as_ref(), &defines) {
+			Ok(modified) => {
+				for file in modified {
+					println!("Formatted {}", file.display());
+				}
+				ExitCode::SUCCESS
+			}
+			Err(err) => {
+				eprintln!("{err}");
+				ExitCode::FAILURE
+			}
+		};
+	}
+	if matches.free.first().is_some_and(|arg| arg == "test") {
+		let root = matches.free.get(1).map_or(TESTS_DIRECTORY, String::as_str);
+		let settings = CompileSettings {
+			target,
+			defines,
+			exec,
+			database: None,
+		};
+		return match run_tests(root.as_ref(), &settings) {
+			Ok(true) => ExitCode::SUCCESS,
+			Ok(false) => ExitCode::FAILURE,
+			Err(err) => {
+				eprintln!("{err}");
+				ExitCode::FAILURE
+			}
+		};
+	}
+	if matches.free.first().is_some_and(|arg| arg == "repl") {
+		let stdin = std::io::stdin();
+		return match repl(
+			matches.free.get(1).map(String::as_str),
+			target,
+			&exec,
+			stdin.lock(),
+			&mut std::io::stdout(),
+		) {
+			Ok(()) => ExitCode::SUCCESS,
+			Err(err) => {
+				eprintln!("{err}");
+				ExitCode::FAILURE
+			}
+		};
+	}
+
+	let path_setting = |opt: &str, setting: &Option<PathBuf>| {
+		matches.opt_str(opt).or(setting
+			.as_ref()
+			.map(|path| path.to_string_lossy().to_string()))
+	};
+
+	let db_path = match path_setting("d", &settings.database) {
+		Some(db) => {
+			if std::fs::exists(&db).unwrap_or(false) {
+				match std::fs::canonicalize(&db)
+					.map_err(|err| format!("Failed to cannonicalize database path `{db}`: {err}"))
+					.as_ref()
+					.map(|path| path.to_str())
+				{
+					Ok(Some(path)) => Some(path.to_string()),
+					Ok(None) => {
+						eprintln!("Failed to transform path to string `{db}`");
+						return ExitCode::FAILURE;
+					}
+					Err(err) => {
+						eprintln!("{err}");
+						return ExitCode::FAILURE;
+					}
+				}
+			} else
+			// Cannonicalize parent path, then append the database name
+			{
+				match std::fs::canonicalize(".")
+					.map_err(|err| {
+						format!("Failed to cannonicalize database parent path `{db}`: {err}")
+					})
+					.map(|path| path.join(&db))
+					.as_ref()
+					.map(|path| path.to_str())
+				{
+					Ok(Some(path)) => Some(path.to_string()),
+					Ok(None) => {
+						eprintln!("Failed to transform path to string `{db}`");
+						return ExitCode::FAILURE;
+					}
+					Err(err) => {
+						eprintln!("{err}");
+						return ExitCode::FAILURE;
+					}
+				}
+			}
+		}
+		None => None,
+	};
+	let options = BuildOptions {
+		target,
+		db_path,
+		force_rebuild: matches.opt_present("force-rebuild"),
+		debug_opts: matches.opt_strs("z"),
+		standalone: matches.opt_present("standalone") || settings.standalone,
+		numbering: settings.numbering.clone(),
+		exec,
+		graph: matches.opt_str("graph"),
+		languages: settings.i18n.languages.clone(),
+	};
+
+	// Finish the current document on interruption, exit immediately on the second one
+	if let Err(err) = ctrlc::set_handler(|| {
+		if process::is_interrupted() {
+			std::process::exit(130);
+		}
+		eprintln!("Interrupted, stopping after the current document...");
+		process::interrupt();
+	}) {
+		eprintln!("Unable to set interruption handler: {err}");
+	}
+
+	// Versions of the project, unless an input is given on the command line
+	if !settings.versions.is_empty() && !matches.opt_present("i") {
+		let Some(output) = path_setting("o", &settings.output) else {
+			print_usage(&program, opts);
+			return ExitCode::FAILURE;
+		};
+		return build_versions(
+			&options,
+			&settings.versions,
+			settings.input.as_deref(),
+			output,
+			&defines,
+		);
+	}
+
+	let (input, output) = match (
+		path_setting("i", &settings.input),
+		path_setting("o", &settings.output),
+	) {
+		(Some(input), Some(output)) => (input, output),
+		_ => {
+			print_usage(&program, opts);
+			return ExitCode::FAILURE;
+		}
+	};
+	build(&options, input, output, &defines)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use std::time::SystemTime;
+
+	use super::*;
+	use crate::tempdir::TempDir;
+
+	#[test]
+	fn unchanged_page() {
+		let root = TempDir::new("write-page");
+		let path = root.join("page.html").to_string_lossy().to_string();
+		let modified = || std::fs::metadata(&path).unwrap().modified().unwrap();
+
+		write_page(&path, &["<p>", "page", "</p>"], false).unwrap();
+		let past = SystemTime::now() - Duration::from_secs(3600);
+		std::fs::File::options()
+			.write(true)
+			.open(&path)
+			.unwrap()
+			.set_modified(past)
+			.unwrap();
+
+		// Same content, the page is kept
+		write_page(&path, &["<p>page", "</p>"], false).unwrap();
+		assert_eq!(modified(), past);
+
+		write_page(&path, &["<p>changed</p>"], false).unwrap();
+		assert_ne!(modified(), past);
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), "<p>changed</p>");
+	}
+}
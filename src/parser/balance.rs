@@ -0,0 +1,217 @@
+use std::ops::Range;
+use std::rc::Rc;
+
+use ariadne::Fmt;
+
+use super::parser::ReportColors;
+use super::reports::macros::*;
+use super::reports::*;
+use super::source::Source;
+
+/// Unbalanced marker found by [`check_balance`]
+#[derive(Debug, PartialEq, Eq)]
+struct Unbalanced {
+	/// Range of the marker in the source
+	range: Range<usize>,
+	/// The marker
+	marker: &'static str,
+	/// The marker it should be matched with
+	expected: &'static str,
+	/// Whether the marker opens a construct that is never closed
+	opening: bool,
+}
+
+/// Finds the first `pattern` of `content` from `pos`, skipping characters escaped by `\`
+fn find_closing(content: &[u8], mut pos: usize, pattern: &str) -> Option<usize> {
+	while pos < content.len() {
+		if content[pos] == b'\\' {
+			pos += 2;
+			continue;
+		}
+		if content[pos..].starts_with(pattern.as_bytes()) {
+			return Some(pos);
+		}
+		pos += 1;
+	}
+	None
+}
+
+/// Finds the unbalanced markers of `content`
+///
+/// This is a fast approximation of the parser: layouts (`#+LAYOUT_BEGIN` and `#+LAYOUT_END`), code
+/// blocks (```` ``` ````) and Lua code (`@<`/`>@` and `%<`/`>%`) are matched line by line, without
+/// running any rule. The content of code, and comments are skipped.
+fn find_unbalanced(content: &str) -> Vec<Unbalanced> {
+	let bytes = content.as_bytes();
+	let line_end = |pos: usize| {
+		bytes[pos..]
+			.iter()
+			.position(|c| *c == b'\n')
+			.map_or(bytes.len(), |end| pos + end)
+	};
+	let mut unbalanced = vec![];
+	let mut layouts = vec![];
+
+	let mut pos = 0;
+	while pos < bytes.len() {
+		if pos == 0 || bytes[pos - 1] == b'\n' {
+			let indent = bytes[pos..]
+				.iter()
+				.take_while(|c| **c == b' ' || **c == b'\t')
+				.count();
+			let start = pos + indent;
+			if bytes[start..].starts_with(b"#+LAYOUT_BEGIN") {
+				layouts.push(start..start + 14);
+				pos = line_end(start);
+				continue;
+			} else if bytes[start..].starts_with(b"#+LAYOUT_END") {
+				if layouts.pop().is_none() {
+					unbalanced.push(Unbalanced {
+						range: start..start + 12,
+						marker: "#+LAYOUT_END",
+						expected: "#+LAYOUT_BEGIN",
+						opening: false,
+					});
+				}
+				pos = line_end(start);
+				continue;
+			} else if indent == 0 && bytes[pos..].starts_with(b"```") {
+				let end = line_end(pos);
+				match find_closing(bytes, end + 1, "```") {
+					Some(closing) => pos = closing + 3,
+					None => {
+						unbalanced.push(Unbalanced {
+							range: pos..pos + 3,
+							marker: "```",
+							expected: "```",
+							opening: true,
+						});
+						pos = end;
+					}
+				}
+				continue;
+			} else if indent == 0 && bytes[pos..].starts_with(b"@<") {
+				match find_closing(bytes, pos + 2, ">@") {
+					Some(closing) => pos = closing + 2,
+					None => {
+						unbalanced.push(Unbalanced {
+							range: pos..pos + 2,
+							marker: "@<",
+							expected: ">@",
+							opening: true,
+						});
+						pos += 2;
+					}
+				}
+				continue;
+			}
+		}
+
+		let rest = &bytes[pos..];
+		if rest[0] == b'\\' {
+			pos += 2;
+		} else if rest.starts_with(b"::") && (pos == 0 || bytes[pos - 1].is_ascii_whitespace()) {
+			// Comment
+			pos = line_end(pos);
+		} else if rest.starts_with(b"``") {
+			// Inline code
+			pos = find_closing(bytes, pos + 2, "``").map_or(pos + 2, |closing| closing + 2);
+		} else if rest.starts_with(b"%<") {
+			match find_closing(bytes, pos + 2, ">%") {
+				Some(closing) => pos = closing + 2,
+				None => {
+					unbalanced.push(Unbalanced {
+						range: pos..pos + 2,
+						marker: "%<",
+						expected: ">%",
+						opening: true,
+					});
+					pos += 2;
+				}
+			}
+		} else {
+			pos += 1;
+		}
+	}
+
+	unbalanced.extend(layouts.into_iter().map(|range| Unbalanced {
+		range,
+		marker: "#+LAYOUT_BEGIN",
+		expected: "#+LAYOUT_END",
+		opening: true,
+	}));
+	unbalanced.sort_by_key(|unbalanced| unbalanced.range.start);
+	unbalanced
+}
+
+/// Reports the unbalanced begin/end markers of a source
+///
+/// The check runs before the source is parsed, so that badly broken documents are still reported
+/// when the parse stops early. Markers are reported as [`Lint::Balance`] lints, the parser may
+/// report the same constructs with more details.
+pub fn check_balance(colors: &ReportColors, source: Rc<dyn Source>) -> Vec<Report> {
+	let mut reports = vec![];
+	for unbalanced in find_unbalanced(source.content()) {
+		let message = if unbalanced.opening {
+			format!(
+				"Missing `{}` for this `{}`",
+				unbalanced.expected.fg(colors.highlight),
+				unbalanced.marker.fg(colors.highlight)
+			)
+		} else {
+			format!(
+				"`{}` has no matching `{}`",
+				unbalanced.marker.fg(colors.highlight),
+				unbalanced.expected.fg(colors.highlight)
+			)
+		};
+		report_lint!(
+			&mut reports,
+			source.clone(),
+			Lint::Balance,
+			"Unbalanced Marker".into(),
+			span(unbalanced.range, message)
+		);
+	}
+	reports
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn balance() {
+		let content = r#"
+#+LAYOUT_BEGIN Split
+```Rust
+%< not lua
+```
+  #+LAYOUT_END
+#+LAYOUT_END
+%<print("a")>% \%< :: %<
+``%<`` %<[kind=block]
+@<
+content
+#+LAYOUT_BEGIN Centered
+```C
+int a;
+"#;
+		let markers = find_unbalanced(content)
+			.into_iter()
+			.map(|unbalanced| (&content[unbalanced.range], unbalanced.opening))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			markers,
+			vec![
+				("#+LAYOUT_END", false),
+				("%<", true),
+				("@<", true),
+				("#+LAYOUT_BEGIN", true),
+				("```", true),
+			]
+		);
+		assert!(find_unbalanced("```\n```").is_empty());
+		assert!(find_unbalanced("é%<é>%é").is_empty());
+	}
+}
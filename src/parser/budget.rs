@@ -0,0 +1,125 @@
+use std::time::Duration;
+use std::time::Instant;
+
+/// Limits on the work done by a parse, e.g to protect the language server from adversarial input
+///
+/// The budget is shared by a document and the documents it imports. The parser checks the budget
+/// before matching each rule, a parse exceeding its budget is reported and stops early, returning
+/// an incomplete document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseBudget {
+	/// Maximum duration of the parse
+	pub timeout: Option<Duration>,
+	/// Maximum number of rules matched by the parse
+	pub max_matches: Option<usize>,
+}
+
+/// Work done by a parse, see [`ParseBudget`]
+#[derive(Debug)]
+pub struct BudgetTracker {
+	budget: ParseBudget,
+	start: Instant,
+	matches: usize,
+	exceeded: bool,
+}
+
+impl BudgetTracker {
+	pub fn new(budget: ParseBudget) -> Self {
+		Self {
+			budget,
+			start: Instant::now(),
+			matches: 0,
+			exceeded: false,
+		}
+	}
+
+	/// Whether the budget has been exceeded
+	pub fn exceeded(&self) -> bool { self.exceeded }
+
+	/// Accounts for a rule match
+	///
+	/// # Errors
+	///
+	/// Returns the reason why the budget is exceeded, the first time it is exceeded.
+	pub fn spend(&mut self) -> Result<(), String> {
+		if self.exceeded {
+			return Ok(());
+		}
+		self.matches += 1;
+		if let Some(max) = self.budget.max_matches.filter(|max| self.matches > *max) {
+			self.exceeded = true;
+			return Err(format!("Parse stopped after {max} rule matches"));
+		}
+		if let Some(timeout) = self
+			.budget
+			.timeout
+			.filter(|timeout| self.start.elapsed() > *timeout)
+		{
+			self.exceeded = true;
+			return Err(format!("Parse stopped after {:.1}s", timeout.as_secs_f64()));
+		}
+		Ok(())
+	}
+}
+
+impl Default for BudgetTracker {
+	fn default() -> Self { Self::new(ParseBudget::default()) }
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::RefCell;
+	use std::rc::Rc;
+
+	use crate::parser::langparser::LangParser;
+	use crate::parser::parser::ParseMode;
+	use crate::parser::parser::Parser;
+	use crate::parser::parser::ParserState;
+	use crate::parser::source::SourceFile;
+
+	use super::*;
+
+	#[test]
+	fn exceeded() {
+		let source = Rc::new(SourceFile::with_content(
+			"".to_string(),
+			"*a* *b* *c* *d*\n".to_string(),
+			None,
+		));
+		let reports = RefCell::new(vec![]);
+		let parser = LangParser::new(
+			false,
+			Box::new(|_, list| {
+				reports
+					.borrow_mut()
+					.extend(list.into_iter().map(|report| report.message))
+			}),
+		);
+
+		let budget = ParseBudget {
+			timeout: None,
+			max_matches: Some(3),
+		};
+		let (doc, state) = parser.parse(
+			ParserState::new(&parser, None).with_budget(budget),
+			source.clone(),
+			None,
+			ParseMode::default(),
+		);
+		assert!(state.budget_exceeded());
+		drop(doc);
+		assert_eq!(reports.take(), vec!["Parse Budget Exceeded".to_string()]);
+
+		// Unlimited
+		let (doc, state) = parser.parse(
+			ParserState::new(&parser, None),
+			source,
+			None,
+			ParseMode::default(),
+		);
+		assert!(!state.budget_exceeded());
+		drop(doc);
+		drop(parser);
+		assert!(reports.into_inner().is_empty());
+	}
+}
@@ -0,0 +1,66 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// Token used to abort a parse, e.g when the language server receives a newer version of a
+/// document
+///
+/// The parser checks the token before matching each rule, a cancelled parse stops early and
+/// returns an incomplete document. Clones of the token share the same state, so it can be
+/// cancelled from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+	pub fn new() -> Self { Self::default() }
+
+	/// Requests the parse to stop
+	pub fn cancel(&self) { self.0.store(true, Ordering::Relaxed); }
+
+	pub fn is_cancelled(&self) -> bool { self.0.load(Ordering::Relaxed) }
+}
+
+#[cfg(test)]
+mod tests {
+	use std::rc::Rc;
+
+	use crate::parser::langparser::LangParser;
+	use crate::parser::parser::ParseMode;
+	use crate::parser::parser::Parser;
+	use crate::parser::parser::ParserState;
+	use crate::parser::source::SourceFile;
+
+	use super::*;
+
+	#[test]
+	fn cancelled() {
+		let source = Rc::new(SourceFile::with_content(
+			"".to_string(),
+			"# Title\n*bold*\n".to_string(),
+			None,
+		));
+		let parser = LangParser::default();
+		let cancellation = Cancellation::new();
+		let other = cancellation.clone();
+
+		let (doc, _) = parser.parse(
+			ParserState::new(&parser, None).with_cancellation(cancellation.clone()),
+			source.clone(),
+			None,
+			ParseMode::default(),
+		);
+		assert!(doc.content().borrow().len() > 1);
+
+		other.cancel();
+		assert!(cancellation.is_cancelled());
+		let (doc, state) = parser.parse(
+			ParserState::new(&parser, None).with_cancellation(cancellation),
+			source,
+			None,
+			ParseMode::default(),
+		);
+		assert!(state.is_cancelled());
+		// Only the end of the document is pushed
+		assert_eq!(doc.content().borrow().len(), 1);
+	}
+}
@@ -0,0 +1,379 @@
+/// Stable code of a report
+///
+/// Reports are identified by their message, codes are displayed with reports and can be explained
+/// using `nml --explain CODE`.
+pub struct ReportCode {
+	/// Code of the report, e.g `NML0001`
+	pub code: &'static str,
+	/// Message of the report, `{}` matches any text
+	pub message: &'static str,
+	/// Extended documentation of the report
+	pub explanation: &'static str,
+}
+
+impl ReportCode {
+	/// Checks whether the code applies to a report's `message`
+	fn matches(&self, message: &str) -> bool {
+		match self.message.split_once("{}") {
+			Some((prefix, suffix)) => {
+				message.len() >= prefix.len() + suffix.len()
+					&& message.starts_with(prefix)
+					&& message.ends_with(suffix)
+			}
+			None => self.message == message,
+		}
+	}
+
+	/// Gets the code of a report from its message
+	///
+	/// Exact messages take precedence over messages with placeholders.
+	pub fn from_message(message: &str) -> Option<&'static ReportCode> {
+		CODES
+			.iter()
+			.find(|code| !code.message.contains("{}") && code.matches(message))
+			.or_else(|| CODES.iter().find(|code| code.matches(message)))
+	}
+
+	/// Gets a code from its name, case insensitive
+	pub fn find(code: &str) -> Option<&'static ReportCode> {
+		CODES
+			.iter()
+			.find(|report_code| report_code.code.eq_ignore_ascii_case(code.trim()))
+	}
+}
+
+macro_rules! codes {
+	($($code:literal: $message:literal => $explanation:literal,)*) => {
+		/// Codes of the reports
+		///
+		/// Codes are stable: they are never reused or renumbered, new reports get new codes.
+		pub static CODES: &[ReportCode] = &[$(ReportCode {
+			code: $code,
+			message: $message,
+			explanation: $explanation,
+		},)*];
+	};
+}
+
+codes! {
+	// Compiler
+	"NML0001": "Unable to compile {}" =>
+		"An element could not be compiled to the output target, the element is removed from the \
+		output. The report's label gives the reason, such as a failed external command or an \
+		unsupported target.",
+	"NML0002": "Unable to resolve references" =>
+		"A reference points to an element that doesn't exist, or to an element defined in several \
+		documents. Check the name of the reference, and prefix it with the name of the document \
+		when it is ambiguous.",
+	"NML0003": "External Command Refused" =>
+		"An element needs an external command (`latex2svg`, `dot`, a code runner, ...) that isn't \
+		allowed. External commands are disabled by `--no-exec`, and restricted by the `[exec]` \
+		table of the project file. The element is displayed without its rendered output.",
+	"NML0004": "Failed to parse {} properties" =>
+		"The properties of an element (`[key=value, ...]`) are invalid: a property is unknown, \
+		duplicated, missing or has a value of the wrong type. `nml --list-properties` lists the \
+		properties of every element.",
+	// Blocks
+	"NML0005": "Unknown Block" =>
+		"The block kind after `>[!...]` doesn't exist. Blocks available by default are `Quote`, \
+		`Warning`, `Note` and `Todo`.",
+	"NML0006": "Unable to Parse Block Entry" =>
+		"The content of a block entry contains an error, see the other reports for details.",
+	// Code
+	"NML0007": "Unknown Code Language" =>
+		"The language of a code block isn't supported by the syntax highlighter. The name must match \
+		one of the languages known to `syntect`, such as `Rust` or `Plain Text`.",
+	"NML0008": "Invalid Code Content" =>
+		"A code block including a file with the `src` property has content, such code blocks must \
+		be empty.",
+	"NML0009": "Invalid Code Source" =>
+		"The file given by the `src` property of a code block can't be read.",
+	"NML0010": "Invalid Code Range" =>
+		"The `lines` property of a code block doesn't describe a valid range of lines of its \
+		source, ranges are written `start-end` and are inclusive.",
+	"NML0011": "Empty Code Content" =>
+		"A code block has no content. Either add content, or remove the block.",
+	"NML0012": "Invalid Callout" =>
+		"A callout marker in a code block is malformed. Callouts are written `<1>` at the end of a \
+		line.",
+	"NML0013": "Orphan Callout" =>
+		"A callout is described, but no line of the code block is marked with it.",
+	"NML0014": "Unknown Callout" =>
+		"A line of a code block is marked with a callout that isn't described.",
+	"NML0015": "Unable to parse Code Callout" =>
+		"The description of a callout contains an error, see the other reports for details.",
+	// Colors, comments and conditionals
+	"NML0016": "Invalid Color" =>
+		"A color isn't valid. Colors are hexadecimal values such as `#f80`, `#ff8800` or \
+		`#ff880080`.",
+	"NML0017": "Empty Comment" =>
+		"A comment has no content.",
+	"NML0018": "Invalid Conditional" =>
+		"The conditions of a conditional block can't be parsed. Conditions are separated by \
+		whitespace and check `target`, `profile` or `flag`, e.g `target=html flag!=draft`.",
+	// Custom styles
+	"NML0019": "Lua execution failed" =>
+		"The Lua code of a custom style raised an error.",
+	"NML0020": "Invalid Custom Style Properties" =>
+		"The properties of a custom style can't be parsed.",
+	"NML0021": "Overlapping Custom Styles" =>
+		"Two custom styles use the same token, or a style is opened while already open. Custom \
+		styles must be closed in the order they were opened.",
+	"NML0022": "Invalid End of Style" =>
+		"A custom style is closed without being opened.",
+	"NML0023": "Invalid Start of Style" =>
+		"A custom style is opened while it is already open.",
+	"NML0024": "Unterminated Custom Style" =>
+		"A custom style is still open at the end of its scope. Close the style before the end of \
+		the paragraph, block or document.",
+	// Diffs
+	"NML0025": "Invalid Inserted Text" =>
+		"The content of inserted text (`{+...+}`) contains an error, see the other reports for \
+		details.",
+	"NML0026": "Invalid Deleted Text" =>
+		"The content of deleted text (`{-...-}`) contains an error, see the other reports for \
+		details.",
+	// Element styles
+	"NML0027": "Unknown Style Field" =>
+		"A field of an element style doesn't exist and is ignored.",
+	"NML0028": "Empty Style Key" =>
+		"The key of an element style (`@@key = {...}`) is empty.",
+	"NML0029": "Unknown Style Key" =>
+		"The key of an element style doesn't correspond to any style.",
+	"NML0030": "Invalid Style Value" =>
+		"The value of an element style isn't valid JSON, or doesn't match the style's fields.",
+	// Graphviz
+	"NML0031": "Unterminated Graph Code" =>
+		"A graph is missing its closing `[/graph]`.",
+	"NML0032": "Invalid Graph Code" =>
+		"A graph read from a file with the `src` property has content, such graphs must be empty.",
+	"NML0033": "Invalid Graph Source" =>
+		"The file given by the `src` property of a graph can't be read.",
+	"NML0034": "Empty Graph Code" =>
+		"A graph has no content.",
+	// Imports
+	"NML0035": "Invalid Import Name" =>
+		"The path of an import is missing or malformed.",
+	"NML0036": "Invalid Import Path" =>
+		"The imported file doesn't exist, or isn't a `.nml` file.",
+	"NML0037": "Invalid Import As" =>
+		"The name given by `@import[as=...]` is invalid.",
+	"NML0038": "Invalid Import File" =>
+		"The imported file can't be read.",
+	"NML0039": "Shadowed Variable" =>
+		"A variable replaces a variable of the same name that is exported or defined from outside \
+		the documents (`--define`, `[defines]`). Definitions made outside the documents take \
+		precedence.",
+	// Layouts
+	"NML0040": "Missing Layout Name" =>
+		"`#+LAYOUT_BEGIN` must be followed by the name of the layout.",
+	"NML0041": "Empty Layout Name" =>
+		"The name of a layout is empty.",
+	"NML0042": "Invalid Layout Name" =>
+		"The name of a layout is malformed.",
+	"NML0043": "Unknown Layout" =>
+		"The layout doesn't exist. Layouts available by default are `Centered` and `Split`.",
+	"NML0044": "Invalid #+LAYOUT_NEXT" =>
+		"`#+LAYOUT_NEXT` is used outside of a layout.",
+	"NML0045": "Unexpected #+LAYOUT_NEXT" =>
+		"`#+LAYOUT_NEXT` adds more blocks than the layout accepts.",
+	"NML0046": "Invalid #+LAYOUT_END" =>
+		"`#+LAYOUT_END` is used outside of a layout.",
+	"NML0047": "Unexpected #+LAYOUT_END" =>
+		"`#+LAYOUT_END` closes the layout before it has all its required blocks.",
+	"NML0048": "Unterminated Layout" =>
+		"A layout is missing its `#+LAYOUT_END`.",
+	// Links
+	"NML0049": "Empty Link Display" =>
+		"The display text of a link (`[display](url)`) is empty.",
+	"NML0050": "Invalid Link Display" =>
+		"The display text of a link contains an error, see the other reports for details.",
+	"NML0051": "Empty Link URL" =>
+		"The url of a link is empty.",
+	// Lists
+	"NML0052": "Unknown custom list data" =>
+		"The custom data of a list entry can't be understood.",
+	"NML0053": "Unable to parse List Entry" =>
+		"The content of a list entry contains an error, see the other reports for details.",
+	// Media
+	"NML0054": "Invalid Media Refname" =>
+		"The reference name of a medium is invalid. Reference names can't contain whitespace.",
+	"NML0055": "Invalid Media URI" =>
+		"The uri of a medium (`![refname](uri)`) is empty or malformed.",
+	"NML0056": "Invalid Media Property" =>
+		"A property of a medium is invalid, such as an unknown media type or an invalid width.",
+	"NML0057": "Missing Image Alt Text" =>
+		"An image has no alternative text, which screen readers rely on. Describe the image with the \
+		`alt` property.",
+	"NML0058": "Invalid Media Description" =>
+		"The description of a medium contains an error, see the other reports for details.",
+	"NML0059": "Invalid Media" =>
+		"The medium can't be added to the document, for instance because it can't be grouped with \
+		the previous media.",
+	// Raw
+	"NML0060": "Unterminated Raw Code" =>
+		"Raw code is missing its closing `?}`.",
+	"NML0061": "Empty Raw Code" =>
+		"Raw code has no content.",
+	// References
+	"NML0062": "Invalid Reference Refname" =>
+		"The name of a reference (`&{refname}`) is empty or malformed.",
+	"NML0063": "Invalid Reference Caption" =>
+		"The caption of a reference contains an error, see the other reports for details.",
+	// Scripts
+	"NML0064": "Invalid Kernel Name" =>
+		"The name of a Lua kernel is malformed.",
+	"NML0065": "Invalid Kernel Code" =>
+		"The Lua code is empty, or raised an error while being executed.",
+	"NML0066": "Invalid Kernel Code Kind" =>
+		"The kind of a Lua script (`%<...>%`) is unknown. Leave it empty to only evaluate the code, \
+		use `\"` to display the result as text, or `!` to parse the result as NML.",
+	// Sections
+	"NML0067": "Invalid Section Depth" =>
+		"A section is deeper than the maximum depth allowed.",
+	"NML0068": "Duplicate Reference Name" =>
+		"Two elements have the same reference name, references resolve to the first one.",
+	"NML0069": "Invalid Section Numbering Kind" =>
+		"The numbering kind of a section is unknown. Use `*` for unnumbered sections, `+` for \
+		sections that are hidden from the table of contents, or both.",
+	"NML0070": "Missing Section Name" =>
+		"A section has no title.",
+	"NML0071": "Missing Section Spacing" =>
+		"The `#` of a section must be followed by a space.",
+	// Styles
+	"NML0072": "Unterminated Style" =>
+		"A style (bold, italic, ...) is still open at the end of the paragraph.",
+	// Tables
+	"NML0073": "Duplicate row property" =>
+		"A property is set twice for the same table row.",
+	"NML0074": "Duplicate table property" =>
+		"A property is set twice for the same table.",
+	"NML0075": "Invalid Table Refname" =>
+		"The reference name of a table is invalid.",
+	"NML0076": "Invalid Table Cell" =>
+		"A table cell is malformed, or spans outside of the table.",
+	"NML0077": "Unable to Parse Table Cell" =>
+		"The content of a table cell contains an error, see the other reports for details.",
+	"NML0078": "Invalid Table Row" =>
+		"A table row is malformed.",
+	"NML0079": "Failed to export lua table" =>
+		"The table can't be exported to Lua.",
+	// LaTeX
+	"NML0080": "LaTeX Rendering Failed" =>
+		"`latex2svg` failed to render a LaTeX element, the report contains LaTeX's error. Check the \
+		LaTeX code and the preamble set by `tex.{env}.preamble`.",
+	"NML0081": "Unterminated Tex Code" =>
+		"A LaTeX element is missing its closing `$` or `|$`.",
+	"NML0082": "Empty Tex Code" =>
+		"A LaTeX element has no content.",
+	// Variables
+	"NML0083": "Unknown Variable Kind" =>
+		"The kind of a variable definition is unknown. Use `@name = value` for regular variables, \
+		`@'name = path` for path variables, and `@!name = code` for computed variables.",
+	"NML0084": "Invalid Variable Name" =>
+		"The name of a variable is empty, or contains `%`.",
+	"NML0085": "Invalid Variable Value" =>
+		"The value of a variable is invalid, for instance because the file of a path variable \
+		doesn't exist.",
+	"NML0086": "Invalid Variable Type" =>
+		"The variable has the wrong type for its usage.",
+	"NML0087": "Conflicting Variable Type" =>
+		"A variable is declared with a type different from its previous declaration.",
+	"NML0088": "Unable to Create Variable" =>
+		"The variable can't be created.",
+	"NML0089": "Empty Variable Name" =>
+		"A variable is used (`%name%`) without a name.",
+	"NML0090": "Unknown Variable Name" =>
+		"A variable is used without being defined. Define it in the document, export it from an \
+		imported document, or pass it with `--define`.",
+	"NML0091": "Variable Evaluation Failed" =>
+		"The value of the variable contains an error, see the other reports for details.",
+	// Project
+	"NML0092": "Invalid Project File" =>
+		"The project file (`nml.toml`) isn't valid TOML.",
+	"NML0093": "Unknown Setting" =>
+		"The project file contains an unknown setting, the report suggests the closest setting.",
+	"NML0094": "Invalid Setting" =>
+		"A setting of the project file has an invalid value, such as a path that doesn't exist.",
+	"NML0095": "Conflicting Settings" =>
+		"Two settings of the project file contradict each other, for instance a directory input \
+		with a file output.",
+	// Style lints
+	"NML0096": "Invalid Lint Setting" =>
+		"A `lint.*` variable has an invalid value, the lint is disabled.",
+	"NML0097": "Incorrect Heading Case" =>
+		"A section title doesn't follow the capitalization set by `lint.heading_case`. In \
+		`sentence` case only the first word is capitalized, in `title` case every word is \
+		capitalized except for short words such as `a`, `of` or `the`. Acronyms are ignored.",
+	"NML0098": "Heading Too Deep" =>
+		"A section is deeper than the depth set by `lint.max_heading_depth`.",
+	"NML0099": "Skipped Heading Level" =>
+		"A section is more than one level deeper than the previous section, which is reported when \
+		`lint.heading_levels` is `true`. Screen readers rely on heading levels to navigate \
+		documents.",
+	"NML0100": "Paragraph Too Long" =>
+		"A paragraph has more words than set by `lint.max_paragraph_length`.",
+	// Tests
+	"NML0101": "Assertion Failed" =>
+		"An assertion of the `nml.assert` Lua table failed, see `nml test`.",
+	// Language server
+	"NML0102": "Unknown Reference" =>
+		"A reference to another document (`&{document#refname}`) names a reference that the \
+		document doesn't define. The references of the other documents are read from the cache \
+		database, where they are stored when the documents are parsed by the language server.",
+	// External renderers
+	"NML0103": "Invalid Render Block" =>
+		"A render block is missing its `#+RENDER_END` delimiter, or a `#+RENDER_END` has no \
+		matching `#+RENDER`.",
+	"NML0104": "Unknown Renderer" =>
+		"A render block uses a renderer that isn't defined. Renderers are declared in the \
+		`[render.<name>]` tables of the project file, or by setting `render.<name>.command`.",
+	"NML0105": "Empty Render Content" =>
+		"A render block has no content. Either add content, or remove the block.",
+	// Size limits
+	"NML0106": "Unusually Large {}" =>
+		"A paragraph, table or code block is larger than the limit set by \
+		`lint.max_paragraph_lines`, `lint.max_table_rows` or `lint.max_code_lines`. This usually \
+		means that a construct was not terminated, e.g a code block whose closing delimiter is \
+		missing swallows the content up to the next code block. Raise the limit for documents \
+		with large elements, a limit of `0` disables the warning.",
+	"NML0107": "Unbalanced Marker" =>
+		"A layout (`#+LAYOUT_BEGIN`/`#+LAYOUT_END`), code block (```` ``` ````) or Lua code \
+		(`%<`/`>%`, `@<`/`>@`) is missing its closing or opening marker. Markers are checked \
+		before parsing, so that they are reported even when the rest of the document can't be \
+		parsed.",
+	// Parser
+	"NML0108": "Rule Panicked" =>
+		"A parser rule crashed while processing the element at the reported location. This is a bug \
+		in nml: the rest of the line is skipped, and parsing continues with the next line. Please \
+		report it along with the content of the line.",
+	"NML0109": "Parse Budget Exceeded" =>
+		"Parsing the document took longer, or matched more rules than allowed, and was stopped at \
+		the reported location. The language server limits parses so that adversarial or badly \
+		broken documents can't block it, see the `parseTimeout` and `maxRuleMatches` settings.",
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn codes() {
+		for (id, code) in CODES.iter().enumerate() {
+			assert_eq!(code.code, format!("NML{:04}", id + 1));
+		}
+		assert_eq!(
+			ReportCode::from_message("Unknown Block").map(|code| code.code),
+			Some("NML0005")
+		);
+		assert_eq!(
+			ReportCode::from_message("Failed to parse Graphviz properties").map(|code| code.code),
+			Some("NML0004")
+		);
+		assert!(ReportCode::from_message("Something Else").is_none());
+		assert_eq!(
+			ReportCode::find("nml0042").map(|code| code.message),
+			Some("Invalid Layout Name")
+		);
+	}
+}
@@ -1,23 +1,29 @@
+use std::any::Any;
 use std::cell::RefCell;
-use std::path::PathBuf;
+use std::panic::AssertUnwindSafe;
 use std::rc::Rc;
 
+use ariadne::Fmt;
+
 use crate::document::document::Document;
 use crate::document::element::DocumentEnd;
 use crate::document::langdocument::LangDocument;
 use crate::elements::text::elem::Text;
 use crate::lsp::semantic::Semantics;
+use crate::parser::balance::check_balance;
+use crate::parser::lint::lint_document;
 
 use super::parser::ParseMode;
 use super::parser::Parser;
 use super::parser::ParserState;
 use super::parser::ReportColors;
+use super::reports::macros::*;
 use super::reports::Report;
+use super::reports::*;
 use super::rule::Rule;
 use super::source::Cursor;
 use super::source::Source;
 use super::source::SourceFile;
-use super::source::SourcePosition;
 use super::source::Token;
 use super::util;
 
@@ -72,6 +78,84 @@ impl<'a> LangParser<'a> {
 	}
 }
 
+impl LangParser<'_> {
+	/// Calls [`Rule::on_match`] for rule `rule_index`, recovering from panics
+	///
+	/// A panicking rule is reported as an error on the line of its match, and parsing resumes after
+	/// that line.
+	fn match_rule<'a>(
+		&self,
+		rule_index: usize,
+		state: &ParserState,
+		document: &'a (dyn Document<'a> + 'a),
+		cursor: Cursor,
+		match_data: Box<dyn Any>,
+	) -> (Cursor, Vec<Report>) {
+		let rule = &self.rules[rule_index];
+		let payload = match std::panic::catch_unwind(AssertUnwindSafe(|| {
+			rule.on_match(state, document, cursor.clone(), match_data)
+		})) {
+			Ok(result) => return result,
+			Err(payload) => payload,
+		};
+		let message = payload
+			.downcast_ref::<&str>()
+			.map(|message| message.to_string())
+			.or_else(|| payload.downcast_ref::<String>().cloned())
+			.unwrap_or("unknown error".into());
+
+		// Skip the rest of the line, or the character at the cursor
+		let content = cursor.source.content();
+		let end = content[cursor.pos..]
+			.find('\n')
+			.map_or(content.len(), |end| cursor.pos + end);
+		let end = match end > cursor.pos {
+			true => end,
+			false => content[cursor.pos..]
+				.chars()
+				.next()
+				.map_or(cursor.pos, |c| cursor.pos + c.len_utf8()),
+		};
+
+		let mut reports = vec![];
+		report_err!(
+			&mut reports,
+			cursor.source.clone(),
+			"Rule Panicked".into(),
+			span(
+				cursor.pos..end,
+				format!(
+					"Rule `{}` panicked: {message}",
+					rule.name().fg(self.colors.highlight)
+				)
+			),
+			note("This is a bug in nml, the rest of the line is skipped".into())
+		);
+		(cursor.at(end), reports)
+	}
+}
+
+impl LangParser<'_> {
+	/// Reports a parse that exceeded its budget at `cursor`, see [`ParseBudget`]
+	///
+	/// [`ParseBudget`]: super::budget::ParseBudget
+	fn budget_report(&self, cursor: &Cursor, reason: String) -> Vec<Report> {
+		let content = cursor.source.content();
+		let end = content[cursor.pos..]
+			.find('\n')
+			.map_or(content.len(), |end| cursor.pos + end);
+		let mut reports = vec![];
+		report_err!(
+			&mut reports,
+			cursor.source.clone(),
+			"Parse Budget Exceeded".into(),
+			span(cursor.pos..end, reason),
+			note("The rest of the document is not parsed".into())
+		);
+		reports
+	}
+}
+
 impl<'b> Parser for LangParser<'b> {
 	fn colors(&self) -> &ReportColors { &self.colors }
 
@@ -88,51 +172,7 @@ impl<'b> Parser for LangParser<'b> {
 		mode: ParseMode,
 	) -> (Box<dyn Document<'doc> + 'doc>, ParserState<'p, 'a>) {
 		let doc = LangDocument::new(source.clone(), parent);
-
-		let current_dir = match std::env::current_dir() {
-			Ok(dir) => dir,
-			Err(err) => {
-				eprintln!("Unable to get the current working directory: {err}");
-				return (Box::new(doc), state);
-			}
-		};
-
-		let path = source
-			.original_position(0)
-			.0
-			.downcast_rc::<SourceFile>()
-			.ok()
-			.map(|source| {
-				if source.path().is_empty()
-				// Test mode
-				{
-					None
-				} else {
-					let start = if source.path().starts_with("file:///") {
-						7
-					} else {
-						0
-					};
-					let mut path = PathBuf::from(&source.path()[start..]);
-					match path.canonicalize() {
-						Ok(cano) => path = cano,
-						Err(err) => {
-							eprintln!("Failed to canonicalize path `{}`: {err}", source.path())
-						}
-					}
-					path.pop();
-					Some(path)
-				}
-			})
-			.unwrap();
-		if let Some(path) = &path {
-			if let Err(err) = std::env::set_current_dir(&path) {
-				eprintln!(
-					"Failed to set working directory to `{}`: {err}",
-					path.to_str().unwrap_or("")
-				);
-			}
-		}
+		state.shared.sources.borrow_mut().push(source.clone());
 
 		// Insert lsp data into state
 		if let (Some(_), Some(lsp)) = (
@@ -142,10 +182,15 @@ impl<'b> Parser for LangParser<'b> {
 			lsp.borrow_mut().new_source(source.clone());
 		}
 
+		// Markers are checked before parsing, so they are reported even when the parse stops early
+		if source.clone().downcast_rc::<SourceFile>().is_ok() {
+			self.handle_reports(check_balance(self.colors(), source.clone()));
+		}
+
 		let content = source.content();
 		let mut cursor = Cursor::new(0usize, doc.source()); // Cursor in file
 
-		if let Some(parent) = parent
+		if let Some(parent) = parent.filter(|_| !state.is_root())
 		// Terminate parent's paragraph state
 		{
 			self.handle_reports(state.shared.rule_state.borrow_mut().on_scope_end(
@@ -156,6 +201,11 @@ impl<'b> Parser for LangParser<'b> {
 		}
 
 		loop {
+			// The rest of the source is dropped when the parse is cancelled, or exceeds its budget
+			if state.is_cancelled() || state.budget_exceeded() {
+				break;
+			}
+
 			let (rule_pos, mut result) = state.update_matches(&mode, &cursor);
 
 			// Unmatched content
@@ -172,10 +222,15 @@ impl<'b> Parser for LangParser<'b> {
 			}
 
 			if let Some((rule_index, match_data)) = result.take() {
+				if let Err(reason) = state.spend_budget() {
+					self.handle_reports(self.budget_report(&rule_pos, reason));
+					break;
+				}
+
 				// Rule callback
 				let dd: &'a dyn Document = unsafe { std::mem::transmute(&doc as &dyn Document) };
 				let (new_cursor, reports) =
-					self.rules[rule_index].on_match(&state, dd, rule_pos, match_data);
+					self.match_rule(rule_index, &state, dd, rule_pos, match_data);
 
 				self.handle_reports(reports);
 
@@ -191,9 +246,18 @@ impl<'b> Parser for LangParser<'b> {
 		// Process the end of the semantics queue
 		Semantics::on_document_end(&state.shared.lsp, source.clone());
 
-		if parent.is_none() {
+		// Style lints
+		self.handle_reports(lint_document(self.colors(), &doc));
+
+		// Constructs left open by a parse that exceeded its budget are not reported
+		let scope_reports = |reports: Vec<Report>| {
+			if !state.budget_exceeded() {
+				self.handle_reports(reports);
+			}
+		};
+		if state.is_root() {
 			// Rule States
-			self.handle_reports(state.shared.rule_state.borrow_mut().on_scope_end(
+			scope_reports(state.shared.rule_state.borrow_mut().on_scope_end(
 				&state,
 				&doc,
 				super::state::Scope::DOCUMENT,
@@ -207,22 +271,13 @@ impl<'b> Parser for LangParser<'b> {
 				))),
 			);
 		} else {
-			self.handle_reports(state.shared.rule_state.borrow_mut().on_scope_end(
+			scope_reports(state.shared.rule_state.borrow_mut().on_scope_end(
 				&state,
 				&doc,
 				super::state::Scope::PARAGRAPH,
 			));
 		}
 
-		if path.is_some() {
-			if let Err(err) = std::env::set_current_dir(&current_dir) {
-				println!(
-					"Failed to set working directory to `{}`: {err} {source:#?}",
-					current_dir.to_str().unwrap_or("")
-				);
-			}
-		}
-
 		(Box::new(doc), state)
 	}
 
@@ -233,10 +288,16 @@ impl<'b> Parser for LangParser<'b> {
 		document: &'doc dyn Document<'doc>,
 		mode: ParseMode,
 	) -> ParserState<'p, 'a> {
+		state.shared.sources.borrow_mut().push(source.clone());
 		let content = source.content();
 		let mut cursor = Cursor::new(0usize, source.clone());
 
 		loop {
+			// The rest of the source is dropped when the parse is cancelled, or exceeds its budget
+			if state.is_cancelled() || state.budget_exceeded() {
+				break;
+			}
+
 			let (rule_pos, mut result) = state.update_matches(&mode, &cursor);
 
 			// Unmatched content
@@ -253,9 +314,14 @@ impl<'b> Parser for LangParser<'b> {
 			}
 
 			if let Some((rule_index, match_data)) = result.take() {
+				if let Err(reason) = state.spend_budget() {
+					self.handle_reports(self.budget_report(&rule_pos, reason));
+					break;
+				}
+
 				// Rule callback
 				let (new_cursor, reports) =
-					self.rules[rule_index].on_match(&state, document, rule_pos, match_data);
+					self.match_rule(rule_index, &state, document, rule_pos, match_data);
 
 				self.handle_reports(reports);
 
@@ -281,3 +347,90 @@ impl<'b> Parser for LangParser<'b> {
 		(self.report_handler)(self.colors(), reports);
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::document::document::Document;
+	use crate::elements::paragraph::elem::Paragraph;
+	use crate::elements::text::elem::Text;
+	use crate::validate_document;
+
+	use super::*;
+
+	/// Rule panicking on `PANIC`
+	struct PanicRule;
+
+	impl Rule for PanicRule {
+		fn name(&self) -> &'static str { "Panic" }
+
+		fn previous(&self) -> Option<&'static str> { None }
+
+		fn next_match(
+			&self,
+			_mode: &ParseMode,
+			_state: &ParserState,
+			cursor: &Cursor,
+		) -> Option<(usize, Box<dyn Any>)> {
+			cursor.source.content()[cursor.pos..]
+				.find("PANIC")
+				.map(|pos| (cursor.pos + pos, Box::new(()) as Box<dyn Any>))
+		}
+
+		fn on_match(
+			&self,
+			_state: &ParserState,
+			_document: &dyn Document,
+			_cursor: Cursor,
+			_match_data: Box<dyn Any>,
+		) -> (Cursor, Vec<Report>) {
+			panic!("boom");
+		}
+	}
+
+	#[test]
+	fn rule_panic() {
+		let source = Rc::new(SourceFile::with_content(
+			"".to_string(),
+			"Before PANIC skipped\nAfter\n".to_string(),
+			None,
+		));
+		let reports = RefCell::new(vec![]);
+		let mut parser = LangParser::new(
+			false,
+			Box::new(|_, list| {
+				reports.borrow_mut().extend(list.into_iter().map(|report| {
+					let span = &report.spans[0];
+					(
+						report.message,
+						span.token.source().content()[span.token.range.clone()].to_string(),
+						span.message.clone(),
+					)
+				}))
+			}),
+		);
+		parser.add_rule(Box::new(PanicRule)).unwrap();
+		let (doc, _) = parser.parse(
+			ParserState::new(&parser, None),
+			source,
+			None,
+			ParseMode::default(),
+		);
+
+		validate_document!(doc.content().borrow(), 0,
+			Paragraph {
+				Text { content == "Before " };
+				Text { content == " After" };
+			};
+		);
+		drop(doc);
+		drop(parser);
+		assert_eq!(
+			reports.into_inner(),
+			vec![(
+				"Rule Panicked".to_string(),
+				"PANIC skipped".to_string(),
+				"Rule `Panic` panicked: boom".to_string()
+			)]
+		);
+	}
+}
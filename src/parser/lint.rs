@@ -0,0 +1,682 @@
+use std::rc::Rc;
+use std::str::FromStr;
+
+use ariadne::Fmt;
+use serde::Deserialize;
+
+use crate::document::document::Document;
+use crate::document::element::ElemKind;
+use crate::document::element::Element;
+use crate::elements::code::elem::Code;
+use crate::elements::paragraph::elem::Paragraph;
+use crate::elements::section::elem::Section;
+use crate::elements::table::elem::Table;
+use crate::elements::text::elem::Text;
+
+use super::parser::ReportColors;
+use super::reports::macros::*;
+use super::reports::*;
+use super::source::Token;
+
+/// Name of the variable setting the capitalization of section titles
+pub const HEADING_CASE_VARIABLE: &str = "lint.heading_case";
+
+/// Name of the variable setting the maximum depth of sections
+pub const HEADING_DEPTH_VARIABLE: &str = "lint.max_heading_depth";
+
+/// Name of the variable reporting sections that skip a level when `true`
+pub const HEADING_LEVELS_VARIABLE: &str = "lint.heading_levels";
+
+/// Name of the variable setting the maximum number of words of paragraphs
+pub const PARAGRAPH_LENGTH_VARIABLE: &str = "lint.max_paragraph_length";
+
+/// Name of the variable setting the number of lines above which paragraphs are reported
+pub const PARAGRAPH_LINES_VARIABLE: &str = "lint.max_paragraph_lines";
+
+/// Name of the variable setting the number of rows above which tables are reported
+pub const TABLE_ROWS_VARIABLE: &str = "lint.max_table_rows";
+
+/// Name of the variable setting the number of lines above which code blocks are reported
+pub const CODE_LINES_VARIABLE: &str = "lint.max_code_lines";
+
+/// Default value of [`PARAGRAPH_LINES_VARIABLE`]
+const DEFAULT_PARAGRAPH_LINES: usize = 100;
+
+/// Default value of [`TABLE_ROWS_VARIABLE`]
+const DEFAULT_TABLE_ROWS: usize = 1000;
+
+/// Default value of [`CODE_LINES_VARIABLE`]
+const DEFAULT_CODE_LINES: usize = 2000;
+
+/// Words that stay lowercase in title case, unless they start or end the title
+const MINOR_WORDS: [&str; 17] = [
+	"a", "an", "and", "as", "at", "but", "by", "for", "in", "nor", "of", "on", "or", "the", "to",
+	"vs", "with",
+];
+
+/// Capitalization of section titles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeadingCase {
+	/// Only the first word is capitalized
+	Sentence,
+	/// Every word is capitalized, except for [`MINOR_WORDS`]
+	Title,
+}
+
+impl HeadingCase {
+	/// Gets the name of the case, as set by [`HEADING_CASE_VARIABLE`]
+	pub fn name(&self) -> &'static str {
+		match self {
+			HeadingCase::Sentence => "sentence",
+			HeadingCase::Title => "title",
+		}
+	}
+
+	/// Finds the words of `title` that don't follow the case
+	///
+	/// Returns the byte range of the words in `title`, along with the expected capitalization.
+	/// Words that don't start with a letter, and words with several uppercase letters (e.g
+	/// acronyms) are ignored.
+	pub fn check(&self, title: &str) -> Vec<(std::ops::Range<usize>, &'static str)> {
+		let mut words = vec![];
+		let mut offset = 0;
+		for word in title.split_whitespace() {
+			let start = offset + title[offset..].find(word).unwrap();
+			offset = start + word.len();
+			let trimmed = word.trim_matches(|c: char| !c.is_alphanumeric());
+			if trimmed.is_empty() {
+				continue;
+			}
+			let start = start + word.find(trimmed).unwrap();
+			words.push((start..start + trimmed.len(), trimmed));
+		}
+
+		let mut errors = vec![];
+		let last = words.len().saturating_sub(1);
+		for (id, (range, word)) in words.into_iter().enumerate() {
+			let mut chars = word.chars();
+			let first = chars.next().unwrap();
+			if !first.is_alphabetic() || chars.any(char::is_uppercase) {
+				continue;
+			}
+			let minor = MINOR_WORDS.contains(&word.to_lowercase().as_str());
+			let capitalized = match self {
+				_ if id == 0 => true,
+				HeadingCase::Sentence => false,
+				HeadingCase::Title => id == last || !minor,
+			};
+			if capitalized && first.is_lowercase() {
+				errors.push((range, "capitalized"));
+			} else if !capitalized && first.is_uppercase() && *self == HeadingCase::Sentence {
+				errors.push((range, "lowercase"));
+			}
+		}
+		errors
+	}
+}
+
+impl FromStr for HeadingCase {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.trim() {
+			"sentence" => Ok(HeadingCase::Sentence),
+			"title" => Ok(HeadingCase::Title),
+			_ => Err(format!(
+				"Unknown heading case `{s}`, expected `sentence` or `title`"
+			)),
+		}
+	}
+}
+
+/// Style lints enabled for a document
+#[derive(Debug, Default)]
+struct StyleLints {
+	heading_case: Option<HeadingCase>,
+	max_heading_depth: Option<usize>,
+	heading_levels: bool,
+	max_paragraph_length: Option<usize>,
+}
+
+/// Sizes above which elements are reported, as they likely result from an unterminated construct
+///
+/// Unlike style lints, the limits are enabled by default. A limit of `0` disables it.
+#[derive(Debug)]
+struct SizeLimits {
+	paragraph_lines: Option<usize>,
+	table_rows: Option<usize>,
+	code_lines: Option<usize>,
+}
+
+impl SizeLimits {
+	/// Reads the limits set by the `lint.max_*` variables, reporting invalid values
+	fn new(colors: &ReportColors, document: &dyn Document, reports: &mut Vec<Report>) -> Self {
+		// Invalid limits disable the warning, like invalid lint settings
+		let mut limit = |name: &str, default: usize| {
+			if document.get_variable(name).is_none() {
+				return Some(default);
+			}
+			read_variable(
+				colors,
+				document,
+				name,
+				|value| {
+					value
+						.trim()
+						.parse::<usize>()
+						.map_err(|_| format!("expected a positive integer or `0`, got `{value}`"))
+				},
+				reports,
+			)
+			.filter(|max| *max != 0)
+		};
+		Self {
+			paragraph_lines: limit(PARAGRAPH_LINES_VARIABLE, DEFAULT_PARAGRAPH_LINES),
+			table_rows: limit(TABLE_ROWS_VARIABLE, DEFAULT_TABLE_ROWS),
+			code_lines: limit(CODE_LINES_VARIABLE, DEFAULT_CODE_LINES),
+		}
+	}
+}
+
+impl StyleLints {
+	/// Reads the lints enabled by the `lint.*` variables, reporting invalid values
+	fn new(colors: &ReportColors, document: &dyn Document, reports: &mut Vec<Report>) -> Self {
+		let positive = |value: &str| match value.trim().parse::<usize>() {
+			Ok(value) if value != 0 => Ok(value),
+			_ => Err(format!("expected a positive integer, got `{value}`")),
+		};
+		let boolean = |value: &str| {
+			value
+				.trim()
+				.parse::<bool>()
+				.map_err(|_| format!("expected `true` or `false`, got `{value}`"))
+		};
+
+		Self {
+			heading_case: read_variable(
+				colors,
+				document,
+				HEADING_CASE_VARIABLE,
+				str::parse,
+				reports,
+			),
+			max_heading_depth: read_variable(
+				colors,
+				document,
+				HEADING_DEPTH_VARIABLE,
+				positive,
+				reports,
+			),
+			heading_levels: read_variable(
+				colors,
+				document,
+				HEADING_LEVELS_VARIABLE,
+				boolean,
+				reports,
+			)
+			.unwrap_or(false),
+			max_paragraph_length: read_variable(
+				colors,
+				document,
+				PARAGRAPH_LENGTH_VARIABLE,
+				positive,
+				reports,
+			),
+		}
+	}
+}
+
+/// Reads the value of a lint variable, invalid values are reported and disable the lint
+fn read_variable<T, F>(
+	colors: &ReportColors,
+	document: &dyn Document,
+	name: &str,
+	parse: F,
+	reports: &mut Vec<Report>,
+) -> Option<T>
+where
+	F: Fn(&str) -> Result<T, String>,
+{
+	let var = document.get_variable(name)?;
+	match parse(var.to_string().as_str()) {
+		Ok(value) => Some(value),
+		Err(err) => {
+			report_warn!(
+				reports,
+				var.value_token().source(),
+				"Invalid Lint Setting".into(),
+				span(
+					var.value_token().range.clone(),
+					format!("Invalid value for `{}`: {err}", name.fg(colors.highlight))
+				),
+				note("The lint is disabled".into())
+			);
+			None
+		}
+	}
+}
+
+/// Gets the ranges of the `#`s and of the title of a section, when it was defined in its source
+fn section_ranges(section: &Section) -> Option<(Token, Token)> {
+	let location = &section.location;
+	let source = location.source();
+	let content = source.content().get(location.range.clone())?;
+	let hashes = location.start() + content.find('#')?;
+	let title = location.end().checked_sub(section.title.len())?;
+	if !content.ends_with(section.title.as_str()) || title < hashes + section.depth {
+		return None;
+	}
+	Some((
+		Token::new(hashes..hashes + section.depth, location.source()),
+		Token::new(title..location.end(), location.source()),
+	))
+}
+
+/// Counts the words of the text contained in `elem`
+fn count_words(elem: &dyn Element) -> usize {
+	if let Some(text) = elem.downcast_ref::<Text>() {
+		return text.content.split_whitespace().count();
+	}
+	elem.as_container().map_or(0, |container| {
+		container
+			.contained()
+			.iter()
+			.map(|elem| count_words(elem.as_ref()))
+			.sum()
+	})
+}
+
+/// Lints the sections of a document
+fn lint_section(
+	colors: &ReportColors,
+	lints: &StyleLints,
+	section: &Section,
+	previous_depth: Option<usize>,
+	reports: &mut Vec<Report>,
+) {
+	let ranges = section_ranges(section);
+	let (hashes, title) = ranges
+		.clone()
+		.unwrap_or((section.location.clone(), section.location.clone()));
+
+	if let Some(max) = lints.max_heading_depth.filter(|max| section.depth > *max) {
+		report_lint!(
+			reports,
+			hashes.source(),
+			Lint::Style,
+			"Heading Too Deep".into(),
+			span(
+				hashes.range.clone(),
+				format!(
+					"Section is of depth {}, the maximum depth is {}",
+					section.depth.fg(colors.info),
+					max.fg(colors.info)
+				)
+			)
+		);
+	}
+
+	if let Some(previous) =
+		previous_depth.filter(|previous| lints.heading_levels && section.depth > previous + 1)
+	{
+		report_lint!(
+			reports,
+			hashes.source(),
+			Lint::Style,
+			"Skipped Heading Level".into(),
+			span(
+				hashes.range.clone(),
+				format!(
+					"Section of depth {} follows a section of depth {}",
+					section.depth.fg(colors.info),
+					previous.fg(colors.info)
+				)
+			),
+			help(format!(
+				"Use a section of depth {}",
+				(previous + 1).fg(colors.info)
+			))
+		);
+	}
+
+	if let Some(case) = lints.heading_case {
+		let errors = case.check(section.title.as_str());
+		if errors.is_empty() {
+			return;
+		}
+		let mut report = Report {
+			kind: ReportKind::Lint(Lint::Style),
+			source: title.source(),
+			message: "Incorrect Heading Case".into(),
+			note: None,
+			help: None,
+			spans: vec![],
+		};
+		for (range, expected) in errors {
+			let word = &section.title[range.clone()];
+			let range = match &ranges {
+				Some(_) => title.start() + range.start..title.start() + range.end,
+				None => title.range.clone(),
+			};
+			report.spans.push(ReportSpan {
+				token: Token::new(range, title.source()),
+				message: format!(
+					"`{}` should be {expected} in {} case",
+					word.fg(colors.highlight),
+					case.name().fg(colors.info)
+				),
+			});
+		}
+		reports.push(report);
+	}
+}
+
+/// Gets the range of a paragraph in its source, up to the end of its last visible element
+fn paragraph_range(paragraph: &Paragraph) -> std::ops::Range<usize> {
+	let location = &paragraph.location;
+	paragraph
+		.content
+		.iter()
+		.rev()
+		.filter(|elem| elem.kind() != ElemKind::Invisible)
+		.map(|elem| elem.location())
+		.find(|end| Rc::ptr_eq(&end.source(), &location.source()) && end.end() > location.start())
+		.map_or(location.range.clone(), |end| location.start()..end.end())
+}
+
+/// Gets the first line of an element, where the construct that may be unterminated starts
+fn opening_line(location: &Token) -> Token {
+	let source = location.source();
+	let content = &source.content()[location.range.clone()];
+	let trimmed = content.trim_start();
+	let start = location.start() + content.len() - trimmed.len();
+	let end = start + trimmed.find('\n').unwrap_or(trimmed.len());
+	Token::new(start..end, location.source())
+}
+
+/// Reports the paragraphs, tables and code blocks of `elem` that exceed the size limits
+///
+/// Elements from other sources (e.g imported documents, or the content of blocks) are reported
+/// when their own source is parsed.
+fn check_size(
+	colors: &ReportColors,
+	limits: &SizeLimits,
+	document: &dyn Document,
+	elem: &dyn Element,
+	reports: &mut Vec<Report>,
+) {
+	if !Rc::ptr_eq(&elem.location().source(), &document.source()) {
+		return;
+	}
+
+	let size = if let Some(paragraph) = elem.downcast_ref::<Paragraph>() {
+		limits.paragraph_lines.map(|max| {
+			let source = paragraph.location.source();
+			let lines = source.content()[paragraph_range(paragraph)]
+				.trim()
+				.lines()
+				.count();
+			(lines, max, "lines", PARAGRAPH_LINES_VARIABLE)
+		})
+	} else if let Some(table) = elem.downcast_ref::<Table>() {
+		limits
+			.table_rows
+			.map(|max| (table.size.1, max, "rows", TABLE_ROWS_VARIABLE))
+	} else if let Some(code) = elem.downcast_ref::<Code>() {
+		// Code included from files isn't written in the document
+		limits
+			.code_lines
+			.filter(|_| code.source.is_none())
+			.map(|max| (code.code.lines().count(), max, "lines", CODE_LINES_VARIABLE))
+	} else {
+		None
+	};
+
+	if let Some((size, max, unit, variable)) = size.filter(|(size, max, ..)| size > max) {
+		let opening = opening_line(elem.location());
+		report_warn!(
+			reports,
+			opening.source(),
+			format!("Unusually Large {}", elem.element_name()),
+			span(
+				opening.range.clone(),
+				format!(
+					"{} spans {} {unit}, more than {}",
+					elem.element_name(),
+					size.fg(colors.info),
+					max.fg(colors.info)
+				)
+			),
+			note("This usually means that a construct was not terminated".into()),
+			help(format!(
+				"Set `{}` to raise the limit, or to `0` to disable this warning",
+				variable.fg(colors.info)
+			))
+		);
+	}
+
+	if let Some(container) = elem.as_container() {
+		for elem in container.contained() {
+			check_size(colors, limits, document, elem.as_ref(), reports);
+		}
+	}
+}
+
+/// Lints the length of a paragraph
+fn lint_paragraph(
+	colors: &ReportColors,
+	max: usize,
+	paragraph: &Paragraph,
+	reports: &mut Vec<Report>,
+) {
+	let words = count_words(paragraph);
+	if words <= max {
+		return;
+	}
+
+	let location = &paragraph.location;
+	let range = paragraph_range(paragraph);
+	let source = location.source();
+	let content = &source.content()[range.clone()];
+	let start = range.start + content.len() - content.trim_start().len();
+	report_lint!(
+		reports,
+		location.source(),
+		Lint::Style,
+		"Paragraph Too Long".into(),
+		span(
+			start..range.end,
+			format!(
+				"Paragraph has {} words, the maximum is {}",
+				words.fg(colors.info),
+				max.fg(colors.info)
+			)
+		),
+		help("Split the paragraph".into())
+	);
+}
+
+/// Runs the style lints enabled by the `lint.*` variables on a parsed document
+///
+/// Lints are disabled by default. Only the top-level elements of the document are checked: the
+/// sections, and the paragraphs. Elements exceeding the size limits are also reported as warnings,
+/// see [`SizeLimits`].
+pub fn lint_document(colors: &ReportColors, document: &dyn Document) -> Vec<Report> {
+	let mut reports = vec![];
+	let lints = StyleLints::new(colors, document, &mut reports);
+	let limits = SizeLimits::new(colors, document, &mut reports);
+
+	let mut previous_depth = None;
+	for elem in document.content().borrow().iter() {
+		if let Some(section) = elem.downcast_ref::<Section>() {
+			lint_section(colors, &lints, section, previous_depth, &mut reports);
+			previous_depth = Some(section.depth);
+		} else if let (Some(paragraph), Some(max)) =
+			(elem.downcast_ref::<Paragraph>(), lints.max_paragraph_length)
+		{
+			lint_paragraph(colors, max, paragraph, &mut reports);
+		}
+		check_size(colors, &limits, document, elem.as_ref(), &mut reports);
+	}
+	reports
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::RefCell;
+
+	use super::*;
+	use crate::parser::langparser::LangParser;
+	use crate::parser::parser::ParseMode;
+	use crate::parser::parser::Parser;
+	use crate::parser::parser::ParserState;
+	use crate::parser::source::SourceFile;
+
+	#[test]
+	fn heading_case() {
+		let words = |case: HeadingCase, title: &'static str| {
+			case.check(title)
+				.into_iter()
+				.map(|(range, expected)| (&title[range], expected))
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(
+			words(HeadingCase::Sentence, "using the NML Compiler (draft)"),
+			vec![("using", "capitalized"), ("Compiler", "lowercase")]
+		);
+		assert_eq!(
+			words(HeadingCase::Title, "the art of writing in 3 steps: a guide"),
+			vec![
+				("the", "capitalized"),
+				("art", "capitalized"),
+				("writing", "capitalized"),
+				("steps", "capitalized"),
+				("guide", "capitalized")
+			]
+		);
+		assert!(words(HeadingCase::Title, "Getting Started with NML").is_empty());
+	}
+
+	#[test]
+	fn lints() {
+		let source = Rc::new(SourceFile::with_content(
+			"".to_string(),
+			r#"
+@lint.heading_case = sentence
+@lint.max_heading_depth = 2
+@lint.heading_levels = true
+@lint.max_paragraph_length = 5
+# Getting Started
+### Writing a document
+One two three *four* five six.
+
+Short paragraph.
+"#
+			.to_string(),
+			None,
+		));
+		let lints = RefCell::new(vec![]);
+		let parser = LangParser::new(
+			false,
+			Box::new(|_, reports| {
+				for report in reports {
+					if let ReportKind::Lint(Lint::Style) = report.kind {
+						let span = &report.spans[0];
+						let text =
+							span.token.source().content()[span.token.range.clone()].to_string();
+						lints.borrow_mut().push((report.message, text));
+					}
+				}
+			}),
+		);
+		parser.parse(
+			ParserState::new(&parser, None),
+			source,
+			None,
+			ParseMode::default(),
+		);
+
+		assert_eq!(
+			*lints.borrow(),
+			vec![
+				("Incorrect Heading Case".to_string(), "Started".to_string()),
+				("Heading Too Deep".to_string(), "###".to_string()),
+				("Skipped Heading Level".to_string(), "###".to_string()),
+				(
+					"Paragraph Too Long".to_string(),
+					"One two three *four* five six.".to_string()
+				),
+			]
+		);
+	}
+	#[test]
+	fn sizes() {
+		let source = Rc::new(SourceFile::with_content(
+			"".to_string(),
+			r#"
+@lint.max_paragraph_lines = 2
+@lint.max_table_rows = 1
+@lint.max_code_lines = 2
+First line
+second line.
+
+Long paragraph
+second line
+third line.
+
+|a|
+|b|
+
+```C, main.c
+int a;
+int b;
+``
+Closing delimiter is too short.
+
+```Rust
+let a = 1;
+```
+"#
+			.to_string(),
+			None,
+		));
+		let warnings = RefCell::new(vec![]);
+		let parser = LangParser::new(
+			false,
+			Box::new(|_, reports| {
+				for report in reports {
+					if let ReportKind::Warning = report.kind {
+						let span = &report.spans[0];
+						let text =
+							span.token.source().content()[span.token.range.clone()].to_string();
+						warnings.borrow_mut().push((report.message, text));
+					}
+				}
+			}),
+		);
+		parser.parse(
+			ParserState::new(&parser, None),
+			source,
+			None,
+			ParseMode::default(),
+		);
+
+		assert_eq!(
+			*warnings.borrow(),
+			vec![
+				(
+					"Unusually Large Paragraph".to_string(),
+					"Long paragraph".to_string()
+				),
+				("Unusually Large Table".to_string(), "|a|".to_string()),
+				(
+					"Unusually Large Code Block".to_string(),
+					"```C, main.c".to_string()
+				),
+				// Remainder of the block swallowed by the unterminated code block
+				("Unusually Large Paragraph".to_string(), "Rust".to_string()),
+			]
+		);
+	}
+}
@@ -1,9 +1,15 @@
+pub mod balance;
+pub mod budget;
+pub mod cancel;
+pub mod codes;
 pub mod langparser;
+pub mod lint;
 pub mod parser;
 pub mod property;
 pub mod reports;
 pub mod rule;
 pub mod source;
+pub mod sourcemap;
 pub mod state;
 pub mod style;
 pub mod util;
@@ -3,6 +3,9 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use unicode_segmentation::UnicodeSegmentation;
 
+use super::budget::BudgetTracker;
+use super::budget::ParseBudget;
+use super::cancel::Cancellation;
 use super::reports::Report;
 use super::rule::Rule;
 use super::source::Cursor;
@@ -57,9 +60,6 @@ impl ReportColors {
 pub struct SharedState {
 	pub rule_state: RefCell<RuleStateHolder>,
 
-	/// The lua [`Kernel`]s
-	pub kernels: RefCell<KernelHolder>,
-
 	/// The styles
 	pub styles: RefCell<StyleHolder>,
 
@@ -74,6 +74,20 @@ pub struct SharedState {
 
 	/// The lsp data
 	pub lsp: Option<RefCell<LSPData>>,
+
+	/// Token to abort the parse, see [`ParserState::with_cancellation`]
+	pub cancellation: RefCell<Cancellation>,
+
+	/// Work done by the parse, see [`ParserState::with_budget`]
+	pub budget: RefCell<BudgetTracker>,
+
+	/// Sources parsed with this state, see [`crate::parser::sourcemap`]
+	pub sources: RefCell<Vec<Rc<dyn Source>>>,
+
+	/// The lua [`Kernel`]s
+	///
+	/// Declared last so that it is dropped after the lua functions held by other fields
+	pub kernels: RefCell<KernelHolder>,
 }
 
 impl SharedState {
@@ -87,6 +101,9 @@ impl SharedState {
 			blocks: RefCell::new(BlockHolder::default()),
 			custom_styles: RefCell::new(CustomStyleHolder::default()),
 			lsp: enable_semantics.then_some(RefCell::new(LSPData::new())),
+			cancellation: RefCell::new(Cancellation::new()),
+			budget: RefCell::new(BudgetTracker::default()),
+			sources: RefCell::new(vec![]),
 		};
 
 		// Register default kernel
@@ -173,6 +190,36 @@ impl<'a, 'b> ParserState<'a, 'b> {
 		f(new_state)
 	}
 
+	/// Gets whether this state is the state of a top-level document
+	///
+	/// Top-level documents may still have a parent document, e.g to hold the variables defined
+	/// from the command line
+	pub fn is_root(&self) -> bool { self.parent.is_none() }
+
+	/// Sets the token used to abort the parse, it is shared with the states created from this
+	/// state
+	pub fn with_cancellation(self, cancellation: Cancellation) -> Self {
+		*self.shared.cancellation.borrow_mut() = cancellation;
+		self
+	}
+
+	/// Whether the parse has been cancelled, see [`Cancellation`]
+	pub fn is_cancelled(&self) -> bool { self.shared.cancellation.borrow().is_cancelled() }
+
+	/// Sets the budget of the parse, it is shared with the states created from this state
+	///
+	/// The duration of the budget starts from this call.
+	pub fn with_budget(self, budget: ParseBudget) -> Self {
+		*self.shared.budget.borrow_mut() = BudgetTracker::new(budget);
+		self
+	}
+
+	/// Whether the parse has exceeded its budget, see [`ParseBudget`]
+	pub fn budget_exceeded(&self) -> bool { self.shared.budget.borrow().exceeded() }
+
+	/// Accounts for a rule match in the budget of the parse, see [`BudgetTracker::spend`]
+	pub fn spend_budget(&self) -> Result<(), String> { self.shared.budget.borrow_mut().spend() }
+
 	/// Updates matches from a given start position e.g [`Cursor`]
 	///
 	/// # Return
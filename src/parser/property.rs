@@ -3,6 +3,7 @@ use std::fmt::Display;
 use std::ops::Range;
 
 use ariadne::Fmt;
+use lsp::hover::Hover;
 use lsp::semantic::Semantics;
 
 use crate::parser::reports::macros::*;
@@ -16,6 +17,8 @@ use super::source::Token;
 pub struct Property {
 	description: String,
 	default: Option<String>,
+	/// Whether the property can be set without specifying its name, see [`PropertyParser::parse`]
+	primary: bool,
 }
 
 impl Property {
@@ -23,8 +26,18 @@ impl Property {
 		Self {
 			description,
 			default,
+			primary: false,
 		}
 	}
+
+	/// Makes this property the primary property of its [`PropertyParser`]
+	///
+	/// The primary property can be set positionally: `[200px]` is equivalent to `[width=200px]` if
+	/// `width` is the primary property. There should be at most one primary property per parser.
+	pub fn primary(mut self) -> Self {
+		self.primary = true;
+		self
+	}
 }
 
 impl core::fmt::Display for Property {
@@ -186,12 +199,105 @@ impl<'a> PropertyMap<'a> {
 	}
 }
 
+/// Documentation for a registered property, see [`property_docs`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyDoc {
+	/// Name of the element the property belongs to
+	pub element: String,
+	/// Name of the property
+	pub name: String,
+	/// Description of the property
+	pub description: String,
+	/// Default value of the property
+	pub default: Option<String>,
+	/// Whether the property can be set positionally
+	pub primary: bool,
+}
+
+impl PropertyDoc {
+	/// Formats the documentation as markdown, used by the language server
+	pub fn to_markdown(&self) -> String {
+		let mut result = format!("**{}** — {} property", self.name, self.element);
+		if self.primary {
+			result += " (primary)";
+		}
+		result += format!("\n\n{}", self.description).as_str();
+		if let Some(default) = &self.default {
+			result += format!("\n\nDefault: `{default}`").as_str();
+		}
+		result
+	}
+}
+
+/// Gets the documentation of every property registered by the parser's rules, blocks and layouts
+///
+/// Entries are sorted by element, then by name.
+pub fn property_docs(state: &ParserState) -> Vec<PropertyDoc> {
+	let mut docs = vec![];
+	for rule in state.parser.rules() {
+		for (element, parser) in rule.properties() {
+			docs.extend(parser.docs(element));
+		}
+	}
+	for block in state.shared.blocks.borrow().iter() {
+		if let Some(parser) = block.properties() {
+			docs.extend(parser.docs(format!("Block {}", block.name()).as_str()));
+		}
+	}
+	for layout in state.shared.layouts.borrow().iter() {
+		if let Some(parser) = layout.properties() {
+			docs.extend(parser.docs(format!("Layout {}", layout.name()).as_str()));
+		}
+	}
+	docs.sort_by(|l, r| l.element.cmp(&r.element).then_with(|| l.name.cmp(&r.name)));
+	docs
+}
+
 #[derive(Debug)]
 pub struct PropertyParser {
 	pub properties: HashMap<String, Property>,
 }
 
 impl PropertyParser {
+	/// Gets the documentation for the properties of this parser, sorted by name
+	pub fn docs(&self, element: &str) -> Vec<PropertyDoc> {
+		let mut docs = self
+			.properties
+			.iter()
+			.map(|(name, prop)| PropertyDoc {
+				element: element.to_string(),
+				name: name.clone(),
+				description: prop.description.clone(),
+				default: prop.default.clone(),
+				primary: prop.primary,
+			})
+			.collect::<Vec<_>>();
+		docs.sort_by(|l, r| l.name.cmp(&r.name));
+		docs
+	}
+
+	/// Formats the documentation of the properties as a markdown list, used by the language server
+	pub fn to_markdown(&self) -> String {
+		self.docs("")
+			.iter()
+			.fold(String::from("**Properties**"), |out, doc| {
+				let mut entry = format!("\n - `{}`", doc.name);
+				if doc.primary {
+					entry += " (primary)";
+				}
+				entry += format!(": {}", doc.description).as_str();
+				if let Some(default) = &doc.default {
+					entry += format!(" (Default: `{default}`)").as_str();
+				}
+				out + entry.as_str()
+			})
+	}
+
+	/// Gets the primary property, see [`Property::primary`]
+	fn primary(&self) -> Option<(&String, &Property)> {
+		self.properties.iter().find(|(_, prop)| prop.primary)
+	}
+
 	fn allowed_properties(&self, state: &ParserState) -> String {
 		self.properties
 			.iter()
@@ -212,6 +318,14 @@ impl PropertyParser {
 	/// Property names/values are separated by a single '=' that cannot be escaped.
 	/// Therefore names cannot contain the '=' character.
 	///
+	/// # Flags and shorthand
+	///
+	/// A property name without a value is a flag, its value is set to `true`: `[centered]` is
+	/// equivalent to `[centered=true]`.
+	///
+	/// If the first entry is a single value that isn't the name of a property, it is used as the
+	/// value of the primary property (see [`Property::primary`]).
+	///
 	/// # Language Server
 	///
 	/// This function also processes properties to add them to the language server's semantics.
@@ -249,29 +363,69 @@ impl PropertyParser {
 		let mut pm = PropertyMap::new(token.clone(), rule_name, state);
 		let mut try_insert = |name: &String,
 		                      name_range: Range<usize>,
-		                      value: &String,
-		                      value_range: Range<usize>|
+		                      value: Option<(&String, Range<usize>)>|
 		 -> bool {
 			let trimmed_name = name.trim_start().trim_end();
-			let trimmed_value = value.trim_start().trim_end();
-			let prop = match self.properties.get(trimmed_name) {
-				None => {
-					report_err!(
-						&mut reports,
-						token.source(),
-						format!("Failed to parse {rule_name} properties"),
-						span(
-							name_range,
-							format!(
-								"Unknown property {}, allowed properties:{}",
-								name.fg(state.parser.colors().info),
-								self.allowed_properties(state)
-							)
-						),
-					);
-					return false;
-				}
-				Some(prop) => prop,
+			let (trimmed_name, prop, trimmed_value, name_range, value_range) = match value {
+				Some((value, value_range)) => match self.properties.get(trimmed_name) {
+					None => {
+						report_err!(
+							&mut reports,
+							token.source(),
+							format!("Failed to parse {rule_name} properties"),
+							span(
+								name_range,
+								format!(
+									"Unknown property {}, allowed properties:{}",
+									name.fg(state.parser.colors().info),
+									self.allowed_properties(state)
+								)
+							),
+						);
+						return false;
+					}
+					Some(prop) => (
+						trimmed_name,
+						prop,
+						value.trim_start().trim_end(),
+						name_range,
+						value_range,
+					),
+				},
+				None => match (self.properties.get_key_value(trimmed_name), self.primary()) {
+					// Flag
+					(Some((key, prop)), _) => (
+						key.as_str(),
+						prop,
+						"true",
+						name_range.clone(),
+						name_range.end..name_range.end,
+					),
+					// Positional shorthand for the primary property
+					(None, Some((key, prop))) if pm.properties.is_empty() => (
+						key.as_str(),
+						prop,
+						trimmed_name,
+						name_range.start..name_range.start,
+						name_range,
+					),
+					_ => {
+						report_err!(
+							&mut reports,
+							token.source(),
+							format!("Failed to parse {rule_name} properties"),
+							span(
+								name_range,
+								format!(
+									"Unknown property {}, allowed properties:{}",
+									name.fg(state.parser.colors().info),
+									self.allowed_properties(state)
+								)
+							),
+						);
+						return false;
+					}
+				},
 			};
 
 			if let Some((_, previous)) = pm.properties.insert(
@@ -335,7 +489,11 @@ impl PropertyParser {
 						in_name = true;
 						value_range.end = pos;
 
-						if !try_insert(&name, name_range.clone(), &value, value_range.clone()) {
+						if !try_insert(
+							&name,
+							name_range.clone(),
+							Some((&value, value_range.clone())),
+						) {
 							return None;
 						}
 						name_range.start = pos + 1;
@@ -347,6 +505,30 @@ impl PropertyParser {
 						value.push(',');
 						escaped = 0;
 					}
+				} else if c == ',' && escaped % 2 == 0 {
+					// Flag or positional value
+					(0..escaped).for_each(|_| name.push('\\'));
+					escaped = 0;
+					name_range.end = pos;
+					if name.trim_start().trim_end().is_empty() {
+						report_err!(
+							&mut reports,
+							token.source(),
+							format!("Failed to parse {rule_name} properties"),
+							span(
+								name_range.start..pos + 1,
+								format!("Expected property before ','",)
+							),
+						);
+						return None;
+					}
+
+					if !try_insert(&name, name_range.clone(), None) {
+						return None;
+					}
+					name_range.start = pos + 1;
+
+					name.clear();
 				} else {
 					if in_name {
 						(0..escaped).for_each(|_| name.push('\\'));
@@ -358,8 +540,32 @@ impl PropertyParser {
 					escaped = 0;
 				}
 			}
-			(0..escaped).for_each(|_| value.push('\\'));
-			if !in_name && value.trim_end().trim_start().is_empty() {
+			if in_name {
+				(0..escaped).for_each(|_| name.push('\\'));
+			} else {
+				(0..escaped).for_each(|_| value.push('\\'));
+			}
+
+			if in_name {
+				// Flag or positional value
+				name_range.end = token.end();
+				if name.trim_end().trim_start().is_empty() {
+					report_err!(
+						&mut reports,
+						token.source(),
+						format!("Failed to parse {rule_name} properties"),
+						span(
+							name_range.start..token.end(),
+							format!("Expected property after last ','",)
+						),
+					);
+					return None;
+				}
+
+				if !try_insert(&name, name_range.clone(), None) {
+					return None;
+				}
+			} else if value.trim_end().trim_start().is_empty() {
 				report_err!(
 					&mut reports,
 					token.source(),
@@ -370,7 +576,7 @@ impl PropertyParser {
 					),
 				);
 				return None;
-			} else if name.is_empty() || value.is_empty() {
+			} else if name.trim_end().trim_start().is_empty() {
 				report_err!(
 					&mut reports,
 					token.source(),
@@ -381,11 +587,15 @@ impl PropertyParser {
 					),
 				);
 				return None;
-			}
-
-			value_range.end = token.end();
-			if !try_insert(&name, name_range.clone(), &value, value_range.clone()) {
-				return None;
+			} else {
+				value_range.end = token.end();
+				if !try_insert(
+					&name,
+					name_range.clone(),
+					Some((&value, value_range.clone())),
+				) {
+					return None;
+				}
 			}
 		}
 
@@ -397,12 +607,19 @@ impl PropertyParser {
 						tokens.prop_comma,
 					);
 				}
-				sems.add_to_queue(value.name_range.clone(), tokens.prop_name);
-				sems.add_to_queue(
-					value.name_range.end..value.value_range.start,
-					tokens.prop_equal,
-				);
-				sems.add_to_queue(value.value_range.clone(), tokens.prop_value);
+				// Flags and positional values have an empty value or name
+				if !value.name_range.is_empty() {
+					sems.add_to_queue(value.name_range.clone(), tokens.prop_name);
+				}
+				if value.name_range.end < value.value_range.start {
+					sems.add_to_queue(
+						value.name_range.end..value.value_range.start,
+						tokens.prop_equal,
+					);
+				}
+				if !value.value_range.is_empty() {
+					sems.add_to_queue(value.value_range.clone(), tokens.prop_value);
+				}
 			}
 			//sems.add(matches.get(0).unwrap().start()..matches.get(0).unwrap().start()+1, tokens.media_sep);
 			// Refname
@@ -411,6 +628,22 @@ impl PropertyParser {
 			//sems.add(matches.get(1).unwrap().end()..matches.get(1).unwrap().end()+1, tokens.media_refname_sep);
 		}
 
+		if let Some(hover) = Hover::from_source(token.source(), &state.shared.lsp) {
+			for (name, (prop, value)) in &pm.properties {
+				if value.name_range.is_empty() {
+					continue;
+				}
+				let doc = PropertyDoc {
+					element: rule_name.to_string(),
+					name: name.clone(),
+					description: prop.description.clone(),
+					default: prop.default.clone(),
+					primary: prop.primary,
+				};
+				hover.add(value.name_range.clone(), doc.to_markdown());
+			}
+		}
+
 		// Insert missing properties with a default
 		for (name, prop) in &self.properties {
 			if pm.properties.contains_key(name) {
@@ -519,4 +752,103 @@ mod tests {
 			.get(&mut reports, "height", |_, s| s.value.parse::<f64>())
 			.is_none());
 	}
+
+	#[test]
+	fn flags_and_shorthand() {
+		let mut properties = HashMap::new();
+		properties.insert(
+			"width".to_string(),
+			Property::new("Width of the element".to_string(), None).primary(),
+		);
+		properties.insert(
+			"centered".to_string(),
+			Property::new("Center the element".to_string(), Some("false".to_string())),
+		);
+		properties.insert(
+			"caption".to_string(),
+			Property::new("Caption".to_string(), None),
+		);
+
+		let langparser = LangParser::default();
+		let state = ParserState::new(&langparser, None);
+		let mut reports = vec![];
+
+		let parser = PropertyParser { properties };
+		let source = Rc::new(SourceFile::with_content(
+			"".into(),
+			"200px, centered, caption=A\\, B".into(),
+			None,
+		)) as Rc<dyn Source>;
+		let pm = parser
+			.parse("Test", &mut reports, &state, source.into())
+			.unwrap();
+		assert_eq!(
+			pm.get(&mut reports, "width", |_, s| Result::<_, String>::Ok(
+				s.value.clone()
+			))
+			.unwrap(),
+			"200px"
+		);
+		assert!(pm
+			.get(&mut reports, "centered", |_, s| s.value.parse::<bool>())
+			.unwrap());
+		assert_eq!(
+			pm.get(&mut reports, "caption", |_, s| Result::<_, String>::Ok(
+				s.value.clone()
+			))
+			.unwrap(),
+			"A, B"
+		);
+
+		// Flag only
+		let source =
+			Rc::new(SourceFile::with_content("".into(), "centered".into(), None)) as Rc<dyn Source>;
+		let pm = parser
+			.parse("Test", &mut reports, &state, source.into())
+			.unwrap();
+		assert!(pm.properties.get("width").is_none());
+		assert_eq!(pm.properties.get("centered").unwrap().1.value, "true");
+		assert!(reports.is_empty());
+
+		// Positional value must come first
+		let source = Rc::new(SourceFile::with_content(
+			"".into(),
+			"centered, 200px".into(),
+			None,
+		)) as Rc<dyn Source>;
+		assert!(parser
+			.parse("Test", &mut reports, &state, source.into())
+			.is_none());
+		assert!(!reports.is_empty());
+	}
+
+	#[test]
+	fn docs() {
+		let langparser = LangParser::default();
+		let state = ParserState::new(&langparser, None);
+		let docs = property_docs(&state);
+
+		let width = docs
+			.iter()
+			.find(|doc| doc.element == "Media" && doc.name == "width")
+			.unwrap();
+		assert!(width.primary);
+		assert!(docs
+			.iter()
+			.any(|doc| doc.element == "Table Cell" && doc.name == "chspan"));
+		assert!(docs
+			.iter()
+			.any(|doc| doc.element == "Block Quote" && doc.name == "author"));
+		let title = docs
+			.iter()
+			.find(|doc| doc.element == "Layout Spoiler" && doc.name == "title")
+			.unwrap();
+		assert_eq!(title.default, Some("".to_string()));
+		assert!(title.to_markdown().contains("Spoiler title"));
+
+		// Sorted by element, then name
+		assert!(docs
+			.windows(2)
+			.all(|w| (&w[0].element, &w[0].name) <= (&w[1].element, &w[1].name)));
+	}
 }
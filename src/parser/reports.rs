@@ -7,16 +7,40 @@ use tower_lsp::lsp_types::Diagnostic;
 
 use crate::parser::source::LineCursor;
 
+use super::codes::ReportCode;
 use super::parser::ReportColors;
 use super::source::OffsetEncoding;
 use super::source::Source;
 use super::source::SourcePosition;
 use super::source::Token;
 
+/// Categories of lints, i.e reports about valid documents that may be improved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lint {
+	/// Accessibility of the generated documents
+	A11y,
+	/// Writing style, enabled by the `lint.*` variables, see [`crate::parser::lint`]
+	Style,
+	/// Unbalanced begin/end markers, see [`crate::parser::balance`]
+	Balance,
+}
+
+impl Lint {
+	/// Gets the name of the lint category
+	pub fn name(&self) -> &'static str {
+		match self {
+			Lint::A11y => "a11y",
+			Lint::Style => "style",
+			Lint::Balance => "balance",
+		}
+	}
+}
+
 #[derive(Debug)]
 pub enum ReportKind {
 	Error,
 	Warning,
+	Lint(Lint),
 }
 
 impl From<&ReportKind> for ariadne::ReportKind<'static> {
@@ -24,6 +48,11 @@ impl From<&ReportKind> for ariadne::ReportKind<'static> {
 		match val {
 			ReportKind::Error => ariadne::ReportKind::Error,
 			ReportKind::Warning => ariadne::ReportKind::Warning,
+			ReportKind::Lint(lint) => match lint {
+				Lint::A11y => ariadne::ReportKind::Custom("Lint(a11y)", ariadne::Color::Cyan),
+				Lint::Style => ariadne::ReportKind::Custom("Lint(style)", ariadne::Color::Cyan),
+				Lint::Balance => ariadne::ReportKind::Custom("Lint(balance)", ariadne::Color::Cyan),
+			},
 		}
 	}
 }
@@ -33,6 +62,7 @@ impl From<&ReportKind> for tower_lsp::lsp_types::DiagnosticSeverity {
 		match val {
 			ReportKind::Error => tower_lsp::lsp_types::DiagnosticSeverity::ERROR,
 			ReportKind::Warning => tower_lsp::lsp_types::DiagnosticSeverity::WARNING,
+			ReportKind::Lint(_) => tower_lsp::lsp_types::DiagnosticSeverity::INFORMATION,
 		}
 	}
 }
@@ -54,10 +84,16 @@ pub struct Report {
 }
 
 impl Report {
+	/// Gets the stable code of the report, see [`ReportCode`]
+	pub fn code(&self) -> Option<&'static str> {
+		ReportCode::from_message(self.message.as_str()).map(|code| code.code)
+	}
+
 	fn ariadne_color(kind: &ReportKind, colors: &ReportColors) -> ariadne::Color {
 		match kind {
 			ReportKind::Error => colors.error.unwrap_or(ariadne::Color::Primary),
 			ReportKind::Warning => colors.warning.unwrap_or(ariadne::Color::Primary),
+			ReportKind::Lint(_) => colors.info.unwrap_or(ariadne::Color::Primary),
 		}
 	}
 
@@ -82,8 +118,12 @@ impl Report {
 			start = 0;
 		}
 		cache.insert(source.clone(), source.content().clone());
-		let mut builder = ariadne::Report::build((&self.kind).into(), self.source, start)
-			.with_message(self.message);
+		let mut builder = ariadne::Report::build((&self.kind).into(), self.source.clone(), start)
+			.with_config(ariadne::Config::default().with_color(colors.error.is_some()));
+		if let Some(code) = self.code() {
+			builder = builder.with_code(code);
+		}
+		builder = builder.with_message(self.message);
 
 		for span in self.spans {
 			cache.insert(span.token.source(), span.token.source().content().clone());
@@ -110,7 +150,21 @@ impl Report {
 		});
 	}
 
+	/// Formats reports as text, as they would be displayed by [`Report::reports_to_stdout`]
+	pub fn reports_to_string(colors: &ReportColors, mut reports: Vec<Report>) -> String {
+		let mut result = vec![];
+		reports.drain(..).for_each(|report| {
+			let (report, cache) = report.to_ariadne(colors);
+			report.write(cache, &mut result).unwrap();
+		});
+		String::from_utf8_lossy(&result).to_string()
+	}
+
 	fn to_diagnostics(self, diagnostic_map: &DashMap<String, Vec<Diagnostic>>) {
+		let code = match &self.kind {
+			ReportKind::Lint(lint) => Some(lint.name()),
+			_ => self.code(),
+		};
 		for span in self.spans {
 			let (source, range) = span.token.source().original_range(span.token.range.clone());
 
@@ -131,7 +185,8 @@ impl Report {
 					},
 				},
 				severity: Some((&self.kind).into()),
-				code: None,
+				code: code
+					.map(|code| tower_lsp::lsp_types::NumberOrString::String(code.to_string())),
 				code_description: None,
 				source: None,
 				message: format!("{}: {}", self.message, span.message),
@@ -219,5 +274,21 @@ pub mod macros {
 		}}
 	}
 
+	#[macro_export]
+	macro_rules! report_lint {
+		($reports:expr, $source:expr, $lint:expr, $message:expr, $($tail:tt)*) => {{
+			let mut r = Report {
+				kind: ReportKind::Lint($lint),
+				source: $source,
+				message: $message,
+				note: None,
+				help: None,
+				spans: vec![],
+			};
+			report_label!(r, $($tail)*);
+			$reports.push(r);
+		}}
+	}
+
 	pub use crate::*;
 }
@@ -1,23 +1,27 @@
 use super::parser::ParseMode;
 use super::parser::ParserState;
+use super::property::PropertyParser;
 use super::reports::Report;
 use super::source::Cursor;
 use super::source::Token;
 use crate::document::document::Document;
+use crate::lua::kernel::Binding;
 use downcast_rs::impl_downcast;
 use downcast_rs::Downcast;
-use mlua::Function;
 use mlua::Lua;
 
 use std::any::Any;
 use std::collections::HashMap;
 
 macro_rules! create_registry {
-	( $($construct:expr),+ $(,)? ) => {{
+	( $( $(@cfg($cfg:meta))? $construct:expr ),+ $(,)? ) => {{
 		let mut map = HashMap::new();
 		$(
-			let boxed = Box::new($construct) as Box<dyn Rule>;
-			map.insert(boxed.name(), boxed);
+			$(#[cfg($cfg)])?
+			{
+				let boxed = Box::new($construct) as Box<dyn Rule>;
+				map.insert(boxed.name(), boxed);
+			}
 		)+
 		map
 	}};
@@ -107,7 +111,10 @@ pub trait Rule: Downcast {
 	) -> (Cursor, Vec<Report>);
 
 	/// Registers lua bindings for this rule
-	fn register_bindings<'lua>(&self, _lua: &'lua Lua) -> Vec<(String, Function<'lua>)> { vec![] }
+	fn register_bindings<'lua>(&self, _lua: &'lua Lua) -> Vec<Binding<'lua>> { vec![] }
+
+	/// Returns the property schemas of this rule, along with the name of the element they apply to
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> { vec![] }
 }
 impl_downcast!(Rule);
 
@@ -145,7 +152,9 @@ pub trait RegexRule {
 		matches: regex::Captures,
 	) -> Vec<Report>;
 
-	fn register_bindings<'lua>(&self, _lua: &'lua Lua) -> Vec<(String, Function<'lua>)> { vec![] }
+	fn register_bindings<'lua>(&self, _lua: &'lua Lua) -> Vec<Binding<'lua>> { vec![] }
+
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> { vec![] }
 }
 
 impl<T: RegexRule + 'static> Rule for T {
@@ -153,6 +162,8 @@ impl<T: RegexRule + 'static> Rule for T {
 
 	fn previous(&self) -> Option<&'static str> { RegexRule::previous(self) }
 
+	fn properties(&self) -> Vec<(&'static str, &PropertyParser)> { RegexRule::properties(self) }
+
 	/// Finds the next match starting from [`Cursor`]
 	fn next_match(
 		&self,
@@ -203,7 +214,7 @@ impl<T: RegexRule + 'static> Rule for T {
 		)
 	}
 
-	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<(String, Function<'lua>)> {
+	fn register_bindings<'lua>(&self, lua: &'lua Lua) -> Vec<Binding<'lua>> {
 		self.register_bindings(lua)
 	}
 }
@@ -224,6 +235,7 @@ mod tests {
 				"Comment",
 				"Paragraph",
 				"Import",
+				"Conditional",
 				"Script",
 				"Element Style",
 				"Variable",
@@ -232,14 +244,20 @@ mod tests {
 				"List",
 				"Block",
 				"Code",
+				"Code Callout",
 				"Tex",
 				"Graphviz",
+				"Render",
 				"Media",
 				"Layout",
 				"Toc",
 				"Table",
 				"Style",
 				"Custom Style",
+				"Color",
+				"Diff",
+				"Break",
+				"Icon",
 				"Section",
 				"Link",
 				"Text",
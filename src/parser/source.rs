@@ -1,6 +1,7 @@
 use core::fmt::Debug;
 use std::fs;
 use std::ops::Range;
+use std::path::PathBuf;
 use std::rc::Rc;
 
 use downcast_rs::impl_downcast;
@@ -83,6 +84,38 @@ impl SourceFile {
 
 	/// Gets the path of this [`SourceFile`]
 	pub fn path(&self) -> &String { &self.path }
+
+	/// Gets the directory of this [`SourceFile`], paths in the file are relative to it
+	///
+	/// Returns `None` if the source has no path (e.g in tests).
+	pub fn directory(&self) -> Option<PathBuf> {
+		if self.path.is_empty() {
+			return None;
+		}
+		let path = self
+			.path
+			.strip_prefix("file://")
+			.filter(|path| path.starts_with('/'))
+			.unwrap_or(&self.path);
+		let path = PathBuf::from(path);
+		let mut path = path.canonicalize().unwrap_or(path);
+		path.pop();
+		Some(path)
+	}
+}
+
+/// Resolves a path found in `source`, relative to the directory of the file it comes from
+///
+/// Paths are resolved relative to the working directory when `source` doesn't come from a file.
+pub fn resolve_path(source: &Rc<dyn Source>, path: &str) -> PathBuf {
+	let (file, _) = source.original_position(0);
+	match file
+		.downcast_ref::<SourceFile>()
+		.and_then(SourceFile::directory)
+	{
+		Some(directory) => directory.join(path),
+		None => PathBuf::from(path),
+	}
 }
 
 impl Source for SourceFile {
@@ -170,6 +203,14 @@ impl VirtualSource {
 			offsets: Some(SourceOffset { offsets }),
 		}
 	}
+
+	/// Gets the offsets relative to the [`Self::location`]'s source, as positions in this source
+	/// and the offset applied from them
+	pub fn offsets(&self) -> &[(usize, isize)] {
+		self.offsets
+			.as_ref()
+			.map_or(&[], |offsets| offsets.offsets.as_slice())
+	}
 }
 
 impl Source for VirtualSource {
@@ -0,0 +1,135 @@
+use std::rc::Rc;
+
+use super::source::Source;
+use super::source::SourcePosition;
+use super::source::VirtualSource;
+
+/// Formats a source of the map, with its range in its parent and its offsets
+fn describe(source: &Rc<dyn Source>) -> String {
+	let mut description = source.name().clone();
+	if let Some(location) = source.location() {
+		description += format!(" [{}..{}]", location.start(), location.end()).as_str();
+	}
+	description += format!(" ({} bytes)", source.content().len()).as_str();
+	if let Some(offsets) = source
+		.downcast_ref::<VirtualSource>()
+		.map(VirtualSource::offsets)
+		.filter(|offsets| !offsets.is_empty())
+	{
+		let offsets = offsets
+			.iter()
+			.map(|(pos, offset)| format!("{pos}:{offset:+}"))
+			.collect::<Vec<_>>();
+		description += format!(", offsets {}", offsets.join(" ")).as_str();
+	}
+	// Range in the original file, for nested sources
+	if let Some(location) = source.location() {
+		let (original, range) = source.original_range(0..source.content().len());
+		if !Rc::ptr_eq(&original, &location.source()) {
+			description += format!(
+				", from {} [{}..{}]",
+				original.name(),
+				range.start,
+				range.end
+			)
+			.as_str();
+		}
+	}
+	description
+}
+
+/// Formats `source` and its children, with `prefix` before each line
+fn render(sources: &[Rc<dyn Source>], source: &Rc<dyn Source>, prefix: &str, result: &mut String) {
+	let mut children = sources
+		.iter()
+		.filter(|child| {
+			child
+				.location()
+				.is_some_and(|location| Rc::ptr_eq(&location.source(), source))
+		})
+		.collect::<Vec<_>>();
+	children.sort_by_key(|child| child.location().unwrap().start());
+
+	for (id, child) in children.iter().enumerate() {
+		let last = id + 1 == children.len();
+		*result += format!(
+			"{prefix}{} {}\n",
+			if last { "└─" } else { "├─" },
+			describe(child)
+		)
+		.as_str();
+		render(
+			sources,
+			child,
+			format!("{prefix}{}", if last { "   " } else { "│  " }).as_str(),
+			result,
+		);
+	}
+}
+
+/// Formats the tree of `sources`, with the range of each source in its parent and in the original
+/// file
+///
+/// The ancestors of `sources` are also part of the tree, and sources are only listed once. This is
+/// used by the `-z sourcemap` debug option, to find the sources with incorrect positions.
+pub fn source_map(sources: &[Rc<dyn Source>]) -> String {
+	let mut all: Vec<Rc<dyn Source>> = vec![];
+	for source in sources {
+		let mut current = Some(source.clone());
+		while let Some(source) = current {
+			if all.iter().any(|other| Rc::ptr_eq(other, &source)) {
+				break;
+			}
+			current = source.location().map(|location| location.source());
+			all.push(source);
+		}
+	}
+
+	let mut result = String::new();
+	for root in all.iter().filter(|source| source.location().is_none()) {
+		result += format!("{}\n", describe(root)).as_str();
+		render(&all, root, "", &mut result);
+	}
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::parser::source::SourceFile;
+	use crate::parser::source::Token;
+
+	use super::*;
+
+	#[test]
+	fn tree() {
+		let file: Rc<dyn Source> = Rc::new(SourceFile::with_content(
+			"input.nml".into(),
+			"[*li\\nk*](url) %<print(1)>%".into(),
+			None,
+		));
+		let link: Rc<dyn Source> = Rc::new(VirtualSource::new_offsets(
+			Token::new(1..8, file.clone()),
+			"Link Display".into(),
+			"*link*".into(),
+			vec![(3, 1)],
+		));
+		let style: Rc<dyn Source> = Rc::new(VirtualSource::new(
+			Token::new(1..5, link.clone()),
+			"Style".into(),
+			"link".into(),
+		));
+		let lua: Rc<dyn Source> = Rc::new(VirtualSource::new(
+			Token::new(17..25, file.clone()),
+			":LUA:main".into(),
+			"print(1)".into(),
+		));
+
+		assert_eq!(
+			source_map(&[lua, style, file.clone()]),
+			"input.nml (27 bytes)\n\
+			├─ Link Display [1..8] (6 bytes), offsets 3:+1\n\
+			│  └─ Style [1..5] (4 bytes), from input.nml [2..7]\n\
+			└─ :LUA:main [17..25] (8 bytes)\n"
+		);
+	}
+}
@@ -24,6 +24,9 @@ pub trait ElementStyle: Downcast + core::fmt::Debug {
 		lua: &mlua::Lua,
 		value: mlua::Value,
 	) -> Result<Rc<dyn ElementStyle>, mlua::Error>;
+
+	/// Serializes the style to json
+	fn to_json(&self) -> serde_json::Value;
 }
 impl_downcast!(ElementStyle);
 
@@ -33,11 +36,14 @@ pub struct StyleHolder {
 }
 
 macro_rules! create_styles {
-	( $($construct:expr),+ $(,)? ) => {{
+	( $( $(@cfg($cfg:meta))? $construct:expr ),+ $(,)? ) => {{
 		let mut map = HashMap::new();
 		$(
-			let val = Rc::new($construct) as Rc<dyn ElementStyle>;
-			map.insert(val.key().to_string(), val);
+			$(#[cfg($cfg)])?
+			{
+				let val = Rc::new($construct) as Rc<dyn ElementStyle>;
+				map.insert(val.key().to_string(), val);
+			}
 		)+
 		map
 	}};
@@ -71,6 +77,13 @@ impl StyleHolder {
 	pub fn set_current(&mut self, style: Rc<dyn ElementStyle>) {
 		self.styles.insert(style.key().to_string(), style);
 	}
+
+	/// Gets all current styles, sorted by key
+	pub fn styles(&self) -> Vec<Rc<dyn ElementStyle>> {
+		let mut styles = self.styles.values().cloned().collect::<Vec<_>>();
+		styles.sort_by_key(|style| style.key());
+		styles
+	}
 }
 
 #[macro_export]
@@ -103,6 +116,10 @@ macro_rules! impl_elementstyle {
 					std::rc::Rc::new(obj) as std::rc::Rc<dyn $crate::parser::style::ElementStyle>
 				})
 			}
+
+			fn to_json(&self) -> serde_json::Value {
+				serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+			}
 		}
 	};
 }
@@ -6,7 +6,11 @@ use unicode_segmentation::UnicodeSegmentation;
 use crate::document::document::Document;
 use crate::document::document::DocumentAccessors;
 use crate::document::element::ElemKind;
+use crate::elements::linebreak::elem::LineBreak;
+use crate::elements::paragraph::elem::french_spacing;
+use crate::elements::paragraph::elem::LineBreaks;
 use crate::elements::paragraph::elem::Paragraph;
+use crate::elements::paragraph::elem::LINE_SEPARATOR;
 
 use super::parser::ParseMode;
 use super::parser::ParserState;
@@ -15,7 +19,10 @@ use super::source::Token;
 use super::source::VirtualSource;
 
 /// Processes text for escape characters and paragraphing
+///
+/// Soft line breaks are handled according to the document's [`LineBreaks`] mode.
 pub fn process_text(document: &dyn Document, content: &str) -> String {
+	let line_breaks = LineBreaks::from_document(document);
 	let mut escaped = false;
 	let mut newlines = 0usize; // Consecutive newlines
 							//println!("Processing: [{content}]");
@@ -30,7 +37,10 @@ pub fn process_text(document: &dyn Document, content: &str) -> String {
 					Some(c) => {
 						// NOTE: \n is considered whitespace, so previous codepoint can be \n
 						// (Which can only be done by escaping it)
-						if !c.is_whitespace() || c == '\n' {
+						if line_breaks == LineBreaks::Preserve {
+							out.truncate(out.trim_end_matches(' ').len());
+							out.push(LINE_SEPARATOR);
+						} else if !c.is_whitespace() || c == '\n' {
 							out += " ";
 						}
 					}
@@ -39,11 +49,18 @@ pub fn process_text(document: &dyn Document, content: &str) -> String {
 							.last_element::<Paragraph>()
 							.and_then(|par| {
 								par.find_back(|e| e.kind() != ElemKind::Invisible)
-									.map(|e| e.kind() == ElemKind::Inline)
+									// No space after a forced line break
+									.map(|e| {
+										e.kind() == ElemKind::Inline
+											&& e.downcast_ref::<LineBreak>().is_none()
+									})
 							})
 							.unwrap_or(false)
 						{
-							out += " ";
+							match line_breaks {
+								LineBreaks::Preserve => out.push(LINE_SEPARATOR),
+								_ => out += " ",
+							}
 						}
 					} // Don't output anything
 				}
@@ -87,7 +104,10 @@ pub fn process_text(document: &dyn Document, content: &str) -> String {
 		.0
 		.to_string();
 
-	processed
+	match line_breaks {
+		LineBreaks::French => french_spacing(processed.as_str()),
+		_ => processed,
+	}
 }
 
 /// Transforms source into a new [`VirtualSource`] using a `range`.
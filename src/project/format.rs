@@ -0,0 +1,110 @@
+use std::ops::Range;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::compiler::process::defines_document;
+use crate::elements::table::format::table_edits;
+use crate::parser::langparser::LangParser;
+use crate::parser::parser::ParseMode;
+use crate::parser::parser::Parser;
+use crate::parser::parser::ParserState;
+use crate::parser::source::SourceFile;
+
+use super::inputs::collect_inputs;
+
+/// Gets the edits formatting a document, see [`table_edits`]
+///
+/// The document is parsed to find its tables, reports are discarded. `name` is the path of the
+/// document, used to resolve imports.
+pub fn format_edits(
+	name: String,
+	content: String,
+	defines: &[(String, String)],
+) -> Vec<(Range<usize>, String)> {
+	let parser = LangParser::new(false, Box::new(|_, _| {}));
+	let defines = defines_document(defines);
+	let (doc, _) = parser.parse(
+		ParserState::new(&parser, None),
+		Rc::new(SourceFile::with_content(name, content, None)),
+		Some(&defines),
+		ParseMode::default(),
+	);
+	table_edits(&*doc)
+}
+
+/// Applies non-overlapping edits to `content`
+pub fn apply_edits(content: &str, mut edits: Vec<(Range<usize>, String)>) -> String {
+	edits.sort_by_key(|(range, _)| range.start);
+	let mut result = String::with_capacity(content.len());
+	let mut pos = 0;
+	for (range, text) in edits {
+		result += &content[pos..range.start];
+		result += text.as_str();
+		pos = range.end;
+	}
+	result += &content[pos..];
+	result
+}
+
+/// Formats the documents at `path`, a file or a directory, in place
+///
+/// # Returns
+///
+/// The documents that were modified.
+pub fn format_path(path: &Path, defines: &[(String, String)]) -> Result<Vec<PathBuf>, String> {
+	let files = if path.is_dir() {
+		collect_inputs(path)?.0
+	} else {
+		vec![path.to_path_buf()]
+	};
+
+	let mut modified = vec![];
+	for file in files {
+		let content = std::fs::read_to_string(&file)
+			.map_err(|err| format!("Unable to read `{}`: {err}", file.display()))?;
+		let edits = format_edits(file.to_string_lossy().to_string(), content.clone(), defines);
+		if edits.is_empty() {
+			continue;
+		}
+		std::fs::write(&file, apply_edits(&content, edits))
+			.map_err(|err| format!("Unable to write `{}`: {err}", file.display()))?;
+		modified.push(file);
+	}
+	Ok(modified)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn tables() {
+		let content = r#"
+:TABLE {numbers} Numbers
+| Name | Value |
+|:align=center: One | 1 |
+
+``Plain Text,
+| In | code |
+``
+
+| Last|
+"#;
+		let edits = format_edits("".into(), content.into(), &[]);
+		assert_eq!(
+			apply_edits(content, edits),
+			r#"
+:TABLE {numbers} Numbers
+| Name              | Value |
+|:align=center: One | 1     |
+
+``Plain Text,
+| In | code |
+``
+
+| Last |
+"#
+		);
+	}
+}
@@ -0,0 +1,143 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use super::settings::PROJECT_FILE;
+
+/// Default project configuration, every option is documented
+const PROJECT_TEMPLATE: &str = r#"# NML project configuration
+# Options given on the command line take precedence over this file.
+# Relative paths are resolved from the directory containing this file.
+
+# Input file or directory
+input = "src"
+# Output file or directory
+output = "out"
+# Cache database location, required when the input is a directory
+database = "cache.db"
+
+# Compilation profile, available to documents as `compiler.profile`
+#profile = "release"
+# Feature flags, available to documents as `compiler.flags`
+#flags = ["draft"]
+
+# Variables defined for every document
+[defines]
+#version = "0.1.0"
+"#;
+
+/// Template shared by the documents
+const TEMPLATE_TEMPLATE: &str = r#"@export html.css = ../theme/style.css
+
+@<
+function make_doc(title)
+	nml.variable.insert("nav.title", title)
+	nml.variable.insert("html.page_title", title)
+	nml.variable.insert("compiler.output", title .. ".html")
+end
+>@
+"#;
+
+/// Sample document
+const INDEX_TEMPLATE: &str = r#"@import template.nml
+%<make_doc("Index")>%
+
+# Welcome
+
+This document was generated by ``Plain Text, nml --init``. Build the project by running ``Plain Text, nml`` from the project's directory.
+
+#+IF flag=draft
+*This is a draft.*
+#+ENDIF
+"#;
+
+/// Theme stub
+const THEME_TEMPLATE: &str = r#"body {
+	font-family: sans-serif;
+	margin: 0;
+	padding: 0;
+}
+
+.content {
+	max-width: 99ch;
+	margin: 0 auto;
+}
+"#;
+
+/// Ignores generated files
+const GITIGNORE_TEMPLATE: &str = "/out/\n/cache.db\n";
+
+/// Creates a new project in `path`
+///
+/// The directory is created if it doesn't exist. Existing files are never overwritten.
+///
+/// # Returns
+///
+/// The list of created files.
+pub fn init_project(path: &Path) -> Result<Vec<PathBuf>, String> {
+	let files = [
+		(PathBuf::from(PROJECT_FILE), PROJECT_TEMPLATE),
+		(PathBuf::from("src/index.nml"), INDEX_TEMPLATE),
+		(PathBuf::from("src/template.nml"), TEMPLATE_TEMPLATE),
+		(PathBuf::from("theme/style.css"), THEME_TEMPLATE),
+		(PathBuf::from(".gitignore"), GITIGNORE_TEMPLATE),
+	];
+
+	// Check for existing files first, so nothing is created on failure
+	for (file, _) in &files {
+		let file = path.join(file);
+		if file.exists() {
+			return Err(format!(
+				"Unable to create project in `{}`: `{}` already exists",
+				path.display(),
+				file.display()
+			));
+		}
+	}
+
+	let mut created = vec![];
+	for (file, content) in files {
+		let file = path.join(file);
+		if let Some(parent) = file.parent() {
+			std::fs::create_dir_all(parent).map_err(|err| {
+				format!("Unable to create directory `{}`: {err}", parent.display())
+			})?;
+		}
+		std::fs::write(&file, content)
+			.map_err(|err| format!("Unable to write `{}`: {err}", file.display()))?;
+		created.push(file);
+	}
+
+	Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::rc::Rc;
+
+	use super::*;
+	use crate::parser::source::SourceFile;
+	use crate::project::settings::ProjectSettings;
+	use crate::tempdir::TempDir;
+
+	#[test]
+	fn init() {
+		let path = TempDir::new("init");
+
+		let created = init_project(&path).unwrap();
+		assert_eq!(created.len(), 5);
+		assert!(path.join("src/index.nml").is_file());
+
+		let source = SourceFile::new(
+			path.join(PROJECT_FILE).to_string_lossy().to_string(),
+			None,
+		)
+		.unwrap();
+		let settings = ProjectSettings::from_source(Rc::new(source)).unwrap();
+		assert_eq!(settings.input, Some(path.join("src")));
+		assert_eq!(settings.database, Some(path.join("cache.db")));
+		assert!(settings.flags.is_empty());
+
+		// Existing projects are left untouched
+		assert!(init_project(&path).is_err());
+	}
+}
@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+use walkdir::WalkDir;
+
+/// Recursively collects the `.nml` files of directory `root`
+///
+/// Symbolic links are followed, links that form a cycle are skipped. Files reachable through
+/// multiple paths are only collected once.
+///
+/// # Returns
+///
+/// The canonical paths of the collected files, in walking order: entries of a directory are
+/// sorted by file name. Followed by the messages describing the skipped entries, for the caller
+/// to display.
+pub fn collect_inputs(root: &Path) -> Result<(Vec<PathBuf>, Vec<String>), String> {
+	let mut files = vec![];
+	let mut skipped = vec![];
+	let mut visited = HashSet::new();
+
+	for entry in WalkDir::new(root).follow_links(true).sort_by_file_name() {
+		let entry = match entry {
+			Ok(entry) => entry,
+			Err(err) => {
+				if let Some(ancestor) = err.loop_ancestor() {
+					skipped.push(format!(
+						"Skipping `{}`: symbolic link cycle to `{}`",
+						err.path().unwrap_or(root).display(),
+						ancestor.display()
+					));
+					continue;
+				}
+				return Err(format!(
+					"Failed to recursively walk over input directory: {err}"
+				));
+			}
+		};
+		if !entry.file_type().is_file() {
+			continue;
+		}
+
+		let path = entry.path();
+		if path.extension().is_none_or(|ext| ext != "nml") {
+			skipped.push(format!("Skipping '{}'", path.display()));
+			continue;
+		}
+
+		let canonical = std::fs::canonicalize(path)
+			.map_err(|err| format!("Failed to canonicalize `{}`: {err}", path.display()))?;
+		if !visited.insert(canonical.clone()) {
+			skipped.push(format!(
+				"Skipping '{}': already included as '{}'",
+				path.display(),
+				canonical.display()
+			));
+			continue;
+		}
+		files.push(canonical);
+	}
+
+	Ok((files, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tempdir::TempDir;
+
+	#[test]
+	#[cfg(unix)]
+	fn symlinks() {
+		let root = TempDir::new("inputs");
+		std::fs::create_dir_all(root.join("docs/sub")).unwrap();
+		std::fs::write(root.join("docs/a.nml"), "A").unwrap();
+		std::fs::write(root.join("docs/sub/b.nml"), "B").unwrap();
+		std::fs::write(root.join("docs/notes.txt"), "").unwrap();
+		// Duplicate file and directory cycle
+		std::os::unix::fs::symlink(root.join("docs/a.nml"), root.join("docs/sub/alias.nml"))
+			.unwrap();
+		std::os::unix::fs::symlink(root.join("docs"), root.join("docs/sub/loop")).unwrap();
+
+		let (mut files, skipped) = collect_inputs(&root.join("docs")).unwrap();
+		files.sort();
+		assert_eq!(skipped.len(), 3);
+		assert_eq!(files, vec![root.join("docs/a.nml"), root.join("docs/sub/b.nml")]);
+	}
+}
@@ -0,0 +1,6 @@
+pub mod format;
+pub mod init;
+pub mod inputs;
+pub mod settings;
+pub mod testing;
+pub mod versions;
@@ -0,0 +1,1463 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use ariadne::Fmt;
+use serde::Deserialize;
+use toml_edit::ImDocument;
+use toml_edit::Item;
+
+use crate::compiler::assets::PROJECT_CSS_VARIABLE;
+use crate::compiler::assets::PROJECT_JS_VARIABLE;
+use crate::compiler::exec::ExecPolicy;
+use crate::compiler::i18n::LANGUAGES_VARIABLE;
+use crate::compiler::numbering::SectionNumbering;
+use crate::compiler::social::IMAGE_VARIABLE;
+use crate::compiler::social::SITE_NAME_VARIABLE;
+use crate::compiler::social::SOCIAL_VARIABLE;
+use crate::compiler::social::TWITTER_VARIABLE;
+use crate::compiler::split::SPLIT_SECTIONS_VARIABLE;
+use crate::compiler::url::url_path;
+use crate::compiler::url::BASE_URL_VARIABLE;
+use crate::compiler::url::PATH_PREFIX_VARIABLE;
+use crate::elements::conditional::condition::FLAGS_VARIABLE;
+use crate::elements::conditional::condition::PROFILE_VARIABLE;
+use crate::elements::conditional::condition::TARGET_VARIABLE;
+use crate::elements::render::elem::RenderInput;
+use crate::elements::render::elem::RenderOutput;
+use crate::elements::render::elem::Renderer;
+use crate::parser::lint::HeadingCase;
+use crate::parser::lint::CODE_LINES_VARIABLE;
+use crate::parser::lint::HEADING_CASE_VARIABLE;
+use crate::parser::lint::HEADING_DEPTH_VARIABLE;
+use crate::parser::lint::HEADING_LEVELS_VARIABLE;
+use crate::parser::lint::PARAGRAPH_LENGTH_VARIABLE;
+use crate::parser::lint::PARAGRAPH_LINES_VARIABLE;
+use crate::parser::lint::TABLE_ROWS_VARIABLE;
+use crate::parser::parser::ReportColors;
+use crate::parser::reports::macros::*;
+use crate::parser::reports::*;
+use crate::parser::source::Source;
+
+/// Name of the project configuration file
+pub const PROJECT_FILE: &str = "nml.toml";
+
+/// Settings accepted in the project file
+const SETTINGS: [&str; 19] = [
+	"input",
+	"output",
+	"database",
+	"profile",
+	"flags",
+	"defines",
+	"exec",
+	"assets",
+	"standalone",
+	"split_sections",
+	"numbering",
+	"base_url",
+	"path_prefix",
+	"lint",
+	"tex",
+	"render",
+	"social",
+	"versions",
+	"i18n",
+];
+
+/// Settings of a project, read from [`PROJECT_FILE`]
+///
+/// Every setting is optional, options given on the command line take precedence.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ProjectSettings {
+	/// Input file or directory
+	pub input: Option<PathBuf>,
+	/// Output file or directory
+	pub output: Option<PathBuf>,
+	/// Cache database location
+	pub database: Option<PathBuf>,
+	/// Compilation profile
+	pub profile: Option<String>,
+	/// Enabled feature flags
+	pub flags: Vec<String>,
+	/// Variables defined for every document
+	pub defines: BTreeMap<String, String>,
+	/// Policy for the external commands documents may invoke
+	pub exec: ExecPolicy,
+	/// Stylesheets and scripts linked in every page
+	pub assets: AssetSettings,
+	/// Whether to output self-contained pages
+	pub standalone: bool,
+	/// Whether to split documents into one page per top-level section
+	pub split_sections: bool,
+	/// Numbering of the sections across documents
+	pub numbering: SectionNumbering,
+	/// Url the project is published at, used for canonical and social links
+	pub base_url: Option<String>,
+	/// Path the project is published under, defaults to the path of `base_url`
+	pub path_prefix: Option<String>,
+	/// Style lints enabled for every document
+	pub lint: LintSettings,
+	/// LaTeX environments shared by every document
+	pub tex: TexSettings,
+	/// External renderers available to every document
+	pub render: RenderSettings,
+	/// OpenGraph and Twitter card tags of every page, disabled without a `[social]` table
+	pub social: Option<SocialSettings>,
+	/// Versions built in subdirectories of the output, the first one is the latest
+	pub versions: Vec<VersionSettings>,
+	/// Languages of the translated sources
+	pub i18n: I18nSettings,
+}
+
+/// Extra assets of a project, read from the `[assets]` table of the project file
+///
+/// Assets are copied to the output and linked in every page, in addition to the assets declared
+/// by the documents.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AssetSettings {
+	/// Stylesheets
+	pub css: Vec<PathBuf>,
+	/// Scripts
+	pub js: Vec<PathBuf>,
+}
+
+impl AssetSettings {
+	/// Gets the variables passing the assets to the documents
+	pub fn defines(&self) -> Vec<(String, String)> {
+		[
+			(PROJECT_CSS_VARIABLE, &self.css),
+			(PROJECT_JS_VARIABLE, &self.js),
+		]
+		.into_iter()
+		.filter(|(_, paths)| !paths.is_empty())
+		.map(|(name, paths)| {
+			let paths = paths
+				.iter()
+				.map(|path| path.to_string_lossy().to_string())
+				.collect::<Vec<_>>();
+			(name.to_string(), paths.join(","))
+		})
+		.collect()
+	}
+}
+
+/// Style lints and size limits of a project, read from the `[lint]` table of the project file
+///
+/// Lints are passed to the documents as `lint.*` variables, see [`crate::parser::lint`].
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintSettings {
+	/// Capitalization of section titles
+	pub heading_case: Option<HeadingCase>,
+	/// Maximum depth of sections
+	pub max_heading_depth: Option<usize>,
+	/// Whether to report sections that skip a level
+	pub heading_levels: bool,
+	/// Maximum number of words of paragraphs
+	pub max_paragraph_length: Option<usize>,
+	/// Number of lines above which paragraphs are reported, `0` disables the warning
+	pub max_paragraph_lines: Option<usize>,
+	/// Number of rows above which tables are reported, `0` disables the warning
+	pub max_table_rows: Option<usize>,
+	/// Number of lines above which code blocks are reported, `0` disables the warning
+	pub max_code_lines: Option<usize>,
+}
+
+impl LintSettings {
+	/// Gets the variables enabling the lints
+	pub fn defines(&self) -> Vec<(String, String)> {
+		[
+			(
+				HEADING_CASE_VARIABLE,
+				self.heading_case.map(|case| case.name().to_string()),
+			),
+			(
+				HEADING_DEPTH_VARIABLE,
+				self.max_heading_depth.map(|depth| depth.to_string()),
+			),
+			(
+				HEADING_LEVELS_VARIABLE,
+				self.heading_levels.then(|| "true".to_string()),
+			),
+			(
+				PARAGRAPH_LENGTH_VARIABLE,
+				self.max_paragraph_length.map(|length| length.to_string()),
+			),
+			(
+				PARAGRAPH_LINES_VARIABLE,
+				self.max_paragraph_lines.map(|lines| lines.to_string()),
+			),
+			(
+				TABLE_ROWS_VARIABLE,
+				self.max_table_rows.map(|rows| rows.to_string()),
+			),
+			(
+				CODE_LINES_VARIABLE,
+				self.max_code_lines.map(|lines| lines.to_string()),
+			),
+		]
+		.into_iter()
+		.filter_map(|(name, value)| Some((name.to_string(), value?)))
+		.collect()
+	}
+}
+
+/// Social tags of a project, read from the `[social]` table of the project file
+///
+/// Settings are passed to the documents as `social.*` variables, documents can override them or
+/// disable the tags with `@social.enabled = false`, see [`crate::compiler::social`].
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SocialSettings {
+	/// Image of pages without images, relative to the base url of the project
+	pub image: Option<String>,
+	/// Name of the website
+	pub site_name: Option<String>,
+	/// Twitter account of the website
+	pub twitter: Option<String>,
+}
+
+impl SocialSettings {
+	/// Gets the variables enabling the social tags
+	pub fn defines(&self) -> Vec<(String, String)> {
+		[
+			(SOCIAL_VARIABLE, Some(&"true".to_string())),
+			(IMAGE_VARIABLE, self.image.as_ref()),
+			(SITE_NAME_VARIABLE, self.site_name.as_ref()),
+			(TWITTER_VARIABLE, self.twitter.as_ref()),
+		]
+		.into_iter()
+		.filter_map(|(name, value)| Some((name.to_string(), value?.clone())))
+		.collect()
+	}
+}
+
+/// Version of a project, read from the `[[versions]]` tables of the project file
+///
+/// Versions are built from their own input directory, or from the project's input as of a git
+/// tag, see [`crate::project::versions`].
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VersionSettings {
+	/// Name of the version, also the name of its output directory
+	pub name: String,
+	/// Input directory of the version
+	pub input: Option<PathBuf>,
+	/// Git tag to build the project's input from
+	pub tag: Option<String>,
+}
+
+/// Translations of a project, read from the `[i18n]` table of the project file
+///
+/// The input directory contains a directory for each language, see
+/// [`crate::compiler::i18n::Translations`].
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct I18nSettings {
+	/// Languages of the project, the first one is the language of the sources
+	pub languages: Vec<String>,
+}
+
+impl I18nSettings {
+	/// Gets the variable passing the languages to the documents
+	pub fn defines(&self) -> Vec<(String, String)> {
+		if self.languages.is_empty() {
+			return vec![];
+		}
+		vec![(LANGUAGES_VARIABLE.to_string(), self.languages.join(","))]
+	}
+}
+
+/// LaTeX environments of a project, read from the `[tex.<env>]` tables of the project file
+///
+/// Environments are passed to the documents as `tex.<env>.*` variables, elements select them with
+/// their `env` property.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct TexSettings(pub BTreeMap<String, TexEnvironment>);
+
+/// Settings of a LaTeX environment
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TexEnvironment {
+	/// Preamble of the LaTeX documents rendered in this environment
+	pub preamble: Option<String>,
+}
+
+impl TexSettings {
+	/// Gets the variables defining the environments
+	pub fn defines(&self) -> Vec<(String, String)> {
+		self.0
+			.iter()
+			.filter_map(|(env, settings)| {
+				Some((format!("tex.{env}.preamble"), settings.preamble.clone()?))
+			})
+			.collect()
+	}
+}
+
+/// External renderers of a project, read from the `[render.<name>]` tables of the project file
+///
+/// Renderers are passed to the documents as `render.<name>.*` variables, render blocks select them
+/// with their `renderer` property.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct RenderSettings(pub BTreeMap<String, RendererSettings>);
+
+/// Settings of an external renderer
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RendererSettings {
+	/// Executable of the renderer, resolved by the execution policy
+	pub command: String,
+	/// Arguments of the executable
+	#[serde(default)]
+	pub args: Vec<String>,
+	/// How the content is passed to the renderer
+	pub input: Option<RenderInput>,
+	/// How the output of the renderer is inserted in the page
+	pub output: Option<RenderOutput>,
+	/// Whether results are stored in the cache database
+	pub cache: Option<bool>,
+	/// Time in seconds after which the renderer is aborted
+	pub timeout: Option<u64>,
+}
+
+impl RenderSettings {
+	/// Gets the variables defining the renderers
+	pub fn defines(&self) -> Vec<(String, String)> {
+		self.0
+			.iter()
+			.flat_map(|(name, settings)| {
+				[
+					("command", Some(settings.command.clone())),
+					(
+						"args",
+						(!settings.args.is_empty()).then(|| settings.args.join(" ")),
+					),
+					(
+						"input",
+						settings.input.map(|input| input.name().to_string()),
+					),
+					(
+						"output",
+						settings.output.map(|output| output.name().to_string()),
+					),
+					("cache", settings.cache.map(|cache| cache.to_string())),
+					(
+						"timeout",
+						settings.timeout.map(|timeout| timeout.to_string()),
+					),
+				]
+				.into_iter()
+				.filter_map(move |(key, value)| Some((Renderer::variable(name, key), value?)))
+			})
+			.collect()
+	}
+}
+
+/// Computes the edit distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+	let b = b.chars().collect::<Vec<_>>();
+	let mut row = (0..=b.len()).collect::<Vec<_>>();
+	for (i, ca) in a.chars().enumerate() {
+		let mut previous = row[0];
+		row[0] = i + 1;
+		for (j, cb) in b.iter().enumerate() {
+			let substitution = previous + (ca != *cb) as usize;
+			previous = row[j + 1];
+			row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+		}
+	}
+	row[b.len()]
+}
+
+/// Finds the known setting closest to `key`, if any is close enough
+fn suggest(key: &str) -> Option<&'static str> {
+	SETTINGS
+		.iter()
+		.map(|setting| (edit_distance(key, setting), *setting))
+		.filter(|(distance, setting)| *distance <= setting.len() / 2)
+		.min()
+		.map(|(_, setting)| setting)
+}
+
+impl ProjectSettings {
+	/// Finds the project file in the current directory, if any
+	pub fn find() -> Option<PathBuf> {
+		let path = PathBuf::from(PROJECT_FILE);
+		path.is_file().then_some(path)
+	}
+
+	/// Gets the variables passing the base url and path prefix to the documents
+	///
+	/// The path prefix defaults to the path of the base url.
+	pub fn url_defines(&self) -> Vec<(String, String)> {
+		let path_prefix = self
+			.path_prefix
+			.as_ref()
+			.map(|path_prefix| path_prefix.trim_end_matches('/').to_string())
+			.or_else(|| self.base_url.as_deref().and_then(url_path));
+		[
+			(BASE_URL_VARIABLE, self.base_url.clone()),
+			(PATH_PREFIX_VARIABLE, path_prefix),
+		]
+		.into_iter()
+		.filter_map(|(name, value)| Some((name.to_string(), value?)))
+		.collect()
+	}
+
+	/// Gets the variables defined for every document of the project
+	///
+	/// These are the [`Self::defines`], followed by the variables passing the other settings (e.g
+	/// the profile, flags or lints) to the documents.
+	pub fn defines(&self) -> Vec<(String, String)> {
+		let mut defines = self.defines.clone().into_iter().collect::<Vec<_>>();
+		if let Some(profile) = &self.profile {
+			defines.push((PROFILE_VARIABLE.to_string(), profile.clone()));
+		}
+		if self.split_sections {
+			defines.push((SPLIT_SECTIONS_VARIABLE.to_string(), "true".to_string()));
+		}
+		defines.extend(self.url_defines());
+		defines.extend(self.assets.defines());
+		defines.extend(self.lint.defines());
+		defines.extend(self.tex.defines());
+		defines.extend(self.render.defines());
+		defines.extend(self.social.iter().flat_map(SocialSettings::defines));
+		defines.extend(self.i18n.defines());
+		if !self.flags.is_empty() {
+			defines.push((FLAGS_VARIABLE.to_string(), self.flags.join(",")));
+		}
+		defines
+	}
+
+	/// Parses and validates settings from a project file
+	///
+	/// Relative paths in the settings are resolved from the directory of the project file, the
+	/// resolved paths are checked against the filesystem.
+	///
+	/// # Errors
+	///
+	/// Returns the list of problems found in the project file.
+	pub fn from_source(source: Rc<dyn Source>) -> Result<Self, Vec<Report>> {
+		let colors = ReportColors::with_colors();
+		let mut reports = vec![];
+
+		let document = match ImDocument::parse(source.content().as_str()) {
+			Ok(document) => document,
+			Err(err) => {
+				report_err!(
+					&mut reports,
+					source.clone(),
+					"Invalid Project File".into(),
+					span(
+						err.span().unwrap_or(0..0),
+						err.message().trim_end().to_string()
+					)
+				);
+				return Err(reports);
+			}
+		};
+		let key_span = |table: &toml_edit::Table, key: &str| {
+			table
+				.get_key_value(key)
+				.and_then(|(key, _)| key.span())
+				.unwrap_or(0..0)
+		};
+		let value_span = |key: &str| {
+			document
+				.get(key)
+				.and_then(Item::span)
+				.unwrap_or_else(|| key_span(document.as_table(), key))
+		};
+
+		// Unknown settings
+		for (key, _) in document.iter() {
+			if SETTINGS.contains(&key) {
+				continue;
+			}
+			let range = key_span(document.as_table(), key);
+			match suggest(key) {
+				Some(suggestion) => report_err!(
+					&mut reports,
+					source.clone(),
+					"Unknown Setting".into(),
+					span(
+						range,
+						format!("Unknown setting `{}`", key.fg(colors.highlight))
+					),
+					help(format!("Did you mean `{}`?", suggestion.fg(colors.info)))
+				),
+				None => report_err!(
+					&mut reports,
+					source.clone(),
+					"Unknown Setting".into(),
+					span(
+						range,
+						format!("Unknown setting `{}`", key.fg(colors.highlight))
+					),
+					note(format!(
+						"Available settings: {}",
+						SETTINGS
+							.iter()
+							.map(|setting| format!("`{}`", setting.fg(colors.info)))
+							.collect::<Vec<_>>()
+							.join(", ")
+					))
+				),
+			}
+		}
+		if !reports.is_empty() {
+			return Err(reports);
+		}
+
+		let mut settings: ProjectSettings = match toml::from_str(source.content().as_str()) {
+			Ok(settings) => settings,
+			Err(err) => {
+				report_err!(
+					&mut reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(
+						err.span().unwrap_or(0..0),
+						err.message().trim_end().to_string()
+					)
+				);
+				return Err(reports);
+			}
+		};
+
+		// Resolve paths
+		let root = Path::new(source.name()).parent().unwrap_or(Path::new(""));
+		for path in [
+			&mut settings.input,
+			&mut settings.output,
+			&mut settings.database,
+		]
+		.into_iter()
+		.flatten()
+		.chain(
+			settings
+				.versions
+				.iter_mut()
+				.filter_map(|version| version.input.as_mut()),
+		)
+		.chain(settings.assets.css.iter_mut())
+		.chain(settings.assets.js.iter_mut())
+		{
+			*path = root.join(&*path);
+		}
+		settings.numbering.files = std::mem::take(&mut settings.numbering.files)
+			.into_iter()
+			.map(|(path, numbering)| (root.join(path), numbering))
+			.collect();
+
+		let table_span = |path: &[&str], key: &str| {
+			path.iter()
+				.try_fold(document.as_table(), |table, name| {
+					table.get(name).and_then(Item::as_table)
+				})
+				.map(|table| key_span(table, key))
+				.unwrap_or(0..0)
+		};
+		let version_span = |index: usize| {
+			document
+				.get("versions")
+				.and_then(Item::as_array_of_tables)
+				.and_then(|versions| versions.get(index))
+				.map(|version| key_span(version, "name"))
+				.unwrap_or_else(|| value_span("versions"))
+		};
+		settings.validate(
+			&source,
+			&colors,
+			&mut reports,
+			value_span,
+			table_span,
+			version_span,
+		);
+		if !reports.is_empty() {
+			return Err(reports);
+		}
+
+		Ok(settings)
+	}
+
+	/// Checks the settings against each other and against the filesystem
+	///
+	/// `value_span` gets the range of a setting's value, `table_span` gets the range of a key in a
+	/// nested table and `version_span` gets the range of the name of a version.
+	fn validate<V, T, W>(
+		&self,
+		source: &Rc<dyn Source>,
+		colors: &ReportColors,
+		reports: &mut Vec<Report>,
+		value_span: V,
+		table_span: T,
+		version_span: W,
+	) where
+		V: Fn(&str) -> Range<usize>,
+		T: Fn(&[&str], &str) -> Range<usize>,
+		W: Fn(usize) -> Range<usize>,
+	{
+		// Paths
+		if let Some(input) = &self.input {
+			if !input.exists() {
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(
+						value_span("input"),
+						format!(
+							"Input `{}` does not exist",
+							input.display().fg(colors.highlight)
+						)
+					)
+				);
+			} else if input.is_dir() {
+				if self.database.is_none() {
+					report_err!(
+						reports,
+						source.clone(),
+						"Conflicting Settings".into(),
+						span(
+							value_span("input"),
+							"Input is a directory, which requires a database".into()
+						),
+						help(format!(
+							"Add `{}` to the project file",
+							"database = \"cache.db\"".fg(colors.info)
+						))
+					);
+				}
+				if self.output.as_ref().is_some_and(|output| output.is_file()) {
+					report_err!(
+						reports,
+						source.clone(),
+						"Conflicting Settings".into(),
+						span(
+							value_span("output"),
+							"Output is a file, but input is a directory".into()
+						),
+						span(value_span("input"), "Input set here".into())
+					);
+				}
+			} else if self.output.as_ref().is_some_and(|output| output.is_dir()) {
+				report_err!(
+					reports,
+					source.clone(),
+					"Conflicting Settings".into(),
+					span(
+						value_span("output"),
+						"Output is a directory, but input is a file".into()
+					),
+					span(value_span("input"), "Input set here".into())
+				);
+			}
+		}
+		if let Some(database) = &self.database {
+			if database.is_dir() {
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(
+						value_span("database"),
+						format!(
+							"Database `{}` is a directory",
+							database.display().fg(colors.highlight)
+						)
+					)
+				);
+			} else if database
+				.parent()
+				.is_some_and(|parent| !parent.as_os_str().is_empty() && !parent.is_dir())
+			{
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(
+						value_span("database"),
+						format!(
+							"Parent directory of database `{}` does not exist",
+							database.display().fg(colors.highlight)
+						)
+					)
+				);
+			}
+		}
+
+		for path in self.numbering.files.keys() {
+			if !path.is_file() {
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(
+						value_span("numbering"),
+						format!(
+							"Numbered document `{}` does not exist",
+							path.display().fg(colors.highlight)
+						)
+					)
+				);
+			}
+		}
+
+		// Profile and flags
+		if self
+			.profile
+			.as_ref()
+			.is_some_and(|profile| profile.trim().is_empty())
+		{
+			report_err!(
+				reports,
+				source.clone(),
+				"Invalid Setting".into(),
+				span(value_span("profile"), "Profile must not be empty".into())
+			);
+		}
+		for flag in &self.flags {
+			if flag.is_empty() || flag.contains(|c: char| c == ',' || c.is_whitespace()) {
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(
+						value_span("flags"),
+						format!(
+							"Invalid flag `{}`, flags must not be empty or contain commas or whitespaces",
+							flag.fg(colors.highlight)
+						)
+					)
+				);
+			}
+		}
+
+		// Defines
+		for name in self.defines.keys() {
+			let conflict = if name == TARGET_VARIABLE {
+				Some("it is set from the compilation target".to_string())
+			} else if name == PROFILE_VARIABLE && self.profile.is_some() {
+				Some(format!("it is set by `{}`", "profile".fg(colors.info)))
+			} else if name == FLAGS_VARIABLE && !self.flags.is_empty() {
+				Some(format!("it is set by `{}`", "flags".fg(colors.info)))
+			} else if self.url_defines().iter().any(|(url, _)| url == name) {
+				let setting = match name == PATH_PREFIX_VARIABLE && self.path_prefix.is_some() {
+					true => "path_prefix",
+					false => "base_url",
+				};
+				Some(format!("it is set by `{}`", setting.fg(colors.info)))
+			} else if name == PROJECT_CSS_VARIABLE || name == PROJECT_JS_VARIABLE {
+				Some(format!("it is set by `{}`", "assets".fg(colors.info)))
+			} else if name == LANGUAGES_VARIABLE && !self.i18n.languages.is_empty() {
+				Some(format!("it is set by `{}`", "i18n".fg(colors.info)))
+			} else if self.lint.defines().iter().any(|(lint, _)| lint == name) {
+				Some(format!("it is set by `{}`", "lint".fg(colors.info)))
+			} else if self.tex.defines().iter().any(|(tex, _)| tex == name) {
+				Some(format!("it is set by `{}`", "tex".fg(colors.info)))
+			} else if self
+				.render
+				.defines()
+				.iter()
+				.any(|(render, _)| render == name)
+			{
+				Some(format!("it is set by `{}`", "render".fg(colors.info)))
+			} else if self
+				.social
+				.iter()
+				.flat_map(SocialSettings::defines)
+				.any(|(social, _)| &social == name)
+			{
+				Some(format!("it is set by `{}`", "social".fg(colors.info)))
+			} else {
+				None
+			};
+
+			if name.trim().is_empty() {
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(
+						table_span(&["defines"], name),
+						"Variable name must not be empty".into()
+					)
+				);
+			} else if let Some(conflict) = conflict {
+				report_err!(
+					reports,
+					source.clone(),
+					"Conflicting Settings".into(),
+					span(
+						table_span(&["defines"], name),
+						format!(
+							"Variable `{}` cannot be defined, {conflict}",
+							name.fg(colors.highlight)
+						)
+					)
+				);
+			}
+		}
+
+		// Assets
+		for (key, paths) in [("css", &self.assets.css), ("js", &self.assets.js)] {
+			for path in paths.iter().filter(|path| !path.is_file()) {
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(
+						table_span(&["assets"], key),
+						format!(
+							"Asset `{}` does not exist",
+							path.display().fg(colors.highlight)
+						)
+					)
+				);
+			}
+		}
+
+		// Lints
+		for (key, value) in [
+			("max_heading_depth", self.lint.max_heading_depth),
+			("max_paragraph_length", self.lint.max_paragraph_length),
+		] {
+			if value == Some(0) {
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(
+						table_span(&["lint"], key),
+						format!("`{}` must be a positive integer", key.fg(colors.info))
+					)
+				);
+			}
+		}
+
+		// LaTeX environments
+		for env in self.tex.0.keys() {
+			if env.is_empty() || env.contains(|c: char| c == '.' || c.is_whitespace()) {
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(
+						table_span(&["tex"], env),
+						format!(
+							"Invalid environment `{}`, environments must not be empty or contain dots or whitespaces",
+							env.fg(colors.highlight)
+						)
+					)
+				);
+			}
+		}
+
+		// Renderers
+		for (name, renderer) in &self.render.0 {
+			if let Err(err) = Renderer::validate_name(name) {
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(table_span(&["render"], name), err)
+				);
+			}
+			if renderer.command.trim().is_empty() {
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(
+						table_span(&["render", name], "command"),
+						format!(
+							"Command of renderer `{}` must not be empty",
+							name.fg(colors.highlight)
+						)
+					)
+				);
+			}
+			for arg in renderer
+				.args
+				.iter()
+				.filter(|arg| arg.is_empty() || arg.contains(char::is_whitespace))
+			{
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(
+						table_span(&["render", name], "args"),
+						format!(
+							"Invalid argument `{}`, arguments must not be empty or contain whitespaces",
+							arg.fg(colors.highlight)
+						)
+					)
+				);
+			}
+			if renderer.timeout == Some(0) {
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(
+						table_span(&["render", name], "timeout"),
+						format!("`{}` must be a positive integer", "timeout".fg(colors.info))
+					)
+				);
+			}
+		}
+
+		// Execution policy
+		for (exec, path) in &self.exec.paths {
+			let problem = if !path.is_absolute() {
+				"must be an absolute path"
+			} else if !path.is_file() {
+				"does not exist"
+			} else {
+				continue;
+			};
+			report_err!(
+				reports,
+				source.clone(),
+				"Invalid Setting".into(),
+				span(
+					table_span(&["exec", "paths"], exec),
+					format!(
+						"`{}` {problem}, got `{}`",
+						format!("exec.paths.{exec}").fg(colors.info),
+						path.display().fg(colors.highlight)
+					)
+				)
+			);
+		}
+
+		// Urls
+		if let Some(base_url) = self.base_url.as_ref().filter(|base_url| {
+			!base_url.starts_with("http://") && !base_url.starts_with("https://")
+		}) {
+			report_err!(
+				reports,
+				source.clone(),
+				"Invalid Setting".into(),
+				span(
+					value_span("base_url"),
+					format!(
+						"Base url `{}` must start with `{}` or `{}`",
+						base_url.fg(colors.highlight),
+						"http://".fg(colors.info),
+						"https://".fg(colors.info)
+					)
+				)
+			);
+		}
+		if let Some(path_prefix) = self
+			.path_prefix
+			.as_ref()
+			.filter(|path_prefix| !path_prefix.starts_with('/'))
+		{
+			report_err!(
+				reports,
+				source.clone(),
+				"Invalid Setting".into(),
+				span(
+					value_span("path_prefix"),
+					format!(
+						"Path prefix `{}` must start with `{}`",
+						path_prefix.fg(colors.highlight),
+						"/".fg(colors.info)
+					)
+				)
+			);
+		}
+
+		// Versions
+		for (i, version) in self.versions.iter().enumerate() {
+			let problem = if version.name.is_empty()
+				|| version.name.starts_with('.')
+				|| version.name.contains(['/', '\\'])
+			{
+				Some(format!(
+					"Invalid version name `{}`, names must not be empty, start with `.` or contain path separators",
+					version.name.as_str().fg(colors.highlight)
+				))
+			} else if self.versions[..i]
+				.iter()
+				.any(|other| other.name == version.name)
+			{
+				Some(format!(
+					"Version `{}` is defined multiple times",
+					version.name.as_str().fg(colors.highlight)
+				))
+			} else {
+				match (&version.input, &version.tag) {
+					(None, None) | (Some(_), Some(_)) => Some(format!(
+						"Version `{}` requires either an `{}` or a `{}`",
+						version.name.as_str().fg(colors.highlight),
+						"input".fg(colors.info),
+						"tag".fg(colors.info)
+					)),
+					(Some(input), None) if !input.is_dir() => Some(format!(
+						"Input `{}` of version `{}` is not a directory",
+						input.display().fg(colors.highlight),
+						version.name.as_str().fg(colors.highlight)
+					)),
+					(None, Some(_)) if self.input.is_none() => Some(format!(
+						"Version `{}` is built from a tag, which requires an `{}`",
+						version.name.as_str().fg(colors.highlight),
+						"input".fg(colors.info)
+					)),
+					_ => None,
+				}
+			};
+			if let Some(problem) = problem {
+				report_err!(
+					reports,
+					source.clone(),
+					"Invalid Setting".into(),
+					span(version_span(i), problem)
+				);
+			}
+		}
+
+		// Languages
+		for (i, language) in self.i18n.languages.iter().enumerate() {
+			let problem = if language.is_empty()
+				|| language.starts_with('.')
+				|| language.contains(['/', '\\', ','])
+			{
+				format!(
+					"Invalid language `{}`, languages must not be empty, start with `.` or contain path separators or commas",
+					language.fg(colors.highlight)
+				)
+			} else if self.i18n.languages[..i].contains(language) {
+				format!(
+					"Language `{}` is listed multiple times",
+					language.fg(colors.highlight)
+				)
+			} else if let Some(input) = self
+				.input
+				.as_ref()
+				.filter(|input| input.is_dir() && !input.join(language).is_dir())
+			{
+				format!(
+					"Directory `{}` of language `{}` does not exist",
+					input.join(language).display().fg(colors.highlight),
+					language.fg(colors.highlight)
+				)
+			} else {
+				continue;
+			};
+			report_err!(
+				reports,
+				source.clone(),
+				"Invalid Setting".into(),
+				span(table_span(&["i18n"], "languages"), problem)
+			);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::source::SourceFile;
+
+	fn parse(content: &str) -> Result<ProjectSettings, Vec<Report>> {
+		ProjectSettings::from_source(Rc::new(SourceFile::with_content(
+			PROJECT_FILE.to_string(),
+			content.to_string(),
+			None,
+		)))
+	}
+
+	#[test]
+	fn settings() {
+		let settings = parse(
+			r#"
+database = "cache.db"
+flags = ["draft"]
+
+[defines]
+version = "1.0"
+"#,
+		)
+		.unwrap();
+
+		assert_eq!(settings.input, None);
+		assert_eq!(settings.database, Some(PathBuf::from("cache.db")));
+		assert_eq!(settings.flags, vec!["draft".to_string()]);
+		assert_eq!(settings.defines.get("version").unwrap(), "1.0");
+		assert_eq!(settings.exec, ExecPolicy::default());
+		assert_eq!(settings.assets, AssetSettings::default());
+		assert!(!settings.standalone);
+		assert!(!settings.split_sections);
+		assert_eq!(settings.lint, LintSettings::default());
+		assert_eq!(settings.tex, TexSettings::default());
+		assert_eq!(settings.render, RenderSettings::default());
+	}
+
+	#[test]
+	fn defines() {
+		let settings = parse(
+			r#"
+profile = "release"
+flags = ["draft", "beta"]
+split_sections = true
+base_url = "https://example.com"
+
+[defines]
+version = "1.0"
+
+[social]
+
+[i18n]
+languages = ["en"]
+"#,
+		)
+		.unwrap();
+
+		let defines = settings.defines();
+		for (name, value) in [
+			("version", "1.0"),
+			(PROFILE_VARIABLE, "release"),
+			(SPLIT_SECTIONS_VARIABLE, "true"),
+			(BASE_URL_VARIABLE, "https://example.com"),
+			(SOCIAL_VARIABLE, "true"),
+			(LANGUAGES_VARIABLE, "en"),
+			(FLAGS_VARIABLE, "draft,beta"),
+		] {
+			assert!(
+				defines.contains(&(name.to_string(), value.to_string())),
+				"missing `{name}` in {defines:?}"
+			);
+		}
+		assert_eq!(parse("").unwrap().defines(), vec![]);
+	}
+
+	#[test]
+	fn tex() {
+		let settings = parse(
+			r#"
+[tex.physics]
+preamble = '\usepackage{physics}'
+
+[tex.plain]
+"#,
+		)
+		.unwrap();
+		assert_eq!(
+			settings.tex.defines(),
+			vec![(
+				"tex.physics.preamble".to_string(),
+				"\\usepackage{physics}".to_string()
+			)]
+		);
+
+		let reports = parse("[tex.main]\nfontsize = \"12\"\n").unwrap_err();
+		assert_eq!(reports[0].message, "Invalid Setting");
+		let reports = parse("[tex.\"a b\"]\n").unwrap_err();
+		assert_eq!(reports[0].spans[0].token.range, 5..10);
+		assert!(
+			parse("[tex.main]\npreamble = \"\"\n[defines]\n\"tex.main.preamble\" = \"\"\n")
+				.is_err()
+		);
+	}
+
+	#[test]
+	fn render() {
+		let settings = parse(
+			r#"
+[render.plantuml]
+command = "plantuml"
+args = ["-tsvg", "-pipe"]
+
+[render.abc]
+command = "abc2svg"
+input = "file"
+output = "html"
+cache = false
+"#,
+		)
+		.unwrap();
+		assert_eq!(
+			settings.render.defines(),
+			vec![
+				("render.abc.command".to_string(), "abc2svg".to_string()),
+				("render.abc.input".to_string(), "file".to_string()),
+				("render.abc.output".to_string(), "html".to_string()),
+				("render.abc.cache".to_string(), "false".to_string()),
+				(
+					"render.plantuml.command".to_string(),
+					"plantuml".to_string()
+				),
+				(
+					"render.plantuml.args".to_string(),
+					"-tsvg -pipe".to_string()
+				),
+			]
+		);
+
+		assert!(parse(
+			"[render.abc]
+input = \"file\"\n"
+		)
+		.is_err());
+		assert!(parse(
+			"[render.abc]
+command = \"abc\"\noutput = \"png\"\n"
+		)
+		.is_err());
+		let reports = parse("[render.\"a.b\"]\ncommand = \"abc\"\n").unwrap_err();
+		assert_eq!(reports[0].message, "Invalid Setting");
+		assert_eq!(reports[0].spans[0].token.range, 8..13);
+		let reports = parse("[render.abc]\ncommand = \"abc\"\nargs = [\"a b\"]\n").unwrap_err();
+		assert_eq!(reports[0].spans[0].token.range, 29..33);
+		assert!(parse(
+			"[render.abc]\ncommand = \"abc\"\n[defines]\n\"render.abc.command\" = \"b\"\n"
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn lint() {
+		let settings = parse("[lint]\nheading_case = \"title\"\nheading_levels = true\n").unwrap();
+		assert_eq!(
+			settings.lint.defines(),
+			vec![
+				(HEADING_CASE_VARIABLE.to_string(), "title".to_string()),
+				(HEADING_LEVELS_VARIABLE.to_string(), "true".to_string())
+			]
+		);
+
+		let reports = parse("[lint]\nmax_heading_depth = 0\n").unwrap_err();
+		assert_eq!(reports[0].message, "Invalid Setting");
+		assert_eq!(reports[0].spans[0].token.range, 7..24);
+		assert_eq!(
+			parse("[lint]\nmax_code_lines = 0\n")
+				.unwrap()
+				.lint
+				.defines(),
+			vec![(CODE_LINES_VARIABLE.to_string(), "0".to_string())]
+		);
+		assert!(parse("[lint]\nheading_case = \"upper\"\n").is_err());
+		assert!(parse(
+			"[lint]\nmax_paragraph_length = 100\n[defines]\n\"lint.max_paragraph_length\" = \"50\"\n"
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn urls() {
+		let settings = parse("base_url = \"https://user.github.io/project/\"\n").unwrap();
+		assert_eq!(
+			settings.url_defines(),
+			vec![
+				(
+					BASE_URL_VARIABLE.to_string(),
+					"https://user.github.io/project/".to_string()
+				),
+				(PATH_PREFIX_VARIABLE.to_string(), "/project".to_string())
+			]
+		);
+		assert_eq!(
+			parse("base_url = \"https://example.com\"\npath_prefix = \"/docs/\"\n")
+				.unwrap()
+				.url_defines()[1],
+			(PATH_PREFIX_VARIABLE.to_string(), "/docs".to_string())
+		);
+
+		let reports = parse("base_url = \"example.com\"\n").unwrap_err();
+		assert_eq!(reports[0].message, "Invalid Setting");
+		assert_eq!(reports[0].spans[0].token.range, 11..24);
+		assert!(parse("path_prefix = \"docs\"\n").is_err());
+		assert!(parse(
+			"base_url = \"https://a.org/b\"\n[defines]\n\"project.path_prefix\" = \"/c\"\n"
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn versions() {
+		let dir = std::env::temp_dir();
+		let settings = parse(
+			format!(
+				"input = \"{0}\"\ndatabase = \"cache.db\"\n[[versions]]\nname = \"dev\"\ninput = \"{0}\"\n[[versions]]\nname = \"1.0\"\ntag = \"v1.0\"\n",
+				dir.display()
+			)
+			.as_str(),
+		)
+		.unwrap();
+		assert_eq!(
+			settings.versions,
+			vec![
+				VersionSettings {
+					name: "dev".into(),
+					input: Some(dir.clone()),
+					tag: None
+				},
+				VersionSettings {
+					name: "1.0".into(),
+					input: None,
+					tag: Some("v1.0".into())
+				}
+			]
+		);
+
+		for versions in [
+			"[[versions]]\nname = \"../up\"\ntag = \"v1\"\n",
+			"[[versions]]\nname = \"a\"\n",
+			"[[versions]]\nname = \"a\"\ntag = \"v1\"\n",
+			"[[versions]]\nname = \"a\"\ninput = \"missing\"\n",
+		] {
+			let reports = parse(versions).unwrap_err();
+			assert_eq!(reports[0].message, "Invalid Setting");
+			assert_eq!(reports[0].spans[0].token.range, 13..17);
+		}
+		assert!(parse(
+			format!(
+				"input = \"{0}\"\ndatabase = \"cache.db\"\n[[versions]]\nname = \"a\"\ntag = \"v1\"\n[[versions]]\nname = \"a\"\ntag = \"v2\"\n",
+				dir.display()
+			)
+			.as_str()
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn i18n() {
+		let settings = parse("[i18n]\nlanguages = [\"en\", \"fr\"]\n").unwrap();
+		assert_eq!(
+			settings.i18n.defines(),
+			vec![(LANGUAGES_VARIABLE.to_string(), "en,fr".to_string())]
+		);
+		assert_eq!(parse("").unwrap().i18n.defines(), vec![]);
+
+		let reports = parse("[i18n]\nlanguages = [\"en\", \"en\"]\n").unwrap_err();
+		assert_eq!(reports[0].message, "Invalid Setting");
+		assert_eq!(reports[0].spans[0].token.range, 7..16);
+		assert!(parse("[i18n]\nlanguages = [\"../en\"]\n").is_err());
+		assert!(parse(
+			format!(
+				"input = \"{}\"\ndatabase = \"cache.db\"\n[i18n]\nlanguages = [\"missing-language\"]\n",
+				std::env::temp_dir().display()
+			)
+			.as_str()
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn social() {
+		assert_eq!(parse("").unwrap().social, None);
+		let settings = parse("[social]\ntwitter = \"@site\"\n").unwrap();
+		assert_eq!(
+			settings.social.unwrap().defines(),
+			vec![
+				(SOCIAL_VARIABLE.to_string(), "true".to_string()),
+				(TWITTER_VARIABLE.to_string(), "@site".to_string())
+			]
+		);
+		assert_eq!(
+			parse("[social]\n").unwrap().social.unwrap().defines(),
+			vec![(SOCIAL_VARIABLE.to_string(), "true".to_string())]
+		);
+
+		assert!(parse("[social]\n[defines]\n\"social.enabled\" = \"false\"\n").is_err());
+	}
+
+	#[test]
+	fn assets() {
+		let reports = parse("[assets]\ncss = [\"missing.css\"]\n").unwrap_err();
+		assert_eq!(reports[0].message, "Invalid Setting");
+		assert_eq!(reports[0].spans[0].token.range, 9..12);
+
+		let assets = AssetSettings {
+			css: vec![PathBuf::from("a.css"), PathBuf::from("theme/b.css")],
+			js: vec![],
+		};
+		assert_eq!(
+			assets.defines(),
+			vec![(
+				PROJECT_CSS_VARIABLE.to_string(),
+				"a.css,theme/b.css".to_string()
+			)]
+		);
+	}
+
+	#[test]
+	fn diagnostics() {
+		let content = "inptu = \"src\"\n";
+		let reports = parse(content).unwrap_err();
+		assert_eq!(reports.len(), 1);
+		assert_eq!(reports[0].message, "Unknown Setting");
+		assert_eq!(reports[0].spans[0].token.range, 0..5);
+		assert!(reports[0].help.as_ref().unwrap().contains("input"));
+
+		let reports = parse("flags = \"draft\"\n").unwrap_err();
+		assert_eq!(reports[0].message, "Invalid Setting");
+		assert_eq!(reports[0].spans[0].token.range, 8..15);
+
+		let reports = parse("input = \"src\"\ninput = \"out\"\n").unwrap_err();
+		assert_eq!(reports[0].message, "Invalid Project File");
+
+		let reports = parse("input = \"/nonexistent/input.nml\"\n").unwrap_err();
+		assert_eq!(reports[0].message, "Invalid Setting");
+		assert_eq!(reports[0].spans[0].token.range, 8..32);
+
+		let reports = parse(
+			r#"
+[exec]
+allow = ["latex2svg"]
+
+[exec.paths]
+dot = "bin/dot"
+"#,
+		)
+		.unwrap_err();
+		assert_eq!(reports[0].message, "Invalid Setting");
+		assert_eq!(reports[0].spans[0].token.range, 44..47);
+		assert!(reports[0].spans[0].message.contains("exec.paths.dot"));
+
+		let reports = parse(
+			r#"
+input = "."
+profile = "release"
+flags = ["a b"]
+
+[defines]
+"compiler.profile" = "debug"
+"#,
+		)
+		.unwrap_err();
+		let messages = reports
+			.iter()
+			.map(|report| report.message.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(
+			messages,
+			vec![
+				"Conflicting Settings",
+				"Invalid Setting",
+				"Conflicting Settings"
+			]
+		);
+	}
+
+	#[test]
+	fn suggestions() {
+		assert_eq!(suggest("outptu"), Some("output"));
+		assert_eq!(suggest("flag"), Some("flags"));
+		assert_eq!(suggest("define"), Some("defines"));
+		assert_eq!(suggest("theme"), None);
+	}
+}
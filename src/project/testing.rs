@@ -0,0 +1,157 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::compiler::api::compile_source;
+use crate::compiler::api::Settings;
+use crate::parser::parser::ReportColors;
+use crate::parser::reports::Report;
+use crate::parser::source::SourceFile;
+
+use super::inputs::collect_inputs;
+
+/// Default directory containing the tests of a project
+pub const TESTS_DIRECTORY: &str = "tests";
+
+/// Outcome of a document test
+#[derive(Debug, PartialEq, Eq)]
+pub enum TestOutcome {
+	/// The document compiled without errors and matches its snapshot
+	Passed,
+	/// The document compiled without errors, and its snapshot was created
+	SnapshotCreated,
+	/// The document failed to compile (e.g a failed assertion), or doesn't match its snapshot
+	Failed(String),
+}
+
+/// Gets the path of the snapshot of a test, i.e `<name>.expected.html` next to the test
+pub fn snapshot_path(test: &Path) -> PathBuf { test.with_extension("expected.html") }
+
+/// Runs the test document at `path`
+///
+/// The test fails when errors are reported while compiling the document, including failed
+/// `nml.assert` assertions. The compiled body is then compared to the snapshot of the test, which
+/// is created when missing.
+pub fn run_test(path: &Path, settings: &Settings, colors: &ReportColors) -> TestOutcome {
+	let source = match SourceFile::new(path.to_string_lossy().to_string(), None) {
+		Ok(source) => source,
+		Err(err) => return TestOutcome::Failed(format!("Unable to read test: {err}")),
+	};
+	let output = match compile_source(Rc::new(source), settings) {
+		Ok(output) => output,
+		Err(reports) => return TestOutcome::Failed(Report::reports_to_string(colors, reports)),
+	};
+
+	let snapshot = snapshot_path(path);
+	match std::fs::read_to_string(&snapshot) {
+		Ok(expected) if expected == output.body => TestOutcome::Passed,
+		Ok(expected) => {
+			let line = expected
+				.lines()
+				.zip(output.body.lines())
+				.position(|(expected, found)| expected != found)
+				.unwrap_or(expected.lines().count().min(output.body.lines().count()));
+			TestOutcome::Failed(format!(
+				"Output differs from snapshot `{}` at line {}:\n  expected: {}\n  found:    {}\n",
+				snapshot.display(),
+				line + 1,
+				expected.lines().nth(line).unwrap_or(""),
+				output.body.lines().nth(line).unwrap_or("")
+			))
+		}
+		Err(_) => match std::fs::write(&snapshot, &output.body) {
+			Ok(()) => TestOutcome::SnapshotCreated,
+			Err(err) => TestOutcome::Failed(format!(
+				"Unable to create snapshot `{}`: {err}",
+				snapshot.display()
+			)),
+		},
+	}
+}
+
+/// Runs the test documents of directory `root`, displaying their outcome
+///
+/// # Returns
+///
+/// Whether every test passed
+pub fn run_tests(root: &Path, settings: &Settings) -> Result<bool, String> {
+	let (tests, skipped) = collect_inputs(root)?;
+	for message in skipped {
+		println!("{message}");
+	}
+	let colors = ReportColors::with_colors();
+	// Tests are displayed relative to `root`
+	let base = std::fs::canonicalize(root).unwrap_or(root.to_path_buf());
+
+	let mut failed = vec![];
+	for test in &tests {
+		let name = test.strip_prefix(&base).unwrap_or(test).display();
+		match run_test(test, settings, &colors) {
+			TestOutcome::Passed => println!("test {name} ... ok"),
+			TestOutcome::SnapshotCreated => println!("test {name} ... ok (snapshot created)"),
+			TestOutcome::Failed(reason) => {
+				println!("test {name} ... FAILED");
+				failed.push((name.to_string(), reason));
+			}
+		}
+	}
+
+	for (name, reason) in &failed {
+		println!("\n---- {name} ----\n{reason}");
+	}
+	println!(
+		"\ntest result: {}. {} passed; {} failed",
+		if failed.is_empty() { "ok" } else { "FAILED" },
+		tests.len() - failed.len(),
+		failed.len()
+	);
+	Ok(failed.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tempdir::TempDir;
+
+	#[test]
+	fn outcomes() {
+		let root = TempDir::new("testing");
+		std::fs::write(
+			root.join("pass.nml"),
+			"# Title\n%<nml.assert.element_count(\"Section\", 1)>%\n",
+		)
+		.unwrap();
+		std::fs::write(
+			root.join("fail.nml"),
+			"@x = 1\n%<nml.assert.variable(\"x\", \"2\")>%\n",
+		)
+		.unwrap();
+
+		let settings = Settings::default();
+		let colors = ReportColors::without_colors();
+		let pass = root.join("pass.nml");
+		assert_eq!(
+			run_test(&pass, &settings, &colors),
+			TestOutcome::SnapshotCreated
+		);
+		assert_eq!(run_test(&pass, &settings, &colors), TestOutcome::Passed);
+
+		// Snapshot mismatch
+		let snapshot = snapshot_path(&pass);
+		let body = std::fs::read_to_string(&snapshot).unwrap();
+		std::fs::write(&snapshot, body.replace("Title", "Other")).unwrap();
+		let TestOutcome::Failed(reason) = run_test(&pass, &settings, &colors) else {
+			panic!("Snapshot mismatch not detected");
+		};
+		assert!(reason.contains("Output differs from snapshot"));
+
+		// Failed assertion
+		let TestOutcome::Failed(reason) = run_test(&root.join("fail.nml"), &settings, &colors)
+		else {
+			panic!("Failed assertion not detected");
+		};
+		assert!(reason.contains("Assertion Failed"));
+		assert!(reason.contains("Expected variable `x` to be `2`, found `1`"));
+		assert!(!snapshot_path(&root.join("fail.nml")).exists());
+	}
+}
@@ -0,0 +1,243 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+
+use serde_json::json;
+
+use crate::compiler::url::BASE_URL_VARIABLE;
+use crate::compiler::url::PATH_PREFIX_VARIABLE;
+
+/// Name of the manifest listing the versions of a project, written at the root of the output
+pub const VERSIONS_MANIFEST: &str = "versions.json";
+
+/// Name of the variable holding the name of the version being built
+pub const VERSION_VARIABLE: &str = "project.version";
+
+/// Name of the variable holding the link from the pages to [`VERSIONS_MANIFEST`], relative to the
+/// output of the version
+pub const VERSIONS_VARIABLE: &str = "project.versions";
+
+/// Gets the variables of the documents of a version, from the variables of the project
+///
+/// Versions are built in subdirectories of the output: the base url and path prefix of the
+/// project are extended with the name of the version.
+pub fn version_defines(name: &str, defines: &[(String, String)]) -> Vec<(String, String)> {
+	let mut defines = defines
+		.iter()
+		.map(|(variable, value)| {
+			if variable == BASE_URL_VARIABLE || variable == PATH_PREFIX_VARIABLE {
+				let value = format!("{}/{name}", value.trim_end_matches('/'));
+				(variable.clone(), value)
+			} else {
+				(variable.clone(), value.clone())
+			}
+		})
+		.collect::<Vec<_>>();
+	if !defines
+		.iter()
+		.any(|(variable, _)| variable == PATH_PREFIX_VARIABLE)
+	{
+		// Without a path prefix, root-relative links would escape the version
+		defines.push((PATH_PREFIX_VARIABLE.to_string(), format!("/{name}")));
+	}
+	defines.push((VERSION_VARIABLE.to_string(), name.to_string()));
+	defines.push((
+		VERSIONS_VARIABLE.to_string(),
+		format!("../{VERSIONS_MANIFEST}"),
+	));
+	defines
+}
+
+/// Gets the manifest listing the versions of a project, for version switchers
+///
+/// The first version is the latest one. Paths are relative to the manifest.
+pub fn versions_manifest(versions: &[&str]) -> String {
+	let manifest = json!({
+		"latest": versions.first(),
+		"versions": versions
+			.iter()
+			.map(|name| json!({ "name": name, "path": format!("{name}/") }))
+			.collect::<Vec<_>>(),
+	});
+	serde_json::to_string_pretty(&manifest).unwrap()
+}
+
+/// Runs git with `args` from directory `dir`, returning its output
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+	let output = Command::new("git")
+		.arg("-C")
+		.arg(dir)
+		.args(args)
+		.output()
+		.map_err(|err| format!("Unable to run git: {err}"))?;
+	if !output.status.success() {
+		return Err(format!(
+			"`git {}` failed: {}",
+			args.join(" "),
+			String::from_utf8_lossy(&output.stderr).trim()
+		));
+	}
+	Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Extracts `input` as of git tag `tag` to directory `destination`
+///
+/// `input` must be part of a git repository. Only the files of `input` are extracted, with their
+/// path in the repository.
+///
+/// # Returns
+///
+/// The path of `input` in `destination`.
+pub fn checkout_tag(tag: &str, input: &Path, destination: &Path) -> Result<PathBuf, String> {
+	let input = std::fs::canonicalize(input)
+		.map_err(|err| format!("Failed to canonicalize `{}`: {err}", input.display()))?;
+	let dir = match input.is_dir() {
+		true => input.as_path(),
+		false => input.parent().unwrap_or(Path::new(".")),
+	};
+	let root = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?);
+	let root = std::fs::canonicalize(&root).unwrap_or(root);
+	let relative = input.strip_prefix(&root).map_err(|_| {
+		format!(
+			"Input `{}` is not part of repository `{}`",
+			input.display(),
+			root.display()
+		)
+	})?;
+	git(
+		&root,
+		&[
+			"rev-parse",
+			"--verify",
+			"--quiet",
+			&format!("{tag}^{{commit}}"),
+		],
+	)
+	.map_err(|_| format!("Unknown git tag `{tag}`"))?;
+
+	if destination.exists() {
+		std::fs::remove_dir_all(destination)
+			.map_err(|err| format!("Unable to remove `{}`: {err}", destination.display()))?;
+	}
+	std::fs::create_dir_all(destination)
+		.map_err(|err| format!("Unable to create `{}`: {err}", destination.display()))?;
+
+	let mut archive = Command::new("git")
+		.arg("-C")
+		.arg(&root)
+		.args(["archive", "--format=tar", tag, "--"])
+		.arg(if relative.as_os_str().is_empty() {
+			Path::new(".")
+		} else {
+			relative
+		})
+		.stdout(Stdio::piped())
+		.spawn()
+		.map_err(|err| format!("Unable to run git: {err}"))?;
+	let extract = Command::new("tar")
+		.arg("-x")
+		.arg("-C")
+		.arg(destination)
+		.stdin(archive.stdout.take().unwrap())
+		.status()
+		.map_err(|err| format!("Unable to run tar: {err}"))?;
+	let archive = archive
+		.wait()
+		.map_err(|err| format!("Unable to run git: {err}"))?;
+	if !archive.success() || !extract.success() {
+		return Err(format!(
+			"Unable to extract `{}` at tag `{tag}`",
+			relative.display()
+		));
+	}
+
+	Ok(destination.join(relative))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tempdir::TempDir;
+
+	#[test]
+	fn defines() {
+		let defines = vec![
+			(
+				BASE_URL_VARIABLE.to_string(),
+				"https://a.org/docs/".to_string(),
+			),
+			(PATH_PREFIX_VARIABLE.to_string(), "/docs".to_string()),
+			("x".to_string(), "y".to_string()),
+		];
+		assert_eq!(
+			version_defines("1.0", &defines),
+			vec![
+				(
+					BASE_URL_VARIABLE.to_string(),
+					"https://a.org/docs/1.0".to_string()
+				),
+				(PATH_PREFIX_VARIABLE.to_string(), "/docs/1.0".to_string()),
+				("x".to_string(), "y".to_string()),
+				(VERSION_VARIABLE.to_string(), "1.0".to_string()),
+				(
+					VERSIONS_VARIABLE.to_string(),
+					"../versions.json".to_string()
+				),
+			]
+		);
+		assert_eq!(
+			version_defines("dev", &[])[0],
+			(PATH_PREFIX_VARIABLE.to_string(), "/dev".to_string())
+		);
+	}
+
+	#[test]
+	fn manifest() {
+		let manifest: serde_json::Value =
+			serde_json::from_str(versions_manifest(&["2.0", "1.0"]).as_str()).unwrap();
+		assert_eq!(
+			manifest,
+			json!({
+				"latest": "2.0",
+				"versions": [
+					{ "name": "2.0", "path": "2.0/" },
+					{ "name": "1.0", "path": "1.0/" },
+				],
+			})
+		);
+	}
+
+	#[test]
+	#[cfg(unix)]
+	fn tags() {
+		let root = TempDir::new("versions");
+		std::fs::create_dir_all(root.join("repo/docs")).unwrap();
+		let repo = root.join("repo");
+		let run = |args: &[&str]| git(&repo, args).unwrap();
+		if git(&root, &["--version"]).is_err() {
+			return;
+		}
+		run(&["init", "-q"]);
+		std::fs::write(repo.join("docs/a.nml"), "Old").unwrap();
+		std::fs::write(repo.join("other.txt"), "").unwrap();
+		run(&["add", "-A"]);
+		run(&[
+			"-c",
+			"user.name=nml",
+			"-c",
+			"user.email=nml@localhost",
+			"commit",
+			"-qm",
+			"Old",
+		]);
+		run(&["tag", "v1"]);
+		std::fs::write(repo.join("docs/a.nml"), "New").unwrap();
+
+		let input = checkout_tag("v1", &repo.join("docs"), &root.join("out")).unwrap();
+		assert_eq!(input, root.join("out/docs"));
+		assert_eq!(std::fs::read_to_string(input.join("a.nml")).unwrap(), "Old");
+		assert!(!root.join("out/other.txt").exists());
+		assert!(checkout_tag("v2", &repo.join("docs"), &root.join("out")).is_err());
+	}
+}
@@ -1,25 +1,31 @@
-mod cache;
-mod compiler;
-mod document;
-mod elements;
-mod lsp;
-mod lua;
-mod parser;
-
-use std::rc::Rc;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::RwLock;
 
 use dashmap::DashMap;
-use lsp::code::CodeRangeInfo;
-use lsp::conceal::ConcealInfo;
-use lsp::conceal::ConcealParams;
-use lsp::styles::StyleInfo;
-use lsp::styles::StyleParams;
-use parser::langparser::LangParser;
-use parser::parser::ParseMode;
-use parser::parser::Parser;
-use parser::parser::ParserState;
-use parser::reports::Report;
-use parser::source::SourceFile;
+use nml_core::cache::cache;
+use nml_core::lsp;
+use nml_core::lsp::analysis::analyze;
+use nml_core::lsp::analysis::AnalysisInput;
+use nml_core::lsp::code::CodeRangeInfo;
+use nml_core::lsp::conceal::ConcealInfo;
+use nml_core::lsp::conceal::ConcealParams;
+use nml_core::lsp::config::ServerConfig;
+use nml_core::lsp::elemstyle::ElemStyleInfo;
+use nml_core::lsp::elemstyle::ElemStyleParams;
+use nml_core::lsp::outline::OutlineItem;
+use nml_core::lsp::outline::OutlineParams;
+use nml_core::lsp::preview::CompilePreview;
+use nml_core::lsp::preview::CompilePreviewParams;
+use nml_core::lsp::references::IndexedDocument;
+use nml_core::lsp::styles::StyleInfo;
+use nml_core::lsp::styles::StyleParams;
+use nml_core::lsp::workspace::Workspace;
+use nml_core::parser::cancel::Cancellation;
+use nml_core::project::settings::PROJECT_FILE;
 use tower_lsp::jsonrpc;
 use tower_lsp::lsp_types::*;
 use tower_lsp::Client;
@@ -27,6 +33,13 @@ use tower_lsp::LanguageServer;
 use tower_lsp::LspService;
 use tower_lsp::Server;
 
+/// Inserts the data of the parsed sources
+fn insert_all<V>(map: &DashMap<String, V>, data: Vec<(String, V)>) {
+	for (path, value) in data {
+		map.insert(path, value);
+	}
+}
+
 #[derive(Debug)]
 struct Backend {
 	client: Client,
@@ -38,6 +51,22 @@ struct Backend {
 	conceals_map: DashMap<String, Vec<ConcealInfo>>,
 	styles_map: DashMap<String, Vec<StyleInfo>>,
 	coderanges_map: DashMap<String, Vec<CodeRangeInfo>>,
+	hover_map: DashMap<String, Vec<(Range, String)>>,
+	colors_map: DashMap<String, Vec<ColorInformation>>,
+	elemstyles_map: DashMap<String, Vec<ElemStyleInfo>>,
+	outline_map: DashMap<String, Vec<OutlineItem>>,
+	linked_map: DashMap<String, Vec<Vec<Range>>>,
+	/// Files imported by the documents
+	dependencies_map: DashMap<String, HashSet<PathBuf>>,
+	/// Cancellation of the running parse of the documents
+	parses_map: DashMap<String, Cancellation>,
+	/// References exported by the opened documents
+	references_map: DashMap<String, IndexedDocument>,
+	config: RwLock<ServerConfig>,
+	/// Workspace folders, each with its own project file
+	workspaces: RwLock<Vec<Workspace>>,
+	/// Whether the client supports registering file watchers
+	watch_files: AtomicBool,
 }
 
 #[derive(Debug)]
@@ -51,119 +80,94 @@ impl Backend {
 		self.document_map
 			.insert(params.uri.to_string(), params.text.clone());
 
-		// TODO: Create a custom parser for the lsp
-		// Which will require a dyn Document to work
-		let source = Rc::new(SourceFile::with_content(
-			params.uri.to_string(),
-			params.text.clone(),
-			None,
-		));
-
-		// Diagnostics
-		self.diagnostic_map.clear();
-		let parser = LangParser::new(
-			false,
-			Box::new(|_colors, reports| {
-				Report::reports_to_diagnostics(&self.diagnostic_map, reports)
-			}),
-		);
-		// Parse
-		let (_doc, state) = parser.parse(
-			ParserState::new_with_semantics(&parser, None),
-			source.clone(),
-			None,
-			ParseMode::default(),
-		);
-
-		if let Some(lsp) = state.shared.lsp.as_ref() {
-			let borrow = lsp.borrow();
-
-			// Semantics
-			for (source, sem) in &borrow.semantic_data {
-				if let Some(path) = source
-					.clone()
-					.downcast_rc::<SourceFile>()
-					.ok()
-					.map(|source| source.path().to_owned())
-				{
-					self.semantic_token_map
-						.insert(path, sem.tokens.replace(vec![]));
-				}
-			}
-
-			// Inlay hints
-			for (source, hints) in &borrow.inlay_hints {
-				if let Some(path) = source
-					.clone()
-					.downcast_rc::<SourceFile>()
-					.ok()
-					.map(|source| source.path().to_owned())
-				{
-					self.hints_map.insert(path, hints.hints.replace(vec![]));
-				}
-			}
-
-			// Definitions
-			for (source, definitions) in &borrow.definitions {
-				if let Some(path) = source
-					.clone()
-					.downcast_rc::<SourceFile>()
-					.ok()
-					.map(|source| source.path().to_owned())
-				{
-					self.definition_map
-						.insert(path, definitions.definitions.replace(vec![]));
-				}
-			}
-
-			// Conceals
-			for (source, conceals) in &borrow.conceals {
-				if let Some(path) = source
-					.clone()
-					.downcast_rc::<SourceFile>()
-					.ok()
-					.map(|source| source.path().to_owned())
-				{
-					self.conceals_map
-						.insert(path, conceals.conceals.replace(vec![]));
-				}
-			}
+		// Cancel the parse of the previous version
+		let cancellation = Cancellation::new();
+		if let Some(previous) = self
+			.parses_map
+			.insert(params.uri.to_string(), cancellation.clone())
+		{
+			previous.cancel();
+		}
+		// Let newer versions arrive before parsing, e.g when typing quickly
+		tokio::task::yield_now().await;
+		if cancellation.is_cancelled() {
+			return;
+		}
 
-			// Styles
-			for (source, styles) in &borrow.styles {
-				if let Some(path) = source
-					.clone()
-					.downcast_rc::<SourceFile>()
-					.ok()
-					.map(|source| source.path().to_owned())
-				{
-					self.styles_map.insert(path, styles.styles.replace(vec![]));
-				}
-			}
+		// Parse on a blocking thread, so that newer versions can cancel it while it runs
+		let path = params.uri.to_file_path().ok();
+		let input = AnalysisInput {
+			uri: params.uri.to_string(),
+			path: path.clone(),
+			text: params.text,
+			defines: path
+				.as_ref()
+				.and_then(|path| {
+					lsp::workspace::find(&self.workspaces.read().unwrap(), path)
+						.map(|workspace| workspace.defines.clone())
+				})
+				.unwrap_or_default(),
+			budget: self.config.read().unwrap().parse_budget(),
+			cancellation: cancellation.clone(),
+			documents: self.indexed_documents(path.as_deref()),
+		};
+		let Ok(Some(analysis)) = tokio::task::spawn_blocking(move || analyze(input)).await else {
+			return;
+		};
+		if cancellation.is_cancelled() {
+			return;
+		}
 
-			// Code Ranges
-			for (source, coderanges) in &borrow.coderanges {
-				if let Some(path) = source
-					.clone()
-					.downcast_rc::<SourceFile>()
-					.ok()
-					.map(|source| source.path().to_owned())
-				{
-					self.coderanges_map
-						.insert(path, coderanges.coderanges.replace(vec![]));
-				}
+		// Diagnostics, replacing those of the parsed sources
+		self.diagnostic_map.remove(params.uri.as_str());
+		for source in &analysis.sources {
+			self.diagnostic_map.remove(source);
+		}
+		insert_all(&self.diagnostic_map, analysis.diagnostics);
+
+		// References, stored in the project's cache for the other documents
+		if let Some(database) = self.database(path.as_deref()) {
+			if let Err(err) = cache::open(&database).and_then(|con| {
+				IndexedDocument::init_cache(&con)?;
+				analysis.indexed.insert_cache(&con)
+			}) {
+				eprintln!(
+					"Unable to store references in `{}`: {err}",
+					database.display()
+				);
 			}
 		}
+		self.references_map
+			.insert(params.uri.to_string(), analysis.indexed);
+		self.dependencies_map
+			.insert(params.uri.to_string(), analysis.dependencies);
+		self.outline_map
+			.insert(params.uri.to_string(), analysis.outline);
+
+		insert_all(&self.semantic_token_map, analysis.semantic_tokens);
+		insert_all(&self.hints_map, analysis.hints);
+		insert_all(&self.definition_map, analysis.definitions);
+		insert_all(&self.conceals_map, analysis.conceals);
+		insert_all(&self.styles_map, analysis.styles);
+		insert_all(&self.coderanges_map, analysis.coderanges);
+		insert_all(&self.hover_map, analysis.hovers);
+		insert_all(&self.colors_map, analysis.colors);
+		insert_all(&self.elemstyles_map, analysis.elemstyles);
+		insert_all(&self.linked_map, analysis.linked);
 	}
 
 	async fn handle_conceal_request(
 		&self,
 		params: ConcealParams,
 	) -> jsonrpc::Result<Vec<ConcealInfo>> {
+		let config = self.config.read().unwrap();
+		if !config.conceals {
+			return Ok(vec![]);
+		}
 		if let Some(conceals) = self.conceals_map.get(params.text_document.uri.as_str()) {
 			let (_, data) = conceals.pair();
 
-			return Ok(data.to_vec());
+			return Ok(lsp::conceal::apply_overrides(data, &config.conceal));
 		}
 		Ok(vec![])
 	}
@@ -177,6 +181,21 @@ impl Backend {
 		Ok(vec![])
 	}
 
+	async fn handle_elemstyle_request(
+		&self,
+		params: ElemStyleParams,
+	) -> jsonrpc::Result<Vec<ElemStyleInfo>> {
+		let assignments = self
+			.elemstyles_map
+			.get(params.text_document.uri.as_str())
+			.map_or(vec![], |assignments| assignments.to_owned());
+
+		Ok(lsp::elemstyle::effective_styles(
+			assignments.as_slice(),
+			params.position,
+		))
+	}
+
 	async fn handle_coderange_request(
 		&self,
 		params: StyleParams,
@@ -188,14 +207,172 @@ impl Backend {
 		}
 		Ok(vec![])
 	}
+
+	/// Gets the cache database of the workspace containing `path`
+	fn database(&self, path: Option<&Path>) -> Option<PathBuf> {
+		lsp::workspace::find(&self.workspaces.read().unwrap(), path?)
+			.and_then(|workspace| workspace.database.clone())
+	}
+
+	/// Gets the references of the documents, from the cache of the workspace containing `path` and
+	/// from the opened documents
+	fn indexed_documents(&self, path: Option<&Path>) -> Vec<IndexedDocument> {
+		let mut documents = self
+			.database(path)
+			.and_then(|database| cache::open(database).ok())
+			.and_then(|con| IndexedDocument::get_references(&con).ok())
+			.unwrap_or_default();
+		for opened in self.references_map.iter() {
+			documents.retain(|document| document.input != opened.input);
+			documents.push(opened.value().clone());
+		}
+		documents
+	}
+
+	/// Reads the configuration from the client's settings, then loads the project files
+	async fn configure(&self, settings: Option<&serde_json::Value>) {
+		if let Some(settings) = settings {
+			match ServerConfig::from_settings(settings) {
+				Ok(config) => *self.config.write().unwrap() = config,
+				Err(err) => self.client.show_message(MessageType::ERROR, err).await,
+			}
+		}
+		self.load_workspaces().await;
+	}
+
+	/// Loads the project file of every workspace
+	async fn load_workspaces(&self) {
+		let errors = {
+			let config = self.config.read().unwrap();
+			self.workspaces
+				.write()
+				.unwrap()
+				.iter_mut()
+				.filter_map(|workspace| workspace.load(&config).err())
+				.collect::<Vec<_>>()
+		};
+		for err in errors {
+			self.client.show_message(MessageType::ERROR, err).await;
+		}
+	}
+
+	/// Parses the opened documents again, e.g after the project files changed
+	///
+	/// When `uris` is set, only these documents are parsed.
+	async fn reparse(&self, uris: Option<&HashSet<String>>) {
+		let documents = self
+			.document_map
+			.iter()
+			.filter(|document| uris.is_none_or(|uris| uris.contains(document.key())))
+			.map(|document| (document.key().clone(), document.value().clone()))
+			.collect::<Vec<_>>();
+		for (uri, text) in documents {
+			if let Ok(uri) = Url::parse(uri.as_str()) {
+				self.on_change(TextDocumentItem { uri, text }).await;
+			}
+		}
+		let _ = self.client.semantic_tokens_refresh().await;
+		let _ = self.client.inlay_hint_refresh().await;
+		let _ = self.client.workspace_diagnostic_refresh().await;
+	}
+
+	async fn handle_outline_request(
+		&self,
+		params: OutlineParams,
+	) -> jsonrpc::Result<Vec<OutlineItem>> {
+		Ok(self
+			.outline_map
+			.get(params.text_document.uri.as_str())
+			.map_or(vec![], |outline| outline.to_owned()))
+	}
+
+	async fn handle_compile_preview_request(
+		&self,
+		params: CompilePreviewParams,
+	) -> jsonrpc::Result<CompilePreview> {
+		let uri = params.text_document.uri;
+		let Some(content) = self
+			.document_map
+			.get(uri.as_str())
+			.map(|content| content.to_owned())
+		else {
+			return Err(jsonrpc::Error::invalid_params(format!(
+				"Document `{uri}` is not opened"
+			)));
+		};
+		let (defines, database) = uri
+			.to_file_path()
+			.ok()
+			.and_then(|path| {
+				lsp::workspace::find(&self.workspaces.read().unwrap(), &path)
+					.map(|workspace| (workspace.defines.clone(), workspace.database.clone()))
+			})
+			.unwrap_or_default();
+
+		Ok(lsp::preview::compile_preview(
+			uri.to_string(),
+			content,
+			defines,
+			database,
+		))
+	}
+
+	/// Gets the edits formatting the tables of an opened document
+	fn format_document(&self, uri: &Url, range: Option<Range>) -> Option<Vec<TextEdit>> {
+		let content = self.document_map.get(uri.as_str())?.to_owned();
+		let defines = uri
+			.to_file_path()
+			.ok()
+			.and_then(|path| {
+				lsp::workspace::find(&self.workspaces.read().unwrap(), &path)
+					.map(|workspace| workspace.defines.clone())
+			})
+			.unwrap_or_default();
+		Some(lsp::format::formatting(
+			uri.to_string(),
+			content,
+			&defines,
+			range,
+		))
+	}
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
 	async fn initialize(
 		&self,
-		_params: InitializeParams,
+		params: InitializeParams,
 	) -> tower_lsp::jsonrpc::Result<InitializeResult> {
+		// Clients without workspace folders only send the root
+		#[allow(deprecated)]
+		let folders = params.workspace_folders.unwrap_or_else(|| {
+			params
+				.root_uri
+				.into_iter()
+				.map(|uri| WorkspaceFolder {
+					name: uri.to_string(),
+					uri,
+				})
+				.collect()
+		});
+		*self.workspaces.write().unwrap() = folders
+			.into_iter()
+			.filter_map(|folder| {
+				let root = folder.uri.to_file_path().ok()?;
+				Some(Workspace::new(folder.name, root))
+			})
+			.collect();
+		self.watch_files.store(
+			params
+				.capabilities
+				.workspace
+				.as_ref()
+				.and_then(|workspace| workspace.did_change_watched_files)
+				.and_then(|watched| watched.dynamic_registration)
+				.unwrap_or(false),
+			Ordering::Relaxed,
+		);
+		self.configure(params.initialization_options.as_ref()).await;
 		Ok(InitializeResult {
 			capabilities: ServerCapabilities {
 				text_document_sync: Some(TextDocumentSyncCapability::Kind(
@@ -204,7 +381,13 @@ impl LanguageServer for Backend {
 				definition_provider: Some(OneOf::Left(true)),
 				completion_provider: Some(CompletionOptions {
 					resolve_provider: Some(false),
-					trigger_characters: Some(vec!["%".to_string()]),
+					trigger_characters: Some(vec![
+						"%".to_string(),
+						"@".to_string(),
+						"\"".to_string(),
+						"{".to_string(),
+						"#".to_string(),
+					]),
 					work_done_progress_options: Default::default(),
 					all_commit_characters: None,
 					completion_item: None,
@@ -243,6 +426,20 @@ impl LanguageServer for Backend {
 					},
 				)),
 				inlay_hint_provider: Some(OneOf::Left(true)),
+				hover_provider: Some(HoverProviderCapability::Simple(true)),
+				document_formatting_provider: Some(OneOf::Left(true)),
+				document_range_formatting_provider: Some(OneOf::Left(true)),
+				color_provider: Some(ColorProviderCapability::Simple(true)),
+				linked_editing_range_provider: Some(LinkedEditingRangeServerCapabilities::Simple(
+					true,
+				)),
+				workspace: Some(WorkspaceServerCapabilities {
+					workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+						supported: Some(true),
+						change_notifications: Some(OneOf::Left(true)),
+					}),
+					file_operations: None,
+				}),
 				..ServerCapabilities::default()
 			},
 			server_info: Some(ServerInfo {
@@ -256,6 +453,42 @@ impl LanguageServer for Backend {
 		self.client
 			.log_message(MessageType::INFO, "server initialized!")
 			.await;
+
+		// Watch documents and project files that are not opened in the editor
+		if self.watch_files.load(Ordering::Relaxed) {
+			let watchers = ["**/*.nml".to_string(), format!("**/{PROJECT_FILE}")]
+				.into_iter()
+				.chain(
+					self.config
+						.read()
+						.unwrap()
+						.project_file
+						.as_ref()
+						.and_then(|path| path.file_name())
+						.map(|name| format!("**/{}", name.to_string_lossy())),
+				)
+				.map(|pattern| FileSystemWatcher {
+					glob_pattern: GlobPattern::String(pattern),
+					kind: None,
+				})
+				.collect();
+			let registration = Registration {
+				id: "nml/watchedFiles".into(),
+				method: "workspace/didChangeWatchedFiles".into(),
+				register_options: serde_json::to_value(DidChangeWatchedFilesRegistrationOptions {
+					watchers,
+				})
+				.ok(),
+			};
+			if let Err(err) = self.client.register_capability(vec![registration]).await {
+				self.client
+					.log_message(
+						MessageType::WARNING,
+						format!("Unable to watch files: {err}"),
+					)
+					.await;
+			}
+		}
 	}
 
 	async fn shutdown(&self) -> tower_lsp::jsonrpc::Result<()> { Ok(()) }
@@ -279,6 +512,77 @@ impl LanguageServer for Backend {
 		.await
 	}
 
+	async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+		self.configure(Some(&params.settings)).await;
+		self.reparse(None).await;
+	}
+
+	async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+		let (projects, paths) = {
+			let workspaces = self.workspaces.read().unwrap();
+			let config = self.config.read().unwrap();
+			let is_project = |path: &PathBuf| {
+				path.file_name().is_some_and(|name| name == PROJECT_FILE)
+					|| workspaces.iter().any(|workspace| {
+						config.project_path(Some(&workspace.root)).as_ref() == Some(path)
+					})
+			};
+
+			let mut projects = false;
+			let mut paths = HashSet::new();
+			for change in &params.changes {
+				// Opened documents are updated by the editor
+				if self.document_map.contains_key(change.uri.as_str()) {
+					continue;
+				}
+				let Ok(path) = change.uri.to_file_path() else {
+					continue;
+				};
+				if is_project(&path) {
+					projects = true;
+				} else {
+					paths.insert(std::fs::canonicalize(&path).unwrap_or(path));
+				}
+			}
+			(projects, paths)
+		};
+
+		if projects {
+			self.load_workspaces().await;
+			self.reparse(None).await;
+			return;
+		}
+
+		// Parse the documents importing the changed files
+		let uris = self
+			.dependencies_map
+			.iter()
+			.filter(|dependencies| !dependencies.is_disjoint(&paths))
+			.map(|dependencies| dependencies.key().clone())
+			.collect::<HashSet<_>>();
+		if !uris.is_empty() {
+			self.reparse(Some(&uris)).await;
+		}
+	}
+
+	async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+		{
+			let mut workspaces = self.workspaces.write().unwrap();
+			for folder in params.event.removed {
+				if let Ok(root) = folder.uri.to_file_path() {
+					workspaces.retain(|workspace| workspace.root != root);
+				}
+			}
+			for folder in params.event.added {
+				if let Ok(root) = folder.uri.to_file_path() {
+					workspaces.push(Workspace::new(folder.name, root));
+				}
+			}
+		}
+		self.load_workspaces().await;
+		self.reparse(None).await;
+	}
+
 	async fn goto_definition(
 		&self,
 		params: GotoDefinitionParams,
@@ -317,14 +621,25 @@ impl LanguageServer for Backend {
 
 	async fn completion(
 		&self,
-		_params: CompletionParams,
+		params: CompletionParams,
 	) -> tower_lsp::jsonrpc::Result<Option<CompletionResponse>> {
-		//let uri = params.text_document_position.text_document.uri;
-		//let position = params.text_document_position.position;
+		let uri = params.text_document_position.text_document.uri;
+		let position = params.text_document_position.position;
 		let completions = || -> Option<Vec<CompletionItem>> {
-			let ret = Vec::with_capacity(0);
-
-			Some(ret)
+			let content = self.document_map.get(uri.as_str())?;
+			let path = uri.to_file_path().ok();
+			let input = path
+				.as_ref()
+				.map_or(uri.to_string(), |path| path.to_string_lossy().to_string());
+
+			let mut items = lsp::elemstyle::completion(content.as_str(), position);
+			items.extend(lsp::references::completion(
+				&self.indexed_documents(path.as_deref()),
+				&input,
+				content.as_str(),
+				position,
+			));
+			Some(items)
 		}();
 		Ok(completions.map(CompletionResponse::Array))
 	}
@@ -338,6 +653,9 @@ impl LanguageServer for Backend {
 			.log_message(MessageType::LOG, "semantic_token_full")
 			.await;
 
+		if !self.config.read().unwrap().semantic_tokens {
+			return Ok(None);
+		}
 		if let Some(semantic_tokens) = self.semantic_token_map.get(uri.as_str()) {
 			let data = semantic_tokens
 				.iter()
@@ -364,7 +682,7 @@ impl LanguageServer for Backend {
 					items: self
 						.diagnostic_map
 						.get(params.text_document.uri.as_str())
-						.map_or(vec![], |v| v.to_owned()),
+						.map_or(vec![], |v| self.config.read().unwrap().apply_severity(&v)),
 				},
 			}),
 		))
@@ -376,11 +694,106 @@ impl LanguageServer for Backend {
 	) -> tower_lsp::jsonrpc::Result<Option<Vec<InlayHint>>> {
 		if let Some(hints) = self.hints_map.get(params.text_document.uri.as_str()) {
 			let (_, data) = hints.pair();
+			let config = self.config.read().unwrap();
+
+			return Ok(Some(
+				data.iter()
+					.filter(|hint| config.hints.enabled(hint))
+					.cloned()
+					.collect(),
+			));
+		}
+		Ok(None)
+	}
+
+	async fn linked_editing_range(
+		&self,
+		params: LinkedEditingRangeParams,
+	) -> tower_lsp::jsonrpc::Result<Option<LinkedEditingRanges>> {
+		let uri = params.text_document_position_params.text_document.uri;
+		Ok(self.linked_map.get(uri.as_str()).and_then(|linked| {
+			lsp::linked::linked_at(
+				linked.as_slice(),
+				params.text_document_position_params.position,
+			)
+			.map(|ranges| LinkedEditingRanges {
+				ranges,
+				word_pattern: None,
+			})
+		}))
+	}
+
+	async fn hover(&self, params: HoverParams) -> tower_lsp::jsonrpc::Result<Option<Hover>> {
+		let uri = &params.text_document_position_params.text_document.uri;
+		let pos = &params.text_document_position_params.position;
+
+		if let Some(hovers) = self.hover_map.get(uri.as_str()) {
+			let (_, data) = hovers.pair();
 
-			return Ok(Some(data.to_owned()));
+			let found = data
+				.iter()
+				.find(|(range, _)| range.start <= *pos && *pos <= range.end);
+			if let Some((range, content)) = found {
+				return Ok(Some(Hover {
+					contents: HoverContents::Markup(MarkupContent {
+						kind: MarkupKind::Markdown,
+						value: content.clone(),
+					}),
+					range: Some(*range),
+				}));
+			}
 		}
 		Ok(None)
 	}
+
+	async fn formatting(
+		&self,
+		params: DocumentFormattingParams,
+	) -> tower_lsp::jsonrpc::Result<Option<Vec<TextEdit>>> {
+		Ok(self.format_document(&params.text_document.uri, None))
+	}
+
+	async fn range_formatting(
+		&self,
+		params: DocumentRangeFormattingParams,
+	) -> tower_lsp::jsonrpc::Result<Option<Vec<TextEdit>>> {
+		Ok(self.format_document(&params.text_document.uri, Some(params.range)))
+	}
+
+	async fn document_color(
+		&self,
+		params: DocumentColorParams,
+	) -> tower_lsp::jsonrpc::Result<Vec<ColorInformation>> {
+		if let Some(colors) = self.colors_map.get(params.text_document.uri.as_str()) {
+			let (_, data) = colors.pair();
+
+			return Ok(data.to_vec());
+		}
+		Ok(vec![])
+	}
+
+	async fn color_presentation(
+		&self,
+		params: ColorPresentationParams,
+	) -> tower_lsp::jsonrpc::Result<Vec<ColorPresentation>> {
+		let color = params.color;
+		let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+		let mut label = format!(
+			"#{:02x}{:02x}{:02x}",
+			channel(color.red),
+			channel(color.green),
+			channel(color.blue)
+		);
+		if channel(color.alpha) != 255 {
+			label += format!("{:02x}", channel(color.alpha)).as_str();
+		}
+
+		Ok(vec![ColorPresentation {
+			label,
+			text_edit: None,
+			additional_text_edits: None,
+		}])
+	}
 }
 
 #[tokio::main]
@@ -398,10 +811,30 @@ async fn main() {
 		conceals_map: DashMap::new(),
 		styles_map: DashMap::new(),
 		coderanges_map: DashMap::new(),
+		hover_map: DashMap::new(),
+		colors_map: DashMap::new(),
+		elemstyles_map: DashMap::new(),
+		outline_map: DashMap::new(),
+		linked_map: DashMap::new(),
+		dependencies_map: DashMap::new(),
+		parses_map: DashMap::new(),
+		references_map: DashMap::new(),
+		config: RwLock::new(ServerConfig::default()),
+		workspaces: RwLock::new(vec![]),
+		watch_files: AtomicBool::new(false),
 	})
 	.custom_method("textDocument/conceal", Backend::handle_conceal_request)
 	.custom_method("textDocument/style", Backend::handle_style_request)
 	.custom_method("textDocument/codeRange", Backend::handle_coderange_request)
+	.custom_method(
+		"textDocument/elementStyles",
+		Backend::handle_elemstyle_request,
+	)
+	.custom_method("nml/outline", Backend::handle_outline_request)
+	.custom_method(
+		"nml/compilePreview",
+		Backend::handle_compile_preview_request,
+	)
 	.finish();
 
 	Server::new(stdin, stdout, socket).serve(service).await;
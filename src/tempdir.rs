@@ -0,0 +1,42 @@
+//! Temporary directories for the tests
+
+use std::ops::Deref;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// Empty directory removed with its content when dropped, even when the test fails
+///
+/// Dereferences to its canonical path.
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+	/// Creates a directory named after `name`, unique to the process
+	pub fn new(name: &str) -> Self {
+		static COUNTER: AtomicUsize = AtomicUsize::new(0);
+		let path = std::env::temp_dir().join(format!(
+			"nml-{name}-{}-{}",
+			std::process::id(),
+			COUNTER.fetch_add(1, Ordering::Relaxed)
+		));
+		let _ = std::fs::remove_dir_all(&path);
+		std::fs::create_dir_all(&path).unwrap();
+		Self(std::fs::canonicalize(&path).unwrap())
+	}
+}
+
+impl Deref for TempDir {
+	type Target = Path;
+
+	fn deref(&self) -> &Path { &self.0 }
+}
+
+impl AsRef<Path> for TempDir {
+	fn as_ref(&self) -> &Path { &self.0 }
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) { let _ = std::fs::remove_dir_all(&self.0); }
+}